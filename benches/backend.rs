@@ -0,0 +1,114 @@
+//! # 读取后端对比基准
+//!
+//! 在同一个大文件上比较 `BufReader` 读取与内存映射（`use_mmap`）读取的耗时，
+//! 用复用的缓冲区调用 `GlogReader::read_entry`，不解析 protobuf。
+//! 文件大小默认 500 MB，可以通过环境变量 `CLOG_BENCH_MB` 调整。
+//!
+//! 运行方式：`cargo bench --bench backend --features memmap`
+
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use clog_reader::reader::{CompressMode, EncryptMode};
+use clog_reader::writer::v4::GlogWriterV4;
+use clog_reader::{GlogReader, Log};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tempfile::TempDir;
+
+/// 默认的文件大小（MB）
+const DEFAULT_SIZE_MB: u64 = 500;
+
+fn sample_log(i: usize) -> Log {
+    Log {
+        log_type: 0,
+        timestamp: "1700000000000".to_string(),
+        log_level: (i % 5) as i32,
+        pid: 1234,
+        tid: "main".to_string(),
+        tag: "bench".to_string(),
+        msg: format!(
+            "request {} finished in {} ms {}",
+            i,
+            i % 97,
+            "x".repeat(i % 200)
+        ),
+    }
+}
+
+/// 记录已写入字节数的输出
+struct Counting<W> {
+    inner: W,
+    written: Rc<Cell<u64>>,
+}
+
+impl<W: Write> Write for Counting<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written.set(self.written.get() + n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 写入约 `size` 字节的 V4 文件，返回实际大小
+fn write_file(path: &Path, size: u64, compress_mode: CompressMode) -> u64 {
+    let written = Rc::new(Cell::new(0));
+    let output = Counting {
+        inner: BufWriter::new(File::create(path).unwrap()),
+        written: Rc::clone(&written),
+    };
+    let mut writer = GlogWriterV4::new(output, "Log").unwrap();
+    let mut i = 0;
+    while written.get() < size {
+        let payload = prost::Message::encode_to_vec(&sample_log(i));
+        writer
+            .write_entry(&payload, compress_mode, EncryptMode::None)
+            .unwrap();
+        i += 1;
+    }
+    writer.into_inner().unwrap().inner.flush().unwrap();
+    written.get()
+}
+
+fn read_all(path: &str, use_mmap: bool) -> usize {
+    let mut reader = GlogReader::builder().use_mmap(use_mmap).open(path).unwrap();
+    let mut buf = Vec::new();
+    let mut entries = 0;
+    while reader.read_entry(&mut buf).unwrap().is_some() {
+        entries += 1;
+    }
+    entries
+}
+
+fn bench_backend(c: &mut Criterion) {
+    let size_mb = std::env::var("CLOG_BENCH_MB")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SIZE_MB);
+    let dir = TempDir::new().unwrap();
+
+    let mut group = c.benchmark_group("read_backend");
+    group.sample_size(10);
+    for (name, compress_mode) in [("plain", CompressMode::None), ("zlib", CompressMode::Zlib)] {
+        let path = dir.path().join(format!("{}.glog", name));
+        let size = write_file(&path, size_mb * 1024 * 1024, compress_mode);
+        let path = path.to_str().unwrap();
+        group.throughput(Throughput::Bytes(size));
+        group.bench_function(format!("{}/bufreader", name), |b| {
+            b.iter(|| read_all(path, false))
+        });
+        group.bench_function(format!("{}/mmap", name), |b| {
+            b.iter(|| read_all(path, true))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_backend);
+criterion_main!(benches);
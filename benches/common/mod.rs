@@ -0,0 +1,44 @@
+//! # 基准测试公共模块
+//!
+//! 提供基准使用的日志和 Glog 文件构造函数，各基准的文件内容保持一致。
+
+#![allow(dead_code)]
+
+use clog_reader::reader::{CompressMode, EncryptMode};
+use clog_reader::writer::v4::GlogWriterV4;
+use clog_reader::Log;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::SecretKey;
+
+/// 基准文件中的第 `i` 条日志
+pub fn sample_log(i: usize) -> Log {
+    Log {
+        log_type: 0,
+        timestamp: "1700000000000".to_string(),
+        log_level: (i % 5) as i32,
+        pid: 1234,
+        tid: "main".to_string(),
+        tag: "bench".to_string(),
+        msg: format!("request {} finished in {} ms", i, i % 97),
+    }
+}
+
+/// 按指定压缩模式逐条加密的 V4 文件，返回文件内容和十六进制私钥
+///
+/// # Arguments
+/// * `entries` - 日志条数
+/// * `compress_mode` - 压缩模式
+pub fn encrypted_file(entries: usize, compress_mode: CompressMode) -> (Vec<u8>, String) {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), "Log")
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..entries {
+        let payload = prost::Message::encode_to_vec(&sample_log(i));
+        writer
+            .write_entry(&payload, compress_mode, EncryptMode::Aes)
+            .unwrap();
+    }
+    (writer.into_inner().unwrap(), hex::encode(server_key.to_bytes()))
+}
@@ -0,0 +1,45 @@
+//! # V4 逐条读取的吞吐量基准
+//!
+//! 用复用的缓冲区调用 `GlogReader::read_entry` 读取整个文件，不解析 protobuf，
+//! 衡量读取、解密和解压本身的开销。
+//!
+//! 运行方式：`cargo bench --bench read`
+
+mod common;
+
+use std::io::Cursor;
+
+use clog_reader::reader::CompressMode;
+use clog_reader::GlogReader;
+use common::encrypted_file;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// 基准文件中的日志条数
+const ENTRIES: usize = 20_000;
+
+fn bench_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("v4_read_entry");
+    for (name, compress_mode) in [("aes", CompressMode::None), ("aes_zlib", CompressMode::Zlib)] {
+        let (content, key) = encrypted_file(ENTRIES, compress_mode);
+        let size = content.len() as u64;
+        group.throughput(Throughput::Bytes(size));
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || GlogReader::from_reader(Cursor::new(content.clone()), size, Some(key.clone())).unwrap(),
+                |mut reader| {
+                    let mut buf = Vec::new();
+                    let mut entries = 0;
+                    while reader.read_entry(&mut buf).unwrap().is_some() {
+                        entries += 1;
+                    }
+                    entries
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_read);
+criterion_main!(benches);
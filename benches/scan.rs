@@ -0,0 +1,94 @@
+//! # 跳过解码的计数基准
+//!
+//! 对比 `GlogReader::count`（只走条目结构）与逐条解码的 `logs().count()`，
+//! 以及按类型过滤时先解析类型字段（`logs().with_filter`）与完整解码后再过滤的差别。
+//!
+//! 运行方式：`cargo bench --bench scan`
+
+mod common;
+
+use std::io::Cursor;
+
+use clog_reader::reader::CompressMode;
+use clog_reader::writer::v3::GlogWriterV3;
+use clog_reader::proto::LogFilter;
+use clog_reader::{GlogReader, Log};
+use common::{encrypted_file, sample_log};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+/// 基准文件中的日志条数
+const ENTRIES: usize = 20_000;
+
+/// 未压缩、未加密的 V3 文件
+fn plain_file() -> Vec<u8> {
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+    for i in 0..ENTRIES {
+        writer.write_log(&sample_log(i)).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+/// 日志类型依次为 1~20 的 V3 文件，按类型 1 过滤时丢弃 95% 的日志
+fn typed_file() -> Vec<u8> {
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+    for i in 0..ENTRIES {
+        let log = Log {
+            log_type: (i % 20) as i32 + 1,
+            ..sample_log(i)
+        };
+        writer.write_log(&log).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+fn open(content: &[u8], key: Option<String>) -> GlogReader {
+    let size = content.len() as u64;
+    GlogReader::from_reader(Cursor::new(content.to_vec()), size, key).unwrap()
+}
+
+fn bench_count(c: &mut Criterion) {
+    // 未压缩、逐条加密
+    let (encrypted, key) = encrypted_file(ENTRIES, CompressMode::None);
+    for (name, content, key) in [("plain", plain_file(), None), ("encrypted", encrypted, Some(key))] {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("count", |b| {
+            b.iter_batched(
+                || open(&content, key.clone()),
+                |mut reader| reader.count().unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function("decode", |b| {
+            b.iter_batched(
+                || open(&content, key.clone()),
+                |mut reader| reader.logs().count(),
+                BatchSize::LargeInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+fn bench_type_filter(c: &mut Criterion) {
+    let content = typed_file();
+    let filter = LogFilter::new().types([1]);
+    let mut group = c.benchmark_group("type_filter");
+    group.bench_function("peek_type", |b| {
+        b.iter_batched(
+            || open(&content, None),
+            |mut reader| reader.logs().with_filter(filter.clone()).count(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("full_decode", |b| {
+        b.iter_batched(
+            || open(&content, None),
+            |mut reader| reader.logs().filter(|log| filter.matches(log.as_ref().unwrap())).count(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_count, bench_type_filter);
+criterion_main!(benches);
@@ -0,0 +1,259 @@
+//! # 日志分析
+//!
+//! 设备时钟被 NTP 校正或手动修改后，日志的时间戳会突然后退或前跳，
+//! 排序和按时间筛选的结果因此令人困惑。[`TimeJumpDetector`] 按文件中的顺序逐条检查时间戳，
+//! 标记后退超过阈值或前跳超过间隔的位置；[`find_time_jumps`] 是对整个序列使用默认阈值的简写。
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::proto::{format_local_millis, Log};
+
+/// 默认的后退阈值：多线程写入时相邻日志可能有少量乱序，不视为时钟跳变
+pub const DEFAULT_BACKWARD_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// 默认的前跳间隔
+pub const DEFAULT_FORWARD_GAP: Duration = Duration::from_secs(60 * 60);
+
+/// 一处时间戳跳变
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TimeJump {
+    /// 跳变前最后一条日志的条目偏移
+    pub previous_offset: u64,
+    /// 跳变后第一条日志的条目偏移
+    pub offset: u64,
+    /// 跳变前的时间戳（毫秒）
+    pub before: i64,
+    /// 跳变后的时间戳（毫秒）
+    pub after: i64,
+}
+
+impl TimeJump {
+    /// 时间戳的变化量（毫秒），后退时为负数
+    pub fn delta_millis(&self) -> i64 {
+        self.after.saturating_sub(self.before)
+    }
+
+    /// 是否为时间后退
+    pub fn is_backward(&self) -> bool {
+        self.after < self.before
+    }
+}
+
+impl fmt::Display for TimeJump {
+    /// 格式化为 `时间 -> 时间 (后退 12.345 秒)`，时间按本地时区显示
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |millis: i64| format_local_millis(millis).unwrap_or_else(|| millis.to_string());
+        let direction = if self.is_backward() {
+            "后退"
+        } else {
+            "前跳"
+        };
+        let delta = self.delta_millis().unsigned_abs();
+        write!(
+            f,
+            "{} -> {} ({} {}.{:03} 秒)",
+            time(self.before),
+            time(self.after),
+            direction,
+            delta / 1000,
+            delta % 1000
+        )
+    }
+}
+
+/// 时间戳跳变检测器
+///
+/// 逐条接收同一文件中按写入顺序排列的日志，时间戳无法解析的日志被忽略。
+/// 多个文件需要各自使用一个检测器，或在切换文件时调用 [`reset`](Self::reset)
+///
+/// ```
+/// use std::time::Duration;
+/// use clog_reader::analysis::TimeJumpDetector;
+/// use clog_reader::proto::Log;
+///
+/// let log = |ts: &str| Log { timestamp: ts.to_string(), ..Log::new() };
+/// let mut detector = TimeJumpDetector::new().forward_gap(Duration::from_secs(60));
+///
+/// assert!(detector.observe(0, &log("1704067200000")).is_none());
+/// let jump = detector.observe(64, &log("1704067500000")).unwrap();
+/// assert_eq!(jump.delta_millis(), 300_000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeJumpDetector {
+    /// 后退超过该值（毫秒）时标记
+    backward_millis: i64,
+    /// 前跳超过该值（毫秒）时标记
+    forward_millis: i64,
+    /// 上一条日志的条目偏移和时间戳
+    previous: Option<(u64, i64)>,
+}
+
+impl Default for TimeJumpDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeJumpDetector {
+    /// 使用默认阈值创建检测器，参见 [`DEFAULT_BACKWARD_THRESHOLD`] 和 [`DEFAULT_FORWARD_GAP`]
+    pub fn new() -> Self {
+        Self {
+            backward_millis: duration_millis(DEFAULT_BACKWARD_THRESHOLD),
+            forward_millis: duration_millis(DEFAULT_FORWARD_GAP),
+            previous: None,
+        }
+    }
+
+    /// 设置后退阈值，时间戳后退超过该值时标记
+    pub fn backward_threshold(mut self, threshold: Duration) -> Self {
+        self.backward_millis = duration_millis(threshold);
+        self
+    }
+
+    /// 设置前跳间隔，时间戳前进超过该值时标记
+    pub fn forward_gap(mut self, gap: Duration) -> Self {
+        self.forward_millis = duration_millis(gap);
+        self
+    }
+
+    /// 检查下一条日志
+    ///
+    /// # Arguments
+    /// * `offset` - 日志条目在源文件中的偏移
+    /// * `log` - 日志
+    ///
+    /// # Returns
+    /// 与上一条日志之间发生跳变时返回跳变信息
+    pub fn observe(&mut self, offset: u64, log: &Log) -> Option<TimeJump> {
+        let after = log.timestamp_millis()?;
+        let (previous_offset, before) = self.previous.replace((offset, after))?;
+        let delta = after.saturating_sub(before);
+        (delta < -self.backward_millis || delta > self.forward_millis).then_some(TimeJump {
+            previous_offset,
+            offset,
+            before,
+            after,
+        })
+    }
+
+    /// 清除上一条日志的记录，之后的第一条日志不会被标记
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    /// 检查一组日志
+    ///
+    /// # Arguments
+    /// * `logs` - 按写入顺序排列的 `(条目偏移, 日志)`
+    ///
+    /// # Returns
+    /// 返回按出现顺序排列的全部跳变
+    pub fn find<I, L>(&mut self, logs: I) -> Vec<TimeJump>
+    where
+        I: IntoIterator<Item = (u64, L)>,
+        L: Borrow<Log>,
+    {
+        logs.into_iter()
+            .filter_map(|(offset, log)| self.observe(offset, log.borrow()))
+            .collect()
+    }
+}
+
+/// 使用默认阈值查找一组日志中的时间戳跳变
+///
+/// # Arguments
+/// * `logs` - 同一文件中按写入顺序排列的 `(条目偏移, 日志)`
+///
+/// # Returns
+/// 返回按出现顺序排列的全部跳变
+pub fn find_time_jumps<I, L>(logs: I) -> Vec<TimeJump>
+where
+    I: IntoIterator<Item = (u64, L)>,
+    L: Borrow<Log>,
+{
+    TimeJumpDetector::new().find(logs)
+}
+
+/// 把时长换算为毫秒，超出 `i64` 时取最大值
+fn duration_millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(timestamps: &[&str]) -> Vec<(u64, Log)> {
+        timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, ts)| {
+                let log = Log {
+                    timestamp: ts.to_string(),
+                    ..Log::new()
+                };
+                (i as u64 * 100, log)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_time_jumps() {
+        let base = 1_704_067_200_000i64;
+        let at = |secs: i64| (base + secs * 1000).to_string();
+        let logs = logs(&[
+            &at(0),
+            &at(1),
+            // 少量乱序不标记
+            &base.to_string(),
+            // 后退一天
+            &at(-86_400),
+            "garbage",
+            // 前跳两小时，跨过无法解析的时间戳
+            &at(-86_400 + 7_200),
+            &at(-86_400 + 7_201),
+        ]);
+
+        let jumps = find_time_jumps(logs.iter().map(|(offset, log)| (*offset, log)));
+        assert_eq!(
+            jumps,
+            vec![
+                TimeJump {
+                    previous_offset: 200,
+                    offset: 300,
+                    before: base,
+                    after: base - 86_400_000,
+                },
+                TimeJump {
+                    previous_offset: 300,
+                    offset: 500,
+                    before: base - 86_400_000,
+                    after: base - 86_400_000 + 7_200_000,
+                },
+            ]
+        );
+        assert!(jumps[0].is_backward());
+        assert_eq!(jumps[1].delta_millis(), 7_200_000);
+        assert!(jumps[0].to_string().ends_with("(后退 86400.000 秒)"));
+
+        // 收紧阈值后少量乱序和正常间隔也会被标记
+        let strict = TimeJumpDetector::new()
+            .backward_threshold(Duration::ZERO)
+            .forward_gap(Duration::from_millis(500))
+            .find(logs);
+        assert_eq!(strict.len(), 5);
+    }
+
+    #[test]
+    fn test_reset_forgets_previous_log() {
+        let logs = logs(&["1704067200000", "1600000000000"]);
+        let mut detector = TimeJumpDetector::new();
+        assert!(detector.observe(logs[0].0, &logs[0].1).is_none());
+        detector.reset();
+        assert!(detector.observe(logs[1].0, &logs[1].1).is_none());
+    }
+}
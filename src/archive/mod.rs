@@ -0,0 +1,924 @@
+//! # 日志压缩包
+//!
+//! 设备上传的日志通常是一个 ZIP 压缩包，包含按天滚动的 `async-YYYYMMdd.glog`
+//! 和仍在写入的 `.glogmmap` 缓存文件。[`GlogArchive`](crate::archive::GlogArchive) 直接从压缩包中发现并排序日志文件，
+//! 读取时把单个条目读入内存，不需要解压到临时目录。
+//!
+//! 处理顺序与命令行工具一致：先是 glog 文件（按文件名中的日期和滚动序号升序），
+//! 再是 mmap 缓存文件（按压缩包中记录的修改时间降序，最新的在前）。
+//! 哪些文件是 glog 文件由 [`FileNamePattern`] 决定，默认为 `async-*.glog`。
+//!
+//! 启用 `rayon` 特性时，`decode_parallel` 等接口可以并行解码解压出的多个日志文件。
+//! `GlogArchive::stream` 在后台线程中解码，通过有界通道逐条交给调用方。
+//!
+//! 设备上传的压缩包中偶尔有截断或加密的条目：无法读取本地文件头的条目在打开时记入
+//! [`GlogArchive::unreadable_entries`]，其余条目照常列出，单个条目解压失败只影响该文件。
+//! 解压的总字节数受 [`GlogArchive::set_extract_limit`] 限制，防范 ZIP 炸弹。
+//!
+//! 中文 Windows 生成的压缩包以 GBK 编码条目名且不设置 UTF-8 标志，条目名不是有效的 UTF-8 时按 GBK 解码，
+//! 解压时按解码后的名称去掉 `..`、盘符等组成部分，得到目标目录下的相对路径。
+
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use zeroize::Zeroizing;
+use zip::result::ZipError;
+use zip::read::ZipFile;
+use zip::ZipArchive;
+
+use crate::error::{GlogError, Result};
+use crate::glog::GlogReader;
+use crate::observer::Observer;
+use crate::proto::Log;
+use crate::reader::InflaterPool;
+
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pattern;
+mod stream;
+
+#[cfg(feature = "rayon")]
+pub use parallel::{
+    decode_parallel, map_files_parallel, FileStats, ParallelDecoder, ParallelOrder,
+};
+pub use pattern::{FileNamePattern, DEFAULT_FILE_PATTERN};
+pub use stream::{DecodedLog, StreamStats, DEFAULT_STREAM_CAPACITY};
+
+use pattern::glog_sort_key;
+
+/// 解压总字节数上限的默认值（4 GB）
+pub const DEFAULT_EXTRACT_LIMIT: u64 = 4 << 30;
+
+/// 压缩包中日志文件的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveEntryKind {
+    /// 按天滚动的 `async-YYYYMMdd.glog`（或符合自定义 [`FileNamePattern`] 的文件）
+    Glog,
+    /// mmap 缓存文件 `.glogmmap`
+    Mmap,
+}
+
+/// 压缩包中的一个日志文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntryInfo {
+    /// 条目在压缩包中的路径
+    pub name: String,
+    /// 日志文件类型
+    pub kind: ArchiveEntryKind,
+    /// 文件名中的日期（仅 glog 文件，无法解析时为 `None`）
+    pub date: Option<NaiveDate>,
+    /// 同一天滚动产生的文件序号，如 `async-20240101.1.glog` 为 1，没有时为 0
+    pub roll: u32,
+    /// 压缩包中记录的修改时间
+    pub modified: Option<NaiveDateTime>,
+    /// 解压后的大小（字节）
+    pub size: u64,
+}
+
+/// 日志压缩包
+///
+/// # 示例
+///
+/// ```rust,no_run
+/// use clog_reader::archive::GlogArchive;
+///
+/// let mut archive = GlogArchive::open("logs.zip", None)?;
+/// for file in archive.files() {
+///     println!("{} ({:?})", file.name, file.kind);
+/// }
+/// for log in archive.iter_all_logs() {
+///     println!("{}", log?);
+/// }
+/// # Ok::<(), clog_reader::GlogError>(())
+/// ```
+pub struct GlogArchive<R = File> {
+    /// ZIP 压缩包
+    zip: ZipArchive<R>,
+    /// 可选的服务器私钥（十六进制字符串）
+    key: Option<Zeroizing<String>>,
+    /// 按处理顺序排列的日志文件及其在压缩包中的序号
+    entries: Vec<(usize, ArchiveEntryInfo)>,
+    /// 交给每个读取器的观察者
+    observer: Option<Arc<dyn Observer>>,
+    /// 各个读取器依次复用的解压上下文
+    inflaters: InflaterPool,
+    /// 无法读取的条目，错误中带有条目路径
+    unreadable: Vec<GlogError>,
+    /// 解压总字节数的上限
+    extract_limit: u64,
+    /// 已经解压的总字节数
+    extracted: u64,
+}
+
+impl GlogArchive<File> {
+    /// 打开日志压缩包
+    ///
+    /// # Arguments
+    /// * `path` - ZIP 文件路径
+    /// * `key` - 可选的服务器私钥（用于解密 V4 版本的加密日志）
+    ///
+    /// # Errors
+    /// 文件无法打开或不是有效的 ZIP 文件时返回错误
+    pub fn open(path: impl AsRef<Path>, key: Option<String>) -> Result<Self> {
+        Self::from_reader(File::open(path)?, key)
+    }
+
+    /// 打开日志压缩包，按 `pattern` 识别 glog 文件
+    ///
+    /// # Arguments
+    /// * `path` - ZIP 文件路径
+    /// * `key` - 可选的服务器私钥
+    /// * `pattern` - glog 文件名规则
+    ///
+    /// # Errors
+    /// 文件无法打开或不是有效的 ZIP 文件时返回错误
+    pub fn open_with_pattern(
+        path: impl AsRef<Path>,
+        key: Option<String>,
+        pattern: &FileNamePattern,
+    ) -> Result<Self> {
+        Self::from_reader_with_pattern(File::open(path)?, key, pattern)
+    }
+}
+
+impl<R: Read + Seek> GlogArchive<R> {
+    /// 从任意可随机访问的输入流读取日志压缩包
+    ///
+    /// # Arguments
+    /// * `reader` - ZIP 数据
+    /// * `key` - 可选的服务器私钥
+    ///
+    /// # Errors
+    /// 不是有效的 ZIP 数据时返回错误
+    pub fn from_reader(reader: R, key: Option<String>) -> Result<Self> {
+        Self::from_reader_with_pattern(reader, key, &FileNamePattern::default())
+    }
+
+    /// 从任意可随机访问的输入流读取日志压缩包，按 `pattern` 识别 glog 文件
+    ///
+    /// # Arguments
+    /// * `reader` - ZIP 数据
+    /// * `key` - 可选的服务器私钥
+    /// * `pattern` - glog 文件名规则
+    ///
+    /// # Errors
+    /// 不是有效的 ZIP 数据时返回错误
+    pub fn from_reader_with_pattern(
+        reader: R,
+        key: Option<String>,
+        pattern: &FileNamePattern,
+    ) -> Result<Self> {
+        let mut zip = ZipArchive::new(reader)?;
+        let mut glogs = Vec::new();
+        let mut mmaps = Vec::new();
+        let mut unreadable = Vec::new();
+        for index in 0..zip.len() {
+            // 只读取条目信息，加密条目也可以列出，解压时再报告
+            let file = match zip.by_index_raw(index) {
+                Ok(file) => file,
+                Err(e) => {
+                    unreadable.push(GlogError::from(e).in_file(format!("条目 #{}", index)));
+                    continue;
+                }
+            };
+            let name = entry_name(&file);
+            if file.is_dir() || sanitized_path(&name).is_none() {
+                continue;
+            }
+            let Some(kind) = entry_kind(&name, pattern) else {
+                continue;
+            };
+            let (date, roll) = glog_date(kind, &name);
+            let info = ArchiveEntryInfo {
+                name,
+                kind,
+                date,
+                roll,
+                modified: zip_time(file.last_modified()),
+                size: file.size(),
+            };
+            match kind {
+                ArchiveEntryKind::Glog => glogs.push((index, info)),
+                ArchiveEntryKind::Mmap => mmaps.push((index, info)),
+            }
+        }
+
+        // glog 按文件名中的日期和滚动序号升序，mmap 按修改时间降序
+        glogs.sort_by_key(|(_, info)| (info.date, info.roll));
+        mmaps.sort_by_key(|(_, info)| Reverse(info.modified));
+        glogs.extend(mmaps);
+
+        Ok(Self {
+            zip,
+            key: key.map(Zeroizing::new),
+            entries: glogs,
+            observer: None,
+            inflaters: InflaterPool::new(),
+            unreadable,
+            extract_limit: DEFAULT_EXTRACT_LIMIT,
+            extracted: 0,
+        })
+    }
+
+    /// 设置读取过程的观察者
+    ///
+    /// 之后由 [`reader_for`](Self::reader_for) 和 [`iter_all_logs`](Self::iter_all_logs)
+    /// 创建的读取器都通知该观察者，文件名为条目在压缩包中的路径
+    ///
+    /// # Arguments
+    /// * `observer` - 观察者
+    pub fn set_observer(&mut self, observer: Arc<dyn Observer>) {
+        self.observer = Some(observer);
+    }
+
+    /// 设置解压总字节数的上限，默认为 [`DEFAULT_EXTRACT_LIMIT`]
+    ///
+    /// [`extract`](Self::extract) 和读取日志时解压的字节数都计入总数，
+    /// 超过上限后返回 [`GlogError::ExtractLimitExceeded`]
+    ///
+    /// # Arguments
+    /// * `limit` - 上限（字节）
+    pub fn set_extract_limit(&mut self, limit: u64) {
+        self.extract_limit = limit;
+    }
+
+    /// 已经解压的总字节数
+    pub fn extracted_bytes(&self) -> u64 {
+        self.extracted
+    }
+
+    /// 打开压缩包时无法读取的条目
+    ///
+    /// 本地文件头损坏的条目无法得知文件名，错误中的路径为 `条目 #序号`
+    pub fn unreadable_entries(&self) -> &[GlogError] {
+        &self.unreadable
+    }
+
+    /// 按处理顺序列出压缩包中的日志文件
+    pub fn files(&self) -> Vec<ArchiveEntryInfo> {
+        self.entries.iter().map(|(_, info)| info.clone()).collect()
+    }
+
+    /// 压缩包中全部条目的路径（包括非日志文件）
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.zip.file_names()
+    }
+
+    /// 为第 `index` 个日志文件创建读取器
+    ///
+    /// 条目内容完整读入内存后交给 [`GlogReader::from_reader`]
+    ///
+    /// # Arguments
+    /// * `index` - 日志文件在 [`files`](Self::files) 中的序号
+    ///
+    /// # Errors
+    /// 序号越界、条目无法解压或文件头无效时返回错误
+    pub fn reader_for(&mut self, index: usize) -> Result<GlogReader> {
+        self.open_reader(index, None)
+    }
+
+    /// 把第 `index` 个日志文件解压到目录中，保留其在压缩包中的相对路径
+    ///
+    /// 路径取自 [`ArchiveEntryInfo::name`]（GBK 编码的条目名已解码），去掉了 `..` 和盘符等组成部分
+    ///
+    /// # Arguments
+    /// * `index` - 日志文件在 [`files`](Self::files) 中的序号
+    /// * `dest_dir` - 目标目录
+    ///
+    /// # Returns
+    /// 返回解压后的文件路径
+    ///
+    /// # Errors
+    /// 序号越界、条目已加密、条目无法解压、超过解压上限或写入失败时返回错误，
+    /// 已写入的部分文件会被删除
+    pub fn extract(&mut self, index: usize, dest_dir: &Path) -> Result<PathBuf> {
+        let zip_index = self.zip_index(index)?;
+        let relative = sanitized_path(&self.entries[index].1.name).ok_or(ZipError::FileNotFound)?;
+        let path = dest_dir.join(relative);
+        let remaining = self.extract_limit.saturating_sub(self.extracted);
+        let mut file = open_entry(&mut self.zip, zip_index)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let copied = copy_limited(&mut file, &mut File::create(&path)?, remaining);
+        drop(file);
+        match self.account(copied) {
+            Ok(()) => Ok(path),
+            Err(e) => {
+                let _ = fs::remove_file(&path);
+                Err(e)
+            }
+        }
+    }
+
+    /// 按处理顺序逐条读取所有日志文件中的日志
+    ///
+    /// 单个文件打开失败或读取出错时以 `Err` 产出，然后继续下一个文件
+    pub fn iter_all_logs(&mut self) -> ArchiveLogs<'_, R> {
+        ArchiveLogs {
+            archive: self,
+            next_index: 0,
+            current: None,
+            buf: Vec::new(),
+            done: false,
+            cancel: None,
+        }
+    }
+
+    /// 为第 `index` 个日志文件创建读取器，可以附带取消标志
+    fn open_reader(&mut self, index: usize, cancel: Option<Arc<AtomicBool>>) -> Result<GlogReader> {
+        let data = self.read_file(index)?;
+        let size = data.len() as u64;
+        let (_, info) = &self.entries[index];
+        let builder = GlogReader::builder()
+            .name(info.name.as_str())
+            .inflater_pool(self.inflaters.clone());
+        let builder = match &self.observer {
+            Some(observer) => builder.observer(Arc::clone(observer)),
+            None => builder,
+        };
+        let builder = match &self.key {
+            Some(key) => builder.key(key.as_str()),
+            None => builder,
+        };
+        let builder = match cancel {
+            Some(cancel) => builder.cancel_token(cancel),
+            None => builder,
+        };
+        builder.from_reader(Cursor::new(data), size)
+    }
+
+    /// 读取第 `index` 个日志文件的完整内容
+    fn read_file(&mut self, index: usize) -> Result<Vec<u8>> {
+        let zip_index = self.zip_index(index)?;
+        let remaining = self.extract_limit.saturating_sub(self.extracted);
+        let mut file = open_entry(&mut self.zip, zip_index)?;
+        // 条目记录的大小不可信，预分配不超过剩余额度
+        let mut data = Vec::with_capacity(file.size().min(remaining) as usize);
+        let copied = copy_limited(&mut file, &mut data, remaining);
+        drop(file);
+        self.account(copied)?;
+        Ok(data)
+    }
+
+    /// 把一次解压的结果计入总字节数
+    fn account(&mut self, copied: Result<u64>) -> Result<()> {
+        match copied {
+            Ok(n) => {
+                self.extracted += n;
+                Ok(())
+            }
+            Err(GlogError::ExtractLimitExceeded { .. }) => {
+                self.extracted = self.extract_limit;
+                Err(GlogError::ExtractLimitExceeded {
+                    limit: self.extract_limit,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 日志文件序号对应的压缩包条目序号
+    fn zip_index(&self, index: usize) -> Result<usize> {
+        let (zip_index, _) = self.entries.get(index).ok_or(ZipError::FileNotFound)?;
+        Ok(*zip_index)
+    }
+}
+
+/// 解压第 `zip_index` 个条目，加密条目返回说明原因的错误
+fn open_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, zip_index: usize) -> Result<ZipFile<'_>> {
+    match zip.by_index(zip_index) {
+        Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)) => Err(
+            ZipError::UnsupportedArchive("条目已加密（ZipCrypto / AES），不支持解密").into(),
+        ),
+        result => Ok(result?),
+    }
+}
+
+/// 把条目内容复制到 `out`，最多复制 `remaining` 字节
+///
+/// # Returns
+/// 返回复制的字节数
+///
+/// # Errors
+/// 条目内容超过 `remaining` 字节时返回 `ExtractLimitExceeded`（其中的上限由调用方填写），
+/// 数据损坏或写入失败时返回 IO 错误
+fn copy_limited(file: &mut impl Read, out: &mut impl Write, remaining: u64) -> Result<u64> {
+    let copied = io::copy(&mut file.take(remaining.saturating_add(1)), out)?;
+    if copied > remaining {
+        return Err(GlogError::ExtractLimitExceeded { limit: remaining });
+    }
+    Ok(copied)
+}
+
+/// 压缩包中全部日志的迭代器，由 [`GlogArchive::iter_all_logs`] 创建
+pub struct ArchiveLogs<'a, R> {
+    /// 日志压缩包
+    archive: &'a mut GlogArchive<R>,
+    /// 下一个要打开的日志文件序号
+    next_index: usize,
+    /// 正在读取的日志文件
+    current: Option<GlogReader>,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+    /// 当前文件是否已读完
+    done: bool,
+    /// 取消标志
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl<R> ArchiveLogs<'_, R> {
+    /// 设置取消标志
+    ///
+    /// 与 [`GlogReaderBuilder::cancel_token`](crate::glog::GlogReaderBuilder::cancel_token) 相同，
+    /// 每条日志之前和打开每个文件之前检查；置位后产出一次 `GlogError::Cancelled`，然后迭代结束
+    ///
+    /// # Arguments
+    /// * `cancel` - 取消标志
+    pub fn cancel_token(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+impl<R: Read + Seek> Iterator for ArchiveLogs<'_, R> {
+    type Item = Result<Log>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(reader) = self.current.as_mut() {
+                if let Some(log) = reader.next_log(&mut self.buf, None, &mut self.done) {
+                    if matches!(&log, Err(e) if matches!(e.kind(), GlogError::Cancelled)) {
+                        self.next_index = self.archive.entries.len();
+                    }
+                    return Some(log.map(|(_, log)| log));
+                }
+                self.current = None;
+            }
+            if self.next_index >= self.archive.entries.len() {
+                return None;
+            }
+            if self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            {
+                self.next_index = self.archive.entries.len();
+                return Some(Err(GlogError::Cancelled));
+            }
+            let index = self.next_index;
+            self.next_index += 1;
+            self.done = false;
+            match self.archive.open_reader(index, self.cancel.clone()) {
+                Ok(reader) => self.current = Some(reader),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// 递归查找目录中的日志文件
+///
+/// 与 [`scan_dir_with_pattern`] 相同，使用默认的文件名规则
+///
+/// # Arguments
+/// * `dir` - 目录，例如用 `adb pull` 取回的日志目录
+///
+/// # Errors
+/// 目录无法读取时返回错误
+pub fn scan_dir(dir: &Path) -> Result<Vec<(PathBuf, ArchiveEntryInfo)>> {
+    scan_dir_with_pattern(dir, &FileNamePattern::default())
+}
+
+/// 递归查找目录中的日志文件，按 `pattern` 识别 glog 文件
+///
+/// 排序规则与压缩包相同：glog 文件按文件名中的日期和滚动序号升序，mmap 缓存文件按修改时间降序。
+/// 返回的 [`ArchiveEntryInfo::name`] 为相对 `dir` 的路径
+///
+/// # Arguments
+/// * `dir` - 目录
+/// * `pattern` - glog 文件名规则
+///
+/// # Returns
+/// 返回按处理顺序排列的 `(文件路径, 文件信息)`
+///
+/// # Errors
+/// 目录无法读取时返回错误
+pub fn scan_dir_with_pattern(
+    dir: &Path,
+    pattern: &FileNamePattern,
+) -> Result<Vec<(PathBuf, ArchiveEntryInfo)>> {
+    let mut glogs = Vec::new();
+    let mut mmaps = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let name = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned();
+            let Some(kind) = entry_kind(&name, pattern) else {
+                continue;
+            };
+            let (date, roll) = glog_date(kind, &name);
+            let info = ArchiveEntryInfo {
+                date,
+                roll,
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .map(|time| chrono::DateTime::<chrono::Local>::from(time).naive_local()),
+                size: metadata.len(),
+                name,
+                kind,
+            };
+            match kind {
+                ArchiveEntryKind::Glog => glogs.push((path, info)),
+                ArchiveEntryKind::Mmap => mmaps.push((path, info)),
+            }
+        }
+    }
+
+    glogs.sort_by_key(|(_, info)| (info.date, info.roll));
+    mmaps.sort_by_key(|(_, info)| Reverse(info.modified));
+    glogs.extend(mmaps);
+    Ok(glogs)
+}
+
+/// 根据文件名判断日志文件类型，不是日志文件时返回 `None`
+fn entry_kind(name: &str, pattern: &FileNamePattern) -> Option<ArchiveEntryKind> {
+    if file_name(name).ends_with(".glogmmap") {
+        Some(ArchiveEntryKind::Mmap)
+    } else if pattern.is_match(name) {
+        Some(ArchiveEntryKind::Glog)
+    } else {
+        None
+    }
+}
+
+/// glog 文件名中的日期和滚动序号，mmap 缓存文件没有日期
+fn glog_date(kind: ArchiveEntryKind, name: &str) -> (Option<NaiveDate>, u32) {
+    match kind {
+        ArchiveEntryKind::Glog => glog_sort_key(name),
+        ArchiveEntryKind::Mmap => (None, 0),
+    }
+}
+
+/// 条目名的文本形式
+///
+/// 原始字节是有效的 UTF-8 时原样使用；否则多半来自中文 Windows，按 GBK 解码；
+/// 仍然无法解码时使用 zip 按 CP437 解码的结果
+fn entry_name(file: &ZipFile<'_>) -> String {
+    let raw = file.name_raw();
+    if let Ok(name) = std::str::from_utf8(raw) {
+        return name.to_string();
+    }
+    match encoding_rs::GBK.decode_without_bom_handling_and_without_replacement(raw) {
+        Some(name) => name.into_owned(),
+        None => file.name().to_string(),
+    }
+}
+
+/// 把条目名转换为解压目标目录下的相对路径
+///
+/// 按 `/` 和 `\` 分隔，丢弃空的、`.`、`..` 以及带 `:` 的（盘符）组成部分，
+/// 因此结果总是位于目标目录之内
+///
+/// # Returns
+/// 没有剩余组成部分或含有 NUL 字符时返回 `None`
+fn sanitized_path(name: &str) -> Option<PathBuf> {
+    if name.contains('\0') {
+        return None;
+    }
+    let path: PathBuf = name
+        .split(['/', '\\'])
+        .filter(|part| !matches!(*part, "" | "." | "..") && !part.contains(':'))
+        .collect();
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// 压缩包条目路径中的文件名部分
+fn file_name(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
+}
+
+/// 把 ZIP 中的 MS-DOS 时间转换为日期时间
+fn zip_time(time: zip::DateTime) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?.and_hms_opt(
+        time.hour().into(),
+        time.minute().into(),
+        time.second().into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::CompressMode;
+    use crate::writer::v3::GlogWriterV3;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn glog_file(msgs: &[&str]) -> Vec<u8> {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+        for msg in msgs {
+            writer
+                .write_log(&Log {
+                    msg: msg.to_string(),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    /// 构造压缩包，`modified` 为条目的修改时间（时, 分）
+    fn archive(files: &[(&str, (u8, u8), Vec<u8>)]) -> GlogArchive<Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, (hour, minute), content) in files {
+            let time = zip::DateTime::from_date_and_time(2024, 1, 2, *hour, *minute, 0).unwrap();
+            zip.start_file(*name, FileOptions::default().last_modified_time(time))
+                .unwrap();
+            zip.write_all(content).unwrap();
+        }
+        GlogArchive::from_reader(Cursor::new(zip.finish().unwrap().into_inner()), None).unwrap()
+    }
+
+    #[test]
+    fn test_files_orders_glog_by_date_then_mmap_by_mtime() {
+        let archive = archive(&[
+            ("logs/async.glogmmap", (8, 0), Vec::new()),
+            ("logs/async-20240102.glog", (9, 0), Vec::new()),
+            ("readme.txt", (9, 0), Vec::new()),
+            ("logs/async-20240101.glog", (9, 0), Vec::new()),
+            ("logs/other.glogmmap", (10, 30), Vec::new()),
+        ]);
+
+        let files = archive.files();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "logs/async-20240101.glog",
+                "logs/async-20240102.glog",
+                "logs/other.glogmmap",
+                "logs/async.glogmmap",
+            ]
+        );
+        assert_eq!(files[0].date, NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert_eq!(files[2].kind, ArchiveEntryKind::Mmap);
+        assert_eq!(files[2].date, None);
+    }
+
+    #[test]
+    fn test_glog_files_sort_by_date_then_roll() {
+        let archive = archive(&[
+            ("async-20240102.1.glog", (9, 0), Vec::new()),
+            ("async-im-20240102.glog", (9, 0), Vec::new()),
+            ("async-20240101.2.glog", (9, 0), Vec::new()),
+            ("async-20240101.10.glog", (9, 0), Vec::new()),
+            ("async-20240101.glog", (9, 0), Vec::new()),
+        ]);
+
+        let files = archive.files();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "async-20240101.glog",
+                "async-20240101.2.glog",
+                "async-20240101.10.glog",
+                "async-im-20240102.glog",
+                "async-20240102.1.glog",
+            ]
+        );
+        assert_eq!(files[2].roll, 10);
+    }
+
+    #[test]
+    fn test_custom_file_pattern() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["applog_20240102.glog", "async-20240101.glog", "applog.txt"] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+        }
+        let data = zip.finish().unwrap().into_inner();
+        let pattern = FileNamePattern::new(r"^applog_\d{8}\.glog$").unwrap();
+
+        let archive =
+            GlogArchive::from_reader_with_pattern(Cursor::new(data), None, &pattern).unwrap();
+        let files = archive.files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "applog_20240102.glog");
+        assert_eq!(files[0].date, NaiveDate::from_ymd_opt(2024, 1, 2));
+    }
+
+    #[test]
+    fn test_scan_dir_finds_nested_log_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("files").join("glog");
+        fs::create_dir_all(&nested).unwrap();
+        for name in [
+            "async-20240102.glog",
+            "async.glogmmap",
+            "async-20240101.glog",
+            "readme.txt",
+        ] {
+            fs::write(nested.join(name), glog_file(&["x"])).unwrap();
+        }
+
+        let files = scan_dir(dir.path()).unwrap();
+        let names: Vec<String> = files
+            .iter()
+            .map(|(_, info)| info.name.replace('\\', "/"))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "files/glog/async-20240101.glog",
+                "files/glog/async-20240102.glog",
+                "files/glog/async.glogmmap",
+            ]
+        );
+        assert_eq!(files[0].0, nested.join("async-20240101.glog"));
+        assert_eq!(files[1].1.date, NaiveDate::from_ymd_opt(2024, 1, 2));
+        assert!(files[2].1.modified.is_some());
+    }
+
+    #[test]
+    fn test_iter_all_logs_reads_files_in_order() {
+        let mut archive = archive(&[
+            ("async-20240102.glog", (0, 0), glog_file(&["b1", "b2"])),
+            ("async-20240101.glog", (0, 0), glog_file(&["a1"])),
+            ("async-20240103.glog", (0, 0), b"not a glog file".to_vec()),
+        ]);
+
+        assert_eq!(archive.reader_for(1).unwrap().logs().count(), 2);
+        assert!(archive.reader_for(3).is_err());
+
+        let logs: Vec<_> = archive.iter_all_logs().collect();
+        let msgs: Vec<&str> = logs[..3].iter().map(|l| l.as_ref().unwrap().msg.as_str()).collect();
+        assert_eq!(msgs, ["a1", "b1", "b2"]);
+        assert_eq!(logs.len(), 4);
+        assert!(logs[3].is_err());
+    }
+
+    #[test]
+    fn test_unreadable_entry_is_listed_and_skipped() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, msg) in [
+            ("async-20240101.glog", "a"),
+            ("async-20240102.glog", "b"),
+            ("async-20240103.glog", "c"),
+        ] {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(&glog_file(&[msg])).unwrap();
+        }
+        let mut bytes = zip.finish().unwrap().into_inner();
+        // 破坏第二个条目的本地文件头签名
+        let mut zip = ZipArchive::new(Cursor::new(&bytes)).unwrap();
+        let header = zip.by_index_raw(1).unwrap().header_start() as usize;
+        drop(zip);
+        bytes[header] = b'X';
+
+        let mut archive = GlogArchive::from_reader(Cursor::new(bytes), None).unwrap();
+        let names: Vec<String> = archive.files().into_iter().map(|f| f.name).collect();
+        assert_eq!(names, ["async-20240101.glog", "async-20240103.glog"]);
+        let unreadable = archive.unreadable_entries();
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].path(), Some("条目 #1"));
+        assert!(matches!(unreadable[0].kind(), GlogError::ZipError(_)));
+
+        let msgs: Vec<String> = archive
+            .iter_all_logs()
+            .map(|log| log.unwrap().msg)
+            .collect();
+        assert_eq!(msgs, ["a", "c"]);
+    }
+
+    #[test]
+    fn test_extract_limit_counts_all_entries() {
+        let content = glog_file(&["x"; 8]);
+        let len = content.len() as u64;
+        let mut archive = archive(&[
+            ("async-20240101.glog", (0, 0), content.clone()),
+            ("async-20240102.glog", (0, 0), content),
+        ]);
+        archive.set_extract_limit(len + len / 2);
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = archive.extract(0, dir.path()).unwrap();
+        assert_eq!(archive.extracted_bytes(), len);
+        assert!(first.exists());
+        let err = archive.extract(1, dir.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            GlogError::ExtractLimitExceeded { limit } if limit == len + len / 2
+        ));
+        // 超限的部分文件被删除，之后的读取也被拒绝
+        assert!(!dir.path().join("async-20240102.glog").exists());
+        assert!(matches!(
+            archive.reader_for(0).unwrap_err(),
+            GlogError::ExtractLimitExceeded { .. }
+        ));
+    }
+
+    #[test]
+    fn test_sanitized_path_stays_inside_dest() {
+        assert_eq!(
+            sanitized_path("../../etc/async-20240101.glog"),
+            Some(PathBuf::from("etc/async-20240101.glog"))
+        );
+        assert_eq!(
+            sanitized_path("C:\\日志\\.\\log.glogmmap"),
+            Some(["日志", "log.glogmmap"].iter().collect())
+        );
+        assert_eq!(
+            sanitized_path("/abs//x.glog"),
+            Some(PathBuf::from("abs/x.glog"))
+        );
+        assert_eq!(sanitized_path("../.."), None);
+        assert_eq!(sanitized_path("a\0b.glog"), None);
+    }
+
+    #[test]
+    fn test_gbk_entry_name_is_decoded_and_extracted() {
+        let placeholder = "XXXX/async-20240101.glog";
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(placeholder, FileOptions::default()).unwrap();
+        zip.write_all(&glog_file(&["gbk"])).unwrap();
+        let mut bytes = zip.finish().unwrap().into_inner();
+        // 把本地文件头和中央目录中的占位名替换为 GBK 编码的“日志”，不设置 UTF-8 标志
+        let mut start = 0;
+        while let Some(pos) = bytes[start..]
+            .windows(placeholder.len())
+            .position(|window| window == placeholder.as_bytes())
+        {
+            start += pos;
+            bytes[start..start + 4].copy_from_slice(&[0xC8, 0xD5, 0xD6, 0xBE]);
+        }
+
+        let mut archive = GlogArchive::from_reader(Cursor::new(bytes), None).unwrap();
+        let files = archive.files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "日志/async-20240101.glog");
+        let dir = tempfile::tempdir().unwrap();
+        let path = archive.extract(0, dir.path()).unwrap();
+        assert_eq!(path, dir.path().join("日志").join("async-20240101.glog"));
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn test_observer_reports_entry_names() {
+        #[derive(Default)]
+        struct Names(std::sync::Mutex<Vec<String>>);
+
+        impl Observer for Names {
+            fn on_file_start(&self, path: Option<&str>) {
+                self.0.lock().unwrap().push(path.unwrap().to_string());
+            }
+        }
+
+        let mut archive = archive(&[
+            ("logs/async-20240102.glog", (0, 0), glog_file(&["b1"])),
+            ("logs/async-20240101.glog", (0, 0), glog_file(&["a1"])),
+        ]);
+        let names = Arc::new(Names::default());
+        archive.set_observer(names.clone());
+
+        assert_eq!(archive.iter_all_logs().count(), 2);
+        assert_eq!(
+            *names.0.lock().unwrap(),
+            ["logs/async-20240101.glog", "logs/async-20240102.glog"]
+        );
+    }
+
+    #[test]
+    fn test_cancel_token_stops_iteration() {
+        let mut archive = archive(&[
+            ("async-20240101.glog", (0, 0), glog_file(&["a1", "a2"])),
+            ("async-20240102.glog", (0, 0), glog_file(&["b1"])),
+        ]);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut logs = archive.iter_all_logs().cancel_token(Arc::clone(&cancel));
+
+        assert_eq!(logs.next().unwrap().unwrap().msg, "a1");
+        cancel.store(true, Ordering::Relaxed);
+        let err = logs.next().unwrap().unwrap_err();
+        assert!(matches!(err.kind(), GlogError::Cancelled));
+        assert!(logs.next().is_none());
+    }
+}
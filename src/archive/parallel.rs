@@ -0,0 +1,578 @@
+//! # 多文件并行解码
+//!
+//! 压缩包解出的日志文件相互独立，每个文件由 rayon 线程池中的一个任务单独解码
+//! （各自的读取器、解压器和 ECDH 共享密钥缓存互不共享），解码结果通过通道交回调用线程，
+//! 回调始终在调用线程上执行，不需要 `Send`。
+//!
+//! 同一文件内的日志总是保持原顺序；文件之间的顺序取决于 [`ParallelOrder`]：
+//! 默认各文件的日志交错产出，[`ParallelOrder::Merged`] 按时间戳合并。
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+
+use rayon::{ScopeFifo, ThreadPool, ThreadPoolBuilder};
+use zeroize::Zeroizing;
+
+use crate::error::GlogError;
+use crate::glog::{GlogMetadata, GlogReader};
+use crate::observer::Observer;
+use crate::proto::{Log, LogFilter};
+use crate::reader::InflaterPool;
+
+/// 交错模式下通道中缓存的日志条数上限，回调跟不上时解码任务等待
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 多文件并行解码时各文件日志之间的顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParallelOrder {
+    /// 日志解码出来即交给回调，同一文件内保持原顺序，文件之间交错
+    #[default]
+    Interleaved,
+    /// 按时间戳合并各文件的日志，顺序与 [`MergedGlogReader`](crate::merge::MergedGlogReader) 相同。
+    /// 需要等全部文件解码完成后才开始回调，解码结果全部保存在内存中
+    Merged,
+}
+
+/// 单个文件的解码统计
+#[derive(Debug)]
+pub struct FileStats {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 交给回调的日志条数（满足过滤条件的）
+    pub logs: usize,
+    /// 文件无法打开、条目解码失败或读取出错时的错误
+    pub errors: Vec<GlogError>,
+    /// 读取结束时的文件元数据，文件无法打开时为 `None`
+    pub metadata: Option<GlogMetadata>,
+}
+
+impl FileStats {
+    /// 文件是否完整读取且没有错误
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.metadata.is_some()
+    }
+}
+
+/// 多文件并行解码器
+///
+/// # 示例
+///
+/// ```rust,no_run
+/// use clog_reader::archive::{ParallelDecoder, ParallelOrder};
+///
+/// let stats = ParallelDecoder::new(vec!["a.glog".into(), "b.glog".into()])
+///     .threads(4)
+///     .order(ParallelOrder::Merged)
+///     .run(|path, log| println!("{}: {}", path.display(), log));
+/// for file in &stats {
+///     println!("{}: {} 条日志, {} 个错误", file.path.display(), file.logs, file.errors.len());
+/// }
+/// ```
+pub struct ParallelDecoder {
+    /// 要解码的日志文件
+    files: Vec<PathBuf>,
+    /// 可选的服务器私钥（十六进制字符串）
+    key: Option<Zeroizing<String>>,
+    /// 过滤条件
+    filter: Option<LogFilter>,
+    /// 日志顺序
+    order: ParallelOrder,
+    /// 工作线程数，`None` 时使用 rayon 全局线程池
+    threads: Option<usize>,
+    /// 取消标志
+    cancel: Option<Arc<AtomicBool>>,
+    /// 交给每个读取器的观察者
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl ParallelDecoder {
+    /// 创建解码器
+    ///
+    /// # Arguments
+    /// * `files` - 日志文件路径，顺序即统计结果的顺序及时间戳相同时的产出顺序
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            key: None,
+            filter: None,
+            order: ParallelOrder::default(),
+            threads: None,
+            cancel: None,
+            observer: None,
+        }
+    }
+
+    /// 设置服务器私钥（用于解密 V4 版本的加密日志）
+    pub fn key(mut self, key: Option<String>) -> Self {
+        self.key = key.map(Zeroizing::new);
+        self
+    }
+
+    /// 设置过滤条件，不满足的日志不交给回调
+    pub fn filter(mut self, filter: Option<LogFilter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// 设置各文件日志之间的顺序
+    pub fn order(mut self, order: ParallelOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// 使用指定线程数的独立线程池，默认使用 rayon 全局线程池
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// 设置取消标志
+    ///
+    /// 各文件的读取器每条日志前检查一次，置位后正在解码的文件记录 `GlogError::Cancelled` 并结束，
+    /// 尚未开始的文件不再打开，同样只记录该错误
+    pub fn cancel_token(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// 设置读取过程的观察者，在各工作线程上由各文件的读取器通知
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// 并行解码全部文件，在调用线程上逐条回调 `sink`
+    ///
+    /// 不应在 rayon 线程池的工作线程中调用：调用线程等待解码结果期间不会执行其他任务
+    ///
+    /// # Arguments
+    /// * `sink` - 接收每条日志及其所在文件的回调
+    ///
+    /// # Returns
+    /// 返回按 `files` 顺序排列的各文件统计。独立线程池无法创建时，每个文件都记录该错误
+    pub fn run<F>(self, mut sink: F) -> Vec<FileStats>
+    where
+        F: FnMut(&Path, Log),
+    {
+        let pool = match build_pool(self.threads, self.files.len()) {
+            Ok(pool) => pool,
+            Err(e) => {
+                let message = e.to_string();
+                return self
+                    .files
+                    .into_iter()
+                    .map(|path| FileStats {
+                        path,
+                        logs: 0,
+                        errors: vec![GlogError::Io(io::Error::other(message.clone()))],
+                        metadata: None,
+                    })
+                    .collect();
+            }
+        };
+        let files = &self.files;
+        let key = self.key.as_deref().map(String::as_str);
+        let filter = self.filter.as_ref();
+        let cancel = self.cancel.as_ref();
+        let observer = self.observer.as_ref();
+        let inflaters = &InflaterPool::new();
+        match self.order {
+            ParallelOrder::Interleaved => in_place_scope_fifo(pool.as_ref(), |scope| {
+                let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+                for (index, path) in files.iter().enumerate() {
+                    let tx = tx.clone();
+                    scope.spawn_fifo(move |_| {
+                        let stats =
+                            decode_file(path, key, filter, cancel, observer, inflaters, |log| {
+                                let _ = tx.send(Event::Log(index, log));
+                            });
+                        let _ = tx.send(Event::Done(index, stats));
+                    });
+                }
+                drop(tx);
+
+                let mut stats: Vec<Option<FileStats>> = files.iter().map(|_| None).collect();
+                for event in rx {
+                    match event {
+                        Event::Log(index, log) => sink(&files[index], log),
+                        Event::Done(index, file_stats) => stats[index] = Some(file_stats),
+                    }
+                }
+                stats.into_iter().flatten().collect()
+            }),
+            ParallelOrder::Merged => {
+                let mut decoded = Vec::with_capacity(files.len());
+                map_in_order(pool.as_ref(), files, |path| {
+                    let mut logs = Vec::new();
+                    let stats =
+                        decode_file(path, key, filter, cancel, observer, inflaters, |log| {
+                            logs.push(log)
+                        });
+                    (stats, logs)
+                }, |_, result| {
+                    decoded.push(result);
+                    Ok::<_, GlogError>(())
+                })
+                .ok();
+                let (stats, logs): (Vec<_>, Vec<_>) = decoded.into_iter().unzip();
+                merge_by_timestamp(logs, |index, log| sink(&files[index], log));
+                stats
+            }
+        }
+    }
+}
+
+/// 交错模式下解码任务发回的消息
+enum Event {
+    /// 文件序号及其中的一条日志
+    Log(usize, Log),
+    /// 文件解码完成
+    Done(usize, FileStats),
+}
+
+/// 并行解码多个日志文件，在调用线程上逐条回调 `sink`
+///
+/// 使用 rayon 全局线程池，同一文件内保持原顺序，文件之间的顺序不确定；
+/// 需要按时间戳合并或指定线程数时使用 [`ParallelDecoder`]
+///
+/// # Arguments
+/// * `files` - 日志文件路径
+/// * `key` - 可选的服务器私钥（十六进制字符串）
+/// * `filter` - 可选的过滤条件
+/// * `sink` - 接收每条日志及其所在文件的回调
+///
+/// # Returns
+/// 返回按 `files` 顺序排列的各文件统计
+pub fn decode_parallel<F>(
+    files: Vec<PathBuf>,
+    key: Option<String>,
+    filter: Option<LogFilter>,
+    sink: F,
+) -> Vec<FileStats>
+where
+    F: FnMut(&Path, Log),
+{
+    ParallelDecoder::new(files).key(key).filter(filter).run(sink)
+}
+
+/// 在线程池中并行处理多个文件，按文件顺序依次把结果交给 `on_result`
+///
+/// 文件按顺序派发给工作线程，提前完成的结果缓存到前面的文件完成为止，
+/// 因此 `on_result` 的调用顺序与单线程逐个处理完全一致。
+/// `on_result` 返回错误后不再派发新的文件，已开始的文件处理完后返回该错误
+///
+/// # Arguments
+/// * `files` - 按顺序排列的文件
+/// * `threads` - 工作线程数，`None` 时使用 rayon 全局线程池
+/// * `process` - 在工作线程上处理单个文件
+/// * `on_result` - 在调用线程上按文件顺序接收处理结果
+///
+/// # Errors
+/// 独立线程池无法创建或 `on_result` 返回错误时返回错误
+pub fn map_files_parallel<T, E, P, F>(
+    files: &[PathBuf],
+    threads: Option<usize>,
+    process: P,
+    on_result: F,
+) -> std::result::Result<(), E>
+where
+    T: Send,
+    E: From<GlogError>,
+    P: Fn(&Path) -> T + Sync,
+    F: FnMut(&Path, T) -> std::result::Result<(), E>,
+{
+    let pool = build_pool(threads, files.len())
+        .map_err(|e| GlogError::Io(io::Error::other(e.to_string())))?;
+    map_in_order(pool.as_ref(), files, process, on_result)
+}
+
+/// [`map_files_parallel`] 的实现，在给定的线程池（`None` 时为全局线程池）中运行
+fn map_in_order<T, E, P, F>(
+    pool: Option<&ThreadPool>,
+    files: &[PathBuf],
+    process: P,
+    mut on_result: F,
+) -> std::result::Result<(), E>
+where
+    T: Send,
+    P: Fn(&Path) -> T + Sync,
+    F: FnMut(&Path, T) -> std::result::Result<(), E>,
+{
+    let cancelled = AtomicBool::new(false);
+    let process = &process;
+    let cancelled_ref = &cancelled;
+    in_place_scope_fifo(pool, |scope| {
+        let (tx, rx) = mpsc::channel();
+        for (index, path) in files.iter().enumerate() {
+            let tx = tx.clone();
+            scope.spawn_fifo(move |_| {
+                if !cancelled_ref.load(Ordering::Relaxed) {
+                    let _ = tx.send((index, process(path)));
+                }
+            });
+        }
+        drop(tx);
+
+        // 缓存提前完成的结果，按文件顺序依次交给回调
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        for (index, result) in rx {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&expected) {
+                if let Err(e) = on_result(&files[expected], result) {
+                    cancelled.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+                expected += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// 解码单个日志文件
+///
+/// # Arguments
+/// * `path` - 日志文件路径
+/// * `key` - 可选的服务器私钥
+/// * `filter` - 可选的过滤条件
+/// * `cancel` - 可选的取消标志
+/// * `observer` - 可选的观察者
+/// * `inflaters` - 各个文件共享的解压上下文池
+/// * `emit` - 接收每条满足过滤条件的日志
+fn decode_file(
+    path: &Path,
+    key: Option<&str>,
+    filter: Option<&LogFilter>,
+    cancel: Option<&Arc<AtomicBool>>,
+    observer: Option<&Arc<dyn Observer>>,
+    inflaters: &InflaterPool,
+    mut emit: impl FnMut(Log),
+) -> FileStats {
+    let mut stats = FileStats {
+        path: path.to_path_buf(),
+        logs: 0,
+        errors: Vec::new(),
+        metadata: None,
+    };
+    if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+        stats.errors.push(GlogError::Cancelled);
+        return stats;
+    }
+    let builder = GlogReader::builder().inflater_pool(inflaters.clone());
+    let builder = match key {
+        Some(key) => builder.key(key),
+        None => builder,
+    };
+    let builder = match cancel {
+        Some(cancel) => builder.cancel_token(Arc::clone(cancel)),
+        None => builder,
+    };
+    let builder = match observer {
+        Some(observer) => builder.observer(Arc::clone(observer)),
+        None => builder,
+    };
+    let mut reader = match builder.open(&path.to_string_lossy()) {
+        Ok(reader) => reader,
+        Err(e) => {
+            stats.errors.push(e);
+            return stats;
+        }
+    };
+    let mut buf = Vec::new();
+    let mut done = false;
+    while let Some(result) = reader.next_log(&mut buf, filter, &mut done) {
+        match result {
+            Ok((_, log)) => {
+                stats.logs += 1;
+                emit(log);
+            }
+            Err(e) => stats.errors.push(e),
+        }
+    }
+    stats.metadata = Some(reader.metadata());
+    stats
+}
+
+/// 按时间戳合并各文件已解码的日志
+///
+/// 时间戳无法解析的日志沿用同一文件上一条日志的时间戳，时间戳相同时按文件顺序
+///
+/// # Arguments
+/// * `files` - 各文件按原顺序排列的日志
+/// * `emit` - 按合并顺序接收文件序号和日志
+fn merge_by_timestamp(files: Vec<Vec<Log>>, mut emit: impl FnMut(usize, Log)) {
+    let mut sources: Vec<_> = files
+        .into_iter()
+        .map(|logs| {
+            let mut last = i64::MIN;
+            logs.into_iter()
+                .map(|log| {
+                    last = log.timestamp_millis().unwrap_or(last);
+                    (last, log)
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .peekable()
+        })
+        .collect();
+    let mut heap: BinaryHeap<_> = sources
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(index, logs)| logs.peek().map(|(ts, _)| Reverse((*ts, index))))
+        .collect();
+    while let Some(Reverse((_, index))) = heap.pop() {
+        let Some((_, log)) = sources[index].next() else {
+            continue;
+        };
+        if let Some((ts, _)) = sources[index].peek() {
+            heap.push(Reverse((*ts, index)));
+        }
+        emit(index, log);
+    }
+}
+
+/// 按配置创建独立线程池，`None` 时使用全局线程池
+///
+/// # Arguments
+/// * `threads` - 工作线程数，不超过文件数
+/// * `files` - 文件数
+fn build_pool(
+    threads: Option<usize>,
+    files: usize,
+) -> std::result::Result<Option<ThreadPool>, rayon::ThreadPoolBuildError> {
+    threads
+        .map(|n| {
+            ThreadPoolBuilder::new()
+                .num_threads(n.clamp(1, files.max(1)))
+                .build()
+        })
+        .transpose()
+}
+
+/// 在给定线程池或全局线程池中创建作用域，`op` 在调用线程上执行
+fn in_place_scope_fifo<'scope, R>(
+    pool: Option<&ThreadPool>,
+    op: impl FnOnce(&ScopeFifo<'scope>) -> R,
+) -> R {
+    match pool {
+        Some(pool) => pool.in_place_scope_fifo(op),
+        None => rayon::in_place_scope_fifo(op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::CompressMode;
+    use crate::writer::v3::GlogWriterV3;
+    use tempfile::TempDir;
+
+    /// 在临时目录中写入日志文件，`logs` 为（时间戳, 内容）
+    fn glog_file(dir: &TempDir, name: &str, logs: &[(&str, &str)]) -> PathBuf {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        for (timestamp, msg) in logs {
+            writer
+                .write_log(&Log {
+                    timestamp: timestamp.to_string(),
+                    msg: msg.to_string(),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        let path = dir.path().join(name);
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_interleaved_keeps_per_file_order_and_reports_stats() {
+        let dir = TempDir::new().unwrap();
+        let msgs: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        let logs: Vec<(&str, &str)> = msgs.iter().map(|m| ("1700000000000", m.as_str())).collect();
+        let files = vec![
+            glog_file(&dir, "a.glog", &logs),
+            dir.path().join("missing.glog"),
+            glog_file(&dir, "b.glog", &logs[..50]),
+        ];
+
+        let mut received: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        let stats = ParallelDecoder::new(files.clone())
+            .threads(3)
+            .run(|path, log| received.entry(path.to_path_buf()).or_default().push(log.msg));
+
+        assert_eq!(received[&files[0]], msgs);
+        assert_eq!(received[&files[2]], msgs[..50]);
+        let counts: Vec<usize> = stats.iter().map(|s| s.logs).collect();
+        assert_eq!(counts, [200, 0, 50]);
+        assert!(stats[0].is_ok() && stats[2].is_ok());
+        assert!(!stats[1].is_ok());
+        assert_eq!(stats[1].path, files[1]);
+    }
+
+    #[test]
+    fn test_merged_orders_by_timestamp_with_filter() {
+        let dir = TempDir::new().unwrap();
+        let files = vec![
+            glog_file(&dir, "a.glog", &[("1000", "a1"), ("3000", "a3"), ("bad", "a3+"), ("5000", "skip")]),
+            glog_file(&dir, "b.glog", &[("2000", "b2"), ("3000", "b3"), ("4000", "b4")]),
+        ];
+
+        let mut msgs = Vec::new();
+        let stats = ParallelDecoder::new(files)
+            .filter(Some(LogFilter::new().msg_regex("^[ab]").unwrap()))
+            .order(ParallelOrder::Merged)
+            .run(|_, log| msgs.push(log.msg));
+
+        assert_eq!(msgs, ["a1", "b2", "a3", "a3+", "b3", "b4"]);
+        assert_eq!(stats[0].logs, 3);
+    }
+
+    #[test]
+    fn test_cancel_token_set_before_run_skips_all_files() {
+        let dir = TempDir::new().unwrap();
+        let files = vec![
+            glog_file(&dir, "a.glog", &[("1000", "a1")]),
+            glog_file(&dir, "b.glog", &[("2000", "b1")]),
+        ];
+
+        let mut received = 0;
+        let stats = ParallelDecoder::new(files)
+            .cancel_token(Arc::new(AtomicBool::new(true)))
+            .run(|_, _| received += 1);
+
+        assert_eq!(received, 0);
+        for file in &stats {
+            assert!(matches!(file.errors[..], [GlogError::Cancelled]));
+        }
+    }
+
+    #[test]
+    fn test_map_files_parallel_preserves_file_order_and_stops_on_error() {
+        let files: Vec<PathBuf> = (0..20).map(|i| PathBuf::from(i.to_string())).collect();
+
+        let mut seen = Vec::new();
+        map_files_parallel(&files, Some(4), |path| path.to_path_buf(), |path, result| {
+            assert_eq!(path, result);
+            seen.push(result);
+            Ok::<_, GlogError>(())
+        })
+        .unwrap();
+        assert_eq!(seen, files);
+
+        let mut calls = 0;
+        let result = map_files_parallel(&files, Some(4), |_| (), |_, ()| {
+            calls += 1;
+            if calls == 3 {
+                return Err(GlogError::MagicMismatch);
+            }
+            Ok(())
+        });
+        assert!(matches!(result, Err(GlogError::MagicMismatch)));
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,117 @@
+//! # 日志文件名规则
+//!
+//! 默认只有 `async-` 开头、`.glog` 结尾的文件被当作 glog 文件，使用其他前缀的写入端可以通过
+//! [`FileNamePattern`] 指定自己的规则。日期取自文件名中第一段 8 位数字（YYYYMMdd），
+//! 紧随其后的 `.N` 是同一天滚动产生的文件序号，如 `async-im-20240101.glog`、`async-20240101.1.glog`。
+
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// 识别 glog 文件名的默认正则表达式
+pub const DEFAULT_FILE_PATTERN: &str = r"^async-.*\.glog$";
+
+/// 识别 glog 文件的文件名规则
+///
+/// 正则表达式只与路径中的文件名部分匹配
+///
+/// ```
+/// use clog_reader::archive::FileNamePattern;
+///
+/// let pattern = FileNamePattern::new(r"^applog_\d{8}\.glog$")?;
+/// assert!(pattern.is_match("logs/applog_20240101.glog"));
+/// assert!(!pattern.is_match("logs/async-20240101.glog"));
+/// # Ok::<(), regex::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileNamePattern {
+    /// 与文件名匹配的正则表达式
+    regex: Regex,
+}
+
+impl FileNamePattern {
+    /// 创建文件名规则
+    ///
+    /// # Arguments
+    /// * `pattern` - 正则表达式，与路径中的文件名部分匹配
+    ///
+    /// # Errors
+    /// 正则表达式无效时返回错误
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// 正则表达式的文本
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// 路径中的文件名部分是否符合规则
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(super::file_name(name))
+    }
+}
+
+impl Default for FileNamePattern {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILE_PATTERN).expect("默认文件名规则是有效的正则表达式")
+    }
+}
+
+/// 提取文件名中的日期和滚动序号，用于排序
+///
+/// 日期为第一段恰好 8 位的数字，不是有效日期时为 `None`；没有滚动序号时为 0
+pub(super) fn glog_sort_key(name: &str) -> (Option<NaiveDate>, u32) {
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let date = DATE.get_or_init(|| {
+        Regex::new(r"(?:^|[^0-9])([0-9]{8})(?:\.([0-9]+))?(?:[^0-9]|$)").expect("日期正则有效")
+    });
+    let Some(caps) = date.captures(super::file_name(name)) else {
+        return (None, 0);
+    };
+    let day = NaiveDate::parse_from_str(&caps[1], "%Y%m%d").ok();
+    let roll = caps
+        .get(2)
+        .and_then(|roll| roll.as_str().parse().ok())
+        .unwrap_or(0);
+    (day, roll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+    }
+
+    #[test]
+    fn test_sort_key_name_shapes() {
+        assert_eq!(glog_sort_key("async-20240101.glog"), (date(1), 0));
+        assert_eq!(glog_sort_key("logs/async-im-20240102.glog"), (date(2), 0));
+        assert_eq!(glog_sort_key("async-20240103.1.glog"), (date(3), 1));
+        assert_eq!(glog_sort_key("async-20240103.12.glog"), (date(3), 12));
+        assert_eq!(glog_sort_key("applog_20240104.glog"), (date(4), 0));
+        // 超过 8 位的数字不是日期
+        assert_eq!(glog_sort_key("async-1704067200000.glog"), (None, 0));
+        assert_eq!(glog_sort_key("async-20241399.glog"), (None, 0));
+        assert_eq!(glog_sort_key("async-.glog"), (None, 0));
+        assert_eq!(glog_sort_key("readme.txt"), (None, 0));
+    }
+
+    #[test]
+    fn test_default_pattern() {
+        let pattern = FileNamePattern::default();
+        assert!(pattern.is_match("async-20240101.glog"));
+        assert!(pattern.is_match("logs/async-im-20240101.glog"));
+        assert!(pattern.is_match("async-20240101.1.glog"));
+        assert!(!pattern.is_match("async.glogmmap"));
+        assert!(!pattern.is_match("applog_20240101.glog"));
+        assert!(!pattern.is_match("async-20240101.glog.bak"));
+        assert!(!pattern.is_match("async-/readme.txt"));
+        assert!(FileNamePattern::new("(").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! # 后台线程流式解码
+//!
+//! [`GlogArchive::stream`] 把压缩包移动到后台线程，按处理顺序逐条解码日志，
+//! 通过有界的 [`mpsc::sync_channel`] 交给调用方，适合 GUI 或服务端边解码边消费。
+//! 通道满时解码线程等待，内存占用不随压缩包大小增长；接收端被丢弃后，
+//! 解码线程在下一次发送时发现并停止，不再打开剩余的文件。
+
+use std::io::{Read, Seek};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use zeroize::Zeroizing;
+
+use super::GlogArchive;
+use crate::error::GlogError;
+use crate::proto::{Log, LogFilter};
+
+/// 通道中缓存的日志条数上限的默认值
+pub const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+/// 一条解码后的日志及其来源
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedLog {
+    /// 来源日志文件在压缩包中的路径
+    pub file: String,
+    /// 条目在日志文件中的起始偏移
+    pub offset: u64,
+    /// 日志内容
+    pub log: Log,
+}
+
+/// 流式解码的统计，解码线程结束时通过 [`JoinHandle::join`] 取回
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    /// 成功打开的日志文件数
+    pub files: usize,
+    /// 发送到通道的日志条数（满足过滤条件的）
+    pub logs: u64,
+    /// 文件无法打开、条目解码失败或读取出错时的错误，错误中带有文件路径
+    pub errors: Vec<GlogError>,
+    /// 接收端在全部日志发送完之前被丢弃，解码提前停止
+    pub disconnected: bool,
+}
+
+impl<R: Read + Seek + Send + 'static> GlogArchive<R> {
+    /// 在后台线程中解码全部日志，通过通道逐条产出
+    ///
+    /// 与 [`stream_with_capacity`](Self::stream_with_capacity) 相同，通道容量为 [`DEFAULT_STREAM_CAPACITY`]
+    ///
+    /// # Arguments
+    /// * `key` - 服务器私钥，为 `None` 时使用打开压缩包时提供的私钥
+    /// * `filter` - 可选的过滤条件
+    pub fn stream(
+        self,
+        key: Option<String>,
+        filter: Option<LogFilter>,
+    ) -> (JoinHandle<StreamStats>, Receiver<DecodedLog>) {
+        self.stream_with_capacity(key, filter, DEFAULT_STREAM_CAPACITY)
+    }
+
+    /// 在后台线程中解码全部日志，通过容量为 `capacity` 的通道逐条产出
+    ///
+    /// 按 [`files`](Self::files) 的顺序读取，单个文件打开失败或读取出错时记入
+    /// [`StreamStats::errors`]，然后继续下一个文件。丢弃接收端即可停止解码
+    ///
+    /// # Arguments
+    /// * `key` - 服务器私钥，为 `None` 时使用打开压缩包时提供的私钥
+    /// * `filter` - 可选的过滤条件
+    /// * `capacity` - 通道中缓存的日志条数上限，为 0 时每条日志都等待接收端取走
+    ///
+    /// # Returns
+    /// 返回解码线程的句柄和接收端；通道关闭（`recv` 返回错误）后 `join` 取回统计
+    pub fn stream_with_capacity(
+        mut self,
+        key: Option<String>,
+        filter: Option<LogFilter>,
+        capacity: usize,
+    ) -> (JoinHandle<StreamStats>, Receiver<DecodedLog>) {
+        if let Some(key) = key {
+            self.key = Some(Zeroizing::new(key));
+        }
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let handle = thread::spawn(move || self.send_all(filter.as_ref(), &sender));
+        (handle, receiver)
+    }
+
+    /// 解码线程：依次读取每个日志文件，发送满足过滤条件的日志
+    fn send_all(
+        &mut self,
+        filter: Option<&LogFilter>,
+        sender: &SyncSender<DecodedLog>,
+    ) -> StreamStats {
+        let mut stats = StreamStats::default();
+        let mut buf = Vec::new();
+        for index in 0..self.entries.len() {
+            let mut reader = match self.open_reader(index, None) {
+                Ok(reader) => reader,
+                Err(e) => {
+                    stats.errors.push(e);
+                    continue;
+                }
+            };
+            stats.files += 1;
+            let file = &self.entries[index].1.name;
+            let mut done = false;
+            while let Some(result) = reader.next_log(&mut buf, filter, &mut done) {
+                let (offset, log) = match result {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        stats.errors.push(e);
+                        continue;
+                    }
+                };
+                let decoded = DecodedLog {
+                    file: file.clone(),
+                    offset,
+                    log,
+                };
+                if sender.send(decoded).is_err() {
+                    stats.disconnected = true;
+                    return stats;
+                }
+                stats.logs += 1;
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::CompressMode;
+    use crate::writer::v3::GlogWriterV3;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn glog_file(count: usize) -> Vec<u8> {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        for i in 0..count {
+            writer
+                .write_log(&Log {
+                    tag: if i % 2 == 0 { "Even" } else { "Odd" }.to_string(),
+                    msg: format!("message {}", i),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    fn archive(files: &[(&str, Vec<u8>)]) -> GlogArchive<Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        GlogArchive::from_reader(Cursor::new(zip.finish().unwrap().into_inner()), None).unwrap()
+    }
+
+    #[test]
+    fn test_stream_sends_all_logs_in_order() {
+        let archive = archive(&[
+            ("async-20240102.glog", glog_file(3)),
+            ("async-20240101.glog", glog_file(2)),
+        ]);
+        let (handle, receiver) = archive.stream_with_capacity(None, None, 1);
+
+        let logs: Vec<DecodedLog> = receiver.iter().collect();
+        let stats = handle.join().unwrap();
+        let sources: Vec<(&str, &str)> = logs
+            .iter()
+            .map(|log| (log.file.as_str(), log.log.msg.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                ("async-20240101.glog", "message 0"),
+                ("async-20240101.glog", "message 1"),
+                ("async-20240102.glog", "message 0"),
+                ("async-20240102.glog", "message 1"),
+                ("async-20240102.glog", "message 2"),
+            ]
+        );
+        assert!(logs[0].offset < logs[1].offset);
+        assert_eq!((stats.files, stats.logs), (2, 5));
+        assert!(stats.errors.is_empty());
+        assert!(!stats.disconnected);
+    }
+
+    #[test]
+    fn test_stream_applies_filter() {
+        let archive = archive(&[("async-20240101.glog", glog_file(6))]);
+        let filter = LogFilter::new().tag("Odd");
+        let (handle, receiver) = archive.stream(None, Some(filter));
+
+        let msgs: Vec<String> = receiver.iter().map(|log| log.log.msg).collect();
+        assert_eq!(msgs, ["message 1", "message 3", "message 5"]);
+        assert_eq!(handle.join().unwrap().logs, 3);
+    }
+
+    #[test]
+    fn test_dropping_receiver_stops_worker() {
+        let archive = archive(&[
+            ("async-20240101.glog", glog_file(2000)),
+            ("async-20240102.glog", glog_file(2000)),
+        ]);
+        let (handle, receiver) = archive.stream_with_capacity(None, None, 4);
+
+        let first: Vec<DecodedLog> = receiver.iter().take(3).collect();
+        assert_eq!(first.len(), 3);
+        drop(receiver);
+        let stats = handle.join().unwrap();
+        assert!(stats.disconnected);
+        // 通道最多缓存 4 条，接收端丢弃后不再继续解码
+        assert!(stats.logs <= 3 + 4, "{}", stats.logs);
+        assert_eq!(stats.files, 1);
+    }
+
+    #[test]
+    fn test_error_file_mid_stream_is_reported_and_skipped() {
+        let archive = archive(&[
+            ("async-20240101.glog", glog_file(2)),
+            ("async-20240102.glog", b"not a glog file".to_vec()),
+            ("async-20240103.glog", glog_file(1)),
+        ]);
+        let (handle, receiver) = archive.stream(None, None);
+
+        let files: Vec<String> = receiver.iter().map(|log| log.file).collect();
+        assert_eq!(
+            files,
+            [
+                "async-20240101.glog",
+                "async-20240101.glog",
+                "async-20240103.glog"
+            ]
+        );
+        let stats = handle.join().unwrap();
+        assert_eq!((stats.files, stats.logs), (2, 3));
+        assert_eq!(stats.errors.len(), 1);
+        assert!(matches!(stats.errors[0].kind(), GlogError::MagicMismatch));
+        assert_eq!(stats.errors[0].path(), Some("async-20240102.glog"));
+    }
+}
@@ -0,0 +1,201 @@
+//! # 异步读取
+//!
+//! 基于 tokio 的服务在执行器线程上解码上传的日志时不能阻塞。[`AsyncGlogReader`] 从
+//! [`AsyncRead`] 读取数据，用 [`framing`](crate::reader::framing) 中的无 IO 分帧函数判断
+//! 已收到的数据中有多少是完整的条目，只把完整的条目交给内部的 [`GlogReader`] 解码，
+//! 因此解码时不会等待 IO。
+//!
+//! 损坏的条目交到其后的下一个同步标记为止，由阻塞读取器按恢复策略处理。
+//! V1 / V2 文件和 `.glogmmap` 包装没有可以流式分帧的结构，读到输入结束后再解码。
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::Result;
+use crate::glog::{GlogReader, GlogReaderBuilder};
+use crate::proto::Log;
+use crate::reader::framing::{entry_frame, header_frame, Frame};
+use crate::reader::{find_sync_markers, MAGIC_NUMBER};
+
+/// 每次从输入读取的字节数
+const READ_CHUNK: usize = 64 * 1024;
+
+/// 异步 Glog 读取器
+///
+/// 输入流实现 `Send` 时读取器也实现 `Send`，可以在 `tokio::spawn` 的任务中使用
+pub struct AsyncGlogReader<R> {
+    /// 输入流
+    input: R,
+    /// 打开读取器时使用的配置，读取器创建后为 `None`
+    builder: Option<GlogReaderBuilder>,
+    /// 内部的阻塞读取器，收到完整的文件头后创建
+    reader: Option<GlogReader>,
+    /// 已收到、尚未交给读取器的数据，总是从条目边界开始
+    pending: Vec<u8>,
+    /// 交给读取器的数据
+    pipe: Pipe,
+    /// 已交给读取器的字节数
+    fed: u64,
+    /// 可以流式分帧的文件格式版本（按完整文件读取时为 `None`）
+    version: Option<u8>,
+    /// 输入是否已结束
+    eof: bool,
+    /// 是否已读完或遇到无法继续的错误
+    finished: bool,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncGlogReader<R> {
+    /// 创建异步读取器
+    ///
+    /// # Arguments
+    /// * `input` - 从文件起始位置开始的输入流
+    /// * `key` - 可选的服务器私钥（十六进制字符串）
+    pub fn new(input: R, key: Option<String>) -> Self {
+        let builder = GlogReader::builder();
+        let builder = match key {
+            Some(key) => builder.key(key),
+            None => builder,
+        };
+        Self::with_builder(input, builder)
+    }
+
+    /// 按给定的配置创建异步读取器，密钥、恢复策略等与 [`GlogReaderBuilder`] 相同
+    ///
+    /// # Arguments
+    /// * `input` - 从文件起始位置开始的输入流
+    /// * `builder` - 读取器配置
+    pub fn with_builder(input: R, builder: GlogReaderBuilder) -> Self {
+        Self {
+            input,
+            builder: Some(builder),
+            reader: None,
+            pending: Vec::new(),
+            pipe: Pipe::default(),
+            fed: 0,
+            version: None,
+            eof: false,
+            finished: false,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 读取并解码下一条日志
+    ///
+    /// 与 [`Logs`](crate::glog::Logs) 相同：protobuf 解码失败以 `Err` 返回后可以继续读取，
+    /// 读取错误（IO、文件头无效、解密等）之后返回 `None`
+    ///
+    /// # Returns
+    /// 返回下一条日志，读到输入末尾时返回 `None`
+    pub async fn next_log(&mut self) -> Result<Option<Log>> {
+        while !self.finished {
+            if let Some(reader) = &mut self.reader {
+                let mut done = false;
+                match reader.next_log(&mut self.buf, None, &mut done) {
+                    Some(Ok((_, log))) => return Ok(Some(log)),
+                    Some(Err(e)) => {
+                        self.finished = done;
+                        return Err(e);
+                    }
+                    // 已交给读取器的数据都读完了
+                    None if self.eof && self.pending.is_empty() => self.finished = true,
+                    None => {}
+                }
+            }
+            if !self.finished {
+                self.fill().await?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// 从输入读取数据，直到可以把新的数据交给读取器
+    async fn fill(&mut self) -> Result<()> {
+        loop {
+            if self.reader.is_none() {
+                match header_frame(&self.pending) {
+                    Frame::Complete(len) => {
+                        self.version = Some(self.pending[MAGIC_NUMBER.len()]);
+                        return self.open(len);
+                    }
+                    // 无法流式分帧的格式读到输入结束后整体解码，错误也由读取器给出
+                    _ if self.eof => return self.open(self.pending.len()),
+                    _ => {}
+                }
+            } else {
+                let ready = match self.version {
+                    Some(version) => ready_len(version, &self.pending),
+                    None => 0,
+                };
+                if ready > 0 || self.eof {
+                    self.feed(if self.eof { self.pending.len() } else { ready });
+                    return Ok(());
+                }
+            }
+
+            self.pending.reserve(READ_CHUNK);
+            if self.input.read_buf(&mut self.pending).await? == 0 {
+                self.eof = true;
+            }
+        }
+    }
+
+    /// 把文件头交给读取器并创建读取器
+    fn open(&mut self, len: usize) -> Result<()> {
+        self.feed(len);
+        let builder = self.builder.take().unwrap_or_default();
+        match builder.from_reader(self.pipe.clone(), self.fed) {
+            Ok(reader) => {
+                self.reader = Some(reader);
+                Ok(())
+            }
+            Err(e) => {
+                self.finished = true;
+                Err(e)
+            }
+        }
+    }
+
+    /// 把 `pending` 开头的 `len` 字节交给读取器
+    fn feed(&mut self, len: usize) {
+        self.pipe.0.lock().unwrap().extend(self.pending.drain(..len));
+        self.fed += len as u64;
+        if let Some(reader) = &mut self.reader {
+            reader.set_size(self.fed);
+        }
+    }
+}
+
+/// 计算 `data` 开头可以交给读取器的字节数
+///
+/// 完整的条目全部交出；开头的条目损坏时交到下一个同步标记为止，
+/// 读取器恢复时扫描到该标记后从下一个条目继续。还需要更多数据时返回 0
+fn ready_len(version: u8, data: &[u8]) -> usize {
+    let mut end = 0;
+    loop {
+        match entry_frame(version, &data[end..]) {
+            Frame::Complete(len) => end += len,
+            Frame::Incomplete => return end,
+            Frame::Corrupt if end > 0 => return end,
+            Frame::Corrupt => {
+                return find_sync_markers(&data[1..])
+                    .next()
+                    .map_or(0, |offset| 1 + offset as usize)
+            }
+        }
+    }
+}
+
+/// 交给阻塞读取器的数据，读完后返回 0
+#[derive(Clone, Default)]
+struct Pipe(Arc<Mutex<VecDeque<u8>>>);
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(buf)
+    }
+}
@@ -0,0 +1,7 @@
+//! # UniFFI 绑定生成器
+//!
+//! 从编译好的动态库生成 Kotlin / Swift 绑定，参见 [`clog_reader::ffi`]。
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,527 @@
+//! # adb 输入
+//!
+//! `adb` 子命令调用 adb 从已连接的设备拉取应用的日志目录，保存到临时目录后按目录输入处理。
+//! 应用私有目录（相对路径，如默认的 `files/glog`）通过 `run-as` 逐个读取，
+//! 只对可调试（debuggable）版本有效；外部存储等绝对路径直接使用 `adb pull`。
+//!
+//! 命令的执行通过 [`CommandRunner`] 完成，测试中可以替换为不依赖真实设备的实现。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use anyhow::{Context, Result};
+use clog_reader::reader::mmap::MMAP_MAGIC_NUMBER;
+use clog_reader::reader::MAGIC_NUMBER;
+
+/// 读取文件的命令输出中，可能是错误信息的标准输出的最大长度
+const MAX_MESSAGE_LEN: usize = 1024;
+
+/// 外部命令的执行结果
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    /// 是否以退出码 0 结束
+    pub success: bool,
+    /// 标准输出
+    pub stdout: Vec<u8>,
+    /// 标准错误
+    pub stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    /// 标准输出和标准错误合并后的文本，用于识别错误信息
+    fn text(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&self.stderr));
+        text
+    }
+
+    /// 读取文件内容（`exec-out cat`）时用于识别错误信息的文本
+    ///
+    /// 标准输出是文件内容本身，日志中完全可能出现 "No such file or directory" 之类的文字；
+    /// 只有较短且不以 glog / mmap 魔数开头的标准输出（旧版 adb 把错误信息也写到标准输出）才参与识别
+    fn file_error_text(&self) -> String {
+        let is_message = self.stdout.len() <= MAX_MESSAGE_LEN
+            && !self.stdout.starts_with(&MAGIC_NUMBER)
+            && !self.stdout.starts_with(&MMAP_MAGIC_NUMBER);
+        if is_message {
+            self.text()
+        } else {
+            String::from_utf8_lossy(&self.stderr).into_owned()
+        }
+    }
+}
+
+/// 执行外部命令
+pub trait CommandRunner {
+    /// 执行 `program` 并等待结束
+    ///
+    /// # Errors
+    /// 命令无法启动时返回错误（如可执行文件不存在）
+    fn run(&self, program: &Path, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// 使用 [`std::process::Command`] 执行命令
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &Path, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = process::Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+}
+
+impl<F> CommandRunner for F
+where
+    F: Fn(&Path, &[&str]) -> io::Result<CommandOutput>,
+{
+    fn run(&self, program: &Path, args: &[&str]) -> io::Result<CommandOutput> {
+        self(program, args)
+    }
+}
+
+/// adb 客户端
+#[derive(Debug)]
+pub struct Adb<R = SystemRunner> {
+    /// adb 可执行文件路径
+    program: PathBuf,
+    /// 目标设备的序列号，为 `None` 时由 adb 自行选择
+    serial: Option<String>,
+    /// 命令执行器
+    runner: R,
+}
+
+impl Adb {
+    /// 创建使用系统命令的 adb 客户端
+    ///
+    /// # Arguments
+    /// * `program` - adb 可执行文件路径
+    /// * `serial` - 目标设备的序列号，多台设备连接时必须指定
+    pub fn new(program: PathBuf, serial: Option<String>) -> Self {
+        Self::with_runner(program, serial, SystemRunner)
+    }
+}
+
+impl<R: CommandRunner> Adb<R> {
+    /// 创建使用指定执行器的 adb 客户端
+    pub fn with_runner(program: PathBuf, serial: Option<String>, runner: R) -> Self {
+        Self {
+            program,
+            serial,
+            runner,
+        }
+    }
+
+    /// 确认目标设备可用，并固定之后命令使用的序列号
+    ///
+    /// 未指定序列号时要求恰好连接了一台设备
+    ///
+    /// # Returns
+    /// 返回目标设备的序列号
+    ///
+    /// # Errors
+    /// 没有设备、连接了多台设备但未指定序列号、指定的设备不存在，
+    /// 或设备未授权调试、处于离线状态时返回错误
+    pub fn select_device(&mut self) -> Result<String> {
+        let output = self.exec(&["devices"])?;
+        if !output.success {
+            anyhow::bail!("adb devices 执行失败: {}", output.text().trim());
+        }
+        let devices = parse_devices(&String::from_utf8_lossy(&output.stdout));
+        let serials = || {
+            devices
+                .iter()
+                .map(|(serial, _)| serial.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let (serial, state) = match &self.serial {
+            _ if devices.is_empty() => {
+                anyhow::bail!(
+                    "未检测到已连接的设备，请确认设备已开启 USB 调试，并用 `adb devices` 检查"
+                )
+            }
+            Some(serial) => devices.iter().find(|(s, _)| s == serial).with_context(|| {
+                format!("未找到序列号为 {} 的设备，已连接: {}", serial, serials())
+            })?,
+            None if devices.len() > 1 => {
+                anyhow::bail!(
+                    "连接了多台设备（{}），请用 --serial 指定其中一台",
+                    serials()
+                )
+            }
+            None => &devices[0],
+        };
+        match state.as_str() {
+            "device" => {}
+            "unauthorized" => anyhow::bail!(
+                "设备 {} 未授权调试，请在设备上允许 USB 调试授权后重试",
+                serial
+            ),
+            "offline" => anyhow::bail!(
+                "设备 {} 处于离线状态，请重新连接或执行 `adb reconnect` 后重试",
+                serial
+            ),
+            state => anyhow::bail!("设备 {} 当前状态为 {}，无法读取日志", serial, state),
+        }
+        self.serial = Some(serial.clone());
+        Ok(serial.clone())
+    }
+
+    /// 把设备上应用的日志文件拉取到本地目录
+    ///
+    /// # Arguments
+    /// * `package` - 应用包名
+    /// * `remote_dir` - 设备上的日志目录：相对路径相对于应用数据目录，通过 `run-as` 读取；
+    ///   绝对路径直接使用 `adb pull`
+    /// * `dest` - 本地目标目录，不存在时自动创建
+    ///
+    /// # Errors
+    /// 包名不合法、应用未安装或不可调试、目录不存在、权限不足或写入本地文件失败时返回错误
+    pub fn pull_logs(&self, package: &str, remote_dir: &str, dest: &Path) -> Result<()> {
+        if package.is_empty()
+            || !package
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+        {
+            anyhow::bail!("包名不合法: {}", package);
+        }
+        fs::create_dir_all(dest).context(format!("创建目录失败: {}", dest.display()))?;
+
+        if remote_dir.starts_with('/') {
+            let dest_arg = dest.to_string_lossy();
+            let output = self.exec(&["pull", remote_dir, &dest_arg])?;
+            return check_output(&output, package, remote_dir);
+        }
+
+        let remote_dir = remote_dir.trim_end_matches('/');
+        let ls = format!("run-as {} ls {}", package, shell_quote(remote_dir));
+        let output = self.exec(&["shell", &ls])?;
+        check_output(&output, package, remote_dir)?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let names = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|name| name.ends_with(".glog") || name.ends_with(".glogmmap"));
+        for name in names {
+            let remote_path = format!("{}/{}", remote_dir, name);
+            let cat = format!("run-as {} cat {}", package, shell_quote(&remote_path));
+            let output = self.exec(&["exec-out", &cat])?;
+            check_text(output.success, &output.file_error_text(), package, &remote_path)?;
+            let path = dest.join(name);
+            fs::write(&path, &output.stdout)
+                .context(format!("写入文件失败: {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// 执行一条 adb 命令，已选定设备时附加 `-s <序列号>`
+    fn exec(&self, args: &[&str]) -> Result<CommandOutput> {
+        let mut full_args = Vec::with_capacity(args.len() + 2);
+        if let Some(serial) = &self.serial {
+            full_args.push("-s");
+            full_args.push(serial.as_str());
+        }
+        full_args.extend_from_slice(args);
+
+        self.runner
+            .run(&self.program, &full_args)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => anyhow::anyhow!(
+                    "找不到 adb 可执行文件: {}，请安装 Android SDK platform-tools 并加入 PATH，或用 --adb 指定路径",
+                    self.program.display()
+                ),
+                _ => anyhow::Error::new(e)
+                    .context(format!("执行 adb 失败: {}", self.program.display())),
+            })
+    }
+}
+
+/// 解析 `adb devices` 的输出
+///
+/// # Returns
+/// 返回 `(序列号, 状态)` 列表，状态如 `device`、`unauthorized`、`offline`
+fn parse_devices(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with("List of devices") && !line.starts_with('*'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?.to_string(), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// 检查命令输出中的错误
+///
+/// 较旧的 adb 不传递设备端命令的退出码，因此即使执行成功也要检查输出中的已知错误信息
+fn check_output(output: &CommandOutput, package: &str, remote_path: &str) -> Result<()> {
+    check_text(output.success, &output.text(), package, remote_path)
+}
+
+/// 检查命令是否成功以及输出文本中的已知错误信息，参见 [`check_output`]
+fn check_text(success: bool, text: &str, package: &str, remote_path: &str) -> Result<()> {
+    if text.contains("not debuggable") {
+        anyhow::bail!(
+            "应用 {} 不是可调试（debuggable）版本，run-as 无法读取其私有目录；\
+             请安装调试版本，或把日志写到外部存储后用 --remote-dir 指定绝对路径",
+            package
+        );
+    }
+    if text.contains("unknown package") || text.contains("is unknown") {
+        anyhow::bail!("设备上未安装应用 {}", package);
+    }
+    if text.contains("Permission denied") {
+        anyhow::bail!(
+            "读取 {} 时权限被拒绝；应用私有目录请使用相对路径（通过 run-as 读取），\
+             外部存储目录请确认 adb 有权限访问",
+            remote_path
+        );
+    }
+    if text.contains("No such file or directory") {
+        anyhow::bail!(
+            "设备上不存在 {}，请用 --remote-dir 指定日志目录",
+            remote_path
+        );
+    }
+    if !success {
+        anyhow::bail!("adb 命令执行失败: {}", text.trim());
+    }
+    Ok(())
+}
+
+/// 按 POSIX shell 规则给参数加单引号
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    type Respond = fn(&[&str]) -> io::Result<CommandOutput>;
+
+    /// 按参数返回预设输出，并记录每次调用的参数
+    struct FakeRunner {
+        calls: RefCell<Vec<Vec<String>>>,
+        respond: Respond,
+    }
+
+    impl CommandRunner for &FakeRunner {
+        fn run(&self, _program: &Path, args: &[&str]) -> io::Result<CommandOutput> {
+            self.calls
+                .borrow_mut()
+                .push(args.iter().map(|arg| arg.to_string()).collect());
+            (self.respond)(args)
+        }
+    }
+
+    fn fake(respond: Respond) -> FakeRunner {
+        FakeRunner {
+            calls: RefCell::new(Vec::new()),
+            respond,
+        }
+    }
+
+    fn ok(stdout: &str) -> io::Result<CommandOutput> {
+        Ok(CommandOutput {
+            success: true,
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn failed(stderr: &str) -> io::Result<CommandOutput> {
+        Ok(CommandOutput {
+            success: false,
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        })
+    }
+
+    fn adb<R: CommandRunner>(serial: Option<&str>, runner: R) -> Adb<R> {
+        Adb::with_runner(PathBuf::from("adb"), serial.map(str::to_string), runner)
+    }
+
+    const TWO_DEVICES: &str =
+        "List of devices attached\nemulator-5554\tdevice\nR58M123\tunauthorized\n\n";
+
+    #[test]
+    fn test_select_device() {
+        let runner = |_: &Path, _: &[&str]| ok("List of devices attached\nemulator-5554\tdevice\n");
+        assert_eq!(adb(None, runner).select_device().unwrap(), "emulator-5554");
+
+        let runner = |_: &Path, _: &[&str]| ok(TWO_DEVICES);
+        let err = adb(None, runner).select_device().unwrap_err().to_string();
+        assert!(
+            err.contains("emulator-5554, R58M123") && err.contains("--serial"),
+            "{}",
+            err
+        );
+        assert_eq!(
+            adb(Some("emulator-5554"), runner).select_device().unwrap(),
+            "emulator-5554"
+        );
+        let err = adb(Some("R58M123"), runner).select_device().unwrap_err();
+        assert!(err.to_string().contains("未授权"), "{}", err);
+        let err = adb(Some("other"), runner).select_device().unwrap_err();
+        assert!(err.to_string().contains("未找到序列号为 other"), "{}", err);
+
+        let runner = |_: &Path, _: &[&str]| ok("List of devices attached\n\n");
+        let err = adb(None, runner).select_device().unwrap_err();
+        assert!(err.to_string().contains("未检测到"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_adb_binary() {
+        let runner = |_: &Path, _: &[&str]| Err(io::Error::from(io::ErrorKind::NotFound));
+        let err = adb(None, runner).select_device().unwrap_err().to_string();
+        assert!(
+            err.contains("找不到 adb") && err.contains("--adb"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_pull_logs_with_run_as() {
+        let runner = fake(|args| match args {
+            [.., "shell", _] => ok("async-20240101.glog\nasync.glogmmap\nnotes.txt\n"),
+            [.., "exec-out", cmd] if cmd.ends_with("async.glogmmap'") => ok("mmap"),
+            [.., "exec-out", _] => ok("glog"),
+            _ => failed("unexpected"),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        adb(Some("emulator-5554"), &runner)
+            .pull_logs("com.example.app", "files/glog/", dir.path())
+            .unwrap();
+
+        assert_eq!(
+            fs::read(dir.path().join("async-20240101.glog")).unwrap(),
+            b"glog"
+        );
+        assert_eq!(
+            fs::read(dir.path().join("async.glogmmap")).unwrap(),
+            b"mmap"
+        );
+        assert!(!dir.path().join("notes.txt").exists());
+        let calls = runner.calls.borrow();
+        assert_eq!(
+            calls[0],
+            [
+                "-s",
+                "emulator-5554",
+                "shell",
+                "run-as com.example.app ls 'files/glog'"
+            ]
+        );
+        assert_eq!(
+            calls[1],
+            [
+                "-s",
+                "emulator-5554",
+                "exec-out",
+                "run-as com.example.app cat 'files/glog/async-20240101.glog'"
+            ]
+        );
+        assert_eq!(calls.len(), 3);
+    }
+
+    #[test]
+    fn test_pull_logs_absolute_dir_uses_pull() {
+        let runner = fake(|_| ok("1 file pulled"));
+        let dir = tempfile::tempdir().unwrap();
+        adb(None, &runner)
+            .pull_logs("com.example.app", "/sdcard/glog", dir.path())
+            .unwrap();
+        let calls = runner.calls.borrow();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0][..2], ["pull", "/sdcard/glog"]);
+    }
+
+    #[test]
+    fn test_pull_logs_reports_actionable_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases: [(Respond, &str); 4] = [
+            (
+                |_| failed("run-as: package not debuggable: com.example.app\n"),
+                "--remote-dir",
+            ),
+            (
+                |_| failed("run-as: unknown package: com.example.app\n"),
+                "未安装",
+            ),
+            // 旧版 adb 不传递退出码
+            (
+                |_| ok("ls: files/glog: No such file or directory\n"),
+                "不存在 files/glog",
+            ),
+            (
+                |_| failed("adb: error: failed to stat remote object: Permission denied\n"),
+                "权限被拒绝",
+            ),
+        ];
+        for (respond, expected) in cases {
+            let runner = fake(respond);
+            let err = adb(None, &runner)
+                .pull_logs("com.example.app", "files/glog", dir.path())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{}", err);
+        }
+
+        let err = adb(None, &fake(|_| ok("")))
+            .pull_logs("com.example.app; rm -rf /", "files/glog", dir.path())
+            .unwrap_err();
+        assert!(err.to_string().contains("包名不合法"), "{}", err);
+    }
+
+    #[test]
+    fn test_pull_logs_ignores_error_text_inside_file_content() {
+        let runner = fake(|args| match args {
+            [.., "shell", _] => ok("async-20240101.glog\n"),
+            [.., "exec-out", _] => {
+                let mut stdout = MAGIC_NUMBER.to_vec();
+                stdout.extend_from_slice(b"open config: No such file or directory");
+                Ok(CommandOutput {
+                    success: true,
+                    stdout,
+                    stderr: Vec::new(),
+                })
+            }
+            _ => failed("unexpected"),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        adb(None, &runner)
+            .pull_logs("com.example.app", "files/glog", dir.path())
+            .unwrap();
+        assert!(fs::read(dir.path().join("async-20240101.glog"))
+            .unwrap()
+            .ends_with(b"No such file or directory"));
+
+        // 旧版 adb 把 cat 的错误信息写到标准输出
+        let runner = fake(|args| match args {
+            [.., "shell", _] => ok("async-20240101.glog\n"),
+            _ => ok("cat: files/glog/async-20240101.glog: Permission denied\n"),
+        });
+        let err = adb(None, &runner)
+            .pull_logs("com.example.app", "files/glog", dir.path())
+            .unwrap_err();
+        assert!(err.to_string().contains("权限被拒绝"), "{}", err);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("files/glog"), "'files/glog'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
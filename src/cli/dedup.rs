@@ -0,0 +1,214 @@
+//! # 连续重复日志折叠
+//!
+//! 崩溃循环会产生成千上万条完全相同的日志。`Deduper` 包装输出写入器，
+//! 当标签、级别和内容都相同的日志连续出现多次时，只写入第一条，
+//! 随后写入一行 `... repeated N times` 标记，并附带首条和末条的时间戳。
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use clog_reader::proto::Log;
+
+use super::format::LineFormatter;
+use super::sink::Source;
+
+/// 连续重复日志折叠器
+pub struct Deduper<W: Write> {
+    /// 底层写入器
+    inner: W,
+    /// 是否启用折叠
+    enabled: bool,
+    /// 日志行格式化器
+    formatter: LineFormatter,
+    /// 作为 [`LogSink`](super::sink::LogSink) 使用时标注的来源文件名（`--show-source`）
+    source_file: Option<Arc<str>>,
+    /// 当前正在累计的重复日志
+    pending: Option<Pending>,
+}
+
+/// 正在累计的一组连续重复日志
+struct Pending {
+    /// 比较用的键：(标签, 级别, 内容)
+    key: (String, i32, String),
+    /// 首条日志的时间戳
+    first_timestamp: String,
+    /// 末条日志的时间戳
+    last_timestamp: String,
+    /// 出现次数（包含首条）
+    count: usize,
+}
+
+impl<W: Write> Deduper<W> {
+    /// 创建新的折叠器
+    ///
+    /// `enabled` 为 false 时原样写入每条日志，便于调用方统一走同一条输出路径
+    pub fn new(inner: W, enabled: bool, formatter: LineFormatter) -> Self {
+        Self {
+            inner,
+            enabled,
+            formatter,
+            source_file: None,
+            pending: None,
+        }
+    }
+
+    /// 设置作为 [`LogSink`](super::sink::LogSink) 使用时标注的来源文件名，
+    /// 之后每行日志都以 `文件:偏移` 开头
+    pub fn with_source_file(mut self, file: Option<&str>) -> Self {
+        self.source_file = file.map(Arc::from);
+        self
+    }
+
+    /// 作为 [`LogSink`](super::sink::LogSink) 使用时标注的来源文件名
+    pub fn source_file(&self) -> Option<&Arc<str>> {
+        self.source_file.as_ref()
+    }
+
+    /// 写入一条日志
+    ///
+    /// 与上一条日志重复时只累计次数，否则先结束上一组重复再写入本条
+    pub fn write_log(&mut self, log: &Log) -> io::Result<()> {
+        self.write_log_with_source(log, None)
+    }
+
+    /// 写入一条日志，`source` 不为 `None` 时在行首标注来源文件和偏移
+    ///
+    /// 重复的日志只标注第一条的来源
+    pub fn write_log_with_source(
+        &mut self,
+        log: &Log,
+        source: Option<Source<'_>>,
+    ) -> io::Result<()> {
+        if !self.enabled {
+            let line = self.formatter.format_with_source(log, source);
+            return writeln!(self.inner, "{}", line);
+        }
+
+        if let Some(pending) = self.pending.as_mut() {
+            let (tag, level, msg) = &pending.key;
+            if *tag == log.tag && *level == log.log_level && *msg == log.msg {
+                pending.count += 1;
+                pending.last_timestamp = log.formatted_timestamp();
+                return Ok(());
+            }
+        }
+
+        self.flush_pending()?;
+        let line = self.formatter.format_with_source(log, source);
+        writeln!(self.inner, "{}", line)?;
+        let timestamp = log.formatted_timestamp();
+        self.pending = Some(Pending {
+            key: (log.tag.clone(), log.log_level, log.msg.clone()),
+            first_timestamp: timestamp.clone(),
+            last_timestamp: timestamp,
+            count: 1,
+        });
+        Ok(())
+    }
+
+    /// 写入一行标记
+    ///
+    /// 先结束当前的一组重复，标记前后的相同日志不会折叠在一起
+    pub fn write_marker(&mut self, text: &str) -> io::Result<()> {
+        self.flush_pending()?;
+        writeln!(self.inner, "{}", text)
+    }
+
+    /// 结束折叠，写入最后一组重复标记并返回底层写入器
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_pending()?;
+        Ok(self.inner)
+    }
+
+    /// 若上一组日志出现多次，写入重复标记
+    fn flush_pending(&mut self) -> io::Result<()> {
+        if let Some(pending) = self.pending.take() {
+            if pending.count > 1 {
+                writeln!(
+                    self.inner,
+                    "... repeated {} times (first: {}, last: {})",
+                    pending.count, pending.first_timestamp, pending.last_timestamp
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: &str, tag: &str, msg: &str) -> Log {
+        Log {
+            timestamp: timestamp.to_string(),
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+            ..Log::default()
+        }
+    }
+
+    fn dedup(logs: &[Log]) -> Vec<String> {
+        let mut deduper = Deduper::new(Vec::new(), true, LineFormatter::default());
+        for l in logs {
+            deduper.write_log(l).unwrap();
+        }
+        let out = deduper.finish().unwrap();
+        String::from_utf8(out).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_collapses_consecutive_duplicates() {
+        let lines = dedup(&[
+            log("t1", "Crash", "boom"),
+            log("t2", "Crash", "boom"),
+            log("t3", "Crash", "boom"),
+            log("t4", "Main", "ok"),
+        ]);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("boom"));
+        assert_eq!(lines[1], "... repeated 3 times (first: t1, last: t3)");
+        assert!(lines[2].contains("ok"));
+    }
+
+    #[test]
+    fn test_keeps_non_consecutive_duplicates() {
+        let lines = dedup(&[
+            log("t1", "A", "x"),
+            log("t2", "B", "y"),
+            log("t3", "A", "x"),
+        ]);
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| !l.starts_with("...")));
+    }
+
+    #[test]
+    fn test_level_is_part_of_the_key() {
+        let mut error = log("t2", "A", "x");
+        error.log_level = 4;
+        let lines = dedup(&[log("t1", "A", "x"), error]);
+
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn test_disabled_writes_every_line() {
+        let mut deduper = Deduper::new(Vec::new(), false, LineFormatter::default());
+        for _ in 0..3 {
+            deduper.write_log(&log("t1", "A", "x")).unwrap();
+        }
+        let out = String::from_utf8(deduper.finish().unwrap()).unwrap();
+
+        assert_eq!(out.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_trailing_run_is_flushed_on_finish() {
+        let lines = dedup(&[log("t1", "A", "x"), log("t2", "A", "x")]);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], "... repeated 2 times (first: t1, last: t2)");
+    }
+}
@@ -0,0 +1,153 @@
+//! # 解码失败条目转储
+//!
+//! `Log::decode_from` 失败的条目默认会被丢弃，格式变化时难以排查。
+//! 指定 `--dump-failed <dir>` 后，每个失败条目的数据会写入单独的文件，
+//! 文件名包含来源文件名、条目序号和条目在源文件中的字节偏移，
+//! 同时打印条目的存储信息和前 64 字节的十六进制转储。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clog_reader::glog::EntryInfo;
+
+/// 十六进制转储中显示的最大字节数
+pub const HEXDUMP_MAX_BYTES: usize = 64;
+
+/// 每行显示的字节数
+const BYTES_PER_LINE: usize = 16;
+
+/// 将解码失败的条目写入转储目录
+///
+/// # Arguments
+/// * `dir` - 转储目录
+/// * `source` - 来源日志文件
+/// * `index` - 条目在来源文件中的序号（从 0 开始）
+/// * `offset` - 条目在来源文件中的字节偏移
+/// * `payload` - 条目数据
+///
+/// # Returns
+/// 返回转储文件路径
+pub fn dump_entry(
+    dir: &Path,
+    source: &Path,
+    index: usize,
+    offset: u64,
+    payload: &[u8],
+) -> io::Result<PathBuf> {
+    let source_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path = dir.join(format!("{}.{}.{}.bin", source_name, index, offset));
+    fs::write(&path, payload)?;
+    Ok(path)
+}
+
+/// 描述条目的存储信息：存储长度、压缩/加密方式、客户端公钥和内容长度
+pub fn describe_entry(entry: &EntryInfo) -> String {
+    let mut parts = vec![format!("存储 {} 字节", entry.stored_len)];
+    if entry.compressed {
+        parts.push("压缩".to_string());
+    }
+    if entry.encrypted {
+        match &entry.client_pubkey {
+            Some(pubkey) => parts.push(format!("加密，客户端公钥 {}", hex::encode(pubkey))),
+            None => parts.push("加密".to_string()),
+        }
+    }
+    parts.push(format!("内容 {} 字节", entry.len));
+    parts.join("，")
+}
+
+/// 生成十六进制转储，最多显示前 [`HEXDUMP_MAX_BYTES`] 字节
+///
+/// 每行格式为 `偏移  十六进制字节  |可打印字符|`
+pub fn hexdump(bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(HEXDUMP_MAX_BYTES)];
+    let mut lines: Vec<String> = shown
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!(
+                "{:08x}  {:<width$}  |{}|",
+                i * BYTES_PER_LINE,
+                hex.join(" "),
+                ascii,
+                width = BYTES_PER_LINE * 3 - 1
+            )
+        })
+        .collect();
+    if bytes.len() > shown.len() {
+        lines.push(format!("... 共 {} 字节", bytes.len()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clog_reader::reader::CompressMode;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hexdump_truncates_to_64_bytes() {
+        let bytes: Vec<u8> = (0..100).collect();
+        let dump = hexdump(&bytes);
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("00000000  00 01 02"));
+        assert!(lines[3].starts_with("00000030  30 31 32"));
+        assert!(lines[3].ends_with("|0123456789:;<=>?|"));
+        assert_eq!(lines[4], "... 共 100 字节");
+    }
+
+    #[test]
+    fn test_describe_entry() {
+        let entry = EntryInfo {
+            offset: 100,
+            entry_len: 80,
+            stored_len: 20,
+            compressed: true,
+            compress_mode: CompressMode::Zlib,
+            encrypted: true,
+            client_pubkey: Some([0x02; 33]),
+            len: 35,
+        };
+        assert_eq!(
+            describe_entry(&entry),
+            format!("存储 20 字节，压缩，加密，客户端公钥 {}，内容 35 字节", "02".repeat(33))
+        );
+
+        let plain = EntryInfo {
+            compressed: false,
+            compress_mode: CompressMode::None,
+            encrypted: false,
+            client_pubkey: None,
+            ..entry
+        };
+        assert_eq!(describe_entry(&plain), "存储 20 字节，内容 35 字节");
+    }
+
+    #[test]
+    fn test_dump_entry_names_file_by_source_index_and_offset() {
+        let dir = TempDir::new().unwrap();
+        let path = dump_entry(
+            dir.path(),
+            Path::new("/tmp/x/async-20240101.glog"),
+            3,
+            1234,
+            b"\xff\xff",
+        )
+        .unwrap();
+
+        assert_eq!(path.file_name().unwrap(), "async-20240101.glog.3.1234.bin");
+        assert_eq!(fs::read(path).unwrap(), b"\xff\xff");
+    }
+}
@@ -0,0 +1,188 @@
+//! # 机器可读的错误报告
+//!
+//! `--errors-json <路径>` 把处理过程中遇到的每个错误写成一个 JSON 数组，供批量解码的 CI 任务按错误码分拣。
+//! 每个元素包含来源压缩包、日志文件名、阶段、字节偏移、稳定错误码（[`GlogError::code`]）、错误信息，
+//! NeedRecover 还带有原因码（[`RecoverReason::code`](clog_reader::error::RecoverReason::code)）。
+//! 格式由仓库中的 `schema/errors.schema.json` 描述，`extract` 和 `check` 子命令共用。
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clog_reader::error::GlogError;
+use serde::Serialize;
+
+use super::report::{self, FileReport};
+
+/// protobuf 解码失败（[`GlogError::ProtobufError`]）的错误码
+///
+/// 观察者只收到失败条目的位置，拿不到错误本身
+pub const DECODE_FAILURE_CODE: u32 = 501;
+
+/// 错误发生的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorStage {
+    /// 从 ZIP 中解压条目失败
+    Extract,
+    /// 打开日志文件或解析文件头失败
+    Header,
+    /// 条目损坏，跳到下一个同步标记恢复（严格模式下读取就此停止）
+    Recover,
+    /// 条目的 protobuf 解码失败
+    Decode,
+    /// 读取中途的其他错误（IO、解压、解密等），之后的部分无法读取
+    Read,
+}
+
+/// 单个日志文件中的一个错误
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileError {
+    /// 错误发生的阶段
+    pub stage: ErrorStage,
+    /// 错误所在的字节偏移，无法确定时为 `None`
+    pub offset: Option<u64>,
+    /// 稳定错误码，错误不是读取器返回的时为 `None`
+    pub code: Option<u32>,
+    /// 错误信息
+    pub message: String,
+    /// NeedRecover 的原因码
+    pub recover_reason: Option<i32>,
+}
+
+impl FileError {
+    /// 由读取器返回的错误创建，NeedRecover 记为恢复阶段并带上原因码
+    ///
+    /// # Arguments
+    /// * `stage` - 错误不是 NeedRecover 时使用的阶段
+    /// * `error` - 读取器返回的错误
+    pub fn from_glog(stage: ErrorStage, error: &GlogError) -> Self {
+        let recover_reason = match error.kind() {
+            GlogError::NeedRecover(reason) => Some(reason.code()),
+            _ => None,
+        };
+        Self {
+            stage: if recover_reason.is_some() {
+                ErrorStage::Recover
+            } else {
+                stage
+            },
+            offset: error.offset(),
+            code: Some(error.code()),
+            message: error.to_string(),
+            recover_reason,
+        }
+    }
+
+    /// 打开日志文件失败，错误链中有 [`GlogError`] 时取其错误码和偏移
+    pub fn header(error: &anyhow::Error) -> Self {
+        let glog = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<GlogError>());
+        Self {
+            stage: ErrorStage::Header,
+            offset: glog.and_then(GlogError::offset),
+            code: report::error_code(error),
+            message: format!("{:#}", error),
+            recover_reason: None,
+        }
+    }
+}
+
+/// `--errors-json` 数组中的一个元素
+#[derive(Debug, Serialize)]
+struct ErrorRecord<'a> {
+    /// 来源压缩包（`-i` 的值）
+    archive: &'a str,
+    /// 日志文件名（不含目录）或 ZIP 条目名
+    file: String,
+    #[serde(flatten)]
+    error: &'a FileError,
+}
+
+/// 把各文件报告中的错误写入 JSON 数组
+///
+/// 同一文件中的错误按偏移排序，没有偏移的排在最后
+///
+/// # Arguments
+/// * `path` - JSON 文件路径
+/// * `archive` - 来源压缩包
+/// * `reports` - 各文件的报告
+pub fn write_json(path: &Path, archive: &str, reports: &[FileReport]) -> Result<()> {
+    let mut records = Vec::new();
+    for file_report in reports {
+        let start = records.len();
+        records.extend(file_report.errors.iter().map(|error| ErrorRecord {
+            archive,
+            file: report::display_name(&file_report.file),
+            error,
+        }));
+        records[start..].sort_by_key(|record| record.error.offset.unwrap_or(u64::MAX));
+    }
+    let file = File::create(path).context(format!("创建错误报告失败: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &records)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clog_reader::error::RecoverReason;
+    use clog_reader::proto::Log;
+    use prost::Message;
+
+    #[test]
+    fn test_decode_failure_code_matches_protobuf_error() {
+        let error = GlogError::from(Log::decode(&[0x0a, 0xff][..]).unwrap_err());
+        assert_eq!(error.code(), DECODE_FAILURE_CODE);
+    }
+
+    #[test]
+    fn test_need_recover_is_recover_stage() {
+        let error = GlogError::NeedRecover(RecoverReason::SyncMarkerMismatch)
+            .at(42)
+            .in_file("a.glog");
+        let file_error = FileError::from_glog(ErrorStage::Read, &error);
+        assert_eq!(file_error.stage, ErrorStage::Recover);
+        assert_eq!(file_error.offset, Some(42));
+        assert_eq!(file_error.code, Some(209));
+        assert_eq!(file_error.recover_reason, Some(-7));
+
+        let error = GlogError::MagicMismatch.at(0);
+        let file_error = FileError::from_glog(ErrorStage::Header, &error);
+        assert_eq!(file_error.stage, ErrorStage::Header);
+        assert_eq!(file_error.recover_reason, None);
+    }
+
+    #[test]
+    fn test_records_sorted_by_offset_per_file() {
+        let error = |offset| FileError {
+            stage: ErrorStage::Decode,
+            offset,
+            code: Some(DECODE_FAILURE_CODE),
+            message: "x".to_string(),
+            recover_reason: None,
+        };
+        let mut report = FileReport::new(Path::new("/tmp/a/async-20240101.glog"));
+        report.errors = vec![error(None), error(Some(30)), error(Some(10))];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("errors.json");
+        write_json(&path, "logs.zip", &[report]).unwrap();
+
+        let records: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let offsets: Vec<Option<u64>> = records
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["offset"].as_u64())
+            .collect();
+        assert_eq!(offsets, [Some(10), Some(30), None]);
+        assert_eq!(records[0]["archive"], "logs.zip");
+        assert_eq!(records[0]["file"], "async-20240101.glog");
+        assert_eq!(records[0]["stage"], "decode");
+    }
+}
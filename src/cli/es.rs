@@ -0,0 +1,159 @@
+//! # Elasticsearch bulk 输出
+//!
+//! `--format es-bulk --index <索引>` 按 Elasticsearch `_bulk` 接口的 NDJSON 格式输出：
+//! 每条日志占两行，先是 `{"index":{...}}` 动作行，再是日志本身的 JSON（与 [`Log::to_json`] 相同），
+//! 可以直接交给 `curl -XPOST .../_bulk --data-binary @logs.ndjson`。
+//!
+//! 文档 ID 由 (压缩包名, 文件名, 条目偏移) 计算，同一份日志重复导入时覆盖已有文档而不会重复。
+//! `--show-source` 时日志 JSON 中增加 `file` 和 `offset` 字段。
+
+use std::io::{self, Write};
+
+use clog_reader::proto::Log;
+
+use super::sink::LogSink;
+
+/// Elasticsearch bulk NDJSON 写入器
+pub struct EsBulkWriter<W: Write> {
+    /// 底层写入器
+    inner: W,
+    /// 动作行中文档 ID 之前的部分，如 `{"index":{"_index":"my-logs","_id":"`
+    action_prefix: String,
+    /// 来源压缩包名（仅文件名）
+    archive: String,
+    /// 来源日志文件名（仅文件名）
+    file: String,
+    /// 是否在日志 JSON 中增加 `file` 和 `offset` 字段（`--show-source`）
+    show_source: bool,
+}
+
+impl<W: Write> EsBulkWriter<W> {
+    /// 创建新的写入器
+    ///
+    /// # Arguments
+    /// * `inner` - 底层写入器
+    /// * `index` - 目标索引名
+    /// * `archive` - 来源压缩包名，参与文档 ID 的计算
+    /// * `file` - 来源日志文件名，参与文档 ID 的计算
+    pub fn new(inner: W, index: &str, archive: &str, file: &str) -> Self {
+        let index = serde_json::to_string(index).expect("字符串都可以序列化为 JSON");
+        Self {
+            inner,
+            action_prefix: format!("{{\"index\":{{\"_index\":{},\"_id\":\"", index),
+            archive: archive.to_string(),
+            file: file.to_string(),
+            show_source: false,
+        }
+    }
+
+    /// 设置是否在日志 JSON 中增加 `file` 和 `offset` 字段
+    pub fn with_source(mut self, show_source: bool) -> Self {
+        self.show_source = show_source;
+        self
+    }
+
+    /// 返回底层写入器
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> LogSink for EsBulkWriter<W> {
+    fn write_log(&mut self, offset: u64, log: Log) -> io::Result<()> {
+        let id = document_id(&self.archive, &self.file, offset);
+        self.inner.write_all(self.action_prefix.as_bytes())?;
+        writeln!(self.inner, "{}\"}}}}", id)?;
+        if !self.show_source {
+            return writeln!(self.inner, "{}", log.to_json());
+        }
+        let mut source = log.to_json_value();
+        source["file"] = self.file.as_str().into();
+        source["offset"] = offset.into();
+        writeln!(self.inner, "{}", source)
+    }
+}
+
+/// 计算 (压缩包名, 文件名, 条目偏移) 的文档 ID
+///
+/// 使用固定的 128 位 FNV-1a 哈希，不依赖标准库哈希的实现，保证不同版本、
+/// 不同平台上重复导入得到相同的 ID；名称之间以 0 字节分隔，避免拼接歧义
+///
+/// # Returns
+/// 返回 32 位十六进制字符串
+pub fn document_id(archive: &str, file: &str, offset: u64) -> String {
+    const FNV_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+    let bytes = archive
+        .bytes()
+        .chain([0])
+        .chain(file.bytes())
+        .chain([0])
+        .chain(offset.to_le_bytes());
+    let hash = bytes.fold(FNV_OFFSET, |h, b| {
+        (h ^ u128::from(b)).wrapping_mul(FNV_PRIME)
+    });
+    format!("{:032x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_are_paired_and_escaped() {
+        let log = Log {
+            tag: "Net\"work".to_string(),
+            msg: "line1\nline2\t\\end".to_string(),
+            ..Log::default()
+        };
+        let mut writer = EsBulkWriter::new(Vec::new(), "my-\"logs\"", "logs.zip", "a.glog");
+        writer.write_log(0, log.clone()).unwrap();
+        writer.write_log(64, Log::default()).unwrap();
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        let action: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(action["index"]["_index"], "my-\"logs\"");
+        assert_eq!(
+            action["index"]["_id"],
+            document_id("logs.zip", "a.glog", 0).as_str()
+        );
+        let source: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(source, log.to_json_value());
+
+        let action: serde_json::Value = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(
+            action["index"]["_id"],
+            document_id("logs.zip", "a.glog", 64).as_str()
+        );
+    }
+
+    #[test]
+    fn test_show_source_adds_fields() {
+        let mut writer =
+            EsBulkWriter::new(Vec::new(), "logs", "logs.zip", "a.glog").with_source(true);
+        writer.write_log(128, Log::default()).unwrap();
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+
+        let source: serde_json::Value = serde_json::from_str(out.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(source["file"], "a.glog");
+        assert_eq!(source["offset"], 128);
+        assert_eq!(source["msg"], "");
+    }
+
+    #[test]
+    fn test_document_id_is_stable() {
+        let id = document_id("logs.zip", "async-20240101.glog", 128);
+        assert_eq!(id.len(), 32);
+        assert_eq!(id, document_id("logs.zip", "async-20240101.glog", 128));
+        assert_ne!(id, document_id("logs.zip", "async-20240101.glog", 129));
+        assert_ne!(
+            document_id("ab", "c", 0),
+            document_id("a", "bc", 0),
+            "名称之间需要分隔"
+        );
+        // 固定值：修改哈希会让已导入的文档在重新导入时重复
+        assert_eq!(id, "d3b8400ae4d35722489a613f527a5f47");
+    }
+}
@@ -0,0 +1,296 @@
+//! # 文本输出格式
+//!
+//! 默认使用 [`Log::format`] 输出完整的日志行；通过 `--fields` 可以只输出
+//! 指定的字段，字段顺序即输出的列顺序。内容中的换行按 `--multiline` 原样输出、
+//! 在每一行重复前缀或转义为 `\n`。`--format logfmt` 使用 [`LogfmtFormatter`]，
+//! 换行总是被转义。
+
+use std::fmt;
+use std::sync::Arc;
+
+use clog_reader::format::{
+    write_logfmt_value, DefaultFormatter, LogFormatter, LogfmtFormatter, Multiline, MultilineMode,
+};
+use clog_reader::proto::{Log, LogTypeRegistry};
+
+use super::sink::Source;
+
+/// 可输出的日志字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// 时间戳
+    Ts,
+    /// 日志级别
+    Level,
+    /// 日志类型
+    Type,
+    /// 进程 ID
+    Pid,
+    /// 线程 ID
+    Tid,
+    /// 日志标签
+    Tag,
+    /// 日志内容
+    Msg,
+}
+
+impl Field {
+    /// 所有字段及其在 `--fields` 中的名称
+    const ALL: [(&'static str, Field); 7] = [
+        ("ts", Field::Ts),
+        ("level", Field::Level),
+        ("type", Field::Type),
+        ("pid", Field::Pid),
+        ("tid", Field::Tid),
+        ("tag", Field::Tag),
+        ("msg", Field::Msg),
+    ];
+
+    /// 按名称查找字段
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, field)| *field)
+    }
+
+    /// 渲染单个字段，设置了类型名称表时类型输出为名称
+    fn render(&self, log: &Log, types: Option<&LogTypeRegistry>) -> String {
+        match self {
+            Field::Ts => log.formatted_timestamp(),
+            Field::Level => format!("[{}]", log.level().as_str()),
+            Field::Type => match types.and_then(|types| types.name(log.log_type)) {
+                Some(name) => format!("type={}", name),
+                None => format!("type={}", log.log_type),
+            },
+            Field::Pid => format!("pid={}", log.pid),
+            Field::Tid => format!("tid={}", log.tid),
+            Field::Tag => format!("[{}]", log.tag),
+            Field::Msg => log.msg.clone(),
+        }
+    }
+}
+
+/// 日志行格式化器
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineFormatter {
+    /// 要输出的字段（`None` 表示使用完整的默认格式）
+    fields: Option<Vec<Field>>,
+    /// 日志类型名称表（`--type-map`）
+    types: Option<Arc<LogTypeRegistry>>,
+    /// 内容中换行的处理方式（`--multiline`）
+    multiline: MultilineMode,
+    /// 是否输出 logfmt（`--format logfmt`）
+    logfmt: bool,
+}
+
+impl LineFormatter {
+    /// 解析 `--fields` 参数
+    ///
+    /// # Arguments
+    /// * `value` - 逗号分隔的字段名，如 `ts,level,msg`
+    ///
+    /// # Returns
+    /// 返回对应的格式化器，字段名无效时返回包含所有可选字段的错误信息
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let fields = value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|name| {
+                Field::from_name(name).ok_or_else(|| {
+                    format!("无效的字段: {}（可选: {}）", name, valid_names())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if fields.is_empty() {
+            return Err(format!("至少需要指定一个字段（可选: {}）", valid_names()));
+        }
+        Ok(Self {
+            fields: Some(fields),
+            ..Self::default()
+        })
+    }
+
+    /// 设置日志类型名称表，默认格式和 `type` 字段输出类型名称
+    pub fn with_types(mut self, types: Option<Arc<LogTypeRegistry>>) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// 设置内容中换行的处理方式（`--multiline`）
+    pub fn with_multiline(mut self, mode: MultilineMode) -> Self {
+        self.multiline = mode;
+        self
+    }
+
+    /// 设置是否输出 logfmt，此时忽略字段选择和换行处理方式
+    pub fn with_logfmt(mut self, logfmt: bool) -> Self {
+        self.logfmt = logfmt;
+        self
+    }
+
+    /// 将日志格式化为文本，内容含换行时按 `--multiline` 处理
+    pub fn format(&self, log: &Log) -> String {
+        self.format_with_source(log, None)
+    }
+
+    /// 将日志格式化为文本，`source` 不为 `None` 时在行首标注来源（`--show-source`）
+    ///
+    /// 文本行以 `文件:偏移` 开头，`--multiline prefix` 时每一行都带有来源；
+    /// logfmt 行以 `file=... offset=...` 开头
+    pub fn format_with_source(&self, log: &Log, source: Option<Source<'_>>) -> String {
+        if self.logfmt {
+            let line = match &self.types {
+                Some(types) => log.format_with(&LogfmtFormatter::with_types(Arc::clone(types))),
+                None => log.format_with(&LogfmtFormatter::new()),
+            };
+            return match source {
+                Some(source) => {
+                    let mut out = String::from("file=");
+                    let _ = write_logfmt_value(&mut out, source.file);
+                    format!("{} offset={} {}", out, source.offset, line)
+                }
+                None => line,
+            };
+        }
+        log.format_with(&Multiline::new(Columns(self, source), self.multiline))
+    }
+}
+
+/// 按字段和类型名称表格式化单行日志，可选地以来源开头
+struct Columns<'a>(&'a LineFormatter, Option<Source<'a>>);
+
+impl LogFormatter for Columns<'_> {
+    fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result {
+        if let Some(source) = self.1 {
+            write!(out, "{} ", source)?;
+        }
+        let LineFormatter { fields, types, .. } = self.0;
+        let Some(fields) = fields else {
+            return match types {
+                Some(types) => DefaultFormatter::with_types(Arc::clone(types)),
+                None => DefaultFormatter::new(),
+            }
+            .format(log, out);
+        };
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                out.write_char(' ')?;
+            }
+            out.write_str(&field.render(log, types.as_deref()))?;
+        }
+        Ok(())
+    }
+}
+
+/// 逗号分隔的所有可选字段名
+fn valid_names() -> String {
+    Field::ALL
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> Log {
+        Log {
+            log_type: 2,
+            timestamp: "not-a-number".to_string(),
+            log_level: 4,
+            pid: 1234,
+            tid: "5678".to_string(),
+            tag: "Main".to_string(),
+            msg: "hello".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_field_order_follows_flag() {
+        let formatter = LineFormatter::parse("msg,level,ts").unwrap();
+        assert_eq!(formatter.format(&log()), "hello [Error] not-a-number");
+
+        let formatter = LineFormatter::parse("type,pid,tid,tag").unwrap();
+        assert_eq!(formatter.format(&log()), "type=2 pid=1234 tid=5678 [Main]");
+    }
+
+    #[test]
+    fn test_default_uses_full_format() {
+        assert_eq!(LineFormatter::default().format(&log()), log().format());
+    }
+
+    #[test]
+    fn test_logfmt_ignores_multiline() {
+        let log = Log {
+            msg: "a\nb".to_string(),
+            ..log()
+        };
+        let formatter = LineFormatter::default()
+            .with_multiline(MultilineMode::Prefix)
+            .with_logfmt(true);
+        assert_eq!(
+            formatter.format(&log),
+            r#"ts=not-a-number level=error type=2 pid=1234 tid=5678 tag=Main msg="a\nb""#
+        );
+    }
+
+    #[test]
+    fn test_source_prefixes_every_line() {
+        let source = Source {
+            file: "async 1.glog",
+            offset: 24,
+        };
+        let formatter = LineFormatter::parse("tag,msg")
+            .unwrap()
+            .with_multiline(MultilineMode::Prefix);
+        let multi = Log {
+            msg: "a\nb".to_string(),
+            ..log()
+        };
+        assert_eq!(
+            formatter.format_with_source(&multi, Some(source)),
+            "async 1.glog:24 [Main] a\nasync 1.glog:24 [Main] b"
+        );
+        assert_eq!(
+            formatter.format_with_source(&multi, None),
+            "[Main] a\n[Main] b"
+        );
+
+        let logfmt = LineFormatter::default().with_logfmt(true);
+        let line = logfmt.format_with_source(&log(), Some(source));
+        assert!(
+            line.starts_with("file=\"async 1.glog\" offset=24 ts="),
+            "{}",
+            line
+        );
+
+        // 文件名与日志字段按相同的规则转义
+        let source = Source {
+            file: "a\tb\u{200b}.glog",
+            offset: 0,
+        };
+        let tagged = Log {
+            tag: "a\tb\u{200b}".to_string(),
+            ..log()
+        };
+        let line = logfmt.format_with_source(&tagged, Some(source));
+        assert!(
+            line.starts_with("file=\"a\\tb\u{200b}.glog\" offset=0 "),
+            "{}",
+            line
+        );
+        assert!(line.contains(" tag=\"a\\tb\u{200b}\" "), "{}", line);
+    }
+
+    #[test]
+    fn test_invalid_field_lists_valid_set() {
+        let err = LineFormatter::parse("ts,time").unwrap_err();
+        assert!(err.contains("time"));
+        assert!(err.contains("ts,level,type,pid,tid,tag,msg"));
+        assert!(LineFormatter::parse(",").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! # HTML 报告
+//!
+//! 把 [`Stats`] 和逐文件的 [`FileReport`] 渲染为单个自包含的 HTML 文件（`--report-html`），
+//! 样式内联，不引用任何外部资源，可以直接作为附件发送。只用字符串拼接生成，
+//! 所有来自日志的文本都经过转义。
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clog_reader::proto::{format_local_millis, LogTypeRegistry};
+
+use super::report::{self, FileReport};
+use super::stats::{self, Stats, HOUR_MILLIS};
+
+/// 错误时间线补齐空白小时的最大跨度，超过时只显示有错误的小时
+const MAX_FILLED_HOURS: i64 = 14 * 24;
+
+/// 内联样式
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+td.n{text-align:right;font-variant-numeric:tabular-nums}\
+td.msg{white-space:pre-wrap;word-break:break-all}\
+.bar{background:#d9534f;height:12px}\
+.bad{color:#d9534f}";
+
+/// HTML 报告的内容
+pub struct HtmlReport<'a> {
+    /// 报告标题，通常为输入压缩包的文件名
+    pub title: &'a str,
+    /// 全部文件合并后的统计
+    pub stats: &'a Stats,
+    /// 逐文件读取报告
+    pub reports: &'a [FileReport],
+    /// 日志类型名称表
+    pub types: Option<&'a LogTypeRegistry>,
+    /// 标签和消息各显示的条数
+    pub top_n: usize,
+}
+
+impl HtmlReport<'_> {
+    /// 渲染为完整的 HTML 文档
+    pub fn render(&self) -> String {
+        let mut html = String::new();
+        let title = escape(self.title);
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>日志报告 - {title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+             <h1>日志报告 - {title}</h1>\n<p>{} 个日志文件，共统计 {} 条日志</p>\n",
+            self.reports.len(),
+            self.stats.total
+        );
+
+        self.render_counts(&mut html);
+        self.render_timeline(&mut html);
+        self.render_files(&mut html);
+
+        html.push_str("<h2>最常见的消息</h2>\n<table>\n<tr><th>条数</th><th>消息</th></tr>\n");
+        for (msg, n) in self.stats.top_messages(self.top_n) {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"n\">{}</td><td class=\"msg\">{}</td></tr>",
+                n,
+                escape(msg)
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// 渲染并写入文件
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.render()).context(format!("写入 HTML 报告失败: {}", path.display()))
+    }
+
+    /// 按级别、类型、标签的条数表格
+    fn render_counts(&self, html: &mut String) {
+        let levels = self
+            .stats
+            .levels_by_severity()
+            .into_iter()
+            .map(|(level, n)| (level.as_str().to_string(), n));
+        count_table(html, "级别", "级别", levels);
+
+        let types = self
+            .stats
+            .types
+            .iter()
+            .map(|(log_type, n)| (stats::type_name(self.types, *log_type), *n));
+        count_table(html, "类型", "类型", types);
+
+        let tags = self
+            .stats
+            .top_tags(self.top_n)
+            .into_iter()
+            .map(|(tag, n)| (tag.to_string(), n));
+        count_table(html, "最常见的标签", "标签", tags);
+    }
+
+    /// 按小时分桶的 Error 级别直方图
+    fn render_timeline(&self, html: &mut String) {
+        html.push_str("<h2>错误时间线（每小时 Error 条数）</h2>\n");
+        let hours = &self.stats.error_hours;
+        let (Some(first), Some(last)) = (hours.keys().next(), hours.keys().next_back()) else {
+            html.push_str("<p>没有 Error 级别的日志</p>\n");
+            return;
+        };
+        let buckets: Vec<(i64, u64)> = if (last - first) / HOUR_MILLIS < MAX_FILLED_HOURS {
+            (*first..=*last)
+                .step_by(HOUR_MILLIS as usize)
+                .map(|hour| (hour, hours.get(&hour).copied().unwrap_or(0)))
+                .collect()
+        } else {
+            hours.iter().map(|(hour, n)| (*hour, *n)).collect()
+        };
+        let max = buckets.iter().map(|(_, n)| *n).max().unwrap_or(1).max(1);
+
+        html.push_str("<table>\n<tr><th>时间</th><th>条数</th><th></th></tr>\n");
+        for (hour, n) in buckets {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"n\">{}</td>\
+                 <td style=\"width:300px\"><div class=\"bar\" style=\"width:{}%\"></div></td></tr>",
+                hour_label(hour),
+                n,
+                n * 100 / max
+            );
+        }
+        html.push_str("</table>\n");
+        if self.stats.errors_without_time > 0 {
+            let _ = writeln!(
+                html,
+                "<p>另有 {} 条 Error 日志的时间戳无法解析</p>",
+                self.stats.errors_without_time
+            );
+        }
+    }
+
+    /// 逐文件的读取、损坏和恢复情况
+    fn render_files(&self, html: &mut String) {
+        html.push_str(
+            "<h2>读取报告</h2>\n<table>\n<tr><th>文件</th><th>成功</th><th>输出</th>\
+             <th>解码失败</th><th>恢复事件</th><th>跳过字节</th><th>时间戳跳变</th><th>状态</th></tr>\n",
+        );
+        for file in self.reports {
+            let status = match &file.error {
+                Some(error) => format!("<span class=\"bad\">{}</span>", escape(error)),
+                None if file.is_clean() => "OK".to_string(),
+                None => format!(
+                    "<span class=\"bad\">{}</span>",
+                    escape(&report::status(file))
+                ),
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td>\
+                 <td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td>{}</td></tr>",
+                escape(&report::display_name(&file.file)),
+                file.decoded,
+                file.count,
+                file.decode_failures,
+                file.total_recover_events(),
+                file.bytes_skipped,
+                file.time_jumps.len(),
+                status
+            );
+        }
+        html.push_str("</table>\n");
+    }
+}
+
+/// 渲染 `名称 | 条数` 两列的表格
+fn count_table(
+    html: &mut String,
+    heading: &str,
+    column: &str,
+    rows: impl Iterator<Item = (String, u64)>,
+) {
+    let _ = write!(
+        html,
+        "<h2>{heading}</h2>\n<table>\n<tr><th>{column}</th><th>条数</th></tr>\n"
+    );
+    for (name, n) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"n\">{}</td></tr>",
+            escape(&name),
+            n
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+/// 整点时间的本地时间显示，如 `2024-01-01 08:00`
+fn hour_label(hour: i64) -> String {
+    format_local_millis(hour).map_or_else(|| hour.to_string(), |time| time[..16].to_string())
+}
+
+/// 转义 HTML 特殊字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<script>alert("x&y's")</script>"#),
+            "&lt;script&gt;alert(&quot;x&amp;y&#39;s&quot;)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let stats = Stats::default();
+        let html = HtmlReport {
+            title: "<empty>.zip",
+            stats: &stats,
+            reports: &[],
+            types: None,
+            top_n: 10,
+        }
+        .render();
+        assert!(html.contains("<title>日志报告 - &lt;empty&gt;.zip</title>"));
+        assert!(html.contains("没有 Error 级别的日志"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,172 @@
+//! # HTTP(S) 输入
+//!
+//! `-i` 给出 `http://` 或 `https://` 地址时，把响应体下载到临时目录，之后与本地文件走同一流程
+//! （ZIP 需要随机访问，无法边下载边读取）。仅在启用 `http` 功能时编译。
+//!
+//! 不自动跟随重定向：`--http-header` 中的认证信息不应被带到另一台主机，
+//! 收到重定向时报告目标地址，由用户确认后重新指定。
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+/// 两次打印下载进度的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 下载 `url` 的响应体并保存到 `dest`
+///
+/// # Arguments
+/// * `url` - HTTP(S) 地址
+/// * `headers` - 附加的请求头，格式为 `名称: 值`
+/// * `dest` - 保存路径
+///
+/// # Errors
+/// 请求头格式错误、连接失败、响应状态不是 200（包括重定向）或写入失败时返回错误
+pub fn download(url: &str, headers: &[String], dest: &Path) -> Result<()> {
+    let agent = ureq::AgentBuilder::new()
+        .redirects(0)
+        .timeout_connect(CONNECT_TIMEOUT)
+        .build();
+    let mut request = agent.get(url);
+    for header in headers {
+        let (name, value) = parse_header(header)?;
+        request = request.set(name, value);
+    }
+
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, response)) => {
+            anyhow::bail!(
+                "下载失败: {} 返回 HTTP {} {}",
+                url,
+                status,
+                response.status_text()
+            )
+        }
+        Err(e) => return Err(e).context(format!("下载失败: {}", url)),
+    };
+    if (300..400).contains(&response.status()) {
+        anyhow::bail!(
+            "下载失败: {} 返回重定向 HTTP {} 到 {}，不会自动跟随，请直接使用目标地址",
+            url,
+            response.status(),
+            response.header("Location").unwrap_or("（未给出地址）")
+        );
+    }
+    if response.status() != 200 {
+        anyhow::bail!(
+            "下载失败: {} 返回 HTTP {} {}",
+            url,
+            response.status(),
+            response.status_text()
+        );
+    }
+
+    let total = response
+        .header("Content-Length")
+        .and_then(|len| len.parse::<u64>().ok());
+    let mut file =
+        fs::File::create(dest).context(format!("创建临时文件失败: {}", dest.display()))?;
+    let mut progress = Progress::new(total);
+    let mut body = response.into_reader();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = match body.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e).context(format!("下载中断: {}", url)),
+        };
+        file.write_all(&buf[..n])?;
+        progress.advance(n as u64);
+    }
+    file.flush()?;
+    if let Some(total) = total.filter(|total| progress.received != *total) {
+        anyhow::bail!(
+            "下载不完整: {} 应为 {} 字节，实际收到 {} 字节",
+            url,
+            total,
+            progress.received
+        );
+    }
+    progress.finish();
+    Ok(())
+}
+
+/// 解析 `名称: 值` 格式的请求头
+fn parse_header(header: &str) -> Result<(&str, &str)> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim(), value.trim())),
+        _ => anyhow::bail!("请求头格式应为 `名称: 值`: {}", header),
+    }
+}
+
+/// 下载进度，每秒最多打印一行
+struct Progress {
+    /// 响应头给出的总字节数
+    total: Option<u64>,
+    /// 已收到的字节数
+    received: u64,
+    /// 上次打印的时间
+    last_print: Instant,
+}
+
+impl Progress {
+    fn new(total: Option<u64>) -> Self {
+        Self {
+            total,
+            received: 0,
+            last_print: Instant::now(),
+        }
+    }
+
+    fn advance(&mut self, n: u64) {
+        self.received += n;
+        if self.last_print.elapsed() >= PROGRESS_INTERVAL {
+            self.last_print = Instant::now();
+            self.print("正在下载");
+        }
+    }
+
+    fn finish(&self) {
+        self.print("下载完成");
+    }
+
+    fn print(&self, label: &str) {
+        let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+        let line = match self.total {
+            Some(total) if total > 0 => format!(
+                "{}: {:.1} / {:.1} MB ({}%)",
+                label,
+                mb(self.received),
+                mb(total),
+                self.received * 100 / total
+            ),
+            _ => format!("{}: {:.1} MB", label, mb(self.received)),
+        };
+        let mut handle = io::stdout().lock();
+        let _ = writeln!(handle, "{}", line);
+        let _ = handle.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization: Bearer abc:def").unwrap(),
+            ("Authorization", "Bearer abc:def")
+        );
+        assert!(parse_header("no colon").is_err());
+        assert!(parse_header(": value").is_err());
+    }
+}
@@ -0,0 +1,654 @@
+//! # 命令行参数定义
+//!
+//! 定义 clog-reader 的顶层参数和子命令。
+//! 未指定子命令时，顶层参数按 `extract` 子命令处理，以兼容旧的 `clog-reader -i <日志.zip>` 用法。
+
+pub mod adb;
+pub mod dedup;
+pub mod dump;
+pub mod errors;
+pub mod es;
+pub mod format;
+pub mod html;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod progress;
+pub mod raw;
+pub mod report;
+pub mod rotate;
+pub mod sample;
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod trace;
+#[cfg(feature = "tui")]
+pub mod view;
+pub mod watch;
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use clog_reader::archive::{FileNamePattern, DEFAULT_FILE_PATTERN};
+use clog_reader::format::MultilineMode;
+use clog_reader::reader::key::parse_svr_pri_key;
+use clog_reader::reader::CompressMode;
+
+/// 服务器私钥（用于解密加密的日志）
+const SVR_PRIV_KEY: &str = "1C74B66FCB1C54FD4386173CFAF3BC53C8DF6B89F799DE1A1E7CEBBC43CBFD38";
+
+/// `extract` 默认的输出文件路径
+pub const DEFAULT_OUTPUT: &str = "log_output.txt";
+
+/// CLog Reader 命令行参数
+#[derive(Parser, Debug)]
+#[command(name = "clog-reader")]
+#[command(author = "CLog Reader Team")]
+#[command(version = env!("CARGO_PKG_VERSION"))]
+#[command(about = "读取和解析 Glog 格式日志文件的工具", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct Cli {
+    /// 子命令（省略时执行 extract）
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// 顶层提取参数（兼容旧用法）
+    #[command(flatten)]
+    pub extract: ExtractArgs,
+}
+
+impl Cli {
+    /// 获取实际要执行的子命令
+    ///
+    /// 未指定子命令时，使用顶层参数构造 `extract` 子命令
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Extract(self.extract))
+    }
+}
+
+/// 子命令
+// 子命令只在启动时解析一次，无需为缩小体积而装箱
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 解压 ZIP 并提取日志到文本文件（默认子命令）
+    Extract(ExtractArgs),
+
+    /// 校验 ZIP 中每个日志文件的完整性，不输出日志
+    Check(CheckArgs),
+
+    /// 显示 ZIP 中每个日志文件的元数据（版本、协议、压缩和加密模式、大小）
+    Info(InfoArgs),
+
+    /// 文件头损坏时按同步标记抢救单个日志文件中的日志
+    Salvage(SalvageArgs),
+
+    /// 把单个日志文件（如加密的 V4 文件）解密后重写为 V3 文件，读取时不再需要私钥
+    Convert(ConvertArgs),
+
+    /// 用旧私钥解密 V4 文件，再针对新的服务器公钥重新加密，压缩模式和日志顺序不变
+    Reencrypt(ReencryptArgs),
+
+    /// 只解密 V4 文件：去掉每条日志的加密，压缩数据原样保留，读取时不再需要私钥
+    Decrypt(DecryptArgs),
+
+    /// 把 ZIP 中的全部日志按时间戳排序，合并写入单个 V3 文件
+    Repack(RepackArgs),
+
+    /// 按日志时间戳把单个日志文件拆分为每天一个的 V3 文件
+    Split(SplitArgs),
+
+    /// 为 ZIP 中的每个日志文件建立索引，供 `extract --use-index` 跳过不可能匹配的日志
+    Index(IndexArgs),
+
+    /// 通过 adb 从已连接的设备拉取应用的日志目录并提取日志
+    Adb(AdbArgs),
+
+    /// 在终端中交互式浏览日志（需要启用 tui 功能编译）
+    View(ViewArgs),
+
+    /// 监视目录，把新出现的 ZIP 和 glog 文件停止增长后逐个提取到输出目录
+    Watch(WatchArgs),
+
+    /// 生成 Shell 自动补全脚本并输出到 stdout
+    Completions {
+        /// 目标 Shell
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// 输出格式
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 文本日志行
+    #[default]
+    Text,
+    /// SQLite 数据库（需要启用 sqlite 功能编译）
+    Sqlite,
+    /// 原始 protobuf 条目（varint 长度前缀 + 数据），不做过滤
+    Raw,
+    /// Elasticsearch `_bulk` 接口的 NDJSON（动作行 + 日志 JSON），需要 --index
+    EsBulk,
+    /// logfmt 行（`ts=... level=... type=... pid=... tid=... tag=... msg=...`）
+    Logfmt,
+    /// Chrome Trace Event Format 的 JSON 数组，可在 chrome://tracing 或 Perfetto UI 中打开
+    TraceJson,
+}
+
+/// 内容中换行的处理方式，参见 [`MultilineMode`]
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultilineArg {
+    /// 原样输出换行，续行没有前缀
+    #[default]
+    Raw,
+    /// 每一行都输出完整的时间、级别等前缀
+    Prefix,
+    /// 换行转义为 `\n`，每条日志只占一行
+    Escape,
+}
+
+impl From<MultilineArg> for MultilineMode {
+    fn from(arg: MultilineArg) -> Self {
+        match arg {
+            MultilineArg::Raw => MultilineMode::Raw,
+            MultilineArg::Prefix => MultilineMode::Prefix,
+            MultilineArg::Escape => MultilineMode::Escape,
+        }
+    }
+}
+
+/// 服务器私钥参数，所有需要解密的子命令共用
+#[derive(Args, Debug, Clone, Default)]
+pub struct KeyArgs {
+    /// 用于解密 V4 加密日志的服务器私钥（十六进制、Base64 或 PEM，默认使用内置私钥）
+    #[arg(long = "key", value_name = "KEY")]
+    pub key: Option<String>,
+
+    /// 服务器私钥文件（十六进制、Base64、32 字节原始私钥、SEC1 / PKCS#8 PEM 或 DER），
+    /// 默认使用内置私钥
+    #[arg(long = "key-file", value_name = "PATH", conflicts_with = "key")]
+    pub key_file: Option<PathBuf>,
+}
+
+impl KeyArgs {
+    /// 确定使用的服务器私钥
+    ///
+    /// 私钥文件解析后统一转换为十六进制；命令行直接给出的私钥由读取器解析
+    ///
+    /// # Returns
+    /// 返回私钥字符串，两者均未给出时返回内置私钥
+    pub fn load(&self) -> Result<String> {
+        let Some(path) = &self.key_file else {
+            return Ok(self.key.as_deref().unwrap_or(SVR_PRIV_KEY).to_string());
+        };
+        let data = fs::read(path).context(format!("读取私钥文件失败: {}", path.display()))?;
+        let secret =
+            parse_svr_pri_key(&data).context(format!("无法解析私钥文件: {}", path.display()))?;
+        Ok(hex::encode(secret.to_bytes()))
+    }
+}
+
+/// 校验日志的参数
+#[derive(Args, Debug, Clone)]
+pub struct CheckArgs {
+    /// 日志 ZIP 文件路径（`-` 表示从标准输入读取）
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+
+    /// 将校验失败以 JSON 数组写入指定路径，参见 `extract --errors-json`
+    #[arg(long = "errors-json", value_name = "PATH")]
+    pub errors_json: Option<PathBuf>,
+}
+
+/// 交互式浏览日志的参数
+#[derive(Args, Debug, Clone)]
+pub struct ViewArgs {
+    /// 日志 ZIP 文件或目录路径
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 显示日志文件元数据的参数
+#[derive(Args, Debug, Clone)]
+pub struct InfoArgs {
+    /// 日志 ZIP 文件路径（`-` 表示从标准输入读取）
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+}
+
+/// 抢救日志的参数
+#[derive(Args, Debug, Clone)]
+pub struct SalvageArgs {
+    /// 损坏的日志文件路径（单个 .glog 文件，不是 ZIP）
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// 输出文件路径（默认输出到标准输出）
+    #[arg(short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 转换后的 V3 文件的压缩模式
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressArg {
+    /// 不压缩
+    #[default]
+    None,
+    /// zlib（raw deflate）压缩
+    Zlib,
+    /// zstd 压缩（需要启用 zstd 功能编译）
+    Zstd,
+}
+
+impl From<CompressArg> for CompressMode {
+    fn from(arg: CompressArg) -> Self {
+        match arg {
+            CompressArg::None => CompressMode::None,
+            CompressArg::Zlib => CompressMode::Zlib,
+            CompressArg::Zstd => CompressMode::Zstd,
+        }
+    }
+}
+
+/// 转换日志文件的参数
+#[derive(Args, Debug, Clone)]
+pub struct ConvertArgs {
+    /// 要转换的日志文件路径（单个 .glog 文件，不是 ZIP）
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// 转换后的 V3 文件路径
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 输出文件的压缩模式
+    #[arg(long = "compress", value_enum, default_value_t = CompressArg::None)]
+    pub compress: CompressArg,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 重新加密日志文件的参数
+#[derive(Args, Debug, Clone)]
+pub struct ReencryptArgs {
+    /// 要重新加密的日志文件路径（单个 .glog 文件，不是 ZIP）
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// 重新加密后的 V4 文件路径
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 解密原文件的旧服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+
+    /// 新的服务器公钥（十六进制，SEC1 压缩或未压缩格式）
+    #[arg(long = "new-public-key", value_name = "HEX")]
+    pub new_public_key: String,
+}
+
+/// 解密日志文件的参数
+#[derive(Args, Debug, Clone)]
+pub struct DecryptArgs {
+    /// 要解密的 V4 日志文件路径（单个 .glog 文件，不是 ZIP）
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// 解密后的 V4 文件路径
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 合并日志文件的参数
+#[derive(Args, Debug, Clone)]
+pub struct RepackArgs {
+    /// 日志 ZIP 文件路径（`-` 表示从标准输入读取）
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// 合并后的 V3 文件路径
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 输出文件的压缩模式，压缩时使用一个新的压缩流
+    #[arg(long = "compress", value_enum, default_value_t = CompressArg::None)]
+    pub compress: CompressArg,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 拆分日志文件的粒度
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SplitBy {
+    /// 按本地日期，每天一个文件
+    #[default]
+    Day,
+}
+
+/// 拆分日志文件的参数
+#[derive(Args, Debug, Clone)]
+pub struct SplitArgs {
+    /// 要拆分的日志文件路径（单个 .glog 文件，不是 ZIP）
+    #[arg(short = 'i', long = "input")]
+    pub input: PathBuf,
+
+    /// 输出目录，不存在时自动创建
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 拆分粒度
+    #[arg(long = "by", value_enum, default_value_t = SplitBy::Day)]
+    pub by: SplitBy,
+
+    /// 输出文件名的前缀，生成 `<前缀>-YYYYMMDD.glog`
+    #[arg(long = "prefix", default_value = "async")]
+    pub prefix: String,
+
+    /// 输出文件的压缩模式
+    #[arg(long = "compress", value_enum, default_value_t = CompressArg::None)]
+    pub compress: CompressArg,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 建立索引的参数
+#[derive(Args, Debug, Clone)]
+pub struct IndexArgs {
+    /// 日志 ZIP 文件路径（`-` 表示从标准输入读取）
+    #[arg(short = 'i', long = "input")]
+    pub input: String,
+
+    /// 索引文件路径
+    #[arg(short = 'o', long = "output")]
+    pub output: PathBuf,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+}
+
+/// 从设备拉取日志的参数
+#[derive(Args, Debug, Clone)]
+pub struct AdbArgs {
+    /// 应用包名，如 com.example.app
+    #[arg(long = "package")]
+    pub package: String,
+
+    /// 目标设备的序列号（连接了多台设备时必须指定，参见 `adb devices`）
+    #[arg(long = "serial")]
+    pub serial: Option<String>,
+
+    /// adb 可执行文件路径
+    #[arg(long = "adb", value_name = "PATH", default_value = "adb")]
+    pub adb: PathBuf,
+
+    /// 设备上的日志目录：相对路径相对于应用数据目录，通过 run-as 读取（需要可调试版本）；
+    /// 绝对路径（如外部存储）直接使用 adb pull
+    #[arg(long = "remote-dir", value_name = "DIR", default_value = "files/glog")]
+    pub remote_dir: String,
+
+    /// 提取参数，`-i` 和 `--follow` 除外
+    #[command(flatten)]
+    pub extract: ExtractArgs,
+}
+
+/// 监视目录的参数
+#[derive(Args, Debug, Clone)]
+pub struct WatchArgs {
+    /// 监视的目录（不含子目录）
+    pub dir: PathBuf,
+
+    /// 文件大小保持不变多少毫秒后视为写入完成
+    #[arg(long = "settle-ms", value_name = "MS", default_value_t = 2000)]
+    pub settle_ms: u64,
+
+    /// 记录已处理文件的状态文件（默认为输出目录下的 .clog-reader-watch.json）
+    #[arg(long = "state", value_name = "PATH")]
+    pub state: Option<PathBuf>,
+
+    /// 提取参数，`-o` 为输出目录（必须指定），`-i` 和 `--follow` 除外
+    #[command(flatten)]
+    pub extract: ExtractArgs,
+}
+
+/// 提取日志的参数
+///
+/// 既可以通过 `extract` 子命令使用，也可以直接在顶层使用（兼容旧的 `-i` 用法）
+#[derive(Args, Debug, Clone)]
+pub struct ExtractArgs {
+    /// 日志 ZIP 文件、单个 glog 文件或目录路径（`-` 表示从标准输入读取 ZIP 或单个 glog 文件；
+    /// 启用 http 功能时也可以是 `http://` 或 `https://` 地址）
+    #[arg(short = 'i', long = "input")]
+    pub input: Option<String>,
+
+    /// 下载 `-i` 给出的地址时附加的请求头（`名称: 值`，可重复），如
+    /// `Authorization: Bearer <令牌>` 或 `Authorization: Basic <Base64>`
+    #[arg(long = "http-header", value_name = "HEADER")]
+    pub http_headers: Vec<String>,
+
+    /// 从 ZIP 中解压的总大小上限（如 512M、4G），超过时中止，防范 ZIP 炸弹
+    #[arg(long = "max-extract-size", value_name = "SIZE", value_parser = rotate::parse_size, default_value = "4G")]
+    pub max_extract_size: u64,
+
+    /// 过滤日志类型（逗号分隔，如 0,1,2）
+    #[arg(short = 't', long = "type", default_value = "")]
+    pub log_types: String,
+
+    /// 排除日志类型（逗号分隔），优先于 -t 生效
+    #[arg(long = "exclude-type", default_value = "", value_name = "TYPES")]
+    pub exclude_types: String,
+
+    /// 排除日志标签（逗号分隔，精确匹配），优先于其他过滤条件生效
+    #[arg(long = "exclude-tag", default_value = "", value_name = "TAGS")]
+    pub exclude_tags: String,
+
+    /// 只处理文件名日期不早于该日期的 glog 文件（YYYYMMDD 或 YYYY-MM-DD，.glogmmap 始终处理）
+    #[arg(long = "file-date-from", value_name = "DATE", value_parser = parse_file_date)]
+    pub file_date_from: Option<NaiveDate>,
+
+    /// 只处理文件名日期不晚于该日期的 glog 文件（YYYYMMDD 或 YYYY-MM-DD，.glogmmap 始终处理）
+    #[arg(long = "file-date-to", value_name = "DATE", value_parser = parse_file_date)]
+    pub file_date_to: Option<NaiveDate>,
+
+    /// 识别 glog 文件的文件名正则（与文件名部分匹配），用于自定义前缀的写入端；
+    /// 日期取自文件名中第一段 8 位数字
+    #[arg(long = "file-pattern", value_name = "REGEX", value_parser = parse_file_pattern, default_value = DEFAULT_FILE_PATTERN)]
+    pub file_pattern: FileNamePattern,
+
+    /// 输出文件路径（默认为当前目录下的 log_output.txt）
+    #[arg(short = 'o', long = "output", default_value = DEFAULT_OUTPUT)]
+    pub output: String,
+
+    /// 输出格式
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// `--format es-bulk` 写入的 Elasticsearch 索引名
+    #[arg(long = "index", value_name = "NAME")]
+    pub es_index: Option<String>,
+
+    /// 只输出指定字段（逗号分隔，可选 ts,level,type,pid,tid,tag,msg），顺序即列顺序
+    #[arg(long = "fields", value_name = "FIELDS", value_parser = format::LineFormatter::parse)]
+    pub fields: Option<format::LineFormatter>,
+
+    /// 日志内容（如堆栈）中换行的处理方式：raw 原样输出，prefix 在每一行重复前缀，escape 转义为 \n
+    #[arg(long = "multiline", value_enum, default_value_t = MultilineArg::Raw)]
+    pub multiline: MultilineArg,
+
+    /// 标注每条日志的来源文件名和条目起始偏移：文本行以 `文件:偏移` 开头，
+    /// logfmt 行以 `file=... offset=...` 开头，es-bulk 和 trace-json 增加 file、offset 字段
+    #[arg(long = "show-source")]
+    pub show_source: bool,
+
+    /// 单个输出文件的大小上限（如 10M、1G），超过后写入 log_output.0001.txt 等分段文件
+    #[arg(long = "max-output-size", value_name = "SIZE", value_parser = rotate::parse_size)]
+    pub max_output_size: Option<u64>,
+
+    /// 覆盖已存在的输出文件
+    #[arg(long = "force", conflicts_with = "append")]
+    pub force: bool,
+
+    /// 以追加模式写入输出文件，并写入包含来源和时间的分隔头
+    #[arg(long = "append")]
+    pub append: bool,
+
+    /// 严格模式：遇到任何解码异常时立即停止处理当前文件
+    #[arg(long = "strict")]
+    pub strict: bool,
+
+    /// 只统计匹配过滤条件的日志条数（逐文件及总计），不生成输出文件
+    #[arg(long = "count")]
+    pub count: bool,
+
+    /// 折叠连续重复的日志（标签、级别、内容均相同），只保留首条并标注重复次数
+    #[arg(long = "dedup-consecutive")]
+    pub dedup_consecutive: bool,
+
+    /// 按比例抽样输出通过过滤的日志（0.0 ~ 1.0），按文件名和条目偏移的哈希选择，重复运行结果相同
+    #[arg(long = "sample", value_name = "RATE", value_parser = sample::parse_rate, group = "sampling")]
+    pub sample: Option<f64>,
+
+    /// 平均每 N 条通过过滤的日志保留一条，与 --sample 使用相同的确定性选择
+    #[arg(long = "every", value_name = "N", value_parser = clap::value_parser!(u64).range(1..), group = "sampling")]
+    pub every: Option<u64>,
+
+    /// 抽样时始终保留 Warn / Error 级别的日志
+    #[arg(long = "sample-keep-errors", requires = "sampling")]
+    pub sample_keep_errors: bool,
+
+    /// 抽样使用的哈希种子，换一个种子得到另一份样本
+    #[arg(
+        long = "sample-seed",
+        value_name = "SEED",
+        default_value_t = 0,
+        requires = "sampling"
+    )]
+    pub sample_seed: u64,
+
+    /// 检测设备时钟跳变：时间戳后退或前跳超过阈值时打印警告并写入报告
+    #[arg(long = "detect-time-jumps")]
+    pub detect_time_jumps: bool,
+
+    /// 时间戳后退超过该秒数时视为跳变（少量乱序来自多线程写入）
+    #[arg(
+        long = "time-jump-back",
+        value_name = "SECONDS",
+        default_value_t = 1,
+        requires = "detect_time_jumps"
+    )]
+    pub time_jump_back: u64,
+
+    /// 时间戳前进超过该秒数时视为跳变
+    #[arg(
+        long = "time-jump-gap",
+        value_name = "SECONDS",
+        default_value_t = 3600,
+        requires = "detect_time_jumps"
+    )]
+    pub time_jump_gap: u64,
+
+    /// 在文本输出中跳变的位置插入一行标记
+    #[arg(long = "mark-time-jumps", requires = "detect_time_jumps")]
+    pub mark_time_jumps: bool,
+
+    /// 将 protobuf 解码失败的条目写入该目录（文件名包含来源文件、条目序号和偏移），并打印十六进制转储
+    #[arg(long = "dump-failed", value_name = "DIR")]
+    pub dump_failed: Option<PathBuf>,
+
+    /// 按 FileDescriptorSet（protoc --descriptor_set_out）解码文件头中记录的消息类型，
+    /// 字段编号 1~7 映射到日志的类型、时间、级别、进程、线程、标签、内容（需要启用 dynamic 功能）
+    #[arg(long = "descriptor-set", value_name = "PATH")]
+    pub descriptor_set: Option<PathBuf>,
+
+    /// 输出前按正则替换标签和内容中的敏感信息，格式为 <正则>=<替换文本>（可重复指定，按顺序应用；
+    /// 正则中的 = 写作 \=，替换文本可用 $1 引用捕获组）
+    #[arg(long = "redact", value_name = "REGEX=REPLACEMENT")]
+    pub redact: Vec<String>,
+
+    /// 日志类型名称映射文件（TOML，每行 `类型 = "名称"`），设置后在输出中显示类型名称
+    #[arg(long = "type-map", value_name = "PATH")]
+    pub type_map: Option<PathBuf>,
+
+    /// 服务器私钥
+    #[command(flatten)]
+    pub key: KeyArgs,
+
+    /// 使用 `index` 子命令生成的索引文件，跳过不可能满足过滤条件的日志，不再解码；
+    /// 文件名或大小与索引不一致的日志文件按常规方式读取
+    #[arg(long = "use-index", value_name = "PATH")]
+    pub use_index: Option<PathBuf>,
+
+    /// 将逐文件读取报告以 JSON 格式写入指定路径
+    #[arg(long = "report", value_name = "PATH")]
+    pub report: Option<String>,
+
+    /// 将每个错误（解压缩失败、文件头错误、恢复事件、解码失败）的压缩包、文件、偏移、
+    /// 错误码、错误信息和恢复原因以 JSON 数组写入指定路径，格式见 `schema/errors.schema.json`
+    #[arg(long = "errors-json", value_name = "PATH")]
+    pub errors_json: Option<PathBuf>,
+
+    /// 统计通过过滤的日志按级别、类型、标签的条数和最常见的消息，打印为表格
+    #[arg(long = "stats")]
+    pub stats: bool,
+
+    /// 将统计表格、每小时错误数、逐文件读取报告和最常见的消息写入单个 HTML 文件
+    #[arg(long = "report-html", value_name = "PATH")]
+    pub report_html: Option<PathBuf>,
+
+    /// 统计表格和 HTML 报告中标签和消息各显示的条数
+    #[arg(long = "top", value_name = "N", default_value_t = 20)]
+    pub top: usize,
+
+    /// 按时间戳合并所有日志文件的输出（要求各文件内部按时间有序），而不是按文件顺序拼接；
+    /// 合并是流式的，不会把全部日志读入内存（仅适用于文本输出）
+    #[arg(long = "sort")]
+    pub sort: bool,
+
+    /// 并行解码的线程数（默认为 CPU 核心数）
+    #[arg(long = "threads", value_name = "N", value_parser = clap::value_parser!(usize))]
+    pub threads: Option<usize>,
+
+    /// 跟随模式：持续读取正在写入的 .glog/.glogmmap 文件并输出到 stdout
+    #[arg(long = "follow", value_name = "PATH", conflicts_with = "input")]
+    pub follow: Option<String>,
+}
+
+/// 解析 `--file-pattern` 参数
+fn parse_file_pattern(value: &str) -> Result<FileNamePattern, String> {
+    FileNamePattern::new(value).map_err(|e| format!("无效的文件名正则: {}", e))
+}
+
+/// 解析日期参数，支持 `YYYYMMDD` 和 `YYYY-MM-DD` 两种格式
+fn parse_file_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+        .map_err(|_| format!("无效的日期: {}（格式为 YYYYMMDD 或 YYYY-MM-DD）", value))
+}
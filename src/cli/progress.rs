@@ -0,0 +1,34 @@
+//! # 处理进度
+//!
+//! 作为读取器的观察者，在每个日志文件开始和结束读取时把进度打印到 stdout。
+
+use std::io::{self, Write};
+
+use clog_reader::observer::{Observer, ReadStats};
+
+/// 打印处理进度的观察者
+pub struct ProgressObserver;
+
+impl ProgressObserver {
+    /// 打印一行并立即刷新，确保管道模式下输出能被及时捕获
+    fn print(&self, line: std::fmt::Arguments) {
+        let mut handle = io::stdout().lock();
+        let _ = writeln!(handle, "{}", line);
+        let _ = handle.flush();
+    }
+}
+
+impl Observer for ProgressObserver {
+    fn on_file_start(&self, path: Option<&str>) {
+        self.print(format_args!("正在处理: {}", path.unwrap_or("-")));
+    }
+
+    fn on_file_end(&self, path: Option<&str>, stats: &ReadStats) {
+        self.print(format_args!(
+            "读取结束: {}（{} 个条目，{} 字节）",
+            path.unwrap_or("-"),
+            stats.entries,
+            stats.bytes
+        ));
+    }
+}
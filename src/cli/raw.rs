@@ -0,0 +1,72 @@
+//! # 原始 protobuf 输出
+//!
+//! `--format raw` 不解码 protobuf，而是把每条解密、解压后的日志条目
+//! 按 `varint 长度前缀 + 数据` 的 length-delimited 格式原样写出，
+//! 供使用自有解码器的下游处理，也便于排查 protobuf 解码失败的条目。
+
+use std::io::{self, Write};
+
+use clog_reader::proto::Log;
+use prost::Message;
+
+use super::sink::LogSink;
+
+/// 原始 protobuf 条目写入器
+pub struct RawWriter<W: Write> {
+    /// 底层写入器
+    inner: W,
+}
+
+impl<W: Write> RawWriter<W> {
+    /// 创建新的写入器
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// 返回底层写入器
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> LogSink for RawWriter<W> {
+    fn write_raw(&mut self, _offset: u64, payload: &[u8]) -> io::Result<()> {
+        let mut prefix = Vec::with_capacity(prost::length_delimiter_len(payload.len()));
+        prost::encoding::encode_varint(payload.len() as u64, &mut prefix);
+        self.inner.write_all(&prefix)?;
+        self.inner.write_all(payload)
+    }
+
+    fn write_log(&mut self, offset: u64, log: Log) -> io::Result<()> {
+        self.write_raw(offset, &log.encode_to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_length_delimited() {
+        let logs: Vec<Log> = (0..3)
+            .map(|i| Log {
+                log_type: i,
+                msg: "x".repeat(100 * i as usize),
+                ..Log::default()
+            })
+            .collect();
+
+        let mut writer = RawWriter::new(Vec::new());
+        for log in &logs {
+            writer.write_raw(0, &log.encode_to_vec()).unwrap();
+        }
+        let out = writer.into_inner();
+
+        let mut buf = out.as_slice();
+        let mut decoded = Vec::new();
+        while !buf.is_empty() {
+            decoded.push(Log::decode_length_delimited(&mut buf).unwrap());
+        }
+        assert_eq!(decoded, logs);
+    }
+}
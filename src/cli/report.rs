@@ -0,0 +1,260 @@
+//! # 逐文件读取报告
+//!
+//! 统计每个日志文件的成功条数、protobuf 解码失败数、各错误码的 NeedRecover 次数
+//! 以及无法读取的字节数和时间戳跳变，处理完成后打印为紧凑表格，并可导出为 JSON。
+//! 恢复事件和解码失败由 [`ReportObserver`] 从读取器收集，逐条的错误明细可通过 [`super::errors`] 导出。
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use clog_reader::analysis::TimeJump;
+use clog_reader::error::GlogError;
+use clog_reader::glog::RecoverEvent;
+use clog_reader::observer::Observer;
+use serde::Serialize;
+
+use super::errors::{ErrorStage, FileError, DECODE_FAILURE_CODE};
+use super::stats::Stats;
+
+/// 单个日志文件的读取报告
+#[derive(Debug, Default, Serialize)]
+pub struct FileReport {
+    /// 日志文件路径
+    pub file: String,
+    /// 成功输出的日志条数（经过过滤后）
+    pub count: usize,
+    /// 成功解码的日志条数（过滤前）
+    pub decoded: usize,
+    /// protobuf 解码失败的条数
+    pub decode_failures: usize,
+    /// 含有无效 UTF-8、按容错方式解码的条数
+    pub lossy_decodes: usize,
+    /// 脱敏时替换的次数
+    pub redactions: u64,
+    /// 按错误码统计的 NeedRecover 次数
+    pub recover_events: BTreeMap<i32, usize>,
+    /// 无法读取而被跳过的字节数
+    pub bytes_skipped: u64,
+    /// 检测到的时间戳跳变（`--detect-time-jumps`）
+    pub time_jumps: Vec<TimeJump>,
+    /// 通过过滤的日志统计（`--stats` / `--report-html`），不写入 JSON 报告
+    #[serde(skip)]
+    pub stats: Stats,
+    /// 读取中途遇到的错误（遇到错误后停止读取该文件）
+    pub error: Option<String>,
+    /// 错误的稳定错误码（参见 [`GlogError::code`]），错误不是读取器返回的时为 `None`
+    pub error_code: Option<u32>,
+    /// 逐条的错误明细（`--errors-json`），不写入 JSON 报告
+    #[serde(skip)]
+    pub errors: Vec<FileError>,
+}
+
+impl FileReport {
+    /// 创建指定文件的空报告
+    pub fn new(file: &Path) -> Self {
+        Self {
+            file: file.display().to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// 是否完整、干净地解析了该文件
+    pub fn is_clean(&self) -> bool {
+        self.recover_events.is_empty() && self.error.is_none()
+    }
+
+    /// 记录一次 NeedRecover 事件
+    pub fn record_recover(&mut self, code: i32) {
+        *self.recover_events.entry(code).or_insert(0) += 1;
+    }
+
+    /// 记录读取中途遇到的错误及其错误码
+    pub fn set_error(&mut self, message: String, code: Option<u32>) {
+        self.error = Some(message);
+        self.error_code = code;
+    }
+
+    /// 记录导致停止读取该文件的错误，同时加入错误明细
+    pub fn fail(&mut self, error: FileError) {
+        self.set_error(error.message.clone(), error.code);
+        self.errors.push(error);
+    }
+
+    /// NeedRecover 事件总数
+    pub fn total_recover_events(&self) -> usize {
+        self.recover_events.values().sum()
+    }
+}
+
+/// 把读取器的恢复事件、解码失败、容错解码和脱敏次数记入报告的观察者
+///
+/// 每个日志文件使用一个，读取结束后通过 [`apply`](Self::apply) 合并到该文件的报告中
+#[derive(Default)]
+pub struct ReportObserver {
+    /// 收集到的恢复事件、解码失败、容错解码和跳过的字节数
+    collected: Mutex<FileReport>,
+}
+
+impl ReportObserver {
+    /// 把收集到的统计合并到报告中
+    pub fn apply(&self, report: &mut FileReport) {
+        let collected = self.collected.lock().unwrap();
+        for (code, count) in &collected.recover_events {
+            *report.recover_events.entry(*code).or_insert(0) += count;
+        }
+        report.decode_failures += collected.decode_failures;
+        report.lossy_decodes += collected.lossy_decodes;
+        report.redactions += collected.redactions;
+        report.bytes_skipped += collected.bytes_skipped;
+        report.errors.extend(collected.errors.iter().cloned());
+    }
+}
+
+impl Observer for ReportObserver {
+    fn on_recover(&self, event: RecoverEvent) {
+        let mut collected = self.collected.lock().unwrap();
+        collected.record_recover(event.reason.code());
+        collected.bytes_skipped += event.bytes_skipped;
+        let error = GlogError::NeedRecover(event.reason).at(event.offset);
+        collected
+            .errors
+            .push(FileError::from_glog(ErrorStage::Recover, &error));
+    }
+
+    fn on_decode_failure(&self, offset: u64, len: u64) {
+        let mut collected = self.collected.lock().unwrap();
+        collected.decode_failures += 1;
+        collected.bytes_skipped += len;
+        collected.errors.push(FileError {
+            stage: ErrorStage::Decode,
+            offset: Some(offset),
+            code: Some(DECODE_FAILURE_CODE),
+            message: format!("protobuf 解码失败，跳过 {} 字节", len),
+            recover_reason: None,
+        });
+    }
+
+    fn on_lossy_decode(&self, _offset: u64, _len: u64) {
+        self.collected.lock().unwrap().lossy_decodes += 1;
+    }
+
+    fn on_redact(&self, _offset: u64, count: u64) {
+        self.collected.lock().unwrap().redactions += count;
+    }
+}
+
+/// 打印逐文件报告表格
+///
+/// # Arguments
+/// * `writer` - 输出目标
+/// * `reports` - 各文件的报告
+pub fn print_table<W: Write>(writer: &mut W, reports: &[FileReport]) -> Result<()> {
+    let name_width = reports
+        .iter()
+        .map(|r| display_name(&r.file).chars().count())
+        .chain(std::iter::once(4))
+        .max()
+        .unwrap_or(4);
+
+    writeln!(
+        writer,
+        "{:<name_width$}  {:>8}  {:>8}  {:>8}  {:>10}  状态",
+        "文件", "成功", "解码失败", "恢复事件", "跳过字节"
+    )?;
+    for report in reports {
+        writeln!(
+            writer,
+            "{:<name_width$}  {:>8}  {:>8}  {:>8}  {:>10}  {}",
+            display_name(&report.file),
+            report.decoded,
+            report.decode_failures,
+            report.total_recover_events(),
+            report.bytes_skipped,
+            status(report)
+        )?;
+    }
+    Ok(())
+}
+
+/// 将报告写入 JSON 文件
+///
+/// # Arguments
+/// * `path` - JSON 文件路径
+/// * `reports` - 各文件的报告
+pub fn write_json(path: &Path, reports: &[FileReport]) -> Result<()> {
+    let file = File::create(path).context(format!("创建报告文件失败: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &serde_json::json!({ "files": reports }))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// 错误链中第一个 [`GlogError`] 的错误码，用于打开文件等返回 `anyhow` 错误的步骤
+pub fn error_code(error: &anyhow::Error) -> Option<u32> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<GlogError>())
+        .map(GlogError::code)
+}
+
+/// 文件的读取状态：`OK`、`失败` 或 `恢复 -2×3`
+pub fn status(report: &FileReport) -> String {
+    match &report.error {
+        Some(_) => "失败".to_string(),
+        None if report.recover_events.is_empty() => "OK".to_string(),
+        None => format!("恢复 {}", format_codes(&report.recover_events)),
+    }
+}
+
+/// 表格中显示的文件名（仅文件名部分）
+pub fn display_name(file: &str) -> String {
+    Path::new(file)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| file.to_string())
+}
+
+/// 将错误码统计格式化为 `-2×3,-3×1` 的紧凑形式
+fn format_codes(codes: &BTreeMap<i32, usize>) -> String {
+    codes
+        .iter()
+        .map(|(code, n)| format!("{}×{}", code, n))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_recover_counts_by_code() {
+        let mut report = FileReport::new(Path::new("async-20240101.glog"));
+        report.record_recover(-2);
+        report.record_recover(-2);
+        report.record_recover(-3);
+
+        assert_eq!(report.recover_events[&-2], 2);
+        assert_eq!(report.total_recover_events(), 3);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_print_table() {
+        let mut report = FileReport::new(Path::new("/tmp/x/async-20240101.glog"));
+        report.decoded = 10;
+        report.record_recover(-3);
+
+        let mut out = Vec::new();
+        print_table(&mut out, &[report]).unwrap();
+        let table = String::from_utf8(out).unwrap();
+
+        assert_eq!(table.lines().count(), 2);
+        assert!(table.contains("async-20240101.glog"));
+        assert!(table.contains("恢复 -3×1"));
+    }
+}
@@ -0,0 +1,197 @@
+//! # 按大小轮转的输出文件
+//!
+//! 解码数 GB 的压缩包时，单个文本输出文件会变得难以处理。
+//! `RotatingWriter` 在当前文件超过阈值后关闭它并打开下一个分段文件
+//! （`log_output.txt` → `log_output.0001.txt` → `log_output.0002.txt` ...），
+//! 轮转只发生在换行符之后，因此不会把一条日志拆到两个文件中。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 按大小轮转的文件写入器
+pub struct RotatingWriter {
+    /// 第一个输出文件的路径，分段文件名由它派生
+    base: PathBuf,
+    /// 单个文件的大小阈值（`None` 表示不轮转）
+    max_size: Option<u64>,
+    /// 是否以追加模式打开文件
+    append: bool,
+    /// 当前文件
+    file: File,
+    /// 当前文件已写入的字节数
+    size: u64,
+    /// 上次写入是否以换行符结束（只有在行首才允许轮转）
+    at_line_start: bool,
+    /// 已打开过的所有文件路径
+    paths: Vec<PathBuf>,
+}
+
+impl RotatingWriter {
+    /// 创建新的轮转写入器
+    ///
+    /// # Arguments
+    /// * `base` - 第一个输出文件的路径
+    /// * `max_size` - 单个文件的大小阈值，`None` 表示不轮转
+    /// * `append` - 是否以追加模式打开文件
+    pub fn new(base: &Path, max_size: Option<u64>, append: bool) -> io::Result<Self> {
+        let file = open_file(base, append)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            base: base.to_path_buf(),
+            max_size,
+            append,
+            file,
+            size,
+            at_line_start: true,
+            paths: vec![base.to_path_buf()],
+        })
+    }
+
+    /// 已写入的所有文件路径（按写入顺序）
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// 位于行首且当前文件超过阈值时切换到下一个分段文件
+    ///
+    /// 在写入新数据之前才检查，避免最后一行恰好越过阈值时留下空的分段文件
+    fn rotate_if_needed(&mut self) -> io::Result<()> {
+        match self.max_size {
+            Some(max_size) if self.at_line_start && self.size >= max_size => {}
+            _ => return Ok(()),
+        }
+        self.file.flush()?;
+        let path = segment_path(&self.base, self.paths.len());
+        self.file = open_file(&path, self.append)?;
+        self.size = self.file.metadata()?.len();
+        self.paths.push(path);
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.rotate_if_needed()?;
+
+        // 只写到第一个换行符为止，使下一次写入能在行首检查是否需要轮转
+        let len = match buf.iter().position(|&b| b == b'\n') {
+            Some(pos) => pos + 1,
+            None => buf.len(),
+        };
+        let written = self.file.write(&buf[..len])?;
+        self.size += written as u64;
+        if written > 0 {
+            self.at_line_start = buf[written - 1] == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 打开输出文件
+fn open_file(path: &Path, append: bool) -> io::Result<File> {
+    if append {
+        OpenOptions::new().create(true).append(true).open(path)
+    } else {
+        File::create(path)
+    }
+}
+
+/// 第 `index` 个分段文件的路径
+///
+/// 序号插入到扩展名之前，如 `log_output.txt` 的第 1 段为 `log_output.0001.txt`
+fn segment_path(base: &Path, index: usize) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}.{:04}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}.{:04}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+/// 解析文件大小参数
+///
+/// 支持纯字节数以及 `K`/`M`/`G` 后缀（按 1024 进制，可带 `B`，不区分大小写），
+/// 如 `1048576`、`10M`、`1GB`
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, multiplier) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 1u64 << 10),
+        Some('M') => (&digits[..digits.len() - 1], 1u64 << 20),
+        Some('G') => (&digits[..digits.len() - 1], 1u64 << 30),
+        _ => (digits, 1),
+    };
+    let size = number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("无效的文件大小: {}（示例: 1048576、10M、1G）", value))?;
+    if size == 0 {
+        return Err("文件大小必须大于 0".to_string());
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("10M"), Ok(10 << 20));
+        assert_eq!(parse_size("1g"), Ok(1 << 30));
+        assert_eq!(parse_size("4KB"), Ok(4096));
+        assert!(parse_size("abc").is_err());
+        assert!(parse_size("0").is_err());
+    }
+
+    #[test]
+    fn test_segment_path() {
+        let base = Path::new("/tmp/log_output.txt");
+        assert_eq!(segment_path(base, 1), Path::new("/tmp/log_output.0001.txt"));
+        assert_eq!(segment_path(Path::new("out"), 12), Path::new("out.0012"));
+    }
+
+    #[test]
+    fn test_rotates_only_at_line_boundaries() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().join("log_output.txt");
+        let mut writer = RotatingWriter::new(&base, Some(10), false).unwrap();
+
+        // 第一行 11 字节超过阈值，但必须完整写入第一个文件
+        writer.write_all(b"first line\n\nsecond line\nthird").unwrap();
+        writer.write_all(b" line\n").unwrap();
+        writer.flush().unwrap();
+
+        let paths = writer.paths().to_vec();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "first line\n");
+        assert_eq!(std::fs::read_to_string(&paths[1]).unwrap(), "\nsecond line\n");
+        assert_eq!(std::fs::read_to_string(&paths[2]).unwrap(), "third line\n");
+    }
+
+    #[test]
+    fn test_no_rotation_without_limit() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path().join("log_output.txt");
+        let mut writer = RotatingWriter::new(&base, None, false).unwrap();
+        for _ in 0..100 {
+            writer.write_all(b"some log line\n").unwrap();
+        }
+
+        assert_eq!(writer.paths(), [base]);
+    }
+}
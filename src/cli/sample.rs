@@ -0,0 +1,169 @@
+//! # 确定性抽样
+//!
+//! 可视化工具难以处理上千万行日志。`Sampler` 对通过过滤的日志按 (文件名, 条目偏移)
+//! 的带种子哈希保留一部分，重复运行得到完全相同的样本。使用条目偏移而不是序号，
+//! 因此 `--sort` 合并输出和逐文件输出选中的是同一批日志；只取文件名，
+//! 压缩包每次解压到不同的临时目录也不影响结果。
+
+use clog_reader::proto::{Level, Log};
+
+/// 确定性抽样器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler {
+    /// 哈希值小于该阈值的日志被保留（按 2^64 缩放的保留比例）
+    threshold: u128,
+    /// 是否始终保留 Warn / Error 级别的日志
+    keep_errors: bool,
+    /// 哈希种子
+    seed: u64,
+}
+
+impl Sampler {
+    /// 按比例抽样
+    ///
+    /// # Arguments
+    /// * `rate` - 保留比例，0.0 ~ 1.0
+    pub fn fraction(rate: f64) -> Self {
+        let threshold = (rate.clamp(0.0, 1.0) * 2f64.powi(64)) as u128;
+        Self {
+            threshold,
+            keep_errors: false,
+            seed: 0,
+        }
+    }
+
+    /// 平均每 `n` 条保留一条
+    ///
+    /// # Arguments
+    /// * `n` - 抽样间隔，至少为 1
+    pub fn every(n: u64) -> Self {
+        Self {
+            threshold: (1u128 << 64) / u128::from(n.max(1)),
+            keep_errors: false,
+            seed: 0,
+        }
+    }
+
+    /// 设置是否始终保留 Warn / Error 级别的日志
+    pub fn keep_errors(mut self, keep_errors: bool) -> Self {
+        self.keep_errors = keep_errors;
+        self
+    }
+
+    /// 设置哈希种子，不同的种子得到不同的样本
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 是否保留一条日志
+    ///
+    /// # Arguments
+    /// * `file` - 来源文件名（不含目录）
+    /// * `offset` - 条目在文件中的偏移
+    /// * `log` - 日志
+    pub fn keeps(&self, file: &str, offset: u64, log: &Log) -> bool {
+        if self.keep_errors && matches!(log.level(), Level::Warn | Level::Error) {
+            return true;
+        }
+        u128::from(self.hash(file, offset)) < self.threshold
+    }
+
+    /// 对 (种子, 文件名, 偏移) 计算哈希
+    ///
+    /// 使用固定的 FNV-1a 加 splitmix64 混合，不依赖标准库哈希的实现，
+    /// 保证不同版本、不同平台上选中相同的日志
+    fn hash(&self, file: &str, offset: u64) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0100_0000_01b3;
+        let bytes = self
+            .seed
+            .to_le_bytes()
+            .into_iter()
+            .chain(file.bytes())
+            .chain(offset.to_le_bytes());
+        let mut h = bytes.fold(FNV_OFFSET, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(FNV_PRIME)
+        });
+        h ^= h >> 30;
+        h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h ^= h >> 27;
+        h = h.wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^ (h >> 31)
+    }
+}
+
+/// 解析 `--sample` 参数
+///
+/// # Returns
+/// 返回 0.0 ~ 1.0 之间的保留比例，否则返回错误信息
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("抽样比例应为 0.0 ~ 1.0 之间的小数: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(level: Level) -> Log {
+        Log {
+            log_level: level as i32,
+            ..Log::default()
+        }
+    }
+
+    fn kept(sampler: &Sampler, file: &str) -> Vec<u64> {
+        (0..10_000)
+            .map(|i| i * 64)
+            .filter(|&offset| sampler.keeps(file, offset, &log(Level::Info)))
+            .collect()
+    }
+
+    #[test]
+    fn test_selection_is_deterministic() {
+        let sampler = Sampler::fraction(0.1);
+        let first = kept(&sampler, "async-20240101.glog");
+        assert_eq!(first, kept(&sampler, "async-20240101.glog"));
+        assert!((800..1200).contains(&first.len()), "{}", first.len());
+
+        // 种子和文件名都参与哈希
+        assert_ne!(first, kept(&sampler.seed(1), "async-20240101.glog"));
+        assert_ne!(first, kept(&sampler, "async-20240102.glog"));
+
+        // 固定的选择结果，哈希实现变化时需要同步更新
+        let sampler = Sampler::every(4);
+        let golden: Vec<u64> = (0..16)
+            .filter(|&offset| sampler.keeps("a.glog", offset, &log(Level::Info)))
+            .collect();
+        assert_eq!(golden, GOLDEN);
+    }
+
+    const GOLDEN: [u64; 4] = [2, 4, 13, 15];
+
+    #[test]
+    fn test_bounds_and_keep_errors() {
+        assert!(kept(&Sampler::fraction(0.0), "a").is_empty());
+        assert_eq!(kept(&Sampler::fraction(1.0), "a").len(), 10_000);
+        assert_eq!(kept(&Sampler::every(1), "a").len(), 10_000);
+        let every = kept(&Sampler::every(10), "a").len();
+        assert!((800..1200).contains(&every), "{}", every);
+
+        let sampler = Sampler::fraction(0.0).keep_errors(true);
+        assert!(sampler.keeps("a", 0, &log(Level::Error)));
+        assert!(sampler.keeps("a", 0, &log(Level::Warn)));
+        assert!(!sampler.keeps("a", 0, &log(Level::Info)));
+        assert!(!Sampler::fraction(0.0).keeps("a", 0, &log(Level::Error)));
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("0.25"), Ok(0.25));
+        assert_eq!(parse_rate("1"), Ok(1.0));
+        for bad in ["-0.1", "1.5", "abc", "NaN"] {
+            assert!(parse_rate(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! # 日志输出目标
+//!
+//! `read_logs` 把通过过滤的日志交给 [`LogSink`]，由具体的输出目标决定如何处理：
+//! 文本输出经由 [`Deduper`] 格式化写入，结构化输出（如 SQLite）则先收集日志及其偏移，
+//! 再由主线程按文件顺序写入；原始输出则在 protobuf 解码之前直接接收条目数据。
+//! 时间戳跳变等标记行只写入文本输出。
+//!
+//! `--show-source` 时各输出目标用 [`Source`] 附加日志的来源文件和条目偏移：文本行以 `文件:偏移` 开头，
+//! logfmt 行以 `file=... offset=...` 开头，JSON 输出增加 `file`、`offset` 字段；SQLite 总是记录这两列。
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use clog_reader::proto::Log;
+
+use super::dedup::Deduper;
+
+/// 日志输出目标
+pub trait LogSink {
+    /// 写入一条通过过滤的日志
+    ///
+    /// # Arguments
+    /// * `offset` - 日志条目在源文件中的字节偏移
+    /// * `log` - 日志
+    fn write_log(&mut self, offset: u64, log: Log) -> io::Result<()>;
+
+    /// 写入一条未经 protobuf 解码的原始条目
+    ///
+    /// 默认不支持，只有原始输出目标会实现
+    ///
+    /// # Arguments
+    /// * `offset` - 日志条目在源文件中的字节偏移
+    /// * `payload` - 解密、解压后的条目数据
+    fn write_raw(&mut self, _offset: u64, _payload: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "该输出目标不支持原始条目",
+        ))
+    }
+
+    /// 在日志之间插入一行标记（如时间戳跳变）
+    ///
+    /// 默认忽略，只有文本输出目标会实现
+    ///
+    /// # Arguments
+    /// * `text` - 标记文本，不含换行
+    fn write_marker(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 日志的来源（`--show-source`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Source<'a> {
+    /// 来源日志文件名（不含目录）
+    pub file: &'a str,
+    /// 条目在文件中的起始偏移，即读取器返回的 `EntryInfo::offset`
+    pub offset: u64,
+}
+
+impl fmt::Display for Source<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.offset)
+    }
+}
+
+impl<W: Write> LogSink for Deduper<W> {
+    fn write_log(&mut self, offset: u64, log: Log) -> io::Result<()> {
+        let Some(file) = self.source_file().map(Arc::clone) else {
+            return Deduper::write_log(self, &log);
+        };
+        let source = Source {
+            file: &file,
+            offset,
+        };
+        self.write_log_with_source(&log, Some(source))
+    }
+
+    fn write_marker(&mut self, text: &str) -> io::Result<()> {
+        Deduper::write_marker(self, text)
+    }
+}
+
+/// 收集日志及其在源文件中的偏移
+impl LogSink for Vec<(u64, Log)> {
+    fn write_log(&mut self, offset: u64, log: Log) -> io::Result<()> {
+        self.push((offset, log));
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! # SQLite 导出
+//!
+//! 将日志写入 SQLite 数据库的 `logs` 表，便于使用 SQL 进行临时分析。
+//! 仅在启用 `sqlite` 功能时编译。
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use clog_reader::proto::Log;
+use rusqlite::{params, Connection};
+
+/// 每个事务插入的最大行数
+const BATCH_SIZE: usize = 5000;
+
+/// 建表及索引语句
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS logs (
+    id       INTEGER PRIMARY KEY,
+    file     TEXT    NOT NULL,
+    "offset" INTEGER NOT NULL,
+    ts_ms    INTEGER,
+    level    INTEGER NOT NULL,
+    "type"   INTEGER NOT NULL,
+    pid      INTEGER NOT NULL,
+    tid      TEXT    NOT NULL,
+    tag      TEXT    NOT NULL,
+    msg      TEXT    NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_logs_ts_ms ON logs (ts_ms);
+CREATE INDEX IF NOT EXISTS idx_logs_tag ON logs (tag);
+"#;
+
+/// SQLite 日志写入器
+pub struct SqliteWriter {
+    /// 数据库连接
+    conn: Connection,
+}
+
+impl SqliteWriter {
+    /// 打开（或创建）数据库并确保 `logs` 表及索引存在
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径
+    ///
+    /// # Returns
+    /// 返回新创建的写入器
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .context(format!("打开数据库失败: {}", path.display()))?;
+        conn.execute_batch(SCHEMA).context("创建 logs 表失败")?;
+        Ok(Self { conn })
+    }
+
+    /// 批量插入同一文件中的日志
+    ///
+    /// 每 [`BATCH_SIZE`] 行提交一次事务
+    ///
+    /// # Arguments
+    /// * `file` - 来源文件名
+    /// * `logs` - 日志及其在源文件中的字节偏移
+    pub fn insert_logs(&mut self, file: &str, logs: &[(u64, Log)]) -> Result<()> {
+        for batch in logs.chunks(BATCH_SIZE) {
+            let tx = self.conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    r#"INSERT INTO logs (file, "offset", ts_ms, level, "type", pid, tid, tag, msg)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+                )?;
+                for (offset, log) in batch {
+                    stmt.execute(params![
+                        file,
+                        *offset as i64,
+                        log.timestamp_millis(),
+                        log.log_level,
+                        log.log_type,
+                        log.pid,
+                        log.tid,
+                        log.tag,
+                        log.msg,
+                    ])?;
+                }
+            }
+            tx.commit()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn log(timestamp: &str, tag: &str, msg: &str) -> Log {
+        Log {
+            timestamp: timestamp.to_string(),
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+            ..Log::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_and_reopen() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("logs.db");
+
+        let mut writer = SqliteWriter::open(&path).unwrap();
+        let logs: Vec<(u64, Log)> = (0..BATCH_SIZE + 10)
+            .map(|i| (i as u64 * 20, log(&format!("{}", 1700000000000u64 + i as u64), "Main", "x")))
+            .collect();
+        writer.insert_logs("async-20240101.glog", &logs).unwrap();
+        writer
+            .insert_logs("async-20240102.glog", &[(16, log("bad", "Net", "timeout"))])
+            .unwrap();
+        drop(writer);
+
+        let conn = Connection::open(&path).unwrap();
+        let total: i64 = conn
+            .query_row("SELECT COUNT(*) FROM logs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(total as usize, BATCH_SIZE + 11);
+
+        let (offset, ts_ms): (i64, Option<i64>) = conn
+            .query_row(
+                r#"SELECT "offset", ts_ms FROM logs WHERE tag = 'Net'"#,
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(offset, 16);
+        assert_eq!(ts_ms, None);
+
+        let plan: String = conn
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT * FROM logs WHERE tag = 'Net'",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("idx_logs_tag"));
+    }
+}
@@ -0,0 +1,203 @@
+//! # 日志统计
+//!
+//! 统计通过过滤（及抽样）的日志按级别、类型、标签的条数，按小时分桶的错误数，
+//! 以及出现次数最多的消息。`--stats` 打印为表格，`--report-html` 渲染为 HTML，
+//! 两者使用同一个 [`Stats`]，数字不会不一致。
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use anyhow::Result;
+use clog_reader::proto::{Level, Log, LogTypeRegistry};
+
+/// 一小时的毫秒数
+pub const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+
+/// 日志统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// 统计的日志总数
+    pub total: u64,
+    /// 各级别的条数
+    pub levels: BTreeMap<Level, u64>,
+    /// 各日志类型的条数
+    pub types: BTreeMap<i32, u64>,
+    /// 各标签的条数
+    pub tags: HashMap<String, u64>,
+    /// 各消息内容的条数
+    pub messages: HashMap<String, u64>,
+    /// 按小时分桶的 Error 级别条数，键为 UTC 整点的毫秒时间戳
+    pub error_hours: BTreeMap<i64, u64>,
+    /// 时间戳无法解析的 Error 级别条数
+    pub errors_without_time: u64,
+}
+
+impl Stats {
+    /// 记录一条日志
+    pub fn record(&mut self, log: &Log) {
+        self.total += 1;
+        let level = log.level();
+        *self.levels.entry(level).or_insert(0) += 1;
+        *self.types.entry(log.log_type).or_insert(0) += 1;
+        increment(&mut self.tags, &log.tag);
+        increment(&mut self.messages, &log.msg);
+        if level == Level::Error {
+            match log.timestamp_millis() {
+                Some(ts) => {
+                    let hour = ts.div_euclid(HOUR_MILLIS) * HOUR_MILLIS;
+                    *self.error_hours.entry(hour).or_insert(0) += 1;
+                }
+                None => self.errors_without_time += 1,
+            }
+        }
+    }
+
+    /// 合并另一个文件的统计
+    pub fn merge(&mut self, other: &Stats) {
+        self.total += other.total;
+        for (level, n) in &other.levels {
+            *self.levels.entry(*level).or_insert(0) += n;
+        }
+        for (log_type, n) in &other.types {
+            *self.types.entry(*log_type).or_insert(0) += n;
+        }
+        for (tag, n) in &other.tags {
+            *self.tags.entry(tag.clone()).or_insert(0) += n;
+        }
+        for (msg, n) in &other.messages {
+            *self.messages.entry(msg.clone()).or_insert(0) += n;
+        }
+        for (hour, n) in &other.error_hours {
+            *self.error_hours.entry(*hour).or_insert(0) += n;
+        }
+        self.errors_without_time += other.errors_without_time;
+    }
+
+    /// 按严重程度从高到低排列的各级别条数
+    pub fn levels_by_severity(&self) -> Vec<(Level, u64)> {
+        let mut levels: Vec<_> = self.levels.iter().map(|(l, n)| (*l, *n)).collect();
+        levels.sort_by_key(|(level, _)| std::cmp::Reverse(level.severity()));
+        levels
+    }
+
+    /// 条数最多的 `n` 个标签
+    pub fn top_tags(&self, n: usize) -> Vec<(&str, u64)> {
+        top(&self.tags, n)
+    }
+
+    /// 出现次数最多的 `n` 条消息
+    pub fn top_messages(&self, n: usize) -> Vec<(&str, u64)> {
+        top(&self.messages, n)
+    }
+}
+
+/// 日志类型的显示名称，未注册的类型显示为整数
+pub fn type_name(types: Option<&LogTypeRegistry>, log_type: i32) -> String {
+    types.and_then(|types| types.name(log_type)).map_or_else(
+        || log_type.to_string(),
+        |name| format!("{} ({})", name, log_type),
+    )
+}
+
+/// 打印统计表格
+///
+/// # Arguments
+/// * `writer` - 输出目标
+/// * `stats` - 统计结果
+/// * `types` - 日志类型名称表
+/// * `top_n` - 标签和消息各显示的条数
+pub fn print_tables<W: Write>(
+    writer: &mut W,
+    stats: &Stats,
+    types: Option<&LogTypeRegistry>,
+    top_n: usize,
+) -> Result<()> {
+    writeln!(writer, "共统计 {} 条日志", stats.total)?;
+
+    writeln!(writer, "\n{:<10}  {:>10}", "级别", "条数")?;
+    for (level, n) in stats.levels_by_severity() {
+        writeln!(writer, "{:<10}  {:>10}", level.as_str(), n)?;
+    }
+
+    writeln!(writer, "\n{:<20}  {:>10}", "类型", "条数")?;
+    for (log_type, n) in &stats.types {
+        writeln!(writer, "{:<20}  {:>10}", type_name(types, *log_type), n)?;
+    }
+
+    writeln!(writer, "\n{:>10}  标签（前 {} 个）", "条数", top_n)?;
+    for (tag, n) in stats.top_tags(top_n) {
+        writeln!(writer, "{:>10}  {}", n, tag)?;
+    }
+
+    writeln!(writer, "\n{:>10}  消息（前 {} 条）", "条数", top_n)?;
+    for (msg, n) in stats.top_messages(top_n) {
+        writeln!(writer, "{:>10}  {}", n, msg.replace('\n', "\\n"))?;
+    }
+    Ok(())
+}
+
+/// 计数加一，已有的键不再分配
+fn increment(counts: &mut HashMap<String, u64>, key: &str) {
+    match counts.get_mut(key) {
+        Some(n) => *n += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
+
+/// 按条数从多到少取前 `n` 项，条数相同时按键排序，保证输出稳定
+fn top(counts: &HashMap<String, u64>, n: usize) -> Vec<(&str, u64)> {
+    let mut items: Vec<_> = counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    items.truncate(n);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(level: Level, tag: &str, msg: &str, timestamp: &str) -> Log {
+        Log {
+            log_level: level as i32,
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+            timestamp: timestamp.to_string(),
+            ..Log::default()
+        }
+    }
+
+    #[test]
+    fn test_record_and_merge() {
+        let mut first = Stats::default();
+        first.record(&log(Level::Info, "Net", "ok", "1700000000000"));
+        first.record(&log(Level::Error, "Net", "fail", "1700000000000"));
+        let mut second = Stats::default();
+        second.record(&log(Level::Error, "Ui", "fail", "1700003600000"));
+        second.record(&log(Level::Error, "Ui", "fail", "garbage"));
+
+        first.merge(&second);
+        assert_eq!(first.total, 4);
+        assert_eq!(
+            first.levels_by_severity(),
+            vec![(Level::Error, 3), (Level::Info, 1)]
+        );
+        assert_eq!(first.top_tags(1), vec![("Net", 2)]);
+        assert_eq!(first.top_messages(5), vec![("fail", 3), ("ok", 1)]);
+        assert_eq!(
+            first.error_hours.into_iter().collect::<Vec<_>>(),
+            vec![(1_699_999_200_000, 1), (1_700_002_800_000, 1)]
+        );
+        assert_eq!(first.errors_without_time, 1);
+    }
+
+    #[test]
+    fn test_top_is_stable_on_ties() {
+        let mut stats = Stats::default();
+        for tag in ["b", "a", "c", "a"] {
+            stats.record(&log(Level::Info, tag, "", ""));
+        }
+        assert_eq!(stats.top_tags(3), vec![("a", 2), ("b", 1), ("c", 1)]);
+    }
+}
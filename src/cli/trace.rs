@@ -0,0 +1,278 @@
+//! # Trace Event 导出
+//!
+//! `--format trace-json` 按 Chrome Trace Event Format 输出一个 JSON 数组，可以直接在
+//! chrome://tracing 或 Perfetto UI 中打开，在时间线上查看日志密度和特定标签的事件。
+//! 每条日志是一个线程范围的即时事件（`"ph":"i"`），`name` 为标签，`args` 包含级别、类型和截断后的内容；
+//! 数组末尾是进程和线程的名称元数据事件（`"ph":"M"`）。`--show-source` 时 `args` 中增加来源的 `file` 和 `offset`。
+//!
+//! 事件边写边输出，只在内存中保留进程和线程列表。时间戳无法解析的日志无法放上时间线，被跳过并计数。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use clog_reader::proto::Log;
+use serde::Serialize;
+
+use super::sink::Source;
+
+/// `args.msg` 保留的最大字符数，超出部分以 `…` 代替
+pub const MSG_LIMIT: usize = 512;
+
+/// 非数字线程 ID 分配的编号起点，避开常见的数字线程 ID
+const SYNTHETIC_TID_BASE: u64 = 1 << 32;
+
+/// 一个 Trace Event
+#[derive(Serialize)]
+struct Event<'a, A: Serialize> {
+    /// 事件名称
+    name: &'a str,
+    /// 事件类型：`i` 为即时事件，`M` 为元数据
+    ph: &'static str,
+    /// 即时事件的范围，`t` 表示线程
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<&'static str>,
+    /// 时间戳（微秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<i64>,
+    /// 进程 ID
+    pid: i32,
+    /// 线程 ID
+    tid: u64,
+    /// 附加参数
+    args: A,
+}
+
+/// 日志事件的附加参数
+#[derive(Serialize)]
+struct LogArgs<'a> {
+    level: &'static str,
+    #[serde(rename = "type")]
+    log_type: i32,
+    msg: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<u64>,
+}
+
+/// 元数据事件的附加参数
+#[derive(Serialize)]
+struct NameArgs<'a> {
+    name: &'a str,
+}
+
+/// Trace Event JSON 写入器
+pub struct TraceWriter<W: Write> {
+    /// 底层写入器
+    inner: W,
+    /// 是否还没有写入任何事件
+    empty: bool,
+    /// 出现过的进程 ID
+    pids: BTreeSet<i32>,
+    /// 出现过的线程：(进程 ID, 线程 ID) -> 原始线程标识
+    threads: BTreeMap<(i32, u64), String>,
+    /// 非数字线程标识分配的编号
+    synthetic_tids: BTreeMap<String, u64>,
+    /// 时间戳无法解析而被跳过的日志数
+    skipped: u64,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// 创建写入器并写入数组的开头
+    pub fn new(mut inner: W) -> io::Result<Self> {
+        inner.write_all(b"[")?;
+        Ok(Self {
+            inner,
+            empty: true,
+            pids: BTreeSet::new(),
+            threads: BTreeMap::new(),
+            synthetic_tids: BTreeMap::new(),
+            skipped: 0,
+        })
+    }
+
+    /// 写入一条日志对应的即时事件，`source` 不为 `None` 时在 `args` 中记录来源
+    ///
+    /// 时间戳无法解析的日志被跳过，计入 [`skipped`](Self::skipped)
+    pub fn write_log(&mut self, log: &Log, source: Option<Source<'_>>) -> io::Result<()> {
+        let Some(millis) = log.timestamp_millis() else {
+            self.skipped += 1;
+            return Ok(());
+        };
+        let tid = self.thread_id(&log.tid);
+        self.pids.insert(log.pid);
+        self.threads
+            .entry((log.pid, tid))
+            .or_insert_with(|| log.tid.clone());
+        let event = Event {
+            name: &log.tag,
+            ph: "i",
+            s: Some("t"),
+            ts: Some(millis.saturating_mul(1000)),
+            pid: log.pid,
+            tid,
+            args: LogArgs {
+                level: log.level().as_str(),
+                log_type: log.log_type,
+                msg: &truncate(&log.msg, MSG_LIMIT),
+                file: source.map(|source| source.file),
+                offset: source.map(|source| source.offset),
+            },
+        };
+        self.write_event(&event)
+    }
+
+    /// 时间戳无法解析而被跳过的日志数
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// 写入进程和线程的名称元数据并结束数组
+    ///
+    /// # Returns
+    /// 返回底层写入器
+    pub fn finish(mut self) -> io::Result<W> {
+        for pid in std::mem::take(&mut self.pids) {
+            let name = format!("pid {}", pid);
+            self.write_metadata("process_name", pid, 0, &name)?;
+        }
+        for ((pid, tid), name) in std::mem::take(&mut self.threads) {
+            self.write_metadata("thread_name", pid, tid, &name)?;
+        }
+        self.inner.write_all(b"\n]\n")?;
+        Ok(self.inner)
+    }
+
+    /// 写入一个元数据事件
+    fn write_metadata(&mut self, kind: &str, pid: i32, tid: u64, name: &str) -> io::Result<()> {
+        let event = Event {
+            name: kind,
+            ph: "M",
+            s: None,
+            ts: None,
+            pid,
+            tid,
+            args: NameArgs { name },
+        };
+        self.write_event(&event)
+    }
+
+    /// 写入一个事件，事件之间以逗号分隔，每个事件占一行
+    fn write_event<A: Serialize>(&mut self, event: &Event<'_, A>) -> io::Result<()> {
+        self.inner
+            .write_all(if self.empty { b"\n" } else { b",\n" })?;
+        self.empty = false;
+        serde_json::to_writer(&mut self.inner, event).map_err(io::Error::from)
+    }
+
+    /// 把线程标识转换为 Trace Event 要求的整数
+    ///
+    /// 数字标识原样使用，其他标识按首次出现的顺序分配编号，原始标识写入线程名称元数据
+    fn thread_id(&mut self, tid: &str) -> u64 {
+        if let Ok(tid) = tid.trim().parse::<u64>() {
+            return tid;
+        }
+        let next = SYNTHETIC_TID_BASE + self.synthetic_tids.len() as u64;
+        *self.synthetic_tids.entry(tid.to_string()).or_insert(next)
+    }
+}
+
+/// 截断到最多 `limit` 个字符，超出时以 `…` 结尾
+fn truncate(msg: &str, limit: usize) -> std::borrow::Cow<'_, str> {
+    match msg.char_indices().nth(limit) {
+        Some((end, _)) => format!("{}…", &msg[..end]).into(),
+        None => msg.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(ts: &str, pid: i32, tid: &str, tag: &str, msg: &str) -> Log {
+        Log {
+            timestamp: ts.to_string(),
+            log_level: 4,
+            pid,
+            tid: tid.to_string(),
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+            ..Log::default()
+        }
+    }
+
+    #[test]
+    fn test_output_is_valid_trace_json() {
+        let mut writer = TraceWriter::new(Vec::new()).unwrap();
+        let long = "x".repeat(MSG_LIMIT + 10);
+        writer
+            .write_log(
+                &log("1704067200000", 10, "11", "Net", "a \"quoted\"\nline"),
+                None,
+            )
+            .unwrap();
+        writer
+            .write_log(&log("1704067200001", 10, "main", "Ui", &long), None)
+            .unwrap();
+        writer
+            .write_log(&log("garbage", 10, "11", "Net", ""), None)
+            .unwrap();
+        assert_eq!(writer.skipped(), 1);
+        let out = writer.finish().unwrap();
+
+        let events: Vec<serde_json::Value> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0]["name"], "Net");
+        assert_eq!(events[0]["ph"], "i");
+        assert_eq!(events[0]["s"], "t");
+        assert_eq!(events[0]["ts"], 1_704_067_200_000_000i64);
+        assert_eq!(events[0]["pid"], 10);
+        assert_eq!(events[0]["tid"], 11);
+        assert_eq!(events[0]["args"]["level"], "Error");
+        assert_eq!(events[0]["args"]["msg"], "a \"quoted\"\nline");
+
+        assert_eq!(events[1]["tid"], SYNTHETIC_TID_BASE);
+        let msg = events[1]["args"]["msg"].as_str().unwrap();
+        assert_eq!(msg.chars().count(), MSG_LIMIT + 1);
+        assert!(msg.ends_with('…'));
+
+        assert_eq!(events[2]["ph"], "M");
+        assert_eq!(events[2]["name"], "process_name");
+        assert_eq!(events[2]["args"]["name"], "pid 10");
+        assert_eq!(events[3]["name"], "thread_name");
+        assert_eq!(events[3]["tid"], 11);
+        assert_eq!(events[3]["args"]["name"], "11");
+        assert_eq!(events[4]["tid"], SYNTHETIC_TID_BASE);
+        assert_eq!(events[4]["args"]["name"], "main");
+    }
+
+    #[test]
+    fn test_source_is_recorded_in_args() {
+        let mut writer = TraceWriter::new(Vec::new()).unwrap();
+        let source = Source {
+            file: "async-20240101.glog",
+            offset: 42,
+        };
+        writer
+            .write_log(&log("1704067200000", 1, "1", "Net", "x"), Some(source))
+            .unwrap();
+        let out = writer.finish().unwrap();
+
+        let events: Vec<serde_json::Value> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(events[0]["args"]["file"], "async-20240101.glog");
+        assert_eq!(events[0]["args"]["offset"], 42);
+    }
+
+    #[test]
+    fn test_empty_output_is_empty_array() {
+        let out = TraceWriter::new(Vec::new()).unwrap().finish().unwrap();
+        let events: Vec<serde_json::Value> = serde_json::from_slice(&out).unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_truncate_counts_chars() {
+        assert_eq!(truncate("日志内容", 2), "日志…");
+        assert_eq!(truncate("日志", 2), "日志");
+    }
+}
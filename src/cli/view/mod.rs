@@ -0,0 +1,445 @@
+//! # 交互式日志查看器
+//!
+//! `view` 子命令（`tui` 功能）在终端中浏览压缩包中的日志，日志按 [`LogWindow`] 惰性解码，
+//! 只在内存中保留当前屏幕附近的部分。每行按级别着色，底部状态栏显示选中日志的来源文件和条目偏移。
+//!
+//! | 按键 | 作用 |
+//! |------|------|
+//! | `j` / `k`、方向键 | 上下移动一行 |
+//! | `PageDown` / `PageUp`、空格 / `b` | 翻页 |
+//! | `g` / `G` | 第一条 / 最后一条日志 |
+//! | `/` | 在标签和内容中增量搜索（不区分大小写），`Enter` 确认，`Esc` 取消 |
+//! | `n` / `N` | 下一个 / 上一个匹配 |
+//! | `f` | 切换最低级别：全部 → Debug → Info → Warn → Error |
+//! | `t` | 切换日志类型：全部 → 已出现过的各个类型 |
+//! | `q` / `Esc` | 退出 |
+
+pub mod window;
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use clog_reader::format::DefaultFormatter;
+use clog_reader::proto::{Level, Log, LogFilter, LogTypeRegistry};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+use ratatui::{DefaultTerminal, Frame};
+
+pub use window::LogWindow;
+
+/// `f` 依次切换的最低级别，`None` 表示不过滤
+const LEVEL_CYCLE: [Option<Level>; 5] = [
+    None,
+    Some(Level::Debug),
+    Some(Level::Info),
+    Some(Level::Warn),
+    Some(Level::Error),
+];
+
+/// 在终端中运行查看器，直到用户退出
+///
+/// # Arguments
+/// * `window` - 日志窗口
+/// * `title` - 状态栏中显示的输入名称
+pub fn run(window: LogWindow, title: &str) -> Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Viewer::new(window, title).run(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+/// 输入模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// 浏览
+    Normal,
+    /// 输入搜索词
+    Search,
+}
+
+/// 查看器的状态
+struct Viewer {
+    /// 日志窗口
+    window: LogWindow,
+    /// 输入名称
+    title: String,
+    /// 日志行的格式化器（带类型名称）
+    formatter: DefaultFormatter,
+    /// 选中的行号
+    selected: usize,
+    /// 屏幕第一行的行号
+    top: usize,
+    /// 日志列表的高度，绘制时更新
+    height: usize,
+    /// 输入模式
+    mode: Mode,
+    /// 搜索词
+    query: String,
+    /// 开始输入搜索词时选中的行，增量搜索从这里开始，取消时回到这里
+    search_origin: usize,
+    /// 当前最低级别在 [`LEVEL_CYCLE`] 中的位置
+    level: usize,
+    /// 只显示的日志类型
+    log_type: Option<i32>,
+    /// 已经显示过的日志类型，供 `t` 切换
+    seen_types: BTreeSet<i32>,
+    /// 状态栏中的提示
+    message: Option<String>,
+    /// 是否退出
+    quit: bool,
+}
+
+impl Viewer {
+    /// 创建查看器，选中第一条日志
+    fn new(window: LogWindow, title: &str) -> Self {
+        Self {
+            window,
+            title: title.to_string(),
+            formatter: DefaultFormatter::with_types(Arc::new(LogTypeRegistry::builtin())),
+            selected: 0,
+            top: 0,
+            height: 1,
+            mode: Mode::Normal,
+            query: String::new(),
+            search_origin: 0,
+            level: 0,
+            log_type: None,
+            seen_types: BTreeSet::new(),
+            message: None,
+            quit: false,
+        }
+    }
+
+    /// 事件循环：绘制界面，等待并处理按键
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 绘制日志列表和状态栏
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        self.height = usize::from(list.height).max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + self.height {
+            self.top = self.selected + 1 - self.height;
+        }
+
+        let mut lines = Vec::with_capacity(self.height);
+        for (index, row) in (self.top..).zip(self.window.rows(self.top, self.height)) {
+            self.seen_types.insert(row.log.log_type);
+            let text = row.log.format_with(&self.formatter).replace('\n', " ⏎ ");
+            let mut style = level_style(row.log.level());
+            if index == self.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            lines.push(Line::styled(text, style));
+        }
+        frame.render_widget(Paragraph::new(lines), list);
+        frame.render_widget(
+            Paragraph::new(self.status_line()).style(Style::new().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+
+    /// 状态栏文本：搜索时为搜索词，否则为选中日志的位置、行号和过滤条件
+    fn status_line(&mut self) -> String {
+        if self.mode == Mode::Search {
+            return format!("/{}", self.query);
+        }
+        let source = self
+            .window
+            .get(self.selected)
+            .map(|row| (row.file, row.offset));
+        let mut status = match source {
+            Some((file, offset)) => format!("{}:{}", self.window.file_name(file), offset),
+            None => format!("{}: 没有日志", self.title),
+        };
+        let total = match self.window.total() {
+            Some(total) => total.to_string(),
+            None => format!("{}+", self.window.loaded()),
+        };
+        status.push_str(&format!("  {}/{}", self.selected + 1, total));
+        if let Some(level) = LEVEL_CYCLE[self.level] {
+            status.push_str(&format!("  级别≥{}", level.as_str()));
+        }
+        if let Some(log_type) = self.log_type {
+            status.push_str(&format!("  类型 {}", log_type));
+        }
+        let errors = self.window.errors().count();
+        if errors > 0 {
+            status.push_str(&format!("  {} 个文件无法读取", errors));
+        }
+        if let Some(message) = &self.message {
+            status.push_str("  ");
+            status.push_str(message);
+        }
+        status
+    }
+
+    /// 处理一次按键
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.message = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match self.mode {
+            Mode::Normal => self.handle_normal_key(key.code),
+            Mode::Search => self.handle_search_key(key.code),
+        }
+    }
+
+    /// 浏览模式的按键
+    fn handle_normal_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('j') | KeyCode::Down => self.select(self.selected + 1),
+            KeyCode::Char('k') | KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.select(self.selected + self.height),
+            KeyCode::PageUp | KeyCode::Char('b') => {
+                self.select(self.selected.saturating_sub(self.height))
+            }
+            KeyCode::Char('g') | KeyCode::Home => self.select(0),
+            KeyCode::Char('G') | KeyCode::End => {
+                if let Some(last) = self.window.last() {
+                    self.select(last);
+                }
+            }
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search;
+                self.query.clear();
+                self.search_origin = self.selected;
+            }
+            KeyCode::Char('n') => self.search_next(true),
+            KeyCode::Char('N') => self.search_next(false),
+            KeyCode::Char('f') => {
+                self.level = (self.level + 1) % LEVEL_CYCLE.len();
+                self.apply_filter();
+            }
+            KeyCode::Char('t') => {
+                self.log_type = match self.log_type {
+                    None => self.seen_types.first().copied(),
+                    Some(current) => self.seen_types.range(current + 1..).next().copied(),
+                };
+                self.apply_filter();
+            }
+            _ => {}
+        }
+    }
+
+    /// 搜索模式的按键：每次修改搜索词后从起点重新查找
+    fn handle_search_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Enter => self.mode = Mode::Normal,
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                self.query.clear();
+                self.selected = self.search_origin;
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.search_from(self.search_origin, true);
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.search_from(self.search_origin, true);
+            }
+            _ => {}
+        }
+    }
+
+    /// 选中第 `index` 行，超出总行数时选中最后一行
+    fn select(&mut self, index: usize) {
+        self.selected = match self.window.get(index) {
+            Some(_) => index,
+            None => self
+                .window
+                .total()
+                .and_then(|total| total.checked_sub(1))
+                .unwrap_or(0),
+        };
+    }
+
+    /// 从选中行的下一行（或上一行）开始查找搜索词
+    fn search_next(&mut self, forward: bool) {
+        if self.query.is_empty() {
+            return;
+        }
+        let from = if forward {
+            self.selected + 1
+        } else if let Some(from) = self.selected.checked_sub(1) {
+            from
+        } else {
+            self.message = Some(format!("未找到: {}", self.query));
+            return;
+        };
+        self.search_from(from, forward);
+    }
+
+    /// 从第 `from` 行开始查找搜索词，找到时选中该行
+    fn search_from(&mut self, from: usize, forward: bool) {
+        if self.query.is_empty() {
+            self.selected = from;
+            return;
+        }
+        let query = self.query.to_lowercase();
+        match self
+            .window
+            .find(from, forward, |row| matches_query(&row.log, &query))
+        {
+            Some(index) => self.selected = index,
+            None => self.message = Some(format!("未找到: {}", self.query)),
+        }
+    }
+
+    /// 按当前的级别和类型更新过滤条件，尽量停留在原来选中的日志附近
+    fn apply_filter(&mut self) {
+        let current = self
+            .window
+            .get(self.selected)
+            .map(|row| (row.file, row.offset));
+        let mut filter = LogFilter::new();
+        if let Some(level) = LEVEL_CYCLE[self.level] {
+            filter = filter.min_level(level);
+        }
+        if let Some(log_type) = self.log_type {
+            filter = filter.types([log_type]);
+        }
+        self.window.set_filter(filter);
+        self.top = 0;
+        self.selected = current
+            .and_then(|current| {
+                self.window
+                    .find(0, true, |row| (row.file, row.offset) >= current)
+            })
+            .unwrap_or(0);
+        self.select(self.selected);
+    }
+}
+
+/// 标签或内容是否包含搜索词（`query` 已转为小写）
+fn matches_query(log: &Log, query: &str) -> bool {
+    log.tag.to_lowercase().contains(query) || log.msg.to_lowercase().contains(query)
+}
+
+/// 日志级别对应的颜色
+fn level_style(level: Level) -> Style {
+    let color = match level {
+        Level::Error => Color::Red,
+        Level::Warn => Color::Yellow,
+        Level::Info => Color::Green,
+        Level::Debug => Color::Cyan,
+        Level::Verbose => Color::DarkGray,
+    };
+    Style::new().fg(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clog_reader::reader::CompressMode;
+    use clog_reader::writer::v3::GlogWriterV3;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use tempfile::TempDir;
+
+    /// 10 条日志：第 `i` 条的类型为 `i % 2`，级别每 5 条一个 Error，内容为 `msg {i}`，第 7 条标签为 `Net`
+    fn viewer(dir: &TempDir) -> Viewer {
+        let path = dir.path().join("async-20240101.glog");
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        for i in 0..10 {
+            writer
+                .write_log(&Log {
+                    log_type: i % 2,
+                    log_level: if i % 5 == 0 { 4 } else { 0 },
+                    tag: if i == 7 { "Net" } else { "Ui" }.to_string(),
+                    msg: format!("msg {}", i),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+        Viewer::new(LogWindow::new(vec![path], None), "logs.zip")
+    }
+
+    fn press(viewer: &mut Viewer, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                c => KeyCode::Char(c),
+            };
+            viewer.handle_key(KeyEvent::from(code));
+        }
+    }
+
+    fn selected_msg(viewer: &mut Viewer) -> String {
+        viewer.window.get(viewer.selected).unwrap().log.msg.clone()
+    }
+
+    #[test]
+    fn test_navigation_search_and_filters() {
+        let dir = TempDir::new().unwrap();
+        let mut viewer = viewer(&dir);
+        press(&mut viewer, "jjj");
+        assert_eq!(selected_msg(&mut viewer), "msg 3");
+        press(&mut viewer, "G");
+        assert_eq!(selected_msg(&mut viewer), "msg 9");
+        press(&mut viewer, "jg");
+        assert_eq!(viewer.selected, 0);
+
+        // 增量搜索不区分大小写，同时匹配标签
+        press(&mut viewer, "/NET\n");
+        assert_eq!(selected_msg(&mut viewer), "msg 7");
+        press(&mut viewer, "n");
+        assert!(viewer.message.as_deref().unwrap().starts_with("未找到"));
+        assert_eq!(selected_msg(&mut viewer), "msg 7");
+        press(&mut viewer, "g/msg 4\n");
+        assert_eq!(selected_msg(&mut viewer), "msg 4");
+
+        // 只看 Error 时停留在原位置之后的第一条
+        press(&mut viewer, "ffff");
+        assert_eq!(selected_msg(&mut viewer), "msg 5");
+        press(&mut viewer, "G");
+        assert_eq!(viewer.selected, 1);
+        press(&mut viewer, "f");
+        assert_eq!(selected_msg(&mut viewer), "msg 5");
+    }
+
+    #[test]
+    fn test_status_bar_shows_source_and_filters() {
+        let dir = TempDir::new().unwrap();
+        let mut viewer = viewer(&dir);
+        let mut terminal = Terminal::new(TestBackend::new(80, 5)).unwrap();
+        terminal.draw(|frame| viewer.draw(frame)).unwrap();
+        let status: String = (0..80)
+            .map(|x| terminal.backend().buffer()[(x, 4)].symbol().to_string())
+            .collect();
+        assert!(status.starts_with("async-20240101.glog:"), "{}", status);
+        // 只读取了一屏（4 行）
+        assert!(status.contains("1/4+"), "{}", status);
+
+        // 类型在显示后才能切换
+        press(&mut viewer, "tj");
+        assert_eq!(selected_msg(&mut viewer), "msg 2");
+        let status = viewer.status_line();
+        assert!(
+            status.contains("  2/") && status.ends_with("类型 0"),
+            "{}",
+            status
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! # 惰性加载的日志窗口
+//!
+//! 查看器只在内存中保留当前屏幕附近的几千条日志：向后滚动时从读取器继续解码，
+//! 回到窗口之前的位置时从最近的检查点重新读取。检查点每 [`CHECKPOINT_INTERVAL`] 行记录一次
+//! 条目所在的文件和偏移，因此打开几 GB 的压缩包也不需要把全部日志读入内存。
+//!
+//! 压缩的日志在整个文件中共用一个 zlib 字典，只能从文件开头顺序解压；回到这类文件中的检查点时
+//! 重新打开文件，用 [`GlogReader::skip_entry`] 跳过之前的条目（不解码 protobuf）。
+//! 没有遇到过压缩条目的文件直接通过 [`GlogReaderBuilder::open_at`] 定位。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+
+use clog_reader::error::Result;
+use clog_reader::glog::{GlogReader, GlogReaderBuilder};
+use clog_reader::proto::{Log, LogFilter};
+
+/// 相邻检查点之间的行数
+pub const CHECKPOINT_INTERVAL: usize = 1024;
+
+/// 窗口中最多保留的行数，至少能容纳一个检查点间隔加上一屏
+const WINDOW_CAPACITY: usize = 4 * CHECKPOINT_INTERVAL;
+
+/// 一条已解码的日志及其位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// 来源文件的序号
+    pub file: usize,
+    /// 条目在文件中的起始偏移
+    pub offset: u64,
+    /// 日志内容
+    pub log: Log,
+}
+
+/// 条目在压缩包中的位置
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    /// 文件序号
+    file: usize,
+    /// 条目的起始偏移
+    offset: u64,
+}
+
+/// 按行号访问满足过滤条件的日志，只解码访问位置附近的部分
+pub struct LogWindow {
+    /// 按处理顺序排列的日志文件
+    files: Vec<PathBuf>,
+    /// 服务器私钥
+    key: Option<String>,
+    /// 当前的过滤条件
+    filter: LogFilter,
+    /// 各文件是否可以直接定位到条目（读到过压缩条目后为 `false`）
+    seekable: Vec<bool>,
+    /// 窗口中已解码的行
+    rows: VecDeque<Row>,
+    /// `rows` 第一行的行号
+    start: usize,
+    /// 第 `i` 个检查点是第 `i * CHECKPOINT_INTERVAL` 行的位置
+    checkpoints: Vec<Position>,
+    /// 读到末尾后得到的总行数
+    total: Option<usize>,
+    /// 正在读取的文件序号，等于文件数时已读完
+    file: usize,
+    /// 正在读取的文件的读取器，紧接在 `rows` 最后一行之后
+    reader: Option<GlogReader>,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+    /// 无法打开或读取中断的文件及原因
+    errors: BTreeMap<usize, String>,
+}
+
+impl LogWindow {
+    /// 创建窗口，此时还没有读取任何文件
+    ///
+    /// # Arguments
+    /// * `files` - 按处理顺序排列的日志文件
+    /// * `key` - 解密 V4 日志的服务器私钥
+    pub fn new(files: Vec<PathBuf>, key: Option<String>) -> Self {
+        Self {
+            seekable: vec![true; files.len()],
+            files,
+            key,
+            filter: LogFilter::new(),
+            rows: VecDeque::new(),
+            start: 0,
+            checkpoints: Vec::new(),
+            total: None,
+            file: 0,
+            reader: None,
+            buf: Vec::new(),
+            errors: BTreeMap::new(),
+        }
+    }
+
+    /// 日志文件的文件名（不含目录）
+    pub fn file_name(&self, file: usize) -> String {
+        self.files[file]
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.files[file].display().to_string())
+    }
+
+    /// 更换过滤条件，之前的行号全部失效，从第一个文件重新读取
+    pub fn set_filter(&mut self, filter: LogFilter) {
+        self.filter = filter;
+        self.rows.clear();
+        self.start = 0;
+        self.checkpoints.clear();
+        self.total = None;
+        self.file = 0;
+        self.reader = None;
+    }
+
+    /// 总行数，还没有读到末尾时为 `None`
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+
+    /// 已经读到的行数，总行数未知时作为下限
+    pub fn loaded(&self) -> usize {
+        self.start + self.rows.len()
+    }
+
+    /// 无法打开或读取中断的文件名及原因
+    pub fn errors(&self) -> impl Iterator<Item = (String, &str)> {
+        self.errors
+            .iter()
+            .map(|(file, error)| (self.file_name(*file), error.as_str()))
+    }
+
+    /// 第 `index` 行，超出总行数时为 `None`
+    pub fn get(&mut self, index: usize) -> Option<&Row> {
+        if !self.ensure(index) {
+            return None;
+        }
+        self.rows.get(index - self.start)
+    }
+
+    /// 从第 `first` 行开始的最多 `count` 行
+    pub fn rows(&mut self, first: usize, count: usize) -> impl Iterator<Item = &Row> {
+        let count = count.min(WINDOW_CAPACITY - CHECKPOINT_INTERVAL);
+        let available = if count > 0 && self.ensure(first) {
+            self.ensure(first + count - 1);
+            count
+        } else {
+            0
+        };
+        let skip = first.saturating_sub(self.start);
+        self.rows.iter().skip(skip).take(available)
+    }
+
+    /// 读到末尾，返回最后一行的行号，没有任何日志时为 `None`
+    pub fn last(&mut self) -> Option<usize> {
+        while self.total.is_none() {
+            self.read_next();
+        }
+        self.total.and_then(|total| total.checked_sub(1))
+    }
+
+    /// 从第 `from` 行开始（包含）查找满足条件的行
+    ///
+    /// # Arguments
+    /// * `from` - 起始行号
+    /// * `forward` - 向后查找，否则向前查找
+    /// * `matches` - 查找条件
+    ///
+    /// # Returns
+    /// 返回第一个满足条件的行号
+    pub fn find(
+        &mut self,
+        from: usize,
+        forward: bool,
+        matches: impl Fn(&Row) -> bool,
+    ) -> Option<usize> {
+        let mut index = from;
+        loop {
+            if matches(self.get(index)?) {
+                return Some(index);
+            }
+            index = if forward {
+                index + 1
+            } else {
+                index.checked_sub(1)?
+            };
+        }
+    }
+
+    /// 确保第 `index` 行在窗口中
+    ///
+    /// # Returns
+    /// 该行存在时返回 `true`
+    fn ensure(&mut self, index: usize) -> bool {
+        if index < self.start {
+            self.seek(index / CHECKPOINT_INTERVAL);
+        }
+        while self.loaded() <= index {
+            if !self.read_next() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 清空窗口，从第 `checkpoint` 个检查点重新读取
+    fn seek(&mut self, checkpoint: usize) {
+        let position = self.checkpoints[checkpoint];
+        self.rows.clear();
+        self.start = checkpoint * CHECKPOINT_INTERVAL;
+        self.file = position.file;
+        self.reader = match self.open_at(position) {
+            Ok(reader) => Some(reader),
+            Err(e) => {
+                // 之前能读到这里，通常是文件在查看期间被删除
+                self.errors.insert(position.file, e.to_string());
+                None
+            }
+        };
+    }
+
+    /// 打开文件并定位到 `position` 处的条目
+    fn open_at(&mut self, position: Position) -> Result<GlogReader> {
+        let path = self.files[position.file].to_string_lossy().into_owned();
+        if self.seekable[position.file] {
+            // V1/V2 没有同步标记，不支持定位，改为顺序跳过
+            if let Ok(reader) = self.builder().open_at(&path, position.offset) {
+                return Ok(reader);
+            }
+        }
+        let mut reader = self.builder().open(&path)?;
+        let mut scratch = Vec::new();
+        while reader.position() < position.offset {
+            if reader.skip_entry(&mut scratch)?.is_none() {
+                break;
+            }
+        }
+        Ok(reader)
+    }
+
+    /// 读取器的配置
+    fn builder(&self) -> GlogReaderBuilder {
+        GlogReader::builder().keys(self.key.clone())
+    }
+
+    /// 读取下一条满足过滤条件的日志并加入窗口
+    ///
+    /// # Returns
+    /// 读到末尾时返回 `false`
+    fn read_next(&mut self) -> bool {
+        while self.file < self.files.len() {
+            if self.reader.is_none() {
+                let path = self.files[self.file].to_string_lossy().into_owned();
+                match self.builder().open(&path) {
+                    Ok(reader) => self.reader = Some(reader),
+                    Err(e) => {
+                        self.errors.insert(self.file, e.to_string());
+                        self.file += 1;
+                        continue;
+                    }
+                }
+            }
+            match self.next_row() {
+                Ok(Some(row)) => {
+                    self.push(row);
+                    return true;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    self.errors.insert(self.file, e.to_string());
+                }
+            }
+            self.reader = None;
+            self.file += 1;
+        }
+        self.total = Some(self.loaded());
+        false
+    }
+
+    /// 从当前文件读取下一条满足过滤条件的日志，解码失败的条目被跳过
+    fn next_row(&mut self) -> Result<Option<Row>> {
+        let reader = self.reader.as_mut().expect("调用前已打开读取器");
+        while let Some(entry) = reader.read_entry(&mut self.buf)? {
+            if entry.compressed {
+                self.seekable[self.file] = false;
+            }
+            if self.buf.is_empty() || reader.rejects_by_type(&self.buf, &self.filter) {
+                continue;
+            }
+            let Ok(log) = reader.decode_log(&self.buf) else {
+                continue;
+            };
+            if self.filter.matches(&log) {
+                return Ok(Some(Row {
+                    file: self.file,
+                    offset: entry.offset,
+                    log,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 把新读到的行加入窗口末尾，必要时记录检查点并丢弃最早的行
+    #[allow(clippy::manual_is_multiple_of)]
+    fn push(&mut self, row: Row) {
+        let index = self.loaded();
+        if index % CHECKPOINT_INTERVAL == 0
+            && index / CHECKPOINT_INTERVAL == self.checkpoints.len()
+        {
+            self.checkpoints.push(Position {
+                file: row.file,
+                offset: row.offset,
+            });
+        }
+        self.rows.push_back(row);
+        if self.rows.len() > WINDOW_CAPACITY {
+            self.rows.pop_front();
+            self.start += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clog_reader::proto::Level;
+    use clog_reader::reader::CompressMode;
+    use clog_reader::writer::v3::GlogWriterV3;
+    use tempfile::TempDir;
+
+    /// 写入 `count` 条日志，第 `i` 条的内容为 `{prefix} {i}`，每 10 条中有一条 Error
+    fn write_file(
+        dir: &TempDir,
+        name: &str,
+        mode: CompressMode,
+        prefix: &str,
+        count: usize,
+    ) -> PathBuf {
+        let path = dir.path().join(name);
+        let mut writer = GlogWriterV3::new(Vec::new(), mode, "Log").unwrap();
+        for i in 0..count {
+            writer
+                .write_log(&Log {
+                    log_level: if i % 10 == 0 { 4 } else { 0 },
+                    tag: "Tag".to_string(),
+                    msg: format!("{} {}", prefix, i),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        std::fs::write(&path, writer.into_inner().unwrap()).unwrap();
+        path
+    }
+
+    fn msg(window: &mut LogWindow, index: usize) -> String {
+        window.get(index).unwrap().log.msg.clone()
+    }
+
+    #[test]
+    fn test_rows_span_files_and_seek_back_across_checkpoints() {
+        let dir = TempDir::new().unwrap();
+        let count = 3 * WINDOW_CAPACITY;
+        let files = vec![
+            write_file(&dir, "a.glog", CompressMode::Zlib, "a", count),
+            write_file(&dir, "b.glog", CompressMode::None, "b", count),
+        ];
+        let mut window = LogWindow::new(files, None);
+
+        assert_eq!(msg(&mut window, 0), "a 0");
+        assert_eq!(window.total(), None);
+        assert_eq!(msg(&mut window, count + 5), "b 5");
+        // 早已移出窗口的行从检查点重新读取，压缩文件从头跳过，未压缩文件直接定位
+        assert!(window.start > CHECKPOINT_INTERVAL + 3);
+        assert_eq!(msg(&mut window, CHECKPOINT_INTERVAL + 3), "a 1027");
+        assert_eq!(msg(&mut window, 2 * count - 1), format!("b {}", count - 1));
+        assert_eq!(msg(&mut window, count + 2), "b 2");
+        assert_eq!(window.get(count + 2).unwrap().file, 1);
+        assert!(window.rows.len() <= WINDOW_CAPACITY);
+
+        assert_eq!(window.last(), Some(2 * count - 1));
+        assert!(window.get(2 * count).is_none());
+        assert_eq!(window.total(), Some(2 * count));
+        let page: Vec<String> = window.rows(10, 3).map(|row| row.log.msg.clone()).collect();
+        assert_eq!(page, ["a 10", "a 11", "a 12"]);
+    }
+
+    #[test]
+    fn test_filter_and_find() {
+        let dir = TempDir::new().unwrap();
+        let files = vec![write_file(&dir, "a.glog", CompressMode::Zlib, "a", 100)];
+        let mut window = LogWindow::new(files, None);
+        assert_eq!(window.find(0, true, |row| row.log.msg == "a 42"), Some(42));
+        assert_eq!(window.find(42, false, |row| row.log.msg == "a 7"), Some(7));
+        assert_eq!(window.find(0, true, |row| row.log.msg == "none"), None);
+        assert_eq!(window.total(), Some(100));
+
+        window.set_filter(LogFilter::new().min_level(Level::Error));
+        assert_eq!(window.total(), None);
+        assert_eq!(msg(&mut window, 3), "a 30");
+        assert_eq!(window.last(), Some(9));
+    }
+
+    #[test]
+    fn test_unreadable_file_is_reported_and_skipped() {
+        let dir = TempDir::new().unwrap();
+        let bad = dir.path().join("bad.glog");
+        std::fs::write(&bad, b"not a glog file").unwrap();
+        let files = vec![bad, write_file(&dir, "b.glog", CompressMode::None, "b", 2)];
+        let mut window = LogWindow::new(files, None);
+
+        assert_eq!(msg(&mut window, 0), "b 0");
+        assert_eq!(window.last(), Some(1));
+        let errors: Vec<(String, &str)> = window.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "bad.glog");
+    }
+}
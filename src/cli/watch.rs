@@ -0,0 +1,308 @@
+//! # 监视目录
+//!
+//! `watch` 子命令监视一个目录（不含子目录），新出现的 `.zip` / `.glog` 文件停止增长后逐个解码到输出目录。
+//! 处理过的文件按名称、大小和修改时间记录在状态文件中，重启后不会重复处理；
+//! 处理后又发生变化的文件（如写入端续写）会重新处理。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use super::OutputFormat;
+
+/// 默认状态文件名，位于输出目录下
+pub const DEFAULT_STATE_FILE: &str = ".clog-reader-watch.json";
+
+/// 等待文件系统事件的超时时间，也是检查文件是否停止增长的间隔
+pub const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 监视的文件扩展名（不区分大小写）
+const WATCHED_EXTENSIONS: [&str; 2] = ["zip", "glog"];
+
+/// 是否是需要处理的文件（`.zip` 或 `.glog`）
+pub fn is_watched(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            WATCHED_EXTENSIONS
+                .iter()
+                .any(|watched| ext.eq_ignore_ascii_case(watched))
+        })
+}
+
+/// 输入文件对应的输出文件名：文件名去掉扩展名，再按输出格式加上扩展名
+///
+/// # Arguments
+/// * `path` - 输入文件路径
+/// * `format` - 输出格式
+pub fn output_name(path: &Path, format: OutputFormat) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = match format {
+        OutputFormat::Text | OutputFormat::Logfmt => "txt",
+        OutputFormat::EsBulk => "ndjson",
+        OutputFormat::TraceJson => "json",
+        OutputFormat::Raw => "bin",
+        OutputFormat::Sqlite => "db",
+    };
+    format!("{}.{}", stem, extension)
+}
+
+/// 等待文件系统事件时的错误
+#[derive(Debug, thiserror::Error)]
+pub enum WatchError {
+    /// 单个事件出错，等待一个检查间隔后可以继续监视
+    #[error("目录监视出错: {0}")]
+    Event(#[from] notify::Error),
+    /// 监视器已停止，不会再收到任何事件
+    #[error("目录监视器已停止")]
+    Disconnected,
+}
+
+/// 文件的大小和修改时间，用于判断文件在处理后是否发生了变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 毫秒）
+    pub modified: u64,
+}
+
+impl FileStamp {
+    /// 读取文件当前的大小和修改时间
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Ok(Self {
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// 已处理文件的记录，以 JSON 保存在状态文件中
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    /// 文件名 → 处理时的大小和修改时间
+    processed: BTreeMap<String, FileStamp>,
+}
+
+impl WatchState {
+    /// 读取状态文件，文件不存在时返回空记录
+    ///
+    /// # Errors
+    /// 状态文件无法读取或格式错误时返回错误
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => {
+                serde_json::from_str(&json).context(format!("解析状态文件失败: {}", path.display()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).context(format!("读取状态文件失败: {}", path.display())),
+        }
+    }
+
+    /// 保存状态文件：先写入同目录下的临时文件再重命名，中途退出不会留下不完整的记录
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(self)?)
+            .context(format!("写入状态文件失败: {}", temp.display()))?;
+        fs::rename(&temp, path).context(format!("写入状态文件失败: {}", path.display()))
+    }
+
+    /// 文件是否已经以相同的大小和修改时间处理过
+    pub fn is_processed(&self, name: &str, stamp: FileStamp) -> bool {
+        self.processed.get(name) == Some(&stamp)
+    }
+
+    /// 记录处理过的文件
+    pub fn mark(&mut self, name: String, stamp: FileStamp) {
+        self.processed.insert(name, stamp);
+    }
+}
+
+/// 等待停止增长的文件：大小在 `settle` 时间内保持不变才视为写入完成
+#[derive(Debug, Default)]
+struct PendingFiles {
+    /// 文件路径 → 最近一次观察到的大小和大小开始保持不变的时刻
+    files: HashMap<PathBuf, (u64, Instant)>,
+}
+
+impl PendingFiles {
+    /// 文件出现或发生变化，重新开始计时
+    fn touch(&mut self, path: PathBuf) {
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        self.files.insert(path, (size, Instant::now()));
+    }
+
+    /// 取出已经停止增长的文件（按路径排序），已被删除的文件直接丢弃
+    fn take_settled(&mut self, settle: Duration) -> Vec<PathBuf> {
+        let now = Instant::now();
+        let mut settled = Vec::new();
+        self.files.retain(|path, (size, since)| {
+            let Ok(metadata) = fs::metadata(path) else {
+                return false;
+            };
+            if metadata.len() != *size {
+                *size = metadata.len();
+                *since = now;
+                return true;
+            }
+            if now.duration_since(*since) < settle {
+                return true;
+            }
+            settled.push(path.clone());
+            false
+        });
+        settled.sort();
+        settled
+    }
+}
+
+/// 基于 notify 的目录监视器
+pub struct DirWatcher {
+    /// 监视器，丢弃后停止接收事件
+    _watcher: RecommendedWatcher,
+    /// 文件系统事件
+    events: Receiver<notify::Result<Event>>,
+    /// 等待停止增长的文件
+    pending: PendingFiles,
+    /// 文件大小保持不变多久后视为写入完成
+    settle: Duration,
+}
+
+impl DirWatcher {
+    /// 开始监视目录，目录中已有的文件也作为待处理文件
+    ///
+    /// # Arguments
+    /// * `dir` - 监视的目录
+    /// * `settle` - 文件大小保持不变多久后视为写入完成
+    ///
+    /// # Errors
+    /// 目录无法读取或无法监视时返回错误
+    pub fn new(dir: &Path, settle: Duration) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).context("创建目录监视器失败")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .context(format!("无法监视目录: {}", dir.display()))?;
+
+        // 先开始监视再扫描，扫描期间新出现的文件不会遗漏
+        let mut pending = PendingFiles::default();
+        for entry in fs::read_dir(dir).context(format!("读取目录失败: {}", dir.display()))? {
+            let path = entry?.path();
+            if path.is_file() && is_watched(&path) {
+                pending.touch(path);
+            }
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            pending,
+            settle,
+        })
+    }
+
+    /// 等待一个检查间隔，返回其间停止增长的文件
+    ///
+    /// # Errors
+    /// 监视器报告错误时返回 [`WatchError::Event`]，之后可以继续调用；
+    /// 监视器已停止时返回 [`WatchError::Disconnected`]，不应再继续调用
+    pub fn poll(&mut self) -> Result<Vec<PathBuf>, WatchError> {
+        match self.events.recv_timeout(POLL_INTERVAL) {
+            Ok(event) => {
+                let event = event?;
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in event.paths.into_iter().filter(|path| is_watched(path)) {
+                        self.pending.touch(path);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(WatchError::Disconnected),
+        }
+        Ok(self.pending.take_settled(self.settle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_watched_files_and_output_names() {
+        assert!(is_watched(Path::new("/in/logs.ZIP")));
+        assert!(is_watched(Path::new("async-20240101.glog")));
+        assert!(!is_watched(Path::new("async-20240101.glogmmap")));
+        assert!(!is_watched(Path::new("logs.zip.part")));
+
+        let path = Path::new("/in/logs.zip");
+        assert_eq!(output_name(path, OutputFormat::Text), "logs.txt");
+        assert_eq!(output_name(path, OutputFormat::EsBulk), "logs.ndjson");
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(DEFAULT_STATE_FILE);
+        let stamp = FileStamp {
+            size: 10,
+            modified: 1000,
+        };
+        let mut state = WatchState::load(&path).unwrap();
+        assert!(!state.is_processed("logs.zip", stamp));
+        state.mark("logs.zip".to_string(), stamp);
+        state.save(&path).unwrap();
+
+        let state = WatchState::load(&path).unwrap();
+        assert!(state.is_processed("logs.zip", stamp));
+        // 处理后文件又被改写
+        assert!(!state.is_processed("logs.zip", FileStamp { size: 20, ..stamp }));
+    }
+
+    #[test]
+    fn test_pending_files_wait_until_size_is_stable() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("logs.zip");
+        fs::write(&path, b"PK").unwrap();
+        let mut pending = PendingFiles::default();
+        pending.touch(path.clone());
+        assert!(pending.take_settled(Duration::from_secs(60)).is_empty());
+
+        // 文件仍在增长，重新计时
+        fs::write(&path, b"PK\x03\x04").unwrap();
+        assert!(pending.take_settled(Duration::ZERO).is_empty());
+        assert_eq!(pending.take_settled(Duration::ZERO), vec![path.clone()]);
+        assert!(pending.take_settled(Duration::ZERO).is_empty());
+
+        // 被删除的文件不再等待
+        pending.touch(path.clone());
+        fs::remove_file(&path).unwrap();
+        assert!(pending.take_settled(Duration::ZERO).is_empty());
+        assert!(pending.files.is_empty());
+    }
+
+    #[test]
+    fn test_poll_reports_disconnected_watcher() {
+        let dir = TempDir::new().unwrap();
+        let mut watcher = DirWatcher::new(dir.path(), Duration::ZERO).unwrap();
+        // 发送端全部丢弃，相当于监视器已停止
+        let (_, events) = mpsc::channel();
+        watcher.events = events;
+        assert!(matches!(watcher.poll(), Err(WatchError::Disconnected)));
+    }
+}
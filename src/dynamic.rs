@@ -0,0 +1,296 @@
+//! # 动态 Protobuf 解码
+//!
+//! 文件头记录了日志使用的 protobuf 消息名称。部分产品写入的消息结构与内置的 [`Log`] 不同，
+//! 此时可以提供编译好的 `FileDescriptorSet`（`protoc --descriptor_set_out`），
+//! 按文件头中的消息名称查找消息类型，用 `prost-reflect` 动态解码。
+//!
+//! 解码结果可以通用地渲染为 `字段名=值` 形式（[`render`]），
+//! 也可以按约定的字段编号映射到 [`Log`]（[`to_log`]）。
+//! 描述符中找不到文件头中的消息名称时不启用动态解码，仍按 [`Log`] 解码。
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use prost_reflect::{DescriptorPool, DynamicMessage, Kind, MapKey, MessageDescriptor, Value};
+
+use crate::error::Result;
+use crate::proto::Log;
+
+/// `FileDescriptorSet` 的来源
+#[derive(Debug, Clone)]
+pub enum DescriptorSet {
+    /// 已读入内存的描述符内容
+    Bytes(Vec<u8>),
+    /// 描述符文件路径，打开读取器时读取
+    Path(PathBuf),
+}
+
+impl DescriptorSet {
+    /// 读取描述符内容
+    fn load(&self) -> Result<Vec<u8>> {
+        match self {
+            DescriptorSet::Bytes(bytes) => Ok(bytes.clone()),
+            DescriptorSet::Path(path) => Ok(std::fs::read(path)?),
+        }
+    }
+}
+
+/// 按描述符动态解码日志条目
+#[derive(Debug, Clone)]
+pub struct DynamicDecoder {
+    /// 文件头中记录的消息类型
+    message: MessageDescriptor,
+}
+
+impl DynamicDecoder {
+    /// 在描述符中查找文件头记录的消息类型
+    ///
+    /// 先按完整名称（`package.Message`）查找，找不到时按不带包名的消息名称查找
+    ///
+    /// # Arguments
+    /// * `descriptor_set` - 描述符来源
+    /// * `proto_name` - 文件头中的消息名称
+    ///
+    /// # Returns
+    /// 找到消息类型时返回解码器，否则返回 `None`
+    ///
+    /// # Errors
+    /// 描述符无法读取或不是有效的 `FileDescriptorSet` 时返回错误
+    pub fn new(descriptor_set: &DescriptorSet, proto_name: &str) -> Result<Option<Self>> {
+        let pool = DescriptorPool::decode(descriptor_set.load()?.as_slice())?;
+        if proto_name.is_empty() {
+            return Ok(None);
+        }
+        let message = pool
+            .get_message_by_name(proto_name)
+            .or_else(|| pool.all_messages().find(|m| m.name() == proto_name));
+        Ok(message.map(|message| Self { message }))
+    }
+
+    /// 消息类型的完整名称
+    pub fn message_name(&self) -> &str {
+        self.message.full_name()
+    }
+
+    /// 解码一条日志条目
+    ///
+    /// # Arguments
+    /// * `buf` - protobuf 编码的条目数据
+    ///
+    /// # Errors
+    /// 数据与消息类型不符时返回 `GlogError::ProtobufError`
+    pub fn decode(&self, buf: &[u8]) -> Result<DynamicMessage> {
+        Ok(DynamicMessage::decode(self.message.clone(), buf)?)
+    }
+
+    /// 解码一条日志条目并映射到 [`Log`]，参见 [`to_log`]
+    ///
+    /// # Arguments
+    /// * `buf` - protobuf 编码的条目数据
+    pub fn decode_log(&self, buf: &[u8]) -> Result<Log> {
+        Ok(to_log(&self.decode(buf)?))
+    }
+}
+
+/// 按约定的字段编号把动态消息映射到 [`Log`]
+///
+/// 字段编号 1~7 与 [`Log`] 相同（类型、时间戳、级别、进程 ID、线程 ID、标签、内容），
+/// 整数和字符串之间按需转换。编号不在其中或类型无法转换的字段以 `字段名=值` 的形式追加到 `msg` 之后
+///
+/// # Arguments
+/// * `message` - 动态解码的消息
+pub fn to_log(message: &DynamicMessage) -> Log {
+    let mut log = Log::new();
+    let mut extra = Vec::new();
+    for (field, value) in message.fields() {
+        let mapped = match field.number() {
+            1 => as_i32(value).map(|v| log.log_type = v),
+            2 => as_string(value).map(|v| log.timestamp = v),
+            3 => as_i32(value).map(|v| log.log_level = v),
+            4 => as_i32(value).map(|v| log.pid = v),
+            5 => as_string(value).map(|v| log.tid = v),
+            6 => as_string(value).map(|v| log.tag = v),
+            7 => as_string(value).map(|v| log.msg = v),
+            _ => None,
+        };
+        if mapped.is_none() {
+            extra.push(format!("{}={}", field.name(), format_value(&field.kind(), value)));
+        }
+    }
+    if !extra.is_empty() {
+        if !log.msg.is_empty() {
+            log.msg.push(' ');
+        }
+        log.msg.push_str(&extra.join(" "));
+    }
+    log
+}
+
+/// 把动态消息渲染为 `字段名=值` 形式，字段之间以空格分隔
+///
+/// 字符串带引号，字节串为十六进制，枚举显示名称，嵌套消息放在 `{}` 中
+///
+/// # Arguments
+/// * `message` - 动态解码的消息
+pub fn render(message: &DynamicMessage) -> String {
+    let mut out = String::new();
+    for (field, value) in message.fields() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        let _ = write!(out, "{}={}", field.name(), format_value(&field.kind(), value));
+    }
+    out
+}
+
+/// 把字段值转换为 i32，无法表示时返回 `None`
+fn as_i32(value: &Value) -> Option<i32> {
+    match value {
+        Value::I32(v) | Value::EnumNumber(v) => Some(*v),
+        Value::I64(v) => i32::try_from(*v).ok(),
+        Value::U32(v) => i32::try_from(*v).ok(),
+        Value::U64(v) => i32::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// 把字符串或整数字段值转换为字符串
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(v) => Some(v.clone()),
+        Value::I32(v) => Some(v.to_string()),
+        Value::I64(v) => Some(v.to_string()),
+        Value::U32(v) => Some(v.to_string()),
+        Value::U64(v) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+/// 格式化字段值
+///
+/// # Arguments
+/// * `kind` - 字段类型，用于查找枚举名称；列表和映射按元素类型格式化
+/// * `value` - 字段值
+fn format_value(kind: &Kind, value: &Value) -> String {
+    match value {
+        Value::Bool(v) => v.to_string(),
+        Value::I32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::F32(v) => v.to_string(),
+        Value::F64(v) => v.to_string(),
+        Value::String(v) => format!("{:?}", v),
+        Value::Bytes(v) => hex::encode(v),
+        Value::EnumNumber(v) => kind
+            .as_enum()
+            .and_then(|e| e.get_value(*v))
+            .map_or_else(|| v.to_string(), |e| e.name().to_string()),
+        Value::Message(m) => format!("{{{}}}", render(m)),
+        Value::List(items) => {
+            let items: Vec<_> = items.iter().map(|v| format_value(kind, v)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Map(entries) => {
+            let value_kind = kind
+                .as_message()
+                .map_or_else(|| kind.clone(), |entry| entry.map_entry_value_field().kind());
+            let mut entries: Vec<_> = entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", format_key(k), format_value(&value_kind, v)))
+                .collect();
+            entries.sort();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+/// 格式化映射的键
+fn format_key(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(v) => v.to_string(),
+        MapKey::I32(v) => v.to_string(),
+        MapKey::I64(v) => v.to_string(),
+        MapKey::U32(v) => v.to_string(),
+        MapKey::U64(v) => v.to_string(),
+        MapKey::String(v) => format!("{:?}", v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    /// 构造包含 `demo.Event` 消息的描述符：
+    /// `int32 log_type = 1; string tag = 6; string body = 7; uint32 retries = 9; bytes trace = 10;`
+    fn descriptor_set() -> DescriptorSet {
+        let field = |name: &str, number: i32, ty: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(ty as i32),
+            ..Default::default()
+        };
+        let set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("demo.proto".to_string()),
+                package: Some("demo".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Event".to_string()),
+                    field: vec![
+                        field("log_type", 1, Type::Int32),
+                        field("tag", 6, Type::String),
+                        field("body", 7, Type::String),
+                        field("retries", 9, Type::Uint32),
+                        field("trace", 10, Type::Bytes),
+                    ],
+                    ..Default::default()
+                }],
+                syntax: Some("proto3".to_string()),
+                ..Default::default()
+            }],
+        };
+        DescriptorSet::Bytes(set.encode_to_vec())
+    }
+
+    /// 按 `demo.Event` 编码一条消息
+    fn event(decoder: &DynamicDecoder) -> Vec<u8> {
+        let mut message = DynamicMessage::new(decoder.message.clone());
+        message.set_field_by_name("log_type", Value::I32(3));
+        message.set_field_by_name("tag", Value::String("net".to_string()));
+        message.set_field_by_name("body", Value::String("timeout".to_string()));
+        message.set_field_by_name("retries", Value::U32(2));
+        message.set_field_by_name("trace", Value::Bytes(vec![0xAB, 0xCD].into()));
+        message.encode_to_vec()
+    }
+
+    #[test]
+    fn test_finds_message_by_full_or_short_name() {
+        let set = descriptor_set();
+        let decoder = DynamicDecoder::new(&set, "Event").unwrap().unwrap();
+        assert_eq!(decoder.message_name(), "demo.Event");
+        assert!(DynamicDecoder::new(&set, "demo.Event").unwrap().is_some());
+        assert!(DynamicDecoder::new(&set, "Log").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_render_and_map_onto_log() {
+        let decoder = DynamicDecoder::new(&descriptor_set(), "Event").unwrap().unwrap();
+        let message = decoder.decode(&event(&decoder)).unwrap();
+
+        assert_eq!(
+            render(&message),
+            r#"log_type=3 tag="net" body="timeout" retries=2 trace=abcd"#
+        );
+
+        let log = to_log(&message);
+        assert_eq!(log.log_type, 3);
+        assert_eq!(log.tag, "net");
+        assert_eq!(log.msg, "timeout retries=2 trace=abcd");
+    }
+}
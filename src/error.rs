@@ -5,6 +5,8 @@
 
 use thiserror::Error;
 
+use crate::version::GLOG_RECOVERY_VERSION;
+
 /// Glog 读取器错误类型
 ///
 /// 定义了读取和解析 Glog 文件时可能遇到的各种错误情况
@@ -60,6 +62,11 @@ pub enum GlogError {
     #[error("解压缩失败: {0}")]
     DecompressError(String),
 
+    /// 压缩错误
+    /// 当写入日志时 zlib 压缩失败时返回此错误
+    #[error("压缩失败: {0}")]
+    CompressError(String),
+
     /// 解密错误
     /// 当 AES 解密失败时返回此错误
     #[error("解密失败: {0}")]
@@ -75,6 +82,16 @@ pub enum GlogError {
     #[error("无效的日志长度: {0}")]
     InvalidLogLength(usize),
 
+    /// 输出缓冲区不足错误
+    /// 当解压后的日志超过调用方提供的缓冲区时返回此错误
+    #[error("输出缓冲区不足: 日志需要 {required} 字节，但缓冲区只有 {available} 字节")]
+    Truncated {
+        /// 日志的实际长度
+        required: usize,
+        /// 缓冲区长度
+        available: usize,
+    },
+
     /// 公钥解压错误
     /// 当解压椭圆曲线公钥失败时返回此错误
     #[error("公钥解压失败: {0}")]
@@ -85,20 +102,290 @@ pub enum GlogError {
     #[error("Protobuf 解析错误: {0}")]
     ProtobufError(#[from] prost::DecodeError),
 
+    /// Protobuf 描述符错误
+    /// 当动态解码使用的 `FileDescriptorSet` 无效时返回此错误
+    #[cfg(feature = "dynamic")]
+    #[error("Protobuf 描述符无效: {0}")]
+    DescriptorError(#[from] prost_reflect::DescriptorError),
+
+    /// 日志类型映射错误
+    /// 当 `LogTypeRegistry` 加载的 TOML 映射无效时返回此错误
+    #[error("日志类型映射无效: {0}")]
+    TypeMapError(String),
+
+    /// 脱敏规则错误
+    /// 当 `Redactor` 的规则格式不正确或正则表达式无效时返回此错误
+    #[error("脱敏规则无效: {0}")]
+    RedactRuleError(String),
+
+    /// 索引文件错误
+    /// 当 `LogIndex` 读取的索引文件魔数或版本不匹配时返回此错误
+    #[error("索引文件无效: {0}")]
+    IndexError(String),
+
     /// ZIP 解压错误
     /// 当解压 ZIP 文件失败时返回此错误
+    #[cfg(feature = "std-fs")]
     #[error("ZIP 解压错误: {0}")]
     ZipError(#[from] zip::result::ZipError),
 
+    /// 解压大小超限错误
+    /// 当从 ZIP 压缩包解压的总字节数超过上限时返回此错误，用于防范 ZIP 炸弹
+    #[cfg(feature = "std-fs")]
+    #[error("解压后的总大小超过上限 {limit} 字节")]
+    ExtractLimitExceeded {
+        /// 解压总字节数的上限
+        limit: u64,
+    },
+
     /// 十六进制解析错误
     /// 当解析十六进制字符串失败时返回此错误
     #[error("十六进制解析错误: {0}")]
     HexError(#[from] hex::FromHexError),
 
+    /// 需要恢复错误
+    /// 恢复策略为 `Abort` 时遇到损坏条目返回此错误，包含条目损坏的原因
+    #[error("需要恢复: {0}")]
+    NeedRecover(RecoverReason),
+
+    /// 已取消错误
+    /// 读取器的取消标志被置位后，下一次读取返回此错误
+    #[error("读取已取消")]
+    Cancelled,
+
     /// 椭圆曲线错误
     /// 当椭圆曲线操作失败时返回此错误
     #[error("椭圆曲线错误: {0}")]
     EllipticCurveError(String),
+
+    /// 私钥曲线不匹配错误
+    /// 当 PEM / DER 格式的私钥不是 secp256k1 私钥时返回此错误，包含识别出的曲线或算法
+    #[error("私钥不是 secp256k1 私钥，而是 {0}")]
+    WrongCurve(String),
+
+    /// 带位置的错误
+    /// 读取日志条目时发生的错误，包含出错时的文件偏移
+    #[error("位置 {offset}: {source}")]
+    At {
+        /// 出错时读取器所在的文件偏移
+        offset: u64,
+        /// 原始错误
+        source: Box<GlogError>,
+    },
+
+    /// 带文件路径的错误
+    /// 读取指定文件时发生的错误，显示为 `文件:偏移: 错误`
+    #[error("{}", display_in_file(path, source))]
+    InFile {
+        /// 日志文件路径
+        path: String,
+        /// 原始错误，可能包含偏移
+        source: Box<GlogError>,
+    },
+}
+
+impl GlogError {
+    /// 附加文件偏移，已经带有位置或路径的错误保持不变
+    ///
+    /// # Arguments
+    /// * `offset` - 出错时的文件偏移
+    pub fn at(self, offset: u64) -> Self {
+        match self {
+            Self::At { .. } | Self::InFile { .. } => self,
+            source => Self::At {
+                offset,
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// 附加文件路径，已经带有路径的错误保持不变
+    ///
+    /// # Arguments
+    /// * `path` - 日志文件路径
+    pub fn in_file(self, path: impl Into<String>) -> Self {
+        match self {
+            Self::InFile { .. } => self,
+            source => Self::InFile {
+                path: path.into(),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// 去掉位置和路径后的原始错误，用于按错误类型匹配
+    pub fn kind(&self) -> &GlogError {
+        match self {
+            Self::At { source, .. } | Self::InFile { source, .. } => source.kind(),
+            e => e,
+        }
+    }
+
+    /// 出错时的文件偏移
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::At { offset, .. } => Some(*offset),
+            Self::InFile { source, .. } => source.offset(),
+            _ => None,
+        }
+    }
+
+    /// 出错的日志文件路径
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::InFile { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// 是否只影响单条日志，跳过该条目（或换用更大的缓冲区重试）后可以继续读取
+    ///
+    /// 同步标记不匹配、日志长度无效、单个条目的模式、公钥、解密、解压或 protobuf 解析失败可以恢复；
+    /// 文件头损坏、魔数或版本不匹配、IO 错误、密钥和配置错误以及取消需要中止读取。
+    /// 带位置或路径的错误按原始错误判断
+    pub fn is_recoverable(&self) -> bool {
+        match self.kind() {
+            Self::SyncMarkerMismatch
+            | Self::IllegalCompressMode(_)
+            | Self::IllegalEncryptMode(_)
+            | Self::DecompressError(_)
+            | Self::DecryptError(_)
+            | Self::InvalidLogLength(_)
+            | Self::Truncated { .. }
+            | Self::PublicKeyDecompressError(_)
+            | Self::ProtobufError(_)
+            | Self::NeedRecover(_) => true,
+            Self::FileCorrupt(_)
+            | Self::Io(_)
+            | Self::UnexpectedEof { .. }
+            | Self::MagicMismatch
+            | Self::UnsupportedVersion(_)
+            | Self::CompressError(_)
+            | Self::CipherNotReady
+            | Self::TypeMapError(_)
+            | Self::RedactRuleError(_)
+            | Self::IndexError(_)
+            | Self::HexError(_)
+            | Self::Cancelled
+            | Self::EllipticCurveError(_)
+            | Self::WrongCurve(_) => false,
+            #[cfg(feature = "dynamic")]
+            Self::DescriptorError(_) => false,
+            #[cfg(feature = "std-fs")]
+            Self::ZipError(_) | Self::ExtractLimitExceeded { .. } => false,
+            Self::At { .. } | Self::InFile { .. } => unreachable!("kind() 已去掉位置和路径"),
+        }
+    }
+
+    /// 稳定的数字错误码，供 FFI 绑定、脚本和 JSON 报告使用
+    ///
+    /// 按类别分段，已分配的编号不再改变，新增的错误类型只在段内追加：
+    ///
+    /// | 段 | 类别 | 错误码 |
+    /// |----|------|--------|
+    /// | 1xx | IO | 101 `Io`，102 `UnexpectedEof`，103 `ZipError`，104 `ExtractLimitExceeded` |
+    /// | 2xx | 文件格式 | 201 `FileCorrupt`，202 `MagicMismatch`，203 `UnsupportedVersion`，204 `SyncMarkerMismatch`，205 `IllegalCompressMode`，206 `IllegalEncryptMode`，207 `InvalidLogLength`，208 `Truncated`，209 `NeedRecover` |
+    /// | 3xx | 加密与密钥 | 301 `DecryptError`，302 `CipherNotReady`，303 `PublicKeyDecompressError`，304 `EllipticCurveError`，305 `WrongCurve`，306 `HexError` |
+    /// | 4xx | 压缩 | 401 `DecompressError`，402 `CompressError` |
+    /// | 5xx | 解码与配置 | 501 `ProtobufError`，502 `DescriptorError`，503 `TypeMapError`，504 `RedactRuleError`，505 `IndexError` |
+    /// | 6xx | 读取控制 | 601 `Cancelled` |
+    ///
+    /// 带位置或路径的错误返回原始错误的错误码
+    pub fn code(&self) -> u32 {
+        match self.kind() {
+            Self::Io(_) => 101,
+            Self::UnexpectedEof { .. } => 102,
+            #[cfg(feature = "std-fs")]
+            Self::ZipError(_) => 103,
+            #[cfg(feature = "std-fs")]
+            Self::ExtractLimitExceeded { .. } => 104,
+            Self::FileCorrupt(_) => 201,
+            Self::MagicMismatch => 202,
+            Self::UnsupportedVersion(_) => 203,
+            Self::SyncMarkerMismatch => 204,
+            Self::IllegalCompressMode(_) => 205,
+            Self::IllegalEncryptMode(_) => 206,
+            Self::InvalidLogLength(_) => 207,
+            Self::Truncated { .. } => 208,
+            Self::NeedRecover(_) => 209,
+            Self::DecryptError(_) => 301,
+            Self::CipherNotReady => 302,
+            Self::PublicKeyDecompressError(_) => 303,
+            Self::EllipticCurveError(_) => 304,
+            Self::WrongCurve(_) => 305,
+            Self::HexError(_) => 306,
+            Self::DecompressError(_) => 401,
+            Self::CompressError(_) => 402,
+            Self::ProtobufError(_) => 501,
+            #[cfg(feature = "dynamic")]
+            Self::DescriptorError(_) => 502,
+            Self::TypeMapError(_) => 503,
+            Self::RedactRuleError(_) => 504,
+            Self::IndexError(_) => 505,
+            Self::Cancelled => 601,
+            Self::At { .. } | Self::InFile { .. } => unreachable!("kind() 已去掉位置和路径"),
+        }
+    }
+
+    /// 转换为 [`std::io::Error`] 时使用的错误类型
+    fn io_kind(&self) -> std::io::ErrorKind {
+        use std::io::ErrorKind;
+
+        match self.kind() {
+            Self::Io(e) => e.kind(),
+            Self::UnexpectedEof { .. } => ErrorKind::UnexpectedEof,
+            Self::FileCorrupt(_)
+            | Self::MagicMismatch
+            | Self::UnsupportedVersion(_)
+            | Self::SyncMarkerMismatch
+            | Self::IllegalCompressMode(_)
+            | Self::IllegalEncryptMode(_)
+            | Self::DecompressError(_)
+            | Self::DecryptError(_)
+            | Self::InvalidLogLength(_)
+            | Self::PublicKeyDecompressError(_)
+            | Self::ProtobufError(_)
+            | Self::IndexError(_)
+            | Self::NeedRecover(_) => ErrorKind::InvalidData,
+            Self::CipherNotReady
+            | Self::Truncated { .. }
+            | Self::TypeMapError(_)
+            | Self::RedactRuleError(_)
+            | Self::HexError(_)
+            | Self::EllipticCurveError(_)
+            | Self::WrongCurve(_) => ErrorKind::InvalidInput,
+            #[cfg(feature = "dynamic")]
+            Self::DescriptorError(_) => ErrorKind::InvalidInput,
+            #[cfg(feature = "std-fs")]
+            Self::ZipError(zip::result::ZipError::Io(e)) => e.kind(),
+            #[cfg(feature = "std-fs")]
+            Self::ZipError(_) | Self::ExtractLimitExceeded { .. } => ErrorKind::InvalidData,
+            // 不使用 `Interrupted`：`Read::read_exact` 等适配器遇到它会无限重试
+            Self::CompressError(_) | Self::Cancelled => ErrorKind::Other,
+            Self::At { .. } | Self::InFile { .. } => unreachable!("kind() 已去掉位置和路径"),
+        }
+    }
+}
+
+/// 转换为 IO 错误，便于把读取器包装在 `std::io` 适配器中
+///
+/// 不带位置的 IO 错误原样取出，其他错误按 [`GlogError::kind`] 映射 [`std::io::ErrorKind`]
+/// 并作为内部错误保留，可以用 `get_ref` / `into_inner` 取回
+impl From<GlogError> for std::io::Error {
+    fn from(e: GlogError) -> Self {
+        match e {
+            GlogError::Io(e) => e,
+            e => std::io::Error::new(e.io_kind(), e),
+        }
+    }
+}
+
+/// 格式化带路径的错误：有偏移时为 `文件:偏移: 错误`，否则为 `文件: 错误`
+fn display_in_file(path: &str, source: &GlogError) -> String {
+    match source {
+        GlogError::At { offset, source } => format!("{}:{}: {}", path, offset, source),
+        source => format!("{}: {}", path, source),
+    }
 }
 
 /// 结果类型别名
@@ -114,6 +401,323 @@ pub enum ReadResult {
     Success(usize),
     /// 已到达文件末尾
     Eof,
-    /// 需要恢复（遇到可恢复的错误）
-    NeedRecover(i32),
+    /// 需要恢复（遇到可恢复的错误），包含条目损坏的原因
+    NeedRecover(RecoverReason),
+}
+
+/// 条目损坏、需要跳到下一个同步标记恢复的原因
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecoverReason {
+    /// V4 条目的压缩模式无法识别
+    #[error("非法压缩模式: {0}")]
+    IllegalCompressMode(u8),
+    /// V4 条目的加密模式无法识别
+    #[error("非法加密模式: {0}")]
+    IllegalEncryptMode(u8),
+    /// 日志长度为 0 或超过长度上限
+    #[error("无效的日志长度: {0}")]
+    InvalidLogLength(usize),
+    /// 条目解密失败
+    #[error("解密失败")]
+    DecryptFailed,
+    /// 条目解压失败，或压缩数据缺少 SYNC_FLUSH 结尾（通常是解密出了乱码）
+    #[error("解压缩失败")]
+    DecompressFailed,
+    /// 条目末尾的同步标记不匹配
+    #[error("同步标记不匹配")]
+    SyncMarkerMismatch,
+    /// 存储长度超出剩余数据，且之后还有同步标记（不是文件末尾的不完整条目）
+    #[error("日志条目不完整")]
+    Truncated,
+}
+
+impl RecoverReason {
+    /// 与文件版本无关的整数原因码，供 FFI 和报告使用
+    ///
+    /// 每个原因对应唯一的编号，取值与 V4 读取器原先的错误码相同。
+    /// V3 读取器原先用 -2 表示无效的日志长度、-3 表示同步标记不匹配，与这里的编号冲突；
+    /// 需要与旧版本逐版本保持一致时使用 [`legacy_code`](Self::legacy_code)
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::IllegalCompressMode(_) => -2,
+            Self::IllegalEncryptMode(_) => -3,
+            Self::InvalidLogLength(_) => -4,
+            Self::DecryptFailed => -5,
+            Self::Truncated => -6,
+            Self::SyncMarkerMismatch => -7,
+            Self::DecompressFailed => -8,
+        }
+    }
+
+    /// 旧版本读取器对同一原因返回的整数错误码
+    ///
+    /// V3 文件沿用 V3 读取器原先的编号（无效的日志长度 -2，同步标记不匹配 -3），
+    /// 其余版本以及 V3 原先没有的原因与 [`code`](Self::code) 相同
+    ///
+    /// # Arguments
+    /// * `version` - 文件版本，参见 [`GlogMetadata::version`](crate::reader::GlogMetadata::version)
+    pub fn legacy_code(&self, version: u8) -> i32 {
+        match (version, self) {
+            (GLOG_RECOVERY_VERSION, Self::InvalidLogLength(_)) => -2,
+            (GLOG_RECOVERY_VERSION, Self::SyncMarkerMismatch) => -3,
+            _ => self.code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_recoverable_and_io_kind_of_every_variant() {
+        let cases: Vec<(GlogError, bool, ErrorKind)> = vec![
+            (
+                GlogError::FileCorrupt("头".into()),
+                false,
+                ErrorKind::InvalidData,
+            ),
+            (
+                std::io::Error::new(ErrorKind::PermissionDenied, "拒绝").into(),
+                false,
+                ErrorKind::PermissionDenied,
+            ),
+            (
+                GlogError::UnexpectedEof {
+                    expected: 8,
+                    available: 3,
+                },
+                false,
+                ErrorKind::UnexpectedEof,
+            ),
+            (GlogError::MagicMismatch, false, ErrorKind::InvalidData),
+            (
+                GlogError::UnsupportedVersion(9),
+                false,
+                ErrorKind::InvalidData,
+            ),
+            (GlogError::SyncMarkerMismatch, true, ErrorKind::InvalidData),
+            (
+                GlogError::IllegalCompressMode(7),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (
+                GlogError::IllegalEncryptMode(7),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (
+                GlogError::DecompressError("流".into()),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (
+                GlogError::CompressError("流".into()),
+                false,
+                ErrorKind::Other,
+            ),
+            (
+                GlogError::DecryptError("密钥".into()),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (GlogError::CipherNotReady, false, ErrorKind::InvalidInput),
+            (GlogError::InvalidLogLength(0), true, ErrorKind::InvalidData),
+            (
+                GlogError::Truncated {
+                    required: 20,
+                    available: 10,
+                },
+                true,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::PublicKeyDecompressError("点".into()),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (
+                prost::DecodeError::new("字段").into(),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            #[cfg(feature = "dynamic")]
+            (
+                prost_reflect::DescriptorPool::decode(&[0xFF][..])
+                    .unwrap_err()
+                    .into(),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::TypeMapError("表".into()),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::RedactRuleError("规则".into()),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::IndexError("魔数".into()),
+                false,
+                ErrorKind::InvalidData,
+            ),
+            #[cfg(feature = "std-fs")]
+            (
+                zip::result::ZipError::FileNotFound.into(),
+                false,
+                ErrorKind::InvalidData,
+            ),
+            #[cfg(feature = "std-fs")]
+            (
+                zip::result::ZipError::Io(ErrorKind::NotFound.into()).into(),
+                false,
+                ErrorKind::NotFound,
+            ),
+            #[cfg(feature = "std-fs")]
+            (
+                GlogError::ExtractLimitExceeded { limit: 1024 },
+                false,
+                ErrorKind::InvalidData,
+            ),
+            (
+                hex::FromHexError::OddLength.into(),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::NeedRecover(RecoverReason::DecryptFailed),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (GlogError::Cancelled, false, ErrorKind::Other),
+            (
+                GlogError::EllipticCurveError("曲线".into()),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::WrongCurve("P-256".into()),
+                false,
+                ErrorKind::InvalidInput,
+            ),
+            (
+                GlogError::SyncMarkerMismatch.at(64),
+                true,
+                ErrorKind::InvalidData,
+            ),
+            (
+                GlogError::MagicMismatch.at(0).in_file("a.glog"),
+                false,
+                ErrorKind::InvalidData,
+            ),
+            (
+                GlogError::from(std::io::Error::from(ErrorKind::TimedOut)).at(8),
+                false,
+                ErrorKind::TimedOut,
+            ),
+        ];
+        for (error, recoverable, kind) in cases {
+            let message = error.to_string();
+            assert_eq!(error.is_recoverable(), recoverable, "{}", message);
+            let io_error = std::io::Error::from(error);
+            assert_eq!(io_error.kind(), kind, "{}", message);
+            // 不带位置的 IO 错误原样取出，没有 `IO 错误:` 前缀
+            assert!(message.ends_with(&io_error.to_string()), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_recover_codes_match_legacy_readers() {
+        use crate::version::GLOG_CIPHER_VERSION;
+
+        let cases = [
+            (RecoverReason::IllegalCompressMode(7), -2, -2),
+            (RecoverReason::IllegalEncryptMode(7), -3, -3),
+            (RecoverReason::InvalidLogLength(0), -2, -4),
+            (RecoverReason::DecryptFailed, -5, -5),
+            (RecoverReason::Truncated, -6, -6),
+            (RecoverReason::SyncMarkerMismatch, -3, -7),
+            (RecoverReason::DecompressFailed, -8, -8),
+        ];
+        for (reason, v3, v4) in cases {
+            assert_eq!(reason.legacy_code(GLOG_RECOVERY_VERSION), v3, "{:?}", reason);
+            assert_eq!(reason.legacy_code(GLOG_CIPHER_VERSION), v4, "{:?}", reason);
+            assert_eq!(reason.code(), v4, "{:?}", reason);
+        }
+    }
+
+    #[test]
+    fn test_error_code_table_is_frozen() {
+        // 错误码已对外公开，修改任何一项都会破坏绑定和脚本
+        let cases: Vec<(GlogError, u32)> = vec![
+            (std::io::Error::from(ErrorKind::NotFound).into(), 101),
+            (
+                GlogError::UnexpectedEof {
+                    expected: 8,
+                    available: 3,
+                },
+                102,
+            ),
+            (GlogError::FileCorrupt("头".into()), 201),
+            (GlogError::MagicMismatch, 202),
+            (GlogError::UnsupportedVersion(9), 203),
+            (GlogError::SyncMarkerMismatch, 204),
+            (GlogError::IllegalCompressMode(7), 205),
+            (GlogError::IllegalEncryptMode(7), 206),
+            (GlogError::InvalidLogLength(0), 207),
+            (
+                GlogError::Truncated {
+                    required: 20,
+                    available: 10,
+                },
+                208,
+            ),
+            (GlogError::NeedRecover(RecoverReason::Truncated), 209),
+            (GlogError::DecryptError("密钥".into()), 301),
+            (GlogError::CipherNotReady, 302),
+            (GlogError::PublicKeyDecompressError("点".into()), 303),
+            (GlogError::EllipticCurveError("曲线".into()), 304),
+            (GlogError::WrongCurve("P-256".into()), 305),
+            (hex::FromHexError::OddLength.into(), 306),
+            (GlogError::DecompressError("流".into()), 401),
+            (GlogError::CompressError("流".into()), 402),
+            (prost::DecodeError::new("字段").into(), 501),
+            (GlogError::TypeMapError("表".into()), 503),
+            (GlogError::RedactRuleError("规则".into()), 504),
+            (GlogError::IndexError("魔数".into()), 505),
+            (GlogError::Cancelled, 601),
+            (GlogError::SyncMarkerMismatch.at(64).in_file("a.glog"), 204),
+            #[cfg(feature = "std-fs")]
+            (zip::result::ZipError::FileNotFound.into(), 103),
+            #[cfg(feature = "std-fs")]
+            (GlogError::ExtractLimitExceeded { limit: 1024 }, 104),
+            #[cfg(feature = "dynamic")]
+            (
+                prost_reflect::DescriptorPool::decode(&[0xFF][..])
+                    .unwrap_err()
+                    .into(),
+                502,
+            ),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.code(), code, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_io_error_keeps_glog_error_inside() {
+        let io_error = std::io::Error::from(GlogError::Cancelled.at(16));
+        let inner = io_error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<GlogError>()
+            .unwrap();
+        assert!(matches!(inner.kind(), GlogError::Cancelled));
+        assert_eq!(inner.offset(), Some(16));
+    }
 }
@@ -0,0 +1,328 @@
+//! # UniFFI 接口
+//!
+//! 通过 UniFFI 向 Kotlin / Swift 暴露读取器，Android 调试页面可以直接在设备上解密查看日志。
+//! 绑定只依赖解码核心：文件由 [`std::fs::File`] 打开后交给 [`GlogReader::from_reader`]，
+//! 不需要 `std-fs` 功能（ZIP、目录遍历、临时文件）。
+//!
+//! ```text
+//! cargo build --release --target aarch64-linux-android --no-default-features --features uniffi
+//! cargo run --no-default-features --features uniffi-cli --bin uniffi-bindgen -- \
+//!     generate --library target/aarch64-linux-android/release/libclog_reader.so \
+//!     --language kotlin --out-dir bindings
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::GlogError;
+use crate::glog::GlogReader;
+use crate::proto::{Level, Log};
+use crate::reader::{CompressMode, EncryptMode};
+
+/// 读取器错误，Kotlin 中为 `ReaderException`
+///
+/// `code` 为 [`GlogError::code`] 给出的稳定错误码，用于按错误类型分支；`message` 仅供显示
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum ReaderError {
+    /// 文件无法打开或读取
+    #[error("{message}")]
+    Io { code: u32, message: String },
+    /// 不是 Glog 文件或版本不受支持
+    #[error("{message}")]
+    InvalidFormat { code: u32, message: String },
+    /// 私钥无效或缺少私钥
+    #[error("{message}")]
+    Key { code: u32, message: String },
+    /// 日志数据损坏或无法解码
+    #[error("{message}")]
+    Corrupt { code: u32, message: String },
+}
+
+impl From<GlogError> for ReaderError {
+    fn from(e: GlogError) -> Self {
+        let (code, message) = (e.code(), e.to_string());
+        match e.kind() {
+            GlogError::Io(_) => Self::Io { code, message },
+            GlogError::MagicMismatch
+            | GlogError::UnsupportedVersion(_)
+            | GlogError::IllegalCompressMode(_)
+            | GlogError::IllegalEncryptMode(_) => Self::InvalidFormat { code, message },
+            GlogError::CipherNotReady
+            | GlogError::HexError(_)
+            | GlogError::EllipticCurveError(_)
+            | GlogError::WrongCurve(_) => Self::Key { code, message },
+            _ => Self::Corrupt { code, message },
+        }
+    }
+}
+
+/// 一条日志
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct LogRecord {
+    /// 日志类型
+    pub log_type: i32,
+    /// 毫秒时间戳（原始字符串）
+    pub timestamp: String,
+    /// 日志级别
+    pub level: Level,
+    /// 进程 ID
+    pub pid: i32,
+    /// 线程 ID
+    pub tid: String,
+    /// 日志标签
+    pub tag: String,
+    /// 日志内容
+    pub msg: String,
+    /// 条目在文件中的起始偏移
+    pub offset: u64,
+}
+
+impl LogRecord {
+    /// 由解码后的日志和条目偏移创建
+    fn new(log: Log, offset: u64) -> Self {
+        Self {
+            log_type: log.log_type,
+            level: log.level(),
+            timestamp: log.timestamp,
+            pid: log.pid,
+            tid: log.tid,
+            tag: log.tag,
+            msg: log.msg,
+            offset,
+        }
+    }
+}
+
+/// 文件元数据，参见 [`GlogMetadata`](crate::glog::GlogMetadata)
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct FileMetadata {
+    /// 文件格式版本
+    pub version: u8,
+    /// 协议名称
+    pub proto_name: String,
+    /// 压缩模式
+    pub compress_mode: CompressMode,
+    /// 加密模式（未知时为 `null`）
+    pub encrypt_mode: Option<EncryptMode>,
+    /// 文件总大小
+    pub file_size: u64,
+    /// 因未提供密钥而跳过的加密日志条数
+    pub skipped_encrypted: u64,
+    /// 文件末尾不完整日志的字节数
+    pub truncated_tail_bytes: u64,
+    /// 共享密钥缓存命中次数
+    pub key_cache_hits: u64,
+    /// 共享密钥缓存未命中次数
+    pub key_cache_misses: u64,
+}
+
+/// 面向 Kotlin / Swift 的 Glog 读取器
+///
+/// UniFFI 对象可能在任意线程上调用，读取器放在互斥锁中
+#[derive(uniffi::Object)]
+pub struct GlogFileReader {
+    /// 底层读取器
+    reader: Mutex<SendReader>,
+}
+
+/// 可以在线程间移动的读取器
+struct SendReader {
+    /// 底层读取器
+    reader: GlogReader,
+    /// 条目缓冲区
+    buf: Vec<u8>,
+}
+
+// SAFETY: 读取器由 `open` / `from_bytes` 独占创建，内部的输入流、解压器和密钥提供者
+// 都不与外部共享（没有泄露出去的 `Rc` 或引用），只通过 `Mutex` 一次由一个线程访问
+unsafe impl Send for SendReader {}
+
+#[uniffi::export]
+impl GlogFileReader {
+    /// 打开日志文件
+    ///
+    /// # Arguments
+    /// * `path` - 日志文件路径
+    /// * `key` - 可选的服务器私钥（十六进制字符串）
+    ///
+    /// # Errors
+    /// 文件无法打开、不是 Glog 文件或私钥格式错误时返回错误
+    #[uniffi::constructor]
+    pub fn open(path: String, key: Option<String>) -> Result<Arc<Self>, ReaderError> {
+        let file = File::open(&path).map_err(GlogError::from)?;
+        let size = file.metadata().map_err(GlogError::from)?.len();
+        Ok(Self::wrap(GlogReader::from_reader(
+            BufReader::new(file),
+            size,
+            key,
+        )?))
+    }
+
+    /// 从内存中的文件内容创建读取器
+    ///
+    /// # Arguments
+    /// * `bytes` - 完整的 Glog 文件内容
+    /// * `key` - 可选的服务器私钥（十六进制字符串）
+    ///
+    /// # Errors
+    /// 不是 Glog 文件或私钥格式错误时返回错误
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>, key: Option<String>) -> Result<Arc<Self>, ReaderError> {
+        let size = bytes.len() as u64;
+        Ok(Self::wrap(GlogReader::from_reader(Cursor::new(bytes), size, key)?))
+    }
+
+    /// 读取下一条日志
+    ///
+    /// # Returns
+    /// 返回下一条日志，文件结束时返回 `null`
+    ///
+    /// # Errors
+    /// 条目无法解码时返回错误，之后可以继续读取下一条
+    pub fn next(&self) -> Result<Option<LogRecord>, ReaderError> {
+        let mut guard = self.lock();
+        let SendReader { reader, buf } = &mut *guard;
+        buf.clear();
+        let Some(entry) = reader.read_entry(buf)? else {
+            return Ok(None);
+        };
+        let log = Log::decode_from(buf).map_err(GlogError::from)?;
+        Ok(Some(LogRecord::new(log, entry.offset)))
+    }
+
+    /// 一次读取最多 `max` 条日志，减少跨语言调用的次数
+    ///
+    /// 参见 [`GlogReader::read_batch`]：无法解码的条目被跳过，
+    /// 批次中途遇到的读取错误推迟到下一次调用时返回
+    ///
+    /// # Returns
+    /// 返回本批日志，文件结束时返回空列表
+    pub fn next_batch(&self, max: u32) -> Result<Vec<LogRecord>, ReaderError> {
+        let batch = self
+            .lock()
+            .reader
+            .read_batch_with(max as usize, |reader, offset, buf| {
+                let log = reader.decode_log(&buf).ok()?;
+                Some(LogRecord::new(log, offset))
+            })?;
+        Ok(batch)
+    }
+
+    /// 获取文件元数据
+    pub fn metadata(&self) -> FileMetadata {
+        let metadata = self.lock().reader.metadata();
+        let key_cache = metadata.key_cache.unwrap_or_default();
+        FileMetadata {
+            version: metadata.version,
+            proto_name: metadata.proto_name,
+            compress_mode: metadata.compress_mode,
+            encrypt_mode: metadata.encrypt_mode,
+            file_size: metadata.file_size,
+            skipped_encrypted: metadata.skipped_encrypted,
+            truncated_tail_bytes: metadata.truncated_tail_bytes,
+            key_cache_hits: key_cache.hits,
+            key_cache_misses: key_cache.misses,
+        }
+    }
+}
+
+impl GlogFileReader {
+    /// 包装打开的读取器
+    fn wrap(reader: GlogReader) -> Arc<Self> {
+        Arc::new(Self {
+            reader: Mutex::new(SendReader {
+                reader,
+                buf: Vec::new(),
+            }),
+        })
+    }
+
+    /// 获取读取器，之前的调用在读取过程中 panic 时仍然继续使用
+    fn lock(&self) -> MutexGuard<'_, SendReader> {
+        self.reader.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writer::v3::GlogWriterV3;
+
+    fn sample_file() -> Vec<u8> {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        for (level, msg) in [(0, "hello"), (4, "boom")] {
+            writer
+                .write_log(&Log {
+                    log_type: 1,
+                    timestamp: "1700000000000".to_string(),
+                    log_level: level,
+                    pid: 42,
+                    tid: "main".to_string(),
+                    tag: "app".to_string(),
+                    msg: msg.to_string(),
+                })
+                .unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_reader_returns_records_until_none() {
+        let reader = GlogFileReader::from_bytes(sample_file(), None).unwrap();
+
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.level, Level::Info);
+        assert_eq!((first.tag.as_str(), first.msg.as_str()), ("app", "hello"));
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.level, Level::Error);
+        assert!(second.offset > first.offset);
+        assert_eq!(reader.next().unwrap(), None);
+
+        let metadata = reader.metadata();
+        assert_eq!(metadata.compress_mode, CompressMode::Zlib);
+        assert_eq!(metadata.proto_name, "Log");
+    }
+
+    /// 生成的 FfiConverter 能把记录序列化到 RustBuffer 并原样读回
+    #[test]
+    fn test_scaffolding_round_trips_records() {
+        use uniffi::{Lift, Lower};
+
+        let reader = GlogFileReader::from_bytes(sample_file(), None).unwrap();
+        let record = reader.next().unwrap().unwrap();
+        let buf = <LogRecord as Lower<crate::UniFfiTag>>::lower(record.clone());
+        assert_eq!(<LogRecord as Lift<crate::UniFfiTag>>::try_lift(buf).unwrap(), record);
+
+        let metadata = reader.metadata();
+        let buf = <FileMetadata as Lower<crate::UniFfiTag>>::lower(metadata.clone());
+        assert_eq!(<FileMetadata as Lift<crate::UniFfiTag>>::try_lift(buf).unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_next_batch_stops_at_eof() {
+        let reader = GlogFileReader::from_bytes(sample_file(), None).unwrap();
+
+        let batch = reader.next_batch(5).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[1].msg, "boom");
+        assert!(batch[0].offset < batch[1].offset);
+        assert!(reader.next_batch(5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_errors_map_to_exception_kinds() {
+        assert!(matches!(
+            GlogFileReader::from_bytes(b"not a glog file".to_vec(), None),
+            Err(ReaderError::InvalidFormat { code: 202, .. })
+        ));
+        assert!(matches!(
+            GlogFileReader::open("/nonexistent/file.glog".to_string(), None),
+            Err(ReaderError::Io { code: 101, .. })
+        ));
+        assert!(matches!(
+            GlogFileReader::from_bytes(sample_file(), Some("zz".to_string())),
+            Err(ReaderError::Key { code: 306, .. })
+        ));
+    }
+}
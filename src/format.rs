@@ -0,0 +1,416 @@
+//! # 日志格式化模块
+//!
+//! [`LogFormatter`] 把一条 [`Log`] 写成文本，调用方可以实现它来定制输出格式，
+//! 而不必先拼出中间字符串。[`DefaultFormatter`] 生成与 Java 版本一致的日志行，
+//! [`Log::format`] 和 `Display` 都使用它；设置 [`LogTypeRegistry`] 后还会输出类型名称。
+//! [`LogfmtFormatter`] 生成供日志平台解析的 logfmt 行。
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::proto::{Log, LogTypeRegistry};
+
+/// 日志格式化器
+pub trait LogFormatter {
+    /// 把一条日志写入 `out`
+    ///
+    /// # Arguments
+    /// * `log` - 要格式化的日志
+    /// * `out` - 输出目标，可以是 `String` 或 `fmt::Formatter`
+    ///
+    /// # Errors
+    /// 只传递 `out` 的写入错误
+    fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+/// 默认格式化器
+///
+/// 输出 `时间 [级别] [标签] {pid:tid} 内容`，时间按本地时区格式化。
+/// 通过 [`with_types`](Self::with_types) 设置类型名称表后，在级别之后插入 `<类型名称>`，
+/// 未注册的类型输出 `<类型整数>`
+#[derive(Debug, Clone, Default)]
+pub struct DefaultFormatter {
+    /// 类型名称表
+    types: Option<Arc<LogTypeRegistry>>,
+}
+
+impl DefaultFormatter {
+    /// 创建不输出类型名称的格式化器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建输出类型名称的格式化器
+    ///
+    /// # Arguments
+    /// * `types` - 类型名称表
+    pub fn with_types(types: Arc<LogTypeRegistry>) -> Self {
+        Self { types: Some(types) }
+    }
+
+    /// 类型名称表
+    pub fn types(&self) -> Option<&Arc<LogTypeRegistry>> {
+        self.types.as_ref()
+    }
+}
+
+impl LogFormatter for DefaultFormatter {
+    fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(
+            out,
+            "{} [{}] ",
+            log.formatted_timestamp(),
+            log.level().as_str()
+        )?;
+        if let Some(types) = &self.types {
+            match types.name(log.log_type) {
+                Some(name) => write!(out, "<{}> ", name)?,
+                None => write!(out, "<{}> ", log.log_type)?,
+            }
+        }
+        write!(out, "[{}] {{{}:{}}} {}", log.tag, log.pid, log.tid, log.msg)
+    }
+}
+
+/// logfmt 格式化器
+///
+/// 输出 `ts=... level=... type=... pid=... tid=... tag=... msg=...`，键名和顺序固定：
+///
+/// - `ts` - UTC 的 RFC 3339 时间，毫秒精度，如 `2024-01-01T00:00:00.000Z`；无法解析时为原始时间戳
+/// - `level` - 小写的级别名称：`info`、`debug`、`verbose`、`warn`、`error`
+/// - `type` - 日志类型整数；通过 [`with_types`](Self::with_types) 设置类型名称表后，已注册的类型输出名称
+/// - `pid`、`tid`、`tag`、`msg` - 对应的日志字段
+///
+/// 值为空时输出 `key=`；含空格、`=`、`"` 或控制字符时加双引号，引号内的 `"`、`\`
+/// 和换行等控制字符按反斜杠转义（`\n`、`\t`、`\u001b` 等），因此每条日志只占一行
+///
+/// ```
+/// use clog_reader::format::LogfmtFormatter;
+/// use clog_reader::proto::Log;
+///
+/// let log = Log {
+///     timestamp: "1704067200000".to_string(),
+///     msg: "a \"b\"".to_string(),
+///     ..Log::new()
+/// };
+/// assert_eq!(
+///     log.format_with(&LogfmtFormatter::new()),
+///     r#"ts=2024-01-01T00:00:00.000Z level=info type=0 pid=0 tid= tag= msg="a \"b\"""#
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LogfmtFormatter {
+    /// 类型名称表
+    types: Option<Arc<LogTypeRegistry>>,
+}
+
+impl LogfmtFormatter {
+    /// 创建 `type` 输出整数的格式化器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建 `type` 输出类型名称的格式化器
+    ///
+    /// # Arguments
+    /// * `types` - 类型名称表
+    pub fn with_types(types: Arc<LogTypeRegistry>) -> Self {
+        Self { types: Some(types) }
+    }
+}
+
+impl LogFormatter for LogfmtFormatter {
+    fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result {
+        let ts = log
+            .timestamp_millis()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|dt| dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
+        out.write_str("ts=")?;
+        write_logfmt_value(out, ts.as_deref().unwrap_or(&log.timestamp))?;
+        write!(out, " level={}", log.level().as_str().to_ascii_lowercase())?;
+        out.write_str(" type=")?;
+        let type_name = self
+            .types
+            .as_ref()
+            .and_then(|types| types.name(log.log_type));
+        match type_name {
+            Some(name) => write_logfmt_value(out, name)?,
+            None => write!(out, "{}", log.log_type)?,
+        }
+        write!(out, " pid={} tid=", log.pid)?;
+        write_logfmt_value(out, &log.tid)?;
+        out.write_str(" tag=")?;
+        write_logfmt_value(out, &log.tag)?;
+        out.write_str(" msg=")?;
+        write_logfmt_value(out, &log.msg)
+    }
+}
+
+/// 写入一个 logfmt 值，按需加引号和转义
+///
+/// 值含空白、`=`、引号或控制字符时加引号，引号、反斜杠和控制字符转义。
+/// 在 logfmt 行中追加其他字段时使用，与 [`LogfmtFormatter`] 的转义规则一致
+///
+/// # Arguments
+/// * `out` - 输出
+/// * `value` - 字段值
+pub fn write_logfmt_value(out: &mut dyn fmt::Write, value: &str) -> fmt::Result {
+    let needs_quotes = value
+        .chars()
+        .any(|c| c <= ' ' || c == '=' || c == '"' || c.is_control());
+    if !needs_quotes {
+        return out.write_str(value);
+    }
+    out.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// 内容中换行的处理方式
+///
+/// 堆栈等多行内容直接输出时，续行没有时间和级别前缀，按行 grep 会丢失上下文
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultilineMode {
+    /// 原样输出换行
+    #[default]
+    Raw,
+    /// 每一行都输出完整的前缀，内容末尾的换行被忽略
+    Prefix,
+    /// 换行（`\n` 或 `\r\n`）转义为 `\n`，单独的 `\r` 转义为 `\r`，每条日志只占一行
+    Escape,
+}
+
+/// 按 [`MultilineMode`] 处理多行内容的格式化器包装
+///
+/// 内容不含换行时直接交给内层格式化器
+///
+/// ```
+/// use clog_reader::format::{DefaultFormatter, Multiline, MultilineMode};
+///
+/// let formatter = Multiline::new(DefaultFormatter::new(), MultilineMode::Prefix);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Multiline<F> {
+    /// 内层格式化器
+    inner: F,
+    /// 换行处理方式
+    mode: MultilineMode,
+}
+
+impl<F: LogFormatter> Multiline<F> {
+    /// 包装一个格式化器
+    ///
+    /// # Arguments
+    /// * `inner` - 格式化单行日志的格式化器
+    /// * `mode` - 换行处理方式
+    pub fn new(inner: F, mode: MultilineMode) -> Self {
+        Self { inner, mode }
+    }
+
+    /// 换行处理方式
+    pub fn mode(&self) -> MultilineMode {
+        self.mode
+    }
+}
+
+impl<F: LogFormatter> LogFormatter for Multiline<F> {
+    fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result {
+        if !log.msg.contains(['\n', '\r']) {
+            return self.inner.format(log, out);
+        }
+        match self.mode {
+            MultilineMode::Raw => self.inner.format(log, out),
+            MultilineMode::Escape => {
+                let escaped = Log {
+                    msg: escape_newlines(&log.msg),
+                    ..log.clone()
+                };
+                self.inner.format(&escaped, out)
+            }
+            MultilineMode::Prefix => {
+                let mut line = log.clone();
+                for (i, text) in log.msg.lines().enumerate() {
+                    if i > 0 {
+                        out.write_char('\n')?;
+                    }
+                    line.msg.clear();
+                    line.msg.push_str(text);
+                    self.inner.format(&line, out)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 把 `\r\n` 和 `\n` 转义为 `\n`，单独的 `\r` 转义为 `\r`
+fn escape_newlines(msg: &str) -> String {
+    msg.replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> Log {
+        Log {
+            log_type: 1,
+            timestamp: "garbage".to_string(),
+            log_level: 3,
+            pid: 42,
+            tid: "main".to_string(),
+            tag: "Net".to_string(),
+            msg: "hello".to_string(),
+        }
+    }
+
+    /// 只输出标签和内容的格式化器
+    struct Short;
+
+    impl LogFormatter for Short {
+        fn format(&self, log: &Log, out: &mut dyn fmt::Write) -> fmt::Result {
+            write!(out, "{}: {}", log.tag, log.msg)
+        }
+    }
+
+    #[test]
+    fn test_display_matches_default_formatter() {
+        let log = log();
+        let mut line = String::new();
+        DefaultFormatter::new().format(&log, &mut line).unwrap();
+
+        assert_eq!(line, "garbage [Warn] [Net] {42:main} hello");
+        assert_eq!(log.to_string(), line);
+        assert_eq!(log.format(), line);
+    }
+
+    #[test]
+    fn test_type_names() {
+        let mut types = LogTypeRegistry::new();
+        types.register(1, "network");
+        let formatter = DefaultFormatter::with_types(Arc::new(types));
+
+        assert_eq!(
+            log().format_with(&formatter),
+            "garbage [Warn] <network> [Net] {42:main} hello"
+        );
+        let other = Log {
+            log_type: 7,
+            ..log()
+        };
+        assert_eq!(
+            other.format_with(&formatter),
+            "garbage [Warn] <7> [Net] {42:main} hello"
+        );
+    }
+
+    #[test]
+    fn test_multiline_modes() {
+        let trace = Log {
+            msg: "boom\r\n  at a()\n  at b()\n".to_string(),
+            ..log()
+        };
+        let format = |mode| trace.format_with(&Multiline::new(DefaultFormatter::new(), mode));
+
+        assert_eq!(
+            format(MultilineMode::Raw),
+            "garbage [Warn] [Net] {42:main} boom\r\n  at a()\n  at b()\n"
+        );
+        assert_eq!(
+            format(MultilineMode::Escape),
+            "garbage [Warn] [Net] {42:main} boom\\n  at a()\\n  at b()\\n"
+        );
+        assert_eq!(
+            format(MultilineMode::Prefix),
+            "garbage [Warn] [Net] {42:main} boom\n\
+             garbage [Warn] [Net] {42:main}   at a()\n\
+             garbage [Warn] [Net] {42:main}   at b()"
+        );
+
+        let single = Multiline::new(Short, MultilineMode::Prefix);
+        assert_eq!(log().format_with(&single), "Net: hello");
+        let carriage = Log {
+            msg: "a\rb".to_string(),
+            ..log()
+        };
+        let escape = Multiline::new(Short, MultilineMode::Escape);
+        assert_eq!(carriage.format_with(&escape), "Net: a\\rb");
+    }
+
+    #[test]
+    fn test_logfmt_golden() {
+        let formatter = LogfmtFormatter::new();
+        let cases = [
+            (
+                log(),
+                "ts=garbage level=warn type=1 pid=42 tid=main tag=Net msg=hello",
+            ),
+            (
+                Log {
+                    timestamp: "1704067200123".to_string(),
+                    log_level: 4,
+                    tid: String::new(),
+                    tag: "a=b".to_string(),
+                    msg: "say \"hi\" to C:\\tmp".to_string(),
+                    ..log()
+                },
+                r#"ts=2024-01-01T00:00:00.123Z level=error type=1 pid=42 tid= tag="a=b" msg="say \"hi\" to C:\\tmp""#,
+            ),
+            (
+                Log {
+                    timestamp: "1704067200".to_string(),
+                    msg: "boom\r\n\tat a()\u{1b}[0m".to_string(),
+                    ..log()
+                },
+                r#"ts=2024-01-01T00:00:00.000Z level=warn type=1 pid=42 tid=main tag=Net msg="boom\r\n\tat a()\u001b[0m""#,
+            ),
+            (
+                Log {
+                    timestamp: "not a time".to_string(),
+                    tag: "网络".to_string(),
+                    msg: "路径=/a\\b 完成".to_string(),
+                    ..log()
+                },
+                r#"ts="not a time" level=warn type=1 pid=42 tid=main tag=网络 msg="路径=/a\\b 完成""#,
+            ),
+            (
+                Log {
+                    msg: "C:\\tmp".to_string(),
+                    ..log()
+                },
+                r"ts=garbage level=warn type=1 pid=42 tid=main tag=Net msg=C:\tmp",
+            ),
+        ];
+        for (log, expected) in cases {
+            let line = log.format_with(&formatter);
+            assert_eq!(line, expected);
+            assert!(!line.contains('\n'));
+        }
+
+        let mut types = LogTypeRegistry::new();
+        types.register(1, "network io");
+        assert_eq!(
+            log().format_with(&LogfmtFormatter::with_types(Arc::new(types))),
+            r#"ts=garbage level=warn type="network io" pid=42 tid=main tag=Net msg=hello"#
+        );
+    }
+
+    #[test]
+    fn test_custom_formatter() {
+        let mut line = String::new();
+        Short.format(&log(), &mut line).unwrap();
+        assert_eq!(line, "Net: hello");
+    }
+}
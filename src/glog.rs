@@ -3,73 +3,1290 @@
 //! 本模块提供了 Glog 文件格式的主读取器实现。
 //! 它会自动检测文件版本并使用相应的读取器处理日志数据。
 
+use std::cell::Cell;
+use std::fmt;
+#[cfg(feature = "std-fs")]
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "std-fs")]
+use std::io::{self, Cursor, Seek, SeekFrom};
+#[cfg(feature = "std-fs")]
+use std::thread;
+#[cfg(feature = "std-fs")]
+use std::time::{Duration, Instant};
 // use log::info;
 
-use crate::error::{GlogError, Result, ReadResult};
-use crate::version::{GLOG_RECOVERY_VERSION, GLOG_CIPHER_VERSION};
+use zeroize::Zeroizing;
+
+use crate::error::{GlogError, Result, ReadResult, RecoverReason};
+#[cfg(feature = "dynamic")]
+use crate::dynamic::{DescriptorSet, DynamicDecoder};
+use crate::observer::{Observer, ReadStats};
+use crate::format::DefaultFormatter;
+use crate::proto::{peek_log_type, Log, LogFilter, LogTypeRegistry};
+use crate::redact::Redactor;
+use crate::version::{
+    GLOG_CIPHER_VERSION, GLOG_FIX_POSITION_VERSION, GLOG_INITIAL_VERSION, GLOG_RECOVERY_VERSION,
+};
+pub use crate::reader::{EntryInfo, GlogMetadata};
+#[cfg(feature = "std-fs")]
+use crate::reader::scan_for_sync_marker;
 use crate::reader::{
-    FileReader, MAGIC_NUMBER, SINGLE_LOG_CONTENT_MAX_LENGTH,
-    read_safely,
+    CompressMode, EncryptMode, FileReader, InflaterPool, StatefulInflater, MAGIC_NUMBER,
+    PROTO_NAME_MAX_LENGTH, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
+    key::{KeyProvider, KeyRing, RedactedKey, StaticKeyProvider, DEFAULT_KEY_CACHE_CAPACITY},
+    mmap::{read_mmap_content, FileReaderMmap, MMAP_HEADER_LENGTH, MMAP_MAGIC_NUMBER},
+    read_proto_name, read_safely, read_u32_le,
+    source::{ByteSource, ReadSource},
+    v1::FileReaderV1,
+    v2::FileReaderV2,
     v3::FileReaderV3,
     v4::FileReaderV4,
 };
 
-/// Glog 读取器
-///
-/// 主入口读取器，负责解析文件头并根据版本号
-/// 委托给相应的版本特定读取器处理日志数据
-pub struct GlogReader {
-    /// 内部文件读取器（版本特定）
-    inner: Box<dyn FileReader>,
-}
+/// 批量读取时预先分配的最大条数，避免很大的 `max` 导致一次性分配过多内存
+const BATCH_PREALLOC: usize = 1024;
+
+/// 打开文件时读缓冲区大小的默认值（256 KB）
+///
+/// `BufReader` 默认的 8 KB 在数百 MB 的文件上会产生大量小的读取调用
+pub const DEFAULT_BUFFER_CAPACITY: usize = 256 * 1024;
+
+/// Glog 读取器
+///
+/// 主入口读取器，负责解析文件头并根据版本号
+/// 委托给相应的版本特定读取器处理日志数据
+///
+/// 读取器实现 `Send`，可以移动到工作线程中读取；同一个读取器不能同时被多个线程使用
+pub struct GlogReader {
+    /// 内部文件读取器（版本特定）
+    inner: Box<dyn FileReader + Send>,
+    /// 遇到损坏条目时的恢复策略
+    recovery: RecoveryPolicy,
+    /// 严格模式：`logs()` 遇到 protobuf 解码失败后结束
+    strict: bool,
+    /// 日志文件路径，附加到读取错误中（从输入流创建时为 `None`）
+    path: Option<String>,
+    /// 取消标志，置位后读取返回 `GlogError::Cancelled`
+    cancel: Option<Arc<AtomicBool>>,
+    /// 读取过程的观察者
+    observers: Vec<Arc<dyn Observer>>,
+    /// 累计统计（`decode_log` 只持有 `&self`，因此放在 `Cell` 中）
+    stats: Cell<ReadStats>,
+    /// 是否已通知观察者读取结束
+    ended: bool,
+    /// 批量读取中途遇到、推迟到下一次批量读取返回的错误
+    pending_error: Option<GlogError>,
+    /// 是否配置了解密密钥（只用于 `Debug` 输出）
+    has_key: bool,
+    /// 日志类型名称表
+    types: Option<Arc<LogTypeRegistry>>,
+    /// 解码后对日志脱敏
+    redactor: Option<Arc<Redactor>>,
+    /// 按描述符动态解码文件头中记录的消息类型
+    #[cfg(feature = "dynamic")]
+    dynamic: Option<DynamicDecoder>,
+}
+
+impl GlogReader {
+    /// 创建读取器构建器，用于配置密钥、恢复策略、长度上限等选项
+    pub fn builder() -> GlogReaderBuilder {
+        GlogReaderBuilder::new()
+    }
+
+    /// 创建新的 Glog 读取器
+    ///
+    /// # Arguments
+    /// * `file_path` - 日志文件路径
+    ///
+    /// # Returns
+    /// 返回新创建的 GlogReader 实例
+    ///
+    /// # Errors
+    /// 如果文件无法打开或格式不正确，返回相应的错误
+    #[cfg(feature = "std-fs")]
+    pub fn new(file_path: &str) -> Result<Self> {
+        Self::with_key(file_path, None)
+    }
+
+    /// 创建带加密密钥的 Glog 读取器
+    ///
+    /// # Arguments
+    /// * `file_path` - 日志文件路径
+    /// * `key` - 可选的服务器私钥（用于解密 V4 版本的加密日志）
+    ///
+    /// # Returns
+    /// 返回新创建的 GlogReader 实例
+    ///
+    /// # Errors
+    /// 如果文件无法打开或格式不正确，返回相应的错误
+    #[cfg(feature = "std-fs")]
+    pub fn with_key(file_path: &str, key: Option<String>) -> Result<Self> {
+        Self::builder().optional_key(key).open(file_path)
+    }
+
+    /// 从保存的偏移处继续读取 Glog 文件
+    ///
+    /// 用于长时间运行的采集任务断点续读：把某次读取后的 [`position`](Self::position)
+    /// 保存下来，之后以它作为 `offset` 重新打开即可跳过已读取的部分。
+    /// `offset` 紧跟在同步标记之后时直接从该处继续，否则向前扫描到下一个同步标记之后；
+    /// 小于文件头长度时从第一条日志开始。
+    ///
+    /// 压缩的日志在整个文件中共用一个 zlib 字典，从中间继续读取时字典已经丢失，
+    /// 之后的前几条压缩日志可能无法解压，这些条目按恢复策略处理；未压缩的日志不受影响。
+    /// 没有同步标记的 V1/V2 格式不支持断点续读
+    ///
+    /// # Arguments
+    /// * `file_path` - 日志文件路径
+    /// * `offset` - 继续读取的文件偏移
+    /// * `key` - 可选的服务器私钥（用于解密 V4 版本的加密日志）
+    ///
+    /// # Returns
+    /// 返回定位到条目边界的 GlogReader 实例
+    ///
+    /// # Errors
+    /// 如果文件无法打开、格式不正确或版本不支持断点续读，返回相应的错误
+    #[cfg(feature = "std-fs")]
+    pub fn open_at(file_path: &str, offset: u64, key: Option<String>) -> Result<Self> {
+        Self::builder().optional_key(key).open_at(file_path, offset)
+    }
+
+    /// 从任意输入流创建 Glog 读取器
+    ///
+    /// 魔数和版本号直接从输入流中读取，因此可以读取网络流、内存缓冲区等，
+    /// 无需先写入临时文件。输入流本身没有缓冲时建议先包装为 `BufReader`；
+    /// 借用型的输入（如 `ZipFile`）需先读入内存，再以 `Cursor` 传入。
+    ///
+    /// # Arguments
+    /// * `input` - 从文件起始位置开始的输入流
+    /// * `size` - 数据总大小（字节）
+    /// * `key` - 可选的服务器私钥（用于解密 V4 版本的加密日志）
+    ///
+    /// # Returns
+    /// 返回新创建的 GlogReader 实例
+    ///
+    /// # Errors
+    /// 如果文件头格式不正确或版本不受支持，返回相应的错误
+    pub fn from_reader<R: Read + Send + 'static>(
+        input: R,
+        size: u64,
+        key: Option<String>,
+    ) -> Result<Self> {
+        Self::builder().optional_key(key).from_reader(input, size)
+    }
+
+    /// 读取下一条日志
+    ///
+    /// 遇到损坏条目时按 [`RecoveryPolicy`] 处理：跳过策略下自动恢复并继续读取下一条，
+    /// 因此不会返回 `ReadResult::NeedRecover`；`Abort` 策略下返回 `GlogError::NeedRecover`
+    ///
+    /// # Arguments
+    /// * `out_buf` - 输出缓冲区
+    ///
+    /// # Returns
+    /// 返回读取结果
+    pub fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
+        self.read_with(|inner| inner.read(out_buf))
+    }
+
+    /// 读取下一条日志到新分配的缓冲区
+    ///
+    /// 与 [`read`](Self::read) 相同，但缓冲区按需扩容，
+    /// 因此解压后超过 [`single_log_max_length`](Self::single_log_max_length) 的日志也能完整读取
+    ///
+    /// # Returns
+    /// 返回日志内容；读到文件末尾时返回 `None`
+    pub fn read_owned(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut out = Vec::new();
+        match self.read_with(|inner| inner.read_to_vec(&mut out))? {
+            ReadResult::Success(_) => Ok(Some(out)),
+            ReadResult::Eof => Ok(None),
+            ReadResult::NeedRecover(reason) => Err(self.locate(GlogError::NeedRecover(reason))),
+        }
+    }
+
+    /// 一次读取并解码最多 `max` 条日志
+    ///
+    /// 用于跨 FFI 边界读取时摊薄每次调用的开销。损坏条目按读取器的 [`RecoveryPolicy`] 处理，
+    /// 跳过策略下在批次内部恢复并继续读取；无法解码的条目被跳过，计入 [`ReadStats::decode_failures`]。
+    /// 批次中途遇到读取错误（包括 `Abort` 策略下的 `NeedRecover`）时先返回已读出的日志，
+    /// 错误推迟到下一次批量读取时返回
+    ///
+    /// # Arguments
+    /// * `max` - 本批最多读取的条数
+    ///
+    /// # Returns
+    /// 返回本批读出的日志；少于 `max` 条说明已读到文件末尾或遇到了错误，读到末尾后返回空列表
+    pub fn read_batch(&mut self, max: usize) -> Result<Vec<Log>> {
+        self.read_batch_with(max, |reader, _, buf| reader.decode_log(&buf).ok())
+    }
+
+    /// 一次读取最多 `max` 条日志的原始数据
+    ///
+    /// 与 [`read_batch`](Self::read_batch) 相同，但不解码，返回解密、解压后的 protobuf 数据；
+    /// 内容为空的条目被跳过
+    ///
+    /// # Arguments
+    /// * `max` - 本批最多读取的条数
+    pub fn read_batch_raw(&mut self, max: usize) -> Result<Vec<Vec<u8>>> {
+        self.read_batch_with(max, |_, _, buf| Some(buf))
+    }
+
+    /// 批量读取的公共逻辑，`convert` 收到条目的起始偏移和数据，返回 `None` 的条目不计入批次
+    pub(crate) fn read_batch_with<T>(
+        &mut self,
+        max: usize,
+        mut convert: impl FnMut(&Self, u64, Vec<u8>) -> Option<T>,
+    ) -> Result<Vec<T>> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        let mut batch = Vec::with_capacity(max.min(BATCH_PREALLOC));
+        while batch.len() < max {
+            let start = self.position();
+            let mut buf = Vec::new();
+            let error = match self.read_with(|inner| inner.read_to_vec(&mut buf)) {
+                Ok(ReadResult::Success(0)) => continue,
+                Ok(ReadResult::Success(_)) => {
+                    let offset = self.inner.last_entry().map_or(start, |entry| entry.offset);
+                    batch.extend(convert(self, offset, buf));
+                    continue;
+                }
+                Ok(ReadResult::Eof) => break,
+                Ok(ReadResult::NeedRecover(reason)) => self.locate(GlogError::NeedRecover(reason)),
+                Err(e) => e,
+            };
+            if batch.is_empty() {
+                return Err(error);
+            }
+            self.pending_error = Some(error);
+            break;
+        }
+        Ok(batch)
+    }
+
+    /// 读取下一条日志及其存储信息
+    ///
+    /// 与 [`read_owned`](Self::read_owned) 相同，但同时返回条目的偏移、存储长度、
+    /// 是否压缩/加密以及所用的客户端公钥，用于排查格式或加密问题
+    ///
+    /// # Arguments
+    /// * `buf` - 输出缓冲区，读取前清空，成功时恰好包含整条日志
+    ///
+    /// # Returns
+    /// 返回条目的存储信息；读到文件末尾时返回 `None`
+    pub fn read_entry(&mut self, buf: &mut Vec<u8>) -> Result<Option<EntryInfo>> {
+        match self.read_with(|inner| inner.read_to_vec(buf))? {
+            ReadResult::Success(_) => Ok(self.inner.last_entry()),
+            ReadResult::Eof => Ok(None),
+            ReadResult::NeedRecover(reason) => Err(self.locate(GlogError::NeedRecover(reason))),
+        }
+    }
+
+    /// 跳过下一条日志，只验证条目结构而不解码
+    ///
+    /// 与 [`count`](Self::count) 相同：未压缩的条目直接跳过数据，不解密也不解析 protobuf；
+    /// 压缩条目共用解压器状态，仍需解压到 `scratch` 中。用于按索引跳过不可能匹配的日志
+    ///
+    /// # Arguments
+    /// * `scratch` - 压缩条目的解压缓冲区，内容没有意义
+    ///
+    /// # Returns
+    /// 返回被跳过条目的存储信息；读到文件末尾时返回 `None`
+    pub fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<Option<EntryInfo>> {
+        loop {
+            match self.read_with(|inner| inner.skip_entry(scratch))? {
+                ReadResult::Success(0) => continue,
+                ReadResult::Success(_) => return Ok(self.inner.last_entry()),
+                ReadResult::Eof => return Ok(None),
+                ReadResult::NeedRecover(reason) => {
+                    return Err(self.locate(GlogError::NeedRecover(reason)))
+                }
+            }
+        }
+    }
+
+    /// 读取下一条日志的存储数据：加密条目解密，但不解压
+    ///
+    /// 用于在不解压、不重新压缩的情况下去掉加密（参见 [`GlogWriterV4::copy_raw_from`]），
+    /// 损坏条目按恢复策略处理。不能与 [`read_entry`](Self::read_entry) 等解码日志的方法混用：
+    /// 压缩条目共用一个压缩流，跳过解压后读取器的解压状态不再连续。目前只支持 V4 文件
+    ///
+    /// # Arguments
+    /// * `buf` - 输出缓冲区，读取前清空，成功时恰好包含条目的存储数据
+    ///
+    /// # Returns
+    /// 返回条目的存储信息（`len` 为存储数据的长度）；读到文件末尾时返回 `None`
+    ///
+    /// # Errors
+    /// 文件不是 V4 版本时返回 `UnsupportedVersion`
+    ///
+    /// [`GlogWriterV4::copy_raw_from`]: crate::writer::v4::GlogWriterV4::copy_raw_from
+    pub fn read_raw(&mut self, buf: &mut Vec<u8>) -> Result<Option<EntryInfo>> {
+        match self.read_with(|inner| inner.read_raw(buf))? {
+            ReadResult::Success(_) => Ok(self.inner.last_entry()),
+            ReadResult::Eof => Ok(None),
+            ReadResult::NeedRecover(reason) => Err(self.locate(GlogError::NeedRecover(reason))),
+        }
+    }
+
+    /// 统计剩余的日志条数
+    ///
+    /// 只验证条目结构而不解码：未压缩的条目直接跳过数据，不解密也不解析 protobuf；
+    /// 压缩条目共用解压器状态，仍需解压（结果丢弃）。损坏条目按恢复策略处理
+    ///
+    /// # Returns
+    /// 返回从当前位置到文件末尾的条目数
+    pub fn count(&mut self) -> Result<usize> {
+        let mut count = 0;
+        self.skip_each(|_| count += 1)?;
+        Ok(count)
+    }
+
+    /// 列出剩余每条日志的偏移和存储长度，用于建立索引
+    ///
+    /// 与 [`count`](Self::count) 相同，只验证条目结构而不解码
+    ///
+    /// # Returns
+    /// 返回从当前位置到文件末尾的条目位置
+    pub fn scan(&mut self) -> Result<Vec<EntryOffset>> {
+        let mut offsets = Vec::new();
+        self.skip_each(|entry| {
+            if let Some(entry) = entry {
+                offsets.push(EntryOffset {
+                    offset: entry.offset,
+                    entry_len: entry.entry_len,
+                });
+            }
+        })?;
+        Ok(offsets)
+    }
+
+    /// 跳过剩余的全部条目，每跳过一条调用一次 `on_entry`
+    fn skip_each(&mut self, mut on_entry: impl FnMut(Option<EntryInfo>)) -> Result<()> {
+        let mut scratch = Vec::new();
+        loop {
+            match self.read_with(|inner| inner.skip_entry(&mut scratch))? {
+                ReadResult::Success(0) => continue,
+                ReadResult::Success(_) => on_entry(self.inner.last_entry()),
+                ReadResult::Eof => return Ok(()),
+                ReadResult::NeedRecover(reason) => {
+                    return Err(self.locate(GlogError::NeedRecover(reason)))
+                }
+            }
+        }
+    }
+
+    /// 读取并解码下一条满足过滤条件的日志，[`Logs`] 和 [`MergedGlogReader`](crate::merge::MergedGlogReader) 的迭代逻辑
+    ///
+    /// # Arguments
+    /// * `buf` - 单条日志缓冲区
+    /// * `filter` - 可选的过滤条件
+    /// * `done` - 迭代是否已结束；读取错误、`NeedRecover` 和严格模式下的解码失败之后置位
+    ///
+    /// # Returns
+    /// 返回日志及其条目的起始偏移，迭代结束时返回 `None`
+    pub(crate) fn next_log(
+        &mut self,
+        buf: &mut Vec<u8>,
+        filter: Option<&LogFilter>,
+        done: &mut bool,
+    ) -> Option<Result<(u64, Log)>> {
+        while !*done {
+            let start = self.position();
+            match self.read_with(|inner| inner.read_to_vec(buf)) {
+                Ok(ReadResult::Success(0)) => continue,
+                Ok(ReadResult::Success(len)) => {
+                    if filter.is_some_and(|filter| self.rejects_by_type(&buf[..len], filter)) {
+                        continue;
+                    }
+                    let log = self.decode_log(&buf[..len]);
+                    if let (Ok(log), Some(filter)) = (&log, filter) {
+                        if !filter.matches(log) {
+                            continue;
+                        }
+                    }
+                    *done = log.is_err() && self.strict;
+                    let offset = self.inner.last_entry().map_or(start, |entry| entry.offset);
+                    return Some(
+                        log.map(|log| (offset, log))
+                            .map_err(|e| self.in_file(e.at(offset))),
+                    );
+                }
+                Ok(ReadResult::NeedRecover(reason)) => {
+                    *done = true;
+                    return Some(Err(self.locate(GlogError::NeedRecover(reason))));
+                }
+                Ok(ReadResult::Eof) => *done = true,
+                Err(e) => {
+                    *done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+
+    /// 使用给定的读取方式读取下一条日志，并按恢复策略处理损坏条目
+    fn read_with(
+        &mut self,
+        mut read_entry: impl FnMut(&mut dyn FileReader) -> Result<ReadResult>,
+    ) -> Result<ReadResult> {
+        loop {
+            if self.is_cancelled() {
+                return Err(self.locate(GlogError::Cancelled));
+            }
+            let offset = self.inner.position();
+            let result = match read_entry(self.inner.as_mut()) {
+                Ok(result) => result,
+                Err(e) => {
+                    self.end();
+                    return Err(self.locate(e));
+                }
+            };
+            match result {
+                ReadResult::NeedRecover(reason) => match &mut self.recovery {
+                    RecoveryPolicy::Abort => {
+                        self.end();
+                        return Err(self.in_file(GlogError::NeedRecover(reason).at(offset)));
+                    }
+                    policy => {
+                        if let Err(e) = self.inner.recover() {
+                            self.end();
+                            return Err(self.in_file(e.at(self.inner.position())));
+                        }
+                        let event = RecoverEvent {
+                            offset,
+                            reason,
+                            bytes_skipped: self.inner.position() - offset,
+                        };
+                        if let RecoveryPolicy::Skip { on_event } = policy {
+                            on_event(event);
+                        }
+                        self.update_stats(|stats| {
+                            stats.recoveries += 1;
+                            stats.bytes_skipped += event.bytes_skipped;
+                        });
+                        for observer in &self.observers {
+                            observer.on_recover(event);
+                        }
+                    }
+                },
+                ReadResult::Success(len) if len > 0 => {
+                    let entry_len = self.inner.position() - offset;
+                    self.update_stats(|stats| {
+                        stats.entries += 1;
+                        stats.bytes += entry_len;
+                    });
+                    for observer in &self.observers {
+                        observer.on_entry(offset, entry_len);
+                    }
+                    return Ok(result);
+                }
+                ReadResult::Eof => {
+                    self.end();
+                    return Ok(result);
+                }
+                result => return Ok(result),
+            }
+        }
+    }
+
+    /// 累计统计
+    ///
+    /// 包括读出的条目数和字节数、恢复次数和跳过的字节数，以及经 [`decode_log`](Self::decode_log)
+    /// （或 [`logs`](Self::logs)）解码失败的条目数
+    pub fn stats(&self) -> ReadStats {
+        self.stats.get()
+    }
+
+    /// 更新累计统计
+    fn update_stats(&self, update: impl FnOnce(&mut ReadStats)) {
+        let mut stats = self.stats.get();
+        update(&mut stats);
+        self.stats.set(stats);
+    }
+
+    /// 通知观察者读取结束，每个读取器只通知一次
+    fn end(&mut self) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+        let stats = self.stats.get();
+        for observer in &self.observers {
+            observer.on_file_end(self.path.as_deref(), &stats);
+        }
+    }
+
+    /// 从损坏处恢复读取
+    ///
+    /// 在 `Abort` 策略下 [`read`](Self::read) 返回 `GlogError::NeedRecover` 之后调用：
+    /// 向前扫描到下一个同步标记之后并重置解压器，使后续读取从下一个条目边界继续。
+    /// 跳过的字节数可以通过调用前后的 [`position`](Self::position) 之差得到
+    ///
+    /// # Returns
+    /// 找到下一个条目边界时返回 `true`；扫描到文件末尾仍未找到时返回 `false`
+    pub fn recover(&mut self) -> Result<bool> {
+        self.inner.recover().map_err(|e| self.locate(e))
+    }
+
+    /// 取消标志是否已置位
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// 为读取错误附加当前位置和文件路径
+    fn locate(&self, e: GlogError) -> GlogError {
+        self.in_file(e.at(self.inner.position()))
+    }
+
+    /// 为错误附加文件路径（从输入流创建的读取器没有路径）
+    fn in_file(&self, e: GlogError) -> GlogError {
+        match &self.path {
+            Some(path) => e.in_file(path.as_str()),
+            None => e,
+        }
+    }
+
+    /// 获取当前读取位置
+    ///
+    /// 返回相对文件起始位置的字节偏移，可用于定位出错的日志条目
+    pub fn position(&self) -> u64 {
+        self.inner.position()
+    }
+
+    /// 获取文件总大小（字节）
+    pub fn len(&self) -> u64 {
+        self.inner.size()
+    }
+
+    /// 文件是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 获取读取进度
+    ///
+    /// # Returns
+    /// 返回已读取字节数占文件总大小的比例，范围为 `0.0..=1.0`；空文件返回 `1.0`
+    pub fn progress(&self) -> f64 {
+        match self.len() {
+            0 => 1.0,
+            len => (self.position() as f64 / len as f64).min(1.0),
+        }
+    }
+
+    /// 更新文件总大小
+    ///
+    /// 跟随读取仍在写入的文件时，在文件增长后调用，
+    /// 使读取器能够继续读取新追加的日志
+    pub fn set_size(&mut self, size: u64) {
+        self.inner.set_size(size);
+    }
+
+    /// 获取文件元数据
+    ///
+    /// 包括版本号、协议名称、压缩和加密模式以及文件大小
+    pub fn metadata(&self) -> GlogMetadata {
+        self.inner.metadata()
+    }
+
+    /// 通过 [`GlogReaderBuilder::type_registry`] 设置的日志类型名称表
+    pub fn type_registry(&self) -> Option<&Arc<LogTypeRegistry>> {
+        self.types.as_ref()
+    }
+
+    /// 按读取器配置创建默认格式化器
+    ///
+    /// 设置了类型名称表时输出类型名称，参见 [`DefaultFormatter::with_types`]
+    pub fn formatter(&self) -> DefaultFormatter {
+        match &self.types {
+            Some(types) => DefaultFormatter::with_types(Arc::clone(types)),
+            None => DefaultFormatter::new(),
+        }
+    }
+
+    /// 解码一条日志条目
+    ///
+    /// 通过 [`GlogReaderBuilder::descriptor_set`] 找到了文件头记录的消息类型时按描述符动态解码，
+    /// 否则按内置的 [`Log`] 解码。非严格模式下字符串字段中的无效 UTF-8 替换为 U+FFFD
+    /// （参见 [`Log::decode_lossy`]），次数记入 [`ReadStats::lossy_decodes`]。
+    /// 设置了 [`GlogReaderBuilder::redactor`] 时随后进行脱敏
+    ///
+    /// # Arguments
+    /// * `buf` - `read` 等方法读出的条目数据
+    ///
+    /// # Errors
+    /// 数据无法按消息类型解码时返回 `GlogError::ProtobufError`
+    pub fn decode_log(&self, buf: &[u8]) -> Result<Log> {
+        #[cfg(feature = "dynamic")]
+        let log = match &self.dynamic {
+            Some(dynamic) => dynamic.decode_log(buf),
+            None => self.decode_builtin(buf),
+        };
+        #[cfg(not(feature = "dynamic"))]
+        let log = self.decode_builtin(buf);
+        let mut log = match log {
+            Ok(log) => log,
+            Err(e) => {
+                self.update_stats(|stats| stats.decode_failures += 1);
+                let (offset, len) = self.last_entry_span();
+                for observer in &self.observers {
+                    observer.on_decode_failure(offset, len);
+                }
+                return Err(e);
+            }
+        };
+        if let Some(redactor) = &self.redactor {
+            let count = redactor.redact(&mut log);
+            if count > 0 {
+                self.update_stats(|stats| stats.redactions += count);
+                let (offset, _) = self.last_entry_span();
+                for observer in &self.observers {
+                    observer.on_redact(offset, count);
+                }
+            }
+        }
+        Ok(log)
+    }
+
+    /// 按内置的 [`Log`] 解码，严格模式下不做容错
+    fn decode_builtin(&self, buf: &[u8]) -> Result<Log> {
+        if self.strict {
+            return Ok(Log::decode_from(buf)?);
+        }
+        let (log, lossy) = Log::decode_lossy(buf)?;
+        if lossy {
+            self.update_stats(|stats| stats.lossy_decodes += 1);
+            let (offset, len) = self.last_entry_span();
+            for observer in &self.observers {
+                observer.on_lossy_decode(offset, len);
+            }
+        }
+        Ok(log)
+    }
+
+    /// 最近读出的条目的起始偏移和长度，通知观察者时使用
+    fn last_entry_span(&self) -> (u64, u64) {
+        self.inner
+            .last_entry()
+            .map_or((self.position(), 0), |entry| (entry.offset, entry.entry_len))
+    }
+
+    /// 只解析类型字段，判断日志是否不满足过滤条件中的类型条件
+    ///
+    /// 用于在完整解码之前跳过大部分日志：返回 `true` 时无需再调用 [`decode_log`](Self::decode_log)。
+    /// 严格模式需要发现每条日志的解码错误，动态解码的消息中字段 1 不一定是 `int32`，这两种情况总是返回 `false`
+    ///
+    /// # Arguments
+    /// * `buf` - protobuf 编码的日志数据
+    /// * `filter` - 过滤条件
+    pub fn rejects_by_type(&self, buf: &[u8], filter: &LogFilter) -> bool {
+        #[cfg(feature = "dynamic")]
+        if self.dynamic.is_some() {
+            return false;
+        }
+        !self.strict && peek_log_type(buf).is_some_and(|log_type| !filter.matches_type(log_type))
+    }
+
+    /// 获取动态解码器，未设置描述符或描述符中没有文件头记录的消息类型时返回 `None`
+    ///
+    /// 可以配合 [`dynamic::render`](crate::dynamic::render) 通用地显示全部字段
+    #[cfg(feature = "dynamic")]
+    pub fn dynamic_decoder(&self) -> Option<&DynamicDecoder> {
+        self.dynamic.as_ref()
+    }
+
+    /// 获取单条日志的最大长度
+    pub fn single_log_max_length() -> usize {
+        SINGLE_LOG_CONTENT_MAX_LENGTH
+    }
+
+    /// 迭代读取并解码日志
+    ///
+    /// 损坏条目按读取器的 [`RecoveryPolicy`] 处理
+    ///
+    /// # Returns
+    /// 返回逐条产出 `Result<Log>` 的迭代器
+    pub fn logs(&mut self) -> Logs<'_> {
+        Logs {
+            reader: self,
+            buf: Vec::new(),
+            done: false,
+            filter: None,
+        }
+    }
+}
+
+impl fmt::Debug for GlogReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlogReader")
+            .field("path", &self.path)
+            .field("position", &self.position())
+            .field("metadata", &self.metadata())
+            .field("strict", &self.strict)
+            .field("key", &RedactedKey(self.has_key))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for GlogReader {
+    fn drop(&mut self) {
+        // 没有读到末尾就丢弃的读取器也通知观察者结束
+        self.end();
+    }
+}
+
+/// 日志条目在文件中的位置，由 [`GlogReader::scan`] 返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryOffset {
+    /// 条目的起始偏移
+    pub offset: u64,
+    /// 条目占用的总字节数（参见 [`EntryInfo::entry_len`]）
+    pub entry_len: u64,
+}
+
+/// 损坏条目的恢复事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoverEvent {
+    /// 损坏条目的起始偏移
+    pub offset: u64,
+    /// 条目损坏的原因
+    pub reason: RecoverReason,
+    /// 为重新同步而跳过的字节数（含损坏条目本身）
+    pub bytes_skipped: u64,
+}
+
+/// 遇到损坏条目时的恢复策略
+#[derive(Default)]
+pub enum RecoveryPolicy {
+    /// 终止读取：`read` 返回 `GlogError::NeedRecover`
+    Abort,
+    /// 跳到下一个同步标记后继续读取，不做任何通知（默认）
+    #[default]
+    SkipSilently,
+    /// 跳到下一个同步标记后继续读取，并通过回调通知每次恢复
+    Skip {
+        /// 每次恢复后调用的回调
+        on_event: Box<dyn FnMut(RecoverEvent) + Send>,
+    },
+}
+
+/// Glog 读取器构建器
+///
+/// 由 [`GlogReader::builder`] 创建，配置完成后通过 [`open`](Self::open)、
+/// [`open_at`](Self::open_at) 或 [`from_reader`](Self::from_reader) 创建读取器
+#[derive(Default)]
+pub struct GlogReaderBuilder {
+    /// 服务器私钥（十六进制字符串），按顺序尝试；构建器丢弃时清零
+    keys: Vec<Zeroizing<String>>,
+    /// 自定义解密密钥提供者（优先于 `keys`）
+    key_provider: Option<Box<dyn KeyProvider>>,
+    /// 共享密钥缓存的容量
+    key_cache_capacity: Option<usize>,
+    /// 没有密钥时是否跳过加密条目
+    skip_encrypted: bool,
+    /// 恢复策略
+    recovery: RecoveryPolicy,
+    /// 单条日志的长度上限
+    max_log_length: Option<usize>,
+    /// 打开文件时的读缓冲区大小
+    buffer_capacity: Option<usize>,
+    /// 共享的解压上下文池
+    inflater_pool: Option<InflaterPool>,
+    /// 打开文件时是否映射到内存读取
+    #[cfg(feature = "memmap")]
+    use_mmap: bool,
+    /// 严格模式
+    strict: bool,
+    /// 取消标志
+    cancel: Option<Arc<AtomicBool>>,
+    /// 读取过程的观察者
+    observers: Vec<Arc<dyn Observer>>,
+    /// 从输入流创建时报告给观察者、附加到错误中的名称
+    name: Option<String>,
+    /// 日志类型名称表
+    types: Option<Arc<LogTypeRegistry>>,
+    /// 解码后对日志脱敏
+    redactor: Option<Arc<Redactor>>,
+    /// 动态解码使用的描述符
+    #[cfg(feature = "dynamic")]
+    descriptor_set: Option<DescriptorSet>,
+}
+
+impl fmt::Debug for GlogReaderBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlogReaderBuilder")
+            .field(
+                "key",
+                &RedactedKey(!self.keys.is_empty() || self.key_provider.is_some()),
+            )
+            .field("skip_encrypted", &self.skip_encrypted)
+            .field("max_log_length", &self.max_log_length)
+            .field("buffer_capacity", &self.buffer_capacity)
+            .field("strict", &self.strict)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GlogReaderBuilder {
+    /// 创建使用默认配置的构建器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置服务器私钥（用于解密 V4 版本的加密日志）
+    ///
+    /// 会替换之前通过 `key` 或 [`keys`](Self::keys) 设置的私钥
+    ///
+    /// # Arguments
+    /// * `key` - 十六进制格式的私钥
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.keys = vec![Zeroizing::new(key.into())];
+        self
+    }
+
+    /// 设置多个候选的服务器私钥
+    ///
+    /// 用于服务器密钥轮换期间，日志可能由任一私钥对应的公钥加密。
+    /// 压缩的加密日志会逐个尝试，并记住每个客户端公钥所用的私钥；
+    /// 未压缩的加密日志无法验证，使用第一个私钥
+    ///
+    /// # Arguments
+    /// * `keys` - 十六进制格式的私钥，按尝试顺序排列
+    pub fn keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys = keys
+            .into_iter()
+            .map(|key| Zeroizing::new(key.into()))
+            .collect();
+        self
+    }
+
+    /// 设置可选的服务器私钥，`None` 时清除已设置的私钥
+    fn optional_key(mut self, key: Option<String>) -> Self {
+        self.keys = key.into_iter().map(Zeroizing::new).collect();
+        self
+    }
+
+    /// 设置自定义的解密密钥提供者
+    ///
+    /// 私钥保存在 HSM、KMS 等外部系统中时使用，设置后忽略 [`key`](Self::key) 和 [`keys`](Self::keys)
+    ///
+    /// # Arguments
+    /// * `provider` - 解密密钥提供者
+    pub fn key_provider(mut self, provider: Box<dyn KeyProvider>) -> Self {
+        self.key_provider = Some(provider);
+        self
+    }
+
+    /// 设置 ECDH 共享密钥缓存的容量（客户端公钥个数），默认 [`DEFAULT_KEY_CACHE_CAPACITY`]
+    ///
+    /// 缓存按最近使用淘汰，命中统计记录在 [`GlogMetadata::key_cache`] 中。
+    /// 只作用于由 [`key`](Self::key) 或 [`keys`](Self::keys) 创建的密钥提供者
+    ///
+    /// # Arguments
+    /// * `capacity` - 最多缓存的客户端公钥个数，0 表示不缓存
+    pub fn key_cache_capacity(mut self, capacity: usize) -> Self {
+        self.key_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// 没有密钥时跳过加密条目，只读取明文条目
+    ///
+    /// 默认遇到加密条目时返回 `GlogError::CipherNotReady`。启用后加密条目被跳过，
+    /// 跳过的条数记录在 [`GlogMetadata::skipped_encrypted`] 中。
+    /// 写入方在同一文件中共用压缩字典，跳过压缩的加密条目后紧随的压缩明文条目可能无法解压，
+    /// 这些条目按恢复策略处理
+    ///
+    /// # Arguments
+    /// * `skip` - 是否跳过加密条目
+    pub fn skip_encrypted(mut self, skip: bool) -> Self {
+        self.skip_encrypted = skip;
+        self
+    }
+
+    /// 设置遇到损坏条目时的恢复策略
+    ///
+    /// # Arguments
+    /// * `policy` - 恢复策略
+    pub fn recovery_policy(mut self, policy: RecoveryPolicy) -> Self {
+        self.recovery = policy;
+        self
+    }
+
+    /// 设置单条日志存储长度的上限
+    ///
+    /// 长度字段超过上限的条目按损坏处理，默认为 [`GlogReader::single_log_max_length`]。
+    /// 已知写入方使用更小的上限时调低该值，可以更早发现损坏的长度字段
+    ///
+    /// # Arguments
+    /// * `max_log_length` - 上限（字节）
+    pub fn max_log_length(mut self, max_log_length: usize) -> Self {
+        self.max_log_length = Some(max_log_length);
+        self
+    }
+
+    /// 设置读缓冲区大小
+    ///
+    /// 用于 [`open`](Self::open) 和 [`open_at`](Self::open_at) 打开的文件，默认为
+    /// [`DEFAULT_BUFFER_CAPACITY`]；对 [`from_reader`](Self::from_reader) 则在输入流外
+    /// 包装一层该大小的 `BufReader`，未设置时不包装
+    ///
+    /// # Arguments
+    /// * `capacity` - 缓冲区大小（字节）
+    pub fn buffer_capacity(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = Some(capacity);
+        self
+    }
+
+    /// 从共享的池中取用 zlib 解压上下文
+    ///
+    /// 依次打开大量文件时（如压缩包中的日志），读取器结束后把解压上下文放回池中，
+    /// 下一个读取器直接复用，省去每个文件一次的上下文分配
+    ///
+    /// # Arguments
+    /// * `pool` - 解压上下文池，克隆的句柄共享同一个池
+    pub fn inflater_pool(mut self, pool: InflaterPool) -> Self {
+        self.inflater_pool = Some(pool);
+        self
+    }
+
+    /// 打开文件时映射到内存读取，而不是通过 `BufReader` 读取
+    ///
+    /// 只作用于 [`open`](Self::open)。V3 / V4 读取器直接在映射上取出条目数据，
+    /// 省去系统调用和缓冲区之间的复制，适合反复读取数百 MB 的大文件；
+    /// 小文件上建立映射的开销抵消了收益。映射期间文件被截断会导致进程收到 `SIGBUS`，
+    /// 不要用于仍在写入的文件
+    ///
+    /// # Arguments
+    /// * `use_mmap` - 是否映射到内存读取
+    #[cfg(feature = "memmap")]
+    pub fn use_mmap(mut self, use_mmap: bool) -> Self {
+        self.use_mmap = use_mmap;
+        self
+    }
+
+    /// 设置严格模式
+    ///
+    /// 严格模式下遇到损坏条目即返回 `GlogError::NeedRecover`（忽略恢复策略），
+    /// [`GlogReader::logs`] 遇到 protobuf 解码失败后也会结束，字符串字段中的无效 UTF-8
+    /// 按解码失败处理而不做容错，用于格式回归测试
+    ///
+    /// # Arguments
+    /// * `strict` - 是否启用严格模式
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// 设置取消标志
+    ///
+    /// 每读取一条日志前检查一次，置位后读取返回 `GlogError::Cancelled`（[`logs`](GlogReader::logs)
+    /// 产出该错误后结束），用于在界面或信号处理中中止长时间的解码。
+    /// 同一个标志可以交给多个读取器，一次取消全部
+    ///
+    /// # Arguments
+    /// * `cancel` - 取消标志
+    pub fn cancel_token(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// 添加读取过程的观察者
+    ///
+    /// 可以多次调用，按添加顺序通知；同一个观察者可以交给多个读取器，参见 [`Observer`]
+    ///
+    /// # Arguments
+    /// * `observer` - 观察者
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// 设置从输入流创建的读取器的名称（如压缩包中的条目名称），代替文件路径附加到错误中并报告给观察者
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
 
-impl GlogReader {
-    /// 创建新的 Glog 读取器
+    /// 设置日志类型名称表
+    ///
+    /// 读取器不改变解码结果，只通过 [`GlogReader::type_registry`] 和 [`GlogReader::formatter`]
+    /// 把名称表交给格式化和统计输出，命令行工具的 `--type-map` 也经过这里
+    ///
+    /// # Arguments
+    /// * `types` - 类型名称表，通常由 [`LogTypeRegistry::builtin`] 加上项目自定义的映射组成
+    pub fn type_registry(mut self, types: Arc<LogTypeRegistry>) -> Self {
+        self.types = Some(types);
+        self
+    }
+
+    /// 设置脱敏器
+    ///
+    /// [`GlogReader::decode_log`] 解码成功后立即对标签和内容应用脱敏规则，
+    /// 过滤条件和所有输出看到的都是替换后的内容，替换次数记入 [`ReadStats::redactions`]
+    ///
+    /// # Arguments
+    /// * `redactor` - 脱敏器，可以在多个读取器之间共享
+    pub fn redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// 设置动态解码使用的 `FileDescriptorSet`
+    ///
+    /// 打开文件后在描述符中查找文件头记录的消息类型，找到时 [`GlogReader::decode_log`]
+    /// 和 [`GlogReader::logs`] 按该类型动态解码并映射到 [`Log`]（参见 [`dynamic::to_log`](crate::dynamic::to_log)），
+    /// 找不到时仍按 [`Log`] 解码
+    ///
+    /// # Arguments
+    /// * `descriptor_set` - 描述符内容或文件路径
+    #[cfg(feature = "dynamic")]
+    pub fn descriptor_set(mut self, descriptor_set: DescriptorSet) -> Self {
+        self.descriptor_set = Some(descriptor_set);
+        self
+    }
+
+    /// 打开日志文件
     ///
     /// # Arguments
     /// * `file_path` - 日志文件路径
     ///
     /// # Returns
-    /// 返回新创建的 GlogReader 实例
-    ///
-    /// # Errors
-    /// 如果文件无法打开或格式不正确，返回相应的错误
-    pub fn new(file_path: &str) -> Result<Self> {
-        Self::with_key(file_path, None)
+    /// 返回按当前配置创建的 GlogReader 实例
+    #[cfg(feature = "std-fs")]
+    pub fn open(self, file_path: &str) -> Result<GlogReader> {
+        self.build(Some(file_path), |options| open_internal(file_path, options))
     }
 
-    /// 创建带加密密钥的 Glog 读取器
+    /// 从保存的偏移处继续读取日志文件，参见 [`GlogReader::open_at`]
     ///
     /// # Arguments
     /// * `file_path` - 日志文件路径
-    /// * `key` - 可选的服务器私钥（用于解密 V4 版本的加密日志）
+    /// * `offset` - 继续读取的文件偏移
     ///
     /// # Returns
-    /// 返回新创建的 GlogReader 实例
+    /// 返回按当前配置创建的 GlogReader 实例
+    #[cfg(feature = "std-fs")]
+    pub fn open_at(self, file_path: &str, offset: u64) -> Result<GlogReader> {
+        self.build(Some(file_path), |options| {
+            open_internal_at(file_path, offset, options)
+        })
+    }
+
+    /// 跟随读取正在写入的日志文件，参见 [`open_follow`]
     ///
-    /// # Errors
-    /// 如果文件无法打开或格式不正确，返回相应的错误
-    pub fn with_key(file_path: &str, key: Option<String>) -> Result<Self> {
-        let inner = open_internal(file_path, key)?;
-        Ok(Self { inner })
+    /// # Arguments
+    /// * `file_path` - 日志文件路径
+    /// * `poll_interval` - 等待新数据时的轮询间隔
+    ///
+    /// # Returns
+    /// 返回按当前配置创建的 GlogReader 实例
+    #[cfg(feature = "std-fs")]
+    pub fn open_follow(self, file_path: &str, poll_interval: Duration) -> Result<GlogReader> {
+        let mut file = File::open(file_path)?;
+        let size = file.metadata()?.len();
+        let mut magic = [0u8; 4];
+        let mmap = file.read_exact(&mut magic).is_ok() && magic == MMAP_MAGIC_NUMBER;
+        file.rewind()?;
+
+        let input = FollowInput::new(file, poll_interval);
+        self.build(Some(file_path), |options| {
+            if mmap {
+                // 写入游标之后的零填充稍后会被新日志覆盖，不能预先读入缓冲区
+                open_stream(input, size, options)
+            } else {
+                open_stream(BufReader::new(input), size, options)
+            }
+        })
     }
 
-    /// 读取下一条日志
+    /// 从任意输入流创建读取器，参见 [`GlogReader::from_reader`]
     ///
     /// # Arguments
-    /// * `out_buf` - 输出缓冲区
+    /// * `input` - 从文件起始位置开始的输入流
+    /// * `size` - 数据总大小（字节）
     ///
     /// # Returns
-    /// 返回读取结果
-    pub fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
-        self.inner.read(out_buf)
+    /// 返回按当前配置创建的 GlogReader 实例
+    pub fn from_reader<R: Read + Send + 'static>(self, input: R, size: u64) -> Result<GlogReader> {
+        self.build(None, |options| match options.buffer_capacity {
+            Some(capacity) => open_stream(BufReader::with_capacity(capacity, input), size, options),
+            None => open_stream(input, size, options),
+        })
     }
 
-    /// 获取单条日志的最大长度
-    pub fn single_log_max_length() -> usize {
-        SINGLE_LOG_CONTENT_MAX_LENGTH
+    /// 按当前配置打开版本特定的读取器并包装为 GlogReader
+    ///
+    /// 给出 `path` 时打开失败的错误和之后的读取错误都附加该路径
+    fn build(
+        mut self,
+        path: Option<&str>,
+        open: impl FnOnce(OpenOptions) -> Result<Box<dyn FileReader + Send>>,
+    ) -> Result<GlogReader> {
+        let path = path.map(str::to_string).or(self.name.take());
+        self.build_inner(path.clone(), open)
+            .map_err(|e| match path {
+                Some(path) => e.in_file(path),
+                None => e,
+            })
+    }
+
+    /// [`build`](Self::build) 的实现
+    fn build_inner(
+        self,
+        path: Option<String>,
+        open: impl FnOnce(OpenOptions) -> Result<Box<dyn FileReader + Send>>,
+    ) -> Result<GlogReader> {
+        let key_provider = match self.key_provider {
+            Some(provider) => Some(provider),
+            None => hex_key_provider(&self.keys, self.key_cache_capacity)?,
+        };
+        let has_key = key_provider.is_some();
+        let inner = open(OpenOptions {
+            key_provider,
+            skip_encrypted: self.skip_encrypted,
+            max_log_length: self.max_log_length.unwrap_or(SINGLE_LOG_CONTENT_MAX_LENGTH),
+            buffer_capacity: self.buffer_capacity,
+            inflater_pool: self.inflater_pool,
+            #[cfg(feature = "memmap")]
+            use_mmap: self.use_mmap,
+        })?;
+        #[cfg(feature = "dynamic")]
+        let dynamic = match &self.descriptor_set {
+            Some(descriptor_set) => {
+                DynamicDecoder::new(descriptor_set, &inner.metadata().proto_name)?
+            }
+            None => None,
+        };
+        let recovery = if self.strict {
+            RecoveryPolicy::Abort
+        } else {
+            self.recovery
+        };
+        for observer in &self.observers {
+            observer.on_file_start(path.as_deref());
+        }
+        Ok(GlogReader {
+            inner,
+            recovery,
+            strict: self.strict,
+            path,
+            cancel: self.cancel,
+            observers: self.observers,
+            stats: Cell::default(),
+            ended: false,
+            pending_error: None,
+            has_key,
+            types: self.types,
+            redactor: self.redactor,
+            #[cfg(feature = "dynamic")]
+            dynamic,
+        })
+    }
+}
+
+/// 打开版本特定读取器时的选项，由 [`GlogReaderBuilder`] 生成
+struct OpenOptions {
+    /// 解密密钥提供者
+    key_provider: Option<Box<dyn KeyProvider>>,
+    /// 没有密钥时是否跳过 V4 的加密条目
+    skip_encrypted: bool,
+    /// 单条日志的长度上限
+    max_log_length: usize,
+    /// 打开文件时的读缓冲区大小
+    buffer_capacity: Option<usize>,
+    /// 共享的解压上下文池
+    inflater_pool: Option<InflaterPool>,
+    /// 打开文件时是否映射到内存读取
+    #[cfg(feature = "memmap")]
+    use_mmap: bool,
+}
+
+impl OpenOptions {
+    /// 按配置的缓冲区大小包装文件，未配置时使用 [`DEFAULT_BUFFER_CAPACITY`]
+    #[cfg(feature = "std-fs")]
+    fn buffered<R: Read>(&self, file: R) -> BufReader<R> {
+        let capacity = self.buffer_capacity.unwrap_or(DEFAULT_BUFFER_CAPACITY);
+        BufReader::with_capacity(capacity, file)
+    }
+
+    /// 新读取器使用的 zlib 解压器，配置了池时从池中取用解压上下文
+    fn inflater(&self) -> StatefulInflater {
+        match &self.inflater_pool {
+            Some(pool) => StatefulInflater::from_pool(pool),
+            None => StatefulInflater::new(),
+        }
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            key_provider: None,
+            skip_encrypted: false,
+            max_log_length: SINGLE_LOG_CONTENT_MAX_LENGTH,
+            buffer_capacity: None,
+            inflater_pool: None,
+            #[cfg(feature = "memmap")]
+            use_mmap: false,
+        }
+    }
+}
+
+/// 解码日志的迭代器
+///
+/// 由 [`GlogReader::logs`] 创建。protobuf 解码失败以 `Err` 产出后继续迭代（严格模式下结束）；
+/// 读取错误（IO、解压、解密等）以 `Err` 产出后迭代结束；读到文件末尾时结束。
+/// 损坏条目按读取器的 [`RecoveryPolicy`] 处理，`Abort` 策略下以 `Err` 产出后迭代结束。
+pub struct Logs<'a> {
+    /// 底层读取器
+    reader: &'a mut GlogReader,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+    /// 是否已结束
+    done: bool,
+    /// 过滤条件，不满足的日志直接跳过
+    filter: Option<LogFilter>,
+}
+
+impl Logs<'_> {
+    /// 只产出满足过滤条件的日志
+    ///
+    /// 不满足条件的日志在交给调用方之前跳过；解码失败和读取错误照常产出
+    ///
+    /// # Arguments
+    /// * `filter` - 过滤条件
+    pub fn with_filter(mut self, filter: LogFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// 获取当前读取位置
+    ///
+    /// 在调用 `next()` 之前获取，即为下一条日志条目的起始偏移
+    pub fn position(&self) -> u64 {
+        self.reader.position()
+    }
+}
+
+impl Iterator for Logs<'_> {
+    type Item = Result<Log>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader
+            .next_log(&mut self.buf, self.filter.as_ref(), &mut self.done)
+            .map(|log| log.map(|(_, log)| log))
     }
 }
 
@@ -82,6 +1299,7 @@ impl GlogReader {
 ///
 /// # Returns
 /// 返回 GlogReader 实例
+#[cfg(feature = "std-fs")]
 pub fn open(file_path: &str) -> Result<GlogReader> {
     open_with_key(file_path, None)
 }
@@ -94,61 +1312,597 @@ pub fn open(file_path: &str) -> Result<GlogReader> {
 ///
 /// # Returns
 /// 返回 GlogReader 实例
+#[cfg(feature = "std-fs")]
 pub fn open_with_key(file_path: &str, key: Option<String>) -> Result<GlogReader> {
-    let inner = open_internal(file_path, key)?;
-    Ok(GlogReader { inner })
+    GlogReader::with_key(file_path, key)
 }
 
-/// 内部打开文件的实现
+/// 文件校验结果
+///
+/// 由 [`validate`] 返回，记录通过校验的日志条数以及首个失败位置
+#[cfg(feature = "std-fs")]
+#[derive(Debug, Default)]
+pub struct Validation {
+    /// 通过校验的日志条数
+    pub entries: usize,
+    /// 首个失败的位置及原因（`None` 表示文件完整）
+    pub failure: Option<ValidationFailure>,
+}
+
+#[cfg(feature = "std-fs")]
+impl Validation {
+    /// 文件是否通过校验
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// 校验失败信息
+#[cfg(feature = "std-fs")]
+#[derive(Debug)]
+pub struct ValidationFailure {
+    /// 失败条目相对文件起始位置的字节偏移（文件头错误时为 0）
+    pub offset: u64,
+    /// 失败原因
+    pub error: String,
+    /// 失败原因的稳定错误码（参见 [`GlogError::code`]）
+    pub code: u32,
+    /// 条目损坏（NeedRecover）的原因，其他错误为 `None`
+    pub recover_reason: Option<RecoverReason>,
+}
+
+#[cfg(feature = "std-fs")]
+impl ValidationFailure {
+    /// 由读取器返回的错误创建
+    fn new(offset: u64, error: &GlogError) -> Self {
+        Self {
+            offset,
+            error: error.to_string(),
+            code: error.code(),
+            recover_reason: match error.kind() {
+                GlogError::NeedRecover(reason) => Some(*reason),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// 校验 Glog 文件的完整性
+///
+/// 依次验证魔数、版本号、文件头、每条日志的同步标记、解密（提供密钥时）
+/// 以及 protobuf 解码，只读取不格式化日志，遇到第一个错误即停止。
 ///
 /// # Arguments
 /// * `file_path` - 日志文件路径
 /// * `key` - 可选的服务器私钥
 ///
 /// # Returns
-/// 返回版本特定的文件读取器
-fn open_internal(file_path: &str, key: Option<String>) -> Result<Box<dyn FileReader>> {
-    // 第一次打开文件，读取版本信息
+/// 返回校验结果
+#[cfg(feature = "std-fs")]
+pub fn validate(file_path: &str, key: Option<String>) -> Validation {
+    let mut validation = Validation::default();
+    let builder = GlogReader::builder().optional_key(key).strict(true);
+    let mut reader = match builder.open(file_path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            validation.failure = Some(ValidationFailure::new(0, &e));
+            return validation;
+        }
+    };
+
+    let mut logs = reader.logs();
+    loop {
+        let offset = logs.position();
+        match logs.next() {
+            Some(Ok(_)) => validation.entries += 1,
+            Some(Err(e)) => {
+                validation.failure = Some(ValidationFailure::new(offset, &e));
+                return validation;
+            }
+            None => return validation,
+        }
+    }
+}
+
+/// [`peek`] 最多读取的字节数：mmap 文件头(8) + 魔数(4) + 版本(1) + 模式(1) + 协议名称长度(2)
+/// + 协议名称 + 同步标记(8) + 第一条日志的模式(1)
+pub const PEEK_MAX_LENGTH: usize = 8 + 4 + 1 + 1 + 2 + PROTO_NAME_MAX_LENGTH + 8 + 1;
+
+/// 文件头探测结果
+///
+/// 由 [`peek`] / [`peek_bytes`] 返回，只解析文件头，不创建读取器
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeekInfo {
+    /// 文件格式版本
+    pub version: u8,
+    /// 是否为 mmap 缓存文件（`.glogmmap`）
+    pub mmap: bool,
+    /// 协议名称
+    pub proto_name: String,
+    /// 压缩模式（V4 取自第一条日志，文件中没有日志或模式无法识别时为 `None`）
+    pub compress_mode: Option<CompressMode>,
+    /// 加密模式（V4 取自第一条日志，同上）
+    pub encrypt_mode: Option<EncryptMode>,
+    /// 文件总大小
+    pub file_size: u64,
+}
+
+impl PeekInfo {
+    /// 读取日志是否需要私钥
+    ///
+    /// V4 的加密模式记录在每条日志中，这里只根据第一条日志判断
+    pub fn needs_key(&self) -> bool {
+        self.encrypt_mode == Some(EncryptMode::Aes)
+    }
+}
+
+/// 探测日志文件的版本和加密要求
+///
+/// 只读取文件开头至多 [`PEEK_MAX_LENGTH`] 字节：验证魔数，解析版本号和文件头，
+/// V4 文件再读取第一条日志的模式字节，判断是否需要私钥。适合在批量处理前快速分拣文件
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+///
+/// # Returns
+/// 返回文件头探测结果
+///
+/// # Errors
+/// 文件无法读取、魔数不匹配、版本不受支持或文件头损坏时返回错误
+#[cfg(feature = "std-fs")]
+pub fn peek(file_path: &str) -> Result<PeekInfo> {
     let file = File::open(file_path)?;
     let size = file.metadata()?.len();
-    let mut reader = BufReader::new(file);
-    
+    let mut prefix = Vec::with_capacity(PEEK_MAX_LENGTH);
+    file.take(PEEK_MAX_LENGTH as u64).read_to_end(&mut prefix)?;
+    peek_prefix(&prefix, size)
+}
+
+/// 探测内存中的日志文件，参见 [`peek`]
+///
+/// # Arguments
+/// * `data` - 文件内容（至少包含文件开头，完整内容也只读取开头部分）
+///
+/// # Returns
+/// 返回文件头探测结果
+///
+/// # Errors
+/// 魔数不匹配、版本不受支持或文件头损坏时返回错误
+pub fn peek_bytes(data: &[u8]) -> Result<PeekInfo> {
+    peek_prefix(&data[..data.len().min(PEEK_MAX_LENGTH)], data.len() as u64)
+}
+
+/// 解析文件开头的数据
+///
+/// # Arguments
+/// * `prefix` - 文件开头至多 [`PEEK_MAX_LENGTH`] 字节
+/// * `file_size` - 文件总大小
+fn peek_prefix(mut prefix: &[u8], file_size: u64) -> Result<PeekInfo> {
+    let input = &mut prefix;
+    let mut magic = [0u8; 4];
+    read_safely(input, 4, &mut magic)?;
+
+    // mmap 缓存文件：Glog 数据从 mmap 文件头之后开始，长度为写入游标（不超过文件范围）
+    let mmap = magic == MMAP_MAGIC_NUMBER;
+    let mut size = file_size;
+    if mmap {
+        let capacity = file_size.saturating_sub(MMAP_HEADER_LENGTH);
+        size = (read_u32_le(input)? as u64).min(capacity);
+        read_safely(input, 4, &mut magic)?;
+    }
+    if magic != MAGIC_NUMBER {
+        return Err(GlogError::MagicMismatch);
+    }
+
+    let mut version = [0u8; 1];
+    read_safely(input, 1, &mut version)?;
+    let version = version[0];
+
+    let (mut compress_mode, mut encrypt_mode) = (None, None);
+    match version {
+        GLOG_INITIAL_VERSION | GLOG_FIX_POSITION_VERSION | GLOG_RECOVERY_VERSION => {
+            // 模式设置：高 4 位为压缩模式，低 4 位为加密模式
+            let mut ms = [0u8; 1];
+            read_safely(input, 1, &mut ms)?;
+            compress_mode = Some(match ms[0] >> 4 {
+                0 => CompressMode::None,
+                1 => CompressMode::Zlib,
+                2 if version == GLOG_RECOVERY_VERSION => CompressMode::Zstd,
+                bits => return Err(GlogError::IllegalCompressMode(bits)),
+            });
+            encrypt_mode = Some(match ms[0] & 0x0F {
+                0 => EncryptMode::None,
+                1 => EncryptMode::Aes,
+                bits => return Err(GlogError::IllegalEncryptMode(bits)),
+            });
+        }
+        GLOG_CIPHER_VERSION => {}
+        _ => return Err(GlogError::UnsupportedVersion(version)),
+    }
+
+    // V3/V4 的协议名称之后有同步标记
+    let name_offset: u64 = if version == GLOG_CIPHER_VERSION { 4 + 1 } else { 4 + 1 + 1 };
+    let trailer = if version >= GLOG_RECOVERY_VERSION { SYNC_MARKER.len() } else { 0 };
+    let (proto_name, name_len) = read_proto_name(input, name_offset, size, trailer)?;
+    let mut sync_marker = [0u8; 8];
+    read_safely(input, trailer, &mut sync_marker)?;
+    if trailer > 0 && sync_marker != SYNC_MARKER {
+        return Err(GlogError::SyncMarkerMismatch);
+    }
+
+    // V4 的压缩和加密模式记录在每条日志的模式字节中
+    let header_len = name_offset + 2 + name_len as u64 + trailer as u64;
+    if version == GLOG_CIPHER_VERSION && header_len < size {
+        if let Some(&ms) = input.first() {
+            compress_mode = match ms >> 4 {
+                1 => Some(CompressMode::None),
+                2 => Some(CompressMode::Zlib),
+                3 => Some(CompressMode::Zstd),
+                _ => None,
+            };
+            encrypt_mode = match ms & 0x0F {
+                1 => Some(EncryptMode::None),
+                2 => Some(EncryptMode::Aes),
+                _ => None,
+            };
+        }
+    }
+
+    Ok(PeekInfo {
+        version,
+        mmap,
+        proto_name,
+        compress_mode,
+        encrypt_mode,
+        file_size,
+    })
+}
+
+/// 以跟随模式打开 Glog 文件
+///
+/// 返回的读取器在读到文件末尾时不会立即失败，而是按 `poll_interval`
+/// 轮询等待写入方追加数据，用于读取正在写入的文件。
+/// 调用方需要在文件增长后通过 [`GlogReader::set_size`] 更新文件大小。
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `key` - 可选的服务器私钥
+/// * `poll_interval` - 等待新数据时的轮询间隔
+///
+/// # Returns
+/// 返回 GlogReader 实例
+#[cfg(feature = "std-fs")]
+pub fn open_follow(
+    file_path: &str,
+    key: Option<String>,
+    poll_interval: Duration,
+) -> Result<GlogReader> {
+    GlogReader::builder()
+        .optional_key(key)
+        .open_follow(file_path, poll_interval)
+}
+
+/// 跟随读取时文件当前的数据大小，用于 [`GlogReader::set_size`]
+///
+/// 普通日志文件为文件长度；mmap 缓存文件为写入游标处的偏移，
+/// 预分配的零填充不计入。写入游标损坏时返回文件长度
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+///
+/// # Returns
+/// 返回数据大小
+///
+/// # Errors
+/// 文件无法读取时返回错误
+#[cfg(feature = "std-fs")]
+pub fn follow_size(file_path: &str) -> Result<u64> {
+    let mut file = File::open(file_path)?;
+    let len = file.metadata()?.len();
+    let mut header = [0u8; MMAP_HEADER_LENGTH as usize];
+    if file.read_exact(&mut header).is_err() || header[..4] != MMAP_MAGIC_NUMBER {
+        return Ok(len);
+    }
+    let cursor = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+    Ok((MMAP_HEADER_LENGTH + cursor).min(len))
+}
+
+/// 跟随读取的输入流
+///
+/// 读到文件末尾时轮询等待新数据，而不是立即返回 0。
+/// 读取器只会在 `space_left()` 表明有完整条目时才开始读取，
+/// 因此这里只需等待写入方把条目的剩余部分写完；
+/// 超过 [`FOLLOW_WAIT_LIMIT`] 仍无数据时返回 0，由调用方按文件结束处理
+#[cfg(feature = "std-fs")]
+struct FollowInput {
+    /// 底层文件
+    file: File,
+    /// 轮询间隔
+    poll_interval: Duration,
+}
+
+/// 跟随读取时等待单次写入完成的最长时间
+#[cfg(feature = "std-fs")]
+const FOLLOW_WAIT_LIMIT: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "std-fs")]
+impl FollowInput {
+    fn new(file: File, poll_interval: Duration) -> Self {
+        Self { file, poll_interval }
+    }
+}
+
+#[cfg(feature = "std-fs")]
+impl Read for FollowInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = Instant::now();
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 || buf.is_empty() || start.elapsed() >= FOLLOW_WAIT_LIMIT {
+                return Ok(n);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+/// 由十六进制格式的服务器私钥创建密钥提供者
+///
+/// # Arguments
+/// * `keys` - 服务器私钥，多个时依次尝试
+/// * `cache_capacity` - 共享密钥缓存的容量，`None` 时使用默认容量
+///
+/// # Errors
+/// 私钥格式无效时返回错误
+fn hex_key_provider(
+    keys: &[Zeroizing<String>],
+    cache_capacity: Option<usize>,
+) -> Result<Option<Box<dyn KeyProvider>>> {
+    let capacity = cache_capacity.unwrap_or(DEFAULT_KEY_CACHE_CAPACITY);
+    match keys {
+        [] => Ok(None),
+        [key] => Ok(Some(Box::new(
+            StaticKeyProvider::from_hex(key)?.with_cache_capacity(capacity),
+        ))),
+        keys => Ok(Some(Box::new(
+            KeyRing::from_hex(keys)?.with_cache_capacity(capacity),
+        ))),
+    }
+}
+
+/// 从输入流打开版本特定的读取器
+///
+/// 从流中读取并验证魔数和版本号，然后交给版本特定的读取器继续解析。
+/// mmap 缓存文件先按写入游标截取有效数据，再从中打开版本特定的读取器
+///
+/// # Arguments
+/// * `input` - 位于文件起始位置的输入流
+/// * `size` - 数据总大小
+/// * `options` - 打开选项
+///
+/// # Returns
+/// 返回版本特定的文件读取器
+fn open_stream<R: Read + Send + 'static>(
+    input: R,
+    size: u64,
+    options: OpenOptions,
+) -> Result<Box<dyn FileReader + Send>> {
+    open_source(ReadSource::new(input), size, options)
+}
+
+/// 从字节来源打开版本特定的读取器，参见 [`open_stream`]
+///
+/// # Arguments
+/// * `input` - 位于文件起始位置的字节来源
+/// * `size` - 数据总大小
+/// * `options` - 打开选项
+///
+/// # Returns
+/// 返回版本特定的文件读取器
+fn open_source<S: ByteSource + Send + 'static>(
+    mut input: S,
+    size: u64,
+    options: OpenOptions,
+) -> Result<Box<dyn FileReader + Send>> {
     // 读取并验证魔数
     let mut magic = [0u8; 4];
-    read_safely(&mut reader, 4, &mut magic)?;
-    
+    read_safely(&mut input, 4, &mut magic)?;
+
+    if magic == MMAP_MAGIC_NUMBER {
+        let content = read_mmap_content(input, size)?;
+        let inner = open_stream(content.input, content.len, options)?;
+        return Ok(Box::new(FileReaderMmap::new(
+            inner,
+            size,
+            content.trimmed,
+            content.cursor,
+        )));
+    }
     if magic != MAGIC_NUMBER {
         return Err(GlogError::MagicMismatch);
     }
 
     // 读取版本号
     let mut version_buf = [0u8; 1];
-    read_safely(&mut reader, 1, &mut version_buf)?;
+    read_safely(&mut input, 1, &mut version_buf)?;
     let version = version_buf[0];
 
-    // 关闭当前读取器
-    drop(reader);
-    
-    // 第二次打开文件，供版本特定读取器使用
-    let file = File::open(file_path)?;
-    
-    // 创建跳过头部的读取器
-    let mut skip_reader = BufReader::new(file);
-    let mut skip_buf = [0u8; 5]; // 跳过魔数(4) + 版本(1)
-    read_safely(&mut skip_reader, 5, &mut skip_buf)?;
-
     // 根据版本号创建相应的读取器
     match version {
+        GLOG_INITIAL_VERSION => {
+            let mut file_reader = FileReaderV1::from_reader(input, size)
+                .with_max_log_length(options.max_log_length);
+            file_reader.read_remain_header()?;
+            Ok(Box::new(file_reader))
+        }
+        GLOG_FIX_POSITION_VERSION => {
+            let mut file_reader = FileReaderV2::from_reader(input, size)
+                .with_max_log_length(options.max_log_length)
+                .with_inflater(options.inflater());
+            file_reader.read_remain_header()?;
+            Ok(Box::new(file_reader))
+        }
         GLOG_RECOVERY_VERSION => {
-            let mut file_reader = FileReaderV3::from_reader(skip_reader, size);
+            let mut file_reader = FileReaderV3::from_source(input, size)
+                .with_max_log_length(options.max_log_length)
+                .with_inflater(options.inflater());
             file_reader.read_remain_header()?;
             Ok(Box::new(file_reader))
         }
         GLOG_CIPHER_VERSION => {
-            let mut file_reader = FileReaderV4::from_reader(skip_reader, size, key)?;
+            let inflater = options.inflater();
+            let mut file_reader = FileReaderV4::from_source(input, size, options.key_provider)
+                .skip_encrypted(options.skip_encrypted)
+                .with_max_log_length(options.max_log_length)
+                .with_inflater(inflater);
             file_reader.read_remain_header()?;
             Ok(Box::new(file_reader))
         }
         _ => Err(GlogError::UnsupportedVersion(version)),
     }
 }
+
+/// 内部打开文件的实现
+///
+/// 文件只打开一次：魔数和版本号从同一个 `BufReader` 中读出，之后交给版本特定的读取器继续读取，
+/// 不会在两次打开之间读到被替换的文件
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `options` - 打开选项
+///
+/// # Returns
+/// 返回版本特定的文件读取器
+#[cfg(feature = "std-fs")]
+fn open_internal(file_path: &str, options: OpenOptions) -> Result<Box<dyn FileReader + Send>> {
+    let file = File::open(file_path)?;
+    let size = file.metadata()?.len();
+    #[cfg(feature = "memmap")]
+    if options.use_mmap {
+        return open_source(crate::reader::source::map_file(&file)?, size, options);
+    }
+    open_stream(options.buffered(file), size, options)
+}
+
+/// 从指定偏移处打开文件的实现
+///
+/// 先解析一次文件头得到第一条日志的起始位置，确定续读的条目边界后，
+/// 把文件头和从该边界开始的文件内容拼接为输入流交给版本特定的读取器
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `offset` - 继续读取的文件偏移
+/// * `options` - 打开选项
+///
+/// # Returns
+/// 返回定位到条目边界的文件读取器
+#[cfg(feature = "std-fs")]
+fn open_internal_at(
+    file_path: &str,
+    offset: u64,
+    options: OpenOptions,
+) -> Result<Box<dyn FileReader + Send>> {
+    let mut file = File::open(file_path)?;
+    let size = file.metadata()?.len();
+
+    let header_len = open_stream(BufReader::new(file.try_clone()?), size, OpenOptions::default())?
+        .position();
+    let start = if offset <= header_len {
+        header_len
+    } else {
+        find_entry_boundary(&mut file, offset, size)?
+    };
+
+    let mut header = vec![0u8; header_len as usize];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)?;
+    file.seek(SeekFrom::Start(start))?;
+
+    let input = Cursor::new(header).chain(options.buffered(file));
+    let mut inner = open_stream(input, size, options)?;
+    inner.resume_at(start)?;
+    Ok(inner)
+}
+
+/// 查找不早于 `offset` 的第一个条目边界
+///
+/// # Arguments
+/// * `file` - 日志文件
+/// * `offset` - 起始偏移（大于文件头长度）
+/// * `size` - 文件大小
+///
+/// # Returns
+/// `offset` 紧跟在同步标记之后时返回 `offset`，否则返回下一个同步标记之后的偏移；
+/// 找不到时返回文件大小
+#[cfg(feature = "std-fs")]
+fn find_entry_boundary(file: &mut File, offset: u64, size: u64) -> Result<u64> {
+    if offset >= size {
+        return Ok(size);
+    }
+
+    let mut marker = [0u8; 8];
+    file.seek(SeekFrom::Start(offset - marker.len() as u64))?;
+    file.read_exact(&mut marker)?;
+    if marker == SYNC_MARKER {
+        return Ok(offset);
+    }
+
+    let (consumed, _) = scan_for_sync_marker(&mut BufReader::new(file), &[], size - offset)?;
+    Ok(offset + consumed)
+}
+
+#[cfg(all(test, feature = "std-fs"))]
+mod tests {
+    use super::*;
+    use crate::writer::v3::GlogWriterV3;
+    use std::sync::atomic::AtomicU64;
+
+    /// 统计读取调用次数和读取字节数的输入
+    struct CountingRead<R> {
+        inner: R,
+        reads: Arc<AtomicU64>,
+        bytes: Arc<AtomicU64>,
+    }
+
+    impl<R: Read> Read for CountingRead<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_open_reads_header_once_with_large_buffer() {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+        for i in 0..600 {
+            writer
+                .write_log(&Log {
+                    msg: format!("{:04} {}", i, "x".repeat(1000)),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        let content = writer.into_inner().unwrap();
+        let size = content.len() as u64;
+        let reads = Arc::new(AtomicU64::new(0));
+        let bytes = Arc::new(AtomicU64::new(0));
+        let input = CountingRead {
+            inner: Cursor::new(content),
+            reads: Arc::clone(&reads),
+            bytes: Arc::clone(&bytes),
+        };
+
+        let options = OpenOptions::default();
+        let inner = open_stream(options.buffered(input), size, options).unwrap();
+        let mut reader = GlogReader::builder().build(None, |_| Ok(inner)).unwrap();
+        assert_eq!(reader.logs().filter(Result::is_ok).count(), 600);
+
+        // 魔数和版本号只读一次，整个文件恰好读完一遍
+        assert_eq!(bytes.load(Ordering::Relaxed), size);
+        // 每次读取填满 256 KB 的缓冲区，8 KB 的默认缓冲区需要约 80 次
+        let max_reads = size.div_ceil(DEFAULT_BUFFER_CAPACITY as u64) + 1;
+        assert!(reads.load(Ordering::Relaxed) <= max_reads);
+    }
+}
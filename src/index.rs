@@ -0,0 +1,403 @@
+//! # 日志索引
+//!
+//! 对同一个大压缩包反复按不同条件过滤时，每次都要重复解密、解压和 protobuf 解码。
+//! [`LogIndex`] 为每个日志文件记录每条日志的条目偏移、毫秒时间戳、类型、级别、进程 ID
+//! 和标签哈希，保存为紧凑的二进制文件。之后按索引判断条目是否可能满足过滤条件
+//! （[`IndexEntry::may_match`]），不可能满足的条目用 [`GlogReader::skip_entry`] 跳过，
+//! 不再解码。
+//!
+//! ## 文件格式（版本 1）
+//!
+//! 整数除标签哈希外均为 varint，有符号数先做 zigzag 编码：
+//!
+//! ```text
+//! 魔数 "GIDX" (4) | 版本 (1) | 文件数
+//! 每个文件：文件名长度 | 文件名 (UTF-8) | 文件大小 | 条目数 | 条目...
+//! 每个条目：偏移增量 | 标志 | [时间戳增量] | 类型 | 级别 | 进程 ID | 标签哈希 (8，小端序)
+//! ```
+//!
+//! 偏移和时间戳相对上一条目存储增量；标志的最低位表示时间戳能否解析。
+
+use std::io::{Read, Write};
+
+use crate::error::{GlogError, Result};
+use crate::glog::GlogReader;
+use crate::proto::{Level, Log, LogFilter, Pattern};
+use crate::reader::{read_safely, read_u64_le, read_varint};
+
+/// 索引文件的魔数
+pub const INDEX_MAGIC: [u8; 4] = *b"GIDX";
+
+/// 当前的索引格式版本
+pub const INDEX_VERSION: u8 = 1;
+
+/// 条目标志：时间戳能够解析
+const FLAG_HAS_TIMESTAMP: u64 = 0x01;
+
+/// 文件名的最大长度
+const MAX_NAME_LENGTH: u64 = 4096;
+
+/// 一条日志的索引
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// 条目在文件中的起始偏移
+    pub offset: u64,
+    /// 毫秒时间戳，参见 [`Log::timestamp_millis`]
+    pub timestamp: Option<i64>,
+    /// 日志类型
+    pub log_type: i32,
+    /// 日志级别的原始整数
+    pub log_level: i32,
+    /// 进程 ID
+    pub pid: i32,
+    /// 标签的哈希，参见 [`tag_hash`]
+    pub tag_hash: u64,
+}
+
+impl IndexEntry {
+    /// 为一条解码后的日志建立索引
+    ///
+    /// # Arguments
+    /// * `offset` - 条目在文件中的起始偏移
+    /// * `log` - 日志
+    pub fn from_log(offset: u64, log: &Log) -> Self {
+        Self {
+            offset,
+            timestamp: log.timestamp_millis(),
+            log_type: log.log_type,
+            log_level: log.log_level,
+            pid: log.pid,
+            tag_hash: tag_hash(&log.tag),
+        }
+    }
+
+    /// 该日志是否可能满足过滤条件
+    ///
+    /// 只检查索引中有的字段：类型、级别、时间范围、进程 ID，以及精确匹配的标签；
+    /// 通配符标签、线程 ID 和内容正则无法判断，视为可能满足。
+    /// 返回 `false` 时日志一定不满足过滤条件（标签哈希碰撞除外）
+    pub fn may_match(&self, filter: &LogFilter) -> bool {
+        let level = Level::from_i32(self.log_level);
+        filter.matches_type(self.log_type)
+            && filter
+                .min_level
+                .is_none_or(|min| level.severity() >= min.severity())
+            && filter.matches_millis(self.timestamp)
+            && filter.pid.is_none_or(|pid| self.pid == pid)
+            && (filter.tags.is_empty() || filter.tags.iter().any(|p| self.may_match_tag(p)))
+            && !filter
+                .exclude_tags
+                .iter()
+                .any(|p| matches!(p, Pattern::Exact(tag) if tag_hash(tag) == self.tag_hash))
+    }
+
+    /// 标签是否可能匹配，只有精确匹配的模式可以按哈希判断
+    fn may_match_tag(&self, pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Exact(tag) => tag_hash(tag) == self.tag_hash,
+            Pattern::Wildcard(_) => true,
+        }
+    }
+}
+
+/// 单个日志文件的索引
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIndex {
+    /// 文件名（不含目录）
+    pub name: String,
+    /// 建立索引时的文件大小，用于判断文件是否已经变化
+    pub size: u64,
+    /// 按偏移递增排列的条目，解码失败的条目不在其中
+    pub entries: Vec<IndexEntry>,
+}
+
+impl FileIndex {
+    /// 读取整个日志文件并建立索引
+    ///
+    /// 解码失败的条目不记入索引；读取错误（包括严格模式下的 `NeedRecover`）时停止，
+    /// 已经读到的条目仍然保留，之后的条目在使用索引时按常规方式读取
+    ///
+    /// # Arguments
+    /// * `name` - 文件名
+    /// * `size` - 文件大小
+    /// * `reader` - 位于第一条日志的读取器
+    ///
+    /// # Returns
+    /// 返回索引和读取中途遇到的错误
+    pub fn build(
+        name: impl Into<String>,
+        size: u64,
+        reader: &mut GlogReader,
+    ) -> (Self, Option<GlogError>) {
+        let mut index = Self {
+            name: name.into(),
+            size,
+            entries: Vec::new(),
+        };
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_entry(&mut buf) {
+                Ok(Some(entry)) if entry.len == 0 => continue,
+                Ok(Some(entry)) => {
+                    if let Ok(log) = reader.decode_log(&buf[..entry.len]) {
+                        index.entries.push(IndexEntry::from_log(entry.offset, &log));
+                    }
+                }
+                Ok(None) => return (index, None),
+                Err(e) => return (index, Some(e)),
+            }
+        }
+    }
+
+    /// 查找起始于 `offset` 的条目
+    pub fn lookup(&self, offset: u64) -> Option<&IndexEntry> {
+        self.entries
+            .binary_search_by_key(&offset, |entry| entry.offset)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+}
+
+/// 一组日志文件的索引
+///
+/// ```
+/// use clog_reader::index::{FileIndex, IndexEntry, LogIndex};
+/// use clog_reader::proto::Log;
+///
+/// let log = Log { tag: "Net".to_string(), ..Log::new() };
+/// let index = LogIndex {
+///     files: vec![FileIndex {
+///         name: "async-20240101.glog".to_string(),
+///         size: 1024,
+///         entries: vec![IndexEntry::from_log(12, &log)],
+///     }],
+/// };
+///
+/// let mut bytes = Vec::new();
+/// index.write_to(&mut bytes)?;
+/// assert_eq!(LogIndex::read_from(&mut bytes.as_slice())?, index);
+/// # Ok::<(), clog_reader::GlogError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogIndex {
+    /// 各文件的索引
+    pub files: Vec<FileIndex>,
+}
+
+impl LogIndex {
+    /// 查找文件的索引，文件名和大小都一致时才返回
+    ///
+    /// # Arguments
+    /// * `name` - 文件名（不含目录）
+    /// * `size` - 当前的文件大小
+    pub fn file(&self, name: &str, size: u64) -> Option<&FileIndex> {
+        self.files
+            .iter()
+            .find(|file| file.name == name && file.size == size)
+    }
+
+    /// 写入索引
+    ///
+    /// # Errors
+    /// 只传递写入错误
+    pub fn write_to<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&INDEX_MAGIC);
+        buf.push(INDEX_VERSION);
+        put_varint(&mut buf, self.files.len() as u64);
+        for file in &self.files {
+            put_varint(&mut buf, file.name.len() as u64);
+            buf.extend_from_slice(file.name.as_bytes());
+            put_varint(&mut buf, file.size);
+            put_varint(&mut buf, file.entries.len() as u64);
+            let (mut offset, mut timestamp) = (0u64, 0i64);
+            for entry in &file.entries {
+                put_varint(&mut buf, entry.offset.wrapping_sub(offset));
+                offset = entry.offset;
+                match entry.timestamp {
+                    Some(ts) => {
+                        put_varint(&mut buf, FLAG_HAS_TIMESTAMP);
+                        put_signed(&mut buf, ts.wrapping_sub(timestamp));
+                        timestamp = ts;
+                    }
+                    None => put_varint(&mut buf, 0),
+                }
+                put_signed(&mut buf, entry.log_type.into());
+                put_signed(&mut buf, entry.log_level.into());
+                put_signed(&mut buf, entry.pid.into());
+                buf.extend_from_slice(&entry.tag_hash.to_le_bytes());
+            }
+            // 分段写出，避免大索引整体缓存在内存中
+            out.write_all(&buf)?;
+            buf.clear();
+        }
+        out.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// 读取索引
+    ///
+    /// # Errors
+    /// 魔数或版本不匹配时返回 [`GlogError::IndexError`]，数据不完整时返回 `UnexpectedEof`
+    pub fn read_from<R: Read>(input: &mut R) -> Result<Self> {
+        let mut header = [0u8; 5];
+        read_safely(input, header.len(), &mut header)?;
+        if header[..4] != INDEX_MAGIC {
+            return Err(GlogError::IndexError(
+                "不是 clog-reader 索引文件".to_string(),
+            ));
+        }
+        if header[4] != INDEX_VERSION {
+            return Err(GlogError::IndexError(format!(
+                "不支持的索引版本 {}（当前版本 {}），请重新生成索引",
+                header[4], INDEX_VERSION
+            )));
+        }
+
+        let file_count = read_varint(input)?;
+        let mut files = Vec::new();
+        for _ in 0..file_count {
+            let name_len = read_varint(input)?;
+            if name_len > MAX_NAME_LENGTH {
+                return Err(GlogError::IndexError(format!("文件名过长: {}", name_len)));
+            }
+            let mut name = vec![0u8; name_len as usize];
+            read_safely(input, name.len(), &mut name)?;
+            let name = String::from_utf8(name)
+                .map_err(|_| GlogError::IndexError("文件名不是有效的 UTF-8".to_string()))?;
+            let size = read_varint(input)?;
+            let entry_count = read_varint(input)?;
+            let mut entries = Vec::new();
+            let (mut offset, mut timestamp) = (0u64, 0i64);
+            for _ in 0..entry_count {
+                offset = offset.wrapping_add(read_varint(input)?);
+                let flags = read_varint(input)?;
+                let entry_timestamp = if flags & FLAG_HAS_TIMESTAMP != 0 {
+                    timestamp = timestamp.wrapping_add(read_signed(input)?);
+                    Some(timestamp)
+                } else {
+                    None
+                };
+                entries.push(IndexEntry {
+                    offset,
+                    timestamp: entry_timestamp,
+                    log_type: read_i32(input)?,
+                    log_level: read_i32(input)?,
+                    pid: read_i32(input)?,
+                    tag_hash: read_u64_le(input)?,
+                });
+            }
+            files.push(FileIndex {
+                name,
+                size,
+                entries,
+            });
+        }
+        Ok(Self { files })
+    }
+}
+
+/// 标签的哈希，使用固定的 FNV-1a，不同版本、不同平台上结果相同
+pub fn tag_hash(tag: &str) -> u64 {
+    tag.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// 写入 varint
+fn put_varint(buf: &mut Vec<u8>, value: u64) {
+    prost::encoding::encode_varint(value, buf);
+}
+
+/// zigzag 编码后写入 varint
+fn put_signed(buf: &mut Vec<u8>, value: i64) {
+    put_varint(buf, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+/// 读取 zigzag 编码的 varint
+fn read_signed<R: Read>(input: &mut R) -> Result<i64> {
+    let value = read_varint(input)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+/// 读取 zigzag 编码的 32 位整数
+fn read_i32<R: Read>(input: &mut R) -> Result<i32> {
+    let value = read_signed(input)?;
+    i32::try_from(value).map_err(|_| GlogError::IndexError(format!("整数超出范围: {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(offset: u64, timestamp: Option<i64>, log_level: i32, tag: &str) -> IndexEntry {
+        IndexEntry {
+            offset,
+            timestamp,
+            log_type: 1,
+            log_level,
+            pid: -7,
+            tag_hash: tag_hash(tag),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let index = LogIndex {
+            files: vec![
+                FileIndex {
+                    name: "async-20240101.glog".to_string(),
+                    size: 1 << 33,
+                    entries: vec![
+                        entry(12, Some(1_704_067_200_000), 4, "Net"),
+                        entry(80, None, 0, ""),
+                        // 时间戳后退
+                        entry(150, Some(1_600_000_000_000), i32::MIN, "Ui"),
+                    ],
+                },
+                FileIndex::default(),
+            ],
+        };
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..5], b"GIDX\x01");
+        assert_eq!(LogIndex::read_from(&mut bytes.as_slice()).unwrap(), index);
+
+        assert!(index.file("async-20240101.glog", 1 << 33).is_some());
+        assert!(index.file("async-20240101.glog", 1).is_none());
+        assert_eq!(index.files[0].lookup(80).map(|e| e.log_level), Some(0));
+        assert!(index.files[0].lookup(81).is_none());
+
+        // 版本不匹配、魔数错误和截断的数据
+        bytes[4] = 2;
+        let err = LogIndex::read_from(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, GlogError::IndexError(_)), "{err}");
+        assert!(LogIndex::read_from(&mut &b"GLOG\x01"[..]).is_err());
+        bytes[4] = INDEX_VERSION;
+        let truncated = &bytes[..bytes.len() - 3];
+        assert!(LogIndex::read_from(&mut &truncated[..]).is_err());
+    }
+
+    #[test]
+    fn test_may_match() {
+        let error = entry(0, Some(1000), Level::Error as i32, "Net");
+        let info = entry(0, None, Level::Info as i32, "Ui");
+
+        assert!(error.may_match(&LogFilter::new()));
+        assert!(!error.may_match(&LogFilter::new().types([2])));
+        let filter = LogFilter::new().min_level(Level::Warn);
+        assert!(error.may_match(&filter) && !info.may_match(&filter));
+
+        let filter = LogFilter::new().time_range(Some(500), Some(1500));
+        assert!(error.may_match(&filter) && !info.may_match(&filter));
+        assert!(!error.may_match(&LogFilter::new().time_range(Some(2000), None)));
+
+        let filter = LogFilter::new().tag("Net");
+        assert!(error.may_match(&filter) && !info.may_match(&filter));
+        // 通配符无法按哈希判断
+        assert!(info.may_match(&LogFilter::new().tag("N*")));
+        let filter = LogFilter::new().exclude_tag(Pattern::Exact("Net".to_string()));
+        assert!(!error.may_match(&filter) && info.may_match(&filter));
+        assert!(!error.may_match(&LogFilter::new().pid(1)));
+    }
+}
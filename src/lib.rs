@@ -4,8 +4,8 @@
 //!
 //! ## 功能特性
 //!
-//! - 支持 Glog V3（恢复版本）和 V4（加密版本）文件格式
-//! - 支持 zlib 压缩的日志数据解压
+//! - 支持 Glog V3（恢复版本）和 V4（加密版本）文件格式，以及已废弃的 V1/V2 格式
+//! - 支持 zlib 和 zstd 压缩的日志数据解压
 //! - 支持 AES-128-CFB 加密的日志数据解密
 //! - 使用 secp256k1 椭圆曲线进行 ECDH 密钥交换
 //! - 支持 Protobuf 格式的日志消息解析
@@ -13,7 +13,7 @@
 //! ## 快速开始
 //!
 //! ```rust,no_run
-//! use clog_reader::{glog, proto::Log, error::ReadResult};
+//! use clog_reader::glog;
 //!
 //! fn main() -> anyhow::Result<()> {
 //!     // 打开日志文件（带解密密钥）
@@ -22,21 +22,11 @@
 //!         Some("your_private_key_hex".to_string())
 //!     )?;
 //!
-//!     // 读取日志
-//!     let mut buf = vec![0u8; glog::GlogReader::single_log_max_length()];
-//!     
-//!     loop {
-//!         match reader.read(&mut buf)? {
-//!             ReadResult::Success(len) => {
-//!                 // 解析 protobuf 日志
-//!                 let log = Log::decode_from(&buf[..len])?;
-//!                 println!("{}", log.format());
-//!             }
-//!             ReadResult::Eof => break,
-//!             ReadResult::NeedRecover(code) => {
-//!                 eprintln!("需要恢复，错误码: {}", code);
-//!                 continue;
-//!             }
+//!     // 逐条读取并解码日志（默认跳过需要恢复的损坏条目）
+//!     for log in reader.logs() {
+//!         match log {
+//!             Ok(log) => println!("{}", log.format()),
+//!             Err(e) => eprintln!("读取日志失败: {}", e),
 //!         }
 //!     }
 //!
@@ -44,13 +34,43 @@
 //! }
 //! ```
 //!
+//! 需要自行管理缓冲区或处理原始条目时，也可以直接调用 [`GlogReader::read`]。
+//!
 //! ## 模块结构
 //!
 //! - [`error`] - 错误类型定义
 //! - [`version`] - Glog 版本常量
 //! - [`reader`] - 文件读取器实现
 //! - [`glog`] - 主读取器接口
+//! - [`merge`] - 多文件按时间戳合并读取
+//! - [`observer`] - 读取进度和计数的观察者
+//! - `archive` - 直接读取日志 ZIP 压缩包（需要启用 `std-fs` 特性）
+//! - [`salvage`] - 文件头损坏时按同步标记抢救日志
+//! - [`text`] - 把读取器包装为输出格式化文本的 `io::Read`
+//! - [`redact`] - 输出前按正则替换日志中的敏感信息
+//! - [`analysis`] - 时间戳跳变等日志分析
+//! - [`index`] - 加速重复过滤的日志索引文件
+//! - `async_reader` - 基于 tokio `AsyncRead` 的异步读取器（需要启用 `async` 特性）
+//! - [`writer`] - 文件写入器实现（用于生成测试数据）
 //! - [`proto`] - Protobuf 日志消息定义
+//! - [`format`] - 日志行格式化
+//! - `dynamic` - 按 `FileDescriptorSet` 动态解码（需要启用 `dynamic` 特性）
+//! - `wasm` - WebAssembly 接口（需要启用 `wasm` 特性）
+//! - `ffi` - Kotlin / Swift 的 UniFFI 接口（需要启用 `uniffi` 特性）
+//!
+//! ## 特性
+//!
+//! - `std-fs`（默认）- 按路径打开文件、跟随模式、ZIP 解压等文件系统相关功能；
+//!   关闭后解码核心只依赖 [`GlogReader::from_reader`]，可以编译到 `wasm32-unknown-unknown`
+//! - `rayon`（默认）- 通过 `rayon` 线程池并行解码多个日志文件（`archive::decode_parallel`），
+//!   命令行工具的 `--threads` 需要
+//! - `zstd`（默认）- 通过 C 版 libzstd 解压较新写入端生成的 zstd 日志；关闭后这些日志按解压失败处理
+//! - `dynamic` - 通过 `prost-reflect` 按文件头中的消息名称动态解码日志
+//! - `wasm` - 通过 `wasm-bindgen` 暴露 `GlogWasmReader`
+//! - `uniffi` - 通过 UniFFI 暴露 `GlogFileReader`，用于 Android 应用内查看日志
+//! - `sqlite` - 命令行工具的 SQLite 输出
+//! - `async` - 通过 tokio 的 `AsyncRead` 读取日志的 `AsyncGlogReader`
+//! - `memmap` - `GlogReaderBuilder::use_mmap`，把日志文件映射到内存读取
 
 /// 错误处理模块
 pub mod error;
@@ -61,16 +81,83 @@ pub mod version;
 /// 文件读取器模块
 pub mod reader;
 
+/// 读取观察者模块
+pub mod observer;
+
 /// Glog 读取器模块
 pub mod glog;
 
+/// 多文件合并读取模块
+pub mod merge;
+
+/// 日志压缩包模块
+#[cfg(feature = "std-fs")]
+pub mod archive;
+
+/// 文件头损坏时的日志抢救模块
+pub mod salvage;
+
+/// 纯文本输入流模块
+pub mod text;
+
+/// 敏感信息脱敏模块
+pub mod redact;
+
+/// 日志分析模块
+pub mod analysis;
+
+/// 日志索引模块
+pub mod index;
+
+/// 异步读取模块
+#[cfg(feature = "async")]
+pub mod async_reader;
+
+/// 文件写入器模块
+pub mod writer;
+
 /// Protobuf 日志消息模块
 pub mod proto;
 
+/// 日志格式化模块
+pub mod format;
+
+/// 动态 Protobuf 解码模块
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
+
+/// WebAssembly 接口模块
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// UniFFI 接口模块（Kotlin / Swift）
+#[cfg(feature = "uniffi")]
+pub mod ffi;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
 // 重新导出常用类型
-pub use error::{GlogError, Result, ReadResult};
-pub use glog::{GlogReader, open, open_with_key};
-pub use proto::Log;
+pub use analysis::{find_time_jumps, TimeJump, TimeJumpDetector};
+pub use error::{GlogError, ReadResult, RecoverReason, Result};
+pub use glog::{
+    EntryInfo, EntryOffset, GlogReader, GlogReaderBuilder, PeekInfo, RecoveryPolicy,
+    DEFAULT_BUFFER_CAPACITY,
+};
+#[cfg(feature = "std-fs")]
+pub use glog::{open, open_with_key, peek};
+pub use index::{FileIndex, IndexEntry, LogIndex};
+pub use merge::MergedGlogReader;
+pub use observer::{MetricsObserver, Observer, ReadStats};
+pub use format::{DefaultFormatter, LogFormatter, LogfmtFormatter, Multiline, MultilineMode};
+pub use proto::{ByTimestamp, Log, LogTypeRegistry};
+pub use reader::key::{
+    KeyCacheStats, KeyProvider, KeyRing, StaticKeyProvider, DEFAULT_KEY_CACHE_CAPACITY,
+};
+pub use redact::Redactor;
+pub use text::GlogTextStream;
+#[cfg(feature = "async")]
+pub use async_reader::AsyncGlogReader;
 
 /// 库版本信息
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
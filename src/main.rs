@@ -9,30 +9,214 @@
 //! # 基本用法：解析日志 ZIP 文件
 //! clog-reader -i <日志.zip>
 //!
+//! # 从标准输入读取 ZIP 压缩包或单个 glog 文件
+//! curl -s <URL> | clog-reader -i -
+//!
 //! # 按日志类型过滤
 //! clog-reader -i <日志.zip> -t 0,1,2
 //!
+//! # 只输出指定字段
+//! clog-reader -i <日志.zip> --fields ts,level,msg
+//!
+//! # 标注每条日志的来源文件和条目偏移
+//! clog-reader -i <日志.zip> --show-source
+//!
+//! # 只处理指定日期范围内的 glog 文件
+//! clog-reader -i <日志.zip> --file-date-from 20240101 --file-date-to 20240102
+//!
+//! # 按正则识别自定义前缀的 glog 文件
+//! clog-reader -i <日志.zip> --file-pattern '^applog_\d{8}\.glog$'
+//!
+//! # 严格模式：遇到任何解码异常即停止处理该文件
+//! clog-reader -i <日志.zip> --strict
+//!
+//! # 跟随正在写入的日志文件，持续输出新日志
+//! clog-reader --follow <async-20240101.glog>
+//!
+//! # 覆盖或追加已存在的输出文件
+//! clog-reader -i <日志.zip> --force
+//! clog-reader -i <日志.zip> --append
+//!
+//! # 只统计匹配的日志条数，不生成输出文件
+//! clog-reader -i <日志.zip> -t 4 --count
+//!
+//! # 折叠连续重复的日志
+//! clog-reader -i <日志.zip> --dedup-consecutive
+//!
+//! # 转储解码失败的条目
+//! clog-reader -i <日志.zip> --dump-failed failed/
+//!
+//! # 导出逐文件读取报告
+//! clog-reader -i <日志.zip> --report report.json
+//!
+//! # 把每个错误的文件、偏移、错误码和恢复原因导出为 JSON 数组（check 子命令同样支持）
+//! clog-reader -i <日志.zip> --errors-json errors.json
+//!
+//! # 单个输出文件超过 1G 后轮转到新的分段文件
+//! clog-reader -i <日志.zip> --max-output-size 1G
+//!
+//! # 限制从 ZIP 中解压的总大小
+//! clog-reader -i <日志.zip> --max-extract-size 512M
+//!
+//! # 直接读取 HTTP(S) 地址（需要启用 http 功能）
+//! clog-reader -i https://logs.example.com/logs.zip --http-header 'Authorization: Bearer <令牌>'
+//!
+//! # 导出到 SQLite 数据库（需要启用 sqlite 功能）
+//! clog-reader -i <日志.zip> --format sqlite -o logs.db
+//!
+//! # 输出 logfmt 行，供日志平台解析
+//! clog-reader -i <日志.zip> --format logfmt -o logs.logfmt
+//!
+//! # 导出为 Chrome Trace Event JSON，在 chrome://tracing 或 Perfetto UI 中按时间线查看
+//! clog-reader -i <日志.zip> --format trace-json -o trace.json
+//!
+//! # 输出 Elasticsearch _bulk 接口的 NDJSON，文档 ID 固定，可重复导入
+//! clog-reader -i <日志.zip> --format es-bulk --index my-logs -o logs.ndjson
+//!
+//! # 按描述符解码文件头中记录的其他消息类型（需要启用 dynamic 功能）
+//! clog-reader -i <日志.zip> --descriptor-set schema.desc
+//!
+//! # 按 TOML 映射在输出中显示日志类型名称
+//! clog-reader -i <日志.zip> --type-map types.toml
+//!
+//! # 在多行内容（如堆栈）的每一行重复时间、级别等前缀，便于按行 grep
+//! clog-reader -i <日志.zip> --multiline prefix
+//!
+//! # 统计各级别、类型、标签的条数，并生成自包含的 HTML 报告
+//! clog-reader -i <日志.zip> --count --stats --report-html report.html
+//!
+//! # 检测设备时钟跳变（后退超过 1 秒或前跳超过 1 小时），并在输出中插入标记行
+//! clog-reader -i <日志.zip> --detect-time-jumps --mark-time-jumps
+//!
+//! # 确定性地抽样 1% 的日志，并始终保留 Warn / Error
+//! clog-reader -i <日志.zip> --sample 0.01 --sample-keep-errors
+//!
+//! # 输出前把手机号替换为 <phone>（可重复指定多条规则）
+//! clog-reader -i <日志.zip> --redact '1[3-9]\d{9}=<phone>'
+//!
+//! # 原样输出 length-delimited 的 protobuf 条目
+//! clog-reader -i <日志.zip> --format raw -o logs.bin
+//!
+//! # 使用 4 个线程并行解码（默认使用全部 CPU 核心）
+//! clog-reader -i <日志.zip> --threads 4
+//!
+//! # 校验日志压缩包的完整性
+//! clog-reader check -i <日志.zip>
+//!
+//! # 查看日志文件的版本、协议、压缩/加密模式和大小
+//! clog-reader info -i <日志.zip>
+//!
+//! # 文件头损坏时按同步标记抢救日志
+//! clog-reader salvage -i <async-20240101.glog> -o salvaged.log
+//!
+//! # 把加密的 V4 文件解密后重写为 V3 文件（可选 --compress zlib 重新压缩），读取时不再需要私钥
+//! clog-reader convert -i <加密.glog> -o plain.glog --key <私钥>
+//!
+//! # 用旧私钥解密 V4 文件，再针对新的服务器公钥重新加密，压缩模式和日志顺序不变
+//! clog-reader reencrypt -i old.glog -o new.glog --key <旧私钥> --new-public-key <新公钥>
+//!
+//! # 只去掉 V4 文件的加密，压缩数据原样保留（不解压也不重新压缩），读取时不再需要私钥
+//! clog-reader decrypt -i v4.glog -o v4-plain.glog --key <私钥>
+//!
+//! # 把压缩包中的全部日志按时间戳排序，合并为单个 V3 文件（可选 --compress zlib 重新压缩）
+//! clog-reader repack -i <日志.zip> -o merged.glog --compress zlib
+//!
+//! # 按日志时间戳（本地日期）把单个文件拆分为 outdir/async-YYYYMMDD.glog，时间戳无法解析的写入 unknown.glog
+//! clog-reader split -i async-all.glog --by day -o outdir/
+//!
+//! # 建立索引，之后按不同条件过滤时跳过不可能匹配的日志
+//! clog-reader index -i <日志.zip> -o logs.idx
+//! clog-reader -i <日志.zip> --use-index logs.idx -t 4
+//!
+//! # 读取解压后的日志目录，或单个 glog 文件
+//! clog-reader -i <日志目录>
+//! clog-reader -i <async-20240101.glog>
+//!
+//! # 通过 adb 从已连接的设备拉取应用日志并提取
+//! clog-reader adb --package com.example.app --serial emulator-5554
+//!
+//! # 在终端中交互式浏览日志（需要启用 tui 功能）
+//! clog-reader view -i <日志.zip>
+//!
+//! # 监视目录，新出现的 ZIP / glog 文件停止增长后提取到 outdir/<文件名>.txt，已处理的文件记录在状态文件中
+//! clog-reader watch incoming/ -o outdir/
+//!
+//! # 生成 Shell 自动补全脚本
+//! clog-reader completions bash > /etc/bash_completion.d/clog-reader
+//!
 //! # 显示帮助信息
 //! clog-reader -h
 //! ```
+//!
+//! ## 退出码
+//!
+//! - `0` - 所有日志文件均解析成功
+//! - `1` - 致命错误（参数错误、输入文件无法读取等）
+//! - `2` - 至少有一个日志文件解析失败或触发了恢复
+//! - `3` - 未找到任何日志文件
+//! - `130` - 被 Ctrl-C 中断（中断前解码的日志和统计仍会写出）
 
 use anyhow::{Context, Result};
-use clap::Parser;
-use std::fs::{self, File};
-use std::io::{self, BufWriter, Write};
+use chrono::NaiveDate;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use prost::Message;
+use std::collections::{btree_map, BTreeMap};
+use std::fs;
+use std::io::{self, BufWriter, Cursor, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 // use log::{info, warn, error};
-use walkdir::WalkDir;
-use zip::ZipArchive;
 
+use cli::adb::Adb;
+use cli::dedup::Deduper;
+use cli::dump;
+use cli::errors::{self, ErrorStage, FileError};
+use cli::es::EsBulkWriter;
+use cli::format::LineFormatter;
+use cli::html::HtmlReport;
+#[cfg(feature = "http")]
+use cli::http;
+use cli::progress::ProgressObserver;
+use cli::report::{self, FileReport, ReportObserver};
+use cli::raw::RawWriter;
+use cli::rotate::RotatingWriter;
+use cli::sample::Sampler;
+use cli::sink::{LogSink, Source};
+#[cfg(feature = "sqlite")]
+use cli::sqlite::SqliteWriter;
+use cli::stats::{self, Stats};
+use cli::trace::TraceWriter;
+use cli::watch::{self, DirWatcher, FileStamp, WatchError, WatchState};
+use cli::{
+    AdbArgs, CheckArgs, Cli, Command, ConvertArgs, DecryptArgs, ExtractArgs, IndexArgs, InfoArgs,
+    OutputFormat, ReencryptArgs, RepackArgs, SalvageArgs, SplitArgs, SplitBy, ViewArgs, WatchArgs,
+    DEFAULT_OUTPUT,
+};
+#[cfg(feature = "dynamic")]
+use clog_reader::dynamic::DescriptorSet;
 use clog_reader::{
-    error::ReadResult,
-    glog::{open_with_key, GlogReader},
-    proto::Log,
+    analysis::TimeJumpDetector,
+    archive::{self, ArchiveEntryInfo, ArchiveEntryKind, FileNamePattern, GlogArchive},
+    error::GlogError,
+    glog::{follow_size, peek, validate, EntryInfo, GlogReader, GlogReaderBuilder, RecoveryPolicy},
+    index::{FileIndex, LogIndex},
+    merge::MergedGlogReader,
+    observer::{Observer, ReadStats},
+    proto::{Log, LogFilter, LogTypeOnly, LogTypeRegistry, Pattern},
+    redact::Redactor,
+    reader::MAGIC_NUMBER,
+    salvage,
+    version::{GLOG_CIPHER_VERSION, GLOG_RECOVERY_VERSION},
+    writer::{v3::GlogWriterV3, v4::GlogWriterV4},
 };
 
+mod cli;
+
 /// 宏：打印到 stdout 并立即刷新，确保在 macOS 管道模式下输出能被及时捕获
 macro_rules! print_flush {
     ($($arg:tt)*) => {{
@@ -53,52 +237,435 @@ macro_rules! eprint_flush {
     }};
 }
 
-/// 服务器私钥（用于解密加密的日志）
-const SVR_PRIV_KEY: &str = "1C74B66FCB1C54FD4386173CFAF3BC53C8DF6B89F799DE1A1E7CEBBC43CBFD38";
+/// 退出码：所有日志文件均解析成功
+const EXIT_OK: i32 = 0;
+
+/// 退出码：致命错误（参数错误、输入无法读取等）
+const EXIT_FATAL: i32 = 1;
+
+/// 退出码：部分日志文件解析失败或需要恢复
+const EXIT_PARTIAL: i32 = 2;
+
+/// 退出码：未找到任何日志文件
+const EXIT_NO_FILES: i32 = 3;
+
+/// 退出码：被 Ctrl-C 中断
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// 跟随模式下等待文件增长的轮询间隔
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 表示从标准输入读取的输入路径
+const STDIN_INPUT: &str = "-";
+
+/// ZIP 文件的魔数（本地文件头签名）
+const ZIP_MAGIC: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
+
+/// 从输入中收集到的日志文件
+#[derive(Default)]
+struct LogFiles {
+    /// 按处理顺序排列的日志文件
+    paths: Vec<PathBuf>,
+    /// 无法解压的压缩包条目，计入最终的读取报告
+    failures: Vec<FileReport>,
+}
+
+impl LogFiles {
+    /// 只包含已就位的日志文件
+    fn from_paths(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths,
+            failures: Vec::new(),
+        }
+    }
+}
 
-/// CLog Reader 命令行参数
-#[derive(Parser, Debug)]
-#[command(name = "clog-reader")]
-#[command(author = "CLog Reader Team")]
-#[command(version = env!("CARGO_PKG_VERSION"))]
-#[command(about = "读取和解析 Glog 格式日志文件的工具", long_about = None)]
-struct Args {
-    /// 日志 ZIP 文件路径
-    #[arg(short = 'i', long = "input", required = true)]
-    input: String,
+/// 按文件名日期筛选 glog 文件的范围（闭区间）
+#[derive(Debug, Default)]
+struct FileDateRange {
+    /// 起始日期（包含）
+    from: Option<NaiveDate>,
+    /// 结束日期（包含）
+    to: Option<NaiveDate>,
+}
 
-    /// 过滤日志类型（逗号分隔，如 0,1,2）
-    #[arg(short = 't', long = "type", default_value = "")]
-    log_types: String,
+impl FileDateRange {
+    /// 是否未设置任何边界
+    fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
 
-    /// 输出文件路径（默认为当前目录下的 log_output.txt）
-    #[arg(short = 'o', long = "output", default_value = "log_output.txt")]
-    output: String,
+    /// 日期是否在范围内
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+    }
 }
 
-fn main() -> Result<()> {
-    let start_time = Instant::now();
+/// 日志读取选项
+#[derive(Debug, Default)]
+struct ReadOptions {
+    /// 日志过滤条件
+    filter: LogFilter,
+    /// 是否启用严格模式
+    strict: bool,
+    /// 是否折叠连续重复的日志
+    dedup_consecutive: bool,
+    /// 仅统计匹配的日志条数，不格式化也不输出
+    count_only: bool,
+    /// 对通过过滤的日志抽样（`--sample` / `--every`）
+    sampler: Option<Sampler>,
+    /// 时间戳跳变检测器模板，每个文件复制一份（`--detect-time-jumps`）
+    time_jumps: Option<TimeJumpDetector>,
+    /// 是否在文本输出中插入时间戳跳变标记
+    mark_time_jumps: bool,
+    /// 是否统计通过过滤的日志（`--stats` / `--report-html`）
+    collect_stats: bool,
+    /// 跳过不可能匹配的日志所用的索引（`--use-index`）
+    index: Option<Arc<LogIndex>>,
+    /// 日志行格式化器
+    formatter: LineFormatter,
+    /// 是否标注每条日志的来源文件和条目偏移（`--show-source`）
+    show_source: bool,
+    /// 输出格式
+    format: OutputFormat,
+    /// `--format es-bulk` 的目标索引名
+    es_index: String,
+    /// 来源压缩包名（仅文件名），参与 `--format es-bulk` 文档 ID 的计算
+    archive_name: String,
+    /// 解码失败条目的转储目录
+    dump_failed: Option<PathBuf>,
+    /// 动态解码使用的 FileDescriptorSet 内容
+    #[cfg_attr(not(feature = "dynamic"), allow(dead_code))]
+    descriptor_set: Option<Vec<u8>>,
+    /// 服务器私钥（十六进制）
+    key: String,
+    /// 日志类型名称表（`--type-map`）
+    types: Option<Arc<LogTypeRegistry>>,
+    /// 输出前的脱敏规则（`--redact`）
+    redactor: Option<Arc<Redactor>>,
+    /// 取消标志，按下 Ctrl-C 时置位
+    cancel: Arc<AtomicBool>,
+}
+
+impl ReadOptions {
+    /// 是否可以只解码日志类型字段
+    ///
+    /// 计数模式下如果只有日志类型过滤，无需解码完整的日志消息；
+    /// 严格模式需要验证每条日志都能完整解码，抽样和统计需要日志级别等字段，
+    /// 时间戳跳变检测需要每条日志的时间戳，因此不走快速路径
+    fn type_only_decode(&self) -> bool {
+        self.count_only
+            && !self.strict
+            && self.sampler.is_none()
+            && self.time_jumps.is_none()
+            && !self.collect_stats
+            && self.filter.is_type_only()
+    }
+
+    /// 检查一条解码后的日志是否发生时间戳跳变
+    ///
+    /// 跳变记入报告，启用 `--mark-time-jumps` 时在输出中插入标记行。
+    /// 检查在过滤之前进行，被过滤掉的日志也参与检测
+    ///
+    /// # Arguments
+    /// * `detector` - 该文件的检测器，未启用检测时为 `None`
+    /// * `offset` - 条目在文件中的偏移
+    /// * `log` - 解码后的日志
+    /// * `file_report` - 该文件的报告
+    /// * `sink` - 输出目标
+    fn check_time_jump<S: LogSink>(
+        &self,
+        detector: &mut Option<TimeJumpDetector>,
+        offset: u64,
+        log: &Log,
+        file_report: &mut FileReport,
+        sink: &mut S,
+    ) -> io::Result<()> {
+        let Some(jump) = detector.as_mut().and_then(|d| d.observe(offset, log)) else {
+            return Ok(());
+        };
+        if self.mark_time_jumps && !self.count_only {
+            sink.write_marker(&format!("----- 时间戳跳变 @{}: {} -----", offset, jump))?;
+        }
+        file_report.time_jumps.push(jump);
+        Ok(())
+    }
+
+    /// 抽样是否保留该日志，未启用抽样时总是保留
+    ///
+    /// # Arguments
+    /// * `file` - 来源文件名
+    /// * `offset` - 条目在文件中的偏移
+    /// * `log` - 通过过滤的日志
+    fn sample_keeps(&self, file: &str, offset: u64, log: &Log) -> bool {
+        self.sampler
+            .as_ref()
+            .is_none_or(|sampler| sampler.keeps(file, offset, log))
+    }
+}
 
+fn main() {
     // 初始化日志
     // env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
     //     .format_timestamp(None)
     //     .init();
 
-    // 解析命令行参数
-    let args = Args::parse();
+    // 解析命令行参数，参数错误统一使用 EXIT_FATAL 退出
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => match e.kind() {
+            ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => e.exit(),
+            _ => {
+                let _ = e.print();
+                exit(EXIT_FATAL);
+            }
+        },
+    };
+
+    let result = match cli.into_command() {
+        Command::Extract(args) => run(&args),
+        Command::Check(args) => check(&args),
+        Command::Info(args) => info(&args),
+        Command::Salvage(args) => salvage_file(&args),
+        Command::Convert(args) => convert_file(&args),
+        Command::Reencrypt(args) => reencrypt_file(&args),
+        Command::Decrypt(args) => decrypt_file(&args),
+        Command::Repack(args) => repack_files(&args),
+        Command::Split(args) => split_file(&args),
+        Command::Index(args) => build_index(&args),
+        Command::Adb(args) => pull_from_device(&args),
+        Command::View(args) => view(&args),
+        Command::Watch(args) => watch_dir(&args),
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "clog-reader", &mut io::stdout());
+            Ok(EXIT_OK)
+        }
+    };
 
-    // 解析日志类型过滤器
-    let types: Vec<i32> = if args.log_types.is_empty() {
-        Vec::new()
-    } else {
-        args.log_types
-            .split(',')
-            .filter_map(|s| s.trim().parse::<i32>().ok())
-            .collect()
+    let code = match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprint_flush!("错误: {:#}", e);
+            EXIT_FATAL
+        }
+    };
+
+    // 统一使用 exit() 退出，确保所有资源正确释放后进程结束
+    exit(code);
+}
+
+/// 执行日志提取流程
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码
+fn run(args: &ExtractArgs) -> Result<i32> {
+    run_with_cancel(args, None)
+}
+
+/// 执行日志提取流程，可以使用调用方的取消标志
+///
+/// # Arguments
+/// * `args` - 命令行参数
+/// * `cancel` - 调用方已为其捕获 Ctrl-C 的取消标志；为 `None` 时自行捕获
+///
+/// # Returns
+/// 返回进程退出码
+fn run_with_cancel(args: &ExtractArgs, cancel: Option<&Arc<AtomicBool>>) -> Result<i32> {
+    let start_time = Instant::now();
+
+    // 解析日志类型及排除过滤器（排除的标签精确匹配）
+    let filter = LogFilter::new()
+        .types(parse_types(&args.log_types))
+        .exclude_types(parse_types(&args.exclude_types));
+    let filter = args
+        .exclude_tags
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .fold(filter, |filter, tag| {
+            filter.exclude_tag(Pattern::Exact(tag.to_string()))
+        });
+
+    if args.descriptor_set.is_some() && cfg!(not(feature = "dynamic")) {
+        anyhow::bail!("未启用 dynamic 功能，请使用 `cargo build --features dynamic` 重新编译");
+    }
+    let descriptor_set = match &args.descriptor_set {
+        Some(path) => Some(
+            fs::read(path).context(format!("读取描述符文件失败: {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let types = match &args.type_map {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .context(format!("读取日志类型映射失败: {}", path.display()))?;
+            let mut types = LogTypeRegistry::builtin();
+            types
+                .extend_from_toml(&source)
+                .context(format!("解析日志类型映射失败: {}", path.display()))?;
+            Some(Arc::new(types))
+        }
+        None => None,
+    };
+
+    let redactor = args
+        .redact
+        .iter()
+        .try_fold(Redactor::new(), |redactor, spec| redactor.parse_rule(spec))?;
+
+    let options = ReadOptions {
+        filter,
+        strict: args.strict,
+        dedup_consecutive: args.dedup_consecutive,
+        count_only: args.count,
+        sampler: args
+            .sample
+            .map(Sampler::fraction)
+            .or(args.every.map(Sampler::every))
+            .map(|sampler| {
+                sampler
+                    .keep_errors(args.sample_keep_errors)
+                    .seed(args.sample_seed)
+            }),
+        time_jumps: args.detect_time_jumps.then(|| {
+            TimeJumpDetector::new()
+                .backward_threshold(Duration::from_secs(args.time_jump_back))
+                .forward_gap(Duration::from_secs(args.time_jump_gap))
+        }),
+        mark_time_jumps: args.mark_time_jumps,
+        collect_stats: args.stats || args.report_html.is_some(),
+        index: args
+            .use_index
+            .as_deref()
+            .map(load_index)
+            .transpose()?
+            .map(Arc::new),
+        formatter: args
+            .fields
+            .clone()
+            .unwrap_or_default()
+            .with_types(types.clone())
+            .with_multiline(args.multiline.into())
+            .with_logfmt(args.format == OutputFormat::Logfmt),
+        show_source: args.show_source,
+        format: args.format,
+        es_index: args.es_index.clone().unwrap_or_default(),
+        archive_name: args
+            .input
+            .as_deref()
+            .map(report::display_name)
+            .unwrap_or_default(),
+        dump_failed: args.dump_failed.clone(),
+        descriptor_set,
+        key: args.key.load()?,
+        types,
+        redactor: (!redactor.is_empty()).then(|| Arc::new(redactor)),
+        cancel: cancel.cloned().unwrap_or_default(),
     };
 
-    if !types.is_empty() {
-        print_flush!("日志类型过滤器: {:?}", types);
+    if args.format == OutputFormat::Raw && !options.filter.is_empty() {
+        anyhow::bail!("--format raw 原样输出全部条目，不能与 -t/--exclude-type/--exclude-tag 同时使用");
+    }
+    if options.index.is_some() {
+        if options.redactor.is_some() {
+            anyhow::bail!("--use-index 按脱敏前的标签判断，不能与 --redact 同时使用");
+        }
+        if options.time_jumps.is_some() {
+            anyhow::bail!("--detect-time-jumps 需要解码每条日志，不能与 --use-index 同时使用");
+        }
+        if args.sort {
+            anyhow::bail!("--use-index 不能与 --sort 同时使用");
+        }
+    }
+    if args.format == OutputFormat::Raw && options.collect_stats {
+        anyhow::bail!("--format raw 不解码日志，不能与 --stats/--report-html 同时使用");
+    }
+    if args.format == OutputFormat::Raw && options.time_jumps.is_some() {
+        anyhow::bail!("--format raw 不解码日志，不能与 --detect-time-jumps 同时使用");
+    }
+    if args.format == OutputFormat::Raw && options.sampler.is_some() {
+        anyhow::bail!("--format raw 原样输出全部条目，不能与 --sample/--every 同时使用");
+    }
+    if args.format == OutputFormat::Raw && options.redactor.is_some() {
+        anyhow::bail!("--format raw 不解码日志，无法与 --redact 同时使用");
+    }
+    if args.format == OutputFormat::Raw && args.show_source {
+        anyhow::bail!("--format raw 原样输出条目数据，无法标注来源，不能与 --show-source 同时使用");
+    }
+    if args.follow.is_some() && args.show_source {
+        anyhow::bail!("--follow 不能与 --show-source 同时使用");
+    }
+    if (args.format == OutputFormat::EsBulk) != args.es_index.is_some() {
+        anyhow::bail!(
+            "--format es-bulk 需要用 --index 指定索引名，--index 也只适用于 --format es-bulk"
+        );
+    }
+    if args.format == OutputFormat::Sqlite && cfg!(not(feature = "sqlite")) {
+        anyhow::bail!("未启用 sqlite 功能，请使用 `cargo build --features sqlite` 重新编译");
+    }
+    let line_output = matches!(args.format, OutputFormat::Text | OutputFormat::Logfmt);
+    if !line_output && args.max_output_size.is_some() {
+        anyhow::bail!("--max-output-size 仅适用于文本输出");
+    }
+    if !line_output && args.sort {
+        anyhow::bail!("--sort 仅适用于文本输出");
+    }
+    if args.format == OutputFormat::TraceJson && args.append {
+        anyhow::bail!("--format trace-json 输出单个 JSON 数组，不能使用 --append");
+    }
+    if args.format == OutputFormat::Logfmt {
+        if args.fields.is_some() {
+            anyhow::bail!("--format logfmt 总是输出全部字段，不能与 --fields 同时使用");
+        }
+        // 折叠标记和跳变标记不是 logfmt 行，会干扰下游解析
+        if args.dedup_consecutive || args.mark_time_jumps {
+            anyhow::bail!("--format logfmt 不能与 --dedup-consecutive/--mark-time-jumps 同时使用");
+        }
+    }
+
+    // 跟随模式：直接读取单个文件并输出到 stdout
+    if let Some(path) = &args.follow {
+        return follow(Path::new(path), &options);
+    }
+
+    let input = args.input.as_deref().context("缺少输入文件参数 -i")?;
+    if cancel.is_none() {
+        install_interrupt_handler(&options.cancel);
+    }
+
+    // 尽早检查输出文件，避免解压完成后才发现无法写入
+    let output_path = PathBuf::from(&args.output);
+    if !args.count && output_path.exists() && !args.force && !args.append {
+        anyhow::bail!(
+            "输出文件已存在: {}（使用 --force 覆盖，或使用 --append 追加）",
+            output_path.display()
+        );
+    }
+
+    let filter = &options.filter;
+    if !filter.types.is_empty() {
+        print_flush!("日志类型过滤器: {:?}", filter.types);
+    }
+    if !filter.exclude_types.is_empty() {
+        print_flush!("排除日志类型: {:?}", filter.exclude_types);
+    }
+    if !filter.exclude_tags.is_empty() {
+        let tags: Vec<_> = filter
+            .exclude_tags
+            .iter()
+            .map(|p| match p {
+                Pattern::Exact(tag) | Pattern::Wildcard(tag) => tag.as_str(),
+            })
+            .collect();
+        print_flush!("排除日志标签: {:?}", tags);
+    }
+
+    if let Some(dir) = &options.dump_failed {
+        fs::create_dir_all(dir).context(format!("创建转储目录失败: {}", dir.display()))?;
     }
 
     // 创建临时目录
@@ -106,241 +673,1854 @@ fn main() -> Result<()> {
     let temp_path = temp_dir.path().to_path_buf();
     print_flush!("临时目录路径: {}", temp_path.display());
 
-    // 解压缩 ZIP 文件
-    unzip(&args.input, &temp_path).context("解压缩失败")?;
+    if let (Some(from), Some(to)) = (args.file_date_from, args.file_date_to) {
+        if from > to {
+            anyhow::bail!("--file-date-from ({}) 晚于 --file-date-to ({})", from, to);
+        }
+    }
+    let date_range = FileDateRange {
+        from: args.file_date_from,
+        to: args.file_date_to,
+    };
+    let LogFiles {
+        paths: log_files,
+        failures: extract_failures,
+    } = extract_log_files(
+        input,
+        &args.http_headers,
+        &temp_path,
+        &date_range,
+        &args.file_pattern,
+        args.max_extract_size,
+    )?;
 
-    // 收集日志文件
-    let mut log_files: Vec<PathBuf> = Vec::new();
-    log_files.extend(get_glog_files(&temp_path)?);
-    log_files.extend(get_mmap_files(&temp_path)?);
+    // 创建输出文件（计数模式不输出日志）
+    let mut writer = if args.count {
+        None
+    } else {
+        Some(Output::open(args, &output_path, input)?)
+    };
 
-    print_flush!("找到 {} 个日志文件", log_files.len());
+    let reports = match writer.as_mut() {
+        // 按时间戳合并各文件的日志，边读边写入输出
+        Some(Output::Text(output)) if args.sort => merge_files(&log_files, &options, output)?,
+        _ => {
+            // 并行解码各日志文件，并按原始文件顺序写入输出
+            let threads = args.threads.unwrap_or_else(default_threads);
+            let mut reports = Vec::with_capacity(log_files.len() + extract_failures.len());
+            reports.extend(extract_failures);
+            decode_files(&log_files, &options, threads, |log_file, result| {
+                match result {
+                    Ok((file_report, output)) => {
+                        if let Some(writer) = writer.as_mut() {
+                            writer.write(log_file, output, options.show_source)?;
+                        }
+                        print_flush!("{}: 成功读取 {} 条日志", log_file.display(), file_report.count);
+                        reports.push(file_report);
+                    }
+                    // 中断后尚未开始的文件不计入报告
+                    Err(e) if is_cancelled(&e) => {}
+                    Err(e) => {
+                        eprint_flush!("读取日志失败 {}: {}", log_file.display(), e);
+                        let mut file_report = FileReport::new(log_file);
+                        file_report.fail(FileError::header(&e));
+                        reports.push(file_report);
+                    }
+                }
+                Ok(())
+            })?;
+            reports
+        }
+    };
+    let failed_files = reports.iter().filter(|r| !r.is_clean()).count();
 
-    // 调试：如果没有找到日志文件，列出临时目录内容
-    if log_files.is_empty() {
-        print_flush!("未找到日志文件，列出临时目录内容:");
-        for entry in WalkDir::new(&temp_path).into_iter().filter_map(|e| e.ok()) {
-            print_flush!("  {}", entry.path().display());
+    if args.count {
+        for file_report in &reports {
+            print_flush!("{}: {}", file_report.file, file_report.count);
         }
+        print_flush!("总计: {}", reports.iter().map(|r| r.count).sum::<usize>());
+    } else if !reports.is_empty() {
+        report::print_table(&mut io::stdout().lock(), &reports)?;
     }
-
-    // 创建输出文件
-    let output_path = PathBuf::from(&args.output);
-    let output_file = File::create(&output_path)
-        .context(format!("创建输出文件失败: {}", output_path.display()))?;
-    let mut writer = BufWriter::new(output_file);
-
-    // 处理每个日志文件
-    for log_file in &log_files {
-        print_flush!("正在处理: {}", log_file.display());
-        match read_logs(log_file, &types, &mut writer) {
-            Ok(count) => {
-                print_flush!("成功读取 {} 条日志", count);
-            }
-            Err(e) => {
-                eprint_flush!("读取日志失败 {}: {}", log_file.display(), e);
+    if let Some(path) = &args.report {
+        report::write_json(Path::new(path), &reports)?;
+        print_flush!("读取报告已保存到: {}", path);
+    }
+    if let Some(path) = &args.errors_json {
+        errors::write_json(path, input, &reports)?;
+        print_flush!("错误报告已保存到: {}", path.display());
+    }
+    if options.collect_stats {
+        let stats = reports.iter().fold(Stats::default(), |mut stats, r| {
+            stats.merge(&r.stats);
+            stats
+        });
+        let types = options.types.as_deref();
+        if args.stats {
+            stats::print_tables(&mut io::stdout().lock(), &stats, types, args.top)?;
+        }
+        if let Some(path) = &args.report_html {
+            let title = report::display_name(input);
+            HtmlReport {
+                title: &title,
+                stats: &stats,
+                reports: &reports,
+                types,
+                top_n: args.top,
             }
+            .write(path)?;
+            print_flush!("HTML 报告已保存到: {}", path.display());
         }
     }
 
-    writer.flush()?;
-    print_flush!("日志输出已保存到: {}", output_path.display());
+    if let Some(writer) = writer {
+        for path in writer.finish()? {
+            print_flush!("日志输出已保存到: {}", path.display());
+        }
+    }
 
     let elapsed = start_time.elapsed();
     print_flush!("程序运行时间: {:.2}秒", elapsed.as_secs_f64());
 
-    // 统一使用 exit(0) 退出，确保所有资源正确释放后进程结束
-    exit(0);
+    if options.cancel.load(Ordering::Relaxed) {
+        eprint_flush!("已中断，输出中只包含中断前解码的日志");
+        return Ok(EXIT_INTERRUPTED);
+    }
+    if log_files.is_empty() {
+        return Ok(EXIT_NO_FILES);
+    }
+    if failed_files > 0 {
+        eprint_flush!("{} 个日志文件解析失败或需要恢复", failed_files);
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
 }
 
-/// 解压缩 ZIP 文件
+/// 校验 ZIP 中的每个日志文件
+///
+/// 逐个文件验证文件头、同步标记、解密和 protobuf 解码，打印每个文件的校验结论
 ///
 /// # Arguments
-/// * `zip_path` - ZIP 文件路径
-/// * `dest_dir` - 目标目录
+/// * `args` - 命令行参数
 ///
 /// # Returns
-/// 成功返回 Ok(())
-fn unzip(zip_path: &str, dest_dir: &Path) -> Result<()> {
-    let file = File::open(zip_path).context(format!("无法打开 ZIP 文件: {}", zip_path))?;
-
-    let mut archive = ZipArchive::new(file).context("无法读取 ZIP 文件")?;
+/// 返回进程退出码
+fn check(args: &CheckArgs) -> Result<i32> {
+    let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+    let LogFiles {
+        paths: log_files,
+        failures: mut reports,
+    } = extract_all(&args.input, temp_dir.path())?;
+    let key = args.key.load()?;
 
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
+    let mut failed_files = 0;
+    for log_file in &log_files {
+        let name = log_file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| log_file.display().to_string());
+        let validation = validate(&log_file.to_string_lossy(), Some(key.clone()));
+        match &validation.failure {
+            None => print_flush!("通过  {}（{} 条日志）", name, validation.entries),
+            Some(failure) => {
+                failed_files += 1;
+                print_flush!(
+                    "失败  {}（{} 条日志后，偏移 {}）: {}",
+                    name,
+                    validation.entries,
+                    failure.offset,
+                    failure.error
+                );
+                let mut file_report = FileReport::new(log_file);
+                file_report.fail(FileError {
+                    stage: match failure.recover_reason {
+                        Some(_) => ErrorStage::Recover,
+                        // 打开文件失败时偏移为 0
+                        None if failure.offset == 0 => ErrorStage::Header,
+                        None if failure.code == errors::DECODE_FAILURE_CODE => ErrorStage::Decode,
+                        None => ErrorStage::Read,
+                    },
+                    offset: Some(failure.offset),
+                    code: Some(failure.code),
+                    message: failure.error.clone(),
+                    recover_reason: failure.recover_reason.map(|reason| reason.code()),
+                });
+                reports.push(file_report);
+            }
+        }
+    }
+    if let Some(path) = &args.errors_json {
+        errors::write_json(path, &args.input, &reports)?;
+        print_flush!("错误报告已保存到: {}", path.display());
+    }
 
-        // 构造输出路径
-        let out_path = match file.enclosed_name() {
-            Some(path) => dest_dir.join(path),
-            None => continue,
-        };
+    if log_files.is_empty() {
+        return Ok(EXIT_NO_FILES);
+    }
+    if failed_files > 0 {
+        eprint_flush!("{} 个日志文件校验失败", failed_files);
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
+}
 
-        if file.name().ends_with('/') {
-            // 创建目录
-            fs::create_dir_all(&out_path)?;
-        } else {
-            // 创建父目录
-            if let Some(parent) = out_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)?;
-                }
-            }
+/// 在终端中交互式浏览 ZIP 中的日志
+///
+/// 解压后按处理顺序惰性解码，参见 [`cli::view`]
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码
+fn view(args: &ViewArgs) -> Result<i32> {
+    #[cfg(not(feature = "tui"))]
+    {
+        let _ = args;
+        anyhow::bail!("未启用 tui 功能，请使用 `cargo build --features tui` 重新编译");
+    }
+    #[cfg(feature = "tui")]
+    {
+        use std::io::IsTerminal;
 
-            // 提取文件
-            let mut out_file = File::create(&out_path)?;
-            std::io::copy(&mut file, &mut out_file)?;
+        // 按键从标准输入读取，因此不能同时从标准输入读取压缩包
+        if args.input == STDIN_INPUT {
+            anyhow::bail!("view 子命令不支持从标准输入读取，请先保存为文件");
+        }
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            anyhow::bail!("view 子命令需要在终端中运行");
         }
+        let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+        let log_files = extract_all(&args.input, temp_dir.path())?.paths;
+        if log_files.is_empty() {
+            return Ok(EXIT_NO_FILES);
+        }
+        let key = args.key.load()?;
+        let window = cli::view::LogWindow::new(log_files, Some(key));
+        cli::view::run(window, &report::display_name(&args.input))?;
+        Ok(EXIT_OK)
     }
-
-    print_flush!("解压缩完成，共 {} 个文件", archive.len());
-    Ok(())
 }
 
-/// 获取目录下所有 .glog 文件
+/// 显示 ZIP 中每个日志文件的元数据
 ///
-/// 按文件名中的日期排序（升序）
+/// 通过 `glog::peek` 只读取文件头，V4 的压缩和加密模式取自第一条日志的模式字节
 ///
 /// # Arguments
-/// * `dir_path` - 目录路径
+/// * `args` - 命令行参数
 ///
 /// # Returns
-/// 返回排序后的文件路径列表
-fn get_glog_files(dir_path: &Path) -> Result<Vec<PathBuf>> {
-    let mut files: Vec<PathBuf> = WalkDir::new(dir_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .filter(|e| {
-            let name = e.file_name().to_string_lossy();
-            name.ends_with(".glog") && name.starts_with("async-") && name.len() >= 18
-            // async-YYYYMMdd.glog
-        })
-        .map(|e| e.path().to_path_buf())
-        .collect();
+/// 返回进程退出码
+fn info(args: &InfoArgs) -> Result<i32> {
+    let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+    let log_files = extract_all(&args.input, temp_dir.path())?.paths;
 
-    // 按日期排序（从文件名中提取日期）
-    files.sort_by(|a, b| {
-        let date_a = extract_date_from_glog_name(a);
-        let date_b = extract_date_from_glog_name(b);
-        date_a.cmp(&date_b)
-    });
+    let mut failed_files = 0;
+    for log_file in &log_files {
+        let name = log_file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| log_file.display().to_string());
+        let info = match peek(&log_file.to_string_lossy()) {
+            Ok(info) => info,
+            Err(e) => {
+                failed_files += 1;
+                print_flush!("{}: 无法读取文件头: {}", name, e);
+                continue;
+            }
+        };
+
+        let unknown = || "未知".to_string();
+        print_flush!(
+            "{}: 版本 {}，协议 {}，压缩 {}，加密 {}，大小 {} 字节",
+            name,
+            info.version,
+            info.proto_name,
+            info.compress_mode.map(|m| format!("{:?}", m)).unwrap_or_else(unknown),
+            info.encrypt_mode.map(|m| format!("{:?}", m)).unwrap_or_else(unknown),
+            info.file_size
+        );
+    }
+
+    if log_files.is_empty() {
+        return Ok(EXIT_NO_FILES);
+    }
+    if failed_files > 0 {
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
+}
 
-    Ok(files)
+/// 以跳过损坏条目的方式打开单个日志文件
+///
+/// 损坏的条目由 [`RecoveryPolicy::SkipSilently`] 跳过并计入 [`GlogReader::stats`]
+///
+/// # Arguments
+/// * `path` - 日志文件路径
+/// * `key` - 服务器私钥，参见 [`cli::KeyArgs::load`]
+fn open_skipping(path: &Path, key: &str) -> Result<GlogReader> {
+    GlogReader::builder()
+        .key(key)
+        .recovery_policy(RecoveryPolicy::SkipSilently)
+        .open(&path.to_string_lossy())
+        .context(format!("打开日志文件失败: {}", path.display()))
 }
 
-/// 从 glog 文件名中提取日期
+/// 汇报 [`open_skipping`] 打开的读取器跳过的条目
 ///
 /// # Arguments
-/// * `path` - 文件路径
+/// * `stats` - 读取器的统计
 ///
 /// # Returns
-/// 返回日期字符串（YYYYMMdd）
-fn extract_date_from_glog_name(path: &Path) -> String {
-    path.file_name()
-        .and_then(|n| n.to_str())
-        .map(|n| {
-            if n.len() >= 14 {
-                n[6..14].to_string() // 提取 YYYYMMdd
-            } else {
-                String::new()
-            }
-        })
-        .unwrap_or_default()
+/// 返回进程退出码，有条目被跳过时为 `EXIT_PARTIAL`
+fn report_skipped(stats: &ReadStats) -> i32 {
+    if stats.recoveries == 0 && stats.decode_failures == 0 {
+        return EXIT_OK;
+    }
+    eprint_flush!(
+        "跳过了损坏或无法解码的条目：恢复 {} 次（跳过 {} 字节），{} 条无法解码",
+        stats.recoveries,
+        stats.bytes_skipped,
+        stats.decode_failures
+    );
+    EXIT_PARTIAL
 }
 
-/// 获取目录下所有 .glogmmap 文件
+/// 把单个日志文件解密、解压后重写为 V3 文件
 ///
-/// 按最后修改时间排序（降序）
+/// 保留日志顺序和文件头中的协议名称，损坏和无法解码的条目被跳过并计数
 ///
 /// # Arguments
-/// * `dir_path` - 目录路径
+/// * `args` - 命令行参数
 ///
 /// # Returns
-/// 返回排序后的文件路径列表
-fn get_mmap_files(dir_path: &Path) -> Result<Vec<PathBuf>> {
-    let mut files: Vec<(PathBuf, std::time::SystemTime)> = WalkDir::new(dir_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .filter(|e| e.file_name().to_string_lossy().ends_with(".glogmmap"))
-        .filter_map(|e| {
-            let path = e.path().to_path_buf();
-            e.metadata()
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .map(|time| (path, time))
-        })
-        .collect();
+/// 返回进程退出码，有条目被跳过时为 `EXIT_PARTIAL`
+fn convert_file(args: &ConvertArgs) -> Result<i32> {
+    let key = args.key.load()?;
+    let mut reader = open_skipping(&args.input, &key)?;
 
-    // 按修改时间降序排序
-    files.sort_by(|a, b| b.1.cmp(&a.1));
+    let file = fs::File::create(&args.output)
+        .context(format!("创建输出文件失败: {}", args.output.display()))?;
+    let proto_name = reader.metadata().proto_name;
+    let mut writer = GlogWriterV3::new(BufWriter::new(file), args.compress.into(), &proto_name)?;
+    let written = writer
+        .copy_from(&mut reader)
+        .context(format!("转换失败: {}", args.input.display()))?;
+    writer.into_inner()?;
 
-    Ok(files.into_iter().map(|(path, _)| path).collect())
+    print_flush!("已转换 {} 条日志，保存到: {}", written, args.output.display());
+    Ok(report_skipped(&reader.stats()))
 }
 
-/// 读取日志文件
+/// 用旧私钥解密单个日志文件，再针对新的服务器公钥重新加密写入 V4 文件
+///
+/// 每条日志使用新的临时客户端密钥加密，压缩模式、日志顺序和协议名称保持不变；
+/// 损坏和无法解码的条目（包括旧私钥不匹配的条目）被跳过并计数
 ///
 /// # Arguments
-/// * `file_path` - 日志文件路径
-/// * `types` - 日志类型过滤器
-/// * `writer` - 输出写入器
+/// * `args` - 命令行参数
 ///
 /// # Returns
-/// 返回读取的日志条数
-fn read_logs<W: Write>(file_path: &Path, types: &[i32], writer: &mut W) -> Result<usize> {
-    let file_path_str = file_path.to_string_lossy().to_string();
-
-    // 使用私钥打开日志文件
-    let mut reader = open_with_key(&file_path_str, Some(SVR_PRIV_KEY.to_string()))
-        .context(format!("打开日志文件失败: {}", file_path.display()))?;
+/// 返回进程退出码，有条目被跳过时为 `EXIT_PARTIAL`
+fn reencrypt_file(args: &ReencryptArgs) -> Result<i32> {
+    let new_public_key = hex::decode(args.new_public_key.trim()).context(format!(
+        "新的服务器公钥不是有效的十六进制: {}",
+        args.new_public_key
+    ))?;
+    let key = args.key.load()?;
+    let mut reader = open_skipping(&args.input, &key)?;
 
-    let mut log_count = 0;
-    let buf_len = GlogReader::single_log_max_length();
-    let mut buf = vec![0u8; buf_len];
+    let proto_name = reader.metadata().proto_name;
+    let file = fs::File::create(&args.output)
+        .context(format!("创建输出文件失败: {}", args.output.display()))?;
+    let mut writer =
+        GlogWriterV4::new(BufWriter::new(file), &proto_name)?.with_server_key(&new_public_key)?;
+    let written = writer
+        .copy_from(&mut reader)
+        .context(format!("重新加密失败: {}", args.input.display()))?;
+    writer.into_inner()?;
 
-    loop {
-        match reader.read(&mut buf) {
-            Ok(ReadResult::Success(len)) => {
-                if len == 0 {
-                    continue;
-                }
+    print_flush!("已重新加密 {} 条日志，保存到: {}", written, args.output.display());
+    Ok(report_skipped(&reader.stats()))
+}
 
-                // 解析 protobuf 日志
-                match Log::decode_from(&buf[..len]) {
-                    Ok(log) => {
-                        // 检查类型过滤
-                        if !types.is_empty() && !types.contains(&log.log_type) {
-                            continue;
-                        }
+/// 去掉 V4 文件中每条日志的加密，压缩数据原样保留
+///
+/// 通过 [`GlogWriterV4::copy_raw_from`] 只解密不解压，文件头和协议名称不变，
+/// 输出文件中的条目全部不加密，读取时不再需要私钥
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码
+fn decrypt_file(args: &DecryptArgs) -> Result<i32> {
+    let key = args.key.load()?;
+    let mut reader = open_skipping(&args.input, &key)?;
 
-                        // 格式化并写入日志
-                        let formatted = log.format();
-                        writeln!(writer, "{}", formatted)?;
-                        log_count += 1;
-                    }
-                    Err(_) => {
-                        // eprintln!("解析日志失败: {}", e);
-                    }
-                }
-            }
-            Ok(ReadResult::Eof) => {
-                print_flush!("读取完成");
-                break;
-            }
-            Ok(ReadResult::NeedRecover(code)) => {
-                // eprintln!("需要恢复，错误码: {}", code);
-                if code == -1 {
-                    break;
-                }
-                continue;
-            }
-            Err(e) => {
-                eprint_flush!("读取错误: {}", e);
-                break;
-            }
-        }
+    let metadata = reader.metadata();
+    if metadata.version != GLOG_CIPHER_VERSION {
+        anyhow::bail!(
+            "decrypt 只支持 V4 文件，{} 的版本为 {}",
+            args.input.display(),
+            metadata.version
+        );
     }
+    let file = fs::File::create(&args.output)
+        .context(format!("创建输出文件失败: {}", args.output.display()))?;
+    let mut writer = GlogWriterV4::new(BufWriter::new(file), &metadata.proto_name)?;
+    let written = writer
+        .copy_raw_from(&mut reader)
+        .context(format!("解密失败: {}", args.input.display()))?;
+    writer.into_inner()?;
+
+    print_flush!(
+        "已解密 {} 条日志，保存到: {}",
+        written,
+        args.output.display()
+    );
+    Ok(report_skipped(&reader.stats()))
+}
 
-    print_flush!("共读取 {} 条日志", log_count);
-    Ok(log_count)
+/// 把 ZIP 中的全部日志按时间戳排序后写入单个 V3 文件
+///
+/// 所有条目的原始 protobuf 数据读入内存后按 [`Log::sort_key`] 排序，时间戳相同时保持读出顺序；
+/// 损坏和无法解码的条目被跳过并计数。协议名称取自第一个日志文件
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码，有文件无法打开或有条目被跳过时为 `EXIT_PARTIAL`
+fn repack_files(args: &RepackArgs) -> Result<i32> {
+    let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+    let log_files = extract_all(&args.input, temp_dir.path())?.paths;
+    if log_files.is_empty() {
+        return Ok(EXIT_NO_FILES);
+    }
+    let key = args.key.load()?;
+
+    let mut proto_name: Option<String> = None;
+    let mut entries = Vec::new();
+    let (mut failed_files, mut skipped) = (0, 0);
+    for log_file in &log_files {
+        let name = base_name(log_file);
+        let reader = open_skipping(log_file, &key);
+        let mut reader = match reader {
+            Ok(reader) => reader,
+            Err(e) => {
+                failed_files += 1;
+                eprint_flush!("打开日志文件失败 {}: {}", name, e.root_cause());
+                continue;
+            }
+        };
+        let file_proto_name = reader.metadata().proto_name;
+        match &proto_name {
+            None => proto_name = Some(file_proto_name),
+            Some(first) if *first != file_proto_name => eprint_flush!(
+                "警告: {} 的协议名称 {} 与第一个文件不同，合并后统一使用 {}",
+                name,
+                file_proto_name,
+                first
+            ),
+            Some(_) => {}
+        }
+
+        let before = entries.len();
+        let mut buf = Vec::new();
+        let error = loop {
+            match reader.read_entry(&mut buf) {
+                Ok(Some(entry)) if entry.len == 0 => {}
+                Ok(Some(entry)) => {
+                    let data = &buf[..entry.len];
+                    if let Ok(log) = reader.decode_log(data) {
+                        entries.push((log.sort_key(entries.len() as u64), data.to_vec()));
+                    }
+                }
+                Ok(None) => break None,
+                Err(e) => break Some(e),
+            }
+        };
+        let stats = reader.stats();
+        skipped += stats.recoveries + stats.decode_failures;
+        print_flush!("{}: 读取 {} 条日志", name, entries.len() - before);
+        if let Some(e) = error {
+            failed_files += 1;
+            eprint_flush!("{}: 读取错误，之后的日志未合并: {}", name, e);
+        }
+    }
+    entries.sort_unstable_by_key(|(key, _)| *key);
+
+    let file = fs::File::create(&args.output)
+        .context(format!("创建输出文件失败: {}", args.output.display()))?;
+    let mut writer = GlogWriterV3::new(
+        BufWriter::new(file),
+        args.compress.into(),
+        proto_name.as_deref().unwrap_or_default(),
+    )?;
+    for (_, data) in &entries {
+        writer.write(data)?;
+    }
+    writer.into_inner()?;
+    print_flush!(
+        "已合并 {} 条日志，保存到: {}",
+        entries.len(),
+        args.output.display()
+    );
+
+    if skipped > 0 {
+        eprint_flush!("跳过了 {} 个损坏或无法解码的条目", skipped);
+    }
+    if failed_files > 0 || skipped > 0 {
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
+}
+
+/// 时间戳无法解析的日志在拆分时写入的文件名
+const SPLIT_UNKNOWN_FILE: &str = "unknown.glog";
+
+/// 按日志时间戳把单个日志文件拆分为多个 V3 文件
+///
+/// 每个输出文件在第一条属于它的日志出现时才创建，日志在文件内保持原顺序。
+/// 压缩流无法在关闭后继续追加，因此所有输出文件保持打开，读完后统一关闭
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码，有条目被跳过或读取出错时为 `EXIT_PARTIAL`
+fn split_file(args: &SplitArgs) -> Result<i32> {
+    let key = args.key.load()?;
+    let mut reader = open_skipping(&args.input, &key)?;
+    fs::create_dir_all(&args.output)
+        .context(format!("创建输出目录失败: {}", args.output.display()))?;
+    let proto_name = reader.metadata().proto_name;
+
+    // 按文件名排序，打印结果时按日期顺序
+    let mut writers = BTreeMap::new();
+    let mut buf = Vec::new();
+    // 读取错误时停止读取，已写入的输出文件仍然正常关闭
+    let error = loop {
+        let entry = match reader.read_entry(&mut buf) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break None,
+            Err(e) => break Some(e),
+        };
+        if entry.len == 0 {
+            continue;
+        }
+        let data = &buf[..entry.len];
+        let Ok(log) = reader.decode_log(data) else {
+            continue;
+        };
+        let name = match args.by {
+            SplitBy::Day => split_day(&log).map_or_else(
+                || SPLIT_UNKNOWN_FILE.to_string(),
+                |day| format!("{}-{}.glog", args.prefix, day.format("%Y%m%d")),
+            ),
+        };
+        let (writer, count) = match writers.entry(name) {
+            btree_map::Entry::Occupied(entry) => entry.into_mut(),
+            btree_map::Entry::Vacant(entry) => {
+                let path = args.output.join(entry.key());
+                let file = fs::File::create(&path)
+                    .context(format!("创建输出文件失败: {}", path.display()))?;
+                let writer =
+                    GlogWriterV3::new(BufWriter::new(file), args.compress.into(), &proto_name)?;
+                entry.insert((writer, 0u64))
+            }
+        };
+        writer.write(data)?;
+        *count += 1;
+    };
+
+    for (name, (writer, count)) in writers {
+        writer.into_inner()?;
+        print_flush!("{}: {} 条日志", args.output.join(name).display(), count);
+    }
+    let status = report_skipped(&reader.stats());
+    if let Some(e) = error {
+        eprint_flush!("{}: 读取错误，之后的日志未拆分: {}", args.input.display(), e);
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(status)
+}
+
+/// 日志时间戳对应的本地日期
+fn split_day(log: &Log) -> Option<NaiveDate> {
+    use chrono::{DateTime, Local};
+
+    let time = DateTime::from_timestamp_millis(log.timestamp_millis()?)?;
+    Some(time.with_timezone(&Local).date_naive())
+}
+
+/// 为 ZIP 中的每个日志文件建立索引并写入索引文件
+///
+/// 每个日志文件完整读取并解码一次，记录每条日志的偏移、时间戳、类型、级别、进程 ID 和标签哈希
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码，有文件无法完整读取时为 `EXIT_PARTIAL`
+fn build_index(args: &IndexArgs) -> Result<i32> {
+    let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+    let log_files = extract_all(&args.input, temp_dir.path())?.paths;
+    if log_files.is_empty() {
+        return Ok(EXIT_NO_FILES);
+    }
+    let key = args.key.load()?;
+
+    let mut index = LogIndex::default();
+    let mut failed_files = 0;
+    for log_file in &log_files {
+        let name = base_name(log_file);
+        let size = fs::metadata(log_file)?.len();
+        let reader = open_skipping(log_file, &key);
+        let mut reader = match reader {
+            Ok(reader) => reader,
+            Err(e) => {
+                failed_files += 1;
+                eprint_flush!("打开日志文件失败 {}: {}", name, e.root_cause());
+                continue;
+            }
+        };
+        let (file_index, error) = FileIndex::build(name.as_str(), size, &mut reader);
+        match error {
+            None => print_flush!("{}: 已索引 {} 条日志", name, file_index.entries.len()),
+            Some(e) => {
+                failed_files += 1;
+                eprint_flush!(
+                    "{}: 读取错误，只索引了前 {} 条日志: {}",
+                    name,
+                    file_index.entries.len(),
+                    e
+                );
+            }
+        }
+        index.files.push(file_index);
+    }
+
+    let file = fs::File::create(&args.output)
+        .context(format!("创建索引文件失败: {}", args.output.display()))?;
+    let mut writer = BufWriter::new(file);
+    index.write_to(&mut writer)?;
+    writer.flush()?;
+    print_flush!("索引已保存到: {}", args.output.display());
+
+    if failed_files > 0 {
+        eprint_flush!("{} 个日志文件无法完整建立索引", failed_files);
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
+}
+
+/// 读取 `--use-index` 指定的索引文件
+fn load_index(path: &Path) -> Result<LogIndex> {
+    let file = fs::File::open(path).context(format!("打开索引文件失败: {}", path.display()))?;
+    LogIndex::read_from(&mut io::BufReader::new(file))
+        .context(format!("读取索引文件失败: {}", path.display()))
+}
+
+/// 抢救文件头损坏的日志文件
+///
+/// 日志写入 `-o` 指定的文件或标准输出，各段解出的条目和汇总信息输出到标准错误
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码，没有抢救出任何日志时为 `EXIT_PARTIAL`
+fn salvage_file(args: &SalvageArgs) -> Result<i32> {
+    let key = args.key.load()?;
+    let report = salvage::salvage(&args.input.to_string_lossy(), Some(key))
+        .context(format!("读取文件失败: {}", args.input.display()))?;
+
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            fs::File::create(path).context(format!("创建输出文件失败: {}", path.display()))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    for (_, log) in &report.logs {
+        writeln!(output, "{}", log.format())?;
+    }
+    output.flush()?;
+
+    for run in &report.runs {
+        let format = match run.version {
+            GLOG_RECOVERY_VERSION => format!("V3（{:?}）", run.compress_mode),
+            version => format!("V{}", version),
+        };
+        eprint_flush!("偏移 {}..{}: 按 {} 解出 {} 条日志", run.start, run.end, format, run.logs);
+    }
+    eprint_flush!("共 {} 个同步标记，抢救出 {} 条日志", report.markers, report.logs.len());
+    if report.logs.is_empty() {
+        return Ok(EXIT_PARTIAL);
+    }
+    Ok(EXIT_OK)
+}
+
+/// 通过 adb 从设备拉取应用的日志目录，再按目录输入提取日志
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码
+fn pull_from_device(args: &AdbArgs) -> Result<i32> {
+    if args.extract.input.is_some() || args.extract.follow.is_some() {
+        anyhow::bail!("adb 子命令从设备读取日志，不能同时指定 -i 或 --follow");
+    }
+
+    let temp_dir = tempfile::tempdir().context("创建临时目录失败")?;
+    let mut adb = Adb::new(args.adb.clone(), args.serial.clone());
+    let serial = adb.select_device()?;
+    print_flush!(
+        "正在从设备 {} 拉取 {} 的日志: {}",
+        serial,
+        args.package,
+        args.remote_dir
+    );
+    // 以包名作为目录名，输出中的来源名称（如 es-bulk 的文档 ID）不随临时目录变化
+    let dest = temp_dir.path().join(&args.package);
+    adb.pull_logs(&args.package, &args.remote_dir, &dest)?;
+
+    let mut extract = args.extract.clone();
+    extract.input = Some(dest.to_string_lossy().into_owned());
+    run(&extract)
+}
+
+/// 监视目录，把新出现的日志文件停止增长后逐个提取到输出目录，直到按下 Ctrl-C
+///
+/// 每个文件按提取参数单独提取到 `-o` 目录下的同名文件（扩展名取决于输出格式）。
+/// 提取完成的文件记入状态文件，重启后不再处理；无法提取的文件打印错误后继续监视，重启后重试
+///
+/// # Arguments
+/// * `args` - 命令行参数
+///
+/// # Returns
+/// 返回进程退出码
+///
+/// # Errors
+/// 参数无效、状态文件无法读写或目录监视器停止时返回错误
+fn watch_dir(args: &WatchArgs) -> Result<i32> {
+    let extract = &args.extract;
+    if extract.input.is_some() || extract.follow.is_some() {
+        anyhow::bail!("watch 子命令从监视的目录读取日志，不能同时指定 -i 或 --follow");
+    }
+    if extract.output == DEFAULT_OUTPUT {
+        anyhow::bail!("watch 子命令需要用 -o 指定输出目录");
+    }
+    if extract.count
+        || extract.report.is_some()
+        || extract.errors_json.is_some()
+        || extract.report_html.is_some()
+    {
+        anyhow::bail!(
+            "watch 子命令逐个文件写入输出目录，不能与 --count/--report/--errors-json/--report-html 同时使用"
+        );
+    }
+    if !args.dir.is_dir() {
+        anyhow::bail!("监视的目录不存在: {}", args.dir.display());
+    }
+
+    let out_dir = PathBuf::from(&extract.output);
+    fs::create_dir_all(&out_dir).context(format!("创建输出目录失败: {}", out_dir.display()))?;
+    let state_path = args
+        .state
+        .clone()
+        .unwrap_or_else(|| out_dir.join(watch::DEFAULT_STATE_FILE));
+    let mut state = WatchState::load(&state_path)?;
+
+    let cancel = Arc::default();
+    install_interrupt_handler(&cancel);
+    let mut watcher = DirWatcher::new(&args.dir, Duration::from_millis(args.settle_ms))?;
+    print_flush!("正在监视目录: {}（按 Ctrl-C 停止）", args.dir.display());
+
+    while !cancel.load(Ordering::Relaxed) {
+        let settled = match watcher.poll() {
+            Ok(settled) => settled,
+            Err(e @ WatchError::Disconnected) => return Err(e.into()),
+            Err(e) => {
+                eprint_flush!("警告: {}", e);
+                thread::sleep(watch::POLL_INTERVAL);
+                continue;
+            }
+        };
+        for path in settled {
+            let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+                continue;
+            };
+            // 文件在停止增长后被删除
+            let Ok(stamp) = FileStamp::of(&path) else {
+                continue;
+            };
+            if state.is_processed(&name, stamp) {
+                continue;
+            }
+
+            let mut file_args = extract.clone();
+            file_args.input = Some(path.to_string_lossy().into_owned());
+            file_args.output = out_dir
+                .join(watch::output_name(&path, extract.format))
+                .to_string_lossy()
+                .into_owned();
+            file_args.force = !extract.append;
+            print_flush!("开始处理: {}", path.display());
+            match run_with_cancel(&file_args, Some(&cancel)) {
+                // 中断时输出不完整，不记录，重启后重新处理
+                Ok(EXIT_INTERRUPTED) => break,
+                Ok(_) => {
+                    state.mark(name, stamp);
+                    state.save(&state_path)?;
+                }
+                Err(e) => eprint_flush!("处理失败 {}: {:#}", path.display(), e),
+            }
+        }
+    }
+    Ok(EXIT_INTERRUPTED)
+}
+
+/// 使用默认规则解压输入中的全部日志文件
+///
+/// 不按日期筛选，不附加请求头，解压上限为 [`archive::DEFAULT_EXTRACT_LIMIT`]，
+/// 供 extract 以外的子命令使用，参见 [`extract_log_files`]
+fn extract_all(input: &str, temp_path: &Path) -> Result<LogFiles> {
+    extract_log_files(
+        input,
+        &[],
+        temp_path,
+        &FileDateRange::default(),
+        &FileNamePattern::default(),
+        archive::DEFAULT_EXTRACT_LIMIT,
+    )
+}
+
+/// 解压 ZIP 文件并收集其中的日志文件
+///
+/// # Arguments
+/// * `input` - ZIP 文件路径、目录、`-`（标准输入）或 HTTP(S) 地址
+/// * `http_headers` - 下载 HTTP(S) 地址时附加的请求头
+/// * `temp_path` - 解压目标目录
+/// * `date_range` - 按文件名日期筛选 glog 文件的范围
+/// * `pattern` - 识别 glog 文件的文件名规则
+/// * `extract_limit` - 从 ZIP 中解压的总字节数上限
+///
+/// # Returns
+/// 返回按处理顺序排列的日志文件列表，以及无法解压的条目
+fn extract_log_files(
+    input: &str,
+    http_headers: &[String],
+    temp_path: &Path,
+    date_range: &FileDateRange,
+    pattern: &FileNamePattern,
+    extract_limit: u64,
+) -> Result<LogFiles> {
+    if input == STDIN_INPUT {
+        return read_stdin_input(temp_path, date_range, pattern, extract_limit);
+    }
+    if input.starts_with("http://") || input.starts_with("https://") {
+        #[cfg(feature = "http")]
+        return read_http_input(
+            input,
+            http_headers,
+            temp_path,
+            date_range,
+            pattern,
+            extract_limit,
+        );
+        #[cfg(not(feature = "http"))]
+        {
+            let _ = http_headers;
+            anyhow::bail!("未启用 http 功能，请使用 `cargo build --features http` 重新编译");
+        }
+    }
+    if Path::new(input).is_dir() {
+        return read_dir_input(Path::new(input), date_range, pattern).map(LogFiles::from_paths);
+    }
+    // 单个 glog 文件直接在原位置读取
+    if read_magic(Path::new(input)).is_ok_and(|magic| magic == MAGIC_NUMBER) {
+        print_flush!("找到 1 个日志文件");
+        return Ok(LogFiles::from_paths(vec![PathBuf::from(input)]));
+    }
+
+    let archive = GlogArchive::open_with_pattern(input, None, pattern)
+        .context(format!("无法打开 ZIP 文件: {}", input))
+        .context("解压缩失败")?;
+    extract_archive(archive, temp_path, date_range, extract_limit)
+}
+
+/// 按处理顺序解压压缩包中的日志文件
+///
+/// 只解压日志文件，顺序由 [`GlogArchive::files`] 决定。单个条目无法读取或解压时
+/// 打印警告并跳过，记入返回的失败列表；超过解压上限时中止
+///
+/// # Arguments
+/// * `archive` - 日志压缩包
+/// * `temp_path` - 解压目标目录
+/// * `date_range` - 按文件名日期筛选 glog 文件的范围（.glogmmap 始终保留）
+/// * `extract_limit` - 解压的总字节数上限
+///
+/// # Returns
+/// 返回解压后的日志文件列表和无法解压的条目
+///
+/// # Errors
+/// 超过解压上限，或有条目无法解压且没有任何日志文件解压成功时返回错误
+fn extract_archive<R: Read + Seek>(
+    mut archive: GlogArchive<R>,
+    temp_path: &Path,
+    date_range: &FileDateRange,
+    extract_limit: u64,
+) -> Result<LogFiles> {
+    archive.set_extract_limit(extract_limit);
+    let files = archive.files();
+    let selected = filter_glog_files_by_date(&files, date_range);
+
+    let mut failures = Vec::new();
+    let mut skip = |name: &str, e: &GlogError| {
+        eprint_flush!("跳过无法解压的条目 {}: {}", name, e.kind());
+        let mut file_report = FileReport::new(Path::new(name));
+        file_report.fail(FileError {
+            message: format!("解压缩失败: {}", e.kind()),
+            ..FileError::from_glog(ErrorStage::Extract, e)
+        });
+        failures.push(file_report);
+    };
+    for e in archive.unreadable_entries() {
+        skip(e.path().unwrap_or_default(), e);
+    }
+    let mut log_files = Vec::with_capacity(selected.len());
+    for index in selected {
+        match archive.extract(index, temp_path) {
+            Ok(path) => log_files.push(path),
+            Err(e @ GlogError::ExtractLimitExceeded { .. }) => {
+                return Err(e).context(format!("解压缩失败: {}", files[index].name));
+            }
+            Err(e) => skip(&files[index].name, &e),
+        }
+    }
+    if log_files.is_empty() && !failures.is_empty() {
+        anyhow::bail!("解压缩失败: {} 个条目都无法解压", failures.len());
+    }
+    print_flush!("找到 {} 个日志文件", log_files.len());
+
+    // 调试：如果没有找到日志文件，列出压缩包内容
+    if log_files.is_empty() {
+        print_flush!("未找到日志文件，列出压缩包内容:");
+        for name in archive.file_names() {
+            print_flush!("  {}", name);
+        }
+    }
+    Ok(LogFiles {
+        paths: log_files,
+        failures,
+    })
+}
+
+/// 收集目录（含子目录）中的日志文件
+///
+/// 日志文件直接在原位置读取，不复制到临时目录；处理顺序与 ZIP 压缩包相同
+///
+/// # Arguments
+/// * `dir` - 日志目录
+/// * `date_range` - 按文件名日期筛选 glog 文件的范围（.glogmmap 始终保留）
+/// * `pattern` - 识别 glog 文件的文件名规则
+///
+/// # Returns
+/// 返回日志文件列表
+fn read_dir_input(
+    dir: &Path,
+    date_range: &FileDateRange,
+    pattern: &FileNamePattern,
+) -> Result<Vec<PathBuf>> {
+    let files = archive::scan_dir_with_pattern(dir, pattern)
+        .context(format!("读取目录失败: {}", dir.display()))?;
+    let infos: Vec<ArchiveEntryInfo> = files.iter().map(|(_, info)| info.clone()).collect();
+    let log_files: Vec<PathBuf> = filter_glog_files_by_date(&infos, date_range)
+        .into_iter()
+        .map(|index| files[index].0.clone())
+        .collect();
+    print_flush!("找到 {} 个日志文件", log_files.len());
+    Ok(log_files)
+}
+
+/// 读取标准输入并收集其中的日志文件
+///
+/// 标准输入会被完整读入内存：ZipArchive 需要随机访问，
+/// 日志读取器也需要知道数据总大小。根据魔数判断输入是 ZIP 压缩包还是单个 glog 数据流。
+///
+/// # Arguments
+/// * `temp_path` - 临时目录
+/// * `date_range` - 按文件名日期筛选 glog 文件的范围（仅对 ZIP 输入生效）
+/// * `pattern` - 识别 glog 文件的文件名规则（仅对 ZIP 输入生效）
+/// * `extract_limit` - 从 ZIP 中解压的总字节数上限
+///
+/// # Returns
+/// 返回日志文件列表
+fn read_stdin_input(
+    temp_path: &Path,
+    date_range: &FileDateRange,
+    pattern: &FileNamePattern,
+    extract_limit: u64,
+) -> Result<LogFiles> {
+    let mut data = Vec::new();
+    io::stdin().lock().read_to_end(&mut data).context("读取标准输入失败")?;
+
+    if data.starts_with(&ZIP_MAGIC) {
+        let archive = GlogArchive::from_reader_with_pattern(Cursor::new(data), None, pattern)
+            .context("无法读取 ZIP 文件")?;
+        return extract_archive(archive, temp_path, date_range, extract_limit);
+    }
+    if data.starts_with(&MAGIC_NUMBER) {
+        let glog_path = temp_path.join("stdin.glog");
+        fs::write(&glog_path, &data)?;
+        print_flush!("从标准输入读取到 1 个日志文件");
+        return Ok(LogFiles::from_paths(vec![glog_path]));
+    }
+    anyhow::bail!("无法识别标准输入的数据格式（既不是 ZIP 压缩包也不是 glog 文件）")
+}
+
+/// 下载 HTTP(S) 地址并收集其中的日志文件
+///
+/// 响应体先完整保存到临时目录：ZipArchive 需要随机访问。与标准输入相同，
+/// 根据魔数判断下载的是 ZIP 压缩包还是单个 glog 文件
+///
+/// # Arguments
+/// * `url` - HTTP(S) 地址
+/// * `headers` - 附加的请求头
+/// * `temp_path` - 临时目录
+/// * `date_range` - 按文件名日期筛选 glog 文件的范围（仅对 ZIP 输入生效）
+/// * `pattern` - 识别 glog 文件的文件名规则（仅对 ZIP 输入生效）
+/// * `extract_limit` - 从 ZIP 中解压的总字节数上限
+///
+/// # Returns
+/// 返回日志文件列表
+#[cfg(feature = "http")]
+fn read_http_input(
+    url: &str,
+    headers: &[String],
+    temp_path: &Path,
+    date_range: &FileDateRange,
+    pattern: &FileNamePattern,
+    extract_limit: u64,
+) -> Result<LogFiles> {
+    let download_path = temp_path.join("download");
+    print_flush!("正在下载: {}", url);
+    http::download(url, headers, &download_path)?;
+
+    let magic = read_magic(&download_path)?;
+    if magic == ZIP_MAGIC {
+        let archive = GlogArchive::open_with_pattern(&download_path, None, pattern)
+            .context(format!("无法读取下载的 ZIP 文件: {}", url))?;
+        return extract_archive(archive, temp_path, date_range, extract_limit);
+    }
+    if magic == MAGIC_NUMBER {
+        let glog_path = temp_path.join("download.glog");
+        fs::rename(&download_path, &glog_path)?;
+        print_flush!("下载到 1 个日志文件");
+        return Ok(LogFiles::from_paths(vec![glog_path]));
+    }
+    anyhow::bail!(
+        "无法识别下载的数据格式（既不是 ZIP 压缩包也不是 glog 文件）: {}",
+        url
+    )
+}
+
+/// 读取文件开头的 4 字节魔数，文件不足 4 字节时返回全部内容
+fn read_magic(path: &Path) -> io::Result<Vec<u8>> {
+    let mut magic = Vec::new();
+    fs::File::open(path)?.take(4).read_to_end(&mut magic)?;
+    Ok(magic)
+}
+
+/// 解析逗号分隔的日志类型列表，忽略无法解析的项
+fn parse_types(value: &str) -> Vec<i32> {
+    value
+        .split(',')
+        .filter_map(|s| s.trim().parse::<i32>().ok())
+        .collect()
+}
+
+/// 默认的解码线程数（CPU 核心数）
+fn default_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// 单个文件的解码输出
+enum FileOutput {
+    /// 直接写入输出文件的字节（格式化文本或原始条目）
+    Text(Vec<u8>),
+    /// 日志及其在源文件中的偏移（结构化输出使用）
+    Logs(Vec<(u64, Log)>),
+}
+
+/// 单个文件的解码结果：读取结果和解码输出
+type DecodeResult = Result<(FileReport, FileOutput)>;
+
+/// 输出目标
+enum Output {
+    /// 文本文件（可按大小轮转），原始输出和 bulk NDJSON 同样写入此处
+    Text(BufWriter<RotatingWriter>),
+    /// SQLite 数据库
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteWriter, PathBuf),
+    /// Trace Event JSON 文件
+    Trace(TraceWriter<BufWriter<fs::File>>, PathBuf),
+}
+
+impl Output {
+    /// 按输出格式打开输出目标
+    ///
+    /// # Arguments
+    /// * `args` - 命令行参数
+    /// * `path` - 输出路径
+    /// * `input` - 来源压缩包路径（用于追加模式的分隔头）
+    fn open(args: &ExtractArgs, path: &Path, input: &str) -> Result<Self> {
+        match args.format {
+            OutputFormat::Text
+            | OutputFormat::Logfmt
+            | OutputFormat::Raw
+            | OutputFormat::EsBulk => {
+                let output = RotatingWriter::new(path, args.max_output_size, args.append)
+                    .context(format!("创建输出文件失败: {}", path.display()))?;
+                let mut writer = BufWriter::new(output);
+                if args.append && args.format == OutputFormat::Text {
+                    write_append_header(&mut writer, input)?;
+                }
+                Ok(Output::Text(writer))
+            }
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => {
+                // 未追加时重新创建数据库，避免与旧数据混在一起
+                if !args.append && path.exists() {
+                    fs::remove_file(path)
+                        .context(format!("删除已存在的数据库失败: {}", path.display()))?;
+                }
+                Ok(Output::Sqlite(SqliteWriter::open(path)?, path.to_path_buf()))
+            }
+            #[cfg(not(feature = "sqlite"))]
+            OutputFormat::Sqlite => unreachable!("sqlite 功能未启用"),
+            OutputFormat::TraceJson => {
+                let file = fs::File::create(path)
+                    .context(format!("创建输出文件失败: {}", path.display()))?;
+                let writer = TraceWriter::new(BufWriter::new(file))?;
+                Ok(Output::Trace(writer, path.to_path_buf()))
+            }
+        }
+    }
+
+    /// 写入单个文件的解码输出
+    ///
+    /// 文本输出在解码时已按需标注来源，SQLite 总是记录来源文件和偏移
+    ///
+    /// # Arguments
+    /// * `log_file` - 来源日志文件
+    /// * `output` - 解码输出
+    /// * `show_source` - 是否在 Trace Event 中记录来源（`--show-source`）
+    fn write(&mut self, log_file: &Path, output: FileOutput, show_source: bool) -> Result<()> {
+        match (self, output) {
+            (Output::Text(writer), FileOutput::Text(text)) => writer.write_all(&text)?,
+            #[cfg(feature = "sqlite")]
+            (Output::Sqlite(writer, _), FileOutput::Logs(logs)) => {
+                let name = log_file
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| log_file.display().to_string());
+                writer.insert_logs(&name, &logs)?;
+            }
+            (Output::Trace(writer, _), FileOutput::Logs(logs)) => {
+                let file = base_name(log_file);
+                for (offset, log) in &logs {
+                    let source = Source {
+                        file: &file,
+                        offset: *offset,
+                    };
+                    writer.write_log(log, show_source.then_some(source))?;
+                }
+            }
+            _ => unreachable!("输出格式与解码输出不匹配: {}", log_file.display()),
+        }
+        Ok(())
+    }
+
+    /// 完成输出，返回写入的所有文件路径
+    fn finish(self) -> Result<Vec<PathBuf>> {
+        match self {
+            Output::Text(writer) => {
+                let output = writer.into_inner().map_err(|e| e.into_error())?;
+                Ok(output.paths().to_vec())
+            }
+            #[cfg(feature = "sqlite")]
+            Output::Sqlite(_, path) => Ok(vec![path]),
+            Output::Trace(writer, path) => {
+                if writer.skipped() > 0 {
+                    eprint_flush!(
+                        "警告: {} 条日志的时间戳无法解析，未写入时间线",
+                        writer.skipped()
+                    );
+                }
+                writer.finish()?.flush()?;
+                Ok(vec![path])
+            }
+        }
+    }
+}
+
+/// 捕获 Ctrl-C
+///
+/// 第一次按下时置位取消标志：正在解码的文件停在下一条日志之前，尚未开始的文件不再解码，
+/// 已解码的日志照常写入输出并打印统计；再次按下时立即退出
+///
+/// # Arguments
+/// * `cancel` - 取消标志
+fn install_interrupt_handler(cancel: &Arc<AtomicBool>) {
+    let cancel = Arc::clone(cancel);
+    let result = ctrlc::set_handler(move || {
+        if cancel.swap(true, Ordering::Relaxed) {
+            exit(EXIT_INTERRUPTED);
+        }
+        eprint_flush!("收到中断信号，正在写出已解码的日志（再次按 Ctrl-C 立即退出）");
+    });
+    if let Err(e) = result {
+        eprint_flush!("警告: 无法捕获 Ctrl-C: {}", e);
+    }
+}
+
+/// 错误是否由取消标志引起
+fn is_cancelled(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<GlogError>()
+        .is_some_and(|e| matches!(e.kind(), GlogError::Cancelled))
+}
+
+/// 使用多个线程并行解码日志文件
+///
+/// 基于 [`archive::map_files_parallel`]：每个文件由一个工作线程独立解码到内存缓冲区中
+/// （各自的 `FileReaderV3/V4` 实例及其 ECDH 共享密钥缓存互不共享），主线程按原始文件顺序依次回调 `on_result`，
+/// 保证输出顺序与单线程解码完全一致。
+///
+/// # Arguments
+/// * `log_files` - 按顺序排列的日志文件
+/// * `options` - 读取选项
+/// * `threads` - 工作线程数
+/// * `on_result` - 按文件顺序接收每个文件解码结果的回调
+fn decode_files<F>(
+    log_files: &[PathBuf],
+    options: &ReadOptions,
+    threads: usize,
+    on_result: F,
+) -> Result<()>
+where
+    F: FnMut(&Path, DecodeResult) -> Result<()>,
+{
+    archive::map_files_parallel(
+        log_files,
+        Some(threads),
+        |log_file| {
+            if options.cancel.load(Ordering::Relaxed) {
+                return Err(GlogError::Cancelled.into());
+            }
+            decode_file(log_file, options)
+        },
+        on_result,
+    )
+}
+
+/// 按输出格式解码单个日志文件
+///
+/// # Arguments
+/// * `log_file` - 日志文件路径
+/// * `options` - 读取选项
+fn decode_file(log_file: &Path, options: &ReadOptions) -> DecodeResult {
+    match options.format {
+        OutputFormat::Text | OutputFormat::Logfmt => {
+            let file = options.show_source.then(|| base_name(log_file));
+            let mut output = Deduper::new(
+                Vec::new(),
+                options.dedup_consecutive,
+                options.formatter.clone(),
+            )
+            .with_source_file(file.as_deref());
+            let file_report = read_logs(log_file, options, &mut output)?;
+            Ok((file_report, FileOutput::Text(output.finish()?)))
+        }
+        OutputFormat::Raw => {
+            let mut output = RawWriter::new(Vec::new());
+            let file_report = read_logs(log_file, options, &mut output)?;
+            Ok((file_report, FileOutput::Text(output.into_inner())))
+        }
+        OutputFormat::EsBulk => {
+            let mut output = EsBulkWriter::new(
+                Vec::new(),
+                &options.es_index,
+                &options.archive_name,
+                &base_name(log_file),
+            )
+            .with_source(options.show_source);
+            let file_report = read_logs(log_file, options, &mut output)?;
+            Ok((file_report, FileOutput::Text(output.into_inner())))
+        }
+        OutputFormat::Sqlite | OutputFormat::TraceJson => {
+            let mut logs = Vec::new();
+            let file_report = read_logs(log_file, options, &mut logs)?;
+            Ok((file_report, FileOutput::Logs(logs)))
+        }
+    }
+}
+
+/// 写入追加模式的分隔头
+///
+/// 分隔头包含来源压缩包名称和当前时间，便于区分多次运行拼接的输出
+///
+/// # Arguments
+/// * `writer` - 输出写入器
+/// * `input` - 来源压缩包路径
+fn write_append_header<W: Write>(writer: &mut W, input: &str) -> Result<()> {
+    let source = Path::new(input)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| input.to_string());
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    writeln!(writer, "===== {} @ {} =====", source, now)?;
+    Ok(())
+}
+
+/// 按文件名中的日期过滤 glog 文件
+///
+/// 文件名中的日期无法解析时默认保留该文件并打印警告；mmap 文件始终保留
+///
+/// # Arguments
+/// * `files` - 压缩包中的日志文件
+/// * `date_range` - 日期范围
+///
+/// # Returns
+/// 返回保留的日志文件在 `files` 中的序号
+fn filter_glog_files_by_date(files: &[ArchiveEntryInfo], date_range: &FileDateRange) -> Vec<usize> {
+    let all = (0..files.len()).collect();
+    if date_range.is_unbounded() {
+        return all;
+    }
+
+    let is_glog = |file: &ArchiveEntryInfo| file.kind == ArchiveEntryKind::Glog;
+    let total = files.iter().filter(|f| is_glog(f)).count();
+    let selected: Vec<usize> = files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| match (file.kind, file.date) {
+            (ArchiveEntryKind::Mmap, _) => true,
+            (ArchiveEntryKind::Glog, Some(date)) => date_range.contains(date),
+            (ArchiveEntryKind::Glog, None) => {
+                eprint_flush!("无法从文件名中解析日期，默认保留: {}", file.name);
+                true
+            }
+        })
+        .map(|(index, _)| index)
+        .collect();
+    let kept = selected.iter().filter(|&&i| is_glog(&files[i])).count();
+    print_flush!("按文件日期过滤后保留 {}/{} 个 glog 文件", kept, total);
+    selected
+}
+
+/// 读取日志文件
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `options` - 读取选项
+/// * `sink` - 通过过滤的日志的输出目标
+///
+/// # Returns
+/// 返回该文件的读取结果
+///
+/// 严格模式下，任何 NeedRecover、解压/解密错误或 protobuf 解码错误
+/// 都会立即终止该文件的处理，并在错误信息中附带文件路径和字节偏移
+fn read_logs<S: LogSink>(
+    file_path: &Path,
+    options: &ReadOptions,
+    sink: &mut S,
+) -> Result<FileReport> {
+    let report_observer = Arc::new(ReportObserver::default());
+    let mut reader = open_reader(file_path, options, GlogReader::builder(), &report_observer)?;
+
+    let file_size = fs::metadata(file_path)?.len();
+    let file_name = base_name(file_path);
+    let mut file_report = FileReport::new(file_path);
+    let mut detector = options.time_jumps.clone();
+    let file_index = options.index.as_ref().and_then(|index| {
+        let found = index.file(&file_name, file_size);
+        if found.is_none() {
+            eprint_flush!(
+                "警告: 索引中没有 {} 或文件大小已变化，按常规方式读取",
+                file_name
+            );
+        }
+        found
+    });
+    let mut scratch = Vec::new();
+    let mut entry_index = 0;
+
+    let mut buf = Vec::new();
+    loop {
+        let entry_start = reader.position();
+        // 索引表明下一条日志不可能满足过滤条件时只跳过条目结构，不再解码
+        let skip = file_index
+            .and_then(|index| index.lookup(entry_start))
+            .is_some_and(|indexed| !indexed.may_match(&options.filter));
+        let result = if skip {
+            reader.skip_entry(&mut scratch)
+        } else {
+            reader.read_entry(&mut buf)
+        };
+        match result {
+            Ok(Some(_)) if skip => {
+                entry_index += 1;
+                file_report.decoded += 1;
+            }
+            Ok(Some(entry)) => {
+                let len = entry.len;
+                entry_index += 1;
+                if len == 0 {
+                    continue;
+                }
+
+                // 原始输出：在 protobuf 解码之前原样写出条目
+                if options.format == OutputFormat::Raw && !options.count_only {
+                    sink.write_raw(entry.offset, &buf[..len])?;
+                    file_report.count += 1;
+                    continue;
+                }
+
+                // 快速路径：只解码日志类型字段
+                if options.type_only_decode() {
+                    match LogTypeOnly::decode(&buf[..len]) {
+                        Ok(t) => {
+                            file_report.decoded += 1;
+                            if options.filter.matches_type(t.log_type) {
+                                file_report.count += 1;
+                            }
+                        }
+                        Err(_) => {
+                            // 只解码类型字段时不经过读取器解码，需要自行通知
+                            report_observer.on_decode_failure(entry.offset, entry.entry_len);
+                            dump_failed_entry(options, file_path, entry_index - 1, &entry, &buf);
+                        }
+                    }
+                    continue;
+                }
+
+                // 先只解析类型字段，不满足类型条件的日志不再完整解码；
+                // 检测时间戳跳变时每条日志都需要完整解码
+                if options.time_jumps.is_none()
+                    && reader.rejects_by_type(&buf[..len], &options.filter)
+                {
+                    file_report.decoded += 1;
+                    continue;
+                }
+
+                // 解析 protobuf 日志
+                match reader.decode_log(&buf[..len]) {
+                    Ok(log) => {
+                        file_report.decoded += 1;
+                        options.check_time_jump(
+                            &mut detector,
+                            entry.offset,
+                            &log,
+                            &mut file_report,
+                            sink,
+                        )?;
+
+                        // 检查类型及标签过滤，再按抽样选择
+                        if !options.filter.matches(&log)
+                            || !options.sample_keeps(&file_name, entry.offset, &log)
+                        {
+                            continue;
+                        }
+                        if options.collect_stats {
+                            file_report.stats.record(&log);
+                        }
+                        if options.count_only {
+                            file_report.count += 1;
+                            continue;
+                        }
+
+                        // 写入日志
+                        sink.write_log(entry.offset, log)?;
+                        file_report.count += 1;
+                    }
+                    Err(e) => {
+                        // 解码失败已由读取器通知报告观察者
+                        dump_failed_entry(options, file_path, entry_index - 1, &entry, &buf);
+                        if options.strict {
+                            let message = format!(
+                                "{}:{}: 解析日志失败: {}",
+                                file_path.display(),
+                                reader.position(),
+                                e
+                            );
+                            eprint_flush!("{}", message);
+                            file_report.set_error(message, Some(e.code()));
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                if let GlogError::NeedRecover(reason) = e.kind() {
+                    file_report.record_recover(reason.code());
+                }
+                // 错误中已包含 `文件:偏移` 前缀
+                eprint_flush!("读取错误: {}", e);
+                file_report.fail(FileError::from_glog(ErrorStage::Read, &e));
+                // 出错后文件剩余部分均无法读取；中断时剩余部分并未损坏
+                if !matches!(e.kind(), GlogError::Cancelled) {
+                    file_report.bytes_skipped += file_size.saturating_sub(entry_start);
+                }
+                break;
+            }
+        }
+    }
+
+    drop(reader);
+    report_observer.apply(&mut file_report);
+
+    print_flush!("共读取 {} 条日志", file_report.count);
+    warn_lossy(&file_report);
+    warn_time_jumps(&file_report);
+    print_redactions(&file_report);
+    Ok(file_report)
+}
+
+/// 按读取选项打开日志文件
+///
+/// 严格模式遇到损坏条目即终止；否则跳过。处理进度由 [`ProgressObserver`] 打印，
+/// 恢复事件和解码失败由 `report_observer` 收集后写入报告
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `options` - 读取选项
+/// * `builder` - 读取器构建器，可预先设置其他选项
+/// * `report_observer` - 该文件的报告观察者
+fn open_reader(
+    file_path: &Path,
+    options: &ReadOptions,
+    builder: GlogReaderBuilder,
+    report_observer: &Arc<ReportObserver>,
+) -> Result<GlogReader> {
+    let recovery = if options.strict {
+        RecoveryPolicy::Abort
+    } else {
+        RecoveryPolicy::SkipSilently
+    };
+
+    // 使用私钥打开日志文件
+    let builder = builder
+        .key(options.key.as_str())
+        .recovery_policy(recovery)
+        .strict(options.strict)
+        .cancel_token(Arc::clone(&options.cancel))
+        .observer(Arc::new(ProgressObserver))
+        .observer(report_observer.clone());
+    let builder = match &options.types {
+        Some(types) => builder.type_registry(Arc::clone(types)),
+        None => builder,
+    };
+    let builder = match &options.redactor {
+        Some(redactor) => builder.redactor(Arc::clone(redactor)),
+        None => builder,
+    };
+    #[cfg(feature = "dynamic")]
+    let builder = match &options.descriptor_set {
+        Some(bytes) => builder.descriptor_set(DescriptorSet::Bytes(bytes.clone())),
+        None => builder,
+    };
+    builder
+        .open(&file_path.to_string_lossy())
+        .context(format!("打开日志文件失败: {}", file_path.display()))
+}
+
+/// 按时间戳合并读取多个日志文件并写入文本输出
+///
+/// 各文件内部需按时间有序，合并时每个文件只预读一条日志。
+/// 严格模式下某个文件遇到解码异常时只停止该文件，其余文件继续合并
+///
+/// # Arguments
+/// * `log_files` - 日志文件，顺序即时间戳相同时的输出顺序
+/// * `options` - 读取选项
+/// * `output` - 文本输出
+///
+/// # Returns
+/// 返回逐文件的读取结果
+fn merge_files<W: Write>(
+    log_files: &[PathBuf],
+    options: &ReadOptions,
+    output: W,
+) -> Result<Vec<FileReport>> {
+    let mut reports: Vec<FileReport> = log_files.iter().map(|f| FileReport::new(f)).collect();
+    let mut observers = Vec::new();
+    let mut readers = Vec::new();
+    let mut sources = Vec::new();
+    for (index, log_file) in log_files.iter().enumerate() {
+        let report_observer = Arc::new(ReportObserver::default());
+        let builder = GlogReader::builder().strict(options.strict);
+        match open_reader(log_file, options, builder, &report_observer) {
+            Ok(reader) => {
+                readers.push(reader);
+                sources.push(index);
+                observers.push(report_observer);
+            }
+            Err(e) => {
+                eprint_flush!("读取日志失败 {}: {:#}", log_file.display(), e);
+                reports[index].fail(FileError::header(&e));
+            }
+        }
+    }
+
+    let file_names: Vec<String> = log_files.iter().map(|f| base_name(f)).collect();
+    // 合并输出保持每个文件内部的顺序，检测器按文件分别记录
+    let mut detectors = vec![options.time_jumps.clone(); log_files.len()];
+    let mut sink = Deduper::new(output, options.dedup_consecutive, options.formatter.clone());
+    let mut merged = MergedGlogReader::new(readers);
+    while let Some((source, result)) = merged.next_with_source() {
+        let index = sources[source];
+        let file_report = &mut reports[index];
+        match result {
+            Ok((offset, log)) => {
+                file_report.decoded += 1;
+                options.check_time_jump(
+                    &mut detectors[index],
+                    offset,
+                    &log,
+                    file_report,
+                    &mut sink,
+                )?;
+                if options.filter.matches(&log)
+                    && options.sample_keeps(&file_names[index], offset, &log)
+                {
+                    if options.collect_stats {
+                        file_report.stats.record(&log);
+                    }
+                    let source = Source {
+                        file: &file_names[index],
+                        offset,
+                    };
+                    sink.write_log_with_source(&log, options.show_source.then_some(source))?;
+                    file_report.count += 1;
+                }
+            }
+            Err(e) if matches!(e.kind(), GlogError::ProtobufError(_)) => {
+                if options.strict {
+                    let message = format!("解析日志失败: {}", e);
+                    eprint_flush!("{}", message);
+                    file_report.set_error(message, Some(e.code()));
+                }
+            }
+            Err(e) => {
+                if let GlogError::NeedRecover(reason) = e.kind() {
+                    file_report.record_recover(reason.code());
+                }
+                eprint_flush!("读取错误: {}", e);
+                file_report.fail(FileError::from_glog(ErrorStage::Read, &e));
+            }
+        }
+    }
+    sink.finish()?;
+    drop(merged);
+
+    for (source, report_observer) in observers.iter().enumerate() {
+        report_observer.apply(&mut reports[sources[source]]);
+    }
+    for file_report in &reports {
+        print_flush!("{}: 成功读取 {} 条日志", file_report.file, file_report.count);
+        warn_lossy(file_report);
+        warn_time_jumps(file_report);
+        print_redactions(file_report);
+    }
+    Ok(reports)
+}
+
+/// 日志文件的文件名（不含目录），用于抽样哈希和匹配索引
+///
+/// 压缩包每次解压到不同的临时目录，只取文件名才能保持不变
+fn base_name(file_path: &Path) -> String {
+    file_path.file_name().map_or_else(
+        || file_path.display().to_string(),
+        |n| n.to_string_lossy().to_string(),
+    )
+}
+
+/// 有条目含有无效 UTF-8 时打印警告
+///
+/// 非严格模式下这些条目按容错方式解码，无效的字节显示为 U+FFFD
+fn warn_lossy(file_report: &FileReport) {
+    if file_report.lossy_decodes > 0 {
+        eprint_flush!(
+            "警告: {}: {} 条日志含有无效的 UTF-8，已替换为 U+FFFD",
+            file_report.file,
+            file_report.lossy_decodes
+        );
+    }
+}
+
+/// 检测到时间戳跳变时逐处打印警告
+fn warn_time_jumps(file_report: &FileReport) {
+    for jump in &file_report.time_jumps {
+        eprint_flush!(
+            "警告: {}: 偏移 {} 处时间戳跳变: {}",
+            file_report.file,
+            jump.offset,
+            jump
+        );
+    }
+}
+
+/// 脱敏规则替换过内容时打印替换次数
+fn print_redactions(file_report: &FileReport) {
+    if file_report.redactions > 0 {
+        print_flush!("{}: 已脱敏 {} 处", file_report.file, file_report.redactions);
+    }
+}
+
+/// 转储解码失败的条目并打印警告
+///
+/// 未指定 `--dump-failed` 时不做任何处理
+///
+/// # Arguments
+/// * `options` - 读取选项
+/// * `file_path` - 来源日志文件
+/// * `index` - 条目序号
+/// * `entry` - 条目的存储信息
+/// * `payload` - 条目数据
+fn dump_failed_entry(
+    options: &ReadOptions,
+    file_path: &Path,
+    index: usize,
+    entry: &EntryInfo,
+    payload: &[u8],
+) {
+    let Some(dir) = &options.dump_failed else {
+        return;
+    };
+    match dump::dump_entry(dir, file_path, index, entry.offset, payload) {
+        Ok(path) => eprint_flush!(
+            "警告: {}:{}: 第 {} 条日志解码失败（{}），已转储到 {}\n{}",
+            file_path.display(),
+            entry.offset,
+            index,
+            dump::describe_entry(entry),
+            path.display(),
+            dump::hexdump(payload)
+        ),
+        Err(e) => eprint_flush!("警告: 转储解码失败的条目出错: {}", e),
+    }
+}
+
+/// 跟随读取正在写入的日志文件
+///
+/// 先读取文件中已有的日志，然后轮询文件增长并持续解码新追加的日志，
+/// 输出到 stdout。mmap 缓存文件按写入游标判断新写入的日志。检测到文件被截断/替换
+/// （或缓存文件刷入 .glog 后游标回退）时重新打开；
+/// 检测到同目录下出现更新的轮转文件（如次日的 async-YYYYMMdd.glog）时切换到新文件。
+///
+/// # Arguments
+/// * `path` - 日志文件路径
+/// * `options` - 读取选项
+///
+/// # Returns
+/// 仅在读取出错时返回，返回进程退出码
+fn follow(path: &Path, options: &ReadOptions) -> Result<i32> {
+    let mut current = path.to_path_buf();
+    let mut reader = open_follow_reader(&current, options)?;
+    eprint_flush!("正在跟随: {}", current.display());
+
+    loop {
+        if let Err(e) = print_follow_logs(&mut reader, options) {
+            eprint_flush!("读取错误: {}", e);
+            return Ok(EXIT_PARTIAL);
+        }
+
+        // 已读到文件末尾，等待写入方追加数据
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+
+        let len = follow_size(&current.to_string_lossy()).unwrap_or(0);
+        if len < reader.position() {
+            eprint_flush!("文件被截断或替换，重新打开: {}", current.display());
+            reader = open_follow_reader(&current, options)?;
+            continue;
+        }
+        reader.set_size(len);
+
+        if let Some(next) = find_rotated_file(&current) {
+            // 轮转前写入旧文件的日志先读完，再切换到新文件
+            if let Err(e) = print_follow_logs(&mut reader, options) {
+                eprint_flush!("读取错误: {}", e);
+                return Ok(EXIT_PARTIAL);
+            }
+            eprint_flush!("切换到新文件: {}", next.display());
+            current = next;
+            reader = open_follow_reader(&current, options)?;
+        }
+    }
+}
+
+/// 输出跟随读取器中当前可读的所有日志，解码失败的条目直接跳过
+///
+/// # Arguments
+/// * `reader` - 跟随模式的读取器
+/// * `options` - 读取选项，使用其中的过滤条件和输出格式
+///
+/// # Errors
+/// 读取出错时返回错误
+fn print_follow_logs(reader: &mut GlogReader, options: &ReadOptions) -> Result<(), GlogError> {
+    for item in reader.logs().with_filter(options.filter.clone()) {
+        match item {
+            Ok(log) => print_flush!("{}", options.formatter.format(&log)),
+            Err(e) if matches!(e.kind(), GlogError::ProtobufError(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// 以跟随模式打开日志文件
+///
+/// # Arguments
+/// * `path` - 日志文件路径
+/// * `options` - 读取选项，使用其中的私钥和脱敏规则
+fn open_follow_reader(path: &Path, options: &ReadOptions) -> Result<GlogReader> {
+    let builder = GlogReader::builder().key(options.key.as_str());
+    let builder = match &options.redactor {
+        Some(redactor) => builder.redactor(Arc::clone(redactor)),
+        None => builder,
+    };
+    builder
+        .open_follow(&path.to_string_lossy(), FOLLOW_POLL_INTERVAL)
+        .context(format!("打开日志文件失败: {}", path.display()))
+}
+
+/// 查找轮转后的新日志文件
+///
+/// 在同一目录下查找扩展名相同、文件名按字典序大于当前文件的文件，
+/// 返回其中最新的一个。`async-YYYYMMdd.glog` 的文件名按日期递增，
+/// 因此字典序更大即表示写入方已轮转到新文件。
+///
+/// # Arguments
+/// * `current` - 当前跟随的文件路径
+///
+/// # Returns
+/// 返回轮转后的新文件路径，没有则返回 None
+fn find_rotated_file(current: &Path) -> Option<PathBuf> {
+    let dir = current.parent()?;
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let name = current.file_name()?.to_string_lossy().to_string();
+    let ext = current.extension()?.to_string_lossy().to_string();
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.ends_with(&format!(".{}", ext)) && n.as_str() > name.as_str())
+        .max()
+        .map(|n| dir.join(n))
 }
@@ -0,0 +1,210 @@
+//! # 多文件合并读取
+//!
+//! 一天的日志通常分布在 `async-YYYYMMdd.glog` 和仍在写入的 `.glogmmap` 中。
+//! [`MergedGlogReader`] 对多个各自按时间有序的读取器做流式 k 路归并，
+//! 每个读取器只预读一条日志，不需要把全部日志读入内存再排序。
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::error::{GlogError, Result};
+use crate::glog::GlogReader;
+use crate::proto::{Log, LogFilter};
+
+/// 按时间戳合并多个读取器的日志
+///
+/// 每次产出所有读取器下一条日志中时间戳最早的一条，时间戳相同时按读取器顺序。
+/// 时间戳无法解析的日志沿用同一读取器上一条日志的时间戳，即保持在原文件中的位置；
+/// 读取器开头的此类日志排在最前面。
+///
+/// 单个读取器的解码失败和读取错误在遇到时立即以 `Err` 产出，其余读取器不受影响
+/// （各读取器之后是否继续与 [`Logs`](crate::glog::Logs) 相同）。
+pub struct MergedGlogReader {
+    /// 参与合并的读取器
+    sources: Vec<Source>,
+    /// 各读取器预读日志的排序键（时间戳, 读取器序号），堆顶为最早的一条
+    heap: BinaryHeap<Reverse<(i64, usize)>>,
+    /// 预读时遇到、尚未产出的错误
+    errors: VecDeque<(usize, GlogError)>,
+    /// 过滤条件，不满足的日志直接跳过
+    filter: Option<LogFilter>,
+    /// 是否已预读各读取器的第一条日志
+    started: bool,
+}
+
+/// 单个读取器及其预读状态
+struct Source {
+    /// 底层读取器
+    reader: GlogReader,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+    /// 是否已读完
+    done: bool,
+    /// 预读的日志及其条目偏移
+    head: Option<(u64, Log)>,
+    /// 上一条日志的时间戳
+    last_timestamp: i64,
+}
+
+impl MergedGlogReader {
+    /// 创建合并读取器
+    ///
+    /// # Arguments
+    /// * `readers` - 各自按时间有序的读取器，顺序即时间戳相同时的产出顺序
+    pub fn new(readers: Vec<GlogReader>) -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(readers.len()),
+            sources: readers
+                .into_iter()
+                .map(|reader| Source {
+                    reader,
+                    buf: Vec::new(),
+                    done: false,
+                    head: None,
+                    last_timestamp: i64::MIN,
+                })
+                .collect(),
+            errors: VecDeque::new(),
+            filter: None,
+            started: false,
+        }
+    }
+
+    /// 只产出满足过滤条件的日志
+    ///
+    /// # Arguments
+    /// * `filter` - 过滤条件
+    pub fn with_filter(mut self, filter: LogFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// 参与合并的读取器数量
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// 是否没有任何读取器
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// 获取第 `index` 个读取器，用于查询进度或元数据
+    pub fn reader(&self, index: usize) -> Option<&GlogReader> {
+        self.sources.get(index).map(|source| &source.reader)
+    }
+
+    /// 读取下一条日志，同时返回它来自哪个读取器
+    ///
+    /// # Returns
+    /// 返回 `(读取器序号, 日志或错误)`，日志附带条目在源文件中的起始偏移；全部读完时返回 `None`
+    pub fn next_with_source(&mut self) -> Option<(usize, Result<(u64, Log)>)> {
+        if !self.started {
+            self.started = true;
+            for index in 0..self.sources.len() {
+                self.fill(index);
+            }
+        }
+        if let Some((index, e)) = self.errors.pop_front() {
+            return Some((index, Err(e)));
+        }
+        let Reverse((_, index)) = self.heap.pop()?;
+        let head = self.sources[index].head.take()?;
+        self.fill(index);
+        Some((index, Ok(head)))
+    }
+
+    /// 预读第 `index` 个读取器的下一条日志，途中遇到的错误放入待产出队列
+    fn fill(&mut self, index: usize) {
+        let source = &mut self.sources[index];
+        while let Some(result) =
+            source
+                .reader
+                .next_log(&mut source.buf, self.filter.as_ref(), &mut source.done)
+        {
+            match result {
+                Ok((offset, log)) => {
+                    if let Some(ts) = log.timestamp_millis() {
+                        source.last_timestamp = ts;
+                    }
+                    self.heap.push(Reverse((source.last_timestamp, index)));
+                    source.head = Some((offset, log));
+                    return;
+                }
+                Err(e) => self.errors.push_back((index, e)),
+            }
+        }
+    }
+}
+
+impl Iterator for MergedGlogReader {
+    type Item = Result<Log>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_source()
+            .map(|(_, result)| result.map(|(_, log)| log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::CompressMode;
+    use crate::writer::v3::GlogWriterV3;
+    use std::io::Cursor;
+
+    /// 构造包含给定 (时间戳, 内容) 日志的读取器
+    fn reader(logs: &[(&str, &str)]) -> GlogReader {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        for (timestamp, msg) in logs {
+            writer
+                .write_log(&Log {
+                    timestamp: timestamp.to_string(),
+                    msg: msg.to_string(),
+                    ..Log::new()
+                })
+                .unwrap();
+        }
+        let content = writer.into_inner().unwrap();
+        let size = content.len() as u64;
+        GlogReader::from_reader(Cursor::new(content), size, None).unwrap()
+    }
+
+    fn messages(merged: MergedGlogReader) -> Vec<String> {
+        merged.map(|log| log.unwrap().msg).collect()
+    }
+
+    #[test]
+    fn test_merges_by_timestamp() {
+        let merged = MergedGlogReader::new(vec![
+            reader(&[("100", "a1"), ("300", "a3"), ("500", "a5")]),
+            reader(&[("200", "b2"), ("300", "b3"), ("400", "b4")]),
+            reader(&[]),
+        ]);
+        assert_eq!(messages(merged), ["a1", "b2", "a3", "b3", "b4", "a5"]);
+    }
+
+    #[test]
+    fn test_unparseable_timestamp_keeps_file_position() {
+        let merged = MergedGlogReader::new(vec![
+            reader(&[("100", "a1"), ("300", "a3")]),
+            reader(&[("bad", "b0"), ("200", "b2"), ("", "b2+"), ("400", "b4")]),
+        ]);
+        assert_eq!(messages(merged), ["b0", "a1", "b2", "b2+", "a3", "b4"]);
+    }
+
+    #[test]
+    fn test_reports_source_and_applies_filter() {
+        let mut merged = MergedGlogReader::new(vec![
+            reader(&[("100", "a1"), ("300", "a3")]),
+            reader(&[("200", "b2")]),
+        ])
+        .with_filter(LogFilter::new().time_range(Some(150), None));
+
+        let (index, log) = merged.next_with_source().unwrap();
+        assert_eq!((index, log.unwrap().1.msg.as_str()), (1, "b2"));
+        let (index, log) = merged.next_with_source().unwrap();
+        assert_eq!((index, log.unwrap().1.msg.as_str()), (0, "a3"));
+        assert!(merged.next_with_source().is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! # 读取观察者
+//!
+//! 嵌入读取器的应用需要进度和计数时，不必解析命令行工具的 stderr 输出：
+//! 通过 [`GlogReaderBuilder::observer`](crate::glog::GlogReaderBuilder::observer)（或
+//! [`GlogArchive::set_observer`](crate::archive::GlogArchive::set_observer)）注册
+//! [`Observer`]，读取循环在打开文件、读出条目、恢复损坏条目、解码失败和结束读取时通知它。
+//!
+//! 同一个观察者可以交给多个读取器（包括并行解码的工作线程），因此方法只接收 `&self`，
+//! 需要累积状态时使用原子变量或锁。[`MetricsObserver`] 是按原子计数汇总的实现。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::glog::RecoverEvent;
+
+/// 读取过程的观察者
+///
+/// 所有方法都有空的默认实现，只需实现关心的事件
+pub trait Observer: Send + Sync {
+    /// 读取器创建完成（文件头已解析）
+    ///
+    /// # Arguments
+    /// * `path` - 日志文件路径或压缩包中的条目名称，从输入流创建时为 `None`
+    fn on_file_start(&self, _path: Option<&str>) {}
+
+    /// 读出一个条目（跳过的加密条目和空条目不计）
+    ///
+    /// # Arguments
+    /// * `offset` - 条目的起始偏移
+    /// * `len` - 条目占用的字节数
+    fn on_entry(&self, _offset: u64, _len: u64) {}
+
+    /// 跳过损坏条目并重新同步（只在跳过策略下发生）
+    ///
+    /// # Arguments
+    /// * `event` - 恢复事件
+    fn on_recover(&self, _event: RecoverEvent) {}
+
+    /// 条目读出后 protobuf 解码失败
+    ///
+    /// # Arguments
+    /// * `offset` - 条目的起始偏移
+    /// * `len` - 条目占用的字节数
+    fn on_decode_failure(&self, _offset: u64, _len: u64) {}
+
+    /// 条目含有无效的 UTF-8，按容错方式解码（只在非严格模式下发生）
+    ///
+    /// # Arguments
+    /// * `offset` - 条目的起始偏移
+    /// * `len` - 条目占用的字节数
+    fn on_lossy_decode(&self, _offset: u64, _len: u64) {}
+
+    /// 解码出的日志经 [`Redactor`](crate::redact::Redactor) 替换了敏感信息
+    ///
+    /// # Arguments
+    /// * `offset` - 条目的起始偏移
+    /// * `count` - 该条日志中替换的次数
+    fn on_redact(&self, _offset: u64, _count: u64) {}
+
+    /// 读取结束：读到文件末尾、遇到无法继续的错误或读取器被丢弃，每个读取器只通知一次
+    ///
+    /// # Arguments
+    /// * `path` - 与 [`on_file_start`](Self::on_file_start) 相同
+    /// * `stats` - 该读取器的累计统计
+    fn on_file_end(&self, _path: Option<&str>, _stats: &ReadStats) {}
+}
+
+/// 单个读取器的累计统计，参见 [`GlogReader::stats`](crate::glog::GlogReader::stats)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadStats {
+    /// 读出的条目数
+    pub entries: u64,
+    /// 读出的条目占用的字节数
+    pub bytes: u64,
+    /// 恢复次数
+    pub recoveries: u64,
+    /// 恢复时跳过的字节数
+    pub bytes_skipped: u64,
+    /// protobuf 解码失败的条目数
+    pub decode_failures: u64,
+    /// 含有无效 UTF-8、按容错方式解码的条目数
+    pub lossy_decodes: u64,
+    /// 脱敏时替换的次数
+    pub redactions: u64,
+}
+
+/// 汇总计数的观察者
+///
+/// 计数使用原子变量，可以在读取进行中从其他线程读取
+///
+/// # 示例
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use clog_reader::observer::MetricsObserver;
+/// use clog_reader::GlogReader;
+///
+/// let metrics = Arc::new(MetricsObserver::new());
+/// let mut reader = GlogReader::builder()
+///     .observer(metrics.clone())
+///     .open("path/to/logfile.glog")?;
+/// let logs = reader.logs().count();
+/// println!("{} 条日志, {:?}", logs, metrics.stats());
+/// # Ok::<(), clog_reader::GlogError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct MetricsObserver {
+    /// 已开始读取的文件数
+    files_started: AtomicU64,
+    /// 已结束读取的文件数
+    files_finished: AtomicU64,
+    /// 读出的条目数
+    entries: AtomicU64,
+    /// 读出的条目占用的字节数
+    bytes: AtomicU64,
+    /// 恢复次数
+    recoveries: AtomicU64,
+    /// 恢复时跳过的字节数
+    bytes_skipped: AtomicU64,
+    /// protobuf 解码失败的条目数
+    decode_failures: AtomicU64,
+    /// 按容错方式解码的条目数
+    lossy_decodes: AtomicU64,
+    /// 脱敏时替换的次数
+    redactions: AtomicU64,
+}
+
+impl MetricsObserver {
+    /// 创建计数全部为 0 的观察者
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已开始读取的文件数
+    pub fn files_started(&self) -> u64 {
+        self.files_started.load(Ordering::Relaxed)
+    }
+
+    /// 已结束读取的文件数
+    pub fn files_finished(&self) -> u64 {
+        self.files_finished.load(Ordering::Relaxed)
+    }
+
+    /// 所有读取器的累计统计
+    ///
+    /// 各项分别读取，读取进行中得到的快照之间可能相差一两个条目
+    pub fn stats(&self) -> ReadStats {
+        ReadStats {
+            entries: self.entries.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            recoveries: self.recoveries.load(Ordering::Relaxed),
+            bytes_skipped: self.bytes_skipped.load(Ordering::Relaxed),
+            decode_failures: self.decode_failures.load(Ordering::Relaxed),
+            lossy_decodes: self.lossy_decodes.load(Ordering::Relaxed),
+            redactions: self.redactions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Observer for MetricsObserver {
+    fn on_file_start(&self, _path: Option<&str>) {
+        self.files_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_entry(&self, _offset: u64, len: u64) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(len, Ordering::Relaxed);
+    }
+
+    fn on_recover(&self, event: RecoverEvent) {
+        self.recoveries.fetch_add(1, Ordering::Relaxed);
+        self.bytes_skipped
+            .fetch_add(event.bytes_skipped, Ordering::Relaxed);
+    }
+
+    fn on_decode_failure(&self, _offset: u64, _len: u64) {
+        self.decode_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_lossy_decode(&self, _offset: u64, _len: u64) {
+        self.lossy_decodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_redact(&self, _offset: u64, count: u64) {
+        self.redactions.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn on_file_end(&self, _path: Option<&str>, _stats: &ReadStats) {
+        self.files_finished.fetch_add(1, Ordering::Relaxed);
+    }
+}
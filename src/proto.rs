@@ -3,12 +3,21 @@
 //! 本模块定义了与 Protobuf Log.proto 对应的 Rust 结构体。
 //! 由于 proto 文件比较简单，我们手动实现而不使用 prost-build。
 
+use std::collections::BTreeMap;
+
 use prost::Message;
+use regex::Regex;
+use serde::{Serialize, Serializer};
+
+use crate::error::{self, GlogError};
+use crate::format::{DefaultFormatter, LogFormatter};
+use crate::reader::read_varint;
 
 /// 日志级别枚举
 ///
 /// 对应 proto 文件中的 Log.Level 枚举
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[repr(i32)]
 pub enum Level {
     /// 信息级别
@@ -55,8 +64,22 @@ impl Level {
             Level::Error => "Error",
         }
     }
+
+    /// 日志级别的严重程度，越大越严重（Verbose < Debug < Info < Warn < Error）
+    ///
+    /// 枚举值沿用 proto 定义（Info 为 0），不能直接用于比较严重程度
+    pub fn severity(&self) -> u8 {
+        match self {
+            Level::Verbose => 0,
+            Level::Debug => 1,
+            Level::Info => 2,
+            Level::Warn => 3,
+            Level::Error => 4,
+        }
+    }
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Level {
     fn default() -> Self {
         Level::Info
@@ -78,7 +101,9 @@ pub struct Log {
     #[prost(int32, tag = "1")]
     pub log_type: i32,
     
-    /// 时间戳（毫秒级 Unix 时间戳的字符串表示）
+    /// 时间戳（Unix 时间戳的字符串表示）
+    ///
+    /// 正常为毫秒，部分设备会写入秒或微秒，换算后的毫秒值见 [`Log::timestamp_millis`]
     #[prost(string, tag = "2")]
     pub timestamp: String,
     
@@ -120,29 +145,55 @@ impl Log {
         Log::decode(buf)
     }
 
+    /// 容忍无效 UTF-8 的解码
+    ///
+    /// Android 端可能把原始字节写入字符串字段，按 `string` 解码会整条失败。
+    /// 先按 [`decode_from`](Self::decode_from) 解码，失败后改用 [`LogBytes`] 重新解码，
+    /// 无效的 UTF-8 序列替换为 U+FFFD
+    ///
+    /// # Arguments
+    /// * `buf` - 包含 protobuf 编码数据的字节切片
+    ///
+    /// # Returns
+    /// 返回日志以及是否经过容错转换
+    ///
+    /// # Errors
+    /// 数据不是有效的 protobuf 时返回第一次解码的错误
+    pub fn decode_lossy(buf: &[u8]) -> Result<(Self, bool), prost::DecodeError> {
+        match Log::decode(buf) {
+            Ok(log) => Ok((log, false)),
+            Err(e) => match LogBytes::decode(buf) {
+                Ok(bytes) => Ok((bytes.to_log_lossy(), true)),
+                Err(_) => Err(e),
+            },
+        }
+    }
+
     /// 获取日志级别枚举
     pub fn level(&self) -> Level {
         Level::from_i32(self.log_level)
     }
 
+    /// 解析毫秒时间戳
+    ///
+    /// 按位数推断写入端使用的单位并换算为毫秒，规则见 [`parse_timestamp_millis`]。
+    /// 原始字符串仍保存在 [`timestamp`](Self::timestamp) 字段中
+    ///
+    /// # Returns
+    /// 时间戳不是整数时返回 `None`
+    pub fn timestamp_millis(&self) -> Option<i64> {
+        parse_timestamp_millis(&self.timestamp)
+    }
+
     /// 获取格式化的时间戳
     ///
     /// # Returns
     /// 返回格式化的日期时间字符串 (yyyy-MM-dd HH:mm:ss.SSS)
     pub fn formatted_timestamp(&self) -> String {
-        use chrono::{DateTime, TimeZone, Local};
-        
-        if let Ok(ts) = self.timestamp.parse::<i64>() {
-            // 将毫秒时间戳转换为 DateTime
-            if let Some(dt) = DateTime::from_timestamp_millis(ts) {
-                // 转换为本地时间
-                let local_dt = Local.from_utc_datetime(&dt.naive_utc());
-                return local_dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string();
-            }
-        }
-        
         // 如果解析失败，返回原始时间戳
-        self.timestamp.clone()
+        self.timestamp_millis()
+            .and_then(format_local_millis)
+            .unwrap_or_else(|| self.timestamp.clone())
     }
 
     /// 格式化为日志字符串
@@ -152,23 +203,570 @@ impl Log {
     /// # Returns
     /// 返回格式化的日志字符串
     pub fn format(&self) -> String {
-        format!(
-            "{} [{}] [{}] {{{}:{}}} {}",
-            self.formatted_timestamp(),
-            self.level().as_str(),
-            self.tag,
-            self.pid,
-            self.tid,
-            self.msg
+        self.format_with(&DefaultFormatter::new())
+    }
+
+    /// 使用给定的格式化器格式化为日志字符串
+    ///
+    /// 例如 `log.format_with(&DefaultFormatter::with_types(types))` 在默认格式中加入类型名称
+    ///
+    /// # Arguments
+    /// * `formatter` - 日志格式化器
+    pub fn format_with(&self, formatter: &dyn LogFormatter) -> String {
+        let mut line = String::new();
+        formatter
+            .format(self, &mut line)
+            .expect("写入 String 不会失败");
+        line
+    }
+
+    /// 合并、排序时使用的全序键
+    ///
+    /// 由换算为毫秒的时间戳、日志类型和读出顺序组成。[`Log`] 由 prost 生成，
+    /// 无法携带读取器注入的序号，因此由调用方传入。时间戳无法解析的日志排在最前面
+    ///
+    /// # Arguments
+    /// * `seq` - 该日志在读取器中的读出顺序
+    ///
+    /// # Returns
+    /// 返回 `(毫秒时间戳, 日志类型, 读出顺序)`
+    pub fn sort_key(&self, seq: u64) -> (i64, i32, u64) {
+        (
+            self.timestamp_millis().unwrap_or(i64::MIN),
+            self.log_type,
+            seq,
         )
     }
+
+    /// 除时间戳以外的字段是否都相同
+    ///
+    /// 用于去重：同一条日志在不同时间重复输出时视为相同内容
+    pub fn content_eq(&self, other: &Log) -> bool {
+        self.log_type == other.log_type
+            && self.log_level == other.log_level
+            && self.pid == other.pid
+            && self.tid == other.tid
+            && self.tag == other.tag
+            && self.msg == other.msg
+    }
+
+    /// 序列化为单行 JSON
+    ///
+    /// 字段名固定为 `type`、`timestamp`、`level`、`pid`、`tid`、`tag`、`msg`，
+    /// 时间戳保留原始字符串，级别使用 [`Level::as_str`] 的名称
+    ///
+    /// # Returns
+    /// 返回不含换行的 JSON 字符串
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("日志字段都可以序列化为 JSON")
+    }
+
+    /// 序列化为 [`serde_json::Value`]
+    ///
+    /// 字段与 [`to_json`](Self::to_json) 相同，便于调用方追加或改写字段
+    pub fn to_json_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("日志字段都可以序列化为 JSON")
+    }
+}
+
+/// 按时间戳排序的日志
+///
+/// 为避免给 prost 生成的 [`Log`] 加上出人意料的 `Ord`，排序通过这个包装进行。
+/// `seq` 由读取方按读出顺序注入（例如 `logs().enumerate()` 的序号），
+/// 时间戳和类型都相同时保持读出顺序。比较只看 [`Log::sort_key`]，
+/// 因此 `==` 表示排序位置相同而不是内容相同
+#[derive(Debug, Clone)]
+pub struct ByTimestamp {
+    /// 日志
+    pub log: Log,
+    /// 读出顺序
+    pub seq: u64,
+}
+
+impl ByTimestamp {
+    /// 包装一条日志
+    ///
+    /// # Arguments
+    /// * `log` - 日志
+    /// * `seq` - 该日志在读取器中的读出顺序
+    pub fn new(log: Log, seq: u64) -> Self {
+        Self { log, seq }
+    }
+
+    /// 排序键，参见 [`Log::sort_key`]
+    pub fn key(&self) -> (i64, i32, u64) {
+        self.log.sort_key(self.seq)
+    }
+}
+
+impl PartialEq for ByTimestamp {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for ByTimestamp {}
+
+impl PartialOrd for ByTimestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByTimestamp {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// 日志序列化时使用的字段视图
+#[derive(Serialize)]
+struct LogJson<'a> {
+    #[serde(rename = "type")]
+    log_type: i32,
+    timestamp: &'a str,
+    level: &'static str,
+    pid: i32,
+    tid: &'a str,
+    tag: &'a str,
+    msg: &'a str,
+}
+
+impl Serialize for Log {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LogJson {
+            log_type: self.log_type,
+            timestamp: &self.timestamp,
+            level: self.level().as_str(),
+            pid: self.pid,
+            tid: &self.tid,
+            tag: &self.tag,
+            msg: &self.msg,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// 按位数推断单位，把 Unix 时间戳字符串换算为毫秒
+///
+/// 部分设备的写入端存在问题，会写入秒或微秒时间戳，直接按毫秒解释会显示为 1970 年
+/// 或五万多年之后。按绝对值的位数推断单位：
+///
+/// - 9~10 位：秒
+/// - 11~13 位：毫秒
+/// - 14~16 位：微秒
+/// - 17~19 位：纳秒
+///
+/// 不超过 8 位的值无法区分单位，按毫秒原样返回
+///
+/// # Arguments
+/// * `raw` - 时间戳字符串，允许首尾空白
+///
+/// # Returns
+/// 不是整数时返回 `None`
+pub fn parse_timestamp_millis(raw: &str) -> Option<i64> {
+    let ts: i64 = raw.trim().parse().ok()?;
+    let millis = match ts.unsigned_abs().checked_ilog10().map_or(1, |log| log + 1) {
+        9..=10 => ts.saturating_mul(1_000),
+        14..=16 => ts / 1_000,
+        17.. => ts / 1_000_000,
+        _ => ts,
+    };
+    Some(millis)
+}
+
+/// 把毫秒时间戳格式化为本地时间 `yyyy-MM-dd HH:mm:ss.SSS`
+///
+/// # Returns
+/// 超出可表示的范围时返回 `None`
+pub fn format_local_millis(millis: i64) -> Option<String> {
+    use chrono::{DateTime, Local, TimeZone};
+
+    let dt = DateTime::from_timestamp_millis(millis)?;
+    let local_dt = Local.from_utc_datetime(&dt.naive_utc());
+    Some(local_dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+}
+
+/// 内置的日志类型名称
+///
+/// 写入端未设置类型时字段为 0，其余类型由各项目自行约定，通过
+/// [`LogTypeRegistry::extend_from_toml`] 加载
+const BUILTIN_LOG_TYPES: &[(i32, &str)] = &[(0, "default")];
+
+/// 日志类型名称表
+///
+/// 把 [`Log::log_type`] 的整数映射为可读的名称，供格式化和统计输出使用。
+/// 项目自定义的映射可以从 TOML 加载，每行一个 `类型 = "名称"`：
+///
+/// ```toml
+/// 1 = "network"
+/// 2 = "player"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogTypeRegistry {
+    /// 类型到名称的映射
+    names: BTreeMap<i32, String>,
+}
+
+impl LogTypeRegistry {
+    /// 创建空的名称表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建包含内置名称的名称表
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for (log_type, name) in BUILTIN_LOG_TYPES {
+            registry.register(*log_type, *name);
+        }
+        registry
+    }
+
+    /// 注册一个类型名称，已存在时覆盖
+    ///
+    /// # Arguments
+    /// * `log_type` - 日志类型
+    /// * `name` - 类型名称
+    pub fn register(&mut self, log_type: i32, name: impl Into<String>) {
+        self.names.insert(log_type, name.into());
+    }
+
+    /// 查找类型名称
+    ///
+    /// # Returns
+    /// 未注册的类型返回 `None`
+    pub fn name(&self, log_type: i32) -> Option<&str> {
+        self.names.get(&log_type).map(String::as_str)
+    }
+
+    /// 从 TOML 加载类型名称，覆盖同名类型
+    ///
+    /// # Arguments
+    /// * `source` - TOML 文本，顶层每个键为类型整数、值为名称字符串
+    ///
+    /// # Errors
+    /// TOML 语法错误、键不是整数或值不是字符串时返回 [`GlogError::TypeMapError`]，
+    /// 此时名称表保持不变
+    pub fn extend_from_toml(&mut self, source: &str) -> error::Result<()> {
+        let table: BTreeMap<String, String> =
+            toml::from_str(source).map_err(|e| GlogError::TypeMapError(e.to_string()))?;
+        let entries = table
+            .into_iter()
+            .map(|(key, name)| match key.trim().parse::<i32>() {
+                Ok(log_type) => Ok((log_type, name)),
+                Err(_) => Err(GlogError::TypeMapError(format!("类型不是整数: {}", key))),
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        self.names.extend(entries);
+        Ok(())
+    }
+
+    /// 遍历所有已注册的类型及名称，按类型升序
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &str)> {
+        self.names.iter().map(|(t, name)| (*t, name.as_str()))
+    }
+}
+
+/// 字符串字段按字节解码的日志消息
+///
+/// 与 [`Log`] 共享字段编号，时间戳、线程 ID、标签和内容保留原始字节，
+/// 用于解码含有无效 UTF-8 的条目，参见 [`Log::decode_lossy`]
+#[derive(Clone, PartialEq, Message)]
+pub struct LogBytes {
+    /// 日志类型
+    #[prost(int32, tag = "1")]
+    pub log_type: i32,
+    /// 时间戳
+    #[prost(bytes = "vec", tag = "2")]
+    pub timestamp: Vec<u8>,
+    /// 日志级别
+    #[prost(int32, tag = "3")]
+    pub log_level: i32,
+    /// 进程 ID
+    #[prost(int32, tag = "4")]
+    pub pid: i32,
+    /// 线程 ID
+    #[prost(bytes = "vec", tag = "5")]
+    pub tid: Vec<u8>,
+    /// 日志标签
+    #[prost(bytes = "vec", tag = "6")]
+    pub tag: Vec<u8>,
+    /// 日志消息内容
+    #[prost(bytes = "vec", tag = "7")]
+    pub msg: Vec<u8>,
+}
+
+impl LogBytes {
+    /// 转换为 [`Log`]，无效的 UTF-8 序列替换为 U+FFFD
+    pub fn to_log_lossy(&self) -> Log {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        Log {
+            log_type: self.log_type,
+            timestamp: lossy(&self.timestamp),
+            log_level: self.log_level,
+            pid: self.pid,
+            tid: lossy(&self.tid),
+            tag: lossy(&self.tag),
+            msg: lossy(&self.msg),
+        }
+    }
+}
+
+/// 仅包含日志类型字段的精简消息
+///
+/// 与 [`Log`] 共享字段编号，解码时其余字段会被直接跳过而不分配字符串，
+/// 适用于只需要按日志类型过滤或计数的场景
+#[derive(Clone, PartialEq, Message)]
+pub struct LogTypeOnly {
+    /// 日志类型
+    #[prost(int32, tag = "1")]
+    pub log_type: i32,
 }
 
 // Default 已由 Message derive 宏自动实现
 
+/// 只解析第一个字段，取出日志类型
+///
+/// 写入端按字段编号顺序编码，日志类型（字段 1，varint）非 0 时总是位于开头。
+/// 与 [`LogTypeOnly`] 不同，这里不检查其余字段，解析成本与条目长度无关，
+/// 用于在完整解码之前排除不满足类型条件的日志
+///
+/// # Arguments
+/// * `buf` - protobuf 编码的日志数据
+///
+/// # Returns
+/// 开头是字段 1 时返回日志类型；字段 1 缺失（类型为 0）、不在开头或无法解析时返回 `None`，
+/// 此时需要完整解码才能确定类型
+pub fn peek_log_type(buf: &[u8]) -> Option<i32> {
+    // 字段 1、wire type 0（varint）的键为 0x08
+    let rest = buf.strip_prefix(&[0x08])?;
+    // int32 与 prost 相同，按 64 位 varint 读取后截断
+    read_varint(&mut &rest[..]).ok().map(|value| value as i32)
+}
+
+/// 日志标签匹配模式
+///
+/// 包含 `*`（任意多个字符）或 `?`（单个字符）时按通配符匹配，否则精确匹配
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// 精确匹配
+    Exact(String),
+    /// 通配符匹配
+    Wildcard(String),
+}
+
+impl Pattern {
+    /// 解析匹配模式
+    ///
+    /// # Arguments
+    /// * `pattern` - 标签或包含 `*`、`?` 的通配符
+    pub fn new(pattern: &str) -> Self {
+        if pattern.contains(['*', '?']) {
+            Pattern::Wildcard(pattern.to_string())
+        } else {
+            Pattern::Exact(pattern.to_string())
+        }
+    }
+
+    /// 文本是否匹配该模式
+    pub fn matches(&self, text: &str) -> bool {
+        match self {
+            Pattern::Exact(exact) => exact == text,
+            Pattern::Wildcard(pattern) => wildcard_matches(pattern, text),
+        }
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Pattern::new(pattern)
+    }
+}
+
+/// 通配符匹配，`*` 匹配任意多个字符，`?` 匹配单个字符
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置及其匹配到的文本位置，失配时回溯到这里多吞一个字符
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 日志过滤条件
+///
+/// 各类条件之间为“与”关系，同一类中的多个值为“或”关系；未设置的条件不过滤。
+/// 排除条件优先于包含条件
+///
+/// ```
+/// use clog_reader::proto::{Level, LogFilter};
+///
+/// let filter = LogFilter::new()
+///     .types([1, 2])
+///     .min_level(Level::Warn)
+///     .tag("Net*")
+///     .msg_regex("time(out)?")
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    /// 日志类型（为空表示不过滤）
+    pub types: Vec<i32>,
+    /// 排除的日志类型
+    pub exclude_types: Vec<i32>,
+    /// 最低日志级别，按 Verbose < Debug < Info < Warn < Error 的严重程度比较
+    pub min_level: Option<Level>,
+    /// 日志标签（为空表示不过滤）
+    pub tags: Vec<Pattern>,
+    /// 排除的日志标签
+    pub exclude_tags: Vec<Pattern>,
+    /// 时间范围（毫秒时间戳，包含两端），时间戳无法解析的日志不匹配任何时间范围
+    pub time_range: (Option<i64>, Option<i64>),
+    /// 进程 ID
+    pub pid: Option<i32>,
+    /// 线程 ID
+    pub tid: Option<String>,
+    /// 日志内容的正则表达式
+    pub msg_regex: Option<Regex>,
+}
+
+impl LogFilter {
+    /// 创建不过滤任何日志的过滤条件
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只保留指定类型的日志
+    pub fn types(mut self, types: impl IntoIterator<Item = i32>) -> Self {
+        self.types = types.into_iter().collect();
+        self
+    }
+
+    /// 排除指定类型的日志
+    pub fn exclude_types(mut self, types: impl IntoIterator<Item = i32>) -> Self {
+        self.exclude_types = types.into_iter().collect();
+        self
+    }
+
+    /// 只保留不低于指定级别的日志
+    pub fn min_level(mut self, level: Level) -> Self {
+        self.min_level = Some(level);
+        self
+    }
+
+    /// 增加一个保留的标签模式，参见 [`Pattern::new`]
+    pub fn tag(mut self, pattern: impl Into<Pattern>) -> Self {
+        self.tags.push(pattern.into());
+        self
+    }
+
+    /// 增加一个排除的标签模式，参见 [`Pattern::new`]
+    pub fn exclude_tag(mut self, pattern: impl Into<Pattern>) -> Self {
+        self.exclude_tags.push(pattern.into());
+        self
+    }
+
+    /// 只保留时间戳在范围内的日志
+    ///
+    /// # Arguments
+    /// * `from` - 起始毫秒时间戳（包含），`None` 表示不限
+    /// * `to` - 结束毫秒时间戳（包含），`None` 表示不限
+    pub fn time_range(mut self, from: Option<i64>, to: Option<i64>) -> Self {
+        self.time_range = (from, to);
+        self
+    }
+
+    /// 只保留指定进程的日志
+    pub fn pid(mut self, pid: i32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    /// 只保留指定线程的日志
+    pub fn tid(mut self, tid: impl Into<String>) -> Self {
+        self.tid = Some(tid.into());
+        self
+    }
+
+    /// 只保留内容匹配正则表达式的日志
+    ///
+    /// # Errors
+    /// 正则表达式无效时返回错误
+    pub fn msg_regex(mut self, regex: &str) -> Result<Self, regex::Error> {
+        self.msg_regex = Some(Regex::new(regex)?);
+        Ok(self)
+    }
+
+    /// 日志是否满足全部条件
+    pub fn matches(&self, log: &Log) -> bool {
+        self.matches_type(log.log_type)
+            && self.min_level.is_none_or(|min| log.level().severity() >= min.severity())
+            && (self.tags.is_empty() || self.tags.iter().any(|p| p.matches(&log.tag)))
+            && !self.exclude_tags.iter().any(|p| p.matches(&log.tag))
+            && self.matches_time(&log.timestamp)
+            && self.pid.is_none_or(|pid| log.pid == pid)
+            && self.tid.as_ref().is_none_or(|tid| &log.tid == tid)
+            && self.msg_regex.as_ref().is_none_or(|re| re.is_match(&log.msg))
+    }
+
+    /// 日志类型是否满足类型条件（包含及排除）
+    ///
+    /// 配合 [`LogTypeOnly`] 使用，可以在不解码完整日志的情况下按类型过滤
+    pub fn matches_type(&self, log_type: i32) -> bool {
+        (self.types.is_empty() || self.types.contains(&log_type))
+            && !self.exclude_types.contains(&log_type)
+    }
+
+    /// 是否只有类型条件，此时 [`matches_type`](Self::matches_type) 与 [`matches`](Self::matches) 等价
+    pub fn is_type_only(&self) -> bool {
+        self.min_level.is_none()
+            && self.tags.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.time_range == (None, None)
+            && self.pid.is_none()
+            && self.tid.is_none()
+            && self.msg_regex.is_none()
+    }
+
+    /// 是否不过滤任何日志
+    pub fn is_empty(&self) -> bool {
+        self.is_type_only() && self.types.is_empty() && self.exclude_types.is_empty()
+    }
+
+    /// 时间戳是否在时间范围内
+    fn matches_time(&self, timestamp: &str) -> bool {
+        self.time_range == (None, None) || self.matches_millis(parse_timestamp_millis(timestamp))
+    }
+
+    /// 毫秒时间戳是否在时间范围内，无法解析（`None`）的时间戳只在没有时间范围时满足
+    pub fn matches_millis(&self, millis: Option<i64>) -> bool {
+        let (from, to) = self.time_range;
+        if from.is_none() && to.is_none() {
+            return true;
+        }
+        millis.is_some_and(|ts| from.is_none_or(|from| ts >= from) && to.is_none_or(|to| ts <= to))
+    }
+}
+
 impl std::fmt::Display for Log {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.format())
+        DefaultFormatter::new().format(self, f)
     }
 }
 
@@ -218,4 +816,298 @@ mod tests {
         assert!(formatted.contains("{1234:5678}"));
         assert!(formatted.contains("Test message"));
     }
+
+    #[test]
+    fn test_log_type_only_decode() {
+        let log = Log {
+            log_type: 7,
+            tag: "TestTag".to_string(),
+            msg: "Test message".to_string(),
+            ..Log::default()
+        };
+        let buf = log.encode_to_vec();
+
+        let type_only = LogTypeOnly::decode(buf.as_slice()).unwrap();
+        assert_eq!(type_only.log_type, 7);
+    }
+
+    #[test]
+    fn test_decode_lossy() {
+        let valid = sample_log(0, "Tag", "ok").encode_to_vec();
+        assert_eq!(Log::decode_lossy(&valid).unwrap(), (sample_log(0, "Tag", "ok"), false));
+
+        let invalid = LogBytes {
+            log_type: 1,
+            tag: b"Tag".to_vec(),
+            msg: b"bad \xFF byte".to_vec(),
+            ..LogBytes::default()
+        }
+        .encode_to_vec();
+        assert!(Log::decode_from(&invalid).is_err());
+        let (log, lossy) = Log::decode_lossy(&invalid).unwrap();
+        assert!(lossy);
+        assert_eq!(log.log_type, 1);
+        assert_eq!(log.tag, "Tag");
+        assert_eq!(log.msg, "bad \u{FFFD} byte");
+
+        // 不是有效的 protobuf 时仍然失败
+        assert!(Log::decode_lossy(&[0xFF, 0xFF, 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_peek_log_type() {
+        for log_type in [7, 300, -1] {
+            let log = Log {
+                log_type,
+                ..sample_log(0, "Tag", "msg")
+            };
+            assert_eq!(peek_log_type(&log.encode_to_vec()), Some(log_type));
+        }
+
+        // 类型为 0 时字段 1 不编码，需要完整解码
+        let log = Log {
+            log_type: 0,
+            ..sample_log(0, "Tag", "msg")
+        };
+        assert_eq!(peek_log_type(&log.encode_to_vec()), None);
+
+        // 字段 1 出现在其他字段之后、varint 截断时同样返回 None
+        let mut reordered = vec![0x32, 0x03];
+        reordered.extend_from_slice(b"Tag");
+        reordered.extend_from_slice(&[0x08, 0x05]);
+        assert_eq!(peek_log_type(&reordered), None);
+        assert_eq!(Log::decode_from(&reordered).unwrap().log_type, 5);
+        assert_eq!(peek_log_type(&[0x08, 0x80]), None);
+        assert_eq!(peek_log_type(&[]), None);
+    }
+
+    fn sample_log(level: i32, tag: &str, msg: &str) -> Log {
+        Log {
+            log_type: 1,
+            timestamp: "1700000000000".to_string(),
+            log_level: level,
+            pid: 1234,
+            tid: "main".to_string(),
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pattern_wildcard() {
+        assert!(Pattern::new("Net*").matches("NetClient"));
+        assert!(Pattern::new("*Client").matches("NetClient"));
+        assert!(Pattern::new("N?t*t").matches("NetClient"));
+        assert!(!Pattern::new("Net*t").matches("Network"));
+        assert!(!Pattern::new("Net").matches("NetClient"));
+        assert_eq!(Pattern::new("Net"), Pattern::Exact("Net".to_string()));
+    }
+
+    #[test]
+    fn test_log_filter_combines_conditions_with_and() {
+        let log = sample_log(3, "NetClient", "request timeout");
+
+        assert!(LogFilter::new().matches(&log));
+        let filter = LogFilter::new()
+            .types([1])
+            .min_level(Level::Info)
+            .tag("Net*")
+            .time_range(Some(1_700_000_000_000), None)
+            .pid(1234)
+            .tid("main")
+            .msg_regex("time(out)?")
+            .unwrap();
+        assert!(filter.matches(&log));
+
+        // 任一类条件不满足即不匹配
+        assert!(!filter.clone().types([2]).matches(&log));
+        assert!(!filter.clone().min_level(Level::Error).matches(&log));
+        assert!(!filter.clone().exclude_tag("NetClient").matches(&log));
+        assert!(!filter.clone().time_range(None, Some(0)).matches(&log));
+        assert!(!filter.clone().msg_regex("^ok$").unwrap().matches(&log));
+
+        // 级别按严重程度比较：Verbose 低于 Info，尽管其枚举值更大
+        assert!(!LogFilter::new().min_level(Level::Info).matches(&sample_log(2, "T", "")));
+    }
+
+    #[test]
+    fn test_to_json_uses_canonical_field_names() {
+        let log = Log {
+            log_type: 2,
+            timestamp: "1700000000000".to_string(),
+            log_level: 4,
+            pid: 7,
+            tid: "io".to_string(),
+            tag: "Db".to_string(),
+            msg: "say \"hi\"".to_string(),
+        };
+
+        assert_eq!(
+            log.to_json(),
+            r#"{"type":2,"timestamp":"1700000000000","level":"Error","pid":7,"tid":"io","tag":"Db","msg":"say \"hi\""}"#
+        );
+        assert_eq!(log.to_json_value()["level"], "Error");
+        assert_eq!(log.to_json_value()["type"], 2);
+    }
+
+    #[test]
+    fn test_parse_timestamp_millis_normalizes_units() {
+        let cases = [
+            // 2023-11-14 22:13:20.123 UTC 的各种单位
+            ("1700000000", 1_700_000_000_000),
+            ("1700000000123", 1_700_000_000_123),
+            ("1700000000123456", 1_700_000_000_123),
+            ("1700000000123456789", 1_700_000_000_123),
+            // 1973 年的秒级时间戳只有 9 位
+            ("100000000", 100_000_000_000),
+            (" 1700000000123\n", 1_700_000_000_123),
+            // 位数太少时无法判断单位，原样返回
+            ("100", 100),
+            ("0", 0),
+        ];
+        for (raw, millis) in cases {
+            assert_eq!(parse_timestamp_millis(raw), Some(millis), "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp_millis_rejects_garbage() {
+        for raw in ["", "abc", "1700000000.5", "17e11", "99999999999999999999"] {
+            assert_eq!(parse_timestamp_millis(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn test_seconds_timestamp_is_formatted_and_filtered_as_millis() {
+        let seconds = Log {
+            timestamp: "1700000000".to_string(),
+            ..Log::new()
+        };
+        let millis = Log {
+            timestamp: "1700000000000".to_string(),
+            ..Log::new()
+        };
+
+        assert_eq!(seconds.timestamp, "1700000000");
+        assert_eq!(seconds.formatted_timestamp(), millis.formatted_timestamp());
+        let filter = LogFilter::new().time_range(Some(1_700_000_000_000), None);
+        assert!(filter.matches(&seconds));
+        assert!(!filter.matches(&Log {
+            timestamp: "1699999999".to_string(),
+            ..Log::new()
+        }));
+    }
+
+    #[test]
+    fn test_sort_key_and_content_eq() {
+        let log = |timestamp: &str, log_type, msg: &str| Log {
+            timestamp: timestamp.to_string(),
+            log_type,
+            msg: msg.to_string(),
+            ..Log::new()
+        };
+        let mut logs = [
+            ByTimestamp::new(log("1700000001000", 0, "later"), 0),
+            ByTimestamp::new(log("1700000000", 1, "type 1"), 1),
+            ByTimestamp::new(log("1700000000000", 0, "first"), 2),
+            ByTimestamp::new(log("1700000000000", 0, "second"), 3),
+            ByTimestamp::new(log("bad", 5, "unparseable"), 4),
+        ];
+        logs.sort();
+        let msgs: Vec<_> = logs.iter().map(|l| l.log.msg.as_str()).collect();
+        assert_eq!(msgs, ["unparseable", "first", "second", "type 1", "later"]);
+        assert_eq!(logs[1].key(), (1_700_000_000_000, 0, 2));
+
+        let a = log("1700000000000", 0, "x");
+        assert!(a.content_eq(&log("1700000009999", 0, "x")));
+        assert!(!a.content_eq(&log("1700000000000", 1, "x")));
+        let other_thread = Log {
+            tid: "io".to_string(),
+            ..a.clone()
+        };
+        assert!(!a.content_eq(&other_thread));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod props {
+        use super::*;
+        use crate::glog::GlogReader;
+        use crate::reader::CompressMode;
+        use crate::writer::v3::GlogWriterV3;
+        use proptest::prelude::*;
+        use std::io::Cursor;
+
+        /// 按 (时间戳, 类型) 有序的条目及一个随机排列
+        fn fixture() -> impl Strategy<Value = (Vec<(i64, i32)>, Vec<usize>)> {
+            proptest::collection::vec((0i64..3, 0i32..3), 1..40).prop_flat_map(|steps| {
+                let mut ts = 1_700_000_000_000;
+                let mut entries: Vec<_> = steps
+                    .into_iter()
+                    .map(|(delta, log_type)| {
+                        ts += delta;
+                        (ts, log_type)
+                    })
+                    .collect();
+                entries.sort();
+                let order = Just((0..entries.len()).collect::<Vec<_>>()).prop_shuffle();
+                (Just(entries), order)
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn sorting_shuffled_logs_restores_reader_order((entries, order) in fixture()) {
+                let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+                for (i, (ts, log_type)) in entries.iter().enumerate() {
+                    writer
+                        .write_log(&Log {
+                            timestamp: ts.to_string(),
+                            log_type: *log_type,
+                            msg: i.to_string(),
+                            ..Log::new()
+                        })
+                        .unwrap();
+                }
+                let content = writer.into_inner().unwrap();
+                let size = content.len() as u64;
+                let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+                let read: Vec<ByTimestamp> = reader
+                    .logs()
+                    .enumerate()
+                    .map(|(seq, log)| ByTimestamp::new(log.unwrap(), seq as u64))
+                    .collect();
+
+                let mut shuffled: Vec<_> = order.iter().map(|&i| read[i].clone()).collect();
+                shuffled.sort();
+                let restored: Vec<_> = shuffled.iter().map(|l| l.seq).collect();
+                prop_assert_eq!(restored, (0..read.len() as u64).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_log_type_registry() {
+        let mut types = LogTypeRegistry::builtin();
+        assert_eq!(types.name(0), Some("default"));
+        assert_eq!(types.name(1), None);
+
+        types.register(1, "network");
+        types
+            .extend_from_toml("2 = \"player\"\n1 = \"net\"\n")
+            .unwrap();
+        assert_eq!(types.name(1), Some("net"));
+        assert_eq!(types.name(2), Some("player"));
+        assert_eq!(
+            types.iter().collect::<Vec<_>>(),
+            [(0, "default"), (1, "net"), (2, "player")]
+        );
+
+        for bad in ["x = \"a\"", "1 = 2", "1 = "] {
+            let err = types.clone().extend_from_toml(bad).unwrap_err();
+            assert!(matches!(err, GlogError::TypeMapError(_)), "{bad}: {err}");
+        }
+        let before = types.clone();
+        assert!(types.extend_from_toml("3 = \"ok\"\nx = \"bad\"").is_err());
+        assert_eq!(types, before);
+    }
 }
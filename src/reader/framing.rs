@@ -0,0 +1,151 @@
+//! # 条目分帧
+//!
+//! 只根据内存中已有的字节计算 V3 / V4 文件头和条目的长度，不做任何 IO。
+//! 阻塞读取器用这里的长度判断剩余数据是否足够一条日志；异步读取器（`async` 功能）
+//! 用它判断已经收到的数据中有多少是完整的条目，可以交给阻塞读取器解码。
+
+use super::{MAGIC_NUMBER, PROTO_NAME_MAX_LENGTH, SYNC_MARKER};
+use crate::version::{GLOG_CIPHER_VERSION, GLOG_RECOVERY_VERSION};
+
+/// 分帧结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frame {
+    /// 数据不足，需要更多字节才能判断
+    Incomplete,
+    /// 开头是一个完整的文件头或条目，包含其总字节数
+    Complete(usize),
+    /// 开头不是可以识别的文件头或完好的条目
+    Corrupt,
+}
+
+/// V3 条目的总字节数：日志长度(2) + 日志数据 + 同步标记(8)
+///
+/// # Arguments
+/// * `log_length` - 存储的日志数据长度
+pub fn v3_entry_len(log_length: usize) -> usize {
+    2 + log_length + 8
+}
+
+/// V4 条目的总字节数：模式(1) + (加密时 IV(16) + 压缩公钥(33)) + 日志长度(2) + 日志数据 + 同步标记(8)
+///
+/// # Arguments
+/// * `log_length` - 存储的日志数据长度
+/// * `encrypted` - 条目是否加密
+pub fn v4_entry_len(log_length: usize, encrypted: bool) -> usize {
+    1 + if encrypted { 16 + 33 } else { 0 } + 2 + log_length + 8
+}
+
+/// 判断数据开头的 V3 / V4 文件头是否完整
+///
+/// 魔数错误、V1 / V2 文件头（没有同步标记）和 `.glogmmap` 包装都返回 [`Frame::Corrupt`]，
+/// 这些格式需要按完整文件读取
+///
+/// # Arguments
+/// * `data` - 从文件起始位置开始的数据
+pub fn header_frame(data: &[u8]) -> Frame {
+    if data.len() < MAGIC_NUMBER.len() + 1 {
+        return Frame::Incomplete;
+    }
+    if data[..MAGIC_NUMBER.len()] != MAGIC_NUMBER {
+        return Frame::Corrupt;
+    }
+    // 魔数(4) + 版本(1)，V3 还有一个模式字节
+    let name_offset = match data[MAGIC_NUMBER.len()] {
+        GLOG_RECOVERY_VERSION => 4 + 1 + 1,
+        GLOG_CIPHER_VERSION => 4 + 1,
+        _ => return Frame::Corrupt,
+    };
+    let Some(name_len) = read_u16_at(data, name_offset) else {
+        return Frame::Incomplete;
+    };
+    if name_len > PROTO_NAME_MAX_LENGTH {
+        return Frame::Corrupt;
+    }
+    marker_frame(data, name_offset + 2 + name_len + SYNC_MARKER.len())
+}
+
+/// 判断数据开头的条目是否完整
+///
+/// 长度为 0、V4 模式字节无法识别或末尾的同步标记不匹配时返回 [`Frame::Corrupt`]；
+/// 长度上限等读取器配置不在这里检查
+///
+/// # Arguments
+/// * `version` - 文件格式版本（`GLOG_RECOVERY_VERSION` 或 `GLOG_CIPHER_VERSION`）
+/// * `data` - 从条目边界开始的数据
+pub fn entry_frame(version: u8, data: &[u8]) -> Frame {
+    let (length_offset, encrypted) = if version == GLOG_CIPHER_VERSION {
+        let Some(&mode) = data.first() else {
+            return Frame::Incomplete;
+        };
+        // 高 4 位为压缩模式（1..=3），低 4 位为加密模式（1..=2）
+        if !(1..=3).contains(&(mode >> 4)) || !(1..=2).contains(&(mode & 0x0F)) {
+            return Frame::Corrupt;
+        }
+        let encrypted = mode & 0x0F == 2;
+        (if encrypted { 1 + 16 + 33 } else { 1 }, encrypted)
+    } else {
+        (0, false)
+    };
+    let Some(log_length) = read_u16_at(data, length_offset) else {
+        return Frame::Incomplete;
+    };
+    if log_length == 0 {
+        return Frame::Corrupt;
+    }
+    let total = if version == GLOG_CIPHER_VERSION {
+        v4_entry_len(log_length, encrypted)
+    } else {
+        v3_entry_len(log_length)
+    };
+    marker_frame(data, total)
+}
+
+/// 检查以 `end` 结尾的同步标记
+fn marker_frame(data: &[u8], end: usize) -> Frame {
+    match data.get(end - SYNC_MARKER.len()..end) {
+        None => Frame::Incomplete,
+        Some(marker) if marker == SYNC_MARKER => Frame::Complete(end),
+        Some(_) => Frame::Corrupt,
+    }
+}
+
+/// 读取指定位置的小端 u16，数据不足时返回 `None`
+fn read_u16_at(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::{CompressMode, EncryptMode};
+    use crate::writer::v4::GlogWriterV4;
+
+    #[test]
+    fn test_frames_v4_header_and_entries_byte_by_byte() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log").unwrap();
+        writer
+            .write_entry(b"hello", CompressMode::None, EncryptMode::None)
+            .unwrap();
+        let data = writer.into_inner().unwrap();
+        let header_len = 4 + 1 + 2 + 3 + 8;
+
+        for end in 0..header_len {
+            assert_eq!(header_frame(&data[..end]), Frame::Incomplete);
+        }
+        assert_eq!(header_frame(&data), Frame::Complete(header_len));
+
+        let entry = &data[header_len..];
+        for end in 0..entry.len() {
+            assert_eq!(entry_frame(GLOG_CIPHER_VERSION, &entry[..end]), Frame::Incomplete);
+        }
+        assert_eq!(entry_frame(GLOG_CIPHER_VERSION, entry), Frame::Complete(entry.len()));
+        assert_eq!(entry.len(), v4_entry_len(5, false));
+
+        let mut bad = entry.to_vec();
+        *bad.last_mut().unwrap() ^= 0xFF;
+        assert_eq!(entry_frame(GLOG_CIPHER_VERSION, &bad), Frame::Corrupt);
+        bad[0] = 0x41;
+        assert_eq!(entry_frame(GLOG_CIPHER_VERSION, &bad), Frame::Corrupt);
+    }
+}
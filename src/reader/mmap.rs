@@ -0,0 +1,283 @@
+//! # mmap 缓存文件读取器
+//!
+//! Android 客户端先把日志写入 mmap 映射的缓存文件（`.glogmmap`），再批量刷入 `.glog` 文件。
+//! 缓存文件在普通的 Glog 数据之前有一个小的文件头，记录写入游标；
+//! 游标之后是预分配空间的零填充，不属于日志数据。
+//!
+//! ## 文件格式 (所有长度使用小端序存储)
+//!
+//! ```text
+//! +-----------------------------------------------------------------+
+//! |                      mmap magic number (4)                      |
+//! +-----------------------------------------------------------------+
+//! |                        write cursor (4)                         |
+//! +-----------------------------------------------------------------+
+//! |       glog file header + entries (write cursor)       ...
+//! +-----------------------------------------------------------------+
+//! |       zero padding ...                                          |
+//! +-----------------------------------------------------------------+
+//! ```
+//!
+//! 写入游标是 mmap 文件头之后有效数据的字节数。游标超出文件范围时视为损坏，
+//! 改为读取全部数据并去掉末尾的零填充。
+//!
+//! 跟随读取正在写入的缓存文件时，调用方重新读取写入游标，
+//! 通过 [`FileReader::set_size`] 把可读范围扩大到新的游标处。
+
+use std::io::{self, Cursor, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::{read_u32_le, EntryInfo, FileReader, GlogMetadata};
+use crate::error::{ReadResult, Result};
+
+/// mmap 缓存文件的魔数
+pub const MMAP_MAGIC_NUMBER: [u8; 4] = [0x1B, 0xAD, 0xCA, 0xCE];
+
+/// mmap 文件头长度（魔数 + 写入游标）
+pub const MMAP_HEADER_LENGTH: u64 = 8;
+
+/// mmap 文件头之后的有效数据
+pub struct MmapContent {
+    /// 有效数据（完整的 Glog 文件头和日志条目）
+    pub input: Box<dyn Read + Send>,
+    /// 有效数据长度
+    pub len: u64,
+    /// 写入游标损坏、有效数据长度由去掉零填充得到时为 `true`
+    pub trimmed: bool,
+    /// `input` 可以读取的字节数，写入游标前移后随之增大
+    pub cursor: Arc<AtomicU64>,
+}
+
+/// 只读取写入游标之前数据的输入流
+///
+/// 与 [`Read::take`] 不同，上限由 [`MmapContent::cursor`] 共享，
+/// 跟随读取时游标前移后可以继续读取新写入的数据
+struct CursorInput<R> {
+    /// 输入流
+    input: R,
+    /// 已经读取的字节数
+    read: u64,
+    /// 可以读取的字节数
+    cursor: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CursorInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self
+            .cursor
+            .load(Ordering::Acquire)
+            .saturating_sub(self.read);
+        let len = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let n = self.input.read(&mut buf[..len])?;
+        self.read += n as u64;
+        Ok(n)
+    }
+}
+
+/// 读取写入游标，得到 mmap 文件头之后的有效数据
+///
+/// 调用方已经读取并验证了魔数，输入流位于写入游标处。
+/// 游标有效时只截取游标之前的数据；游标损坏时读取全部数据并去掉末尾的零填充
+///
+/// # Arguments
+/// * `input` - 输入流
+/// * `size` - 文件总大小
+///
+/// # Returns
+/// 返回有效数据
+pub fn read_mmap_content<R: Read + Send + 'static>(mut input: R, size: u64) -> Result<MmapContent> {
+    let cursor = read_u32_le(&mut input)? as u64;
+    let capacity = size.saturating_sub(MMAP_HEADER_LENGTH);
+
+    if cursor <= capacity {
+        let shared = Arc::new(AtomicU64::new(cursor));
+        return Ok(MmapContent {
+            input: Box::new(CursorInput {
+                input,
+                read: 0,
+                cursor: Arc::clone(&shared),
+            }),
+            len: cursor,
+            trimmed: false,
+            cursor: shared,
+        });
+    }
+
+    let mut content = Vec::new();
+    input.take(capacity).read_to_end(&mut content)?;
+    let end = content.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    content.truncate(end);
+    let len = content.len() as u64;
+    Ok(MmapContent {
+        len,
+        input: Box::new(Cursor::new(content)),
+        trimmed: true,
+        cursor: Arc::new(AtomicU64::new(len)),
+    })
+}
+
+/// mmap 缓存文件读取器
+///
+/// 包装读取有效数据的版本特定读取器，把读取位置换算为缓存文件中的偏移
+pub struct FileReaderMmap {
+    /// 读取有效数据的版本特定读取器
+    inner: Box<dyn FileReader + Send>,
+    /// 缓存文件总大小（包括零填充）
+    file_size: u64,
+    /// 有效数据长度是否由去掉零填充得到
+    trimmed: bool,
+    /// 有效数据的输入流可以读取的字节数，参见 [`MmapContent::cursor`]
+    cursor: Arc<AtomicU64>,
+}
+
+impl FileReaderMmap {
+    /// 创建 mmap 缓存文件读取器
+    ///
+    /// # Arguments
+    /// * `inner` - 读取 [`MmapContent::input`] 的版本特定读取器
+    /// * `file_size` - 缓存文件总大小
+    /// * `trimmed` - 参见 [`MmapContent::trimmed`]
+    /// * `cursor` - 参见 [`MmapContent::cursor`]
+    pub fn new(
+        inner: Box<dyn FileReader + Send>,
+        file_size: u64,
+        trimmed: bool,
+        cursor: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            inner,
+            file_size,
+            trimmed,
+            cursor,
+        }
+    }
+}
+
+impl FileReader for FileReaderMmap {
+    fn read_remain_header(&mut self) -> Result<()> {
+        // 文件头已由内部读取器解析
+        Ok(())
+    }
+
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.inner.read_to_vec(out)
+    }
+
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
+        self.inner.read(out_buf)
+    }
+
+    fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<ReadResult> {
+        self.inner.skip_entry(scratch)
+    }
+
+    fn read_raw(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.inner.read_raw(out)
+    }
+
+    fn last_entry(&self) -> Option<EntryInfo> {
+        self.inner.last_entry().map(|entry| EntryInfo {
+            offset: MMAP_HEADER_LENGTH + entry.offset,
+            ..entry
+        })
+    }
+
+    fn position(&self) -> u64 {
+        MMAP_HEADER_LENGTH + self.inner.position()
+    }
+
+    fn space_left(&self) -> u64 {
+        self.inner.space_left()
+    }
+
+    /// 有效数据的末尾偏移，零填充不计入
+    fn size(&self) -> u64 {
+        MMAP_HEADER_LENGTH + self.inner.size()
+    }
+
+    /// 更新有效数据的末尾偏移（mmap 文件头长度 + 写入游标），零填充不计入
+    ///
+    /// 写入游标损坏时有效数据已经全部读入内存，不再变化
+    fn set_size(&mut self, size: u64) {
+        if self.trimmed {
+            return;
+        }
+        let len = size.saturating_sub(MMAP_HEADER_LENGTH);
+        self.cursor.store(len, Ordering::Release);
+        self.inner.set_size(len);
+    }
+
+    fn metadata(&self) -> GlogMetadata {
+        GlogMetadata {
+            file_size: self.file_size,
+            ..self.inner.metadata()
+        }
+    }
+
+    fn recover(&mut self) -> Result<bool> {
+        self.inner.recover()
+    }
+
+    fn resume_at(&mut self, position: u64) -> Result<()> {
+        // 续读时输入流跳过了文件头之后的部分数据，去掉零填充得到的长度需要补上这部分
+        if self.trimmed {
+            let skipped = position.saturating_sub(self.position());
+            self.inner.set_size(self.inner.size() + skipped);
+        }
+        self.inner.resume_at(position.saturating_sub(MMAP_HEADER_LENGTH))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造 mmap 文件（魔数之后的部分）：写入游标 + 数据 + 零填充，返回内容和文件大小
+    fn mmap_body(cursor: u32, data: &[u8], padding: usize) -> (Vec<u8>, u64) {
+        let mut out = cursor.to_le_bytes().to_vec();
+        out.extend_from_slice(data);
+        out.resize(out.len() + padding, 0);
+        let size = MMAP_MAGIC_NUMBER.len() as u64 + out.len() as u64;
+        (out, size)
+    }
+
+    fn read_all(content: MmapContent) -> Vec<u8> {
+        let mut out = Vec::new();
+        content.input.take(content.len).read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_read_mmap_content_stops_at_cursor() {
+        let (body, size) = mmap_body(3, b"abc\0def", 32);
+        let content = read_mmap_content(Cursor::new(body), size).unwrap();
+        assert!(!content.trimmed);
+        assert_eq!(read_all(content), b"abc");
+    }
+
+    #[test]
+    fn test_read_mmap_content_continues_after_cursor_advances() {
+        let (body, size) = mmap_body(3, b"abcdef", 32);
+        let mut content = read_mmap_content(Cursor::new(body), size).unwrap();
+        let mut out = Vec::new();
+        content.input.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"abc");
+
+        content.cursor.store(6, Ordering::Release);
+        content.input.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"abcdef");
+    }
+
+    #[test]
+    fn test_read_mmap_content_trims_zero_tail_when_cursor_corrupt() {
+        let (body, size) = mmap_body(u32::MAX, b"abc\0def", 32);
+        let content = read_mmap_content(Cursor::new(body), size).unwrap();
+        assert!(content.trimmed);
+        assert_eq!(content.len, 7);
+        assert_eq!(read_all(content), b"abc\0def");
+    }
+}
@@ -12,16 +12,28 @@
 //!
 //! 这意味着多个日志块实际上是作为一个连续的 deflate 流压缩的，
 //! 因此 Rust 实现也需要使用有状态的流式解压器。
+//!
+//! 较早的 iOS / 桌面端写入器在压缩流开头带有 2 字节的 zlib 头部，
+//! [`StatefulInflater`] 在第一条压缩日志上识别头部并据此解压整个文件，
+//! 识别结果见 [`GlogMetadata::deflate_wrapper`]。
 
+pub mod framing;
+pub mod key;
+pub mod mmap;
+pub mod source;
+pub mod v1;
+pub mod v2;
 pub mod v3;
 pub mod v4;
 
-use std::io::{self, Read, Cursor, Write};
-use flate2::read::ZlibDecoder;
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use flate2::Decompress;
 use flate2::FlushDecompress;
-// use flate2::Status;
+use flate2::Status;
 use crate::error::{GlogError, Result, ReadResult};
+use key::KeyCacheStats;
 // use log::{info, debug};
 
 /// 单条日志内容的最大长度 (16KB)
@@ -37,17 +49,54 @@ pub const SYNC_MARKER: [u8; 8] = [0xB7, 0xDB, 0xE7, 0xDB, 0x80, 0xAD, 0xD9, 0x57
 
 /// 压缩模式枚举
 /// 定义了日志数据支持的压缩方式
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum CompressMode {
     /// 无压缩
     None,
     /// Zlib 压缩
     Zlib,
+    /// Zstd 压缩（V4 条目模式字节高 4 位为 3，V3 文件头高 4 位为 2），需要启用 `zstd` 功能才能解压
+    Zstd,
+}
+
+/// zlib 压缩流的封装格式
+///
+/// 由 [`StatefulInflater`] 根据第一条压缩日志的开头识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeflateWrapper {
+    /// raw deflate，无头部（Android 写入器）
+    Raw,
+    /// 带 2 字节 zlib 头部（较早的 iOS / 桌面端写入器）
+    Zlib,
+}
+
+impl DeflateWrapper {
+    /// 根据压缩流的开头识别封装格式
+    ///
+    /// zlib 头部的第一个字节为 `0x78`（deflate、32KB 窗口），
+    /// 两个字节按大端组成的整数是 31 的倍数（FCHECK），且没有预置字典（FDICT）
+    ///
+    /// # Arguments
+    /// * `stream` - 压缩流开头的数据
+    // `is_multiple_of` 在 Rust 1.87 才稳定，这里用取模以免提高最低 Rust 版本
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn detect(stream: &[u8]) -> Self {
+        let [0x78, flags, ..] = *stream else {
+            return DeflateWrapper::Raw;
+        };
+        if u16::from_be_bytes([0x78, flags]) % 31 == 0 && flags & 0x20 == 0 {
+            DeflateWrapper::Zlib
+        } else {
+            DeflateWrapper::Raw
+        }
+    }
 }
 
 /// 加密模式枚举
 /// 定义了日志数据支持的加密方式
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 pub enum EncryptMode {
     /// 无加密
     None,
@@ -55,6 +104,57 @@ pub enum EncryptMode {
     Aes,
 }
 
+/// Glog 文件元数据
+///
+/// 由读取器在解析文件头时填充。V4 的压缩和加密模式记录在每条日志中，
+/// 因此取自第一条日志，读取第一条日志之前压缩模式为 `None`、加密模式未知
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlogMetadata {
+    /// 文件格式版本
+    pub version: u8,
+    /// 协议名称
+    pub proto_name: String,
+    /// 压缩模式
+    pub compress_mode: CompressMode,
+    /// 加密模式（未知时为 `None`）
+    pub encrypt_mode: Option<EncryptMode>,
+    /// 文件总大小
+    pub file_size: u64,
+    /// 因未提供密钥而跳过的加密日志条数（仅 V4，参见 `GlogReaderBuilder::skip_encrypted`）
+    pub skipped_encrypted: u64,
+    /// 文件在最后一条日志中间结束时，该日志已写入的字节数（仅 V3/V4）
+    ///
+    /// 应用被杀死时采集的文件通常以不完整的日志结尾，这部分数据按文件结束处理而不是报错
+    pub truncated_tail_bytes: u64,
+    /// ECDH 共享密钥缓存的命中统计（仅 V4 且提供了密钥时，参见 `KeyProvider::cache_stats`）
+    pub key_cache: Option<KeyCacheStats>,
+    /// zlib 压缩流的封装格式，读取第一条 zlib 压缩的日志之前为 `None`
+    pub deflate_wrapper: Option<DeflateWrapper>,
+}
+
+/// 单条日志的存储信息
+///
+/// 由读取器在成功读取一条日志后填充，用于排查格式或加密问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryInfo {
+    /// 条目在文件中的起始偏移
+    pub offset: u64,
+    /// 条目在文件中占用的总字节数（包括模式、IV、公钥、长度字段和同步标记）
+    pub entry_len: u64,
+    /// 存储的日志数据长度（压缩、加密后）
+    pub stored_len: usize,
+    /// 是否压缩
+    pub compressed: bool,
+    /// 压缩模式，V4 文件中每条日志可以不同
+    pub compress_mode: CompressMode,
+    /// 是否加密
+    pub encrypted: bool,
+    /// 加密条目使用的客户端公钥（压缩格式）
+    pub client_pubkey: Option<[u8; 33]>,
+    /// 日志内容长度（解密、解压后）
+    pub len: usize,
+}
+
 /// 文件读取器特征
 ///
 /// 定义了所有 Glog 文件读取器必须实现的接口
@@ -65,6 +165,17 @@ pub trait FileReader {
     /// 成功返回 `Ok(())`，失败返回相应的错误
     fn read_remain_header(&mut self) -> Result<()>;
 
+    /// 读取下一条日志到可增长的缓冲区
+    ///
+    /// 解压后的日志可能超过 [`SINGLE_LOG_CONTENT_MAX_LENGTH`]，缓冲区会按需扩容
+    ///
+    /// # Arguments
+    /// * `out` - 输出缓冲区，读取前清空，成功时恰好包含整条日志
+    ///
+    /// # Returns
+    /// 返回 `ReadResult` 枚举，表示读取结果
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult>;
+
     /// 读取下一条日志
     ///
     /// # Arguments
@@ -72,13 +183,182 @@ pub trait FileReader {
     ///
     /// # Returns
     /// 返回 `ReadResult` 枚举，表示读取结果
-    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult>;
+    ///
+    /// # Errors
+    /// 日志超过 `out_buf` 的长度时返回 `GlogError::Truncated`，该条日志已被消费，
+    /// 需要完整内容时应使用 [`read_to_vec`](Self::read_to_vec)
+    ///
+    /// 默认实现每次读取都分配临时缓冲区，有复用缓冲区的读取器应覆盖此方法
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
+        let mut entry = Vec::new();
+        let result = self.read_to_vec(&mut entry);
+        copy_entry(result, &entry, out_buf)
+    }
+
+    /// 最近一次成功读取的日志的存储信息
+    ///
+    /// 默认返回 `None`，适用于不记录条目信息的读取器
+    fn last_entry(&self) -> Option<EntryInfo> {
+        None
+    }
+
+    /// 跳过下一条日志，只验证条目结构（长度字段和同步标记）
+    ///
+    /// 未压缩的条目直接丢弃数据，不解密也不复制；压缩条目共用解压器状态，
+    /// 仍然解压到 `scratch` 中以便后续条目能够正确解压。
+    /// 返回值与 [`read_to_vec`](Self::read_to_vec) 相同，成功时 [`last_entry`](Self::last_entry) 同样可用。
+    /// 默认实现直接调用 `read_to_vec`
+    ///
+    /// # Arguments
+    /// * `scratch` - 压缩条目的解压缓冲区，内容没有意义
+    fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<ReadResult> {
+        self.read_to_vec(scratch)
+    }
+
+    /// 读取下一条日志的存储数据：加密条目解密，但不解压
+    ///
+    /// 压缩条目共用一个压缩流，原样输出的数据只有按顺序写入另一个文件才能解压，
+    /// 因此不能与 [`read_to_vec`](Self::read_to_vec) 在同一个读取器上混用。
+    /// 成功时 [`last_entry`](Self::last_entry) 同样可用，其中的 `len` 为存储数据的长度。
+    /// 默认返回 `UnsupportedVersion`，目前只有 V4 读取器支持
+    ///
+    /// # Arguments
+    /// * `out` - 输出缓冲区，读取前清空，成功时恰好包含条目的存储数据
+    fn read_raw(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        let _ = out;
+        Err(GlogError::UnsupportedVersion(self.metadata().version))
+    }
 
     /// 获取当前读取位置
     fn position(&self) -> u64;
 
     /// 获取剩余可读取的字节数
     fn space_left(&self) -> u64;
+
+    /// 获取数据总大小
+    fn size(&self) -> u64;
+
+    /// 更新数据总大小
+    ///
+    /// 用于跟随读取仍在写入的文件：文件增长后更新大小，
+    /// 读取器即可继续读取新追加的日志，而不是把打开时的长度当作最终长度
+    fn set_size(&mut self, size: u64);
+
+    /// 获取文件元数据
+    fn metadata(&self) -> GlogMetadata;
+
+    /// 从损坏处恢复读取
+    ///
+    /// 在 `read` 返回 `ReadResult::NeedRecover` 之后调用，跳到下一个可信的条目边界，
+    /// 使后续 `read` 能够继续读取。默认实现不做任何处理，适用于没有同步标记的格式
+    ///
+    /// # Returns
+    /// 找到下一个条目边界时返回 `true`；扫描到数据末尾仍未找到时返回 `false`
+    fn recover(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// 从指定的条目边界继续读取
+    ///
+    /// 调用方已经读完文件头，并把输入流定位到 `position`（某个同步标记之后）。
+    /// 读取器更新读取位置并重置解压器。默认实现返回错误，适用于没有同步标记、
+    /// 无法确认条目边界的格式
+    ///
+    /// # Arguments
+    /// * `position` - 输入流当前对应的文件偏移
+    ///
+    /// # Errors
+    /// 格式不支持从中间位置继续读取时返回 `GlogError::UnsupportedVersion`
+    fn resume_at(&mut self, position: u64) -> Result<()> {
+        let _ = position;
+        Err(GlogError::UnsupportedVersion(self.metadata().version))
+    }
+}
+
+/// 把 [`FileReader::read_to_vec`] 读出的日志复制到调用方的缓冲区，用于实现 [`FileReader::read`]
+///
+/// # Arguments
+/// * `result` - `read_to_vec` 的返回值
+/// * `entry` - `read_to_vec` 读出的日志
+/// * `out_buf` - 调用方的输出缓冲区
+///
+/// # Errors
+/// 日志超过 `out_buf` 的长度时返回 `GlogError::Truncated`
+pub(crate) fn copy_entry(
+    result: Result<ReadResult>,
+    entry: &[u8],
+    out_buf: &mut [u8],
+) -> Result<ReadResult> {
+    match result? {
+        ReadResult::Success(len) => {
+            if len > out_buf.len() {
+                return Err(GlogError::Truncated {
+                    required: len,
+                    available: out_buf.len(),
+                });
+            }
+            out_buf[..len].copy_from_slice(&entry[..len]);
+            Ok(ReadResult::Success(len))
+        }
+        result => Ok(result),
+    }
+}
+
+/// 池中最多保留的解压上下文数，超出的在读取器结束时直接释放
+const INFLATER_POOL_LIMIT: usize = 64;
+
+/// 可在多个读取器之间复用的 raw deflate 解压上下文池
+///
+/// 每个解压上下文带有约 40KB 的窗口和状态，压缩包和并行路径会依次打开成百上千个文件。
+/// 通过 [`StatefulInflater::from_pool`] 创建的解压器第一次解压时从池中取出上下文，
+/// 销毁时重置并放回池中，下一个文件直接复用。克隆得到的句柄共享同一个池
+#[derive(Clone, Default)]
+pub struct InflaterPool {
+    /// 空闲的解压上下文
+    contexts: Arc<Mutex<Vec<Decompress>>>,
+}
+
+impl InflaterPool {
+    /// 创建空的解压上下文池
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 池中空闲的解压上下文数
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// 池中是否没有空闲的解压上下文
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 取出一个空闲的解压上下文，池为空时返回 `None`
+    fn take(&self) -> Option<Decompress> {
+        self.lock().pop()
+    }
+
+    /// 放回解压上下文，池已满时丢弃
+    fn put(&self, decompressor: Decompress) {
+        let mut contexts = self.lock();
+        if contexts.len() < INFLATER_POOL_LIMIT {
+            contexts.push(decompressor);
+        }
+    }
+
+    /// 锁定池；持有锁的线程 panic 不影响池中的上下文，它们在放回前都已重置
+    fn lock(&self) -> MutexGuard<'_, Vec<Decompress>> {
+        self.contexts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for InflaterPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InflaterPool")
+            .field("len", &self.len())
+            .finish()
+    }
 }
 
 /// 有状态的 Raw Deflate 解压器
@@ -88,14 +368,22 @@ pub trait FileReader {
 /// - 在多次调用之间保持内部状态（字典等）
 /// - 支持 SYNC_FLUSH 模式
 ///
+/// 第一次解压时按 [`DeflateWrapper::detect`] 识别压缩流是否带有 zlib 头部，
+/// 带有头部时改用 zlib 格式解压整个文件。底层解压上下文在第一次解压时才创建，
+/// 没有压缩条目的文件不会分配
+///
 /// # 设计说明
 ///
 /// Java 的 Glog 实现将多个日志块作为一个连续的 deflate 流压缩，
 /// 每次写入后使用 SYNC_FLUSH 确保数据可以立即读取，但 zlib 字典等状态是持续的。
 /// 这个结构体复制了这种行为。
 pub struct StatefulInflater {
-    /// flate2 的底层解压器
-    decompressor: Decompress,
+    /// flate2 的底层解压器，第一次解压之前为 `None`
+    decompressor: Option<Decompress>,
+    /// 识别出的封装格式，第一次解压之前为 `None`
+    wrapper: Option<DeflateWrapper>,
+    /// 解压上下文的来源池，销毁时把上下文放回
+    pool: Option<InflaterPool>,
     /// 累计输入字节数（用于调试）
     total_in: u64,
     /// 累计输出字节数（用于调试）
@@ -106,71 +394,142 @@ impl StatefulInflater {
     /// 创建新的有状态解压器
     ///
     /// 使用 raw deflate 格式（对应 Java 的 WrapperType.NONE）
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        // false 表示 raw deflate（无 zlib 头部）
-        // 这对应 Java 的 JZlib.WrapperType.NONE
         Self {
-            decompressor: Decompress::new(false),
+            decompressor: None,
+            wrapper: None,
+            pool: None,
             total_in: 0,
             total_out: 0,
         }
     }
 
+    /// 创建从池中取用解压上下文的有状态解压器
+    ///
+    /// 第一次解压时从池中取出上下文，池为空时新建；解压器销毁时上下文重置后放回池中
+    ///
+    /// # Arguments
+    /// * `pool` - 解压上下文池
+    pub fn from_pool(pool: &InflaterPool) -> Self {
+        let mut inflater = Self::new();
+        inflater.pool = Some(pool.clone());
+        inflater
+    }
+
+    /// 识别出的封装格式
+    ///
+    /// # Returns
+    /// 第一次解压之前返回 `None`
+    pub fn wrapper(&self) -> Option<DeflateWrapper> {
+        self.wrapper
+    }
+
     /// 解压数据块
     ///
-    /// 模拟 Java 的 `inflater.inflate(Z_SYNC_FLUSH)` 行为
+    /// 模拟 Java 的 `inflater.inflate(Z_SYNC_FLUSH)` 行为。
+    /// 压缩后不超过 16KB 的日志解压后可能远大于 16KB，因此输出缓冲区写满时会扩容并继续解压；
+    /// 解压器也可能因内部窗口写满而提前返回，此时继续送入剩余输入，
+    /// 直到输入全部消费且没有待输出的数据。未消费的输入不能丢弃，否则后续日志的压缩流都会错位
     ///
     /// # Arguments
     /// * `in_buf` - 输入的压缩数据
-    /// * `out_buf` - 输出缓冲区
+    /// * `out` - 输出缓冲区，解压后的数据追加到末尾
     ///
     /// # Returns
-    /// 成功返回解压后的数据长度
-    pub fn decompress(&mut self, in_buf: &[u8], out_buf: &mut [u8]) -> Result<usize> {
-        let before_in = self.decompressor.total_in();
-        let before_out = self.decompressor.total_out();
+    /// 成功返回 `(消费的输入字节数, 解压后的数据长度)`，消费的输入字节数总是等于 `in_buf` 的长度
+    ///
+    /// # Errors
+    /// 压缩数据无效，或解压器不再前进而输入仍未消费完时返回 `DecompressError`
+    pub fn decompress(&mut self, in_buf: &[u8], out: &mut Vec<u8>) -> Result<(usize, usize)> {
+        let first = self.wrapper.is_none();
+        let wrapper = *self
+            .wrapper
+            .get_or_insert_with(|| DeflateWrapper::detect(in_buf));
+        let pool = self.pool.as_ref();
+        let decompressor = self.decompressor.get_or_insert_with(|| {
+            // 池中的上下文放回前已按 raw deflate 重置
+            pool.and_then(InflaterPool::take)
+                .unwrap_or_else(|| Decompress::new(false))
+        });
+        if first && wrapper == DeflateWrapper::Zlib {
+            decompressor.reset(true);
+        }
+
+        let before_in = decompressor.total_in();
+        let before_out = decompressor.total_out();
+        let consumed = |d: &Decompress| (d.total_in() - before_in) as usize;
 
-        // 使用 FlushDecompress::Sync 对应 Z_SYNC_FLUSH
-        let _ = self.decompressor.decompress(
-            in_buf,
-            out_buf,
-            FlushDecompress::Sync
-        ).map_err(|e| GlogError::DecompressError(format!("decompress error: {}", e)))?;
+        loop {
+            if out.len() == out.capacity() {
+                out.reserve(SINGLE_LOG_CONTENT_MAX_LENGTH);
+            }
+            let last_in = consumed(decompressor);
+            let last_out = out.len();
 
-        let consumed = (self.decompressor.total_in() - before_in) as usize;
-        let produced = (self.decompressor.total_out() - before_out) as usize;
+            // 使用 FlushDecompress::Sync 对应 Z_SYNC_FLUSH
+            let status = decompressor.decompress_vec(
+                &in_buf[last_in..],
+                out,
+                FlushDecompress::Sync
+            ).map_err(|e| GlogError::DecompressError(format!("decompress error: {}", e)))?;
+
+            // 输入已全部消费且输出缓冲区未写满，说明解压器已经输出了全部数据
+            let all_in = consumed(decompressor) == in_buf.len();
+            if status == Status::StreamEnd || (all_in && out.len() < out.capacity()) {
+                break;
+            }
+            if consumed(decompressor) == last_in && out.len() == last_out {
+                if all_in {
+                    break;
+                }
+                return Err(GlogError::DecompressError(format!(
+                    "输入未完全消费: 提供 {} 字节, 消费 {} 字节",
+                    in_buf.len(),
+                    last_in
+                )));
+            }
+        }
+
+        let consumed = consumed(decompressor);
+        let produced = (decompressor.total_out() - before_out) as usize;
 
         self.total_in += consumed as u64;
         self.total_out += produced as u64;
 
         // debug!(
-        //     "解压: 输入 {} 字节 (已消费 {}), 输出 {} 字节, 状态: {:?}",
+        //     "解压: 输入 {} 字节 (已消费 {}), 输出 {} 字节",
         //     in_buf.len(),
         //     consumed,
-        //     produced,
-        //     status
+        //     produced
         // );
 
-        // 检查是否消费了所有输入
-        if consumed != in_buf.len() {
-            // debug!(
-            //     "警告: 输入未完全消费: 提供 {} 字节, 消费 {} 字节",
-            //     in_buf.len(),
-            //     consumed
-            // );
-        }
-
-        Ok(produced)
+        Ok((consumed, produced))
     }
 
-    /// 重置解压器状态
+    /// 重置为新建时的状态
     ///
-    /// 在某些情况下需要重置（例如文件损坏后的恢复）
-    #[allow(dead_code)]
+    /// 丢弃之前识别的封装格式，下一次解压重新识别，适用于从头解压另一个压缩流。
+    /// 已创建的解压上下文原地重置，不重新分配
     pub fn reset(&mut self) {
-        self.decompressor.reset(false);
-        println!("解压器已重置, 之前累计: 输入 {} 字节, 输出 {} 字节", self.total_in, self.total_out);
-        io::stdout().flush().unwrap();
+        if let Some(decompressor) = &mut self.decompressor {
+            decompressor.reset(false);
+        }
+        self.wrapper = None;
+        self.total_in = 0;
+        self.total_out = 0;
+    }
+
+    /// 恢复时重置解压状态，保留已分配的解压上下文
+    ///
+    /// 文件损坏后的恢复或从中间位置继续读取时使用。此时之前的字典已经失效，
+    /// 后续数据位于压缩流中间，没有 zlib 头部，因此总是按 raw deflate 解压；
+    /// 已识别的封装格式保留用于 [`GlogMetadata::deflate_wrapper`]，尚未识别时不再识别
+    pub fn reset_keep_buffers(&mut self) {
+        if let Some(decompressor) = &mut self.decompressor {
+            decompressor.reset(false);
+        }
+        self.wrapper.get_or_insert(DeflateWrapper::Raw);
         self.total_in = 0;
         self.total_out = 0;
     }
@@ -188,6 +547,113 @@ impl StatefulInflater {
     }
 }
 
+impl Drop for StatefulInflater {
+    fn drop(&mut self) {
+        if let (Some(pool), Some(mut decompressor)) = (&self.pool, self.decompressor.take()) {
+            decompressor.reset(false);
+            pool.put(decompressor);
+        }
+    }
+}
+
+/// 有状态的 Zstd 流式解压器
+///
+/// 与 [`StatefulInflater`] 相同，写入方把整个文件的 zstd 条目压缩为一个连续的流，
+/// 每条日志写入后执行 `ZSTD_e_flush`，因此解压上下文（窗口）在条目之间保持。
+/// 解压上下文在第一次解压时创建，不含 zstd 条目的文件不需要分配。
+/// 未启用 `zstd` 功能时解压总是返回 `DecompressError`
+#[derive(Default)]
+pub struct StatefulZstdDecoder {
+    /// zstd 的底层解压上下文
+    #[cfg(feature = "zstd")]
+    decoder: Option<zstd::stream::raw::Decoder<'static>>,
+}
+
+impl StatefulZstdDecoder {
+    /// 创建新的有状态解压器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解压一条日志
+    ///
+    /// # Arguments
+    /// * `in_buf` - 输入的压缩数据
+    /// * `out` - 输出缓冲区，解压后的数据追加到末尾
+    ///
+    /// # Returns
+    /// 成功返回解压后的数据长度
+    ///
+    /// # Errors
+    /// 压缩数据无效、解压器不再前进而输入仍未消费完，或未启用 `zstd` 功能时返回 `DecompressError`
+    #[cfg(feature = "zstd")]
+    pub fn decompress(&mut self, in_buf: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+        use zstd::stream::raw::{Decoder, InBuffer, Operation, OutBuffer};
+
+        let decoder = match &mut self.decoder {
+            Some(decoder) => decoder,
+            None => self.decoder.insert(
+                Decoder::new().map_err(|e| GlogError::DecompressError(e.to_string()))?,
+            ),
+        };
+        let start = out.len();
+        let mut input = InBuffer::around(in_buf);
+        loop {
+            if out.len() == out.capacity() {
+                out.reserve(SINGLE_LOG_CONTENT_MAX_LENGTH);
+            }
+            let last_in = input.pos();
+            let last_out = out.len();
+            decoder
+                .run(&mut input, &mut OutBuffer::around_pos(out, last_out))
+                .map_err(|e| GlogError::DecompressError(format!("zstd decompress error: {}", e)))?;
+
+            // 输入已全部消费且输出缓冲区未写满，说明解压器已经输出了全部数据
+            let all_in = input.pos() == in_buf.len();
+            if all_in && out.len() < out.capacity() {
+                break;
+            }
+            if input.pos() == last_in && out.len() == last_out {
+                if all_in {
+                    break;
+                }
+                return Err(GlogError::DecompressError(format!(
+                    "输入未完全消费: 提供 {} 字节, 消费 {} 字节",
+                    in_buf.len(),
+                    last_in
+                )));
+            }
+        }
+        Ok(out.len() - start)
+    }
+
+    /// 未启用 `zstd` 功能，无法解压
+    ///
+    /// # Errors
+    /// 总是返回 `DecompressError`
+    #[cfg(not(feature = "zstd"))]
+    pub fn decompress(&mut self, in_buf: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+        let _ = (in_buf, out);
+        Err(GlogError::DecompressError(
+            "未启用 zstd 功能，无法解压 zstd 日志".to_string(),
+        ))
+    }
+
+    /// 重置解压器状态
+    ///
+    /// 与 [`StatefulInflater::reset_keep_buffers`] 相同，在恢复或从中间位置继续读取时使用
+    pub fn reset(&mut self) {
+        #[cfg(feature = "zstd")]
+        {
+            use zstd::stream::raw::Operation;
+            // 重置失败时丢弃上下文，下次解压时重新创建
+            if self.decoder.as_mut().is_some_and(|decoder| decoder.reinit().is_err()) {
+                self.decoder = None;
+            }
+        }
+    }
+}
+
 /// 安全读取函数
 ///
 /// 从输入流中安全地读取指定数量的字节到缓冲区
@@ -221,6 +687,25 @@ pub fn read_safely<R: Read>(input: &mut R, expected: usize, filled: &mut [u8]) -
     Ok(total_read)
 }
 
+/// 从输入流中跳过指定数量的字节
+///
+/// # Arguments
+/// * `input` - 输入流
+/// * `expected` - 要跳过的字节数
+///
+/// # Errors
+/// 如果可用字节数少于期望值或读取失败，返回 `UnexpectedEof` 错误
+pub fn skip_safely<R: Read>(input: &mut R, expected: usize) -> Result<()> {
+    let skipped = io::copy(&mut input.take(expected as u64), &mut io::sink())?;
+    if skipped < expected as u64 {
+        return Err(GlogError::UnexpectedEof {
+            expected,
+            available: skipped as usize,
+        });
+    }
+    Ok(())
+}
+
 /// 读取小端序 16 位无符号整数
 ///
 /// # Arguments
@@ -234,84 +719,257 @@ pub fn read_u16_le<R: Read>(input: &mut R) -> Result<u16> {
     Ok(u16::from_le_bytes(buf))
 }
 
-/// 解压缩数据
+/// 读取小端序 32 位无符号整数
 ///
-/// 使用 zlib 算法解压缩数据
+/// # Arguments
+/// * `input` - 输入流
+///
+/// # Returns
+/// 返回读取的 u32 值
+pub fn read_u32_le<R: Read>(input: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    read_safely(input, 4, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// 读取小端序 64 位无符号整数
 ///
 /// # Arguments
-/// * `in_buf` - 输入的压缩数据
-/// * `out_buf` - 输出缓冲区，用于存储解压后的数据
+/// * `input` - 输入流
 ///
 /// # Returns
-/// 成功返回解压后的数据长度，失败返回错误
-pub fn decompress(in_buf: &[u8], out_buf: &mut [u8]) -> Result<usize> {
-    // 创建一个 ZlibDecoder 来解压数据
-    // 注意：Java 代码使用的是 raw deflate (无 zlib header)
-    // 所以我们使用 DeflateDecoder 而不是 ZlibDecoder
-    let cursor = Cursor::new(in_buf);
-    let mut decoder = ZlibDecoder::new(cursor);
-    
-    let mut total_read = 0;
-    loop {
-        match decoder.read(&mut out_buf[total_read..]) {
-            Ok(0) => break,
-            Ok(n) => {
-                total_read += n;
-                if total_read >= out_buf.len() {
-                    break;
-                }
-            }
-            Err(e) => {
-                return Err(GlogError::DecompressError(e.to_string()));
-            }
+/// 返回读取的 u64 值
+pub fn read_u64_le<R: Read>(input: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    read_safely(input, 8, &mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// varint 的最大字节数（64 位整数每字节 7 位）
+const VARINT_MAX_LENGTH: usize = 10;
+
+/// 读取 protobuf 格式的 varint（低位在前，每字节 7 位，最高位表示后面还有字节）
+///
+/// # Arguments
+/// * `input` - 输入流
+///
+/// # Returns
+/// 返回读取的 u64 值
+///
+/// # Errors
+/// 输入在 varint 结束之前耗尽时返回 `UnexpectedEof`（`expected` 为已读字节数加一），
+/// 超过 10 字节或超出 64 位时返回 `FileCorrupt`
+pub fn read_varint<R: Read>(input: &mut R) -> Result<u64> {
+    let mut value = 0u64;
+    for i in 0..VARINT_MAX_LENGTH {
+        let mut byte = [0u8; 1];
+        read_safely(input, 1, &mut byte).map_err(|e| match e {
+            GlogError::UnexpectedEof { .. } => GlogError::UnexpectedEof {
+                expected: i + 1,
+                available: i,
+            },
+            e => e,
+        })?;
+        let bits = (byte[0] & 0x7F) as u64;
+        // 第 10 个字节只能提供最高的 1 位
+        if i == VARINT_MAX_LENGTH - 1 && bits > 1 {
+            break;
+        }
+        value |= bits << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
         }
     }
-    
-    println!("解压缩完成，输入 {} 字节，输出 {} 字节", in_buf.len(), total_read);
-    io::stdout().flush().unwrap();
-    Ok(total_read)
+    Err(GlogError::FileCorrupt("varint 超出 64 位".to_string()))
 }
 
-/// 解压缩数据 (使用 raw deflate)
+/// 协议名称的最大长度 (1KB)
+pub const PROTO_NAME_MAX_LENGTH: usize = 1024;
+
+/// 读取文件头中的协议名称长度和协议名称
 ///
-/// 使用 raw deflate 算法解压缩数据（无 zlib/gzip 头部）
+/// 长度字段来自文件内容，分配缓冲区之前先检查上限和剩余数据，
+/// 损坏或恶意构造的文件头不会导致大块内存分配
 ///
 /// # Arguments
-/// * `in_buf` - 输入的压缩数据
-/// * `out_buf` - 输出缓冲区，用于存储解压后的数据
+/// * `input` - 位于协议名称长度字段的输入流
+/// * `offset` - 协议名称长度字段在文件中的偏移
+/// * `size` - 数据总大小
+/// * `trailer` - 协议名称之后文件头还需要的字节数（V3/V4 为同步标记的 8 字节）
 ///
 /// # Returns
-/// 成功返回解压后的数据长度，失败返回错误
-pub fn decompress_raw(in_buf: &[u8], out_buf: &mut [u8]) -> Result<usize> {
-    use flate2::read::DeflateDecoder;
-    
-    let cursor = Cursor::new(in_buf);
-    let mut decoder = DeflateDecoder::new(cursor);
-    
-    let mut total_read = 0;
-    loop {
-        match decoder.read(&mut out_buf[total_read..]) {
-            Ok(0) => break,
-            Ok(n) => {
-                total_read += n;
-                if total_read >= out_buf.len() {
-                    break;
+/// 返回协议名称及其在文件中的字节数
+///
+/// # Errors
+/// 长度超过 [`PROTO_NAME_MAX_LENGTH`] 或超出剩余数据时返回 `FileCorrupt`
+pub fn read_proto_name<R: Read>(
+    input: &mut R,
+    offset: u64,
+    size: u64,
+    trailer: usize,
+) -> Result<(String, usize)> {
+    let len = read_u16_le(input)? as usize;
+    if len > PROTO_NAME_MAX_LENGTH {
+        return Err(GlogError::FileCorrupt(format!(
+            "协议名称长度 {} 超过上限 {}，位置: {}",
+            len, PROTO_NAME_MAX_LENGTH, offset
+        )));
+    }
+    let available = size.saturating_sub(offset + 2);
+    if ((len + trailer) as u64) > available {
+        return Err(GlogError::FileCorrupt(format!(
+            "协议名称长度 {} 超出文件头剩余的 {} 字节，位置: {}",
+            len, available, offset
+        )));
+    }
+
+    let mut name = vec![0u8; len];
+    read_safely(input, len, &mut name)?;
+    Ok((String::from_utf8_lossy(&name).into_owned(), len))
+}
+
+/// 同步标记损坏时仍视为原位标记所允许的最大差异字节数
+const SYNC_MARKER_MAX_DAMAGED_BYTES: usize = 2;
+
+/// 判断读到的 8 字节是否为仅有个别字节损坏的同步标记
+///
+/// 这种情况下条目边界本身仍然可信，下一条日志就从当前位置开始
+///
+/// # Arguments
+/// * `bytes` - 本应是同步标记的 8 字节
+pub fn is_damaged_sync_marker(bytes: &[u8; 8]) -> bool {
+    let damaged = bytes
+        .iter()
+        .zip(SYNC_MARKER.iter())
+        .filter(|(a, b)| a != b)
+        .count();
+    damaged <= SYNC_MARKER_MAX_DAMAGED_BYTES
+}
+
+/// 向前扫描到下一个同步标记之后
+///
+/// 使用 8 字节的滚动窗口逐字节读取，找到同步标记时停在它之后，
+/// 即下一条日志条目的起始位置
+///
+/// # Arguments
+/// * `input` - 输入流
+/// * `window` - 已读取但尚未匹配的字节，作为滚动窗口的初始内容（最多取最后 8 字节）
+/// * `limit` - 最多读取的字节数
+///
+/// # Returns
+/// 返回 `(读取的字节数, 是否找到同步标记)`
+pub fn scan_for_sync_marker<R: Read>(
+    input: &mut R,
+    window: &[u8],
+    limit: u64,
+) -> Result<(u64, bool)> {
+    let mut rolling = [0u8; 8];
+    let mut filled = window.len().min(8);
+    rolling[8 - filled..].copy_from_slice(&window[window.len() - filled..]);
+
+    let mut consumed = 0u64;
+    let mut byte = [0u8; 1];
+    while consumed < limit {
+        read_safely(input, 1, &mut byte)?;
+        consumed += 1;
+        rolling.copy_within(1.., 0);
+        rolling[7] = byte[0];
+        filled = (filled + 1).min(8);
+        if filled == 8 && rolling == SYNC_MARKER {
+            return Ok((consumed, true));
+        }
+    }
+    Ok((consumed, false))
+}
+
+/// [`find_sync_markers`] 每次从输入流读取的字节数
+const SYNC_SCAN_CHUNK: usize = 64 * 1024;
+
+/// 在任意字节流中查找同步标记
+///
+/// 文件头损坏时读取器无法打开文件，但其后的条目通常完好，每个同步标记之后都是一条条目的开头。
+/// 按块读取输入，用 `memchr` 查找同步标记的第一个字节后再比较完整标记，
+/// 跨越块边界的标记同样能找到。读取出错时视为输入结束
+///
+/// # Arguments
+/// * `input` - 输入流，偏移从流的当前位置开始计算
+///
+/// # Returns
+/// 返回依次产出每个同步标记之后偏移的迭代器
+pub fn find_sync_markers<R: Read>(input: R) -> SyncMarkers<R> {
+    SyncMarkers {
+        input,
+        buf: Vec::with_capacity(SYNC_SCAN_CHUNK),
+        pos: 0,
+        base: 0,
+        eof: false,
+    }
+}
+
+/// 同步标记迭代器，由 [`find_sync_markers`] 创建
+pub struct SyncMarkers<R> {
+    /// 输入流
+    input: R,
+    /// 已读取、尚未扫描完的数据
+    buf: Vec<u8>,
+    /// `buf` 中下一个要扫描的位置
+    pos: usize,
+    /// `buf[0]` 在输入流中的偏移
+    base: u64,
+    /// 输入流是否已结束
+    eof: bool,
+}
+
+impl<R: Read> Iterator for SyncMarkers<R> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            match memchr::memchr(SYNC_MARKER[0], &self.buf[self.pos..]) {
+                Some(i) => {
+                    let at = self.pos + i;
+                    if at + SYNC_MARKER.len() <= self.buf.len() {
+                        if self.buf[at..at + SYNC_MARKER.len()] == SYNC_MARKER {
+                            self.pos = at + SYNC_MARKER.len();
+                            return Some(self.base + self.pos as u64);
+                        }
+                        self.pos = at + 1;
+                        continue;
+                    }
+                    // 末尾可能是不完整的同步标记，读取更多数据后再判断
+                    self.pos = at;
                 }
+                None => self.pos = self.buf.len(),
             }
-            Err(e) => {
-                return Err(GlogError::DecompressError(e.to_string()));
+            if self.eof {
+                return None;
+            }
+
+            // 丢弃已扫描的数据，再读取一块
+            self.buf.drain(..self.pos);
+            self.base += self.pos as u64;
+            self.pos = 0;
+            let len = self.buf.len();
+            self.buf.resize(len + SYNC_SCAN_CHUNK, 0);
+            match self.input.read(&mut self.buf[len..]) {
+                Ok(n) => {
+                    self.buf.truncate(len + n);
+                    self.eof = n == 0;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.buf.truncate(len),
+                Err(_) => {
+                    self.buf.truncate(len);
+                    self.eof = true;
+                }
             }
         }
     }
-    
-    println!("Raw 解压缩完成，输入 {} 字节，输出 {} 字节", in_buf.len(), total_read);
-    io::stdout().flush().unwrap();
-    Ok(total_read)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Cursor;
 
     #[test]
     fn test_magic_number() {
@@ -323,6 +981,190 @@ mod tests {
         assert_eq!(SYNC_MARKER.len(), 8);
     }
 
+    #[test]
+    fn test_find_sync_markers_across_chunk_boundaries() {
+        /// 每次最多读取 3 字节的输入流
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = buf.len().min(3).min(self.0.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        // 两个相邻的同步标记、一个第一个字节相同的假标记，以及末尾不完整的标记
+        let mut data = vec![0xB7, 0x00];
+        data.extend_from_slice(&SYNC_MARKER);
+        data.extend_from_slice(&SYNC_MARKER);
+        data.extend_from_slice(&[0xB7, 0xDB, 0x01]);
+        let third = data.len() + SYNC_MARKER.len();
+        data.extend_from_slice(&SYNC_MARKER);
+        data.extend_from_slice(&SYNC_MARKER[..5]);
+
+        let expected = vec![10, 18, third as u64];
+        assert_eq!(find_sync_markers(data.as_slice()).collect::<Vec<_>>(), expected);
+        assert_eq!(find_sync_markers(Trickle(&data)).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_inflate_across_window_boundary() {
+        use flate2::{Compress, Compression, FlushCompress};
+
+        // 伪随机数据几乎不可压缩，第二条日志的 SYNC_FLUSH 块跨越解压器 32KB 内部窗口的边界
+        let mut seed = 0x2545_F491u32;
+        let mut noise = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    seed as u8
+                })
+                .collect()
+        };
+        let entries = [noise(30 * 1024), noise(12 * 1024), noise(40 * 1024)];
+
+        let mut compressor = Compress::new(Compression::default(), false);
+        let mut inflater = StatefulInflater::new();
+        for entry in &entries {
+            let mut compressed = Vec::with_capacity(entry.len() * 2);
+            compressor
+                .compress_vec(entry, &mut compressed, FlushCompress::Sync)
+                .unwrap();
+
+            let mut out = Vec::new();
+            let (consumed, produced) = inflater.decompress(&compressed, &mut out).unwrap();
+            assert_eq!(consumed, compressed.len());
+            assert_eq!(produced, entry.len());
+            assert_eq!(&out, entry);
+        }
+    }
+
+    #[test]
+    fn test_detect_deflate_wrapper() {
+        for header in [[0x78, 0x01], [0x78, 0x9C], [0x78, 0xDA]] {
+            assert_eq!(DeflateWrapper::detect(&header), DeflateWrapper::Zlib);
+        }
+        // FCHECK 不正确、设置了 FDICT 或数据过短时按 raw deflate 处理
+        assert_eq!(DeflateWrapper::detect(&[0x78, 0x9D]), DeflateWrapper::Raw);
+        assert_eq!(DeflateWrapper::detect(&[0x78, 0xBB]), DeflateWrapper::Raw);
+        assert_eq!(DeflateWrapper::detect(&[0x78]), DeflateWrapper::Raw);
+        assert_eq!(DeflateWrapper::detect(&[0xCA, 0x48]), DeflateWrapper::Raw);
+    }
+
+    #[test]
+    fn test_inflater_follows_detected_wrapper() {
+        use flate2::{Compress, Compression, FlushCompress};
+
+        let entries: [&[u8]; 3] = [b"first entry", b"second entry", b"first entry again"];
+        for (zlib_header, expected) in [(true, DeflateWrapper::Zlib), (false, DeflateWrapper::Raw)]
+        {
+            let mut compressor = Compress::new(Compression::default(), zlib_header);
+            let mut inflater = StatefulInflater::new();
+            assert_eq!(inflater.wrapper(), None);
+            for entry in entries {
+                let mut compressed = Vec::with_capacity(entry.len() + 64);
+                compressor
+                    .compress_vec(entry, &mut compressed, FlushCompress::Sync)
+                    .unwrap();
+
+                let mut out = Vec::new();
+                inflater.decompress(&compressed, &mut out).unwrap();
+                assert_eq!(out, entry);
+            }
+            assert_eq!(inflater.wrapper(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_inflater_reset_variants() {
+        use flate2::{Compress, Compression, FlushCompress};
+
+        let compress = |zlib_header: bool, entry: &[u8]| {
+            let mut compressed = Vec::with_capacity(entry.len() + 64);
+            Compress::new(Compression::default(), zlib_header)
+                .compress_vec(entry, &mut compressed, FlushCompress::Sync)
+                .unwrap();
+            compressed
+        };
+        let mut inflater = StatefulInflater::new();
+        let mut out = Vec::new();
+        inflater
+            .decompress(&compress(true, b"zlib"), &mut out)
+            .unwrap();
+        assert_eq!(inflater.wrapper(), Some(DeflateWrapper::Zlib));
+
+        // 恢复后的数据位于压缩流中间，按 raw deflate 解压，保留识别结果
+        inflater.reset_keep_buffers();
+        assert_eq!(inflater.wrapper(), Some(DeflateWrapper::Zlib));
+        out.clear();
+        inflater
+            .decompress(&compress(false, b"raw"), &mut out)
+            .unwrap();
+        assert_eq!(out, b"raw");
+
+        // 完整重置后重新识别封装格式
+        inflater.reset();
+        assert_eq!(inflater.wrapper(), None);
+        out.clear();
+        inflater
+            .decompress(&compress(true, b"zlib again"), &mut out)
+            .unwrap();
+        assert_eq!(out, b"zlib again");
+        assert_eq!(inflater.wrapper(), Some(DeflateWrapper::Zlib));
+    }
+
+    #[test]
+    fn test_inflater_pool_reuses_context() {
+        let pool = InflaterPool::new();
+        let compressed = crate::writer::StatefulDeflater::new()
+            .compress(b"pooled")
+            .unwrap();
+
+        // 没有解压过的解压器不占用上下文
+        drop(StatefulInflater::from_pool(&pool));
+        assert!(pool.is_empty());
+
+        for _ in 0..3 {
+            let mut inflater = StatefulInflater::from_pool(&pool);
+            let mut out = Vec::new();
+            inflater.decompress(&compressed, &mut out).unwrap();
+            assert_eq!(out, b"pooled");
+            assert!(pool.is_empty());
+            drop(inflater);
+            assert_eq!(pool.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_scan_for_sync_marker() {
+        let mut data = vec![0xAB; 5];
+        data.extend_from_slice(&SYNC_MARKER);
+        data.extend_from_slice(&[1, 2, 3]);
+
+        let mut cursor = Cursor::new(&data);
+        assert_eq!(scan_for_sync_marker(&mut cursor, &[], 100).unwrap(), (13, true));
+        assert_eq!(cursor.position(), 13);
+
+        // 滚动窗口的初始内容参与匹配
+        let mut cursor = Cursor::new(&SYNC_MARKER[3..]);
+        assert_eq!(scan_for_sync_marker(&mut cursor, &SYNC_MARKER[..3], 100).unwrap(), (5, true));
+
+        // 超过扫描上限时停止
+        let mut cursor = Cursor::new(&data);
+        assert_eq!(scan_for_sync_marker(&mut cursor, &[], 10).unwrap(), (10, false));
+    }
+
+    #[test]
+    fn test_is_damaged_sync_marker() {
+        let mut bytes = SYNC_MARKER;
+        bytes[7] ^= 0xFF;
+        assert!(is_damaged_sync_marker(&bytes));
+        assert!(!is_damaged_sync_marker(&[0xAB; 8]));
+    }
+
     #[test]
     fn test_read_u16_le() {
         let data = [0x34, 0x12]; // 0x1234 in little endian
@@ -330,4 +1172,78 @@ mod tests {
         let result = read_u16_le(&mut cursor).unwrap();
         assert_eq!(result, 0x1234);
     }
+
+    #[test]
+    fn test_read_varint_edge_cases() {
+        assert_eq!(read_varint(&mut Cursor::new([0x00])).unwrap(), 0);
+        assert_eq!(read_varint(&mut Cursor::new([0xAC, 0x02])).unwrap(), 300);
+        let max = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        assert_eq!(read_varint(&mut Cursor::new(max)).unwrap(), u64::MAX);
+
+        assert!(matches!(
+            read_varint(&mut Cursor::new([0x80, 0x80])),
+            Err(GlogError::UnexpectedEof {
+                expected: 3,
+                available: 2
+            })
+        ));
+        let overflow = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+        assert!(matches!(
+            read_varint(&mut Cursor::new(overflow)),
+            Err(GlogError::FileCorrupt(_))
+        ));
+        assert!(matches!(
+            read_varint(&mut Cursor::new([0x80; 11])),
+            Err(GlogError::FileCorrupt(_))
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    mod props {
+        use super::*;
+        use byteorder::{LittleEndian, ReadBytesExt};
+        use proptest::prelude::*;
+
+        /// 按 protobuf 规则编码 varint
+        fn encode_varint(mut value: u64) -> Vec<u8> {
+            let mut out = Vec::new();
+            while value >= 0x80 {
+                out.push(value as u8 | 0x80);
+                value >>= 7;
+            }
+            out.push(value as u8);
+            out
+        }
+
+        proptest! {
+            #[test]
+            fn read_le_matches_byteorder(bytes in proptest::collection::vec(any::<u8>(), 0..16)) {
+                let mut ours = Cursor::new(&bytes);
+                let mut theirs = Cursor::new(&bytes);
+                prop_assert_eq!(read_u16_le(&mut ours).ok(), theirs.read_u16::<LittleEndian>().ok());
+                prop_assert_eq!(read_u32_le(&mut ours).ok(), theirs.read_u32::<LittleEndian>().ok());
+                prop_assert_eq!(read_u64_le(&mut ours).ok(), theirs.read_u64::<LittleEndian>().ok());
+            }
+
+            #[test]
+            fn read_le_reports_available_bytes(bytes in proptest::collection::vec(any::<u8>(), 0..8)) {
+                let err = read_u64_le(&mut Cursor::new(&bytes)).unwrap_err();
+                let eof = matches!(
+                    err,
+                    GlogError::UnexpectedEof { expected: 8, available } if available == bytes.len()
+                );
+                prop_assert!(eof);
+            }
+
+            #[test]
+            fn read_varint_round_trips(value in any::<u64>(), trailing in any::<u8>()) {
+                let mut bytes = encode_varint(value);
+                let len = bytes.len();
+                bytes.push(trailing);
+                let mut cursor = Cursor::new(&bytes);
+                prop_assert_eq!(read_varint(&mut cursor).unwrap(), value);
+                prop_assert_eq!(cursor.position(), len as u64);
+            }
+        }
+    }
 }
@@ -0,0 +1,171 @@
+//! # 字节来源
+//!
+//! V3 / V4 读取器通过 [`ByteSource`] 读取条目，解析代码不关心数据来自输入流还是内存：
+//!
+//! - [`ReadSource`] 包装任意 [`Read`]，条目数据读入复用的缓冲区；
+//! - [`SliceSource`] 直接在内存中的字节（如 `memmap2` 映射的文件）上移动游标，
+//!   取出条目数据时不发生系统调用，也不需要复制。
+//!
+//! 文件头、恢复扫描等不在热路径上的部分仍通过 [`Read`] 读取，两种来源都实现了它。
+
+use std::io::{self, Read};
+
+use super::read_safely;
+use crate::error::{GlogError, Result};
+
+/// 读取器的字节来源
+pub trait ByteSource: Read {
+    /// 取出接下来的 `n` 个字节
+    ///
+    /// 返回的切片在下一次读取前有效
+    ///
+    /// # Errors
+    /// 剩余数据不足 `n` 字节时返回 `UnexpectedEof`
+    fn next_exact(&mut self, n: usize) -> Result<&[u8]>;
+}
+
+/// 从输入流读取的字节来源
+pub struct ReadSource<R> {
+    /// 输入流
+    input: R,
+    /// `next_exact` 返回的数据
+    buf: Vec<u8>,
+}
+
+impl<R: Read> ReadSource<R> {
+    /// 包装输入流
+    ///
+    /// # Arguments
+    /// * `input` - 输入流
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 取回输入流
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+impl<R: Read> Read for ReadSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buf)
+    }
+}
+
+impl<R: Read> ByteSource for ReadSource<R> {
+    fn next_exact(&mut self, n: usize) -> Result<&[u8]> {
+        self.buf.resize(n, 0);
+        read_safely(&mut self.input, n, &mut self.buf)?;
+        Ok(&self.buf)
+    }
+}
+
+/// 内存中的字节来源
+///
+/// `B` 可以是 `Vec<u8>`、`&'static [u8]` 或 `memmap2::Mmap` 等任意字节容器
+pub struct SliceSource<B> {
+    /// 全部数据
+    data: B,
+    /// 下一个要读取的字节
+    pos: usize,
+}
+
+impl<B: AsRef<[u8]>> SliceSource<B> {
+    /// 从数据起始位置开始读取
+    ///
+    /// # Arguments
+    /// * `data` - 从文件起始位置开始的数据
+    pub fn new(data: B) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// 尚未读取的数据
+    fn remaining(&self) -> &[u8] {
+        &self.data.as_ref()[self.pos..]
+    }
+}
+
+impl<B: AsRef<[u8]>> Read for SliceSource<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.remaining().read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<B: AsRef<[u8]>> ByteSource for SliceSource<B> {
+    fn next_exact(&mut self, n: usize) -> Result<&[u8]> {
+        let start = self.pos;
+        let available = self.data.as_ref().len() - start;
+        if available < n {
+            self.pos += available;
+            return Err(GlogError::UnexpectedEof {
+                expected: n,
+                available,
+            });
+        }
+        self.pos += n;
+        Ok(&self.data.as_ref()[start..start + n])
+    }
+}
+
+/// 以只读方式映射整个文件
+///
+/// 映射期间文件被其他进程截断时访问映射区域会触发 `SIGBUS`，
+/// 因此只应用于不再写入的日志文件
+///
+/// # Arguments
+/// * `file` - 打开的文件
+///
+/// # Returns
+/// 返回在映射上读取的字节来源
+#[cfg(feature = "memmap")]
+pub fn map_file(file: &std::fs::File) -> Result<SliceSource<memmap2::Mmap>> {
+    // SAFETY: 映射为只读，调用方保证文件在读取期间不会被截断
+    let map = unsafe { memmap2::Mmap::map(file)? };
+    Ok(SliceSource::new(map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_sources_return_same_bytes() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut stream = ReadSource::new(Cursor::new(data.clone()));
+        let mut slice = SliceSource::new(data);
+
+        assert_eq!(stream.next_exact(3).unwrap(), [0, 1, 2]);
+        assert_eq!(slice.next_exact(3).unwrap(), [0, 1, 2]);
+
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        slice.read_exact(&mut byte).unwrap();
+        assert_eq!(byte, [3]);
+
+        assert_eq!(
+            stream.next_exact(252).unwrap(),
+            slice.next_exact(252).unwrap()
+        );
+        assert!(matches!(
+            stream.next_exact(2),
+            Err(GlogError::UnexpectedEof {
+                expected: 2,
+                available: 0
+            })
+        ));
+        assert!(matches!(
+            slice.next_exact(2),
+            Err(GlogError::UnexpectedEof {
+                expected: 2,
+                available: 0
+            })
+        ));
+    }
+}
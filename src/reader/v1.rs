@@ -0,0 +1,86 @@
+//! # Glog V1 版本文件读取器
+//!
+//! V1（初始版本）与 V2 的文件格式相同，区别在于 V1 写入方计算初始写入位置有误，
+//! 文件头与第一条日志之间可能留有零填充。读取逻辑委托给 [`FileReaderV2`]，
+//! 只在读取第一条日志之前跳过这段填充。
+
+use std::io::Read;
+
+use super::v2::FileReaderV2;
+use super::{EntryInfo, FileReader, GlogMetadata};
+use crate::error::{ReadResult, Result};
+use crate::version::GLOG_INITIAL_VERSION;
+
+/// V1 版本文件读取器
+pub struct FileReaderV1<R: Read> {
+    /// 共用的 V1/V2 读取逻辑
+    inner: FileReaderV2<R>,
+}
+
+impl<R: Read> FileReaderV1<R> {
+    /// 从任意 Read 实现创建 V1 读取器
+    ///
+    /// # Arguments
+    /// * `input` - 输入流
+    /// * `size` - 数据总大小
+    ///
+    /// # Returns
+    /// 返回新创建的 FileReaderV1 实例
+    pub fn from_reader(input: R, size: u64) -> Self {
+        Self {
+            inner: FileReaderV2::with_version(input, size, GLOG_INITIAL_VERSION),
+        }
+    }
+
+    /// 设置单条日志的长度上限，参见 [`FileReaderV2::with_max_log_length`]
+    ///
+    /// # Arguments
+    /// * `max_log_length` - 单条日志存储长度的上限（字节）
+    pub fn with_max_log_length(self, max_log_length: usize) -> Self {
+        Self {
+            inner: self.inner.with_max_log_length(max_log_length),
+        }
+    }
+}
+
+impl<R: Read> FileReader for FileReaderV1<R> {
+    fn read_remain_header(&mut self) -> Result<()> {
+        self.inner.read_remain_header()
+    }
+
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.inner.read_to_vec(out)
+    }
+
+    fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<ReadResult> {
+        self.inner.skip_entry(scratch)
+    }
+
+    fn last_entry(&self) -> Option<EntryInfo> {
+        self.inner.last_entry()
+    }
+
+    fn position(&self) -> u64 {
+        self.inner.position()
+    }
+
+    fn space_left(&self) -> u64 {
+        self.inner.space_left()
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn set_size(&mut self, size: u64) {
+        self.inner.set_size(size);
+    }
+
+    fn recover(&mut self) -> Result<bool> {
+        self.inner.recover()
+    }
+
+    fn metadata(&self) -> GlogMetadata {
+        self.inner.metadata()
+    }
+}
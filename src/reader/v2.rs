@@ -0,0 +1,285 @@
+//! # Glog V2 版本文件读取器
+//!
+//! 本模块实现了已废弃的 Glog V2（修复位置版本）文件格式的读取器，
+//! [`v1`](super::v1) 读取器也复用这里的实现。
+//! V1/V2 没有同步标记，日志条目只由长度前缀分隔。
+//!
+//! ## 文件格式 (所有长度使用小端序存储)
+//!
+//! ```text
+//! +-----------------------------------------------------------------+
+//! |                         magic number (4)                        |
+//! +----------------+----------------+-------------------------------+
+//! |   version (1)  |  mode set (1)  |     proto name length (2)     |
+//! +----------------+----------------+-------------------------------+
+//! |       proto name (0...)       ...
+//! +=================================+===============================+
+//! |         log length (2)          |
+//! +---------------------------------+-------------------------------+
+//! |                           log data (0...)         ...
+//! +-----------------------------------------------------------------+
+//! |         log length (2)          |
+//! +---------------------------------+-------------------------------+
+//! |                              ...
+//! +-----------------------------------------------------------------+
+//! ```
+//!
+//! ## 重要说明
+//!
+//! 写入方使用 mmap 预分配文件空间，最后一条日志之后是零填充，
+//! 因此读到长度为 0 的条目即视为文件结束。
+//! 由于没有同步标记，条目损坏后无法找到下一个条目边界，
+//! [`recover`](FileReader::recover) 会直接跳过文件剩余部分。
+
+use std::io::Read;
+
+use super::{
+    read_proto_name, read_safely, read_u16_le, CompressMode, EncryptMode, EntryInfo, FileReader,
+    GlogMetadata, StatefulInflater, SINGLE_LOG_CONTENT_MAX_LENGTH,
+};
+use crate::error::{GlogError, ReadResult, RecoverReason, Result};
+use crate::version::{GLOG_FIX_POSITION_VERSION, GLOG_INITIAL_VERSION};
+
+/// V2 版本文件读取器
+pub struct FileReaderV2<R: Read> {
+    /// 输入流
+    input: R,
+    /// 文件格式版本（V1 或 V2）
+    version: u8,
+    /// 压缩模式
+    compress_mode: CompressMode,
+    /// 加密模式
+    encrypt_mode: EncryptMode,
+    /// 协议名称
+    proto_name: String,
+    /// 当前读取位置
+    position: u64,
+    /// 文件总大小
+    size: u64,
+    /// 有状态的解压器（模拟 Java 的 Inflater 行为）
+    inflater: StatefulInflater,
+    /// 是否已读到第一条日志（V1 在此之前可能有零填充）
+    started: bool,
+    /// 条目损坏后不再继续读取
+    exhausted: bool,
+    /// 单条日志的长度上限
+    max_log_length: usize,
+    /// 最近一次成功读取的日志的存储信息
+    last_entry: Option<EntryInfo>,
+}
+
+impl<R: Read> FileReaderV2<R> {
+    /// 从任意 Read 实现创建 V2 读取器
+    ///
+    /// # Arguments
+    /// * `input` - 输入流
+    /// * `size` - 数据总大小
+    ///
+    /// # Returns
+    /// 返回新创建的 FileReaderV2 实例
+    pub fn from_reader(input: R, size: u64) -> Self {
+        Self::with_version(input, size, GLOG_FIX_POSITION_VERSION)
+    }
+
+    /// 创建指定版本的读取器
+    ///
+    /// # Arguments
+    /// * `input` - 输入流
+    /// * `size` - 数据总大小
+    /// * `version` - 文件格式版本
+    pub(super) fn with_version(input: R, size: u64, version: u8) -> Self {
+        Self {
+            input,
+            version,
+            compress_mode: CompressMode::None,
+            encrypt_mode: EncryptMode::None,
+            proto_name: String::new(),
+            position: 5, // 跳过魔数(4字节) + 版本(1字节)
+            size,
+            inflater: StatefulInflater::new(),
+            started: false,
+            exhausted: false,
+            max_log_length: SINGLE_LOG_CONTENT_MAX_LENGTH,
+            last_entry: None,
+        }
+    }
+
+    /// 设置单条日志的长度上限
+    ///
+    /// 长度字段超过上限的条目按损坏处理，默认为 [`SINGLE_LOG_CONTENT_MAX_LENGTH`]
+    ///
+    /// # Arguments
+    /// * `max_log_length` - 单条日志存储长度的上限（字节）
+    pub fn with_max_log_length(mut self, max_log_length: usize) -> Self {
+        self.max_log_length = max_log_length;
+        self
+    }
+
+    /// 使用指定的解压器，例如 [`StatefulInflater::from_pool`] 创建的共享上下文的解压器
+    ///
+    /// 必须在读取任何条目之前调用
+    ///
+    /// # Arguments
+    /// * `inflater` - 尚未使用的解压器
+    pub fn with_inflater(mut self, inflater: StatefulInflater) -> Self {
+        self.inflater = inflater;
+        self
+    }
+}
+
+impl<R: Read> FileReader for FileReaderV2<R> {
+    /// 读取剩余的文件头信息
+    ///
+    /// 解析模式设置字节和协议名称
+    fn read_remain_header(&mut self) -> Result<()> {
+        // 读取模式设置字节
+        let mut ms_buf = [0u8; 1];
+        read_safely(&mut self.input, 1, &mut ms_buf)?;
+        let ms = ms_buf[0];
+
+        // 解析压缩模式 (高4位)
+        match ms >> 4 {
+            0 => self.compress_mode = CompressMode::None,
+            1 => self.compress_mode = CompressMode::Zlib,
+            _ => return Err(GlogError::IllegalCompressMode(ms >> 4)),
+        }
+
+        // 解析加密模式 (低4位)
+        match ms & 0x0F {
+            0 => self.encrypt_mode = EncryptMode::None,
+            1 => self.encrypt_mode = EncryptMode::Aes,
+            _ => return Err(GlogError::IllegalEncryptMode(ms & 0x0F)),
+        }
+
+        // 读取协议名称：魔数(4) + 版本(1) + 模式(1) 之后是长度字段
+        let (proto_name, proto_name_len) =
+            read_proto_name(&mut self.input, 4 + 1 + 1, self.size, 0)?;
+        self.proto_name = proto_name;
+
+        // 更新位置：魔数(4) + 版本(1) + 模式(1) + 协议名称长度(2) + 协议名称
+        self.position = 4 + 1 + 1 + 2 + proto_name_len as u64;
+
+        Ok(())
+    }
+
+    /// 读取下一条日志
+    ///
+    /// # Arguments
+    /// * `out` - 输出缓冲区
+    ///
+    /// # Returns
+    /// 返回读取结果
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.last_entry = None;
+        out.clear();
+        let log_length = loop {
+            // 至少需要: 日志长度(2) + 日志数据(1)
+            if self.exhausted || self.space_left() < 3 {
+                return Ok(ReadResult::Eof);
+            }
+            let log_length = read_u16_le(&mut self.input)? as usize;
+            self.position += 2;
+
+            if log_length != 0 {
+                break log_length;
+            }
+            // V1 写入方的初始写入位置有误，文件头与第一条日志之间可能有零填充
+            if self.version != GLOG_INITIAL_VERSION || self.started {
+                // 零填充：已到达有效数据末尾
+                self.exhausted = true;
+                return Ok(ReadResult::Eof);
+            }
+        };
+        self.started = true;
+        let entry_start = self.position - 2;
+
+        if log_length > self.max_log_length {
+            return Ok(ReadResult::NeedRecover(RecoverReason::InvalidLogLength(
+                log_length,
+            )));
+        }
+        if (log_length as u64) > self.space_left() {
+            return Ok(ReadResult::NeedRecover(RecoverReason::Truncated));
+        }
+
+        // 读取日志数据
+        let mut buf = vec![0u8; log_length];
+        read_safely(&mut self.input, log_length, &mut buf)?;
+        self.position += log_length as u64;
+
+        // 根据压缩模式处理数据
+        let final_length = match self.compress_mode {
+            CompressMode::Zlib => match self.inflater.decompress(&buf, out) {
+                Ok((_, len)) => len,
+                Err(_) => return Ok(ReadResult::NeedRecover(RecoverReason::DecompressFailed)),
+            },
+            CompressMode::None => {
+                *out = buf;
+                log_length
+            }
+            // V1/V2 文件头只有无压缩和 zlib 两种模式
+            CompressMode::Zstd => unreachable!("V2 文件头不会解析出 zstd 模式"),
+        };
+
+        self.last_entry = Some(EntryInfo {
+            offset: entry_start,
+            entry_len: self.position - entry_start,
+            stored_len: log_length,
+            compressed: self.compress_mode == CompressMode::Zlib,
+            compress_mode: self.compress_mode,
+            encrypted: self.encrypt_mode == EncryptMode::Aes,
+            client_pubkey: None,
+            len: final_length,
+        });
+        Ok(ReadResult::Success(final_length))
+    }
+
+    /// 最近一次成功读取的日志的存储信息
+    fn last_entry(&self) -> Option<EntryInfo> {
+        self.last_entry.clone()
+    }
+
+    /// 获取当前读取位置
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// 获取剩余可读取的字节数
+    fn space_left(&self) -> u64 {
+        self.size.saturating_sub(self.position)
+    }
+
+    /// 获取数据总大小
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 更新数据总大小
+    fn set_size(&mut self, size: u64) {
+        self.size = size;
+    }
+
+    /// 从损坏处恢复读取
+    ///
+    /// 没有同步标记可供重新同步，跳过文件剩余部分
+    fn recover(&mut self) -> Result<bool> {
+        self.exhausted = true;
+        self.position = self.position.max(self.size);
+        Ok(false)
+    }
+
+    /// 获取文件元数据
+    fn metadata(&self) -> GlogMetadata {
+        GlogMetadata {
+            version: self.version,
+            proto_name: self.proto_name.clone(),
+            compress_mode: self.compress_mode,
+            encrypt_mode: Some(self.encrypt_mode),
+            file_size: self.size,
+            skipped_encrypted: 0,
+            truncated_tail_bytes: 0,
+            key_cache: None,
+            deflate_wrapper: self.inflater.wrapper(),
+        }
+    }
+}
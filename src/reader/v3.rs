@@ -42,17 +42,22 @@
 //! Java 版本的实现使用有状态的 Inflater，在整个文件读取过程中保持 zlib 字典状态。
 //! 这意味着多个日志块实际上是作为一个连续的 deflate 流压缩的。
 //! 因此本实现也使用 `StatefulInflater` 来保持解压状态。
+//! 较新的写入端支持 zstd（模式设置高 4 位为 2），同样是贯穿整个文件的压缩流，
+//! 使用 `StatefulZstdDecoder` 解压。
 
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufReader, Read};
 // use log::{info, warn};
 
+use super::framing::v3_entry_len;
+use super::source::{ByteSource, ReadSource};
 use super::{
-    read_safely, read_u16_le, CompressMode,
-    EncryptMode, FileReader,
-    StatefulInflater, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
+    copy_entry, is_damaged_sync_marker, read_proto_name, read_safely, scan_for_sync_marker,
+    skip_safely, CompressMode, EncryptMode, EntryInfo, FileReader, GlogMetadata, StatefulInflater,
+    StatefulZstdDecoder, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
 };
-use crate::error::{GlogError, ReadResult, Result};
+use crate::error::{GlogError, ReadResult, RecoverReason, Result};
+use crate::version::GLOG_RECOVERY_VERSION;
 
 /// V3 版本文件读取器
 ///
@@ -61,23 +66,38 @@ use crate::error::{GlogError, ReadResult, Result};
 /// ## 解压状态
 ///
 /// 内置有状态的解压器，模拟 Java 的 jzlib Inflater 行为
-pub struct FileReaderV3<R: Read> {
-    /// 输入流
-    input: R,
+pub struct FileReaderV3<S: ByteSource> {
+    /// 字节来源
+    input: S,
     /// 压缩模式
     compress_mode: CompressMode,
     /// 加密模式
-    #[allow(dead_code)]
     encrypt_mode: EncryptMode,
+    /// 协议名称
+    proto_name: String,
     /// 当前读取位置
     position: u64,
     /// 文件总大小
     size: u64,
     /// 有状态的解压器（模拟 Java 的 Inflater 行为）
     inflater: StatefulInflater,
+    /// 有状态的 zstd 解压器（文件头为 zstd 模式时使用）
+    zstd: StatefulZstdDecoder,
+    /// 最近一次读到的不匹配的同步标记，供 `recover` 使用
+    mismatched_marker: Option<[u8; 8]>,
+    /// 单条日志的长度上限
+    max_log_length: usize,
+    /// 最后一条不完整日志的字节数
+    truncated_tail_bytes: u64,
+    /// 最近一次成功读取的日志的存储信息
+    last_entry: Option<EntryInfo>,
+    /// 只验证条目结构，丢弃未压缩的数据（`skip_entry`）
+    framing_only: bool,
+    /// `read` 复用的日志缓冲区，解码后复制到调用方的缓冲区
+    read_buf: Vec<u8>,
 }
 
-impl FileReaderV3<BufReader<File>> {
+impl FileReaderV3<ReadSource<BufReader<File>>> {
     /// 从文件创建 V3 读取器
     ///
     /// # Arguments
@@ -87,19 +107,11 @@ impl FileReaderV3<BufReader<File>> {
     /// # Returns
     /// 返回新创建的 FileReaderV3 实例
     pub fn new(file: File, size: u64) -> Result<Self> {
-        let reader = BufReader::new(file);
-        Ok(Self {
-            input: reader,
-            compress_mode: CompressMode::None,
-            encrypt_mode: EncryptMode::None,
-            position: 5, // 跳过魔数(4字节) + 版本(1字节)
-            size,
-            inflater: StatefulInflater::new(),
-        })
+        Ok(Self::from_reader(BufReader::new(file), size))
     }
 }
 
-impl<R: Read> FileReaderV3<R> {
+impl<R: Read> FileReaderV3<ReadSource<R>> {
     /// 从任意 Read 实现创建 V3 读取器
     ///
     /// # Arguments
@@ -108,18 +120,61 @@ impl<R: Read> FileReaderV3<R> {
     ///
     /// # Returns
     /// 返回新创建的 FileReaderV3 实例
-    #[allow(dead_code)]
     pub fn from_reader(input: R, size: u64) -> Self {
+        Self::from_source(ReadSource::new(input), size)
+    }
+}
+
+impl<S: ByteSource> FileReaderV3<S> {
+    /// 从字节来源创建 V3 读取器
+    ///
+    /// # Arguments
+    /// * `input` - 位于魔数和版本之后的字节来源
+    /// * `size` - 数据总大小
+    ///
+    /// # Returns
+    /// 返回新创建的 FileReaderV3 实例
+    pub fn from_source(input: S, size: u64) -> Self {
         Self {
             input,
             compress_mode: CompressMode::None,
             encrypt_mode: EncryptMode::None,
+            proto_name: String::new(),
             position: 5,
             size,
             inflater: StatefulInflater::new(),
+            zstd: StatefulZstdDecoder::new(),
+            mismatched_marker: None,
+            max_log_length: SINGLE_LOG_CONTENT_MAX_LENGTH,
+            truncated_tail_bytes: 0,
+            last_entry: None,
+            framing_only: false,
+            read_buf: Vec::new(),
         }
     }
 
+    /// 设置单条日志的长度上限
+    ///
+    /// 长度字段超过上限的条目按损坏处理，默认为 [`SINGLE_LOG_CONTENT_MAX_LENGTH`]
+    ///
+    /// # Arguments
+    /// * `max_log_length` - 单条日志存储长度的上限（字节）
+    pub fn with_max_log_length(mut self, max_log_length: usize) -> Self {
+        self.max_log_length = max_log_length;
+        self
+    }
+
+    /// 使用指定的解压器，例如 [`StatefulInflater::from_pool`] 创建的共享上下文的解压器
+    ///
+    /// 必须在读取任何条目之前调用
+    ///
+    /// # Arguments
+    /// * `inflater` - 尚未使用的解压器
+    pub fn with_inflater(mut self, inflater: StatefulInflater) -> Self {
+        self.inflater = inflater;
+        self
+    }
+
     /// 计算日志存储大小
     ///
     /// # Arguments
@@ -128,12 +183,68 @@ impl<R: Read> FileReaderV3<R> {
     /// # Returns
     /// 返回包含长度字段和同步标记的总存储大小
     fn log_store_size(&self, len: usize) -> usize {
-        // 日志长度(2字节) + 日志数据 + 同步标记(8字节)
-        2 + len + 8
+        v3_entry_len(len)
+    }
+
+    /// 处理存储长度超出剩余数据的条目
+    ///
+    /// 向前扫描剩余数据：没有同步标记说明文件在最后一条日志中间结束，按文件结束处理；
+    /// 找到同步标记说明长度字段损坏，停在该同步标记之后触发恢复
+    ///
+    /// # Arguments
+    /// * `entry_start` - 条目的起始位置
+    ///
+    /// # Returns
+    /// 返回 `ReadResult::Eof` 或 `ReadResult::NeedRecover`
+    fn truncated_entry(&mut self, entry_start: u64) -> Result<ReadResult> {
+        let limit = self.space_left();
+        let (consumed, found) = scan_for_sync_marker(&mut self.input, &[], limit)?;
+        self.position += consumed;
+        if found {
+            // 已经位于同步标记之后，`recover` 无需再扫描
+            self.mismatched_marker = Some(SYNC_MARKER);
+            return Ok(ReadResult::NeedRecover(RecoverReason::Truncated));
+        }
+        self.truncated_tail_bytes = self.position - entry_start;
+        Ok(ReadResult::Eof)
+    }
+
+    /// 读取并验证条目末尾的同步标记，记录条目信息
+    ///
+    /// # Arguments
+    /// * `entry_start` - 条目的起始位置
+    /// * `log_length` - 存储的日志数据长度
+    /// * `final_length` - 解压后的日志长度
+    fn finish_entry(
+        &mut self,
+        entry_start: u64,
+        log_length: usize,
+        final_length: usize,
+    ) -> Result<ReadResult> {
+        let mut sync_marker = [0u8; 8];
+        sync_marker.copy_from_slice(self.input.next_exact(8)?);
+        self.position += 8;
+
+        if sync_marker != SYNC_MARKER {
+            self.mismatched_marker = Some(sync_marker);
+            return Ok(ReadResult::NeedRecover(RecoverReason::SyncMarkerMismatch));
+        }
+
+        self.last_entry = Some(EntryInfo {
+            offset: entry_start,
+            entry_len: self.position - entry_start,
+            stored_len: log_length,
+            compressed: self.compress_mode != CompressMode::None,
+            compress_mode: self.compress_mode,
+            encrypted: self.encrypt_mode == EncryptMode::Aes,
+            client_pubkey: None,
+            len: final_length,
+        });
+        Ok(ReadResult::Success(final_length))
     }
 }
 
-impl<R: Read> FileReader for FileReaderV3<R> {
+impl<S: ByteSource> FileReader for FileReaderV3<S> {
     /// 读取剩余的文件头信息
     ///
     /// 解析模式设置字节、协议名称和同步标记
@@ -147,6 +258,7 @@ impl<R: Read> FileReader for FileReaderV3<R> {
         match ms >> 4 {
             0 => self.compress_mode = CompressMode::None,
             1 => self.compress_mode = CompressMode::Zlib,
+            2 => self.compress_mode = CompressMode::Zstd,
             _ => return Err(GlogError::IllegalCompressMode(ms >> 4)),
         }
 
@@ -159,27 +271,11 @@ impl<R: Read> FileReader for FileReaderV3<R> {
 
         // info!("压缩模式: {:?}, 加密模式: {:?}", self.compress_mode, self.encrypt_mode);
 
-        // 读取协议名称长度
-        let proto_name_len = read_u16_le(&mut self.input)?;
-        println!("协议名称长度: {}", proto_name_len);
-        std::io::stdout().flush().unwrap();
-
-        // 检查是否有足够的数据
-        let required = proto_name_len as usize + 8;
-        let available = self.space_left() as usize;
-        if available < required {
-            return Err(GlogError::UnexpectedEof {
-                expected: required,
-                available,
-            });
-        }
-
-        // 读取协议名称
-        let mut name = vec![0u8; proto_name_len as usize];
-        read_safely(&mut self.input, proto_name_len as usize, &mut name)?;
-        let proto_name = String::from_utf8_lossy(&name);
-        println!("协议名称: {}", proto_name);
-        std::io::stdout().flush().unwrap();
+        // 读取协议名称：魔数(4) + 版本(1) + 模式(1) 之后是长度字段
+        let (proto_name, proto_name_len) =
+            read_proto_name(&mut self.input, 4 + 1 + 1, self.size, SYNC_MARKER.len())?;
+        self.proto_name = proto_name;
+        // info!("协议名称: {}", self.proto_name);
 
         // 读取并验证同步标记
         let mut sync_marker = [0u8; 8];
@@ -191,8 +287,7 @@ impl<R: Read> FileReader for FileReaderV3<R> {
 
         // 更新位置：魔数(4) + 版本(1) + 模式(1) + 协议名称长度(2) + 协议名称 + 同步标记(8)
         self.position = 4 + 1 + 1 + 2 + proto_name_len as u64 + 8;
-        println!("读取头部完成，当前位置: {}", self.position);
-        std::io::stdout().flush().unwrap();
+        // info!("读取头部完成，当前位置: {}", self.position);
 
         Ok(())
     }
@@ -200,71 +295,95 @@ impl<R: Read> FileReader for FileReaderV3<R> {
     /// 读取下一条日志
     ///
     /// # Arguments
-    /// * `out_buf` - 输出缓冲区
+    /// * `out` - 输出缓冲区
     ///
     /// # Returns
     /// 返回读取结果
-    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
-        // 检查是否有足够的数据读取最小的日志条目
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.mismatched_marker = None;
+        self.last_entry = None;
+        out.clear();
+
+        // 检查是否有足够的数据读取最小的日志条目，不足时剩余数据是不完整的最后一条日志
         if self.space_left() < self.log_store_size(1) as u64 {
+            if self.space_left() > 0 {
+                self.truncated_tail_bytes = self.space_left();
+            }
             return Ok(ReadResult::Eof);
         }
 
         // 读取日志长度
-        let log_length = read_u16_le(&mut self.input)? as usize;
+        let entry_start = self.position;
+        let length = self.input.next_exact(2)?;
+        let log_length = u16::from_le_bytes([length[0], length[1]]) as usize;
         self.position += 2;
 
-        // 检查是否有足够的数据
-        let required = log_length + 8;
-        let available = self.space_left() as usize;
-        if available < required {
-            return Err(GlogError::UnexpectedEof {
-                expected: required,
-                available,
-            });
+        // 验证日志长度（先于剩余数据检查，损坏的长度字段应当触发恢复而不是报错）
+        if log_length == 0 || log_length > self.max_log_length {
+            return Ok(ReadResult::NeedRecover(RecoverReason::InvalidLogLength(
+                log_length,
+            )));
         }
 
-        // 验证日志长度
-        if log_length == 0 || log_length > SINGLE_LOG_CONTENT_MAX_LENGTH {
-            eprintln!("无效的日志长度: {}，位置: {}", log_length, self.position);
-            std::io::stderr().flush().unwrap();
-            return Ok(ReadResult::NeedRecover(-2));
+        // 检查是否有足够的数据
+        if self.space_left() < (log_length + 8) as u64 {
+            return self.truncated_entry(entry_start);
         }
 
-        println!("日志长度: {}", log_length);
-        std::io::stdout().flush().unwrap();
+        // info!("日志长度: {}", log_length);
 
-        // 读取日志数据
-        let mut buf = vec![0u8; log_length];
-        read_safely(&mut self.input, log_length, &mut buf)?;
+        // 只验证结构时，未压缩的数据直接丢弃
+        if self.framing_only && self.compress_mode == CompressMode::None {
+            skip_safely(&mut self.input, log_length)?;
+            self.position += log_length as u64;
+            return self.finish_entry(entry_start, log_length, log_length);
+        }
+
+        // 读取日志数据（内存中的来源不复制）
+        let buf = self.input.next_exact(log_length)?;
         self.position += log_length as u64;
 
-        // 根据压缩模式处理数据
+        // 根据压缩模式处理数据，解压失败说明数据已损坏，交给恢复流程跳过
         let final_length = match self.compress_mode {
             CompressMode::Zlib => {
                 // 使用有状态的解压器解压数据
-                self.inflater.decompress(&buf, out_buf)?
+                match self.inflater.decompress(buf, out) {
+                    Ok((_, len)) => len,
+                    Err(_) => return Ok(ReadResult::NeedRecover(RecoverReason::DecompressFailed)),
+                }
             }
+            CompressMode::Zstd => match self.zstd.decompress(buf, out) {
+                Ok(len) => len,
+                Err(_) => return Ok(ReadResult::NeedRecover(RecoverReason::DecompressFailed)),
+            },
             CompressMode::None => {
-                // 直接复制数据
-                let copy_len = log_length.min(out_buf.len());
-                out_buf[..copy_len].copy_from_slice(&buf[..copy_len]);
-                copy_len
+                // 直接使用原始数据
+                out.extend_from_slice(buf);
+                log_length
             }
         };
 
-        // 读取并验证同步标记
-        let mut sync_marker = [0u8; 8];
-        read_safely(&mut self.input, 8, &mut sync_marker)?;
+        self.finish_entry(entry_start, log_length, final_length)
+    }
 
-        if sync_marker != SYNC_MARKER {
-            eprintln!("同步标记不匹配，位置: {}", self.position);
-            std::io::stderr().flush().unwrap();
-            return Ok(ReadResult::NeedRecover(-3));
-        }
-        self.position += 8;
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
+        let mut entry = std::mem::take(&mut self.read_buf);
+        let result = self.read_to_vec(&mut entry);
+        let result = copy_entry(result, &entry, out_buf);
+        self.read_buf = entry;
+        result
+    }
 
-        Ok(ReadResult::Success(final_length))
+    fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<ReadResult> {
+        self.framing_only = true;
+        let result = self.read_to_vec(scratch);
+        self.framing_only = false;
+        result
+    }
+
+    /// 最近一次成功读取的日志的存储信息
+    fn last_entry(&self) -> Option<EntryInfo> {
+        self.last_entry.clone()
     }
 
     /// 获取当前读取位置
@@ -273,6 +392,7 @@ impl<R: Read> FileReader for FileReaderV3<R> {
     }
 
     /// 获取剩余可读取的字节数
+    #[allow(clippy::implicit_saturating_sub)]
     fn space_left(&self) -> u64 {
         if self.size <= self.position {
             0
@@ -280,6 +400,61 @@ impl<R: Read> FileReader for FileReaderV3<R> {
             self.size - self.position
         }
     }
+
+    /// 获取数据总大小
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 更新数据总大小
+    fn set_size(&mut self, size: u64) {
+        self.size = size;
+    }
+
+    /// 从损坏处恢复读取
+    ///
+    /// 同步标记只有个别字节损坏时直接从当前位置继续；
+    /// 否则逐字节向前扫描到下一个同步标记之后，并重置解压器（字典已经失效）
+    fn recover(&mut self) -> Result<bool> {
+        let window = match self.mismatched_marker.take() {
+            Some(marker) if is_damaged_sync_marker(&marker) => return Ok(true),
+            Some(marker) => marker.to_vec(),
+            None => Vec::new(),
+        };
+
+        let limit = self.space_left();
+        let (consumed, found) = scan_for_sync_marker(&mut self.input, &window, limit)?;
+        self.position += consumed;
+        self.inflater.reset_keep_buffers();
+        self.zstd.reset();
+        Ok(found)
+    }
+
+    /// 从指定的条目边界继续读取
+    ///
+    /// 之前的压缩字典已经丢失，因此重置解压器
+    fn resume_at(&mut self, position: u64) -> Result<()> {
+        self.position = position;
+        self.mismatched_marker = None;
+        self.inflater.reset_keep_buffers();
+        self.zstd.reset();
+        Ok(())
+    }
+
+    /// 获取文件元数据
+    fn metadata(&self) -> GlogMetadata {
+        GlogMetadata {
+            version: GLOG_RECOVERY_VERSION,
+            proto_name: self.proto_name.clone(),
+            compress_mode: self.compress_mode,
+            encrypt_mode: Some(self.encrypt_mode),
+            file_size: self.size,
+            skipped_encrypted: 0,
+            truncated_tail_bytes: self.truncated_tail_bytes,
+            key_cache: None,
+            deflate_wrapper: self.inflater.wrapper(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -288,15 +463,23 @@ mod tests {
 
     #[test]
     fn test_log_store_size() {
-        let reader = FileReaderV3::<std::io::Cursor<Vec<u8>>> {
-            input: std::io::Cursor::new(vec![]),
+        let reader = FileReaderV3 {
+            input: ReadSource::new(std::io::Cursor::new(vec![])),
             compress_mode: CompressMode::None,
             encrypt_mode: EncryptMode::None,
+            proto_name: String::new(),
             position: 0,
             size: 0,
             inflater: StatefulInflater::new(),
+            zstd: StatefulZstdDecoder::new(),
+            mismatched_marker: None,
+            max_log_length: SINGLE_LOG_CONTENT_MAX_LENGTH,
+            truncated_tail_bytes: 0,
+            last_entry: None,
+            framing_only: false,
+            read_buf: Vec::new(),
         };
-        
+
         // 日志长度(2) + 数据(10) + 同步标记(8) = 20
         assert_eq!(reader.log_store_size(10), 20);
     }
@@ -47,9 +47,12 @@
 //! Java 版本的实现使用有状态的 Inflater，在整个文件读取过程中保持 zlib 字典状态。
 //! 这意味着多个日志块实际上是作为一个连续的 deflate 流压缩的。
 //! 因此本实现也使用 `StatefulInflater` 来保持解压状态。
+//! 较新的写入端支持 zstd（模式设置高 4 位为 3），所有 zstd 条目同样共用一个压缩流，
+//! 使用 `StatefulZstdDecoder` 解压；未知的压缩模式按损坏条目处理，跳到下一个同步标记。
 
+use std::fmt;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::{BufReader, Read};
 // use log::{info, warn};
 
 use aes::cipher::AsyncStreamCipher;
@@ -58,20 +61,32 @@ use cfb_mode::cipher::KeyIvInit;
 use cfb_mode::Decryptor;
 use k256::{
     elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint}, PublicKey,
-    SecretKey,
 };
-use std::collections::HashMap;
 
+use zeroize::{Zeroize, Zeroizing};
+
+use super::key::{KeyProvider, RedactedKey, StaticKeyProvider};
+use super::framing::v4_entry_len;
+use super::source::{ByteSource, ReadSource};
 use super::{
-    read_safely, read_u16_le, CompressMode,
-    EncryptMode, FileReader,
-    StatefulInflater, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
+    copy_entry, is_damaged_sync_marker, read_proto_name, read_safely, scan_for_sync_marker,
+    skip_safely,
+    CompressMode,
+    EncryptMode, EntryInfo, FileReader, GlogMetadata,
+    StatefulInflater, StatefulZstdDecoder, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
 };
-use crate::error::{GlogError, ReadResult, Result};
+use crate::error::{GlogError, ReadResult, RecoverReason, Result};
+use crate::version::GLOG_CIPHER_VERSION;
 
 /// AES CFB 解密器类型别名
 type Aes128CfbDec = Decryptor<Aes128>;
 
+/// SYNC_FLUSH 在每段压缩数据末尾写入的空存储块
+///
+/// 写入方每条日志都以 SYNC_FLUSH 结束，缺少该结尾说明数据已损坏（如 IV 被改写后解密出乱码），
+/// 此时不能交给解压器，否则会破坏它的内部状态并连带后续所有日志
+const DEFLATE_SYNC_TRAILER: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
 /// V4 版本文件读取器
 ///
 /// 实现了 Glog 加密版本 (V4) 的日志读取功能
@@ -80,24 +95,61 @@ type Aes128CfbDec = Decryptor<Aes128>;
 /// ## 解压状态
 ///
 /// 内置有状态的解压器，模拟 Java 的 jzlib Inflater 行为
-pub struct FileReaderV4<R: Read> {
-    /// 输入流
-    input: R,
-    /// 服务器私钥（十六进制字符串）
-    svr_pri_key: Option<String>,
-    /// 服务器 EC 私钥
-    svr_ec_pri_key: Option<SecretKey>,
+pub struct FileReaderV4<S: ByteSource> {
+    /// 字节来源
+    input: S,
+    /// 解密密钥提供者
+    key_provider: Option<Box<dyn KeyProvider>>,
     /// 当前读取位置
     position: u64,
     /// 文件总大小
     size: u64,
     /// 有状态的解压器（模拟 Java 的 Inflater 行为）
     inflater: StatefulInflater,
-    /// ECDH 共享密钥缓存（压缩公钥 -> 共享密钥）
-    shared_key_cache: HashMap<[u8; 33], Vec<u8>>,
+    /// 有状态的 zstd 解压器（所有 zstd 条目共享）
+    zstd: StatefulZstdDecoder,
+    /// 协议名称
+    proto_name: String,
+    /// 第一条日志的压缩和加密模式
+    first_modes: Option<(CompressMode, EncryptMode)>,
+    /// 最近一次读到的不匹配的同步标记，供 `recover` 使用
+    mismatched_marker: Option<[u8; 8]>,
+    /// 没有密钥时是否跳过加密条目（否则返回 `CipherNotReady`）
+    skip_encrypted: bool,
+    /// 因没有密钥而跳过的加密条目数
+    skipped_encrypted: u64,
+    /// 单条日志的长度上限
+    max_log_length: usize,
+    /// 最后一条不完整日志的字节数
+    truncated_tail_bytes: u64,
+    /// 最近一次成功读取的日志的存储信息
+    last_entry: Option<EntryInfo>,
+    /// 只验证条目结构，丢弃未压缩的数据（`skip_entry`）
+    framing_only: bool,
+    /// 只解密不解压，输出存储数据（`read_raw`）
+    raw: bool,
+    /// 存储的日志数据，加密条目在其中原地解密后交给解压器
+    entry_buf: Vec<u8>,
+    /// 有多个候选密钥时逐个试解密的缓冲区
+    trial_buf: Vec<u8>,
+    /// `read` 复用的日志缓冲区，解码后复制到调用方的缓冲区
+    read_buf: Vec<u8>,
+    /// 候选密钥，每次使用前和丢弃时清零
+    candidates: Zeroizing<Vec<[u8; 16]>>,
 }
 
-impl FileReaderV4<BufReader<File>> {
+impl<S: ByteSource> fmt::Debug for FileReaderV4<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileReaderV4")
+            .field("proto_name", &self.proto_name)
+            .field("position", &self.position)
+            .field("size", &self.size)
+            .field("key", &RedactedKey(self.key_provider.is_some()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileReaderV4<ReadSource<BufReader<File>>> {
     /// 从文件创建 V4 读取器
     ///
     /// # Arguments
@@ -108,26 +160,11 @@ impl FileReaderV4<BufReader<File>> {
     /// # Returns
     /// 返回新创建的 FileReaderV4 实例
     pub fn new(file: File, size: u64, key: Option<String>) -> Result<Self> {
-        let reader = BufReader::new(file);
-        let svr_ec_pri_key = if let Some(ref k) = key {
-            Some(prepare_svr_pri_key(k)?)
-        } else {
-            None
-        };
-
-        Ok(Self {
-            input: reader,
-            svr_pri_key: key,
-            svr_ec_pri_key,
-            position: 5, // 跳过魔数(4字节) + 版本(1字节)
-            size,
-            inflater: StatefulInflater::new(),
-            shared_key_cache: HashMap::new(),
-        })
+        Self::from_reader(BufReader::new(file), size, key)
     }
 }
 
-impl<R: Read> FileReaderV4<R> {
+impl<R: Read> FileReaderV4<ReadSource<R>> {
     /// 从任意 Read 实现创建 V4 读取器
     ///
     /// # Arguments
@@ -137,120 +174,293 @@ impl<R: Read> FileReaderV4<R> {
     ///
     /// # Returns
     /// 返回新创建的 FileReaderV4 实例
-    #[allow(dead_code)]
     pub fn from_reader(input: R, size: u64, key: Option<String>) -> Result<Self> {
-        let svr_ec_pri_key = if let Some(ref k) = key {
-            Some(prepare_svr_pri_key(k)?)
-        } else {
-            None
+        // 私钥字符串解析后即清零
+        let key = key.map(Zeroizing::new);
+        let key_provider = match key {
+            Some(ref k) => Some(Box::new(StaticKeyProvider::from_hex(k)?) as Box<dyn KeyProvider>),
+            None => None,
         };
+        Ok(Self::with_key_provider(input, size, key_provider))
+    }
+
+    /// 使用自定义密钥提供者创建 V4 读取器
+    ///
+    /// # Arguments
+    /// * `input` - 输入流
+    /// * `size` - 数据总大小
+    /// * `key_provider` - 可选的解密密钥提供者
+    ///
+    /// # Returns
+    /// 返回新创建的 FileReaderV4 实例
+    pub fn with_key_provider(
+        input: R,
+        size: u64,
+        key_provider: Option<Box<dyn KeyProvider>>,
+    ) -> Self {
+        Self::from_source(ReadSource::new(input), size, key_provider)
+    }
+}
 
-        Ok(Self {
+impl<S: ByteSource> FileReaderV4<S> {
+    /// 从字节来源创建 V4 读取器
+    ///
+    /// # Arguments
+    /// * `input` - 位于魔数和版本之后的字节来源
+    /// * `size` - 数据总大小
+    /// * `key_provider` - 可选的解密密钥提供者
+    ///
+    /// # Returns
+    /// 返回新创建的 FileReaderV4 实例
+    pub fn from_source(input: S, size: u64, key_provider: Option<Box<dyn KeyProvider>>) -> Self {
+        Self {
             input,
-            svr_pri_key: key,
-            svr_ec_pri_key,
-            position: 5,
+            key_provider,
+            position: 5, // 跳过魔数(4字节) + 版本(1字节)
             size,
             inflater: StatefulInflater::new(),
-            shared_key_cache: HashMap::new(),
-        })
+            zstd: StatefulZstdDecoder::new(),
+            proto_name: String::new(),
+            first_modes: None,
+            mismatched_marker: None,
+            skip_encrypted: false,
+            skipped_encrypted: 0,
+            max_log_length: SINGLE_LOG_CONTENT_MAX_LENGTH,
+            truncated_tail_bytes: 0,
+            last_entry: None,
+            framing_only: false,
+            raw: false,
+            entry_buf: Vec::with_capacity(SINGLE_LOG_CONTENT_MAX_LENGTH),
+            trial_buf: Vec::with_capacity(SINGLE_LOG_CONTENT_MAX_LENGTH),
+            read_buf: Vec::new(),
+            candidates: Zeroizing::new(Vec::new()),
+        }
     }
 
-    /// 计算日志存储大小
+    /// 设置单条日志的长度上限
+    ///
+    /// 长度字段超过上限的条目按损坏处理，默认为 [`SINGLE_LOG_CONTENT_MAX_LENGTH`]
     ///
     /// # Arguments
-    /// * `len` - 日志数据长度
-    /// * `cipher` - 是否加密
+    /// * `max_log_length` - 单条日志存储长度的上限（字节）
+    pub fn with_max_log_length(mut self, max_log_length: usize) -> Self {
+        self.max_log_length = max_log_length;
+        self
+    }
+
+    /// 使用指定的解压器，例如 [`StatefulInflater::from_pool`] 创建的共享上下文的解压器
     ///
-    /// # Returns
-    /// 返回包含所有字段的总存储大小
-    #[allow(dead_code)]
-    fn log_store_size(&self, len: usize, cipher: bool) -> usize {
-        // 模式(1) + 日志长度(2) + 日志数据 + 同步标记(8) + (如果加密: IV(16) + 压缩公��(33))
-        1 + 2 + len + 8 + if cipher { 16 + 33 } else { 0 }
+    /// 必须在读取任何条目之前调用
+    ///
+    /// # Arguments
+    /// * `inflater` - 尚未使用的解压器
+    pub fn with_inflater(mut self, inflater: StatefulInflater) -> Self {
+        self.inflater = inflater;
+        self
     }
 
-    /// 获取 ECDH 共享密钥（使用压缩公钥作为缓存 key）
+    /// 设置没有密钥时是否跳过加密条目
+    ///
+    /// 加密条目的存储长度可以从条目本身得到，跳过时不需要解密，明文条目照常返回。
+    /// 写入方在整个文件中共用一个压缩流，跳过压缩的加密条目后，
+    /// 后续压缩的明文条目可能因缺少字典而解压失败并触发恢复
     ///
     /// # Arguments
-    /// * `compressed_pub_key` - 压缩的客户端公钥（33字节）
+    /// * `skip` - 是否跳过加密条目
+    pub fn skip_encrypted(mut self, skip: bool) -> Self {
+        self.skip_encrypted = skip;
+        self
+    }
+
+    /// 跳过一条加密条目（模式字节已读取）
     ///
     /// # Returns
-    /// 返回共享密钥的字节数组
-    fn get_shared_key_cached(&mut self, compressed_pub_key: &[u8; 33]) -> Result<Vec<u8>> {
-        // 检查缓存
-        if let Some(cached_key) = self.shared_key_cache.get(compressed_pub_key) {
-            return Ok(cached_key.clone());
+    /// 成功跳过时返回 `None`；长度无效或同步标记不匹配时返回 `NeedRecover`
+    fn skip_encrypted_entry(&mut self) -> Result<Option<ReadResult>> {
+        let entry_start = self.position - 1;
+        if self.space_left() < (16 + 33 + 2) as u64 {
+            return self.truncated_entry(entry_start).map(Some);
         }
 
-        let svr_key = self.svr_ec_pri_key.as_ref()
-            .ok_or(GlogError::CipherNotReady)?;
+        // IV (16字节) + 压缩的客户端公钥 (33字节)
+        self.input.next_exact(16 + 33)?;
+        self.position += 16 + 33;
 
-        // 解压缩公钥
-        let client_pub_key = decompress_public_key(compressed_pub_key)?;
+        let log_length = self.read_log_length()?;
+        if log_length == 0 || log_length > self.max_log_length {
+            return Ok(Some(ReadResult::NeedRecover(
+                RecoverReason::InvalidLogLength(log_length),
+            )));
+        }
+        if self.space_left() < (log_length + 8) as u64 {
+            return self.truncated_entry(entry_start).map(Some);
+        }
 
-        // 将客户端公钥转换为 PublicKey
-        let client_ec_pub_key = prepare_client_pub_key(&client_pub_key)?;
+        skip_safely(&mut self.input, log_length)?;
+        self.position += log_length as u64;
 
-        // 执行 ECDH 密钥交换
-        let shared_secret = k256::ecdh::diffie_hellman(
-            svr_key.to_nonzero_scalar(),
-            client_ec_pub_key.as_affine()
-        );
+        let mut sync_marker = [0u8; 8];
+        sync_marker.copy_from_slice(self.input.next_exact(8)?);
+        self.position += 8;
+        if sync_marker != SYNC_MARKER {
+            self.mismatched_marker = Some(sync_marker);
+            return Ok(Some(ReadResult::NeedRecover(
+                RecoverReason::SyncMarkerMismatch,
+            )));
+        }
 
-        let key_bytes = shared_secret.raw_secret_bytes().to_vec();
+        Ok(None)
+    }
 
-        // 缓存结果
-        self.shared_key_cache.insert(*compressed_pub_key, key_bytes.clone());
+    /// 读取并验证条目末尾的同步标记，记录条目信息
+    ///
+    /// # Arguments
+    /// * `entry_start` - 条目的起始位置（模式字节）
+    /// * `modes` - 条目的压缩和加密模式
+    /// * `stored_len` - 存储的日志数据长度
+    /// * `client_pubkey` - 加密条目的客户端公钥
+    /// * `final_length` - 解压后的日志长度
+    fn finish_entry(
+        &mut self,
+        entry_start: u64,
+        (compress_mode, encrypt_mode): (CompressMode, EncryptMode),
+        stored_len: usize,
+        client_pubkey: Option<[u8; 33]>,
+        final_length: usize,
+    ) -> Result<ReadResult> {
+        let mut sync_marker = [0u8; 8];
+        sync_marker.copy_from_slice(self.input.next_exact(8)?);
+        self.position += 8;
+
+        if sync_marker != SYNC_MARKER {
+            self.mismatched_marker = Some(sync_marker);
+            return Ok(ReadResult::NeedRecover(RecoverReason::SyncMarkerMismatch));
+        }
 
-        Ok(key_bytes)
+        self.last_entry = Some(EntryInfo {
+            offset: entry_start,
+            entry_len: self.position - entry_start,
+            stored_len,
+            compressed: compress_mode != CompressMode::None,
+            compress_mode,
+            encrypted: encrypt_mode == EncryptMode::Aes,
+            client_pubkey,
+            len: final_length,
+        });
+        Ok(ReadResult::Success(final_length))
     }
 
-    /// 解密数据
+    /// 处理存储长度超出剩余数据的条目
     ///
-    /// 使用 ECDH 共享密钥和 AES-128-CFB 算法解密数据
+    /// 向前扫描剩余数据：没有同步标记说明文件在最后一条日志中间结束，按文件结束处理；
+    /// 找到同步标记说明条目损坏，停在该同步标记之后触发恢复
     ///
     /// # Arguments
-    /// * `compressed_pub_key` - 压缩的客户端公钥（33字节）
-    /// * `iv` - 初始化向量（16字节）
-    /// * `encrypt` - 加密的数据
+    /// * `entry_start` - 条目的起始位置（模式字节）
+    ///
+    /// # Returns
+    /// 返回 `ReadResult::Eof` 或 `ReadResult::NeedRecover`
+    fn truncated_entry(&mut self, entry_start: u64) -> Result<ReadResult> {
+        let limit = self.space_left();
+        let (consumed, found) = scan_for_sync_marker(&mut self.input, &[], limit)?;
+        self.position += consumed;
+        if found {
+            // 已经位于同步标记之后，`recover` 无需再扫描
+            self.mismatched_marker = Some(SYNC_MARKER);
+            return Ok(ReadResult::NeedRecover(RecoverReason::Truncated));
+        }
+        self.truncated_tail_bytes = self.position - entry_start;
+        Ok(ReadResult::Eof)
+    }
+
+    /// 计算日志存储大小
+    ///
+    /// # Arguments
+    /// * `len` - 日志数据长度
+    /// * `cipher` - 是否加密
     ///
     /// # Returns
-    /// 返回解密后的数据
-    fn decrypt(&mut self, compressed_pub_key: &[u8; 33], iv: &[u8], encrypt: &[u8]) -> Result<Vec<u8>> {
-        // 获取共享密钥（使用压缩公钥缓存）
-        let aes_key = self.get_shared_key_cached(compressed_pub_key)?;
-        
-        // 只使用前16字节作为 AES-128 密钥
-        let key_bytes: [u8; 16] = aes_key[..16]
-            .try_into()
-            .map_err(|_| GlogError::DecryptError("密钥长度错误".to_string()))?;
-        
-        let iv_bytes: [u8; 16] = iv
-            .try_into()
-            .map_err(|_| GlogError::DecryptError("IV 长度错误".to_string()))?;
-
-        // 创建 AES-CFB 解密器并解密数据
-        let mut plain = encrypt.to_vec();
-        let decryptor = Aes128CfbDec::new(&key_bytes.into(), &iv_bytes.into());
-        decryptor.decrypt(&mut plain);
-
-        Ok(plain)
+    /// 返回包含所有字段的总存储大小
+    #[allow(dead_code)]
+    fn log_store_size(&self, len: usize, cipher: bool) -> usize {
+        v4_entry_len(len, cipher)
+    }
+
+    /// 读取条目的日志长度字段
+    fn read_log_length(&mut self) -> Result<usize> {
+        let length = self.input.next_exact(2)?;
+        self.position += 2;
+        Ok(u16::from_le_bytes([length[0], length[1]]) as usize)
+    }
+
+    /// 把条目的存储数据读入复用的 `entry_buf`
+    ///
+    /// 加密条目需要原地解密，因此无论来源如何都复制一次
+    ///
+    /// # Arguments
+    /// * `len` - 存储的日志数据长度
+    fn read_entry_data(&mut self, len: usize) -> Result<()> {
+        self.entry_buf.resize(len, 0);
+        read_safely(&mut self.input, len, &mut self.entry_buf)?;
+        self.position += len as u64;
+        Ok(())
+    }
+
+    /// 原地解密 `entry_buf` 中的数据
+    ///
+    /// 使用密钥提供者给出的 AES 密钥和 AES-128-CFB 算法解密数据。
+    /// 提供者给出多个候选密钥时，在 `trial_buf` 中逐个试解密，
+    /// 以压缩数据的 SYNC_FLUSH 结尾验证解密结果，验证通过后与 `entry_buf` 交换
+    ///
+    /// # Arguments
+    /// * `compressed_pub_key` - 压缩的客户端公钥（33字节）
+    /// * `iv` - 初始化向量（16字节）
+    /// * `compress_mode` - 解密后数据的压缩模式
+    fn decrypt(
+        &mut self,
+        compressed_pub_key: &[u8; 33],
+        iv: &[u8; 16],
+        compress_mode: CompressMode,
+    ) -> Result<()> {
+        let provider = self.key_provider.as_ref().ok_or(GlogError::CipherNotReady)?;
+        self.candidates.zeroize();
+        provider.candidate_keys(compressed_pub_key, &mut self.candidates)?;
+
+        // 只有一个候选或无法验证时直接使用第一个密钥
+        if compress_mode != CompressMode::Zlib || self.candidates.len() <= 1 {
+            let key_bytes = self
+                .candidates
+                .first()
+                .ok_or_else(|| GlogError::DecryptError("没有可用的密钥".to_string()))?;
+            Aes128CfbDec::new(key_bytes.into(), iv.into()).decrypt(&mut self.entry_buf);
+            return Ok(());
+        }
+
+        for key_bytes in self.candidates.iter() {
+            self.trial_buf.clear();
+            self.trial_buf.extend_from_slice(&self.entry_buf);
+            Aes128CfbDec::new(key_bytes.into(), iv.into()).decrypt(&mut self.trial_buf);
+
+            if self.trial_buf.ends_with(&DEFLATE_SYNC_TRAILER) {
+                provider.confirm_key(compressed_pub_key, key_bytes);
+                std::mem::swap(&mut self.entry_buf, &mut self.trial_buf);
+                return Ok(());
+            }
+        }
+
+        Err(GlogError::DecryptError("没有可用的密钥".to_string()))
     }
 }
 
-impl<R: Read> FileReader for FileReaderV4<R> {
+impl<S: ByteSource> FileReader for FileReaderV4<S> {
     /// 读取剩余的文件头信息
     ///
     /// 解析协议名称长度、协议名称和同步标记
     fn read_remain_header(&mut self) -> Result<()> {
-        // 读取协议名称长度
-        let proto_name_len = read_u16_le(&mut self.input)?;
-        
-        // 读取协议名称
-        let mut name = vec![0u8; proto_name_len as usize];
-        read_safely(&mut self.input, proto_name_len as usize, &mut name)?;
-        let _proto_name = String::from_utf8_lossy(&name);
+        // 读取协议名称：魔数(4) + 版本(1) 之后是长度字段
+        let (proto_name, proto_name_len) =
+            read_proto_name(&mut self.input, 4 + 1, self.size, SYNC_MARKER.len())?;
+        self.proto_name = proto_name;
 
         // 读取并验证同步标记
         let mut sync_marker = [0u8; 8];
@@ -269,135 +479,217 @@ impl<R: Read> FileReader for FileReaderV4<R> {
     /// 读取下一条日志
     ///
     /// # Arguments
-    /// * `out_buf` - 输出缓冲区
+    /// * `out` - 输出缓冲区
     ///
     /// # Returns
     /// 返回读取结果
-    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
-        // 检查是否有足够的数据（最小需要: 模式(1) + 长度(2) + 同步标记(8)）
-        if self.space_left() < (1 + 2 + 8) as u64 {
-            return Ok(ReadResult::Eof);
-        }
+    fn read_to_vec(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.mismatched_marker = None;
+        self.last_entry = None;
+        out.clear();
 
-        // 读取模式设置字节
-        let mut ms_buf = [0u8; 1];
-        read_safely(&mut self.input, 1, &mut ms_buf)?;
-        let ms = ms_buf[0];
-
-        // 解析压缩模式 (高4位)
-        let compress_mode = match ms >> 4 {
-            1 => CompressMode::None,
-            2 => CompressMode::Zlib,
-            _ => {
-                // eprintln!("非法压缩模式: {}", ms >> 4);
-                return Ok(ReadResult::NeedRecover(-2));
+        let (compress_mode, encrypt_mode) = loop {
+            // 检查是否有足够的数据（最小需要: 模式(1) + 长度(2) + 同步标记(8)），
+            // 不足时剩余数据是不完整的最后一条日志
+            if self.space_left() < (1 + 2 + 8) as u64 {
+                if self.space_left() > 0 {
+                    self.truncated_tail_bytes = self.space_left();
+                }
+                return Ok(ReadResult::Eof);
             }
-        };
 
-        // 解析加密模式 (低4位)
-        let encrypt_mode = match ms & 0x0F {
-            1 => EncryptMode::None,
-            2 => EncryptMode::Aes,
-            _ => {
-                // eprintln!("非法加密模式: {}", ms & 0x0F);
-                return Ok(ReadResult::NeedRecover(-3));
-            }
-        };
+            // 读取模式设置字节
+            let ms = self.input.next_exact(1)?[0];
+            self.position += 1;
 
-        // info!("压缩模式: {:?}, 加密模式: {:?}", compress_mode, encrypt_mode);
+            // 解析压缩模式 (高4位)
+            let compress_mode = match ms >> 4 {
+                1 => CompressMode::None,
+                2 => CompressMode::Zlib,
+                3 => CompressMode::Zstd,
+                _ => {
+                    return Ok(ReadResult::NeedRecover(RecoverReason::IllegalCompressMode(
+                        ms >> 4,
+                    )));
+                }
+            };
 
-        // 如果需要解密但没有密钥，返回错误
-        if encrypt_mode == EncryptMode::Aes && self.svr_pri_key.is_none() {
-            return Err(GlogError::CipherNotReady);
-        }
+            // 解析加密模式 (低4位)
+            let encrypt_mode = match ms & 0x0F {
+                1 => EncryptMode::None,
+                2 => EncryptMode::Aes,
+                _ => {
+                    return Ok(ReadResult::NeedRecover(RecoverReason::IllegalEncryptMode(
+                        ms & 0x0F,
+                    )));
+                }
+            };
+
+            // info!("压缩模式: {:?}, 加密模式: {:?}", compress_mode, encrypt_mode);
+            self.first_modes.get_or_insert((compress_mode, encrypt_mode));
+
+            // 需要解密但没有密钥时，按配置跳过该条目或返回错误；
+            // 只验证结构时未压缩的加密条目不需要解密
+            let skippable = self.framing_only && compress_mode == CompressMode::None;
+            if encrypt_mode == EncryptMode::Aes && self.key_provider.is_none() && !skippable {
+                if !self.skip_encrypted {
+                    return Err(GlogError::CipherNotReady);
+                }
+                if let Some(result) = self.skip_encrypted_entry()? {
+                    return Ok(result);
+                }
+                self.skipped_encrypted += 1;
+                continue;
+            }
 
-        self.position += 1;
+            break (compress_mode, encrypt_mode);
+        };
 
+        let entry_start = self.position - 1;
+        let stored_len;
+        let mut client_pubkey = None;
         let final_length = if encrypt_mode == EncryptMode::Aes {
-            // 读取 IV (16字节)
-            let mut iv = [0u8; 16];
-            read_safely(&mut self.input, 16, &mut iv)?;
+            if self.space_left() < (16 + 33 + 2) as u64 {
+                return self.truncated_entry(entry_start);
+            }
 
-            // 读取压缩的客户端公钥 (33字节)
+            // 读取 IV (16字节) 和压缩的客户端公钥 (33字节)
+            let header = self.input.next_exact(16 + 33)?;
+            let mut iv = [0u8; 16];
+            iv.copy_from_slice(&header[..16]);
             let mut compressed_pub_key = [0u8; 33];
-            read_safely(&mut self.input, 33, &mut compressed_pub_key)?;
-
+            compressed_pub_key.copy_from_slice(&header[16..]);
             self.position += 16 + 33;
 
             // 读取日志长度
-            let log_length = read_u16_le(&mut self.input)? as usize;
+            let log_length = self.read_log_length()?;
             // info!("日志长度: {}", log_length);
 
-            if log_length == 0 || log_length > SINGLE_LOG_CONTENT_MAX_LENGTH {
-                eprintln!("无效的日志长度: {}", log_length);
-                std::io::stderr().flush().unwrap();
-                return Ok(ReadResult::NeedRecover(-4));
+            if log_length == 0 || log_length > self.max_log_length {
+                return Ok(ReadResult::NeedRecover(RecoverReason::InvalidLogLength(
+                    log_length,
+                )));
+            }
+            if self.space_left() < (log_length + 8) as u64 {
+                return self.truncated_entry(entry_start);
             }
 
-            self.position += 2 + log_length as u64;
+            stored_len = log_length;
+            client_pubkey = Some(compressed_pub_key);
+
+            // 只验证结构时，未压缩的数据无需解密，直接丢弃
+            if self.framing_only && compress_mode == CompressMode::None {
+                skip_safely(&mut self.input, log_length)?;
+                self.position += log_length as u64;
+                return self.finish_entry(
+                    entry_start,
+                    (compress_mode, encrypt_mode),
+                    stored_len,
+                    client_pubkey,
+                    log_length,
+                );
+            }
 
             // 读取加密的日志数据
-            let mut buf = vec![0u8; log_length];
-            read_safely(&mut self.input, log_length, &mut buf)?;
-
-            // 解密数据（直接使用压缩公钥）
-            let plain = match self.decrypt(&compressed_pub_key, &iv, &buf) {
-                Ok(p) => p,
-                Err(_) => {
-                    eprintln!("解密失败");
-                    std::io::stderr().flush().unwrap();
-                    return Ok(ReadResult::NeedRecover(-5));
-                }
-            };
+            self.read_entry_data(log_length)?;
 
-            // 根据压缩模式处理数据
-            match compress_mode {
-                CompressMode::Zlib => self.inflater.decompress(&plain, out_buf)?,
-                CompressMode::None => {
-                    let copy_len = plain.len().min(out_buf.len());
-                    out_buf[..copy_len].copy_from_slice(&plain[..copy_len]);
-                    copy_len
-                }
+            // 原地解密数据（直接使用压缩公钥）
+            if self.decrypt(&compressed_pub_key, &iv, compress_mode).is_err() {
+                return Ok(ReadResult::NeedRecover(RecoverReason::DecryptFailed));
+            }
+
+            let emitted = if self.raw {
+                emit_raw(compress_mode, &self.entry_buf, out)
+            } else {
+                emit_entry(
+                    &mut self.inflater,
+                    &mut self.zstd,
+                    compress_mode,
+                    &self.entry_buf,
+                    out,
+                )
+            };
+            match emitted {
+                Ok(len) => len,
+                Err(reason) => return Ok(ReadResult::NeedRecover(reason)),
             }
         } else {
             // 非加密模式
-            let log_length = read_u16_le(&mut self.input)? as usize;
+            let log_length = self.read_log_length()?;
 
-            if log_length == 0 || log_length > SINGLE_LOG_CONTENT_MAX_LENGTH {
-                eprintln!("无效的日志长度: {}", log_length);
-                std::io::stderr().flush().unwrap();
-                return Ok(ReadResult::NeedRecover(-6));
+            if log_length == 0 || log_length > self.max_log_length {
+                return Ok(ReadResult::NeedRecover(RecoverReason::InvalidLogLength(
+                    log_length,
+                )));
             }
+            if self.space_left() < (log_length + 8) as u64 {
+                return self.truncated_entry(entry_start);
+            }
+
+            stored_len = log_length;
 
-            self.position += 2 + log_length as u64;
+            // 只验证结构时，未压缩的数据直接丢弃
+            if self.framing_only && compress_mode == CompressMode::None {
+                skip_safely(&mut self.input, log_length)?;
+                self.position += log_length as u64;
+                return self.finish_entry(
+                    entry_start,
+                    (compress_mode, encrypt_mode),
+                    stored_len,
+                    None,
+                    log_length,
+                );
+            }
 
-            // 读取日志数据
-            let mut buf = vec![0u8; log_length];
-            read_safely(&mut self.input, log_length, &mut buf)?;
+            // 读取日志数据（内存中的来源不复制）
+            let data = self.input.next_exact(log_length)?;
+            self.position += log_length as u64;
 
-            // 根据压缩模式处理数据
-            match compress_mode {
-                CompressMode::Zlib => self.inflater.decompress(&buf, out_buf)?,
-                CompressMode::None => {
-                    let copy_len = log_length.min(out_buf.len());
-                    out_buf[..copy_len].copy_from_slice(&buf[..copy_len]);
-                    copy_len
-                }
+            let emitted = if self.raw {
+                emit_raw(compress_mode, data, out)
+            } else {
+                emit_entry(&mut self.inflater, &mut self.zstd, compress_mode, data, out)
+            };
+            match emitted {
+                Ok(len) => len,
+                Err(reason) => return Ok(ReadResult::NeedRecover(reason)),
             }
         };
 
-        // 读取并验证同步标记
-        let mut sync_marker = [0u8; 8];
-        read_safely(&mut self.input, 8, &mut sync_marker)?;
+        self.finish_entry(
+            entry_start,
+            (compress_mode, encrypt_mode),
+            stored_len,
+            client_pubkey,
+            final_length,
+        )
+    }
 
-        if sync_marker != SYNC_MARKER {
-            eprintln!("同步标记不匹配");
-            std::io::stderr().flush().unwrap();
-            return Ok(ReadResult::NeedRecover(-7));
-        }
-        self.position += 8;
+    fn read(&mut self, out_buf: &mut [u8]) -> Result<ReadResult> {
+        let mut entry = std::mem::take(&mut self.read_buf);
+        let result = self.read_to_vec(&mut entry);
+        let result = copy_entry(result, &entry, out_buf);
+        self.read_buf = entry;
+        result
+    }
 
-        Ok(ReadResult::Success(final_length))
+    fn skip_entry(&mut self, scratch: &mut Vec<u8>) -> Result<ReadResult> {
+        self.framing_only = true;
+        let result = self.read_to_vec(scratch);
+        self.framing_only = false;
+        result
+    }
+
+    fn read_raw(&mut self, out: &mut Vec<u8>) -> Result<ReadResult> {
+        self.raw = true;
+        let result = self.read_to_vec(out);
+        self.raw = false;
+        result
+    }
+
+    /// 最近一次成功读取的日志的存储信息
+    fn last_entry(&self) -> Option<EntryInfo> {
+        self.last_entry.clone()
     }
 
     /// 获取当前读取位置
@@ -406,6 +698,7 @@ impl<R: Read> FileReader for FileReaderV4<R> {
     }
 
     /// 获取剩余可读取的字节数
+    #[allow(clippy::implicit_saturating_sub)]
     fn space_left(&self) -> u64 {
         if self.size <= self.position {
             0
@@ -413,57 +706,139 @@ impl<R: Read> FileReader for FileReaderV4<R> {
             self.size - self.position
         }
     }
+
+    /// 获取数据总大小
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 更新数据总大小
+    fn set_size(&mut self, size: u64) {
+        self.size = size;
+    }
+
+    /// 从损坏处恢复读取
+    ///
+    /// 同步标记只有个别字节损坏时直接从当前位置继续；
+    /// 否则逐字节向前扫描到下一个同步标记之后，并重置解压器（字典已经失效）
+    fn recover(&mut self) -> Result<bool> {
+        let window = match self.mismatched_marker.take() {
+            Some(marker) if is_damaged_sync_marker(&marker) => return Ok(true),
+            Some(marker) => marker.to_vec(),
+            None => Vec::new(),
+        };
+
+        let limit = self.space_left();
+        let (consumed, found) = scan_for_sync_marker(&mut self.input, &window, limit)?;
+        self.position += consumed;
+        self.inflater.reset_keep_buffers();
+        self.zstd.reset();
+        Ok(found)
+    }
+
+    /// 从指定的条目边界继续读取
+    ///
+    /// 之前的压缩字典已经丢失，因此重置解压器
+    fn resume_at(&mut self, position: u64) -> Result<()> {
+        self.position = position;
+        self.mismatched_marker = None;
+        self.inflater.reset_keep_buffers();
+        self.zstd.reset();
+        Ok(())
+    }
+
+    /// 获取文件元数据
+    ///
+    /// 压缩和加密模式取自第一条日志
+    fn metadata(&self) -> GlogMetadata {
+        GlogMetadata {
+            version: GLOG_CIPHER_VERSION,
+            proto_name: self.proto_name.clone(),
+            compress_mode: self.first_modes.map_or(CompressMode::None, |(c, _)| c),
+            encrypt_mode: self.first_modes.map(|(_, e)| e),
+            file_size: self.size,
+            skipped_encrypted: self.skipped_encrypted,
+            truncated_tail_bytes: self.truncated_tail_bytes,
+            key_cache: self.key_provider.as_ref().and_then(|p| p.cache_stats()),
+            deflate_wrapper: self.inflater.wrapper(),
+        }
+    }
 }
 
-/// 准备服务器私钥
-///
-/// 将十六进制字符串格式的私钥转换为 EC 私钥
+/// 根据压缩模式把条目数据（已解密）输出到 `out`
 ///
 /// # Arguments
-/// * `svr_pri_key` - 十六进制格式的私钥字符串
+/// * `inflater` - 有状态的 zlib 解压器
+/// * `zstd` - 有状态的 zstd 解压器
+/// * `compress_mode` - 数据的压缩模式
+/// * `data` - 条目的存储数据
+/// * `out` - 输出缓冲区
 ///
 /// # Returns
-/// 返回 SecretKey
-fn prepare_svr_pri_key(svr_pri_key: &str) -> Result<SecretKey> {
-    // 将十六进制字符串转换为字节数组
-    let key_bytes = hex::decode(svr_pri_key)?;
-    
-    // 创建 SecretKey
-    SecretKey::from_slice(&key_bytes)
-        .map_err(|e| GlogError::EllipticCurveError(format!("无效的私钥: {}", e)))
+/// 成功返回日志长度；解压失败时返回 [`RecoverReason::DecompressFailed`]
+fn emit_entry(
+    inflater: &mut StatefulInflater,
+    zstd: &mut StatefulZstdDecoder,
+    compress_mode: CompressMode,
+    data: &[u8],
+    out: &mut Vec<u8>,
+) -> std::result::Result<usize, RecoverReason> {
+    match compress_mode {
+        CompressMode::Zlib => decompress_entry(inflater, data, out),
+        CompressMode::Zstd => zstd
+            .decompress(data, out)
+            .map_err(|_| RecoverReason::DecompressFailed),
+        CompressMode::None => {
+            out.extend_from_slice(data);
+            Ok(data.len())
+        }
+    }
 }
 
-/// 准备客户端公钥
+/// 不解压，把条目数据（已解密）原样输出到 `out`
 ///
-/// 将64字节的原始公钥数据转换为 EC 公钥
+/// zlib 数据同样要求以 SYNC_FLUSH 结尾，以便发现解密出的乱码
 ///
 /// # Arguments
-/// * `client_pub_key` - 64字节的公钥数据（X和Y坐标各32字节）
+/// * `compress_mode` - 数据的压缩模式
+/// * `data` - 条目的存储数据
+/// * `out` - 输出缓冲区
 ///
 /// # Returns
-/// 返回 PublicKey
-fn prepare_client_pub_key(client_pub_key: &[u8]) -> Result<PublicKey> {
-    if client_pub_key.len() != 64 {
-        return Err(GlogError::PublicKeyDecompressError(
-            format!("公钥长度错误: 期望64字节，实际{}字节", client_pub_key.len())
-        ));
+/// 成功返回数据长度；zlib 数据缺少 SYNC_FLUSH 结尾时返回 [`RecoverReason::DecompressFailed`]
+fn emit_raw(
+    compress_mode: CompressMode,
+    data: &[u8],
+    out: &mut Vec<u8>,
+) -> std::result::Result<usize, RecoverReason> {
+    if compress_mode == CompressMode::Zlib && !data.ends_with(&DEFLATE_SYNC_TRAILER) {
+        return Err(RecoverReason::DecompressFailed);
     }
+    out.extend_from_slice(data);
+    Ok(data.len())
+}
 
-    // 构造未压缩格式的公钥（0x04 + X + Y）
-    let mut uncompressed = vec![0x04u8];
-    uncompressed.extend_from_slice(client_pub_key);
-
-    // 解析公钥
-    let encoded_point = k256::EncodedPoint::from_bytes(&uncompressed)
-        .map_err(|e| GlogError::PublicKeyDecompressError(format!("编码点解析失败: {}", e)))?;
-    
-    let pub_key = PublicKey::from_encoded_point(&encoded_point);
-    
-    if pub_key.is_some().into() {
-        Ok(pub_key.unwrap())
-    } else {
-        Err(GlogError::PublicKeyDecompressError("无效的公钥点".to_string()))
+/// 解压单条日志
+///
+/// # Arguments
+/// * `inflater` - 有状态的 zlib 解压器
+/// * `data` - 压缩数据
+/// * `out` - 输出缓冲区
+///
+/// # Returns
+/// 成功返回解压后的长度；数据缺少 SYNC_FLUSH 结尾或解压失败时返回 [`RecoverReason::DecompressFailed`]
+fn decompress_entry(
+    inflater: &mut StatefulInflater,
+    data: &[u8],
+    out: &mut Vec<u8>,
+) -> std::result::Result<usize, RecoverReason> {
+    if !data.ends_with(&DEFLATE_SYNC_TRAILER) {
+        return Err(RecoverReason::DecompressFailed);
     }
+    inflater
+        .decompress(data, out)
+        .map(|(_, len)| len)
+        .map_err(|_| RecoverReason::DecompressFailed)
 }
 
 /// 解压缩公钥
@@ -510,13 +885,151 @@ pub fn decompress_public_key(compressed_key: &[u8]) -> Result<Vec<u8>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aes::cipher::AsyncStreamCipher;
+    use cfb_mode::Encryptor;
+    use flate2::{Compress, Compression, FlushCompress};
+    use k256::SecretKey;
+    use std::io::Cursor;
+
+    const SVR_KEY: &str = "1C74B66FCB1C54FD4386173CFAF3BC53C8DF6B89F799DE1A1E7CEBBC43CBFD38";
+
+    /// 构造 V4 文件（从协议名称长度开始，魔数和版本号由调用方跳过）
+    fn v4_body(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&3u16.to_le_bytes());
+        out.extend_from_slice(b"Log");
+        out.extend_from_slice(&SYNC_MARKER);
+        for entry in entries {
+            out.extend_from_slice(entry);
+        }
+        out
+    }
+
+    /// 使用共享字典的 raw deflate 压缩（每条日志 SYNC_FLUSH）
+    fn deflate(compressor: &mut Compress, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 64);
+        compressor
+            .compress_vec(data, &mut out, FlushCompress::Sync)
+            .unwrap();
+        out
+    }
+
+    /// 构造一条 zlib 压缩 + AES 加密的日志条目
+    fn encrypted_entry(compressed: &[u8], iv: [u8; 16]) -> Vec<u8> {
+        let svr_pub = SecretKey::from_slice(&hex::decode(SVR_KEY).unwrap()).unwrap().public_key();
+        let client = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let shared = k256::ecdh::diffie_hellman(client.to_nonzero_scalar(), svr_pub.as_affine());
+        let key: [u8; 16] = shared.raw_secret_bytes()[..16].try_into().unwrap();
+
+        let mut cipher = compressed.to_vec();
+        Encryptor::<Aes128>::new(&key.into(), &iv.into()).encrypt(&mut cipher);
+
+        let mut out = vec![0x22]; // 高 4 位：zlib，低 4 位：AES
+        out.extend_from_slice(&iv);
+        out.extend_from_slice(client.public_key().to_encoded_point(true).as_bytes());
+        out.extend_from_slice(&(cipher.len() as u16).to_le_bytes());
+        out.extend_from_slice(&cipher);
+        out.extend_from_slice(&SYNC_MARKER);
+        out
+    }
+
+    /// 读取全部条目，返回成功读取的内容以及 NeedRecover 的原因
+    fn read_all(body: Vec<u8>) -> (Vec<Vec<u8>>, Vec<RecoverReason>) {
+        let size = 5 + body.len() as u64;
+        let mut reader =
+            FileReaderV4::from_reader(Cursor::new(body), size, Some(SVR_KEY.to_string())).unwrap();
+        reader.read_remain_header().unwrap();
+
+        let mut buf = vec![0u8; SINGLE_LOG_CONTENT_MAX_LENGTH];
+        let (mut logs, mut reasons) = (Vec::new(), Vec::new());
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                ReadResult::Success(len) => logs.push(buf[..len].to_vec()),
+                ReadResult::NeedRecover(reason) => {
+                    reasons.push(reason);
+                    reader.recover().unwrap();
+                }
+                ReadResult::Eof => break,
+            }
+        }
+        assert_eq!(reader.position(), size);
+        (logs, reasons)
+    }
 
     #[test]
-    fn test_prepare_svr_pri_key() {
-        // 测试有效的私钥
-        let key = "1C74B66FCB1C54FD4386173CFAF3BC53C8DF6B89F799DE1A1E7CEBBC43CBFD38";
-        let result = prepare_svr_pri_key(key);
-        assert!(result.is_ok());
+    fn test_recover_after_corrupted_iv() {
+        let mut compressor = Compress::new(Compression::default(), false);
+        let payloads: [&[u8]; 3] = [b"alpha", b"bravo", b"charlie"];
+        let mut entries: Vec<Vec<u8>> = payloads
+            .iter()
+            .map(|p| encrypted_entry(&deflate(&mut compressor, p), [0x42; 16]))
+            .collect();
+        // 改写第二条日志的 IV
+        for b in &mut entries[1][1..17] {
+            *b ^= 0x5A;
+        }
+
+        let (logs, reasons) = read_all(v4_body(&entries));
+        assert_eq!(reasons, [RecoverReason::DecompressFailed]);
+        assert_eq!(logs, [b"alpha".to_vec(), b"charlie".to_vec()]);
+    }
+
+    #[test]
+    fn test_recover_after_corrupted_length() {
+        let mut compressor = Compress::new(Compression::default(), false);
+        let mut entries: Vec<Vec<u8>> = [b"alpha".as_slice(), b"bravo", b"charlie"]
+            .iter()
+            .map(|p| encrypted_entry(&deflate(&mut compressor, p), [0x42; 16]))
+            .collect();
+        // 第二条日志的长度字段改为超出上限的值
+        entries[1][50..52].copy_from_slice(&u16::MAX.to_le_bytes());
+
+        let (logs, reasons) = read_all(v4_body(&entries));
+        assert_eq!(
+            reasons,
+            [RecoverReason::InvalidLogLength(u16::MAX as usize)]
+        );
+        assert_eq!(logs, [b"alpha".to_vec(), b"charlie".to_vec()]);
+    }
+
+    /// 忽略客户端公钥、总是返回固定密钥的提供者
+    struct FixedKeyProvider([u8; 16]);
+
+    impl KeyProvider for FixedKeyProvider {
+        fn shared_key(&self, _client_pubkey: &[u8; 33]) -> Result<[u8; 16]> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn test_custom_key_provider() {
+        let key = [0x24; 16];
+        let iv = [0x42; 16];
+        let mut cipher = b"from hsm".to_vec();
+        Encryptor::<Aes128>::new(&key.into(), &iv.into()).encrypt(&mut cipher);
+
+        let mut entry = vec![0x12]; // 高 4 位：无压缩，低 4 位：AES
+        entry.extend_from_slice(&iv);
+        entry.extend_from_slice(&[0x02; 33]);
+        entry.extend_from_slice(&(cipher.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&cipher);
+        entry.extend_from_slice(&SYNC_MARKER);
+
+        let body = v4_body(&[entry]);
+        let size = 5 + body.len() as u64;
+        let mut reader = FileReaderV4::with_key_provider(
+            Cursor::new(body),
+            size,
+            Some(Box::new(FixedKeyProvider(key))),
+        );
+        reader.read_remain_header().unwrap();
+
+        let mut buf = vec![0u8; SINGLE_LOG_CONTENT_MAX_LENGTH];
+        match reader.read(&mut buf).unwrap() {
+            ReadResult::Success(len) => assert_eq!(&buf[..len], b"from hsm"),
+            other => panic!("意外的读取结果: {:?}", other),
+        }
+        assert!(matches!(reader.read(&mut buf).unwrap(), ReadResult::Eof));
     }
 
     #[test]
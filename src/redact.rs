@@ -0,0 +1,182 @@
+//! # 敏感信息脱敏
+//!
+//! 日志离开分析机器之前不能包含手机号、令牌等敏感信息。[`Redactor`] 按顺序对日志的
+//! 标签和内容应用一组正则替换。通过 [`GlogReaderBuilder::redactor`](crate::glog::GlogReaderBuilder::redactor)
+//! 交给读取器后，每条日志在解码完成时立即替换，之后的过滤、格式化和各种输出看到的都是替换后的内容，
+//! 替换次数记入 [`ReadStats::redactions`](crate::observer::ReadStats::redactions)。
+
+use regex::{Captures, Regex};
+
+use crate::error::{GlogError, Result};
+use crate::proto::Log;
+
+/// 一条脱敏规则
+#[derive(Debug, Clone)]
+struct Rule {
+    /// 匹配敏感信息的正则表达式
+    pattern: Regex,
+    /// 替换文本，支持 `$1`、`${name}` 引用捕获组
+    replacement: String,
+}
+
+/// 日志脱敏器
+///
+/// ```
+/// use clog_reader::proto::Log;
+/// use clog_reader::redact::Redactor;
+///
+/// let redactor = Redactor::new()
+///     .rule(r"1[3-9]\d{9}", "<phone>")?
+///     .parse_rule(r"token\=\w+=token=***")?;
+/// let mut log = Log {
+///     msg: "call 13800138000 with token=abc".to_string(),
+///     ..Log::new()
+/// };
+/// assert_eq!(redactor.redact(&mut log), 2);
+/// assert_eq!(log.msg, "call <phone> with token=***");
+/// # Ok::<(), clog_reader::GlogError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    /// 按添加顺序应用的规则
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// 创建没有规则的脱敏器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一条规则
+    ///
+    /// # Arguments
+    /// * `pattern` - 正则表达式
+    /// * `replacement` - 替换文本，支持 `$1`、`${name}` 引用捕获组，`$$` 表示字面的 `$`
+    ///
+    /// # Errors
+    /// 正则表达式无效时返回 [`GlogError::RedactRuleError`]
+    pub fn rule(mut self, pattern: &str, replacement: impl Into<String>) -> Result<Self> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| GlogError::RedactRuleError(format!("{}: {}", pattern, e)))?;
+        self.rules.push(Rule {
+            pattern,
+            replacement: replacement.into(),
+        });
+        Ok(self)
+    }
+
+    /// 解析 `<正则>=<替换文本>` 形式的规则并添加，用于命令行的 `--redact`
+    ///
+    /// 在第一个未转义的 `=` 处分割；正则中需要匹配 `=` 时写作 `\=`
+    ///
+    /// # Arguments
+    /// * `spec` - 规则文本，如 `1[3-9]\d{9}=<phone>`
+    ///
+    /// # Errors
+    /// 缺少 `=`、正则为空或无效时返回 [`GlogError::RedactRuleError`]
+    pub fn parse_rule(self, spec: &str) -> Result<Self> {
+        let (pattern, replacement) = split_rule(spec).ok_or_else(|| {
+            GlogError::RedactRuleError(format!("{}: 格式应为 <正则>=<替换文本>", spec))
+        })?;
+        self.rule(pattern, replacement)
+    }
+
+    /// 是否没有任何规则
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 对日志的标签和内容依次应用所有规则
+    ///
+    /// # Arguments
+    /// * `log` - 要脱敏的日志，原地修改
+    ///
+    /// # Returns
+    /// 返回替换的次数
+    pub fn redact(&self, log: &mut Log) -> u64 {
+        self.redact_str(&mut log.tag) + self.redact_str(&mut log.msg)
+    }
+
+    /// 对一个字符串依次应用所有规则，返回替换的次数
+    fn redact_str(&self, text: &mut String) -> u64 {
+        let mut count = 0;
+        for rule in &self.rules {
+            let mut matched = 0;
+            let replaced = rule.pattern.replace_all(text, |caps: &Captures| {
+                matched += 1;
+                let mut out = String::new();
+                caps.expand(&rule.replacement, &mut out);
+                out
+            });
+            if matched > 0 {
+                *text = replaced.into_owned();
+                count += matched;
+            }
+        }
+        count
+    }
+}
+
+/// 在第一个未转义的 `=` 处分割规则文本，正则部分不能为空
+fn split_rule(spec: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '=' if !escaped => {
+                return (i > 0).then(|| (&spec[..i], &spec[i + 1..]));
+            }
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(tag: &str, msg: &str) -> Log {
+        Log {
+            tag: tag.to_string(),
+            msg: msg.to_string(),
+            ..Log::new()
+        }
+    }
+
+    #[test]
+    fn test_redacts_tag_and_msg_in_rule_order() {
+        let redactor = Redactor::new()
+            .parse_rule(r"1[3-9]\d{9}=<phone>")
+            .unwrap()
+            .parse_rule(r"(?P<k>token|key)\=\w+=${k}=***")
+            .unwrap()
+            .parse_rule("<phone>=[removed]")
+            .unwrap();
+        let mut sensitive = log("user-13800138000", "token=abc key=x9 13900000000");
+
+        assert_eq!(redactor.redact(&mut sensitive), 6);
+        assert_eq!(sensitive.tag, "user-[removed]");
+        assert_eq!(sensitive.msg, "token=*** key=*** [removed]");
+
+        let mut clean = log("Net", "nothing here");
+        assert_eq!(redactor.redact(&mut clean), 0);
+        assert_eq!(clean.msg, "nothing here");
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert_eq!(split_rule(r"a\=b=c=d"), Some((r"a\=b", "c=d")));
+        assert_eq!(split_rule(r"a\\=b"), Some((r"a\\", "b")));
+        assert_eq!(split_rule("a="), Some(("a", "")));
+        for spec in ["no-separator", "=x", r"only\=escaped", "(=x"] {
+            let err = Redactor::new().parse_rule(spec).unwrap_err();
+            assert!(
+                matches!(err, GlogError::RedactRuleError(_)),
+                "{spec}: {err}"
+            );
+        }
+        assert!(Redactor::new().is_empty());
+    }
+}
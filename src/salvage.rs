@@ -0,0 +1,219 @@
+//! # 文件头损坏时的日志抢救
+//!
+//! 文件头被覆盖（例如前几 KB 被清零）时读取器无法识别版本，整个文件都无法打开，
+//! 但其后的条目通常完好。[`salvage`] 用 [`find_sync_markers`] 找到每个同步标记，
+//! 依次尝试把标记之后的数据当作 V4、V3（zlib）和 V3（无压缩）的条目流解码，
+//! 保留解出日志最多的一种，从它停止的位置继续查找下一个同步标记。
+//!
+//! V3 zlib 的压缩流在整个文件中共享解压器状态，从中间开始解压时引用了之前数据的条目无法还原，
+//! 这些条目会被跳过；zstd 压缩流只有开头带有帧头，无法从中间开始解压，因此不作为候选格式。
+
+use std::io::{self, Cursor, Read};
+use std::sync::Arc;
+
+use crate::glog::GlogReader;
+use crate::proto::Log;
+use crate::reader::{find_sync_markers, CompressMode, MAGIC_NUMBER, SYNC_MARKER};
+use crate::version::{GLOG_CIPHER_VERSION, GLOG_RECOVERY_VERSION};
+
+/// 一段成功解码的连续条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct SalvageRun {
+    /// 第一条日志在文件中的偏移
+    pub start: u64,
+    /// 最后一条日志之后的偏移
+    pub end: u64,
+    /// 按哪种格式解码（`GLOG_CIPHER_VERSION` 或 `GLOG_RECOVERY_VERSION`）
+    pub version: u8,
+    /// V3 文件头中的压缩模式（V4 的压缩模式记录在每条日志中，为 `CompressMode::None`）
+    pub compress_mode: CompressMode,
+    /// 解出的日志条数
+    pub logs: usize,
+}
+
+/// 抢救结果
+#[derive(Debug, Default)]
+pub struct SalvageReport {
+    /// 解出的日志及其条目在文件中的偏移，按偏移排列
+    pub logs: Vec<(u64, Log)>,
+    /// 文件中同步标记的个数
+    pub markers: usize,
+    /// 各段成功解码的连续条目
+    pub runs: Vec<SalvageRun>,
+}
+
+/// 尝试的条目格式：（版本, V3 文件头中的压缩模式）
+const CANDIDATES: [(u8, CompressMode); 3] = [
+    (GLOG_CIPHER_VERSION, CompressMode::None),
+    (GLOG_RECOVERY_VERSION, CompressMode::Zlib),
+    (GLOG_RECOVERY_VERSION, CompressMode::None),
+];
+
+/// 从文件中抢救日志，不依赖文件头
+///
+/// # Arguments
+/// * `file_path` - 日志文件路径
+/// * `key` - 可选的服务器私钥（十六进制字符串），用于 V4 加密条目
+///
+/// # Errors
+/// 文件无法读取时返回错误；没有解出任何日志不是错误
+#[cfg(feature = "std-fs")]
+pub fn salvage(file_path: &str, key: Option<String>) -> crate::error::Result<SalvageReport> {
+    let data = std::fs::read(file_path)?;
+    Ok(salvage_bytes(&data, key))
+}
+
+/// 从内存中的文件内容抢救日志，参见 [`salvage`]
+///
+/// # Arguments
+/// * `data` - 完整的文件内容
+/// * `key` - 可选的服务器私钥（十六进制字符串）
+pub fn salvage_bytes(data: &[u8], key: Option<String>) -> SalvageReport {
+    let shared: Arc<[u8]> = Arc::from(data);
+    let mut report = SalvageReport::default();
+    let mut resume = 0u64;
+    for offset in find_sync_markers(data) {
+        report.markers += 1;
+        if offset < resume {
+            continue;
+        }
+        // 条数相同时保留先尝试的格式
+        let mut best: Option<(SalvageRun, Vec<(u64, Log)>)> = None;
+        for &(version, compress_mode) in &CANDIDATES {
+            let (run, logs) = decode_run(&shared, offset, version, compress_mode, key.clone());
+            if run.logs > best.as_ref().map_or(0, |(best, _)| best.logs) {
+                best = Some((run, logs));
+            }
+        }
+        if let Some((run, logs)) = best {
+            resume = run.end;
+            report.logs.extend(logs);
+            report.runs.push(run);
+        }
+    }
+    report
+}
+
+/// 从 `offset` 开始按指定格式解码条目，直到第一个错误
+///
+/// 在数据前拼接一个合成的文件头，用严格模式的读取器解码，日志偏移换算回原文件中的偏移
+///
+/// # Returns
+/// 返回这段条目的信息及解出的日志
+fn decode_run(
+    data: &Arc<[u8]>,
+    offset: u64,
+    version: u8,
+    compress_mode: CompressMode,
+    key: Option<String>,
+) -> (SalvageRun, Vec<(u64, Log)>) {
+    let mut run = SalvageRun {
+        start: offset,
+        end: offset,
+        version,
+        compress_mode,
+        logs: 0,
+    };
+    let header = synthetic_header(version, compress_mode);
+    let header_len = header.len() as u64;
+    let size = header_len + data.len() as u64 - offset;
+    let stream = Cursor::new(header).chain(Tail {
+        data: Arc::clone(data),
+        pos: offset as usize,
+    });
+
+    let mut builder = GlogReader::builder().strict(true);
+    if let Some(key) = key {
+        builder = builder.key(key);
+    }
+    let Ok(mut reader) = builder.from_reader(stream, size) else {
+        return (run, Vec::new());
+    };
+
+    let to_file_offset = |position: u64| offset + position - header_len;
+    let mut logs = Vec::new();
+    let mut buf = Vec::new();
+    let mut done = false;
+    while let Some(Ok((entry_offset, log))) = reader.next_log(&mut buf, None, &mut done) {
+        if logs.is_empty() {
+            run.start = to_file_offset(entry_offset);
+        }
+        run.end = to_file_offset(reader.position());
+        logs.push((to_file_offset(entry_offset), log));
+    }
+    run.logs = logs.len();
+    (run, logs)
+}
+
+/// 从指定位置读取共享的文件内容，避免每次尝试都复制剩余数据
+struct Tail {
+    /// 完整的文件内容
+    data: Arc<[u8]>,
+    /// 下一个要读取的位置
+    pos: usize,
+}
+
+impl Read for Tail {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = (&self.data[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 构造协议名称为空的文件头
+fn synthetic_header(version: u8, compress_mode: CompressMode) -> Vec<u8> {
+    let mut header = MAGIC_NUMBER.to_vec();
+    header.push(version);
+    if version == GLOG_RECOVERY_VERSION {
+        // 高 4 位为压缩模式，低 4 位为加密模式（无加密）
+        header.push(match compress_mode {
+            CompressMode::None => 0x00,
+            CompressMode::Zlib => 0x10,
+            CompressMode::Zstd => 0x20,
+        });
+    }
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&SYNC_MARKER);
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::EncryptMode;
+    use crate::writer::v4::GlogWriterV4;
+    use prost::Message;
+
+    #[test]
+    fn test_salvage_v4_after_destroyed_header_and_corrupt_entry() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log").unwrap();
+        // 文件头：魔数(4) + 版本(1) + 协议名称长度(2) + "Log" + 同步标记(8)
+        let mut offsets = vec![18u64];
+        for i in 0..6 {
+            let payload = Log {
+                msg: format!("entry {}", i),
+                ..Log::new()
+            }
+            .encode_to_vec();
+            writer
+                .write_entry(&payload, CompressMode::None, EncryptMode::None)
+                .unwrap();
+            // 模式(1) + 长度(2) + 数据 + 同步标记(8)
+            offsets.push(offsets[i] + 1 + 2 + payload.len() as u64 + 8);
+        }
+        let mut data = writer.into_inner().unwrap();
+        // 破坏文件头和第一条日志，以及第四条日志的长度字段
+        data[..offsets[0] as usize + 4].fill(0);
+        let fourth = offsets[3] as usize;
+        data[fourth + 1..fourth + 3].copy_from_slice(&0xFFFFu16.to_le_bytes());
+
+        let report = salvage_bytes(&data, None);
+        let msgs: Vec<&str> = report.logs.iter().map(|(_, log)| log.msg.as_str()).collect();
+        assert_eq!(msgs, ["entry 1", "entry 2", "entry 4", "entry 5"]);
+        assert_eq!(report.logs[0].0, offsets[1]);
+        assert_eq!(report.runs.len(), 2);
+        assert_eq!(report.runs[0].version, GLOG_CIPHER_VERSION);
+        assert_eq!(report.runs[1].start, offsets[4]);
+    }
+}
@@ -0,0 +1,174 @@
+//! # 文本流
+//!
+//! 有些工具只接受纯文本的 [`io::Read`]。[`GlogTextStream`] 包装一个 [`GlogReader`]，
+//! 按需解码日志并格式化为一行文本（默认使用 [`Log::format`]），调用方每次 `read`
+//! 可以只取走一行中的一部分。无法解码的日志输出为以 `#` 开头的注释行。
+
+use std::io::{self, Read};
+
+use crate::glog::GlogReader;
+use crate::proto::Log;
+
+/// 单行格式化函数
+type Formatter = Box<dyn Fn(&Log) -> String>;
+
+/// 把日志读取器转换为纯文本输入流
+///
+/// 每条日志输出为一行（以 `\n` 结尾）；protobuf 解码失败的日志和读取错误输出为
+/// `# 错误: ...` 注释行。读取错误之后流结束，与 [`Logs`](crate::glog::Logs) 相同
+pub struct GlogTextStream {
+    /// 底层读取器
+    reader: GlogReader,
+    /// 单行格式化函数
+    formatter: Formatter,
+    /// 单条日志缓冲区
+    buf: Vec<u8>,
+    /// 当前行中尚未被读取的文本
+    line: Vec<u8>,
+    /// `line` 中下一个要输出的字节
+    pos: usize,
+    /// 底层读取器是否已读完
+    done: bool,
+}
+
+impl GlogTextStream {
+    /// 创建文本流，每条日志按读取器的 [`GlogReader::formatter`] 格式化
+    ///
+    /// 未设置类型名称表时与 [`Log::format`] 相同
+    ///
+    /// # Arguments
+    /// * `reader` - 日志读取器
+    pub fn new(reader: GlogReader) -> Self {
+        let formatter = reader.formatter();
+        Self::with_formatter(reader, move |log| log.format_with(&formatter))
+    }
+
+    /// 创建文本流，每条日志按给定的函数格式化
+    ///
+    /// # Arguments
+    /// * `reader` - 日志读取器
+    /// * `formatter` - 把一条日志格式化为一行文本的函数，返回值不需要包含换行符
+    pub fn with_formatter(
+        reader: GlogReader,
+        formatter: impl Fn(&Log) -> String + 'static,
+    ) -> Self {
+        Self {
+            reader,
+            formatter: Box::new(formatter),
+            buf: Vec::new(),
+            line: Vec::new(),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    /// 取回底层读取器
+    pub fn into_inner(self) -> GlogReader {
+        self.reader
+    }
+
+    /// 解码下一条日志并格式化到 `line`
+    ///
+    /// # Returns
+    /// 读取器已读完时返回 `false`
+    fn next_line(&mut self) -> bool {
+        let text = match self.reader.next_log(&mut self.buf, None, &mut self.done) {
+            Some(Ok((_, log))) => (self.formatter)(&log),
+            Some(Err(e)) => format!("# 错误: {}", e),
+            None => return false,
+        };
+        self.line.clear();
+        self.line.extend_from_slice(text.as_bytes());
+        self.line.push(b'\n');
+        self.pos = 0;
+        true
+    }
+}
+
+impl Read for GlogTextStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.line.len() && !self.next_line() {
+            return Ok(0);
+        }
+        let n = (&self.line[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::CompressMode;
+    use crate::writer::v3::GlogWriterV3;
+    use std::io::{BufRead, BufReader, Cursor};
+
+    fn log(msg: &str) -> Log {
+        Log {
+            log_type: 1,
+            timestamp: "1700000000000".to_string(),
+            pid: 42,
+            tid: "main".to_string(),
+            tag: "app".to_string(),
+            msg: msg.to_string(),
+            ..Log::new()
+        }
+    }
+
+    fn reader_for(data: Vec<u8>) -> GlogReader {
+        let size = data.len() as u64;
+        GlogReader::from_reader(Cursor::new(data), size, None).unwrap()
+    }
+
+    #[test]
+    fn test_lines_match_direct_formatting() {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+        let logs: Vec<Log> = (0..50)
+            .map(|i| log(&format!("第 {} 条 {}", i, "x".repeat(i))))
+            .collect();
+        for log in &logs {
+            writer.write_log(log).unwrap();
+        }
+        let data = writer.into_inner().unwrap();
+
+        let expected: Vec<String> = logs.iter().map(Log::format).collect();
+        let lines: Vec<String> = BufReader::new(GlogTextStream::new(reader_for(data.clone())))
+            .lines()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, expected);
+
+        // 每次只读 3 字节，多字节字符和行都会被拆开
+        let mut stream = GlogTextStream::with_formatter(reader_for(data), |log| log.msg.clone());
+        let mut out = Vec::new();
+        let mut chunk = [0u8; 3];
+        loop {
+            let n = stream.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&chunk[..n]);
+        }
+        let msgs: Vec<&str> = logs.iter().map(|log| log.msg.as_str()).collect();
+        assert_eq!(String::from_utf8(out).unwrap(), msgs.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_decode_failure_becomes_comment_line() {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+        writer.write_log(&log("before")).unwrap();
+        writer.write(&[0xFF, 0xFF]).unwrap();
+        writer.write_log(&log("after")).unwrap();
+        let data = writer.into_inner().unwrap();
+
+        let stream = GlogTextStream::with_formatter(reader_for(data), |log| log.msg.clone());
+        let lines: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "before");
+        assert!(lines[1].starts_with("# 错误: "), "{}", lines[1]);
+        assert_eq!(lines[2], "after");
+    }
+}
@@ -3,16 +3,14 @@
 //! 本模块定义了 Glog 文件格式的版本号常量。
 //! 不同版本的 Glog 文件具有不同的格式和特性。
 
-/// Glog 初始版本
+/// Glog 初始版本 (V1)
 /// 
-/// @deprecated 已废弃的初始版本
-#[allow(dead_code)]
+/// @deprecated 已废弃的初始版本，没有同步标记
 pub const GLOG_INITIAL_VERSION: u8 = 0x01;
 
-/// Glog 修复位置版本
+/// Glog 修复位置版本 (V2)
 /// 
-/// @deprecated 修复了错误的初始写入位置问题
-#[allow(dead_code)]
+/// @deprecated 修复了错误的初始写入位置问题，没有同步标记
 pub const GLOG_FIX_POSITION_VERSION: u8 = 0x02;
 
 /// Glog 恢复版本 (V3)
@@ -0,0 +1,88 @@
+//! # WebAssembly 接口
+//!
+//! 通过 `wasm-bindgen` 向 JavaScript 暴露解码核心，用于在浏览器中直接查看 Glog 日志。
+//! 需要关闭默认的 `std-fs` 特性并启用 `wasm` 特性构建：
+//!
+//! ```text
+//! wasm-pack build --target web --no-default-features --features wasm
+//! ```
+//!
+//! ```js
+//! const reader = GlogWasmReader.fromBytes(new Uint8Array(buffer), keyHex);
+//! for (let log; (log = reader.nextLog()) !== null; ) {
+//!     console.log(new Date(Number(log.timestamp)), log.level, log.tag, log.msg);
+//! }
+//! // 或者每次取出一批，减少跨越边界的调用
+//! for (let batch; (batch = reader.nextBatch(1000)).length > 0; ) {
+//!     batch.forEach((log) => console.log(log.tag, log.msg));
+//! }
+//! ```
+
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::glog::GlogReader;
+use crate::proto::Log;
+
+/// 面向 JavaScript 的 Glog 读取器
+///
+/// 持有整个文件内容，逐条解码日志
+#[wasm_bindgen]
+pub struct GlogWasmReader {
+    /// 底层读取器
+    reader: GlogReader,
+    /// 条目缓冲区
+    buf: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl GlogWasmReader {
+    /// 从文件内容创建读取器
+    ///
+    /// # Arguments
+    /// * `bytes` - 完整的 Glog 文件内容（JavaScript 中为 `Uint8Array`）
+    /// * `key` - 可选的服务器私钥（十六进制字符串）
+    ///
+    /// # Errors
+    /// 文件头无效或私钥格式错误时抛出异常
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8], key: Option<String>) -> Result<GlogWasmReader, JsError> {
+        let size = bytes.len() as u64;
+        let reader = GlogReader::from_reader(Cursor::new(bytes.to_vec()), size, key)?;
+        Ok(Self {
+            reader,
+            buf: Vec::new(),
+        })
+    }
+
+    /// 读取下一条日志
+    ///
+    /// # Returns
+    /// 返回日志对象 `{ type, timestamp, level, pid, tid, tag, msg }`，文件结束时返回 `null`
+    ///
+    /// # Errors
+    /// 条目无法解码时抛出异常，捕获后可以继续读取下一条
+    #[wasm_bindgen(js_name = nextLog)]
+    pub fn next_log(&mut self) -> Result<JsValue, JsError> {
+        self.buf.clear();
+        if self.reader.read_entry(&mut self.buf)?.is_none() {
+            return Ok(JsValue::NULL);
+        }
+        let log = Log::decode_from(&self.buf)?;
+        Ok(serde_wasm_bindgen::to_value(&log)?)
+    }
+
+    /// 一次读取最多 `max` 条日志，减少跨越 JavaScript 边界的次数
+    ///
+    /// 无法解码的条目被跳过；批次中途遇到读取错误时先返回已读出的日志，下一次调用时抛出异常
+    ///
+    /// # Returns
+    /// 返回日志对象数组，文件结束时返回空数组
+    #[wasm_bindgen(js_name = nextBatch)]
+    pub fn next_batch(&mut self, max: usize) -> Result<JsValue, JsError> {
+        let logs = self.reader.read_batch(max)?;
+        Ok(serde_wasm_bindgen::to_value(&logs)?)
+    }
+}
+
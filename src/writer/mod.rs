@@ -0,0 +1,164 @@
+//! # 文件写入器基础模块
+//!
+//! 本模块提供生成 Glog 文件的写入器，主要用于构造测试数据，
+//! 格式与 [`crate::reader`] 中的读取器一一对应。
+//!
+//! ## 重要说明
+//!
+//! 与读取端的 `StatefulInflater` 对应，压缩使用一个贯穿整个文件的 raw deflate 流：
+//! 每条日志写入后执行 `Z_SYNC_FLUSH`，使该条日志能被独立读出，同时保留压缩字典。
+//! zstd 模式与此相同，由 `StatefulZstdEncoder` 在每条日志后执行 `ZSTD_e_flush`。
+
+pub mod v3;
+pub mod v4;
+
+use flate2::{Compress, Compression, FlushCompress};
+
+use crate::error::{GlogError, Result};
+
+/// 有状态的 Raw Deflate 压缩器
+///
+/// 模拟 Java 端 jzlib Deflater 的行为：
+/// - 使用 raw deflate 格式（无 zlib/gzip 头部）
+/// - 在多条日志之间共享压缩字典
+/// - 每条日志以 SYNC_FLUSH 结束，输出以空存储块 `00 00 FF FF` 结尾
+pub struct StatefulDeflater {
+    /// flate2 的底层压缩器
+    compressor: Compress,
+}
+
+impl StatefulDeflater {
+    /// 创建新的有状态压缩器
+    pub fn new() -> Self {
+        // false 表示 raw deflate（无 zlib 头部），与读取端保持一致
+        Self {
+            compressor: Compress::new(Compression::default(), false),
+        }
+    }
+
+    /// 压缩一条日志
+    ///
+    /// # Arguments
+    /// * `data` - 原始数据
+    ///
+    /// # Returns
+    /// 返回以 SYNC_FLUSH 结束的压缩数据
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() + 64);
+        let mut consumed = 0;
+        loop {
+            if out.capacity() - out.len() < 64 {
+                out.reserve(out.capacity().max(64));
+            }
+            let before_in = self.compressor.total_in();
+            self.compressor
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|e| GlogError::CompressError(e.to_string()))?;
+            consumed += (self.compressor.total_in() - before_in) as usize;
+
+            // 输入已全部消费且输出缓冲区仍有空间，说明 SYNC_FLUSH 已经完成
+            if consumed == data.len() && out.len() < out.capacity() {
+                return Ok(out);
+            }
+        }
+    }
+}
+
+impl Default for StatefulDeflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 有状态的 Zstd 流式压缩器
+///
+/// 与读取端的 `StatefulZstdDecoder` 对应：文件中所有 zstd 条目组成一个连续的帧，
+/// 每条日志以 `ZSTD_e_flush` 结束，使该条日志能被独立读出，同时保留压缩窗口。
+/// 未启用 `zstd` 功能时压缩总是返回 `CompressError`
+#[derive(Default)]
+pub struct StatefulZstdEncoder {
+    /// zstd 的底层压缩上下文，第一次压缩时创建
+    #[cfg(feature = "zstd")]
+    encoder: Option<zstd::stream::raw::Encoder<'static>>,
+}
+
+impl StatefulZstdEncoder {
+    /// 创建新的有状态压缩器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 压缩一条日志
+    ///
+    /// # Arguments
+    /// * `data` - 原始数据
+    ///
+    /// # Returns
+    /// 返回以 `ZSTD_e_flush` 结束的压缩数据
+    #[cfg(feature = "zstd")]
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        use zstd::stream::raw::{Encoder, InBuffer, Operation, OutBuffer};
+
+        let to_error = |e: std::io::Error| GlogError::CompressError(e.to_string());
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => self
+                .encoder
+                .insert(Encoder::new(zstd::DEFAULT_COMPRESSION_LEVEL).map_err(to_error)?),
+        };
+
+        let mut out = Vec::with_capacity(data.len() + 64);
+        let mut input = InBuffer::around(data);
+        loop {
+            if out.len() == out.capacity() {
+                out.reserve(out.capacity().max(64));
+            }
+            let pos = out.len();
+            let mut output = OutBuffer::around_pos(&mut out, pos);
+            // 输入全部送入后持续 flush，直到压缩器内部没有待输出的数据
+            let remaining = if input.pos() < data.len() {
+                encoder.run(&mut input, &mut output).map_err(to_error)?;
+                1
+            } else {
+                encoder.flush(&mut output).map_err(to_error)?
+            };
+            if remaining == 0 {
+                return Ok(out);
+            }
+        }
+    }
+
+    /// 未启用 `zstd` 功能，无法压缩
+    ///
+    /// # Errors
+    /// 总是返回 `CompressError`
+    #[cfg(not(feature = "zstd"))]
+    pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let _ = data;
+        Err(GlogError::CompressError("未启用 zstd 功能".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::StatefulInflater;
+
+    #[test]
+    fn test_compress_round_trip_with_shared_dictionary() {
+        let mut deflater = StatefulDeflater::new();
+        let mut inflater = StatefulInflater::new();
+        let mut out = Vec::new();
+
+        let first = deflater.compress(b"hello glog hello glog").unwrap();
+        let second = deflater.compress(b"hello glog again").unwrap();
+        assert!(first.ends_with(&[0x00, 0x00, 0xFF, 0xFF]));
+        assert!(second.ends_with(&[0x00, 0x00, 0xFF, 0xFF]));
+
+        let (_, len) = inflater.decompress(&first, &mut out).unwrap();
+        assert_eq!(&out[..len], b"hello glog hello glog");
+        out.clear();
+        let (_, len) = inflater.decompress(&second, &mut out).unwrap();
+        assert_eq!(&out[..len], b"hello glog again");
+    }
+}
@@ -0,0 +1,206 @@
+//! # Glog V3 版本文件写入器
+//!
+//! 按 [`crate::reader::v3`] 描述的格式写出文件头，以及每条日志的
+//! `长度(2) + 数据 + 同步标记(8)`。V3 不支持加密，加密模式固定为无加密。
+
+use std::io::Write;
+
+use prost::Message;
+
+use super::{StatefulDeflater, StatefulZstdEncoder};
+use crate::error::{GlogError, Result};
+use crate::glog::GlogReader;
+use crate::proto::Log;
+use crate::reader::{CompressMode, MAGIC_NUMBER, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER};
+use crate::version::GLOG_RECOVERY_VERSION;
+
+/// V3 版本文件写入器
+pub struct GlogWriterV3<W: Write> {
+    /// 输出流
+    output: W,
+    /// 文件头中的压缩模式
+    compress_mode: CompressMode,
+    /// 有状态的 zlib 压缩器
+    deflater: StatefulDeflater,
+    /// 有状态的 zstd 压缩器
+    zstd: StatefulZstdEncoder,
+}
+
+impl<W: Write> GlogWriterV3<W> {
+    /// 创建写入器并写出文件头
+    ///
+    /// # Arguments
+    /// * `output` - 输出流
+    /// * `compress_mode` - 压缩模式
+    /// * `proto_name` - 协议名称
+    ///
+    /// # Returns
+    /// 返回新创建的 GlogWriterV3 实例
+    pub fn new(mut output: W, compress_mode: CompressMode, proto_name: &str) -> Result<Self> {
+        // 模式设置：高 4 位为压缩模式，低 4 位为加密模式（固定为无加密）
+        let mode = match compress_mode {
+            CompressMode::None => 0x00,
+            CompressMode::Zlib => 0x10,
+            CompressMode::Zstd => 0x20,
+        };
+
+        output.write_all(&MAGIC_NUMBER)?;
+        output.write_all(&[GLOG_RECOVERY_VERSION, mode])?;
+        output.write_all(&(proto_name.len() as u16).to_le_bytes())?;
+        output.write_all(proto_name.as_bytes())?;
+        output.write_all(&SYNC_MARKER)?;
+
+        Ok(Self {
+            output,
+            compress_mode,
+            deflater: StatefulDeflater::new(),
+            zstd: StatefulZstdEncoder::new(),
+        })
+    }
+
+    /// 写入一条原始日志数据
+    ///
+    /// # Arguments
+    /// * `data` - 日志数据（压缩前）
+    ///
+    /// # Errors
+    /// 写入文件的数据长度为 0 或超过 [`SINGLE_LOG_CONTENT_MAX_LENGTH`] 时返回 `InvalidLogLength`
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        let compressed;
+        let payload = match self.compress_mode {
+            CompressMode::Zlib => {
+                compressed = self.deflater.compress(data)?;
+                &compressed[..]
+            }
+            CompressMode::Zstd => {
+                compressed = self.zstd.compress(data)?;
+                &compressed[..]
+            }
+            CompressMode::None => data,
+        };
+        if payload.is_empty() || payload.len() > SINGLE_LOG_CONTENT_MAX_LENGTH {
+            return Err(GlogError::InvalidLogLength(payload.len()));
+        }
+
+        self.output
+            .write_all(&(payload.len() as u16).to_le_bytes())?;
+        self.output.write_all(payload)?;
+        self.output.write_all(&SYNC_MARKER)?;
+        Ok(())
+    }
+
+    /// 编码并写入一条日志
+    ///
+    /// # Arguments
+    /// * `log` - 日志消息
+    pub fn write_log(&mut self, log: &Log) -> Result<()> {
+        self.write(&log.encode_to_vec())
+    }
+
+    /// 把读取器中剩余的日志逐条写入
+    ///
+    /// 写入的是解密、解压后的原始 protobuf 数据，顺序不变；无法解码的条目被跳过，
+    /// 与恢复事件一样按读取器的恢复策略和观察者报告。可用于把加密的 V4 文件
+    /// 转换为不需要私钥即可读取的 V3 文件
+    ///
+    /// # Arguments
+    /// * `reader` - 日志读取器
+    ///
+    /// # Returns
+    /// 返回写入的日志条数
+    ///
+    /// # Errors
+    /// 读取错误（包括 `Abort` 策略下的 `NeedRecover`）和写入错误
+    pub fn copy_from(&mut self, reader: &mut GlogReader) -> Result<u64> {
+        let mut buf = Vec::new();
+        let mut written = 0;
+        while let Some(entry) = reader.read_entry(&mut buf)? {
+            let data = &buf[..entry.len];
+            if data.is_empty() || reader.decode_log(data).is_err() {
+                continue;
+            }
+            self.write(data)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// 刷新输出流
+    pub fn flush(&mut self) -> Result<()> {
+        self.output.flush()?;
+        Ok(())
+    }
+
+    /// 刷新并取回输出流
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ReadResult;
+    use crate::reader::v3::FileReaderV3;
+    use crate::reader::FileReader;
+    use std::io::Cursor;
+
+    fn log(i: usize) -> Log {
+        Log {
+            timestamp: format!("{}", 1700000000000u64 + i as u64),
+            tag: "Main".to_string(),
+            msg: format!("message {}", i),
+            ..Log::default()
+        }
+    }
+
+    /// 写入 `n` 条日志后使用 FileReaderV3 读回
+    fn round_trip(compress_mode: CompressMode, n: usize) -> Vec<Log> {
+        let mut writer = GlogWriterV3::new(Vec::new(), compress_mode, "Log").unwrap();
+        for i in 0..n {
+            writer.write_log(&log(i)).unwrap();
+        }
+        let bytes = writer.into_inner().unwrap();
+
+        // 魔数和版本号由 GlogReader 读取，这里直接跳过
+        let size = bytes.len() as u64;
+        let mut reader = FileReaderV3::from_reader(Cursor::new(bytes[5..].to_vec()), size);
+        reader.read_remain_header().unwrap();
+        assert_eq!(reader.metadata().compress_mode, compress_mode);
+
+        let mut buf = vec![0u8; SINGLE_LOG_CONTENT_MAX_LENGTH];
+        let mut logs = Vec::new();
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                ReadResult::Success(len) => logs.push(Log::decode_from(&buf[..len]).unwrap()),
+                ReadResult::Eof => break,
+                ReadResult::NeedRecover(reason) => panic!("意外的 NeedRecover: {}", reason),
+            }
+        }
+        assert_eq!(reader.position(), size);
+        logs
+    }
+
+    #[test]
+    fn test_round_trip_uncompressed() {
+        let logs = round_trip(CompressMode::None, 100);
+        assert_eq!(logs, (0..100).map(log).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_round_trip_compressed() {
+        let logs = round_trip(CompressMode::Zlib, 1000);
+        assert_eq!(logs, (0..1000).map(log).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_rejects_oversized_entry() {
+        let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::None, "Log").unwrap();
+        let data = vec![0u8; SINGLE_LOG_CONTENT_MAX_LENGTH + 1];
+        assert!(matches!(
+            writer.write(&data),
+            Err(GlogError::InvalidLogLength(len)) if len == data.len()
+        ));
+    }
+}
@@ -0,0 +1,445 @@
+//! # Glog V4 版本文件写入器
+//!
+//! 按 [`crate::reader::v4`] 描述的格式写出文件头和日志条目。
+//! 加密条目的写入流程与读取端相反：先压缩，再使用 ECDH 共享密钥的前 16 字节
+//! 作为 AES-128-CFB 密钥、随机生成的 16 字节 IV 加密，最后写出
+//! `模式(1) + IV(16) + 客户端压缩公钥(33) + 长度(2) + 密文 + 同步标记(8)`。
+//! [`copy_raw_from`](GlogWriterV4::copy_raw_from) 则把读取器解密出的压缩数据原样写入不加密的条目，
+//! 不经过解压和重新压缩。
+
+use std::io::Write;
+
+use aes::cipher::AsyncStreamCipher;
+use aes::Aes128;
+use cfb_mode::cipher::KeyIvInit;
+use cfb_mode::Encryptor;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{PublicKey, SecretKey};
+use prost::Message;
+use rand_core::{OsRng, RngCore};
+
+use super::{StatefulDeflater, StatefulZstdEncoder};
+use crate::error::{GlogError, Result};
+use crate::glog::GlogReader;
+use crate::proto::Log;
+use crate::reader::{
+    CompressMode, EncryptMode, MAGIC_NUMBER, SINGLE_LOG_CONTENT_MAX_LENGTH, SYNC_MARKER,
+};
+use crate::version::GLOG_CIPHER_VERSION;
+
+/// AES CFB 加密器类型别名
+type Aes128CfbEnc = Encryptor<Aes128>;
+
+/// 客户端临时密钥的轮换方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyRotation {
+    /// 整个文件使用同一个客户端密钥（默认）
+    #[default]
+    PerFile,
+    /// 每条加密日志生成新的客户端密钥
+    PerEntry,
+}
+
+/// 客户端临时密钥
+struct ClientKey {
+    /// 压缩格式的客户端公钥
+    compressed_pub_key: [u8; 33],
+    /// AES-128 密钥（ECDH 共享密钥的前 16 字节）
+    aes_key: [u8; 16],
+}
+
+impl ClientKey {
+    /// 生成新的客户端密钥并与服务器公钥协商 AES 密钥
+    fn generate(server_key: &PublicKey) -> Self {
+        let secret = SecretKey::random(&mut OsRng);
+        let shared = k256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), server_key.as_affine());
+
+        let mut compressed_pub_key = [0u8; 33];
+        compressed_pub_key.copy_from_slice(secret.public_key().to_encoded_point(true).as_bytes());
+        let mut aes_key = [0u8; 16];
+        aes_key.copy_from_slice(&shared.raw_secret_bytes()[..16]);
+
+        Self {
+            compressed_pub_key,
+            aes_key,
+        }
+    }
+}
+
+/// V4 版本文件写入器
+pub struct GlogWriterV4<W: Write> {
+    /// 输出流
+    output: W,
+    /// 有状态的压缩器（所有 zlib 条目共享）
+    deflater: StatefulDeflater,
+    /// 有状态的 zstd 压缩器（所有 zstd 条目共享）
+    zstd: StatefulZstdEncoder,
+    /// 服务器公钥（`None` 表示不加密）
+    server_key: Option<PublicKey>,
+    /// 客户端密钥轮换方式
+    rotation: KeyRotation,
+    /// 当前使用的客户端密钥
+    client_key: Option<ClientKey>,
+}
+
+impl<W: Write> GlogWriterV4<W> {
+    /// 创建写入器并写出文件头
+    ///
+    /// 默认以 zlib 压缩、不加密的方式写入日志，
+    /// 调用 [`with_server_key`](Self::with_server_key) 后默认加密
+    ///
+    /// # Arguments
+    /// * `output` - 输出流
+    /// * `proto_name` - 协议名称
+    ///
+    /// # Returns
+    /// 返回新创建的 GlogWriterV4 实例
+    pub fn new(mut output: W, proto_name: &str) -> Result<Self> {
+        output.write_all(&MAGIC_NUMBER)?;
+        output.write_all(&[GLOG_CIPHER_VERSION])?;
+        output.write_all(&(proto_name.len() as u16).to_le_bytes())?;
+        output.write_all(proto_name.as_bytes())?;
+        output.write_all(&SYNC_MARKER)?;
+
+        Ok(Self {
+            output,
+            deflater: StatefulDeflater::new(),
+            zstd: StatefulZstdEncoder::new(),
+            server_key: None,
+            rotation: KeyRotation::default(),
+            client_key: None,
+        })
+    }
+
+    /// 设置服务器公钥，启用加密
+    ///
+    /// # Arguments
+    /// * `server_public_key` - SEC1 编码（压缩或未压缩格式）的服务器公钥
+    pub fn with_server_key(mut self, server_public_key: &[u8]) -> Result<Self> {
+        let key = PublicKey::from_sec1_bytes(server_public_key)
+            .map_err(|e| GlogError::EllipticCurveError(format!("无效的服务器公钥: {}", e)))?;
+        self.server_key = Some(key);
+        self.client_key = None;
+        Ok(self)
+    }
+
+    /// 设置客户端密钥的轮换方式
+    ///
+    /// # Arguments
+    /// * `rotation` - 轮换方式
+    pub fn with_key_rotation(mut self, rotation: KeyRotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// 按默认模式写入一条原始日志数据
+    ///
+    /// 使用 zlib 压缩；设置了服务器公钥时同时加密
+    ///
+    /// # Arguments
+    /// * `data` - 日志数据（压缩前）
+    pub fn write(&mut self, data: &[u8]) -> Result<()> {
+        let encrypt_mode = match self.server_key {
+            Some(_) => EncryptMode::Aes,
+            None => EncryptMode::None,
+        };
+        self.write_entry(data, CompressMode::Zlib, encrypt_mode)
+    }
+
+    /// 按指定的压缩和加密模式写入一条原始日志数据
+    ///
+    /// # Arguments
+    /// * `data` - 日志数据（压缩前）
+    /// * `compress_mode` - 压缩模式
+    /// * `encrypt_mode` - 加密模式
+    ///
+    /// # Errors
+    /// 需要加密但未设置服务器公钥时返回 `CipherNotReady`；
+    /// 写入文件的数据长度为 0 或超过 [`SINGLE_LOG_CONTENT_MAX_LENGTH`] 时返回 `InvalidLogLength`
+    pub fn write_entry(
+        &mut self,
+        data: &[u8],
+        compress_mode: CompressMode,
+        encrypt_mode: EncryptMode,
+    ) -> Result<()> {
+        let payload = match compress_mode {
+            CompressMode::Zlib => self.deflater.compress(data)?,
+            CompressMode::Zstd => self.zstd.compress(data)?,
+            CompressMode::None => data.to_vec(),
+        };
+        self.write_payload(payload, compress_mode, encrypt_mode)
+    }
+
+    /// 把已经压缩的存储数据原样写入一条不加密的条目
+    ///
+    /// 压缩条目共用一个压缩流：`data` 必须按原文件中的顺序逐条写入（如来自
+    /// [`GlogReader::read_raw`]），且不能与本写入器压缩的条目混用，否则读取时无法解压
+    ///
+    /// # Arguments
+    /// * `data` - 条目的存储数据（已压缩、未加密）
+    /// * `compress_mode` - 数据的压缩模式
+    ///
+    /// # Errors
+    /// 数据长度为 0 或超过 [`SINGLE_LOG_CONTENT_MAX_LENGTH`] 时返回 `InvalidLogLength`
+    pub fn write_raw_entry(&mut self, data: &[u8], compress_mode: CompressMode) -> Result<()> {
+        self.write_payload(data.to_vec(), compress_mode, EncryptMode::None)
+    }
+
+    /// 按需加密存储数据，写出完整的条目
+    fn write_payload(
+        &mut self,
+        mut payload: Vec<u8>,
+        compress_mode: CompressMode,
+        encrypt_mode: EncryptMode,
+    ) -> Result<()> {
+        if payload.is_empty() || payload.len() > SINGLE_LOG_CONTENT_MAX_LENGTH {
+            return Err(GlogError::InvalidLogLength(payload.len()));
+        }
+
+        // 模式设置：高 4 位为压缩模式，低 4 位为加密模式
+        let compress_bits = match compress_mode {
+            CompressMode::None => 0x10,
+            CompressMode::Zlib => 0x20,
+            CompressMode::Zstd => 0x30,
+        };
+        let encrypt_bits = match encrypt_mode {
+            EncryptMode::None => 0x01,
+            EncryptMode::Aes => 0x02,
+        };
+        let mut entry = vec![compress_bits | encrypt_bits];
+
+        if encrypt_mode == EncryptMode::Aes {
+            let client_key = self.client_key()?;
+            let (compressed_pub_key, aes_key) = (client_key.compressed_pub_key, client_key.aes_key);
+
+            let mut iv = [0u8; 16];
+            OsRng.fill_bytes(&mut iv);
+            Aes128CfbEnc::new(&aes_key.into(), &iv.into()).encrypt(&mut payload);
+
+            entry.extend_from_slice(&iv);
+            entry.extend_from_slice(&compressed_pub_key);
+        }
+
+        entry.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&payload);
+        entry.extend_from_slice(&SYNC_MARKER);
+        self.output.write_all(&entry)?;
+        Ok(())
+    }
+
+    /// 编码并按默认模式写入一条日志
+    ///
+    /// # Arguments
+    /// * `log` - 日志消息
+    pub fn write_log(&mut self, log: &Log) -> Result<()> {
+        self.write(&log.encode_to_vec())
+    }
+
+    /// 把读取器中剩余的日志逐条写入
+    ///
+    /// 每条日志保持原条目的压缩模式，顺序不变；设置了服务器公钥时全部加密，
+    /// 否则全部不加密。配合读取端的旧私钥，可以把文件迁移到新的服务器密钥，
+    /// 解密后的数据只在内存中经过。无法解码的条目被跳过，与恢复事件一样按读取器的恢复策略和观察者报告
+    ///
+    /// # Arguments
+    /// * `reader` - 日志读取器
+    ///
+    /// # Returns
+    /// 返回写入的日志条数
+    ///
+    /// # Errors
+    /// 读取错误（包括 `Abort` 策略下的 `NeedRecover`）和写入错误
+    pub fn copy_from(&mut self, reader: &mut GlogReader) -> Result<u64> {
+        let encrypt_mode = match self.server_key {
+            Some(_) => EncryptMode::Aes,
+            None => EncryptMode::None,
+        };
+        let mut buf = Vec::new();
+        let mut written = 0;
+        while let Some(entry) = reader.read_entry(&mut buf)? {
+            let data = &buf[..entry.len];
+            if data.is_empty() || reader.decode_log(data).is_err() {
+                continue;
+            }
+            self.write_entry(data, entry.compress_mode, encrypt_mode)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// 把读取器中剩余条目的存储数据去掉加密后原样写入
+    ///
+    /// 通过 [`GlogReader::read_raw`] 只解密不解压，压缩数据逐字节保留，条目顺序和压缩模式不变，
+    /// 写出的文件读取时不再需要私钥。写入器应当新建且未写入过日志，设置的服务器公钥被忽略。
+    /// 损坏条目按读取器的恢复策略跳过；跳过压缩条目后，后续压缩条目可能因缺少字典而无法解压
+    ///
+    /// # Arguments
+    /// * `reader` - V4 日志读取器
+    ///
+    /// # Returns
+    /// 返回写入的条目数
+    ///
+    /// # Errors
+    /// 读取错误（包括非 V4 文件的 `UnsupportedVersion`）和写入错误
+    pub fn copy_raw_from(&mut self, reader: &mut GlogReader) -> Result<u64> {
+        let mut buf = Vec::new();
+        let mut written = 0;
+        while let Some(entry) = reader.read_raw(&mut buf)? {
+            self.write_raw_entry(&buf, entry.compress_mode)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// 刷新输出流
+    pub fn flush(&mut self) -> Result<()> {
+        self.output.flush()?;
+        Ok(())
+    }
+
+    /// 刷新并取回输出流
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.output)
+    }
+
+    /// 获取当前条目使用的客户端密钥，按轮换方式生成新密钥
+    fn client_key(&mut self) -> Result<&ClientKey> {
+        let server_key = self.server_key.as_ref().ok_or(GlogError::CipherNotReady)?;
+        if self.rotation == KeyRotation::PerEntry || self.client_key.is_none() {
+            self.client_key = Some(ClientKey::generate(server_key));
+        }
+        Ok(self.client_key.as_ref().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ReadResult;
+    use crate::reader::key::{KeyProvider, StaticKeyProvider};
+    use crate::reader::v4::FileReaderV4;
+    use crate::reader::FileReader;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    /// 测试用的服务器私钥
+    const SVR_KEY: &str = "1C74B66FCB1C54FD4386173CFAF3BC53C8DF6B89F799DE1A1E7CEBBC43CBFD38";
+
+    fn server_public_key() -> Vec<u8> {
+        let secret = SecretKey::from_slice(&hex::decode(SVR_KEY).unwrap()).unwrap();
+        secret
+            .public_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    fn log(i: usize) -> Log {
+        Log {
+            timestamp: format!("{}", 1700000000000u64 + i as u64),
+            tag: "Main".to_string(),
+            msg: format!("message {}", i),
+            ..Log::default()
+        }
+    }
+
+    /// 与测试共享的密钥提供者，用于在读取后检查缓存
+    struct SharedKeyProvider(Arc<Mutex<StaticKeyProvider>>);
+
+    impl KeyProvider for SharedKeyProvider {
+        fn shared_key(&self, client_pubkey: &[u8; 33]) -> crate::error::Result<[u8; 16]> {
+            self.0.lock().unwrap().shared_key(client_pubkey)
+        }
+    }
+
+    /// 使用 FileReaderV4 读回全部日志，返回日志和读取器中缓存的共享密钥数量
+    fn read_back(bytes: Vec<u8>) -> (Vec<Log>, usize) {
+        // 魔数和版本号由 GlogReader 读取，这里直接跳过
+        let size = bytes.len() as u64;
+        let input = Cursor::new(bytes[5..].to_vec());
+        let provider = Arc::new(Mutex::new(StaticKeyProvider::from_hex(SVR_KEY).unwrap()));
+        let mut reader = FileReaderV4::with_key_provider(
+            input,
+            size,
+            Some(Box::new(SharedKeyProvider(provider.clone()))),
+        );
+        reader.read_remain_header().unwrap();
+
+        let mut buf = vec![0u8; SINGLE_LOG_CONTENT_MAX_LENGTH];
+        let mut logs = Vec::new();
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                ReadResult::Success(len) => logs.push(Log::decode_from(&buf[..len]).unwrap()),
+                ReadResult::Eof => break,
+                ReadResult::NeedRecover(reason) => panic!("意外的 NeedRecover: {}", reason),
+            }
+        }
+        assert_eq!(reader.position(), size);
+        let cached_keys = provider.lock().unwrap().cached_keys();
+        (logs, cached_keys)
+    }
+
+    #[test]
+    fn test_round_trip_per_file_key() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log")
+            .unwrap()
+            .with_server_key(&server_public_key())
+            .unwrap();
+        for i in 0..50 {
+            writer.write_log(&log(i)).unwrap();
+        }
+
+        let (logs, cached_keys) = read_back(writer.into_inner().unwrap());
+        assert_eq!(logs, (0..50).map(log).collect::<Vec<_>>());
+        // 同一个客户端公钥只需协商一次
+        assert_eq!(cached_keys, 1);
+    }
+
+    #[test]
+    fn test_round_trip_per_entry_key() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log")
+            .unwrap()
+            .with_server_key(&server_public_key())
+            .unwrap()
+            .with_key_rotation(KeyRotation::PerEntry);
+        for i in 0..5 {
+            writer.write_log(&log(i)).unwrap();
+        }
+
+        let (logs, cached_keys) = read_back(writer.into_inner().unwrap());
+        assert_eq!(logs, (0..5).map(log).collect::<Vec<_>>());
+        assert_eq!(cached_keys, 5);
+    }
+
+    #[test]
+    fn test_round_trip_mixed_modes() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log")
+            .unwrap()
+            .with_server_key(&server_public_key())
+            .unwrap();
+        let modes = [
+            (CompressMode::None, EncryptMode::None),
+            (CompressMode::Zlib, EncryptMode::None),
+            (CompressMode::None, EncryptMode::Aes),
+            (CompressMode::Zlib, EncryptMode::Aes),
+        ];
+        for (i, (compress_mode, encrypt_mode)) in modes.iter().cycle().take(12).enumerate() {
+            let data = log(i).encode_to_vec();
+            writer
+                .write_entry(&data, *compress_mode, *encrypt_mode)
+                .unwrap();
+        }
+
+        let (logs, cached_keys) = read_back(writer.into_inner().unwrap());
+        assert_eq!(logs, (0..12).map(log).collect::<Vec<_>>());
+        assert_eq!(cached_keys, 1);
+    }
+
+    #[test]
+    fn test_encrypt_without_key() {
+        let mut writer = GlogWriterV4::new(Vec::new(), "Log").unwrap();
+        let result = writer.write_entry(b"data", CompressMode::None, EncryptMode::Aes);
+        assert!(matches!(result, Err(GlogError::CipherNotReady)));
+    }
+}
@@ -0,0 +1,180 @@
+//! # 读取路径的内存分配测试
+//!
+//! 使用计数分配器验证 V3 / V4 读取器在稳定状态下逐条读取（`read_entry` 和 `read`）时不分配内存，
+//! 以及共享解压上下文池减少依次打开多个文件时的分配。
+//! 全局分配器作用于整个测试二进制文件，因此单独放在这个文件中，计数按线程记录
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::Cursor;
+
+use clog_reader::reader::{CompressMode, EncryptMode, InflaterPool};
+use clog_reader::writer::v3::GlogWriterV3;
+use clog_reader::writer::v4::GlogWriterV4;
+use clog_reader::{GlogReader, Log, ReadResult};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::SecretKey;
+use prost::Message;
+
+/// 统计当前线程分配次数的分配器
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// 当前线程累计的分配次数
+fn allocations() -> u64 {
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// 依次使用的压缩和加密模式
+const MODES: [(CompressMode, EncryptMode); 4] = [
+    (CompressMode::Zlib, EncryptMode::Aes),
+    (CompressMode::None, EncryptMode::Aes),
+    (CompressMode::Zlib, EncryptMode::None),
+    (CompressMode::None, EncryptMode::None),
+];
+
+/// 构造轮流使用 [`MODES`] 的 200 条日志的 V4 文件，返回读取它的读取器
+fn v4_reader() -> GlogReader {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), "Log")
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..200 {
+        let log = Log {
+            tag: "alloc".to_string(),
+            msg: format!("entry {}", i),
+            ..Log::new()
+        };
+        let (compress_mode, encrypt_mode) = MODES[i % MODES.len()];
+        writer
+            .write_entry(&log.encode_to_vec(), compress_mode, encrypt_mode)
+            .unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+    let key = hex::encode(server_key.to_bytes());
+    GlogReader::from_reader(Cursor::new(content), size, Some(key)).unwrap()
+}
+
+#[test]
+fn test_v4_steady_state_read_does_not_allocate() {
+    let mut reader = v4_reader();
+
+    // 预热：缓冲区扩容、计算并缓存共享密钥
+    let mut buf = Vec::with_capacity(1024);
+    for _ in 0..MODES.len() {
+        assert!(reader.read_entry(&mut buf).unwrap().is_some());
+    }
+
+    let before = allocations();
+    let mut read = MODES.len();
+    while reader.read_entry(&mut buf).unwrap().is_some() {
+        read += 1;
+    }
+    assert_eq!(allocations() - before, 0);
+    assert_eq!(read, 200);
+}
+
+#[test]
+fn test_steady_state_read_into_slice_does_not_allocate() {
+    let mut v3 = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+    for i in 0..200 {
+        let log = Log {
+            msg: format!("entry {}", i),
+            ..Log::new()
+        };
+        v3.write_log(&log).unwrap();
+    }
+    let content = v3.into_inner().unwrap();
+    let size = content.len() as u64;
+    let v3_reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+
+    for mut reader in [v4_reader(), v3_reader] {
+        // 预热：读取器内部的缓冲区扩容、计算并缓存共享密钥
+        let mut buf = [0u8; 1024];
+        for _ in 0..MODES.len() {
+            let result = reader.read(&mut buf).unwrap();
+            assert!(matches!(result, ReadResult::Success(_)));
+        }
+
+        let before = allocations();
+        let mut read = MODES.len();
+        while let ReadResult::Success(_) = reader.read(&mut buf).unwrap() {
+            read += 1;
+        }
+        assert_eq!(allocations() - before, 0);
+        assert_eq!(read, 200);
+    }
+}
+
+#[test]
+fn test_inflater_pool_saves_context_per_file() {
+    const FILES: u64 = 20;
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Log").unwrap();
+    for i in 0..10 {
+        let log = Log {
+            msg: format!("entry {}", i),
+            ..Log::new()
+        };
+        writer.write_log(&log).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+
+    let read_files = |pool: Option<&InflaterPool>| {
+        let before = allocations();
+        for _ in 0..FILES {
+            let builder = GlogReader::builder();
+            let builder = match pool {
+                Some(pool) => builder.inflater_pool(pool.clone()),
+                None => builder,
+            };
+            let size = content.len() as u64;
+            let mut reader = builder
+                .from_reader(Cursor::new(content.clone()), size)
+                .unwrap();
+            let logs = reader.logs().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(logs.len(), 10);
+        }
+        allocations() - before
+    };
+
+    // 预热：一次性的初始化，以及池中第一个上下文和存放上下文的 Vec
+    let pool = InflaterPool::new();
+    read_files(None);
+    read_files(Some(&pool));
+    assert_eq!(pool.len(), 1);
+
+    let without_pool = read_files(None);
+    let with_pool = read_files(Some(&pool));
+    assert_eq!(pool.len(), 1);
+    // 每个文件至少省去一次解压上下文的分配
+    assert!(
+        with_pool + FILES <= without_pool,
+        "with pool: {}, without pool: {}",
+        with_pool,
+        without_pool
+    );
+}
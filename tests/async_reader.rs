@@ -0,0 +1,170 @@
+//! # 异步读取器集成测试
+//!
+//! 用每次只返回少量字节、并时常返回 `Pending` 的输入验证 `AsyncGlogReader`
+//! 与阻塞读取器的结果一致。
+
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::io::Cursor;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use clog_reader::glog::GlogReader;
+use clog_reader::reader::{CompressMode, EncryptMode};
+use clog_reader::writer::v4::GlogWriterV4;
+use clog_reader::AsyncGlogReader;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::SecretKey;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use common::{
+    encode_log, legacy_file, mmap_file, v3_file_with_corrupt_entry, v3_file_with_junk,
+    v3_file_with_logs, PROTO_NAME,
+};
+
+/// 每次最多返回 `chunk` 字节，每隔一次先返回 `Pending` 的输入
+struct SlowInput {
+    data: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+    polls: usize,
+}
+
+impl SlowInput {
+    fn new(data: Vec<u8>, chunk: usize) -> Self {
+        Self {
+            data,
+            pos: 0,
+            chunk,
+            polls: 0,
+        }
+    }
+}
+
+impl AsyncRead for SlowInput {
+    #[allow(clippy::manual_is_multiple_of)]
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        self.polls += 1;
+        if self.polls % 2 == 0 {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let end = (self.pos + self.chunk).min(self.data.len());
+        let end = self.pos + (end - self.pos).min(buf.remaining());
+        buf.put_slice(&self.data[self.pos..end]);
+        self.pos = end;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// 阻塞读取器解码出的消息，解码失败记为 `None`
+fn sync_msgs(data: &[u8], key: Option<String>) -> Vec<Option<String>> {
+    let mut reader =
+        GlogReader::from_reader(Cursor::new(data.to_vec()), data.len() as u64, key).unwrap();
+    reader
+        .logs()
+        .map(|log| log.ok().map(|log| log.msg))
+        .collect()
+}
+
+/// 异步读取器解码出的消息，解码失败记为 `None`
+async fn async_msgs(data: &[u8], chunk: usize, key: Option<String>) -> Vec<Option<String>> {
+    let mut reader = AsyncGlogReader::new(SlowInput::new(data.to_vec(), chunk), key);
+    let mut msgs = Vec::new();
+    loop {
+        match reader.next_log().await {
+            Ok(Some(log)) => msgs.push(Some(log.msg)),
+            Ok(None) => break,
+            Err(_) => msgs.push(None),
+        }
+    }
+    msgs
+}
+
+/// 明文与加密条目交替写入、压缩模式轮换的 V4 文件
+fn v4_file_with_mixed_entries() -> Vec<u8> {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let compress_modes = [CompressMode::None, CompressMode::Zlib];
+    for i in 0..12 {
+        let encrypt_mode = if i % 3 == 0 {
+            EncryptMode::Aes
+        } else {
+            EncryptMode::None
+        };
+        let payload = encode_log(0, 0, "Tag", &format!("entry {} {}", i, "x".repeat(i * 40)));
+        writer
+            .write_entry(&payload, compress_modes[i % 2], encrypt_mode)
+            .unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+#[tokio::test]
+async fn test_matches_blocking_reader_for_streamed_formats() {
+    let key = Some("5a".repeat(32));
+    let fixtures = [
+        (v3_file_with_logs(20), None),
+        (v4_file_with_mixed_entries(), key),
+    ];
+    for (data, key) in fixtures {
+        let expected = sync_msgs(&data, key.clone());
+        assert!(expected.iter().all(Option::is_some));
+        for chunk in [1, 7, 4096] {
+            assert_eq!(
+                async_msgs(&data, chunk, key.clone()).await,
+                expected,
+                "chunk {}",
+                chunk
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_recovers_from_corrupt_entries_and_junk() {
+    for data in [v3_file_with_corrupt_entry(), v3_file_with_junk()] {
+        let expected = sync_msgs(&data, None);
+        for chunk in [1, 5, 4096] {
+            assert_eq!(
+                async_msgs(&data, chunk, None).await,
+                expected,
+                "chunk {}",
+                chunk
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_reads_legacy_and_mmap_files_after_eof() {
+    let payloads: Vec<Vec<u8>> = (0..3)
+        .map(|i| encode_log(0, 0, "Tag", &format!("legacy {}", i)))
+        .collect();
+    let legacy = legacy_file(2, &payloads);
+    let mmap = mmap_file(&v3_file_with_logs(3), 128);
+    for data in [legacy, mmap] {
+        let expected = sync_msgs(&data, None);
+        assert_eq!(expected.len(), 3);
+        assert_eq!(async_msgs(&data, 3, None).await, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_truncated_tail_matches_blocking_reader() {
+    let mut data = v3_file_with_logs(5);
+    data.truncate(data.len() - 4);
+
+    let expected = sync_msgs(&data, None);
+    assert_eq!(expected.len(), 4);
+    assert_eq!(async_msgs(&data, 6, None).await, expected);
+}
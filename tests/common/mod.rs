@@ -0,0 +1,273 @@
+//! # 集成测试公共模块
+//!
+//! 提供构造 Glog 测试文件和 ZIP 压缩包的辅助函数，
+//! 避免测试依赖从真实设备采集的日志文件。
+
+#![allow(dead_code)]
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clog_reader::proto::{Log, LogBytes};
+use clog_reader::reader::mmap::MMAP_MAGIC_NUMBER;
+use clog_reader::reader::{MAGIC_NUMBER, SYNC_MARKER};
+use clog_reader::version::{GLOG_CIPHER_VERSION, GLOG_RECOVERY_VERSION};
+use flate2::{Compress, Compression, FlushCompress};
+use prost::Message;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// 测试使用的协议名称
+pub const PROTO_NAME: &str = "Log";
+
+/// 构造一条日志并编码为 protobuf 字节
+///
+/// # Arguments
+/// * `log_type` - 日志类型
+/// * `level` - 日志级别
+/// * `tag` - 日志标签
+/// * `msg` - 日志内容
+pub fn encode_log(log_type: i32, level: i32, tag: &str, msg: &str) -> Vec<u8> {
+    make_log(log_type, level, tag, msg).encode_to_vec()
+}
+
+/// 构造一条日志
+///
+/// 时间戳固定为 2023-11-14
+pub fn make_log(log_type: i32, level: i32, tag: &str, msg: &str) -> Log {
+    Log {
+        log_type,
+        timestamp: "1700000000000".to_string(),
+        log_level: level,
+        pid: 1234,
+        tid: "5678".to_string(),
+        tag: tag.to_string(),
+        msg: msg.to_string(),
+    }
+}
+
+/// 构造内容中夹有字节 0xFF（无效 UTF-8）的日志，编码为 protobuf 字节
+///
+/// 内容为 `bad \xFF byte`，容错解码后为 `bad \u{FFFD} byte`
+pub fn encode_invalid_utf8_log() -> Vec<u8> {
+    LogBytes {
+        timestamp: b"1700000000000".to_vec(),
+        tag: b"Tag".to_vec(),
+        msg: b"bad \xFF byte".to_vec(),
+        ..LogBytes::default()
+    }
+    .encode_to_vec()
+}
+
+/// 构造未压缩、未加密的 V3 文件头
+pub fn v3_header() -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC_NUMBER);
+    out.push(GLOG_RECOVERY_VERSION);
+    out.push(0x00); // 无压缩 + 无加密
+    out.extend_from_slice(&(PROTO_NAME.len() as u16).to_le_bytes());
+    out.extend_from_slice(PROTO_NAME.as_bytes());
+    out.extend_from_slice(&SYNC_MARKER);
+    out
+}
+
+/// 构造一条 V3 日志条目：长度(2) + 数据 + 同步标记(8)
+pub fn v3_entry(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&SYNC_MARKER);
+    out
+}
+
+/// 构造包含给定条目的完整 V3 文件（未压缩）
+pub fn v3_file(payloads: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = v3_header();
+    for payload in payloads {
+        out.extend(v3_entry(payload));
+    }
+    out
+}
+
+/// 构造包含 `n` 条日志的 V3 文件，日志类型依次为 `0..n`
+pub fn v3_file_with_logs(n: usize) -> Vec<u8> {
+    let payloads: Vec<Vec<u8>> = (0..n)
+        .map(|i| encode_log(i as i32, 0, "Tag", &format!("message {}", i)))
+        .collect();
+    v3_file(&payloads)
+}
+
+/// 构造一个第二条日志损坏（同步标记被破坏）的 V3 文件
+///
+/// 第一条日志完好，第二条日志之后的同步标记被改写，
+/// 因此读取第二条时会触发 NeedRecover
+pub fn v3_file_with_corrupt_entry() -> Vec<u8> {
+    let mut out = v3_header();
+    out.extend(v3_entry(&encode_log(0, 0, "Tag", "good")));
+    let mut bad = v3_entry(&encode_log(1, 0, "Tag", "bad"));
+    let len = bad.len();
+    bad[len - 1] ^= 0xFF;
+    out.extend(bad);
+    out.extend(v3_entry(&encode_log(2, 0, "Tag", "after")));
+    out
+}
+
+/// 构造一个中间插入了 100 字节垃圾数据的 V3 文件
+///
+/// 垃圾数据位于 `before 1` 之后；读取器需要扫描到下一个同步标记才能恢复，
+/// 紧跟垃圾数据的 `after 0` 会随扫描一起被跳过
+pub fn v3_file_with_junk() -> Vec<u8> {
+    let mut out = v3_header();
+    for i in 0..2 {
+        out.extend(v3_entry(&encode_log(0, 0, "Tag", &format!("before {}", i))));
+    }
+    out.extend_from_slice(&[0xAB; 100]);
+    for i in 0..3 {
+        out.extend(v3_entry(&encode_log(0, 0, "Tag", &format!("after {}", i))));
+    }
+    out
+}
+
+/// 构造 zlib 压缩的 V3 文件
+///
+/// 所有条目组成一个连续的压缩流，每条以 SYNC_FLUSH 结束
+///
+/// # Arguments
+/// * `payloads` - 各条日志的 protobuf 数据
+/// * `zlib_header` - 压缩流是否带有 zlib 头部（较早的 iOS / 桌面端写入器），否则为 raw deflate
+pub fn v3_zlib_file(payloads: &[Vec<u8>], zlib_header: bool) -> Vec<u8> {
+    let mut out = v3_header();
+    out[5] = 0x10; // zlib 压缩 + 无加密
+    let mut compressor = Compress::new(Compression::default(), zlib_header);
+    for payload in payloads {
+        let mut compressed = Vec::with_capacity(payload.len() + 64);
+        compressor
+            .compress_vec(payload, &mut compressed, FlushCompress::Sync)
+            .unwrap();
+        out.extend(v3_entry(&compressed));
+    }
+    out
+}
+
+/// 构造未压缩、未加密的 V1/V2 文件头（没有同步标记）
+pub fn legacy_header(version: u8) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC_NUMBER);
+    out.push(version);
+    out.push(0x00); // 无压缩 + 无加密
+    out.extend_from_slice(&(PROTO_NAME.len() as u16).to_le_bytes());
+    out.extend_from_slice(PROTO_NAME.as_bytes());
+    out
+}
+
+/// 构造包含给定条目的 V1/V2 文件：每条为长度(2) + 数据，末尾带 mmap 零填充
+pub fn legacy_file(version: u8, payloads: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = legacy_header(version);
+    for payload in payloads {
+        out.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        out.extend_from_slice(payload);
+    }
+    out.extend_from_slice(&[0u8; 64]);
+    out
+}
+
+/// 构造 V4 文件头（V4 的压缩和加密模式记录在每条日志中）
+pub fn v4_header() -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&MAGIC_NUMBER);
+    out.push(GLOG_CIPHER_VERSION);
+    out.extend_from_slice(&(PROTO_NAME.len() as u16).to_le_bytes());
+    out.extend_from_slice(PROTO_NAME.as_bytes());
+    out.extend_from_slice(&SYNC_MARKER);
+    out
+}
+
+/// 构造一条未压缩、未加密的 V4 日志条目：模式(1) + 长度(2) + 数据 + 同步标记(8)
+pub fn v4_entry(payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x11]; // 高 4 位：无压缩，低 4 位：无加密
+    out.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    out.extend_from_slice(payload);
+    out.extend_from_slice(&SYNC_MARKER);
+    out
+}
+
+/// 构造包含给定条目的完整 V4 文件（未压缩、未加密）
+pub fn v4_file(payloads: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = v4_header();
+    for payload in payloads {
+        out.extend(v4_entry(payload));
+    }
+    out
+}
+
+/// 构造 mmap 缓存文件：mmap 文件头 + Glog 数据 + 零填充
+///
+/// # Arguments
+/// * `content` - 写入游标之前的 Glog 数据
+/// * `padding` - 末尾零填充的字节数
+pub fn mmap_file(content: &[u8], padding: usize) -> Vec<u8> {
+    let mut out = MMAP_MAGIC_NUMBER.to_vec();
+    out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+    out.extend_from_slice(content);
+    out.resize(out.len() + padding, 0);
+    out
+}
+
+/// 将给定文件写入 ZIP 压缩包
+///
+/// # Arguments
+/// * `dir` - 压缩包所在目录
+/// * `name` - 压缩包文件名
+/// * `files` - 压缩包内的文件（名称, 内容）
+///
+/// # Returns
+/// 返回压缩包路径
+pub fn write_zip(dir: &Path, name: &str, files: &[(&str, Vec<u8>)]) -> PathBuf {
+    let path = dir.join(name);
+    let file = File::create(&path).unwrap();
+    let mut zip = ZipWriter::new(file);
+    for (entry_name, content) in files {
+        zip.start_file(*entry_name, FileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+    path
+}
+
+/// 在 `dir` 中创建一个 ZIP 文件，条目名按原始字节写入且不设置 UTF-8 标志
+///
+/// 模拟中文 Windows 生成的压缩包（条目名为 GBK 编码）：先以等长的 ASCII 占位名写入，
+/// 再把本地文件头和中央目录中的占位名替换为原始字节
+pub fn write_zip_raw_names(dir: &Path, name: &str, files: &[(&[u8], Vec<u8>)]) -> PathBuf {
+    let placeholders: Vec<String> = files
+        .iter()
+        .map(|(raw, _)| {
+            raw.iter()
+                .map(|&b| if b.is_ascii() { b as char } else { 'X' })
+                .collect()
+        })
+        .collect();
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (placeholder, (_, content)) in placeholders.iter().zip(files) {
+        zip.start_file(placeholder.as_str(), FileOptions::default())
+            .unwrap();
+        zip.write_all(content).unwrap();
+    }
+    let mut bytes = zip.finish().unwrap().into_inner();
+    for (placeholder, (raw, _)) in placeholders.iter().zip(files) {
+        let placeholder = placeholder.as_bytes();
+        let mut start = 0;
+        while let Some(pos) = bytes[start..]
+            .windows(placeholder.len())
+            .position(|window| window == placeholder)
+        {
+            let pos = start + pos;
+            bytes[pos..pos + raw.len()].copy_from_slice(raw);
+            start = pos + raw.len();
+        }
+    }
+    let path = dir.join(name);
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
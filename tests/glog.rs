@@ -0,0 +1,1810 @@
+//! # 库接口集成测试
+//!
+//! 使用构造的 Glog 文件验证 `GlogReader` 的公开接口。
+
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use clog_reader::glog::{self, GlogReader, RecoverEvent, RecoveryPolicy};
+use clog_reader::observer::{MetricsObserver, Observer, ReadStats};
+use clog_reader::reader::source::ReadSource;
+use clog_reader::reader::v3::FileReaderV3;
+use clog_reader::reader::v4::FileReaderV4;
+use clog_reader::reader::{CompressMode, DeflateWrapper, EncryptMode};
+use clog_reader::writer::v3::GlogWriterV3;
+use clog_reader::writer::v4::GlogWriterV4;
+use clog_reader::version::{
+    GLOG_CIPHER_VERSION, GLOG_FIX_POSITION_VERSION, GLOG_INITIAL_VERSION, GLOG_RECOVERY_VERSION,
+};
+use clog_reader::proto::{Level, LogFilter};
+use clog_reader::{EntryOffset, FileIndex, GlogError, ReadResult, RecoverReason, Redactor};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::SecretKey;
+use tempfile::TempDir;
+
+use common::{
+    encode_invalid_utf8_log, encode_log, legacy_file, mmap_file, legacy_header, v3_entry, v3_file, v3_file_with_corrupt_entry, v3_file_with_junk, v3_file_with_logs, v3_header, v3_zlib_file,
+    v4_entry, v4_file, v4_header, PROTO_NAME,
+};
+
+/// 将文件内容写入临时目录并返回路径
+fn write_fixture(dir: &TempDir, content: Vec<u8>) -> PathBuf {
+    let path = dir.path().join("async-20240101.glog");
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_logs_yields_decoded_logs_in_order() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_logs(3));
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["message 0", "message 1", "message 2"]);
+}
+
+#[test]
+fn test_logs_skips_recover_events_by_default() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_corrupt_entry());
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["good", "after"]);
+}
+
+#[test]
+fn test_skip_policy_reports_recover_events() {
+    let dir = TempDir::new().unwrap();
+    let content = v3_file_with_junk();
+    let path = write_fixture(&dir, content);
+
+    let events: Arc<Mutex<Vec<RecoverEvent>>> = Arc::default();
+    let sink = Arc::clone(&events);
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Skip {
+            on_event: Box::new(move |event| sink.lock().unwrap().push(event)),
+        })
+        .open(path.to_str().unwrap())
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["before 0", "before 1", "after 1", "after 2"]);
+
+    // 跳过的范围：垃圾数据 + 紧随其后的 `after 0` 条目
+    let before = v3_entry(&encode_log(0, 0, "Tag", "before 0")).len() as u64;
+    let after = v3_entry(&encode_log(0, 0, "Tag", "after 0")).len() as u64;
+    assert_eq!(
+        *events.lock().unwrap(),
+        [RecoverEvent {
+            offset: v3_header().len() as u64 + 2 * before,
+            reason: RecoverReason::InvalidLogLength(0xABAB),
+            bytes_skipped: 100 + after,
+        }]
+    );
+}
+
+#[test]
+fn test_abort_policy_ends_iteration() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_corrupt_entry());
+
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Abort)
+        .open(path.to_str().unwrap())
+        .unwrap();
+    let items: Vec<_> = reader.logs().collect();
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap().msg, "good");
+    let err = items[1].as_ref().unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        GlogError::NeedRecover(RecoverReason::SyncMarkerMismatch)
+    ));
+
+    // 错误带有文件路径和损坏条目的起始偏移
+    let offset = (v3_header().len() + v3_entry(&encode_log(0, 0, "Tag", "good")).len()) as u64;
+    assert_eq!(err.offset(), Some(offset));
+    assert_eq!(err.path(), path.to_str());
+    assert!(err
+        .to_string()
+        .starts_with(&format!("{}:{}: ", path.display(), offset)));
+}
+
+/// 记录事件顺序的观察者
+#[derive(Default)]
+struct Recorder(Mutex<Vec<String>>);
+
+impl Observer for Recorder {
+    fn on_file_start(&self, path: Option<&str>) {
+        self.0.lock().unwrap().push(format!("start {}", path.is_some()));
+    }
+
+    fn on_entry(&self, offset: u64, len: u64) {
+        self.0.lock().unwrap().push(format!("entry {} {}", offset, len));
+    }
+
+    fn on_recover(&self, event: RecoverEvent) {
+        self.0.lock().unwrap().push(format!("recover {}", event.offset));
+    }
+
+    fn on_decode_failure(&self, offset: u64, _len: u64) {
+        self.0.lock().unwrap().push(format!("decode failure {}", offset));
+    }
+
+    fn on_file_end(&self, _path: Option<&str>, stats: &ReadStats) {
+        self.0.lock().unwrap().push(format!("end {}", stats.entries));
+    }
+}
+
+#[test]
+fn test_observers_see_entries_recoveries_and_decode_failures() {
+    let dir = TempDir::new().unwrap();
+    let mut content = v3_file_with_corrupt_entry();
+    let garbage = v3_entry(&[0xFF, 0xFF, 0xFF]);
+    content.extend(&garbage);
+    let path = write_fixture(&dir, content.clone());
+
+    let recorder = Arc::new(Recorder::default());
+    let metrics = Arc::new(MetricsObserver::new());
+    let mut reader = GlogReader::builder()
+        .observer(recorder.clone())
+        .observer(metrics.clone())
+        .open(path.to_str().unwrap())
+        .unwrap();
+    assert_eq!(reader.logs().count(), 3);
+    let stats = reader.stats();
+    drop(reader);
+
+    let header = v3_header().len() as u64;
+    let good = v3_entry(&encode_log(0, 0, "Tag", "good")).len() as u64;
+    let bad = v3_entry(&encode_log(1, 0, "Tag", "bad")).len() as u64;
+    let after = v3_entry(&encode_log(2, 0, "Tag", "after")).len() as u64;
+    let last = header + good + bad + after;
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "start true".to_string(),
+            format!("entry {} {}", header, good),
+            format!("recover {}", header + good),
+            format!("entry {} {}", header + good + bad, after),
+            format!("entry {} {}", last, garbage.len()),
+            format!("decode failure {}", last),
+            "end 3".to_string(),
+        ]
+    );
+    assert_eq!(
+        stats,
+        ReadStats {
+            entries: 3,
+            bytes: good + after + garbage.len() as u64,
+            recoveries: 1,
+            bytes_skipped: bad,
+            decode_failures: 1,
+            lossy_decodes: 0,
+            redactions: 0,
+        }
+    );
+    assert_eq!(metrics.stats(), stats);
+    assert_eq!((metrics.files_started(), metrics.files_finished()), (1, 1));
+}
+
+#[test]
+fn test_observer_notified_of_end_when_reader_dropped_early() {
+    let recorder = Arc::new(Recorder::default());
+    let data = v3_file_with_logs(3);
+    let mut reader = GlogReader::builder()
+        .observer(recorder.clone())
+        .from_reader(Cursor::new(data.clone()), data.len() as u64)
+        .unwrap();
+    reader.logs().next().unwrap().unwrap();
+    drop(reader);
+
+    let events = recorder.0.lock().unwrap();
+    assert_eq!(events.first().unwrap(), "start false");
+    assert_eq!(events.last().unwrap(), "end 1");
+    assert_eq!(events.len(), 3);
+}
+
+#[test]
+fn test_cancel_token_ends_iteration() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_logs(5));
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let mut reader = GlogReader::builder()
+        .cancel_token(Arc::clone(&cancel))
+        .open(path.to_str().unwrap())
+        .unwrap();
+    let mut logs = reader.logs();
+    assert!(logs.next().unwrap().is_ok());
+    cancel.store(true, Ordering::Relaxed);
+
+    let err = logs.next().unwrap().unwrap_err();
+    assert!(matches!(err.kind(), GlogError::Cancelled));
+    assert_eq!(err.path(), path.to_str());
+    assert!(logs.next().is_none());
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn test_reader_types_are_send() {
+    assert_send::<GlogReader>();
+    assert_send::<glog::GlogReaderBuilder>();
+    assert_send::<FileReaderV3<ReadSource<Cursor<Vec<u8>>>>>();
+    assert_send::<FileReaderV4<ReadSource<Cursor<Vec<u8>>>>>();
+}
+
+#[test]
+fn test_readers_decode_on_separate_threads() {
+    let dir = TempDir::new().unwrap();
+    let first = write_fixture(&dir, v3_file_with_logs(50));
+    let second = dir.path().join("second.glog");
+    std::fs::write(&second, v3_file_with_logs(30)).unwrap();
+
+    // 在当前线程打开，移动到工作线程中同时读取
+    let readers = [
+        glog::open(first.to_str().unwrap()).unwrap(),
+        glog::open(second.to_str().unwrap()).unwrap(),
+    ];
+    let handles: Vec<_> = readers
+        .into_iter()
+        .map(|mut reader| std::thread::spawn(move || reader.logs().map(Result::unwrap).count()))
+        .collect();
+    let counts: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(counts, [50, 30]);
+}
+
+#[test]
+fn test_logs_continues_after_decode_failure() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(
+        &dir,
+        v3_file(&[vec![0xFF, 0xFF, 0xFF], encode_log(0, 0, "Tag", "ok")]),
+    );
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let items: Vec<_> = reader.logs().collect();
+
+    assert_eq!(items.len(), 2);
+    assert!(matches!(
+        items[0].as_ref().map_err(GlogError::kind),
+        Err(GlogError::ProtobufError(_))
+    ));
+    assert_eq!(items[1].as_ref().unwrap().msg, "ok");
+}
+
+#[test]
+fn test_validate_reports_error_code_and_recover_reason() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_corrupt_entry());
+    let validation = glog::validate(path.to_str().unwrap(), None);
+    let failure = validation.failure.unwrap();
+    assert_eq!(validation.entries, 1);
+    assert_eq!(failure.code, 209);
+    assert_eq!(
+        failure.recover_reason,
+        Some(RecoverReason::SyncMarkerMismatch)
+    );
+
+    std::fs::write(&path, b"not a glog file").unwrap();
+    let failure = glog::validate(path.to_str().unwrap(), None)
+        .failure
+        .unwrap();
+    assert_eq!((failure.offset, failure.code), (0, 202));
+    assert_eq!(failure.recover_reason, None);
+}
+
+#[test]
+fn test_invalid_utf8_is_decoded_lossily_unless_strict() {
+    let content = v3_file(&[encode_invalid_utf8_log(), encode_log(0, 0, "Tag", "ok")]);
+    let size = content.len() as u64;
+    let metrics = Arc::new(MetricsObserver::new());
+
+    let mut reader = GlogReader::builder()
+        .observer(metrics.clone())
+        .from_reader(Cursor::new(content.clone()), size)
+        .unwrap();
+    let msgs: Vec<_> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["bad \u{FFFD} byte", "ok"]);
+    assert_eq!(reader.stats().lossy_decodes, 1);
+    assert_eq!(reader.stats().decode_failures, 0);
+    drop(reader);
+    assert_eq!(metrics.stats().lossy_decodes, 1);
+
+    let mut reader = GlogReader::builder()
+        .strict(true)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let first = reader.logs().next().unwrap();
+    assert!(matches!(first.unwrap_err().kind(), GlogError::ProtobufError(_)));
+}
+
+#[test]
+fn test_redactor_runs_before_filter_and_is_counted() {
+    let content = v3_file(&[
+        encode_log(0, 0, "Tag", "call 13800138000 or 13900000000"),
+        encode_log(1, 0, "user-13700000000", "ok"),
+    ]);
+    let size = content.len() as u64;
+    let metrics = Arc::new(MetricsObserver::new());
+    let redactor = Redactor::new().parse_rule(r"1[3-9]\d{9}=<phone>").unwrap();
+
+    let mut reader = GlogReader::builder()
+        .redactor(Arc::new(redactor))
+        .observer(metrics.clone())
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let filter = LogFilter::new().msg_regex("1[3-9]\\d{9}|phone").unwrap();
+    let logs: Vec<_> = reader
+        .logs()
+        .with_filter(filter)
+        .map(|log| log.unwrap())
+        .collect();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].msg, "call <phone> or <phone>");
+    assert_eq!(reader.stats().redactions, 3);
+    drop(reader);
+    assert_eq!(metrics.stats().redactions, 3);
+}
+
+#[test]
+fn test_metadata_v3() {
+    let dir = TempDir::new().unwrap();
+    let content = v3_file_with_logs(1);
+    let size = content.len() as u64;
+    let path = write_fixture(&dir, content);
+
+    let reader = glog::open(path.to_str().unwrap()).unwrap();
+    let metadata = reader.metadata();
+
+    assert_eq!(metadata.version, GLOG_RECOVERY_VERSION);
+    assert_eq!(metadata.proto_name, PROTO_NAME);
+    assert_eq!(metadata.compress_mode, CompressMode::None);
+    assert_eq!(metadata.encrypt_mode, Some(EncryptMode::None));
+    assert_eq!(metadata.file_size, size);
+}
+
+#[test]
+fn test_zlib_and_raw_deflate_streams_are_detected() {
+    let dir = TempDir::new().unwrap();
+    let payloads: Vec<Vec<u8>> = (0..3)
+        .map(|i| encode_log(0, 0, "Tag", &format!("compressed {}", i)))
+        .collect();
+
+    for (zlib_header, expected) in [(true, DeflateWrapper::Zlib), (false, DeflateWrapper::Raw)] {
+        let path = write_fixture(&dir, v3_zlib_file(&payloads, zlib_header));
+        let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(reader.metadata().compress_mode, CompressMode::Zlib);
+        assert_eq!(reader.metadata().deflate_wrapper, None);
+
+        let msgs: Vec<_> = reader.logs().map(|log| log.unwrap().msg).collect();
+        assert_eq!(msgs, ["compressed 0", "compressed 1", "compressed 2"]);
+        assert_eq!(reader.metadata().deflate_wrapper, Some(expected));
+    }
+}
+
+#[test]
+fn test_metadata_v4_modes_come_from_first_entry() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v4_file(&[encode_log(0, 0, "Tag", "v4")]));
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(reader.metadata().version, GLOG_CIPHER_VERSION);
+    assert_eq!(reader.metadata().proto_name, PROTO_NAME);
+    assert_eq!(reader.metadata().encrypt_mode, None);
+
+    assert_eq!(reader.logs().next().unwrap().unwrap().msg, "v4");
+    assert_eq!(reader.metadata().encrypt_mode, Some(EncryptMode::None));
+}
+
+/// 逐条读取由 `header` 和 `entries` 拼接成的文件，
+/// 断言每次读取后的位置都等于已消耗的字节数
+fn assert_position_tracks_bytes(header: Vec<u8>, entries: Vec<Vec<u8>>) {
+    let dir = TempDir::new().unwrap();
+    let mut content = header.clone();
+    for entry in &entries {
+        content.extend_from_slice(entry);
+    }
+    let path = write_fixture(&dir, content.clone());
+
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Abort)
+        .open(path.to_str().unwrap())
+        .unwrap();
+    assert_eq!(reader.len(), content.len() as u64);
+    assert_eq!(reader.position(), header.len() as u64);
+
+    let mut buf = vec![0u8; GlogReader::single_log_max_length()];
+    let mut consumed = header.len() as u64;
+    for entry in &entries {
+        match reader.read(&mut buf).as_ref().map_err(GlogError::kind) {
+            Ok(ReadResult::Success(_)) | Err(GlogError::NeedRecover(_)) => {}
+            other => panic!("意外的读取结果: {:?}", other),
+        }
+        consumed += entry.len() as u64;
+        assert_eq!(reader.position(), consumed);
+    }
+    assert!(matches!(reader.read(&mut buf).unwrap(), ReadResult::Eof));
+    assert_eq!(reader.progress(), 1.0);
+}
+
+/// 同步标记被破坏的条目
+fn with_bad_sync_marker(mut entry: Vec<u8>) -> Vec<u8> {
+    let len = entry.len();
+    entry[len - 1] ^= 0xFF;
+    entry
+}
+
+#[test]
+fn test_position_tracks_bytes_consumed_v3() {
+    assert_position_tracks_bytes(
+        v3_header(),
+        vec![
+            v3_entry(&encode_log(0, 0, "Tag", "first")),
+            with_bad_sync_marker(v3_entry(&encode_log(0, 0, "Tag", "bad"))),
+            v3_entry(&encode_log(0, 0, "Tag", "third")),
+        ],
+    );
+}
+
+#[test]
+fn test_position_tracks_bytes_consumed_v4() {
+    assert_position_tracks_bytes(
+        v4_header(),
+        vec![
+            v4_entry(&encode_log(0, 0, "Tag", "first")),
+            with_bad_sync_marker(v4_entry(&encode_log(0, 0, "Tag", "bad"))),
+            v4_entry(&encode_log(0, 0, "Tag", "third")),
+        ],
+    );
+}
+
+#[test]
+fn test_progress() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_logs(2));
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let start = reader.progress();
+    assert!(start > 0.0 && start < 1.0);
+
+    let mut logs = reader.logs();
+    logs.next().unwrap().unwrap();
+    let middle = logs.position() as f64;
+    drop(logs);
+    assert!(reader.progress() > start);
+    assert_eq!(reader.progress(), middle / reader.len() as f64);
+
+    reader.logs().for_each(drop);
+    assert_eq!(reader.progress(), 1.0);
+}
+
+#[test]
+fn test_from_reader_in_memory() {
+    for content in [
+        v3_file(&[encode_log(0, 0, "Tag", "in memory")]),
+        v4_file(&[encode_log(0, 0, "Tag", "in memory")]),
+    ] {
+        let size = content.len() as u64;
+        let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+        let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+        assert_eq!(msgs, ["in memory"]);
+    }
+}
+
+#[test]
+fn test_from_reader_rejects_bad_magic() {
+    let content = b"not a glog file".to_vec();
+    let size = content.len() as u64;
+    let result = GlogReader::from_reader(Cursor::new(content), size, None);
+    assert!(matches!(result, Err(GlogError::MagicMismatch)));
+}
+
+#[test]
+fn test_logs_resync_after_junk() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_junk());
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["before 0", "before 1", "after 1", "after 2"]);
+}
+
+#[test]
+fn test_corrupt_compressed_payload_recovers_v3() {
+    let dir = TempDir::new().unwrap();
+    let before: Vec<Vec<u8>> = (0..2)
+        .map(|i| encode_log(0, 0, "Tag", &format!("before {}", i)))
+        .collect();
+    let mut content = v3_zlib_file(&before, false);
+    // 第二条日志的压缩数据首字节改为保留的块类型，解压必然失败
+    let second = v3_zlib_file(&before[..1], false).len();
+    content[second + 2] = 0xFF;
+    // 之后的日志使用新的压缩流，不引用损坏条目之前的字典
+    let after: Vec<Vec<u8>> = (0..2)
+        .map(|i| encode_log(0, 0, "Tag", &format!("after {}", i)))
+        .collect();
+    content.extend_from_slice(&v3_zlib_file(&after, false)[v3_header().len()..]);
+    let path = write_fixture(&dir, content);
+
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["before 0", "after 0", "after 1"]);
+    assert_eq!(reader.stats().recoveries, 1);
+}
+
+#[test]
+fn test_v3_recover_reasons_keep_legacy_codes() {
+    let dir = TempDir::new().unwrap();
+    for (content, expected) in [(v3_file_with_junk(), -2), (v3_file_with_corrupt_entry(), -3)] {
+        let path = write_fixture(&dir, content);
+        let mut reader = GlogReader::builder()
+            .recovery_policy(RecoveryPolicy::Abort)
+            .open(path.to_str().unwrap())
+            .unwrap();
+        let mut buf = vec![0u8; GlogReader::single_log_max_length()];
+        let reason = loop {
+            match reader.read(&mut buf).as_ref().map_err(GlogError::kind) {
+                Ok(ReadResult::Success(_)) => {}
+                Err(GlogError::NeedRecover(reason)) => break *reason,
+                other => panic!("{:?}", other),
+            }
+        };
+        assert_eq!(reason.legacy_code(reader.metadata().version), expected);
+    }
+}
+
+#[test]
+fn test_recover_skips_to_next_sync_marker() {
+    let dir = TempDir::new().unwrap();
+    let content = v3_file_with_junk();
+    let size = content.len() as u64;
+    let path = write_fixture(&dir, content);
+
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Abort)
+        .open(path.to_str().unwrap())
+        .unwrap();
+    let mut buf = vec![0u8; GlogReader::single_log_max_length()];
+    for _ in 0..2 {
+        assert!(matches!(reader.read(&mut buf).unwrap(), ReadResult::Success(_)));
+    }
+    assert!(matches!(
+        reader.read(&mut buf).as_ref().map_err(GlogError::kind),
+        Err(GlogError::NeedRecover(RecoverReason::InvalidLogLength(0xABAB)))
+    ));
+
+    assert!(reader.recover().unwrap());
+    let after = v3_entry(&encode_log(0, 0, "Tag", "after 1")).len() as u64;
+    assert_eq!(reader.position(), size - 2 * after);
+}
+
+/// 读取文件中的全部日志内容
+fn read_msgs(content: Vec<u8>) -> Vec<String> {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, content);
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    reader.logs().map(|log| log.unwrap().msg).collect()
+}
+
+#[test]
+fn test_legacy_v2() {
+    let payloads: Vec<Vec<u8>> = (0..3)
+        .map(|i| encode_log(0, 0, "Tag", &format!("v2 {}", i)))
+        .collect();
+    let content = legacy_file(GLOG_FIX_POSITION_VERSION, &payloads);
+
+    assert_eq!(read_msgs(content), ["v2 0", "v2 1", "v2 2"]);
+}
+
+#[test]
+fn test_legacy_v1_skips_leading_padding() {
+    let payload = encode_log(0, 0, "Tag", "v1");
+    let mut content = legacy_header(GLOG_INITIAL_VERSION);
+    content.extend_from_slice(&[0u8; 6]);
+    content.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    content.extend_from_slice(&payload);
+
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, content);
+    let mut reader = glog::open(path.to_str().unwrap()).unwrap();
+    assert_eq!(reader.metadata().version, GLOG_INITIAL_VERSION);
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["v1"]);
+}
+
+#[test]
+fn test_legacy_corrupt_entry_ends_file() {
+    let mut content = legacy_header(GLOG_FIX_POSITION_VERSION);
+    let good = encode_log(0, 0, "Tag", "good");
+    content.extend_from_slice(&(good.len() as u16).to_le_bytes());
+    content.extend_from_slice(&good);
+    // 超出上限的长度：没有同步标记可供恢复，剩余部分被整体跳过
+    content.extend_from_slice(&u16::MAX.to_le_bytes());
+    content.extend_from_slice(&[0xAB; 100]);
+    let size = content.len() as u64;
+
+    let events: Arc<Mutex<Vec<RecoverEvent>>> = Arc::default();
+    let sink = Arc::clone(&events);
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, content);
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Skip {
+            on_event: Box::new(move |event| sink.lock().unwrap().push(event)),
+        })
+        .open(path.to_str().unwrap())
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["good"]);
+    assert_eq!(events.lock().unwrap().len(), 1);
+    assert_eq!(reader.position(), size);
+}
+
+/// 明文与加密条目交替写入的 V4 文件（均不压缩）
+fn v4_file_with_encrypted_entries() -> Vec<u8> {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let server_public_key = server_key.public_key().to_encoded_point(true);
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_public_key.as_bytes())
+        .unwrap();
+    for i in 0..6 {
+        let encrypt_mode = if i % 2 == 0 { EncryptMode::None } else { EncryptMode::Aes };
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, CompressMode::None, encrypt_mode).unwrap();
+    }
+    writer.into_inner().unwrap()
+}
+
+#[test]
+fn test_skip_encrypted_entries_without_key() {
+    let content = v4_file_with_encrypted_entries();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::builder()
+        .skip_encrypted(true)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["entry 0", "entry 2", "entry 4"]);
+    assert_eq!(reader.metadata().skipped_encrypted, 3);
+    assert_eq!(reader.position(), size);
+}
+
+#[test]
+fn test_encrypted_entry_without_key_is_error_by_default() {
+    let content = v4_file_with_encrypted_entries();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let results: Vec<_> = reader.logs().collect();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().msg, "entry 0");
+    assert!(matches!(
+        results[1].as_ref().map_err(GlogError::kind),
+        Err(GlogError::CipherNotReady)
+    ));
+}
+
+/// 读取前 `n` 条日志，返回之后的读取位置作为断点
+fn checkpoint_after(path: &str, n: usize, key: Option<String>) -> u64 {
+    let mut reader = GlogReader::with_key(path, key).unwrap();
+    assert_eq!(reader.logs().take(n).count(), n);
+    reader.position()
+}
+
+#[test]
+fn test_open_at_resumes_v3_from_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_logs(5));
+    let path = path.to_str().unwrap();
+    let checkpoint = checkpoint_after(path, 2, None);
+
+    let mut reader = GlogReader::open_at(path, checkpoint, None).unwrap();
+    assert_eq!(reader.position(), checkpoint);
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["message 2", "message 3", "message 4"]);
+
+    // 偏移落在条目中间时扫描到下一个同步标记
+    let mut reader = GlogReader::open_at(path, checkpoint + 3, None).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["message 3", "message 4"]);
+
+    // 偏移位于文件头内时从第一条日志开始
+    let mut reader = GlogReader::open_at(path, 0, None).unwrap();
+    assert_eq!(reader.logs().count(), 5);
+}
+
+#[test]
+fn test_open_at_resumes_encrypted_v4() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let key_hex = hex::encode(server_key.to_bytes());
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..4 {
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, CompressMode::None, EncryptMode::Aes).unwrap();
+    }
+
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, writer.into_inner().unwrap());
+    let path = path.to_str().unwrap();
+    let checkpoint = checkpoint_after(path, 3, Some(key_hex.clone()));
+
+    let mut reader = GlogReader::open_at(path, checkpoint, Some(key_hex)).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["entry 3"]);
+    assert_eq!(reader.progress(), 1.0);
+}
+
+#[test]
+fn test_debug_output_redacts_keys() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let key_hex = hex::encode(server_key.to_bytes());
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let payload = encode_log(0, 0, "Tag", "secret");
+    writer.write_entry(&payload, CompressMode::Zlib, EncryptMode::Aes).unwrap();
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let builder = GlogReader::builder().key(key_hex.clone());
+    let builder_debug = format!("{:?}", builder);
+    let mut reader = builder
+        .from_reader(Cursor::new(content.clone()), size)
+        .unwrap();
+    assert_eq!(reader.logs().next().unwrap().unwrap().msg, "secret");
+    let v4 = FileReaderV4::from_reader(Cursor::new(content), size, Some(key_hex.clone())).unwrap();
+
+    for debug in [builder_debug, format!("{:?}", reader), format!("{:#?}", v4)] {
+        assert!(debug.contains("<redacted>"), "{}", debug);
+        assert!(!debug.contains(&key_hex), "{}", debug);
+        assert!(!debug.contains(&key_hex.to_uppercase()), "{}", debug);
+    }
+    assert!(format!("{:?}", GlogReader::builder()).contains("key: None"));
+}
+
+#[test]
+fn test_metadata_reports_key_cache_stats() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..4 {
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, CompressMode::None, EncryptMode::Aes).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::builder()
+        .key(hex::encode(server_key.to_bytes()))
+        .key_cache_capacity(8)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    assert_eq!(reader.logs().count(), 4);
+    // 同一客户端公钥只计算一次共享密钥
+    let stats = reader.metadata().key_cache.unwrap();
+    assert_eq!((stats.hits, stats.misses), (3, 1));
+
+    let content = v3_file(&[encode_log(0, 0, "Tag", "plain")]);
+    let size = content.len() as u64;
+    let reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    assert_eq!(reader.metadata().key_cache, None);
+}
+
+#[test]
+fn test_unbounded_key_cache_capacity_allocates_on_demand() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let payload = encode_log(0, 0, "Tag", "entry");
+    writer
+        .write_entry(&payload, CompressMode::None, EncryptMode::Aes)
+        .unwrap();
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    // 单个私钥和多个私钥（每个私钥一个缓存）都不按容量预先分配
+    let other_key = hex::encode(SecretKey::from_slice(&[0x11; 32]).unwrap().to_bytes());
+    let server_key = hex::encode(server_key.to_bytes());
+    for keys in [vec![server_key.clone()], vec![other_key, server_key]] {
+        let mut reader = GlogReader::builder()
+            .keys(keys)
+            .key_cache_capacity(usize::MAX)
+            .from_reader(Cursor::new(content.clone()), size)
+            .unwrap();
+        assert_eq!(reader.logs().count(), 1);
+    }
+}
+
+#[test]
+fn test_open_at_rejects_legacy_format() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, legacy_file(GLOG_FIX_POSITION_VERSION, &[encode_log(0, 0, "Tag", "v2")]));
+
+    let result = GlogReader::open_at(path.to_str().unwrap(), 0, None);
+    assert!(matches!(
+        result.as_ref().map_err(GlogError::kind),
+        Err(GlogError::UnsupportedVersion(GLOG_FIX_POSITION_VERSION))
+    ));
+}
+
+#[test]
+fn test_read_owned_returns_entries_larger_than_max_length() {
+    let large: Vec<u8> = b"glog ".iter().copied().cycle().take(100 * 1024).collect();
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, PROTO_NAME).unwrap();
+    writer.write(&large).unwrap();
+    writer.write(b"small").unwrap();
+    writer.write(&large).unwrap();
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+    assert_eq!(reader.read_owned().unwrap().unwrap(), large);
+    assert_eq!(reader.read_owned().unwrap().unwrap(), b"small");
+    assert_eq!(reader.read_owned().unwrap().unwrap(), large);
+    assert!(reader.read_owned().unwrap().is_none());
+
+    // 固定大小的缓冲区放不下时报告所需长度，后续日志不受影响
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let mut buf = vec![0u8; GlogReader::single_log_max_length()];
+    assert!(matches!(
+        reader.read(&mut buf).as_ref().map_err(GlogError::kind),
+        Err(GlogError::Truncated { required, .. }) if *required == large.len()
+    ));
+    assert!(matches!(reader.read(&mut buf), Ok(ReadResult::Success(5))));
+    assert_eq!(&buf[..5], b"small");
+}
+
+#[test]
+fn test_builder_keys_tries_each_key() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..3 {
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, CompressMode::Zlib, EncryptMode::Aes).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    // 轮换前的旧私钥排在前面，读取器应验证失败后改用第二个私钥
+    let old_key = hex::encode(SecretKey::from_slice(&[0x33; 32]).unwrap().to_bytes());
+    let mut reader = GlogReader::builder()
+        .keys([old_key, hex::encode(server_key.to_bytes())])
+        .recovery_policy(RecoveryPolicy::Abort)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["entry 0", "entry 1", "entry 2"]);
+}
+
+#[test]
+fn test_builder_max_log_length_rejects_longer_entries() {
+    let short = encode_log(0, 0, "Tag", "short");
+    let content = v3_file(&[short.clone(), encode_log(0, 0, "Tag", &"x".repeat(64))]);
+    let size = content.len() as u64;
+
+    let events: Arc<Mutex<Vec<RecoverEvent>>> = Arc::default();
+    let sink = Arc::clone(&events);
+    let mut reader = GlogReader::builder()
+        .max_log_length(short.len())
+        .recovery_policy(RecoveryPolicy::Skip {
+            on_event: Box::new(move |event| sink.lock().unwrap().push(event)),
+        })
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["short"]);
+    assert_eq!(events.lock().unwrap().len(), 1);
+    assert!(matches!(events.lock().unwrap()[0].reason, RecoverReason::InvalidLogLength(_)));
+}
+
+/// 统计底层 `read` 调用次数的输入流
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    reads: Arc<AtomicUsize>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.inner.read(buf)
+    }
+}
+
+#[test]
+fn test_builder_buffer_capacity_wraps_reader() {
+    let content = v3_file_with_logs(20);
+    let size = content.len() as u64;
+    let count_reads = |builder: glog::GlogReaderBuilder| {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let input = CountingReader {
+            inner: Cursor::new(content.clone()),
+            reads: Arc::clone(&reads),
+        };
+        let mut reader = builder.from_reader(input, size).unwrap();
+        assert_eq!(reader.logs().count(), 20);
+        reads.load(Ordering::Relaxed)
+    };
+
+    let unbuffered = count_reads(GlogReader::builder());
+    let buffered = count_reads(GlogReader::builder().buffer_capacity(64 * 1024));
+
+    assert!(buffered < unbuffered, "buffered={} unbuffered={}", buffered, unbuffered);
+}
+
+#[cfg(feature = "memmap")]
+#[test]
+fn test_use_mmap_matches_buffered_reads() {
+    let dir = TempDir::new().unwrap();
+    let fixtures = [
+        (v3_file_with_logs(20), None),
+        (v3_file_with_junk(), None),
+        (v4_file_with_encrypted_entries(), Some("5a".repeat(32))),
+    ];
+    for (content, key) in fixtures {
+        let path = write_fixture(&dir, content);
+        let read = |use_mmap: bool| {
+            let builder = GlogReader::builder().use_mmap(use_mmap);
+            let builder = match &key {
+                Some(key) => builder.key(key.as_str()),
+                None => builder,
+            };
+            let mut reader = builder.open(path.to_str().unwrap()).unwrap();
+            let msgs: Vec<_> = reader.logs().map(|log| log.ok().map(|log| log.msg)).collect();
+            (msgs, reader.position())
+        };
+        assert_eq!(read(true), read(false));
+    }
+}
+
+#[test]
+fn test_builder_strict_stops_at_first_error() {
+    // 解码失败后结束迭代
+    let content = v3_file(&[vec![0xFF, 0xFF, 0xFF], encode_log(0, 0, "Tag", "ok")]);
+    let size = content.len() as u64;
+    let mut reader = GlogReader::builder()
+        .strict(true)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let items: Vec<_> = reader.logs().collect();
+    assert_eq!(items.len(), 1);
+    assert!(matches!(
+        items[0].as_ref().map_err(GlogError::kind),
+        Err(GlogError::ProtobufError(_))
+    ));
+
+    // 损坏条目不再按恢复策略跳过
+    let content = v3_file_with_corrupt_entry();
+    let size = content.len() as u64;
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Skip { on_event: Box::new(|_| {}) })
+        .strict(true)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let items: Vec<_> = reader.logs().collect();
+    assert_eq!(items.len(), 2);
+    assert!(matches!(
+        items[1].as_ref().map_err(GlogError::kind),
+        Err(GlogError::NeedRecover(_))
+    ));
+}
+
+#[test]
+fn test_mmap_file_stops_at_write_cursor() {
+    let content = v3_file_with_logs(3);
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, mmap_file(&content, 4096));
+    let path = path.to_str().unwrap();
+
+    let mut reader = glog::open(path).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["message 0", "message 1", "message 2"]);
+    assert_eq!(reader.position(), 8 + content.len() as u64);
+    assert_eq!(reader.progress(), 1.0);
+    assert_eq!(reader.metadata().file_size, 8 + content.len() as u64 + 4096);
+
+    // 断点续读使用缓存文件中的偏移
+    let checkpoint = checkpoint_after(path, 1, None);
+    let mut reader = GlogReader::open_at(path, checkpoint, None).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["message 1", "message 2"]);
+}
+
+/// 在最后一条日志内部的各个位置截断文件，验证之前的日志照常读出、截断部分按文件结束处理
+fn assert_truncated_tail_is_eof(content: &[u8], last_entry_len: usize, key: Option<String>) {
+    let last_start = content.len() - last_entry_len;
+    for kept in [1, 2, 3, 10, 20, last_entry_len / 2, last_entry_len - 8, last_entry_len - 1] {
+        let truncated = content[..last_start + kept].to_vec();
+        let size = truncated.len() as u64;
+
+        let mut builder = GlogReader::builder().recovery_policy(RecoveryPolicy::Abort);
+        if let Some(key) = &key {
+            builder = builder.key(key.as_str());
+        }
+        let mut reader = builder.from_reader(Cursor::new(truncated), size).unwrap();
+        let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+        assert_eq!(msgs, ["entry 0", "entry 1"], "保留 {} 字节", kept);
+        assert_eq!(reader.metadata().truncated_tail_bytes, kept as u64, "保留 {} 字节", kept);
+    }
+}
+
+#[test]
+fn test_truncated_final_entry_is_eof_v3() {
+    let payloads: Vec<Vec<u8>> = (0..3).map(|i| encode_log(0, 0, "Tag", &format!("entry {}", i))).collect();
+    let content = v3_file(&payloads);
+    assert_truncated_tail_is_eof(&content, v3_entry(&payloads[2]).len(), None);
+}
+
+#[test]
+fn test_truncated_final_entry_is_eof_v4() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let mut last = Vec::new();
+    for i in 0..3 {
+        last = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&last, CompressMode::None, EncryptMode::Aes).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+
+    // 模式(1) + IV(16) + 公钥(33) + 长度(2) + 数据 + 同步标记(8)
+    let last_entry_len = 1 + 16 + 33 + 2 + last.len() + 8;
+    assert_truncated_tail_is_eof(&content, last_entry_len, Some(hex::encode(server_key.to_bytes())));
+}
+
+#[test]
+fn test_truncated_header_is_error() {
+    let content = v3_header();
+    let truncated = content[..content.len() - 3].to_vec();
+    let size = truncated.len() as u64;
+
+    assert!(GlogReader::from_reader(Cursor::new(truncated), size, None).is_err());
+}
+
+/// 把文件头中的协议名称长度字段改为 `len`，名称之后的数据保持不变
+fn with_proto_name_len(mut header: Vec<u8>, offset: usize, len: u16) -> Vec<u8> {
+    header[offset..offset + 2].copy_from_slice(&len.to_le_bytes());
+    header
+}
+
+#[test]
+fn test_malformed_headers_are_graceful_errors() {
+    let mut long_name = v4_header();
+    long_name.splice(5..5 + 2 + PROTO_NAME.len(), [0u8; 2 + 1100]);
+    let long_name = with_proto_name_len(long_name, 5, 1100);
+    let cases: Vec<(&str, Vec<u8>, Option<&str>)> = vec![
+        ("空文件", Vec::new(), None),
+        (
+            "V3 名称长度 65535",
+            with_proto_name_len(v3_header(), 6, u16::MAX),
+            Some("协议名称长度 65535 超过上限 1024，位置: 6"),
+        ),
+        (
+            "V4 名称长度 65535",
+            with_proto_name_len(v4_header(), 5, u16::MAX),
+            Some("协议名称长度 65535 超过上限 1024，位置: 5"),
+        ),
+        (
+            "V4 名称长度超过上限但数据足够",
+            long_name,
+            Some("协议名称长度 1100 超过上限 1024"),
+        ),
+        (
+            "V3 名称长度超出剩余数据",
+            with_proto_name_len(v3_header(), 6, 200),
+            Some("协议名称长度 200 超出文件头剩余的 11 字节，位置: 6"),
+        ),
+        (
+            "V3 同步标记被截断",
+            v3_header()[..v3_header().len() - 3].to_vec(),
+            Some("超出文件头剩余的 8 字节"),
+        ),
+        (
+            "V4 同步标记被截断",
+            v4_header()[..v4_header().len() - 3].to_vec(),
+            Some("超出文件头剩余的 8 字节"),
+        ),
+    ];
+
+    for (name, content, expected) in cases {
+        let size = content.len() as u64;
+        let err = match GlogReader::from_reader(Cursor::new(content), size, None) {
+            Ok(_) => panic!("{}: 应当返回错误", name),
+            Err(e) => e,
+        };
+        match expected {
+            Some(message) => {
+                assert!(
+                    matches!(err, GlogError::FileCorrupt(_)),
+                    "{}: {:?}",
+                    name,
+                    err
+                );
+                assert!(err.to_string().contains(message), "{}: {}", name, err);
+            }
+            None => assert!(
+                matches!(err, GlogError::UnexpectedEof { .. }),
+                "{}: {:?}",
+                name,
+                err
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_overlong_length_before_sync_marker_recovers() {
+    // 长度字段损坏但之后还有同步标记，说明不是截断而是损坏，应当恢复而不是结束
+    let mut content = v3_header();
+    content.extend(v3_entry(&encode_log(0, 0, "Tag", "entry 0")));
+    let mut corrupt = v3_entry(&encode_log(0, 0, "Tag", "lost"));
+    corrupt[..2].copy_from_slice(&200u16.to_le_bytes());
+    content.extend(corrupt);
+    content.extend(v3_entry(&encode_log(0, 0, "Tag", "entry 1")));
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    assert_eq!(msgs, ["entry 0", "entry 1"]);
+    assert_eq!(reader.metadata().truncated_tail_bytes, 0);
+}
+
+#[test]
+fn test_read_entry_reports_envelope() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let payload = encode_log(0, 0, "Tag", &"repeated ".repeat(20));
+    writer.write_entry(&payload, CompressMode::None, EncryptMode::None).unwrap();
+    writer.write_entry(&payload, CompressMode::Zlib, EncryptMode::Aes).unwrap();
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::builder()
+        .key(hex::encode(server_key.to_bytes()))
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let mut buf = Vec::new();
+
+    let plain = reader.read_entry(&mut buf).unwrap().unwrap();
+    assert_eq!(buf, payload);
+    assert_eq!(plain.offset, v4_header().len() as u64);
+    assert_eq!(plain.entry_len, (1 + 2 + payload.len() + 8) as u64);
+    assert_eq!(plain.stored_len, payload.len());
+    assert!(!plain.compressed && !plain.encrypted);
+    assert_eq!(plain.client_pubkey, None);
+    assert_eq!(plain.len, payload.len());
+
+    let cipher = reader.read_entry(&mut buf).unwrap().unwrap();
+    assert_eq!(buf, payload);
+    assert_eq!(cipher.offset, plain.offset + plain.entry_len);
+    assert_eq!(cipher.offset + cipher.entry_len, size);
+    assert!(cipher.compressed && cipher.encrypted);
+    assert_eq!(cipher.compress_mode, CompressMode::Zlib);
+    assert!(cipher.client_pubkey.is_some());
+    assert!(cipher.stored_len < payload.len());
+    assert_eq!(cipher.len, payload.len());
+
+    assert!(reader.read_entry(&mut buf).unwrap().is_none());
+}
+
+#[test]
+fn test_read_batch_straddles_eof() {
+    let content = v3_file_with_logs(5);
+    let size = content.len() as u64;
+    let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+
+    let batches: Vec<Vec<String>> = (0..4)
+        .map(|_| {
+            reader
+                .read_batch(2)
+                .unwrap()
+                .into_iter()
+                .map(|log| log.msg)
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        batches,
+        [
+            vec!["message 0", "message 1"],
+            vec!["message 2", "message 3"],
+            vec!["message 4"],
+            vec![],
+        ]
+    );
+    assert_eq!(reader.position(), size);
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let raw = reader.read_batch_raw(10).unwrap();
+    assert_eq!(raw.len(), 5);
+    assert_eq!(raw[3], encode_log(3, 0, "Tag", "message 3"));
+    assert!(reader.read_batch_raw(10).unwrap().is_empty());
+    assert!(reader.read_batch(0).unwrap().is_empty());
+}
+
+#[test]
+fn test_read_batch_recovers_inside_batch() {
+    let content = v3_file_with_corrupt_entry();
+    let size = content.len() as u64;
+    let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+    let expected: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let msgs: Vec<String> = reader
+        .read_batch(10)
+        .unwrap()
+        .into_iter()
+        .map(|log| log.msg)
+        .collect();
+    assert_eq!(msgs, expected);
+    assert_eq!(reader.stats().recoveries, 1);
+}
+
+#[test]
+fn test_read_batch_defers_error_to_next_call() {
+    let content = v3_file_with_corrupt_entry();
+    let size = content.len() as u64;
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Abort)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+
+    // 已读出的日志先返回，错误在下一次调用时返回
+    let first = reader.read_batch(10).unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].msg, "good");
+    let error = reader.read_batch(10).unwrap_err();
+    assert!(matches!(error.kind(), GlogError::NeedRecover(_)));
+    assert!(reader.recover().unwrap());
+    let rest: Vec<String> = reader
+        .read_batch(10)
+        .unwrap()
+        .into_iter()
+        .map(|log| log.msg)
+        .collect();
+    assert_eq!(rest, ["after"]);
+}
+
+#[test]
+fn test_v4_copy_from_reencrypts_for_new_key() {
+    let old_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let new_key = SecretKey::from_slice(&[0x3C; 32]).unwrap();
+    let modes = [CompressMode::None, CompressMode::Zlib, CompressMode::Zstd];
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(old_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for (i, compress_mode) in modes.iter().cycle().take(9).enumerate() {
+        let encrypt_mode = if i % 2 == 0 { EncryptMode::Aes } else { EncryptMode::None };
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, *compress_mode, encrypt_mode).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::builder()
+        .key(hex::encode(old_key.to_bytes()))
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(new_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    assert_eq!(writer.copy_from(&mut reader).unwrap(), 9);
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::builder()
+        .key(hex::encode(new_key.to_bytes()))
+        .from_reader(Cursor::new(content.clone()), size)
+        .unwrap();
+    let mut buf = Vec::new();
+    for (i, compress_mode) in modes.iter().cycle().take(9).enumerate() {
+        let entry = reader.read_entry(&mut buf).unwrap().unwrap();
+        assert_eq!(entry.compress_mode, *compress_mode);
+        assert!(entry.encrypted);
+        let log = clog_reader::Log::decode_from(&buf).unwrap();
+        assert_eq!(log.msg, format!("entry {}", i));
+    }
+    assert!(reader.read_entry(&mut buf).unwrap().is_none());
+
+    // 旧私钥协商出的 AES 密钥不同，解密结果不是原来的日志
+    let mut reader = GlogReader::builder()
+        .key(hex::encode(old_key.to_bytes()))
+        .recovery_policy(RecoveryPolicy::SkipSilently)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let recovered = reader
+        .logs()
+        .filter_map(|log| log.ok())
+        .filter(|log| log.msg.starts_with("entry "))
+        .count();
+    assert_eq!(recovered, 0);
+}
+
+#[test]
+fn test_v4_copy_raw_from_strips_encryption_without_recompressing() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let modes = [CompressMode::Zlib, CompressMode::None, CompressMode::Zstd];
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for (i, compress_mode) in modes.iter().cycle().take(9).enumerate() {
+        let encrypt_mode = if i % 3 == 2 {
+            EncryptMode::None
+        } else {
+            EncryptMode::Aes
+        };
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer
+            .write_entry(&payload, *compress_mode, encrypt_mode)
+            .unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+    let open = |content: &[u8], key: Option<&str>| {
+        let mut builder = GlogReader::builder();
+        if let Some(key) = key {
+            builder = builder.key(key);
+        }
+        builder
+            .from_reader(Cursor::new(content.to_vec()), content.len() as u64)
+            .unwrap()
+    };
+    let key = hex::encode(server_key.to_bytes());
+
+    let mut reader = open(&content, Some(&key));
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME).unwrap();
+    assert_eq!(writer.copy_raw_from(&mut reader).unwrap(), 9);
+    assert_eq!(reader.position(), size);
+    let plain = writer.into_inner().unwrap();
+
+    // 文件头不变，每条日志的存储数据与原文件解密后的逐字节相同
+    let header_len = 4 + 1 + 2 + PROTO_NAME.len() + 8;
+    assert_eq!(plain[..header_len], content[..header_len]);
+    let (mut original, mut stripped) = (open(&content, Some(&key)), open(&plain, None));
+    let (mut expected, mut actual) = (Vec::new(), Vec::new());
+    for compress_mode in modes.iter().cycle().take(9) {
+        let entry = stripped.read_raw(&mut actual).unwrap().unwrap();
+        assert!(!entry.encrypted);
+        assert_eq!(entry.compress_mode, *compress_mode);
+        original.read_raw(&mut expected).unwrap().unwrap();
+        assert_eq!(actual, expected);
+    }
+    assert!(stripped.read_raw(&mut actual).unwrap().is_none());
+
+    // 不需要私钥即可解码
+    let msgs: Vec<String> = open(&plain, None)
+        .logs()
+        .map(|log| log.unwrap().msg)
+        .collect();
+    let expected: Vec<String> = (0..9).map(|i| format!("entry {}", i)).collect();
+    assert_eq!(msgs, expected);
+}
+
+#[test]
+fn test_read_raw_requires_v4() {
+    let content = v3_file_with_logs(1);
+    let size = content.len() as u64;
+    let mut reader = GlogReader::builder()
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let error = reader.read_raw(&mut Vec::new()).unwrap_err();
+    assert!(matches!(error.kind(), GlogError::UnsupportedVersion(3)));
+}
+
+#[cfg(feature = "dynamic")]
+#[test]
+fn test_descriptor_set_decodes_message_named_in_header() {
+    use clog_reader::dynamic::DescriptorSet;
+    use prost::Message;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Event {
+        #[prost(int32, tag = "1")]
+        log_type: i32,
+        #[prost(string, tag = "7")]
+        body: String,
+        #[prost(uint32, tag = "9")]
+        retries: u32,
+    }
+
+    let field = |name: &str, number: i32, ty: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        label: Some(Label::Optional as i32),
+        r#type: Some(ty as i32),
+        ..Default::default()
+    };
+    let descriptor = |message: &str| {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("demo.proto".to_string()),
+                package: Some("demo".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some(message.to_string()),
+                    field: vec![
+                        field("log_type", 1, Type::Int32),
+                        field("body", 7, Type::String),
+                        field("retries", 9, Type::Uint32),
+                    ],
+                    ..Default::default()
+                }],
+                syntax: Some("proto3".to_string()),
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    };
+
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, "Event").unwrap();
+    let event = Event {
+        log_type: 5,
+        body: "timeout".to_string(),
+        retries: 2,
+    };
+    writer.write(&event.encode_to_vec()).unwrap();
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    // 描述符中有文件头记录的消息类型：未映射的字段追加到 msg
+    let mut reader = GlogReader::builder()
+        .descriptor_set(DescriptorSet::Bytes(descriptor("Event")))
+        .from_reader(Cursor::new(content.clone()), size)
+        .unwrap();
+    assert_eq!(reader.dynamic_decoder().unwrap().message_name(), "demo.Event");
+    let log = reader.logs().next().unwrap().unwrap();
+    assert_eq!(log.log_type, 5);
+    assert_eq!(log.msg, "timeout retries=2");
+
+    // 描述符中没有该消息类型时按内置的 Log 解码
+    let mut reader = GlogReader::builder()
+        .descriptor_set(DescriptorSet::Bytes(descriptor("Other")))
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    assert!(reader.dynamic_decoder().is_none());
+    assert_eq!(reader.logs().next().unwrap().unwrap().msg, "timeout");
+}
+
+#[test]
+fn test_logs_with_filter_skips_non_matching() {
+    use clog_reader::proto::{Level, LogFilter};
+
+    let content = v3_file(&[
+        encode_log(1, 0, "Net", "connected"),
+        encode_log(1, 4, "Net", "timeout"),
+        encode_log(2, 4, "Ui", "crash"),
+    ]);
+    let size = content.len() as u64;
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    let filter = LogFilter::new().types([1]).min_level(Level::Warn);
+    let msgs: Vec<_> = reader
+        .logs()
+        .with_filter(filter)
+        .map(|log| log.unwrap().msg)
+        .collect();
+    assert_eq!(msgs, ["timeout"]);
+}
+
+#[test]
+fn test_type_filter_skips_entries_before_decoding() {
+    use clog_reader::proto::LogFilter;
+
+    // 类型字段之后的数据损坏，只有完整解码时才会发现
+    let content = v3_file(&[
+        vec![0x08, 0x02, 0xFF, 0xFF],
+        encode_log(1, 0, "Net", "keep"),
+    ]);
+    let size = content.len() as u64;
+    let filter = LogFilter::new().types([1]);
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+    let items: Vec<_> = reader.logs().with_filter(filter.clone()).collect();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].as_ref().unwrap().msg, "keep");
+    assert_eq!(reader.stats().decode_failures, 0);
+
+    // 严格模式完整解码每条日志，仍然报告损坏
+    let mut reader = GlogReader::builder()
+        .strict(true)
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let first = reader.logs().with_filter(filter).next().unwrap();
+    assert!(matches!(first.unwrap_err().kind(), GlogError::ProtobufError(_)));
+}
+
+/// 逐条读取并记录每条日志的位置，作为 `scan` 的对照
+fn read_offsets(content: Vec<u8>, key: Option<String>) -> Vec<EntryOffset> {
+    let size = content.len() as u64;
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, key).unwrap();
+    let mut buf = Vec::new();
+    let mut offsets = Vec::new();
+    while let Some(entry) = reader.read_entry(&mut buf).unwrap() {
+        offsets.push(EntryOffset {
+            offset: entry.offset,
+            entry_len: entry.entry_len,
+        });
+    }
+    offsets
+}
+
+#[test]
+fn test_count_and_scan_match_full_read() {
+    let mut compressed = GlogWriterV3::new(Vec::new(), CompressMode::Zlib, PROTO_NAME).unwrap();
+    for i in 0..4 {
+        compressed.write(&encode_log(0, 0, "Tag", &format!("zlib {}", i))).unwrap();
+    }
+
+    for content in [v3_file_with_logs(6), compressed.into_inner().unwrap()] {
+        let size = content.len() as u64;
+        let expected = read_offsets(content.clone(), None);
+
+        let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+        assert_eq!(reader.count().unwrap(), expected.len());
+        let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+        assert_eq!(reader.scan().unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_scan_skips_encrypted_entries_without_key() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    for i in 0..3 {
+        let payload = encode_log(0, 0, "Tag", &format!("secret {}", i));
+        writer.write_entry(&payload, CompressMode::None, EncryptMode::Aes).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+    let expected = read_offsets(content.clone(), Some(hex::encode(server_key.to_bytes())));
+
+    // 未压缩的加密条目只需要长度即可跳过，不需要私钥
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    assert_eq!(reader.scan().unwrap(), expected);
+    assert_eq!(expected.len(), 3);
+}
+
+#[test]
+fn test_salvage_recovers_entries_after_zeroed_header() {
+    let mut content = v3_file_with_logs(400);
+    assert!(content.len() > 3 * 4096);
+    let size = content.len() as u64;
+    let mut reader = GlogReader::from_reader(Cursor::new(content.clone()), size, None).unwrap();
+    // 条目之前的同步标记必须完整才能找到该条目
+    let expected: Vec<u64> = reader
+        .scan()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.offset)
+        .filter(|&offset| offset >= 4096 + 8)
+        .collect();
+
+    content[..4096].fill(0);
+    assert!(GlogReader::from_reader(Cursor::new(content.clone()), size, None).is_err());
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("async-20240101.glog");
+    std::fs::write(&path, &content).unwrap();
+
+    let report = clog_reader::salvage::salvage(path.to_str().unwrap(), None).unwrap();
+    let offsets: Vec<u64> = report.logs.iter().map(|(offset, _)| *offset).collect();
+    assert_eq!(offsets, expected);
+    let first = 400 - expected.len();
+    assert_eq!(report.logs[0].1.msg, format!("message {}", first));
+    assert_eq!(report.logs.last().unwrap().1.msg, "message 399");
+    assert_eq!(report.runs.len(), 1);
+    assert_eq!(report.runs[0].version, GLOG_RECOVERY_VERSION);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_entries_share_stream_state() {
+    let msg = "zstd compressed message with a long repeated body ".repeat(8);
+    let mut writer = GlogWriterV3::new(Vec::new(), CompressMode::Zstd, PROTO_NAME).unwrap();
+    for i in 0..20 {
+        writer.write(&encode_log(0, 0, "Tag", &format!("{} {}", msg, i))).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, None).unwrap();
+    assert_eq!(reader.metadata().compress_mode, CompressMode::Zstd);
+    let mut buf = Vec::new();
+    let mut stored = Vec::new();
+    for i in 0..20 {
+        let entry = reader.read_entry(&mut buf).unwrap().unwrap();
+        let log = clog_reader::Log::decode_from(&buf).unwrap();
+        assert_eq!(log.msg, format!("{} {}", msg, i));
+        assert!(entry.compressed);
+        stored.push(entry.stored_len);
+    }
+    assert!(reader.read_entry(&mut buf).unwrap().is_none());
+    // 之后的条目引用之前的数据，压缩后远小于第一条
+    assert!(stored[1] * 4 < stored[0]);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_v4_mixes_zstd_with_other_modes() {
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    let modes = [
+        (CompressMode::Zstd, EncryptMode::None),
+        (CompressMode::Zlib, EncryptMode::None),
+        (CompressMode::Zstd, EncryptMode::Aes),
+        (CompressMode::None, EncryptMode::Aes),
+        (CompressMode::Zstd, EncryptMode::None),
+    ];
+    for (i, (compress_mode, encrypt_mode)) in modes.into_iter().enumerate() {
+        let payload = encode_log(0, 0, "Tag", &format!("entry {}", i));
+        writer.write_entry(&payload, compress_mode, encrypt_mode).unwrap();
+    }
+    let content = writer.into_inner().unwrap();
+    let size = content.len() as u64;
+
+    let key = Some(hex::encode(server_key.to_bytes()));
+    let mut reader = GlogReader::from_reader(Cursor::new(content), size, key).unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["entry 0", "entry 1", "entry 2", "entry 3", "entry 4"]);
+}
+
+#[test]
+fn test_v4_unknown_compress_mode_recovers() {
+    let mut unknown = v4_entry(&encode_log(0, 0, "Tag", "future"));
+    unknown[0] = 0x41; // 高 4 位：尚未定义的压缩模式
+    let mut content = v4_header();
+    content.extend(v4_entry(&encode_log(0, 0, "Tag", "before")));
+    content.extend(unknown);
+    content.extend(v4_entry(&encode_log(0, 0, "Tag", "after")));
+    let size = content.len() as u64;
+
+    let events: Arc<Mutex<Vec<RecoverEvent>>> = Arc::default();
+    let sink = Arc::clone(&events);
+    let mut reader = GlogReader::builder()
+        .recovery_policy(RecoveryPolicy::Skip {
+            on_event: Box::new(move |event| sink.lock().unwrap().push(event)),
+        })
+        .from_reader(Cursor::new(content), size)
+        .unwrap();
+    let msgs: Vec<String> = reader.logs().map(|log| log.unwrap().msg).collect();
+    assert_eq!(msgs, ["before", "after"]);
+    assert_eq!(events.lock().unwrap().len(), 1);
+    assert_eq!(events.lock().unwrap()[0].reason, RecoverReason::IllegalCompressMode(4));
+    assert_eq!(events.lock().unwrap()[0].reason.code(), -2);
+}
+
+#[test]
+fn test_peek_reports_version_and_key_requirement() {
+    let dir = TempDir::new().unwrap();
+    let path = write_fixture(&dir, v3_file_with_logs(2));
+    let info = glog::peek(path.to_str().unwrap()).unwrap();
+    assert_eq!(info.version, GLOG_RECOVERY_VERSION);
+    assert_eq!(info.proto_name, PROTO_NAME);
+    assert_eq!(info.compress_mode, Some(CompressMode::None));
+    assert!(!info.needs_key());
+    assert!(!info.mmap);
+    assert_eq!(info.file_size, std::fs::metadata(&path).unwrap().len());
+
+    let server_key = SecretKey::from_slice(&[0x5A; 32]).unwrap();
+    let mut writer = GlogWriterV4::new(Vec::new(), PROTO_NAME)
+        .unwrap()
+        .with_server_key(server_key.public_key().to_encoded_point(true).as_bytes())
+        .unwrap();
+    writer.write(&encode_log(0, 0, "Tag", "secret")).unwrap();
+    let mut encrypted = writer.into_inner().unwrap();
+    // 第一条日志的模式字节之后的内容不会被读取
+    encrypted.resize(encrypted.len() + (1 << 20), 0xEE);
+    let info = glog::peek_bytes(&encrypted).unwrap();
+    assert_eq!(info.version, GLOG_CIPHER_VERSION);
+    assert_eq!(info.compress_mode, Some(CompressMode::Zlib));
+    assert_eq!(info.encrypt_mode, Some(EncryptMode::Aes));
+    assert!(info.needs_key());
+    assert_eq!(info.file_size, encrypted.len() as u64);
+
+    // 还没有日志的 V4 文件无法判断加密模式
+    let info = glog::peek_bytes(&v4_header()).unwrap();
+    assert_eq!((info.compress_mode, info.encrypt_mode), (None, None));
+
+    let info = glog::peek_bytes(&mmap_file(&v4_file(&[encode_log(0, 0, "Tag", "x")]), 4096)).unwrap();
+    assert!(info.mmap);
+    assert_eq!(info.encrypt_mode, Some(EncryptMode::None));
+
+    assert!(matches!(glog::peek_bytes(b"not a glog file"), Err(GlogError::MagicMismatch)));
+    assert!(matches!(
+        glog::peek_bytes(&legacy_header(9)),
+        Err(GlogError::UnsupportedVersion(9))
+    ));
+}
+
+#[test]
+fn test_index_skips_entries_in_compressed_stream() {
+    let dir = TempDir::new().unwrap();
+    let payloads: Vec<Vec<u8>> = (0..6)
+        .map(|i| {
+            let level = if i % 3 == 0 { 4 } else { 1 };
+            encode_log(0, level, &format!("T{}", i % 2), &format!("m{i}"))
+        })
+        .collect();
+    let path = write_fixture(&dir, v3_zlib_file(&payloads, true));
+    let path = path.to_str().unwrap();
+    let size = std::fs::metadata(path).unwrap().len();
+
+    let mut reader = glog::open(path).unwrap();
+    let (index, error) = FileIndex::build("async-20240101.glog", size, &mut reader);
+    assert!(error.is_none());
+    assert_eq!(index.entries.len(), 6);
+
+    let filter = LogFilter::new().min_level(Level::Error);
+    let mut reader = glog::open(path).unwrap();
+    let (mut buf, mut scratch, mut msgs) = (Vec::new(), Vec::new(), Vec::new());
+    loop {
+        let indexed = index.lookup(reader.position()).expect("每个条目都应在索引中");
+        if !indexed.may_match(&filter) {
+            assert!(reader.skip_entry(&mut scratch).unwrap().is_some());
+            continue;
+        }
+        match reader.read_entry(&mut buf).unwrap() {
+            Some(entry) => msgs.push(reader.decode_log(&buf[..entry.len]).unwrap().msg),
+            None => break,
+        }
+        if msgs.len() == 2 {
+            break;
+        }
+    }
+    // 跳过的条目仍然推进了共用的解压器状态，之后的条目可以正常解码
+    assert_eq!(msgs, ["m0", "m3"]);
+    assert!(reader.skip_entry(&mut scratch).unwrap().is_some());
+    assert!(reader.skip_entry(&mut scratch).unwrap().is_some());
+    assert!(reader.skip_entry(&mut scratch).unwrap().is_none());
+
+    let tags = LogFilter::new().tag("T1");
+    assert_eq!(index.entries.iter().filter(|e| e.may_match(&tags)).count(), 3);
+}
@@ -0,0 +1,71 @@
+//! WebAssembly 接口测试
+//!
+//! 运行方式：`wasm-pack test --node --no-default-features --features wasm`
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use clog_reader::wasm::GlogWasmReader;
+use serde::Deserialize;
+use wasm_bindgen_test::*;
+
+/// 未压缩、未加密的 V3 文件，包含两条日志：
+/// `app: hello`（Verbose）和 `net: timeout`（Error）
+const FIXTURE: &[u8] = &[
+    0x1B, 0xAD, 0xC0, 0xDE, 0x03, 0x00, 0x03, 0x00, 0x4C, 0x6F, 0x67, 0xB7,
+    0xDB, 0xE7, 0xDB, 0x80, 0xAD, 0xD9, 0x57, 0x26, 0x00, 0x12, 0x0D, 0x31,
+    0x37, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30,
+    0x18, 0x02, 0x20, 0xD2, 0x09, 0x2A, 0x04, 0x35, 0x36, 0x37, 0x38, 0x32,
+    0x03, 0x61, 0x70, 0x70, 0x3A, 0x05, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0xB7,
+    0xDB, 0xE7, 0xDB, 0x80, 0xAD, 0xD9, 0x57, 0x2A, 0x00, 0x08, 0x01, 0x12,
+    0x0D, 0x31, 0x37, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30,
+    0x30, 0x30, 0x18, 0x04, 0x20, 0xD2, 0x09, 0x2A, 0x04, 0x35, 0x36, 0x37,
+    0x38, 0x32, 0x03, 0x6E, 0x65, 0x74, 0x3A, 0x07, 0x74, 0x69, 0x6D, 0x65,
+    0x6F, 0x75, 0x74, 0xB7, 0xDB, 0xE7, 0xDB, 0x80, 0xAD, 0xD9, 0x57,
+];
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct JsLog {
+    #[serde(rename = "type")]
+    log_type: i32,
+    timestamp: String,
+    level: String,
+    pid: i32,
+    tid: String,
+    tag: String,
+    msg: String,
+}
+
+#[wasm_bindgen_test]
+fn test_next_log_returns_objects_until_null() {
+    let mut reader = GlogWasmReader::from_bytes(FIXTURE, None).unwrap();
+
+    let mut logs = Vec::new();
+    loop {
+        let value = reader.next_log().unwrap();
+        if value.is_null() {
+            break;
+        }
+        logs.push(serde_wasm_bindgen::from_value::<JsLog>(value).unwrap());
+    }
+
+    assert_eq!(logs.len(), 2);
+    assert_eq!(
+        logs[0],
+        JsLog {
+            log_type: 0,
+            timestamp: "1700000000000".to_string(),
+            level: "Verbose".to_string(),
+            pid: 1234,
+            tid: "5678".to_string(),
+            tag: "app".to_string(),
+            msg: "hello".to_string(),
+        }
+    );
+    assert_eq!(logs[1].level, "Error");
+    assert_eq!(logs[1].msg, "timeout");
+}
+
+#[wasm_bindgen_test]
+fn test_from_bytes_rejects_invalid_header() {
+    assert!(GlogWasmReader::from_bytes(&FIXTURE[..3], None).is_err());
+}